@@ -0,0 +1,287 @@
+//! AI 応答のキャッシュ（`[ai] cache = true`）
+//!
+//! 同じ質問を繰り返したときに API を呼ばずに済むよう、自然言語応答をデータディレクトリの
+//! `ai_cache.db`（SQLite）に保存する。キーはモデル名・リクエストパラメータ（`temperature` /
+//! `top_p` / `max_tokens`）・システムプロンプト（コンテキストを含む）・入力の SHA-256 で、
+//! 有効期間（`cache_ttl_secs`）内の同一キーはキャッシュから返す。
+//!
+//! キャッシュするのはツールを使わずに 1 ラウンドで完結した自然言語応答のみ。
+//! コマンド判定やファイルを読み書きした応答は実行環境に依存するため対象外とする。
+
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::ai::markdown::is_markdown;
+use crate::cli::jarvis::{jarvis_print_plain, jarvis_render_markdown, jarvis_usage};
+use crate::config::AiConfig;
+use crate::engine::classifier::strip_goodbye_marker;
+use crate::storage::BlackBox;
+
+/// キャッシュした応答をストリーミング風に再生するときの所要時間の目安
+const REPLAY_DURATION: Duration = Duration::from_millis(600);
+
+/// AI 応答のキャッシュ
+pub(super) struct ResponseCache {
+    /// キャッシュの SQLite ファイル
+    path: PathBuf,
+    /// 有効期間（秒、0 で無期限）
+    ttl_secs: u64,
+    /// 最大件数（0 で無制限）
+    max_entries: usize,
+}
+
+impl ResponseCache {
+    /// `[ai] cache` が有効ならデータディレクトリの `ai_cache.db` を使うキャッシュを返す。
+    pub(super) fn from_config(ai_config: &AiConfig) -> Option<Self> {
+        ai_config.cache.then(|| Self {
+            path: BlackBox::data_dir().join("ai_cache.db"),
+            ttl_secs: ai_config.cache_ttl_secs,
+            max_entries: ai_config.cache_max_entries,
+        })
+    }
+
+    /// モデル名・リクエストパラメータ・システムプロンプト（コンテキストを含む）・入力から
+    /// キャッシュのキーを作る。
+    ///
+    /// `params` は応答を変えうるリクエストパラメータを文字列化したもの。プロファイルの切り替えや
+    /// `max_tokens` の変更前に保存した応答（途中で切れたものを含む）を再生しないよう、キーに含める。
+    pub(super) fn key(model: &str, params: &str, system_prompt: &str, input: &str) -> String {
+        let mut hasher = Sha256::new();
+        for part in [model, params, system_prompt, input] {
+            hasher.update(part.as_bytes());
+            // 区切りを入れて ("ab", "c") と ("a", "bc") を区別する
+            hasher.update([0]);
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// 有効期間内のキャッシュがあれば応答を返す。読み込みに失敗した場合は `None`。
+    pub(super) fn get(&self, key: &str) -> Option<String> {
+        let result = self
+            .open()
+            .and_then(|conn| lookup(&conn, key, unix_now(), self.ttl_secs));
+        match result {
+            Ok(hit) => {
+                debug!(key = %key, hit = hit.is_some(), "AI response cache lookup");
+                hit
+            }
+            Err(e) => {
+                warn!(error = %e, path = %self.path.display(), "Failed to read AI response cache");
+                None
+            }
+        }
+    }
+
+    /// 応答を保存し、期限切れ・上限超過のエントリを削除する。失敗しても警告のみ。
+    pub(super) fn put(&self, key: &str, response: &str) {
+        let result = self.open().and_then(|conn| {
+            store(
+                &conn,
+                key,
+                response,
+                unix_now(),
+                self.ttl_secs,
+                self.max_entries,
+            )
+        });
+        match result {
+            Ok(()) => debug!(key = %key, response_length = response.len(), "AI response cached"),
+            Err(e) => {
+                warn!(error = %e, path = %self.path.display(), "Failed to write AI response cache")
+            }
+        }
+    }
+
+    fn open(&self) -> rusqlite::Result<Connection> {
+        if let Some(parent) = self.path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let conn = Connection::open(&self.path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS ai_response_cache (
+                key        TEXT    PRIMARY KEY,
+                response   TEXT    NOT NULL,
+                created_at INTEGER NOT NULL
+            );",
+        )?;
+        Ok(conn)
+    }
+}
+
+/// キャッシュした応答を表示する。
+///
+/// Markdown はライブ応答と同じく一括でレンダリングし、プレーンテキストは少しずつ表示して
+/// ストリーミング風に再生する。最後にキャッシュからの応答であることを控えめに表示する。
+/// `code_highlight` はライブ応答と同じく Markdown のコードブロックを色付けするか。
+pub(super) fn replay(text: &str, markdown_rendering: bool, code_highlight: bool) {
+    // 会話終了マーカーは表示しない（ライブ応答と同じ）
+    let text = strip_goodbye_marker(text).unwrap_or_else(|| text.to_string());
+    if markdown_rendering && is_markdown(&text) {
+        jarvis_render_markdown(&text, code_highlight);
+    } else if io::stdout().is_terminal() {
+        replay_plain(&text, REPLAY_DURATION);
+    } else {
+        jarvis_print_plain(&text);
+    }
+    jarvis_usage("[cached response]");
+}
+
+/// プレーンテキストを単語ごとに区切り、合計でおよそ `duration` かけて表示する。
+fn replay_plain(text: &str, duration: Duration) {
+    let chunks: Vec<&str> = text
+        .trim_end_matches('\n')
+        .split_inclusive(char::is_whitespace)
+        .collect();
+    let delay = duration / u32::try_from(chunks.len().max(1)).unwrap_or(u32::MAX);
+    let mut stdout = io::stdout().lock();
+    let _ = write!(stdout, "🤵 ");
+    for chunk in chunks {
+        let _ = write!(stdout, "{chunk}");
+        let _ = stdout.flush();
+        std::thread::sleep(delay);
+    }
+    let _ = writeln!(stdout);
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// `now` 時点で有効期間内のエントリを引く。
+fn lookup(
+    conn: &Connection,
+    key: &str,
+    now: i64,
+    ttl_secs: u64,
+) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT response FROM ai_response_cache WHERE key = ?1 AND (?2 = 0 OR created_at > ?3 - ?2)",
+        params![key, ttl_secs as i64, now],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// エントリを保存（同じキーは上書き）し、期限切れと上限を超えた古いエントリを削除する。
+fn store(
+    conn: &Connection,
+    key: &str,
+    response: &str,
+    now: i64,
+    ttl_secs: u64,
+    max_entries: usize,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO ai_response_cache (key, response, created_at) VALUES (?1, ?2, ?3)",
+        params![key, response, now],
+    )?;
+    if ttl_secs > 0 {
+        conn.execute(
+            "DELETE FROM ai_response_cache WHERE created_at <= ?1 - ?2",
+            params![now, ttl_secs as i64],
+        )?;
+    }
+    if max_entries > 0 {
+        conn.execute(
+            "DELETE FROM ai_response_cache WHERE key NOT IN (
+                SELECT key FROM ai_response_cache ORDER BY created_at DESC, rowid DESC LIMIT ?1
+            )",
+            params![max_entries as i64],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cache(dir: &tempfile::TempDir, ttl_secs: u64, max_entries: usize) -> ResponseCache {
+        ResponseCache {
+            path: dir.path().join("ai_cache.db"),
+            ttl_secs,
+            max_entries,
+        }
+    }
+
+    #[test]
+    fn key_depends_on_model_params_context_and_input() {
+        let key = ResponseCache::key("gpt-4o", "max_tokens=None", "prompt", "hello");
+        assert_eq!(
+            key,
+            ResponseCache::key("gpt-4o", "max_tokens=None", "prompt", "hello")
+        );
+        assert_ne!(
+            key,
+            ResponseCache::key("gpt-4o-mini", "max_tokens=None", "prompt", "hello")
+        );
+        assert_ne!(
+            key,
+            ResponseCache::key("gpt-4o", "max_tokens=Some(16)", "prompt", "hello")
+        );
+        assert_ne!(
+            key,
+            ResponseCache::key("gpt-4o", "max_tokens=None", "prompt2", "hello")
+        );
+        assert_ne!(
+            key,
+            ResponseCache::key("gpt-4o", "max_tokens=None", "prompt", "hello!")
+        );
+        assert_ne!(
+            ResponseCache::key("gpt-4o", "", "ab", "c"),
+            ResponseCache::key("gpt-4o", "", "a", "bc")
+        );
+    }
+
+    #[test]
+    fn put_then_get_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir, 3600, 10);
+        assert_eq!(cache.get("k"), None);
+
+        cache.put("k", "answer");
+        assert_eq!(cache.get("k").as_deref(), Some("answer"));
+        cache.put("k", "updated");
+        assert_eq!(cache.get("k").as_deref(), Some("updated"));
+    }
+
+    #[test]
+    fn expired_entries_are_not_returned() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir, 60, 10);
+        let conn = cache.open().unwrap();
+        store(&conn, "k", "answer", 1_000, 60, 10).unwrap();
+
+        assert_eq!(
+            lookup(&conn, "k", 1_059, 60).unwrap().as_deref(),
+            Some("answer")
+        );
+        assert_eq!(lookup(&conn, "k", 1_060, 60).unwrap(), None);
+        // 0 は無期限
+        assert_eq!(
+            lookup(&conn, "k", 1_000_000, 0).unwrap().as_deref(),
+            Some("answer")
+        );
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted_over_the_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = test_cache(&dir, 0, 2);
+        let conn = cache.open().unwrap();
+        store(&conn, "a", "1", 100, 0, 2).unwrap();
+        store(&conn, "b", "2", 101, 0, 2).unwrap();
+        store(&conn, "c", "3", 102, 0, 2).unwrap();
+
+        assert_eq!(lookup(&conn, "a", 103, 0).unwrap(), None);
+        assert!(lookup(&conn, "b", 103, 0).unwrap().is_some());
+        assert!(lookup(&conn, "c", 103, 0).unwrap().is_some());
+    }
+}
@@ -1,16 +1,25 @@
 //! エージェントループ — ツールコール付き複数ステップ処理
 
 use anyhow::Result;
+use std::path::PathBuf;
+use std::time::Instant;
+
 use async_openai::types::{
     ChatCompletionRequestAssistantMessage, ChatCompletionRequestAssistantMessageContent,
     ChatCompletionRequestMessage, ChatCompletionRequestToolMessage,
-    ChatCompletionRequestToolMessageContent, CreateChatCompletionRequest,
+    ChatCompletionRequestToolMessageContent, ChatCompletionStreamOptions, ChatCompletionTool,
+    CreateChatCompletionRequest,
 };
 use tracing::{debug, info, warn};
 
-use crate::ai::stream::process_stream;
+use crate::ai::cache::replay as replay_cached;
+use crate::ai::conversation;
+use crate::ai::markdown::is_markdown;
+use crate::ai::stream::{process_stream, StreamResult};
 use crate::ai::tools;
 use crate::ai::types::AiResponse;
+use crate::ai::usage::{format_usage_line, UsageStats};
+use crate::cli::jarvis::{jarvis_offer_pager, jarvis_usage};
 
 impl super::JarvisAI {
     /// エージェントループを実行する共通メソッド。
@@ -18,12 +27,84 @@ impl super::JarvisAI {
     /// 会話履歴（messages）に対して API リクエスト → ストリーム処理 → ツール実行を繰り返す。
     /// 最終応答の NaturalLanguage テキストもアシスタントメッセージとして messages に追加する
     /// （会話継続のため）。
+    ///
+    /// `AiResponse::Command` を返す場合、そのラウンドのアシスタントメッセージ（tool_calls 付き）と
+    /// 他ツールの実行結果は messages に積むが、実行対象の `execute_shell_command` への
+    /// tool メッセージは積まない。Shell 側がコマンドを実行した後に
+    /// `ConversationState::record_command_result` で結果を積む。
+    ///
+    /// `show_usage` が有効な場合、ループ完了後に全ラウンド合計のトークン使用量と
+    /// 所要時間を一行で表示する。
+    /// `pager` が設定されている場合、自然言語応答が一画面に収まらなければ
+    /// ストリーミング表示の後にページャで開くかを確認する。
+    /// `show_git_diff_after_write` が有効な場合、ループ中にツールで変更したファイルの
+    /// `git diff` を最後にまとめて表示する。
+    ///
+    /// `cache_key` を指定すると、キャッシュ（`[ai] cache`）にあれば API を呼ばずに応答を再生し、
+    /// なければツールを使わずに完結した自然言語応答をキャッシュに保存する。
     pub(super) async fn run_agent_loop(
         &self,
         messages: &mut Vec<ChatCompletionRequestMessage>,
+        cache_key: Option<&str>,
+    ) -> Result<AiResponse> {
+        let started = Instant::now();
+        let mut usage = UsageStats::default();
+        let mut modified = Vec::new();
+        let mut answered_directly = false;
+
+        let cache = self.cache.as_ref().zip(cache_key);
+        let cached = cache.and_then(|(cache, key)| cache.get(key));
+        let response = match cached {
+            Some(text) => {
+                info!(
+                    response_length = text.len(),
+                    "AI response served from cache"
+                );
+                replay_cached(&text, self.markdown_rendering, self.code_highlight);
+                messages.push(super::build_text_assistant_message(text.clone()));
+                Ok(AiResponse::NaturalLanguage(text))
+            }
+            None => {
+                self.run_agent_rounds(messages, &mut usage, &mut modified, &mut answered_directly)
+                    .await
+            }
+        };
+        if let (Some((cache, key)), Ok(AiResponse::NaturalLanguage(text))) = (cache, &response) {
+            if answered_directly && !text.is_empty() {
+                cache.put(key, text);
+            }
+        }
+
+        if self.show_usage && usage.total_tokens() > 0 {
+            jarvis_usage(&format_usage_line(&self.model, started.elapsed(), &usage));
+        }
+        if let Ok(AiResponse::NaturalLanguage(text)) = &response {
+            if !self.pager.is_empty() && !text.is_empty() {
+                jarvis_offer_pager(
+                    text,
+                    &self.pager,
+                    self.markdown_rendering && is_markdown(text),
+                );
+            }
+        }
+        if self.show_git_diff_after_write {
+            tools::git_diff::show_git_diff(&modified);
+        }
+        response
+    }
+
+    /// エージェントループの各ラウンドを実行し、トークン使用量を `usage` に加算する。
+    /// ツールで変更したファイルのパスは `modified` に記録する。
+    /// 初回ラウンドでツールを使わずに応答が完結した場合は `answered_directly` を true にする。
+    async fn run_agent_rounds(
+        &self,
+        messages: &mut Vec<ChatCompletionRequestMessage>,
+        usage: &mut UsageStats,
+        modified: &mut Vec<PathBuf>,
+        answered_directly: &mut bool,
     ) -> Result<AiResponse> {
         let model = self.model.clone();
-        let tool_defs = tools::build_tools();
+        let tool_defs = tools::build_tools(&self.enabled_tools);
 
         for round in 0..self.max_rounds {
             debug!(
@@ -35,9 +116,15 @@ impl super::JarvisAI {
             let request = CreateChatCompletionRequest {
                 model: model.clone(),
                 messages: messages.clone(),
-                tools: Some(tool_defs.clone()),
+                tools: request_tools(&tool_defs),
                 stream: Some(true),
-                temperature: Some(self.temperature),
+                temperature: self.temperature,
+                top_p: self.top_p,
+                max_completion_tokens: self.max_tokens,
+                // 使用量表示時のみ、ストリーム最終チャンクに usage を含めるよう要求する
+                stream_options: self.show_usage.then_some(ChatCompletionStreamOptions {
+                    include_usage: true,
+                }),
                 ..Default::default()
             };
 
@@ -50,20 +137,36 @@ impl super::JarvisAI {
                 "Sending API request to OpenAI"
             );
 
-            let stream_result =
-                process_stream(&self.client, request, round == 0, self.markdown_rendering).await?;
+            let stream_result = process_stream(
+                &self.client,
+                request,
+                round == 0,
+                self.markdown_rendering,
+                self.code_highlight,
+                self.stream_idle_timeout,
+            )
+            .await?;
+
+            if self.show_usage {
+                usage.add(round_usage(messages, &stream_result));
+            }
 
-            if stream_result.interrupted {
+            // 中断・タイムアウト時は受信途中の Tool Call を実行せず、受信済みのテキストを返して
+            // 次の操作（コマンドの直接実行や再質問）をユーザーに委ねる。
+            // 受信済みの部分はマーカー付きで会話に積み、次の入力で続きを頼めるようにする
+            if stream_result.interrupted || stream_result.timed_out {
                 info!(
                     round = round,
                     text_length = stream_result.full_text.len(),
-                    "Stream interrupted by Ctrl-C, returning partial result"
+                    timed_out = stream_result.timed_out,
+                    "Stream interrupted, returning partial result"
                 );
-                if !stream_result.full_text.is_empty() {
-                    messages.push(super::build_text_assistant_message(
-                        stream_result.full_text.clone(),
-                    ));
-                }
+                messages.push(super::build_text_assistant_message(
+                    conversation::partial_response(
+                        &stream_result.full_text,
+                        stream_result.timed_out,
+                    ),
+                ));
                 return Ok(AiResponse::NaturalLanguage(stream_result.full_text));
             }
 
@@ -88,16 +191,39 @@ impl super::JarvisAI {
                     ));
                 }
 
+                *answered_directly = round == 0;
                 return Ok(AiResponse::NaturalLanguage(stream_result.full_text));
             }
 
-            if let Some(cmd) = tools::call::extract_shell_command(&stream_result.tool_calls) {
+            let assistant_message = build_tool_call_assistant_message(
+                &stream_result.full_text,
+                &stream_result.tool_calls,
+            );
+
+            // execute_shell_command が無効な場合は他のツールと同様に「tool disabled」を返す
+            let shell_command = tools::is_enabled("execute_shell_command", &self.enabled_tools)
+                .then(|| tools::call::extract_shell_command(&stream_result.tool_calls))
+                .flatten();
+            if let Some((shell_call, cmd)) = shell_command {
+                let shell_call_id = shell_call.id.clone();
+                messages.push(assistant_message);
+
                 // execute_shell_command と同時に返された他のツール（read_file, write_file,
                 // search_replace 等）を先に実行する。これにより、AI が「ファイル修正 → ビルド」
                 // を1ラウンドで返した場合でもファイル修正が確実に適用される。
+                // 結果は会話履歴に積み、次ターンで AI が参照できるようにする。
+                // 実行対象コマンド自体の結果は Shell 側で実行後に積む。
                 let non_shell = tools::call::extract_non_shell_tools(&stream_result.tool_calls);
-                for tc in &non_shell {
-                    let result = tools::executor::execute_tool(&tc.function_name, &tc.arguments);
+                // 1 ラウンドで実行するシェルコマンドは 1 つだけ。残りは未実行として応答する
+                let skipped_shell = stream_result.tool_calls.iter().filter(|tc| {
+                    tc.function_name == "execute_shell_command" && tc.id != shell_call_id
+                });
+                for tc in non_shell.into_iter().chain(skipped_shell) {
+                    let result = if tc.function_name == "execute_shell_command" {
+                        "Not executed: only one shell command can run per turn.".to_string()
+                    } else {
+                        self.execute_tool(tc, modified)
+                    };
                     debug!(
                         tool = %tc.function_name,
                         tool_call_id = %tc.id,
@@ -105,6 +231,12 @@ impl super::JarvisAI {
                         round = round,
                         "Pre-command tool executed locally"
                     );
+                    messages.push(ChatCompletionRequestMessage::Tool(
+                        ChatCompletionRequestToolMessage {
+                            content: ChatCompletionRequestToolMessageContent::Text(result),
+                            tool_call_id: tc.id.clone(),
+                        },
+                    ));
                 }
 
                 info!(
@@ -116,29 +248,10 @@ impl super::JarvisAI {
                 return Ok(AiResponse::Command(cmd));
             }
 
-            let assistant_tool_calls =
-                tools::call::build_assistant_tool_calls(&stream_result.tool_calls);
-
-            messages.push(ChatCompletionRequestMessage::Assistant(
-                ChatCompletionRequestAssistantMessage {
-                    content: if stream_result.full_text.is_empty() {
-                        None
-                    } else {
-                        Some(ChatCompletionRequestAssistantMessageContent::Text(
-                            stream_result.full_text,
-                        ))
-                    },
-                    refusal: None,
-                    name: None,
-                    audio: None,
-                    tool_calls: Some(assistant_tool_calls),
-                    #[allow(deprecated)]
-                    function_call: None,
-                },
-            ));
+            messages.push(assistant_message);
 
             for tc in &stream_result.tool_calls {
-                let result = tools::executor::execute_tool(&tc.function_name, &tc.arguments);
+                let result = self.execute_tool(tc, modified);
 
                 debug!(
                     tool = %tc.function_name,
@@ -165,4 +278,92 @@ impl super::JarvisAI {
             "I apologize, sir. I've reached the maximum number of processing steps.".to_string(),
         ))
     }
+
+    /// ツールをローカルで実行し、ファイルを変更した場合はそのパスを `modified` に記録する。
+    fn execute_tool(
+        &self,
+        tc: &tools::call::ToolCallAccumulator,
+        modified: &mut Vec<PathBuf>,
+    ) -> String {
+        let result =
+            tools::execute_enabled_tool(&tc.function_name, &tc.arguments, &self.enabled_tools);
+        if let Some(path) =
+            tools::git_diff::modified_path(&tc.function_name, &tc.arguments, &result)
+        {
+            modified.push(path);
+        }
+        result
+    }
+}
+
+/// リクエストに載せるツール定義。
+///
+/// 空の `tools` 配列は API が 400 で拒否するため、有効なツールがなければ `tools` 自体を送らない
+/// （`[ai] enabled_tools = []`）。
+fn request_tools(tool_defs: &[ChatCompletionTool]) -> Option<Vec<ChatCompletionTool>> {
+    (!tool_defs.is_empty()).then(|| tool_defs.to_vec())
+}
+
+/// 1 ラウンド分のトークン使用量を返す。
+///
+/// API が usage を返さなかった場合（中断時や usage 非対応のエンドポイント）は、
+/// 送信した会話履歴と受信したテキスト・ツール引数の文字数から概算する。
+fn round_usage(
+    messages: &[ChatCompletionRequestMessage],
+    stream_result: &StreamResult,
+) -> UsageStats {
+    if let Some(usage) = stream_result.usage {
+        return usage;
+    }
+    let prompt_text = serde_json::to_string(messages).unwrap_or_default();
+    let mut completion_text = stream_result.full_text.clone();
+    for tc in &stream_result.tool_calls {
+        completion_text.push_str(&tc.function_name);
+        completion_text.push_str(&tc.arguments);
+    }
+    UsageStats::estimate(&prompt_text, &completion_text)
+}
+
+/// tool_calls 付きのアシスタントメッセージを構築する（会話履歴に追加用）。
+fn build_tool_call_assistant_message(
+    text: &str,
+    tool_calls: &[tools::call::ToolCallAccumulator],
+) -> ChatCompletionRequestMessage {
+    ChatCompletionRequestMessage::Assistant(ChatCompletionRequestAssistantMessage {
+        content: if text.is_empty() {
+            None
+        } else {
+            Some(ChatCompletionRequestAssistantMessageContent::Text(
+                text.to_string(),
+            ))
+        },
+        refusal: None,
+        name: None,
+        audio: None,
+        tool_calls: Some(tools::call::build_assistant_tool_calls(tool_calls)),
+        #[allow(deprecated)]
+        function_call: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_omits_tools_when_none_are_enabled() {
+        let request = CreateChatCompletionRequest {
+            tools: request_tools(&tools::build_tools(&[])),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert!(json.get("tools").is_none(), "{json}");
+        assert!(json.get("tool_choice").is_none(), "{json}");
+
+        let enabled = vec!["read_file".to_string()];
+        assert_eq!(
+            request_tools(&tools::build_tools(&enabled)).unwrap().len(),
+            1
+        );
+    }
 }
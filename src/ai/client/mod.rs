@@ -3,13 +3,17 @@
 //! ユーザー入力を AI に送信し、コマンドか自然言語かを判定する。
 //! ストリーミングレスポンスに対応し、Tool Calling でコマンド実行を支援する。
 //! エージェントループにより、複数ステップのファイル操作（読み取り→編集→書き込み）が可能。
+//! リクエスト前に API への到達性を確かめ、オフライン時は接続を待たずに判定できる（[`reachability`]）。
 
 mod agent;
 mod pipe;
+mod reachability;
+
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use async_openai::{
-    config::OpenAIConfig,
+    config::{Config, OpenAIConfig},
     types::{
         ChatCompletionRequestAssistantMessage, ChatCompletionRequestAssistantMessageContent,
         ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage,
@@ -20,11 +24,16 @@ use async_openai::{
 };
 use tracing::{debug, info};
 
-use crate::config::AiConfig;
+use crate::config::{AiConfig, Verbosity};
 use crate::engine::CommandResult;
 
-use super::prompts::{ERROR_INVESTIGATION_PROMPT, SYSTEM_PROMPT};
+use super::cache::ResponseCache;
+use super::prompts::{
+    CONCISE_NOTE, DETAILED_NOTE, DETAILED_PROMPT, ERROR_INVESTIGATION_PROMPT, SYSTEM_PROMPT,
+};
+use super::tools;
 use super::types::{AiResponse, ConversationOrigin, ConversationResult, ConversationState};
+use reachability::Reachability;
 
 /// テキストのみのアシスタントメッセージを構築する。
 fn build_text_assistant_message(text: String) -> ChatCompletionRequestMessage {
@@ -39,6 +48,40 @@ fn build_text_assistant_message(text: String) -> ChatCompletionRequestMessage {
     })
 }
 
+/// `[ai] stream_idle_timeout_secs` を Duration に変換する（0 は無効）。
+fn stream_idle_timeout(ai_config: &AiConfig) -> Option<Duration> {
+    (ai_config.stream_idle_timeout_secs > 0)
+        .then(|| Duration::from_secs(ai_config.stream_idle_timeout_secs))
+}
+
+/// 入力末尾の `--detailed` / `--concise` を取り除き、その入力だけに使う回答の詳しさを返す。
+///
+/// フラグだけの入力や、単語の一部（`foo--detailed`）はフラグとみなさない。
+fn split_verbosity_flag(input: &str) -> (&str, Option<Verbosity>) {
+    let trimmed = input.trim_end();
+    for (flag, verbosity) in [
+        ("--detailed", Verbosity::Detailed),
+        ("--concise", Verbosity::Concise),
+    ] {
+        if let Some(rest) = trimmed.strip_suffix(flag) {
+            if rest.ends_with(char::is_whitespace) && !rest.trim().is_empty() {
+                return (rest.trim_end(), Some(verbosity));
+            }
+        }
+    }
+    (input, None)
+}
+
+/// 新規会話のシステムプロンプトを組み立てる（`detailed` なら詳しく答える指示を追加する）。
+fn build_system_prompt(verbosity: Verbosity) -> String {
+    let mut prompt = SYSTEM_PROMPT.to_string();
+    if verbosity == Verbosity::Detailed {
+        prompt.push_str("\n\n");
+        prompt.push_str(DETAILED_PROMPT);
+    }
+    prompt
+}
+
 /// J.A.R.V.I.S. AI クライアント
 pub struct JarvisAI {
     client: Client<OpenAIConfig>,
@@ -48,12 +91,34 @@ pub struct JarvisAI {
     max_rounds: usize,
     /// AI レスポンスを Markdown としてレンダリングするか
     markdown_rendering: bool,
+    /// Markdown 表示時にコードブロックをシンタックスハイライトするか
+    code_highlight: bool,
     /// AI パイプの入力テキスト文字数上限
     ai_pipe_max_chars: usize,
     /// AI リダイレクトの入力テキスト文字数上限
     ai_redirect_max_chars: usize,
-    /// 回答のランダム性（0.0 = 決定的、2.0 = 最大ランダム）
-    temperature: f32,
+    /// 回答のランダム性（`None` ならリクエストに含めずプロバイダの既定値）
+    temperature: Option<f32>,
+    /// nucleus sampling の確率質量（`None` ならプロバイダの既定値）
+    top_p: Option<f32>,
+    /// 1 回の応答の最大トークン数（`None` ならプロバイダの既定値）
+    max_tokens: Option<u32>,
+    /// 応答完了後にトークン使用量・所要時間を表示するか
+    show_usage: bool,
+    /// 長い自然言語応答を開き直すページャ（空文字列なら無効）
+    pager: String,
+    /// ストリームの無通信タイムアウト（`None` なら無効）
+    stream_idle_timeout: Option<Duration>,
+    /// AI に渡すツール名（`[ai] enabled_tools`）
+    enabled_tools: Vec<String>,
+    /// 回答の詳しさ（`[ai] verbosity`、入力末尾のフラグで 1 回だけ上書きできる）
+    verbosity: Verbosity,
+    /// AI がファイルを変更した応答の後に `git diff` を表示するか
+    show_git_diff_after_write: bool,
+    /// 自然言語応答のキャッシュ（`[ai] cache = false` なら `None`）
+    cache: Option<ResponseCache>,
+    /// API エンドポイントへの到達性（オフライン検出）
+    reachability: Reachability,
 }
 
 impl JarvisAI {
@@ -67,15 +132,28 @@ impl JarvisAI {
         }
 
         let config = OpenAIConfig::new().with_api_key(&api_key);
+        let reachability = Reachability::new(config.api_base());
         let client = Client::with_config(config);
+        tools::warn_unknown_tools(&ai_config.enabled_tools);
         Ok(Self {
             client,
             model: ai_config.model.clone(),
             max_rounds: ai_config.max_rounds,
             markdown_rendering: ai_config.markdown_rendering,
+            code_highlight: ai_config.code_highlight,
             ai_pipe_max_chars: ai_config.ai_pipe_max_chars,
             ai_redirect_max_chars: ai_config.ai_redirect_max_chars,
             temperature: ai_config.temperature,
+            top_p: ai_config.top_p,
+            max_tokens: ai_config.max_tokens,
+            show_usage: ai_config.show_usage,
+            pager: ai_config.pager.clone(),
+            stream_idle_timeout: stream_idle_timeout(ai_config),
+            enabled_tools: ai_config.enabled_tools.clone(),
+            verbosity: ai_config.verbosity,
+            show_git_diff_after_write: ai_config.show_git_diff_after_write,
+            cache: ResponseCache::from_config(ai_config),
+            reachability,
         })
     }
 
@@ -84,34 +162,87 @@ impl JarvisAI {
         self.model = ai_config.model.clone();
         self.max_rounds = ai_config.max_rounds;
         self.markdown_rendering = ai_config.markdown_rendering;
+        self.code_highlight = ai_config.code_highlight;
         self.ai_pipe_max_chars = ai_config.ai_pipe_max_chars;
         self.ai_redirect_max_chars = ai_config.ai_redirect_max_chars;
         self.temperature = ai_config.temperature;
+        self.top_p = ai_config.top_p;
+        self.max_tokens = ai_config.max_tokens;
+        self.show_usage = ai_config.show_usage;
+        self.pager = ai_config.pager.clone();
+        self.stream_idle_timeout = stream_idle_timeout(ai_config);
+        self.enabled_tools = ai_config.enabled_tools.clone();
+        tools::warn_unknown_tools(&self.enabled_tools);
+        self.verbosity = ai_config.verbosity;
+        self.show_git_diff_after_write = ai_config.show_git_diff_after_write;
+        self.cache = ResponseCache::from_config(ai_config);
         info!(
             model = %self.model,
             max_rounds = self.max_rounds,
             markdown_rendering = self.markdown_rendering,
+            code_highlight = self.code_highlight,
             ai_pipe_max_chars = self.ai_pipe_max_chars,
             ai_redirect_max_chars = self.ai_redirect_max_chars,
-            temperature = self.temperature,
+            temperature = ?self.temperature,
+            top_p = ?self.top_p,
+            max_tokens = ?self.max_tokens,
+            show_usage = self.show_usage,
+            pager = %self.pager,
+            stream_idle_timeout = ?self.stream_idle_timeout,
+            enabled_tools = ?self.enabled_tools,
+            verbosity = %self.verbosity,
+            show_git_diff_after_write = self.show_git_diff_after_write,
+            cache = self.cache.is_some(),
             "AI config updated"
         );
     }
 
+    /// システムプロンプトに、無効なツールの注記（`[ai] enabled_tools`）とコンテキストを続ける。
+    fn system_content(&self, prompt: &str, context: &str) -> String {
+        let mut content = prompt.to_string();
+        if let Some(note) = tools::disabled_tools_note(&self.enabled_tools) {
+            content.push_str("\n\n");
+            content.push_str(&note);
+        }
+        if !context.is_empty() {
+            content.push_str("\n\n");
+            content.push_str(context);
+        }
+        content
+    }
+
+    /// API に到達できるか。直近の判定結果が有効ならそれを返し、なければ短いタイムアウトで
+    /// 接続を試す（オフライン判定の後はバックオフの間、再チェックせずに `false` を返す）。
+    pub async fn is_online(&self) -> bool {
+        self.reachability.is_online().await
+    }
+
+    /// 判定結果の期限にかかわらず API への到達性を確かめ直す（リクエスト失敗後など）。
+    pub async fn recheck_online(&self) -> bool {
+        self.reachability.recheck().await
+    }
+
+    /// 到達性を確かめる接続先のホスト名（API ホスト、またはプロキシ）
+    pub fn reachability_host(&self) -> &str {
+        self.reachability.host()
+    }
+
     /// ユーザー入力を AI に送信し、コマンドか自然言語かを判定する。
+    ///
+    /// 入力末尾の `--detailed` / `--concise` は取り除き、この会話の回答の詳しさとして使う。
+    /// `[ai] cache` が有効なら、同じ入力＋コンテキストへの自然言語応答をキャッシュから返す。
     pub async fn process_input(&self, input: &str, context: &str) -> Result<ConversationResult> {
+        let (input, verbosity_flag) = split_verbosity_flag(input);
+        let verbosity = verbosity_flag.unwrap_or(self.verbosity);
         debug!(
             user_input = %input,
             context_length = context.len(),
             context_empty = context.is_empty(),
+            verbosity = %verbosity,
             "process_input() called"
         );
 
-        let system_content = if context.is_empty() {
-            SYSTEM_PROMPT.to_string()
-        } else {
-            format!("{SYSTEM_PROMPT}\n\n{context}")
-        };
+        let system_content = self.system_content(&build_system_prompt(verbosity), context);
 
         debug!(
             system_prompt_length = system_content.len(),
@@ -119,6 +250,14 @@ impl JarvisAI {
         );
         debug!(system_prompt = %system_content, "Full system prompt content");
 
+        let cache_key = self.cache.as_ref().map(|_| {
+            let params = format!(
+                "temperature={:?} top_p={:?} max_tokens={:?}",
+                self.temperature, self.top_p, self.max_tokens
+            );
+            ResponseCache::key(&self.model, &params, &system_content, input)
+        });
+
         let mut messages: Vec<ChatCompletionRequestMessage> = vec![
             ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
                 content: ChatCompletionRequestSystemMessageContent::Text(system_content),
@@ -130,7 +269,9 @@ impl JarvisAI {
             }),
         ];
 
-        let response = self.run_agent_loop(&mut messages).await?;
+        let response = self
+            .run_agent_loop(&mut messages, cache_key.as_deref())
+            .await?;
         Ok(ConversationResult {
             response,
             conversation: ConversationState {
@@ -169,11 +310,7 @@ impl JarvisAI {
         }
         error_details.push_str("\nPlease investigate the error and suggest a fix.");
 
-        let system_content = if context.is_empty() {
-            ERROR_INVESTIGATION_PROMPT.to_string()
-        } else {
-            format!("{ERROR_INVESTIGATION_PROMPT}\n\n{context}")
-        };
+        let system_content = self.system_content(ERROR_INVESTIGATION_PROMPT, context);
 
         let mut messages: Vec<ChatCompletionRequestMessage> = vec![
             ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
@@ -186,7 +323,7 @@ impl JarvisAI {
             }),
         ];
 
-        let response = self.run_agent_loop(&mut messages).await?;
+        let response = self.run_agent_loop(&mut messages, None).await?;
         Ok(ConversationResult {
             response,
             conversation: ConversationState {
@@ -197,6 +334,9 @@ impl JarvisAI {
     }
 
     /// 既存の会話コンテキストを使って会話を継続する。
+    ///
+    /// 入力末尾に `--detailed` / `--concise` があれば取り除き、その質問だけ詳しさを切り替える
+    /// 指示をユーザーメッセージに添える（システムプロンプトは会話の開始時のまま）。
     pub async fn continue_conversation(
         &self,
         state: &mut ConversationState,
@@ -208,14 +348,23 @@ impl JarvisAI {
             "continue_conversation() called"
         );
 
+        // 結果が積まれなかったシェルコマンドのツールコールが残っていると
+        // API がリクエストを拒否するため、未実行として閉じておく
+        state.close_pending_shell_tool_call();
+
+        let content = match split_verbosity_flag(input) {
+            (input, Some(Verbosity::Detailed)) => format!("{input}\n\n{DETAILED_NOTE}"),
+            (input, Some(Verbosity::Concise)) => format!("{input}\n\n{CONCISE_NOTE}"),
+            (input, None) => input.to_string(),
+        };
         state.messages.push(ChatCompletionRequestMessage::User(
             ChatCompletionRequestUserMessage {
-                content: ChatCompletionRequestUserMessageContent::Text(input.to_string()),
+                content: ChatCompletionRequestUserMessageContent::Text(content),
                 name: None,
             },
         ));
 
-        self.run_agent_loop(&mut state.messages).await
+        self.run_agent_loop(&mut state.messages, None).await
     }
 }
 
@@ -237,4 +386,35 @@ mod tests {
             std::env::set_var("OPENAI_API_KEY", key);
         }
     }
+
+    #[test]
+    fn split_verbosity_flag_strips_trailing_flag() {
+        assert_eq!(
+            split_verbosity_flag("explain git rebase --detailed"),
+            ("explain git rebase", Some(Verbosity::Detailed))
+        );
+        assert_eq!(
+            split_verbosity_flag("explain git rebase  --concise  "),
+            ("explain git rebase", Some(Verbosity::Concise))
+        );
+        assert_eq!(split_verbosity_flag("hello"), ("hello", None));
+        // フラグだけの入力・単語の一部・途中のフラグは対象外
+        assert_eq!(split_verbosity_flag("--detailed"), ("--detailed", None));
+        assert_eq!(
+            split_verbosity_flag("foo--detailed"),
+            ("foo--detailed", None)
+        );
+        assert_eq!(
+            split_verbosity_flag("what does --detailed do"),
+            ("what does --detailed do", None)
+        );
+    }
+
+    #[test]
+    fn system_prompt_reflects_verbosity() {
+        assert_eq!(build_system_prompt(Verbosity::Concise), SYSTEM_PROMPT);
+        let detailed = build_system_prompt(Verbosity::Detailed);
+        assert!(detailed.starts_with(SYSTEM_PROMPT));
+        assert!(detailed.ends_with(DETAILED_PROMPT));
+    }
 }
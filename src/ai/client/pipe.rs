@@ -56,11 +56,20 @@ impl super::JarvisAI {
             model: self.model.clone(),
             messages,
             stream: Some(true),
-            temperature: Some(self.temperature),
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_completion_tokens: self.max_tokens,
             ..Default::default()
         };
 
-        let raw = process_ai_pipe_stream(&self.client, request, self.markdown_rendering).await?;
+        let raw = process_ai_pipe_stream(
+            &self.client,
+            request,
+            self.markdown_rendering,
+            self.code_highlight,
+            self.stream_idle_timeout,
+        )
+        .await?;
         Ok(sanitize_ai_pipe_output(&raw))
     }
 
@@ -105,11 +114,20 @@ impl super::JarvisAI {
             model: self.model.clone(),
             messages,
             stream: Some(true),
-            temperature: Some(self.temperature),
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_completion_tokens: self.max_tokens,
             ..Default::default()
         };
 
-        let raw = process_ai_pipe_stream(&self.client, request, self.markdown_rendering).await?;
+        let raw = process_ai_pipe_stream(
+            &self.client,
+            request,
+            self.markdown_rendering,
+            self.code_highlight,
+            self.stream_idle_timeout,
+        )
+        .await?;
         Ok(raw)
     }
 }
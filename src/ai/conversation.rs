@@ -0,0 +1,468 @@
+//! 会話履歴へのコマンド実行結果の蓄積
+//!
+//! AI が `execute_shell_command` で提案したコマンドはエージェントループの外
+//! （Shell 側）で実行される。その実行結果を `ConversationState` に積み戻し、
+//! 次のターンで「さっきのコマンドの結果は?」に正確に答えられるようにする。
+//!
+//! Ctrl-C や無通信タイムアウトで打ち切った応答も、受信済みの部分をマーカー付きで積み
+//! （[`partial_response`]）、次の入力で「続けて」と頼めるようにする。
+
+use std::collections::HashSet;
+
+use async_openai::types::{
+    ChatCompletionMessageToolCall, ChatCompletionRequestAssistantMessage,
+    ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestMessage,
+    ChatCompletionRequestToolMessage, ChatCompletionRequestToolMessageContent,
+    ChatCompletionRequestUserMessage, ChatCompletionRequestUserMessageContent,
+};
+use tracing::debug;
+
+use crate::engine::CommandResult;
+
+use super::types::ConversationState;
+
+/// 会話履歴に積むコマンド出力（stdout / stderr それぞれ）の最大文字数。
+/// 大量出力のコマンドで次ターンのトークンを食い潰さないための上限。
+const COMMAND_OUTPUT_MAX_CHARS: usize = 8_000;
+
+/// 実行されなかった `execute_shell_command` に返すツール結果。
+const SHELL_CALL_NOT_EXECUTED: &str = "Not executed: the command was not run by the user's shell.";
+
+/// Ctrl-C で中断した応答の末尾に付けるマーカー（システムプロンプトで意味を伝えている）
+const INTERRUPTED_MARKER: &str = "[interrupted]";
+
+/// 無通信タイムアウトで打ち切った応答の末尾に付けるマーカー
+const TIMED_OUT_MARKER: &str = "[timed out]";
+
+/// 途中で打ち切った応答を、会話履歴に積むテキストにする。
+///
+/// 受信済みの部分にマーカーを付け、AI が次のターンで応答が途切れたことを把握して
+/// 続きから答えられるようにする。何も受信していなくてもマーカーだけを積み、
+/// 質問に答えていないことを残す。
+pub(super) fn partial_response(text: &str, timed_out: bool) -> String {
+    let marker = if timed_out {
+        TIMED_OUT_MARKER
+    } else {
+        INTERRUPTED_MARKER
+    };
+    let text = text.trim_end();
+    if text.is_empty() {
+        marker.to_string()
+    } else {
+        format!("{text}\n\n{marker}")
+    }
+}
+
+impl ConversationState {
+    /// AI が提案したコマンドの実行結果を会話履歴に追加する。
+    ///
+    /// 直前のアシスタントメッセージに未応答の `execute_shell_command` ツールコールが
+    /// あれば、その `tool_call_id` に対する tool メッセージとして積む。
+    /// 対応するツールコールがない場合（調査フローでの修正コマンド等）は
+    /// user メッセージとして積む。
+    ///
+    /// stdout / stderr はそれぞれ末尾 `max_lines` 行（`[ai] max_tool_output_lines`、0 は無制限）に
+    /// 切り詰めてから積む。
+    pub fn record_command_result(
+        &mut self,
+        command: &str,
+        result: &CommandResult,
+        max_lines: usize,
+    ) {
+        let content = format_command_result(command, result, max_lines);
+        match self.pending_shell_tool_call_id() {
+            Some(id) => {
+                debug!(tool_call_id = %id, command = %command, "Recording command result as tool message");
+                self.messages.push(tool_message(id, content));
+            }
+            None => {
+                debug!(command = %command, "Recording command result as user message");
+                self.messages.push(ChatCompletionRequestMessage::User(
+                    ChatCompletionRequestUserMessage {
+                        content: ChatCompletionRequestUserMessageContent::Text(content),
+                        name: None,
+                    },
+                ));
+            }
+        }
+    }
+
+    /// 未応答の `execute_shell_command` ツールコールを「未実行」として閉じる。
+    ///
+    /// assistant の tool_calls に対応する tool メッセージが欠けたまま次のリクエストを
+    /// 送ると API がエラーを返すため、会話継続の前に呼び出して整合性を保つ。
+    pub(crate) fn close_pending_shell_tool_call(&mut self) {
+        if let Some(id) = self.pending_shell_tool_call_id() {
+            debug!(tool_call_id = %id, "Closing pending shell tool call as not executed");
+            self.messages
+                .push(tool_message(id, SHELL_CALL_NOT_EXECUTED.to_string()));
+        }
+    }
+
+    /// 保留中の `execute_shell_command` について、AI が添えた実行理由を返す。
+    ///
+    /// ツール引数の `reason` を優先し、なければ同じアシスタントメッセージ本文の
+    /// 最初の空でない行を使う。`[ai] command_notice = "verbose"` の表示に使用する。
+    pub fn pending_command_reason(&self) -> Option<String> {
+        let (assistant, call) = self.pending_shell_tool_call()?;
+        let from_args = serde_json::from_str::<serde_json::Value>(&call.function.arguments)
+            .ok()
+            .and_then(|args| {
+                args.get("reason")
+                    .and_then(|r| r.as_str())
+                    .map(str::trim)
+                    .filter(|r| !r.is_empty())
+                    .map(String::from)
+            });
+        from_args.or_else(|| match &assistant.content {
+            Some(ChatCompletionRequestAssistantMessageContent::Text(text)) => text
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(String::from),
+            _ => None,
+        })
+    }
+
+    /// 最後のアシスタントメッセージのうち、tool メッセージで応答されていない
+    /// `execute_shell_command` ツールコールの ID を返す。
+    fn pending_shell_tool_call_id(&self) -> Option<String> {
+        self.pending_shell_tool_call()
+            .map(|(_, call)| call.id.clone())
+    }
+
+    /// 最後のアシスタントメッセージと、その中で tool メッセージで応答されていない
+    /// `execute_shell_command` ツールコールを返す。
+    fn pending_shell_tool_call(
+        &self,
+    ) -> Option<(
+        &ChatCompletionRequestAssistantMessage,
+        &ChatCompletionMessageToolCall,
+    )> {
+        let mut answered: HashSet<&str> = HashSet::new();
+        for msg in self.messages.iter().rev() {
+            match msg {
+                ChatCompletionRequestMessage::Tool(tool) => {
+                    answered.insert(tool.tool_call_id.as_str());
+                }
+                ChatCompletionRequestMessage::Assistant(assistant) => {
+                    let call = assistant.tool_calls.as_ref()?.iter().find(|c| {
+                        c.function.name == "execute_shell_command"
+                            && !answered.contains(c.id.as_str())
+                    })?;
+                    return Some((assistant, call));
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+}
+
+/// tool メッセージを構築する。
+fn tool_message(tool_call_id: String, content: String) -> ChatCompletionRequestMessage {
+    ChatCompletionRequestMessage::Tool(ChatCompletionRequestToolMessage {
+        content: ChatCompletionRequestToolMessageContent::Text(content),
+        tool_call_id,
+    })
+}
+
+/// コマンド実行結果を AI 向けのテキストに整形する。
+fn format_command_result(command: &str, result: &CommandResult, max_lines: usize) -> String {
+    let mut text = format!(
+        "Command executed: {command}\n\
+         Exit code: {}\n",
+        result.exit_code
+    );
+    if !result.stdout.is_empty() {
+        text.push_str(&format!(
+            "\nstdout:\n{}\n",
+            truncate_output(&result.stdout, max_lines)
+        ));
+    }
+    if !result.stderr.is_empty() {
+        text.push_str(&format!(
+            "\nstderr:\n{}\n",
+            truncate_output(&result.stderr, max_lines)
+        ));
+    }
+    if result.stdout.is_empty() && result.stderr.is_empty() {
+        text.push_str("\n(no output)\n");
+    }
+    text
+}
+
+/// 出力を末尾 `max_lines` 行に切り詰めた上で、[`COMMAND_OUTPUT_MAX_CHARS`] 文字に
+/// 切り詰める（文字境界を保つ）。
+fn truncate_output(output: &str, max_lines: usize) -> String {
+    let output = truncate_lines(output, max_lines);
+    let total = output.chars().count();
+    if total <= COMMAND_OUTPUT_MAX_CHARS {
+        return output;
+    }
+    let head: String = output.chars().take(COMMAND_OUTPUT_MAX_CHARS).collect();
+    format!(
+        "{head}\n... (output truncated, {} more characters)",
+        total - COMMAND_OUTPUT_MAX_CHARS
+    )
+}
+
+/// 出力を末尾 `max_lines` 行に切り詰め、省略した行数を先頭に明記する（0 は無制限）。
+fn truncate_lines(output: &str, max_lines: usize) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    if max_lines == 0 || lines.len() <= max_lines {
+        return output.to_string();
+    }
+    let skip = lines.len() - max_lines;
+    format!(
+        "... ({skip} lines omitted) ...\n{}",
+        lines[skip..].join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::types::ConversationOrigin;
+    use async_openai::types::{ChatCompletionToolType, FunctionCall};
+
+    fn user(text: &str) -> ChatCompletionRequestMessage {
+        ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
+            content: ChatCompletionRequestUserMessageContent::Text(text.to_string()),
+            name: None,
+        })
+    }
+
+    fn assistant_with_calls(calls: &[(&str, &str)]) -> ChatCompletionRequestMessage {
+        assistant_message(None, calls, "{}")
+    }
+
+    fn assistant_message(
+        text: Option<&str>,
+        calls: &[(&str, &str)],
+        arguments: &str,
+    ) -> ChatCompletionRequestMessage {
+        ChatCompletionRequestMessage::Assistant(ChatCompletionRequestAssistantMessage {
+            content: text
+                .map(|t| ChatCompletionRequestAssistantMessageContent::Text(t.to_string())),
+            refusal: None,
+            name: None,
+            audio: None,
+            tool_calls: Some(
+                calls
+                    .iter()
+                    .map(|(id, name)| ChatCompletionMessageToolCall {
+                        id: id.to_string(),
+                        r#type: ChatCompletionToolType::Function,
+                        function: FunctionCall {
+                            name: name.to_string(),
+                            arguments: arguments.to_string(),
+                        },
+                    })
+                    .collect(),
+            ),
+            #[allow(deprecated)]
+            function_call: None,
+        })
+    }
+
+    fn state(messages: Vec<ChatCompletionRequestMessage>) -> ConversationState {
+        ConversationState {
+            messages,
+            origin: ConversationOrigin::NaturalLanguage,
+        }
+    }
+
+    fn tool_text(msg: &ChatCompletionRequestMessage) -> (&str, &str) {
+        match msg {
+            ChatCompletionRequestMessage::Tool(t) => match &t.content {
+                ChatCompletionRequestToolMessageContent::Text(s) => (t.tool_call_id.as_str(), s),
+                _ => panic!("unexpected tool content"),
+            },
+            other => panic!("expected tool message, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn records_result_as_tool_message_for_pending_shell_call() {
+        let mut conv = state(vec![
+            user("list files"),
+            assistant_with_calls(&[("call_1", "execute_shell_command")]),
+        ]);
+        let result = CommandResult::success("a.txt\nb.txt\n".to_string());
+
+        conv.record_command_result("ls", &result, 200);
+
+        assert_eq!(conv.messages.len(), 3);
+        let (id, text) = tool_text(&conv.messages[2]);
+        assert_eq!(id, "call_1");
+        assert!(text.contains("Command executed: ls"));
+        assert!(text.contains("Exit code: 0"));
+        assert!(text.contains("a.txt\nb.txt"));
+    }
+
+    #[test]
+    fn skips_tool_calls_already_answered() {
+        // read_file は応答済み、execute_shell_command だけが保留中
+        let mut conv = state(vec![
+            user("fix and build"),
+            assistant_with_calls(&[
+                ("call_read", "read_file"),
+                ("call_shell", "execute_shell_command"),
+            ]),
+            tool_message("call_read".to_string(), "file content".to_string()),
+        ]);
+        let result = CommandResult::error("build failed\n".to_string(), 101);
+
+        conv.record_command_result("cargo build", &result, 200);
+
+        let (id, text) = tool_text(conv.messages.last().unwrap());
+        assert_eq!(id, "call_shell");
+        assert!(text.contains("Exit code: 101"));
+        assert!(text.contains("stderr:\nbuild failed"));
+    }
+
+    #[test]
+    fn records_result_as_user_message_without_pending_call() {
+        let mut conv = state(vec![user("why did it fail?")]);
+        let result = CommandResult::success(String::new());
+
+        conv.record_command_result("make", &result, 200);
+
+        assert_eq!(conv.messages.len(), 2);
+        match &conv.messages[1] {
+            ChatCompletionRequestMessage::User(u) => match &u.content {
+                ChatCompletionRequestUserMessageContent::Text(s) => {
+                    assert!(s.contains("Command executed: make"));
+                    assert!(s.contains("(no output)"));
+                }
+                _ => panic!("unexpected user content"),
+            },
+            other => panic!("expected user message, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn second_record_does_not_reuse_answered_call() {
+        let mut conv = state(vec![
+            user("run it"),
+            assistant_with_calls(&[("call_1", "execute_shell_command")]),
+        ]);
+        conv.record_command_result("true", &CommandResult::success(String::new()), 200);
+        conv.record_command_result("false", &CommandResult::error(String::new(), 1), 200);
+
+        assert_eq!(conv.messages.len(), 4);
+        assert!(matches!(
+            conv.messages[3],
+            ChatCompletionRequestMessage::User(_)
+        ));
+    }
+
+    #[test]
+    fn close_pending_shell_tool_call_marks_not_executed() {
+        let mut conv = state(vec![
+            user("run it"),
+            assistant_with_calls(&[("call_1", "execute_shell_command")]),
+        ]);
+        conv.close_pending_shell_tool_call();
+
+        let (id, text) = tool_text(conv.messages.last().unwrap());
+        assert_eq!(id, "call_1");
+        assert_eq!(text, SHELL_CALL_NOT_EXECUTED);
+
+        // 既に閉じられていれば何もしない
+        conv.close_pending_shell_tool_call();
+        assert_eq!(conv.messages.len(), 3);
+    }
+
+    #[test]
+    fn pending_command_reason_prefers_tool_argument() {
+        let conv = state(vec![
+            user("free up space"),
+            assistant_message(
+                Some("Let me check first."),
+                &[("call_1", "execute_shell_command")],
+                r#"{"command": "du -sh *", "reason": "Find the largest directories"}"#,
+            ),
+        ]);
+        assert_eq!(
+            conv.pending_command_reason().as_deref(),
+            Some("Find the largest directories")
+        );
+    }
+
+    #[test]
+    fn pending_command_reason_falls_back_to_assistant_text() {
+        let mut conv = state(vec![
+            user("free up space"),
+            assistant_message(
+                Some("\nChecking disk usage.\nThen I'll clean up."),
+                &[("call_1", "execute_shell_command")],
+                r#"{"command": "du -sh *"}"#,
+            ),
+        ]);
+        assert_eq!(
+            conv.pending_command_reason().as_deref(),
+            Some("Checking disk usage.")
+        );
+
+        // 応答済みなら理由も返さない
+        conv.record_command_result("du -sh *", &CommandResult::success(String::new()), 200);
+        assert!(conv.pending_command_reason().is_none());
+    }
+
+    #[test]
+    fn many_lines_keep_only_the_tail() {
+        let output: String = (1..=10).map(|i| format!("line {i}\n")).collect();
+        let truncated = truncate_lines(&output, 3);
+        assert_eq!(
+            truncated,
+            "... (7 lines omitted) ...\nline 8\nline 9\nline 10"
+        );
+        assert_eq!(truncate_lines(&output, 0), output);
+        assert_eq!(truncate_lines(&output, 10), output);
+    }
+
+    #[test]
+    fn recorded_result_uses_given_line_limit() {
+        let output: String = (1..=10).map(|i| format!("line {i}\n")).collect();
+        let mut conv = state(vec![]);
+        conv.record_command_result("seq 10", &CommandResult::success(output.clone()), 2);
+        conv.record_command_result("seq 10", &CommandResult::success(output), 0);
+        let texts: Vec<String> = conv
+            .messages
+            .iter()
+            .map(|m| match m {
+                ChatCompletionRequestMessage::User(u) => match &u.content {
+                    ChatCompletionRequestUserMessageContent::Text(t) => t.clone(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            })
+            .collect();
+        assert!(texts[0].contains("(8 lines omitted)"));
+        assert!(!texts[0].contains("line 1\n"));
+        assert!(texts[1].contains("line 1\n"));
+        assert!(!texts[1].contains("omitted"));
+    }
+
+    #[test]
+    fn long_output_is_truncated() {
+        let long = "x".repeat(COMMAND_OUTPUT_MAX_CHARS + 10);
+        let text = format_command_result("yes", &CommandResult::success(long), 0);
+        assert!(text.contains("output truncated, 10 more characters"));
+    }
+
+    #[test]
+    fn partial_response_is_marked() {
+        assert_eq!(
+            partial_response("Step 1: install\n", false),
+            "Step 1: install\n\n[interrupted]"
+        );
+        assert_eq!(
+            partial_response("Step 1: install", true),
+            "Step 1: install\n\n[timed out]"
+        );
+        assert_eq!(partial_response("", false), "[interrupted]");
+    }
+}
@@ -1,9 +1,13 @@
+mod cache;
 pub mod client;
+mod conversation;
 pub mod markdown;
 mod prompts;
 mod stream;
 mod tools;
+pub mod type_ahead;
 mod types;
+mod usage;
 
 pub use client::JarvisAI;
 #[allow(unused_imports)]
@@ -2,7 +2,8 @@
 //!
 //! OpenAI API からのストリーミングレスポンスを処理し、
 //! テキスト応答と Tool Call を分離して返す。
-//! Ctrl-C (SIGINT) による中断にも対応する。
+//! Ctrl-C (SIGINT) による中断と、応答が途絶えた場合の無通信タイムアウトにも対応する。
+//! 応答中の打鍵は [`TypeAheadGuard`] で退避し、次のプロンプトに引き継ぐ。
 
 use anyhow::{Context, Result};
 use async_openai::{config::OpenAIConfig, types::CreateChatCompletionRequest, Client};
@@ -11,15 +12,18 @@ use tokio::signal::unix::{signal, SignalKind};
 use tracing::{debug, info, warn};
 
 use std::io::Write;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::cli::color::red;
 use crate::cli::jarvis::{
     jarvis_print_plain, jarvis_render_markdown, jarvis_spinner, render_markdown,
 };
+use crate::engine::classifier::strip_goodbye_marker;
 
 use super::markdown::is_markdown;
 use super::tools::call::{accumulate_tool_call, ToolCallAccumulator};
+use super::type_ahead::TypeAheadGuard;
+use super::usage::UsageStats;
 
 /// ストリーム処理の結果
 pub struct StreamResult {
@@ -29,17 +33,41 @@ pub struct StreamResult {
     pub tool_calls: Vec<ToolCallAccumulator>,
     /// Ctrl-C (SIGINT) でストリームが中断されたかどうか
     pub interrupted: bool,
+    /// 無通信タイムアウトでストリームを打ち切ったかどうか
+    pub timed_out: bool,
+    /// API が返したトークン使用量（`stream_options.include_usage` 指定時の最終チャンク）
+    pub usage: Option<UsageStats>,
+}
+
+/// 無通信タイムアウトまで待つ。`limit` が `None`（無効）の場合は完了しない。
+///
+/// `tokio::select!` のループ内で毎回作り直すことで、直前のチャンク受信からの経過時間を測る。
+async fn idle_timeout(limit: Option<Duration>) {
+    match limit {
+        Some(limit) => tokio::time::sleep(limit).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 無通信タイムアウト時に表示する注記。
+fn timed_out_notice(limit: Option<Duration>) -> String {
+    let secs = limit.map_or(0, |l| l.as_secs());
+    red(&format!("[timed out: no response for {secs}s]"))
 }
 
 /// ストリーミングレスポンスを処理し、テキストと Tool Call を分離して返す。
 ///
 /// `is_first_round`: true の場合、初回ラウンドでスピナーを表示する。
 /// 後続ラウンドではツール実行中のメッセージを表示する。
+/// `code_highlight`: Markdown 表示時にコードブロックをシンタックスハイライトするか。
+/// `idle_timeout_limit`: 接続待ち・チャンク間でこの時間応答がなければ打ち切る（`None` で無効）。
 pub async fn process_stream(
     client: &Client<OpenAIConfig>,
     request: CreateChatCompletionRequest,
     is_first_round: bool,
     markdown_rendering: bool,
+    code_highlight: bool,
+    idle_timeout_limit: Option<Duration>,
 ) -> Result<StreamResult> {
     // SIGINT (Ctrl-C) リスナーを作成。
     // tokio::signal::unix::signal() は作成時点以降のシグナルのみ受け取るため、
@@ -47,6 +75,9 @@ pub async fn process_stream(
     let mut sigint =
         signal(SignalKind::interrupt()).context("Failed to register SIGINT handler")?;
 
+    // 応答の表示中は打鍵をエコーせず退避する（Enter で次のコマンドが実行されないように）
+    let _type_ahead = TypeAheadGuard::new();
+
     // ローディングスピナーを開始
     let spinner = jarvis_spinner();
 
@@ -69,6 +100,20 @@ pub async fn process_stream(
                 full_text: String::new(),
                 tool_calls: vec![],
                 interrupted: true,
+                timed_out: false,
+                usage: None,
+            });
+        }
+        _ = idle_timeout(idle_timeout_limit) => {
+            warn!(timeout = ?idle_timeout_limit, "Timed out while waiting for API connection");
+            spinner.finish_and_clear();
+            eprintln!("{}", timed_out_notice(idle_timeout_limit));
+            return Ok(StreamResult {
+                full_text: String::new(),
+                tool_calls: vec![],
+                interrupted: false,
+                timed_out: true,
+                usage: None,
             });
         }
     };
@@ -81,6 +126,8 @@ pub async fn process_stream(
     let mut started_text = false;
     let mut chunk_count: u32 = 0;
     let mut interrupted = false;
+    let mut timed_out = false;
+    let mut usage: Option<UsageStats> = None;
     let mut last_spinner_update = Instant::now();
 
     loop {
@@ -107,6 +154,11 @@ pub async fn process_stream(
                     }
                 };
 
+                // usage は choices が空の最終チャンクにのみ含まれる
+                if let Some(ref u) = response.usage {
+                    usage = Some(UsageStats::measured(u.prompt_tokens, u.completion_tokens));
+                }
+
                 for choice in &response.choices {
                     let delta = &choice.delta;
 
@@ -160,6 +212,16 @@ pub async fn process_stream(
                 interrupted = true;
                 break;
             }
+            _ = idle_timeout(idle_timeout_limit) => {
+                warn!(
+                    timeout = ?idle_timeout_limit,
+                    chunks_received = chunk_count,
+                    text_so_far_len = full_text.len(),
+                    "No chunk received within idle timeout, aborting AI stream"
+                );
+                timed_out = true;
+                break;
+            }
         }
     }
 
@@ -170,17 +232,27 @@ pub async fn process_stream(
     spinner.finish_and_clear();
 
     if started_text {
-        let render = if markdown_rendering && is_markdown(&full_text) {
-            jarvis_render_markdown
-        } else {
-            jarvis_print_plain
+        // 会話終了マーカーは表示しない（シェル側が goodbye 判定に使うため full_text には残す）
+        let full_text = strip_goodbye_marker(&full_text).unwrap_or_else(|| full_text.clone());
+        let as_markdown = markdown_rendering && is_markdown(&full_text);
+        let render = |text: &str| {
+            if as_markdown {
+                jarvis_render_markdown(text, code_highlight);
+            } else {
+                jarvis_print_plain(text);
+            }
         };
         if interrupted {
             let display_text = format!("{}\n\n{}", full_text, red("[interrupted]"));
             render(&display_text);
+        } else if timed_out {
+            let display_text = format!("{}\n\n{}", full_text, timed_out_notice(idle_timeout_limit));
+            render(&display_text);
         } else {
             render(&full_text);
         }
+    } else if timed_out {
+        eprintln!("{}", timed_out_notice(idle_timeout_limit));
     }
 
     debug!(
@@ -190,6 +262,8 @@ pub async fn process_stream(
         started_text = started_text,
         is_first_round = is_first_round,
         interrupted = interrupted,
+        timed_out = timed_out,
+        usage = ?usage,
         "Stream processing completed"
     );
 
@@ -197,6 +271,8 @@ pub async fn process_stream(
         full_text,
         tool_calls,
         interrupted,
+        timed_out,
+        usage,
     })
 }
 
@@ -208,20 +284,26 @@ pub async fn process_stream(
 ///
 /// `markdown_rendering` が `true` の場合:
 ///   バッファリングモードで動作し、完了後に `is_markdown()` で判定。
-///   Markdown であれば `render_markdown()` でレンダリングする。
+///   Markdown であれば `render_markdown()` でレンダリングする（`code_highlight` でコードブロックを色付け）。
 ///
 /// `markdown_rendering` が `false` の場合:
 ///   従来通りチャンクを即時 stdout に流す（tee パターン）。
 ///
+/// `idle_timeout_limit` の扱いは `process_stream()` と同じ（打ち切り時は受信済みのテキストを返す）。
+///
 /// 返却値: AI が出力したテキスト全文（`CommandResult.stdout` に格納用）
 pub async fn process_ai_pipe_stream(
     client: &Client<OpenAIConfig>,
     request: CreateChatCompletionRequest,
     markdown_rendering: bool,
+    code_highlight: bool,
+    idle_timeout_limit: Option<Duration>,
 ) -> Result<String> {
     let mut sigint =
         signal(SignalKind::interrupt()).context("Failed to register SIGINT handler")?;
 
+    let _type_ahead = TypeAheadGuard::new();
+
     let spinner = jarvis_spinner();
 
     let chat = client.chat();
@@ -240,6 +322,12 @@ pub async fn process_ai_pipe_stream(
             spinner.finish_and_clear();
             return Ok(String::new());
         }
+        _ = idle_timeout(idle_timeout_limit) => {
+            warn!(timeout = ?idle_timeout_limit, "Timed out while waiting for AI pipe API connection");
+            spinner.finish_and_clear();
+            eprintln!("{}", timed_out_notice(idle_timeout_limit));
+            return Ok(String::new());
+        }
     };
 
     spinner.set_message("Thinking...");
@@ -247,6 +335,7 @@ pub async fn process_ai_pipe_stream(
     let mut full_text = String::new();
     let mut started = false;
     let mut interrupted = false;
+    let mut timed_out = false;
     let mut last_spinner_update = Instant::now();
 
     loop {
@@ -296,6 +385,15 @@ pub async fn process_ai_pipe_stream(
                 interrupted = true;
                 break;
             }
+            _ = idle_timeout(idle_timeout_limit) => {
+                warn!(
+                    timeout = ?idle_timeout_limit,
+                    text_so_far_len = full_text.len(),
+                    "No chunk received within idle timeout, aborting AI pipe stream"
+                );
+                timed_out = true;
+                break;
+            }
         }
     }
 
@@ -309,20 +407,24 @@ pub async fn process_ai_pipe_stream(
             if is_markdown(&full_text) {
                 if interrupted {
                     let display_text = format!("{}\n\n{}", full_text, red("[interrupted]"));
-                    render_markdown(&display_text);
+                    render_markdown(&display_text, code_highlight);
+                } else if timed_out {
+                    let display_text =
+                        format!("{}\n\n{}", full_text, timed_out_notice(idle_timeout_limit));
+                    render_markdown(&display_text, code_highlight);
                 } else {
-                    render_markdown(&full_text);
+                    render_markdown(&full_text, code_highlight);
                 }
             } else {
-                print!("{full_text}");
-                if !full_text.ends_with('\n') {
-                    println!();
-                }
+                println!("{}", full_text.trim_end_matches('\n'));
                 if interrupted {
                     eprintln!("{}", red("[interrupted]"));
                 }
             }
         }
+        if timed_out && (!started || !is_markdown(&full_text)) {
+            eprintln!("{}", timed_out_notice(idle_timeout_limit));
+        }
     } else {
         if !started {
             spinner.finish_and_clear();
@@ -339,14 +441,47 @@ pub async fn process_ai_pipe_stream(
         if interrupted {
             eprintln!("{}", red("[interrupted]"));
         }
+        if timed_out {
+            eprintln!("{}", timed_out_notice(idle_timeout_limit));
+        }
     }
 
     debug!(
         full_text_length = full_text.len(),
         interrupted = interrupted,
+        timed_out = timed_out,
         markdown_rendering = markdown_rendering,
         "AI pipe stream processing completed"
     );
 
     Ok(full_text)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn idle_timeout_fires_after_limit() {
+        let fired = tokio::select! {
+            _ = idle_timeout(Some(Duration::from_millis(10))) => true,
+            _ = tokio::time::sleep(Duration::from_secs(5)) => false,
+        };
+        assert!(fired);
+    }
+
+    #[tokio::test]
+    async fn idle_timeout_disabled_never_fires() {
+        let fired = tokio::select! {
+            _ = idle_timeout(None) => true,
+            _ = tokio::time::sleep(Duration::from_millis(50)) => false,
+        };
+        assert!(!fired);
+    }
+
+    #[test]
+    fn timed_out_notice_mentions_seconds() {
+        let notice = timed_out_notice(Some(Duration::from_secs(60)));
+        assert!(notice.contains("[timed out: no response for 60s]"));
+    }
+}
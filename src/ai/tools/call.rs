@@ -44,9 +44,11 @@ pub fn accumulate_tool_call(
     }
 }
 
-/// 蓄積した Tool Call から execute_shell_command のコマンド文字列を抽出する。
+/// 蓄積した Tool Call から execute_shell_command の呼び出しとコマンド文字列を抽出する。
 /// read_file / write_file はここでは抽出しない。
-pub fn extract_shell_command(tool_calls: &[ToolCallAccumulator]) -> Option<String> {
+pub fn extract_shell_command(
+    tool_calls: &[ToolCallAccumulator],
+) -> Option<(&ToolCallAccumulator, String)> {
     for tc in tool_calls {
         debug!(
             function_name = %tc.function_name,
@@ -60,7 +62,7 @@ pub fn extract_shell_command(tool_calls: &[ToolCallAccumulator]) -> Option<Strin
                 Ok(parsed) => {
                     if let Some(cmd) = parsed.get("command").and_then(|v| v.as_str()) {
                         debug!(extracted_command = %cmd, "Successfully extracted command from tool call");
-                        return Some(cmd.to_string());
+                        return Some((tc, cmd.to_string()));
                     }
                     warn!(parsed = %parsed, "Tool call JSON parsed but 'command' field not found");
                 }
@@ -117,14 +119,14 @@ mod tests {
             arguments: r#"{"command": "ls -la"}"#.to_string(),
         }];
 
-        let cmd = extract_shell_command(&tool_calls);
+        let cmd = extract_shell_command(&tool_calls).map(|(_, cmd)| cmd);
         assert_eq!(cmd, Some("ls -la".to_string()));
     }
 
     #[test]
     fn extract_shell_command_returns_none_for_empty() {
         let tool_calls: Vec<ToolCallAccumulator> = Vec::new();
-        let cmd = extract_shell_command(&tool_calls);
+        let cmd = extract_shell_command(&tool_calls).map(|(_, cmd)| cmd);
         assert!(cmd.is_none());
     }
 
@@ -136,7 +138,7 @@ mod tests {
             arguments: "invalid json".to_string(),
         }];
 
-        let cmd = extract_shell_command(&tool_calls);
+        let cmd = extract_shell_command(&tool_calls).map(|(_, cmd)| cmd);
         assert!(cmd.is_none());
     }
 
@@ -155,7 +157,7 @@ mod tests {
             },
         ];
 
-        let cmd = extract_shell_command(&tool_calls);
+        let cmd = extract_shell_command(&tool_calls).map(|(_, cmd)| cmd);
         assert!(cmd.is_none());
     }
 
@@ -227,7 +229,7 @@ mod tests {
             },
         ];
 
-        let cmd = extract_shell_command(&tool_calls);
+        let cmd = extract_shell_command(&tool_calls).map(|(_, cmd)| cmd);
         assert_eq!(cmd, Some("make build".to_string()));
 
         let non_shell = extract_non_shell_tools(&tool_calls);
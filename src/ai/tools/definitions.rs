@@ -20,6 +20,10 @@ pub fn shell_command_tool() -> ChatCompletionTool {
                     "command": {
                         "type": "string",
                         "description": "The full shell command to execute"
+                    },
+                    "reason": {
+                        "type": "string",
+                        "description": "A short one-line explanation of why this command is being run"
                     }
                 },
                 "required": ["command"]
@@ -36,7 +40,10 @@ pub fn read_file_tool() -> ChatCompletionTool {
         function: FunctionObject {
             name: "read_file".to_string(),
             description: Some(
-                "Read the contents of a file. Use this to inspect a file before editing it. The path is relative to the user's current working directory."
+                "Read the contents of a file. Use this to inspect a file before editing it. \
+                 Each line is prefixed with its 1-based line number and a tab (the prefix is NOT part of the file content). \
+                 Large files are truncated; use start_line/end_line to read a specific range. \
+                 The path is relative to the user's current working directory."
                     .to_string(),
             ),
             parameters: Some(serde_json::json!({
@@ -45,6 +52,14 @@ pub fn read_file_tool() -> ChatCompletionTool {
                     "path": {
                         "type": "string",
                         "description": "The file path to read (relative to CWD)"
+                    },
+                    "start_line": {
+                        "type": "integer",
+                        "description": "First line to read (1-based, inclusive). Defaults to 1."
+                    },
+                    "end_line": {
+                        "type": "integer",
+                        "description": "Last line to read (1-based, inclusive). Defaults to the end of the file."
                     }
                 },
                 "required": ["path"]
@@ -54,14 +69,52 @@ pub fn read_file_tool() -> ChatCompletionTool {
     }
 }
 
+/// read_files ツールの定義（複数ファイルを 1 回のツールコールで読む）
+pub fn read_files_tool() -> ChatCompletionTool {
+    ChatCompletionTool {
+        r#type: ChatCompletionToolType::Function,
+        function: FunctionObject {
+            name: "read_files".to_string(),
+            description: Some(
+                "Read several files at once (up to 20). Prefer this over calling read_file repeatedly \
+                 when you need to look at multiple files, e.g. for a refactoring. \
+                 Each file is returned under a `=== <path> ===` header, with every line prefixed by its 1-based line number and a tab. \
+                 Each file is truncated to 500 lines and the total output is size-limited; use read_file with start_line/end_line for the rest. \
+                 Files that cannot be read are reported with an error line under their header. \
+                 Paths are relative to the user's current working directory."
+                    .to_string(),
+            ),
+            parameters: Some(serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "paths": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "The file paths to read (relative to CWD)"
+                    }
+                },
+                "required": ["paths"]
+            })),
+            strict: None,
+        },
+    }
+}
+
 /// write_file ツールの定義
+///
+/// `mode` で上書き/追記を切り替え、`old_string`/`new_string` 指定時は部分置換として動作する。
 pub fn write_file_tool() -> ChatCompletionTool {
     ChatCompletionTool {
         r#type: ChatCompletionToolType::Function,
         function: FunctionObject {
             name: "write_file".to_string(),
             description: Some(
-                "Write content to a file, creating it if it doesn't exist or overwriting if it does. Always read_file first before writing to preserve existing content. The path is relative to the user's current working directory."
+                "Write content to a file, creating it if it doesn't exist. \
+                 By default the file is overwritten (mode=\"overwrite\"); use mode=\"append\" to add content to the end. \
+                 Alternatively, pass old_string and new_string (without content) to replace exactly one occurrence; \
+                 zero or multiple matches are rejected, and a diff of the change is returned. \
+                 Always read_file first before writing to preserve existing content. \
+                 The path is relative to the user's current working directory."
                     .to_string(),
             ),
             parameters: Some(serde_json::json!({
@@ -73,10 +126,23 @@ pub fn write_file_tool() -> ChatCompletionTool {
                     },
                     "content": {
                         "type": "string",
-                        "description": "The complete file content to write"
+                        "description": "The content to write (complete file content for overwrite, appended text for append). Not used with old_string."
+                    },
+                    "mode": {
+                        "type": "string",
+                        "enum": ["overwrite", "append"],
+                        "description": "How to write content. Defaults to overwrite."
+                    },
+                    "old_string": {
+                        "type": "string",
+                        "description": "Exact string to replace (must be unique within the file). Enables partial replace mode."
+                    },
+                    "new_string": {
+                        "type": "string",
+                        "description": "The replacement string for old_string"
                     }
                 },
-                "required": ["path", "content"]
+                "required": ["path"]
             })),
             strict: None,
         },
@@ -118,3 +184,34 @@ pub fn search_replace_tool() -> ChatCompletionTool {
         },
     }
 }
+
+/// get_project_tree ツールの定義
+pub fn project_tree_tool() -> ChatCompletionTool {
+    ChatCompletionTool {
+        r#type: ChatCompletionToolType::Function,
+        function: FunctionObject {
+            name: "get_project_tree".to_string(),
+            description: Some(
+                "Get the directory tree of the user's project to understand its structure. \
+                 Paths ignored by .gitignore and heavy directories (.git, node_modules, target, etc.) are excluded. \
+                 Large trees are truncated; request a subdirectory path or a smaller depth for details. \
+                 The path is relative to the user's current working directory."
+                    .to_string(),
+            ),
+            parameters: Some(serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Directory to list (relative to CWD). Defaults to the CWD."
+                    },
+                    "depth": {
+                        "type": "integer",
+                        "description": "How many directory levels to descend (1-8). Defaults to 3."
+                    }
+                }
+            })),
+            strict: None,
+        },
+    }
+}
@@ -1,11 +1,15 @@
 //! AI ツールのローカル実行
 //!
-//! AI が呼び出したツール（read_file, write_file, search_replace）をローカルで実行する。
+//! AI が呼び出したツール（read_file, read_files, write_file, search_replace, get_project_tree）を
+//! ローカルで実行する。
 //! execute_shell_command はここでは処理しない（呼び出し前にフィルタ済み）。
 
 use tracing::{debug, info, warn};
 
-use crate::cli::jarvis::{jarvis_read_file, jarvis_search_replace, jarvis_write_file};
+use super::tree::{build_project_tree, TREE_DEFAULT_DEPTH, TREE_MAX_DEPTH};
+use crate::cli::jarvis::{
+    jarvis_project_tree, jarvis_read_file, jarvis_search_replace, jarvis_write_file,
+};
 
 /// ツール名と引数に基づいてローカルでツールを実行する。
 /// execute_shell_command はこの関数では処理しない（呼び出し前にフィルタ済み）。
@@ -18,8 +22,10 @@ pub fn execute_tool(function_name: &str, arguments: &str) -> String {
 
     match function_name {
         "read_file" => execute_read_file(arguments),
+        "read_files" => execute_read_files(arguments),
         "write_file" => execute_write_file(arguments),
         "search_replace" => execute_search_replace(arguments),
+        "get_project_tree" => execute_get_project_tree(arguments),
         other => {
             warn!(tool = %other, "Unknown tool called");
             format!("Error: Unknown tool '{other}'")
@@ -27,6 +33,118 @@ pub fn execute_tool(function_name: &str, arguments: &str) -> String {
     }
 }
 
+/// read_file で一度に返す最大行数。
+/// 範囲指定の有無に関わらず、これを超えた分は切り詰めて残り行数を通知する。
+const READ_FILE_MAX_LINES: usize = 2000;
+
+/// read_file で一度に返す最大バイト数（行番号プレフィックスを除く本文ベース）。
+/// 1 行が極端に長いファイル（minify 済み JS 等）でトークンを食い潰さないための上限。
+const READ_FILE_MAX_BYTES: usize = 100_000;
+
+/// read_files で 1 回に指定できる最大ファイル数
+const READ_FILES_MAX_PATHS: usize = 20;
+
+/// read_files でファイルごとに返す最大行数
+const READ_FILES_MAX_LINES_PER_FILE: usize = 500;
+
+/// read_files で返す本文の合計最大バイト数。
+/// 上限に達した後のファイルは読まずに、read_file で個別に読むよう通知する。
+const READ_FILES_MAX_TOTAL_BYTES: usize = 200_000;
+
+/// read_file の内部ロジック（テスト用に分離）。
+///
+/// `start_line` / `end_line` は 1-based・両端含む。省略時はファイル先頭/末尾。
+/// 各行は `{行番号}\t{内容}` 形式で返し、AI が次の範囲指定をしやすくする。
+/// [`READ_FILE_MAX_LINES`] / [`READ_FILE_MAX_BYTES`] を超えた場合は先頭部分のみ返し、
+/// 末尾に `... (file truncated, N more lines)` を付与する。1 行目だけで
+/// [`READ_FILE_MAX_BYTES`] を超える場合はその行を途中で切り、`... (line N truncated, ...)` を付与する。
+fn read_file_inner(path: &str, start_line: Option<usize>, end_line: Option<usize>) -> String {
+    read_file_limited(
+        path,
+        start_line,
+        end_line,
+        READ_FILE_MAX_LINES,
+        READ_FILE_MAX_BYTES,
+    )
+}
+
+/// [`read_file_inner`] の本体。行数・バイト数の上限を呼び出し元が指定する（read_files 用）。
+fn read_file_limited(
+    path: &str,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+    max_lines: usize,
+    max_bytes: usize,
+) -> String {
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            warn!(path = %path, error = %e, "Failed to read file");
+            return format!("Error reading file '{path}': {e}");
+        }
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let total = lines.len();
+    let start = start_line.unwrap_or(1).max(1);
+    let end = end_line.unwrap_or(total).min(total);
+
+    if total == 0 {
+        return String::new();
+    }
+    if start > total {
+        return format!("Error: start_line {start} is beyond the end of '{path}' ({total} lines)");
+    }
+    if start > end {
+        return format!("Error: start_line ({start}) must not exceed end_line ({end})");
+    }
+
+    let mut output = String::new();
+    let mut bytes = 0;
+    let mut last_emitted = start - 1;
+    for (idx, line) in lines[start - 1..end].iter().enumerate() {
+        let line_no = start + idx;
+        let emitted = line_no - start;
+        if emitted >= max_lines || (emitted > 0 && bytes + line.len() > max_bytes) {
+            break;
+        }
+        if line.len() > max_bytes {
+            // 1 行目だけで上限を超える（minify 済み JS 等）場合は、char 境界で行を切って返す
+            let mut cut = max_bytes;
+            while !line.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            output.push_str(&format!("{line_no}\t{}\n", &line[..cut]));
+            output.push_str(&format!(
+                "... (line {line_no} truncated, {} more bytes)\n",
+                line.len() - cut
+            ));
+            last_emitted = line_no;
+            break;
+        }
+        output.push_str(&format!("{line_no}\t{line}\n"));
+        bytes += line.len() + 1;
+        last_emitted = line_no;
+    }
+
+    let remaining = end - last_emitted;
+    if remaining > 0 {
+        output.push_str(&format!(
+            "... (file truncated, {remaining} more lines; use start_line={} to continue)\n",
+            last_emitted + 1
+        ));
+    }
+
+    info!(
+        path = %path,
+        total_lines = total,
+        start_line = start,
+        end_line = last_emitted,
+        "File read successfully"
+    );
+    output
+}
+
 /// read_file ツールのローカル実行
 fn execute_read_file(arguments: &str) -> String {
     let parsed: serde_json::Value = match serde_json::from_str(arguments) {
@@ -38,28 +156,117 @@ fn execute_read_file(arguments: &str) -> String {
         Some(p) => p,
         None => return "Error: 'path' parameter is required".to_string(),
     };
+    let start_line = parsed
+        .get("start_line")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize);
+    let end_line = parsed
+        .get("end_line")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize);
 
     let spinner = jarvis_read_file(path);
+    let result = read_file_inner(path, start_line, end_line);
+    spinner.finish_and_clear();
 
-    let result = match std::fs::read_to_string(path) {
-        Ok(content) => {
-            info!(path = %path, content_length = content.len(), "File read successfully");
-            content
+    if !result.starts_with("Error") {
+        match (start_line, end_line) {
+            (None, None) => println!("  📖 Read: {path}"),
+            (s, e) => println!(
+                "  📖 Read: {path} (L{}-{})",
+                s.unwrap_or(1),
+                e.map(|n| format!("L{n}"))
+                    .unwrap_or_else(|| "EOF".to_string())
+            ),
         }
-        Err(e) => {
-            warn!(path = %path, error = %e, "Failed to read file");
-            format!("Error reading file '{path}': {e}")
+    }
+    result
+}
+
+/// read_files の内部ロジック（テスト用に分離）。
+///
+/// 各ファイルを `=== {path} ===` の見出しに続けて read_file と同じ行番号付き形式で連結する。
+/// ファイルごとに [`READ_FILES_MAX_LINES_PER_FILE`] 行までに切り詰め、本文の合計が
+/// [`READ_FILES_MAX_TOTAL_BYTES`] に達した後のファイルは読まずにスキップを通知する。
+/// 読めないファイルはその見出しの下にエラー行を含め、残りのファイルは続けて読む。
+fn read_files_inner(paths: &[&str]) -> String {
+    let mut output = String::new();
+    let mut remaining_bytes = READ_FILES_MAX_TOTAL_BYTES;
+    for path in paths {
+        output.push_str(&format!("=== {path} ===\n"));
+        if remaining_bytes == 0 {
+            output.push_str("... (skipped: total size limit reached; use read_file to read it)\n");
+            continue;
+        }
+        let content = read_file_limited(
+            path,
+            None,
+            None,
+            READ_FILES_MAX_LINES_PER_FILE,
+            remaining_bytes.min(READ_FILE_MAX_BYTES),
+        );
+        remaining_bytes = remaining_bytes.saturating_sub(content.len());
+        output.push_str(&content);
+        if !content.ends_with('\n') {
+            output.push('\n');
         }
+    }
+    output
+}
+
+/// read_files ツールのローカル実行
+fn execute_read_files(arguments: &str) -> String {
+    let parsed: serde_json::Value = match serde_json::from_str(arguments) {
+        Ok(v) => v,
+        Err(e) => return format!("Error parsing arguments: {e}"),
     };
 
-    spinner.finish_and_clear();
-    if !result.starts_with("Error") {
-        println!("  📖 Read: {path}");
+    let paths: Vec<&str> = match parsed.get("paths").and_then(|v| v.as_array()) {
+        Some(paths) => paths.iter().filter_map(|v| v.as_str()).collect(),
+        None => return "Error: 'paths' parameter is required".to_string(),
+    };
+    if paths.is_empty() {
+        return "Error: 'paths' must contain at least one file path".to_string();
     }
+    if paths.len() > READ_FILES_MAX_PATHS {
+        return format!(
+            "Error: too many paths ({}, max {READ_FILES_MAX_PATHS}); split them into several calls",
+            paths.len()
+        );
+    }
+
+    let joined = paths.join(", ");
+    let spinner = jarvis_read_file(&joined);
+    let result = read_files_inner(&paths);
+    spinner.finish_and_clear();
+
+    println!("  📖 Read: {joined}");
     result
 }
 
+/// write_file の書き込みモード
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WriteMode {
+    /// ファイル全体を上書きする（デフォルト）
+    Overwrite,
+    /// ファイル末尾に追記する
+    Append,
+}
+
+impl WriteMode {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "overwrite" => Some(Self::Overwrite),
+            "append" => Some(Self::Append),
+            _ => None,
+        }
+    }
+}
+
 /// write_file ツールのローカル実行
+///
+/// `old_string` が指定された場合は部分置換（search_replace と同じ一意マッチ規則）、
+/// それ以外は `mode`（`overwrite` / `append`）に従って `content` を書き込む。
 fn execute_write_file(arguments: &str) -> String {
     let parsed: serde_json::Value = match serde_json::from_str(arguments) {
         Ok(v) => v,
@@ -71,44 +278,130 @@ fn execute_write_file(arguments: &str) -> String {
         None => return "Error: 'path' parameter is required".to_string(),
     };
 
+    // 部分置換モード: old_string → new_string
+    if let Some(old_string) = parsed.get("old_string").and_then(|v| v.as_str()) {
+        let new_string = match parsed.get("new_string").and_then(|v| v.as_str()) {
+            Some(s) => s,
+            None => {
+                return "Error: 'new_string' parameter is required when 'old_string' is given"
+                    .to_string()
+            }
+        };
+        let spinner = jarvis_write_file(path);
+        let result = search_replace_inner(path, old_string, new_string);
+        spinner.finish_and_clear();
+        if result.starts_with("Successfully") {
+            println!("  🔧 Patched: {path}");
+        }
+        return result;
+    }
+
     let content = match parsed.get("content").and_then(|v| v.as_str()) {
         Some(c) => c,
         None => return "Error: 'content' parameter is required".to_string(),
     };
 
+    let mode = match parsed.get("mode").and_then(|v| v.as_str()) {
+        None => WriteMode::Overwrite,
+        Some(m) => match WriteMode::parse(m) {
+            Some(mode) => mode,
+            None => return format!("Error: invalid mode '{m}' (expected 'overwrite' or 'append')"),
+        },
+    };
+
     let spinner = jarvis_write_file(path);
+    let result = write_file_inner(path, content, mode);
+    spinner.finish_and_clear();
+    if result.starts_with("Successfully") {
+        match mode {
+            WriteMode::Overwrite => println!("  📝 Wrote: {path}"),
+            WriteMode::Append => println!("  📝 Appended: {path}"),
+        }
+    }
+    result
+}
 
-    // 親ディレクトリが存在しない場合は作成
+/// write_file の内部ロジック（テスト用に分離）。
+/// 親ディレクトリが存在しない場合は作成してから書き込む。
+fn write_file_inner(path: &str, content: &str, mode: WriteMode) -> String {
     if let Some(parent) = std::path::Path::new(path).parent() {
         if !parent.as_os_str().is_empty() {
             if let Err(e) = std::fs::create_dir_all(parent) {
                 warn!(path = %path, error = %e, "Failed to create parent directory");
-                spinner.finish_and_clear();
                 return format!("Error creating directory for '{path}': {e}");
             }
         }
     }
 
-    let result = match std::fs::write(path, content) {
+    let written = match mode {
+        WriteMode::Overwrite => std::fs::write(path, content),
+        WriteMode::Append => std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut f| std::io::Write::write_all(&mut f, content.as_bytes())),
+    };
+
+    match written {
         Ok(()) => {
-            info!(path = %path, content_length = content.len(), "File written successfully");
-            format!("Successfully wrote {} bytes to '{path}'", content.len())
+            info!(path = %path, content_length = content.len(), mode = ?mode, "File written successfully");
+            match mode {
+                WriteMode::Overwrite => {
+                    format!("Successfully wrote {} bytes to '{path}'", content.len())
+                }
+                WriteMode::Append => {
+                    format!("Successfully appended {} bytes to '{path}'", content.len())
+                }
+            }
         }
         Err(e) => {
             warn!(path = %path, error = %e, "Failed to write file");
             format!("Error writing file '{path}': {e}")
         }
-    };
+    }
+}
 
-    spinner.finish_and_clear();
-    if result.starts_with("Successfully") {
-        println!("  📝 Wrote: {path}");
+/// 一意マッチ置換の前後を行単位の簡易 unified diff として整形する。
+///
+/// 置換箇所は 1 箇所のみなので、マッチ位置を含む行範囲だけを 1 ハンクとして出力する。
+/// AI が置換結果を確認できるよう、ツール結果に添えて返す。
+fn replacement_diff(content: &str, offset: usize, old_string: &str, new_string: &str) -> String {
+    // マッチを含む行全体に範囲を広げる
+    let line_start = content[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let match_end = offset + old_string.len();
+    let line_end = content[match_end..]
+        .find('\n')
+        .map(|i| match_end + i)
+        .unwrap_or(content.len());
+
+    let old_block = &content[line_start..line_end];
+    let new_block = format!(
+        "{}{new_string}{}",
+        &content[line_start..offset],
+        &content[match_end..line_end]
+    );
+
+    let start_line_no = content[..line_start].matches('\n').count() + 1;
+    let old_lines: Vec<&str> = old_block.split('\n').collect();
+    let new_lines: Vec<&str> = new_block.split('\n').collect();
+
+    let mut diff = format!(
+        "@@ -{start_line_no},{} +{start_line_no},{} @@\n",
+        old_lines.len(),
+        new_lines.len()
+    );
+    for line in &old_lines {
+        diff.push_str(&format!("-{line}\n"));
     }
-    result
+    for line in &new_lines {
+        diff.push_str(&format!("+{line}\n"));
+    }
+    diff
 }
 
 /// search_replace の内部ロジック（テスト用に分離）。
 /// スピナーなしで純粋な置換処理のみを行う。
+/// write_file の部分置換モードからも共有され、成功時は置換箇所の diff を返す。
 fn search_replace_inner(path: &str, old_string: &str, new_string: &str) -> String {
     let content = match std::fs::read_to_string(path) {
         Ok(c) => c,
@@ -135,7 +428,9 @@ fn search_replace_inner(path: &str, old_string: &str, new_string: &str) -> Strin
     match std::fs::write(path, &new_content) {
         Ok(()) => {
             info!(path = %path, "search_replace applied successfully");
-            format!("Successfully applied search_replace to '{path}'")
+            let offset = content.find(old_string).unwrap_or(0);
+            let diff = replacement_diff(&content, offset, old_string, new_string);
+            format!("Successfully applied search_replace to '{path}'\n{diff}")
         }
         Err(e) => {
             warn!(path = %path, error = %e, "Failed to write file after search_replace");
@@ -173,11 +468,207 @@ fn execute_search_replace(arguments: &str) -> String {
     result
 }
 
+/// get_project_tree ツールのローカル実行
+fn execute_get_project_tree(arguments: &str) -> String {
+    let parsed: serde_json::Value = match serde_json::from_str(arguments) {
+        Ok(v) => v,
+        Err(e) => return format!("Error parsing arguments: {e}"),
+    };
+
+    let path = parsed.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+    let depth = parsed
+        .get("depth")
+        .and_then(|v| v.as_u64())
+        .map(|n| (n as usize).clamp(1, TREE_MAX_DEPTH))
+        .unwrap_or(TREE_DEFAULT_DEPTH);
+
+    let spinner = jarvis_project_tree(path);
+    let result = build_project_tree(std::path::Path::new(path), path, depth);
+    spinner.finish_and_clear();
+
+    if !result.starts_with("Error") {
+        println!("  🌳 Tree: {path} (depth {depth})");
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::io::Write;
 
+    // ── read_file ──
+
+    fn numbered_file(dir: &tempfile::TempDir, lines: usize) -> String {
+        let file_path = dir.path().join("lines.txt");
+        let content: String = (1..=lines).map(|i| format!("line {i}\n")).collect();
+        std::fs::write(&file_path, content).unwrap();
+        file_path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn read_file_whole_with_line_numbers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = numbered_file(&dir, 3);
+
+        let result = read_file_inner(&path, None, None);
+        assert_eq!(result, "1\tline 1\n2\tline 2\n3\tline 3\n");
+    }
+
+    #[test]
+    fn read_file_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = numbered_file(&dir, 10);
+
+        let result = read_file_inner(&path, Some(4), Some(6));
+        assert_eq!(result, "4\tline 4\n5\tline 5\n6\tline 6\n");
+    }
+
+    #[test]
+    fn read_file_end_line_clamped_to_eof() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = numbered_file(&dir, 5);
+
+        let result = read_file_inner(&path, Some(4), Some(100));
+        assert_eq!(result, "4\tline 4\n5\tline 5\n");
+    }
+
+    #[test]
+    fn read_file_start_beyond_eof() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = numbered_file(&dir, 5);
+
+        let result = read_file_inner(&path, Some(10), None);
+        assert!(result.starts_with("Error"));
+        assert!(result.contains("5 lines"));
+    }
+
+    #[test]
+    fn read_file_start_after_end() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = numbered_file(&dir, 5);
+
+        let result = read_file_inner(&path, Some(4), Some(2));
+        assert!(result.starts_with("Error"));
+    }
+
+    #[test]
+    fn read_file_truncates_large_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = numbered_file(&dir, READ_FILE_MAX_LINES + 50);
+
+        let result = read_file_inner(&path, None, None);
+        assert!(result.contains(&format!(
+            "{READ_FILE_MAX_LINES}\tline {READ_FILE_MAX_LINES}\n"
+        )));
+        assert!(!result.contains(&format!("{}\t", READ_FILE_MAX_LINES + 1)));
+        assert!(result.contains("file truncated, 50 more lines"));
+        assert!(result.contains(&format!("start_line={}", READ_FILE_MAX_LINES + 1)));
+    }
+
+    #[test]
+    fn read_file_truncates_by_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("long.txt");
+        let long_line = "x".repeat(READ_FILE_MAX_BYTES / 2);
+        let content = format!("{long_line}\n{long_line}\n{long_line}\n");
+        std::fs::write(&file_path, content).unwrap();
+
+        let result = read_file_inner(file_path.to_str().unwrap(), None, None);
+        assert!(result.starts_with("1\t"));
+        assert!(result.contains("file truncated, 2 more lines"));
+    }
+
+    #[test]
+    fn read_file_truncates_single_long_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("minified.js");
+        // 上限の位置にマルチバイト文字を置き、char 境界で切られることも確かめる
+        let content = format!(
+            "{}あ{}",
+            "x".repeat(READ_FILE_MAX_BYTES - 1),
+            "x".repeat(READ_FILE_MAX_BYTES - 2)
+        );
+        assert_eq!(content.len(), 2 * READ_FILE_MAX_BYTES);
+        std::fs::write(&file_path, &content).unwrap();
+
+        let result = read_file_inner(file_path.to_str().unwrap(), None, None);
+        let (first, note) = result.split_once('\n').unwrap();
+        assert_eq!(first, format!("1\t{}", "x".repeat(READ_FILE_MAX_BYTES - 1)));
+        assert_eq!(
+            note,
+            format!(
+                "... (line 1 truncated, {} more bytes)\n",
+                READ_FILE_MAX_BYTES + 1
+            )
+        );
+    }
+
+    #[test]
+    fn read_file_not_found() {
+        let result = read_file_inner("/tmp/nonexistent_file_12345.txt", None, None);
+        assert!(result.contains("Error reading file"));
+    }
+
+    // ── read_files ──
+
+    #[test]
+    fn read_files_concatenates_with_headers_and_reports_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        std::fs::write(&a, "alpha\n").unwrap();
+        std::fs::write(&b, "beta\ngamma\n").unwrap();
+        let missing = dir.path().join("missing.txt");
+        let (a, b, missing) = (
+            a.to_str().unwrap(),
+            b.to_str().unwrap(),
+            missing.to_str().unwrap(),
+        );
+
+        let result = read_files_inner(&[a, missing, b]);
+        let expected_prefix =
+            format!("=== {a} ===\n1\talpha\n=== {missing} ===\nError reading file");
+        assert!(result.starts_with(&expected_prefix), "{result}");
+        assert!(result.ends_with(&format!("=== {b} ===\n1\tbeta\n2\tgamma\n")));
+    }
+
+    #[test]
+    fn read_files_applies_per_file_and_total_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let long = numbered_file(&dir, READ_FILES_MAX_LINES_PER_FILE + 10);
+        let result = read_files_inner(&[&long]);
+        assert!(result.contains("file truncated, 10 more lines"));
+
+        let big = dir.path().join("big.txt");
+        let line = "x".repeat(1000);
+        let content: String = (0..150).map(|_| format!("{line}\n")).collect();
+        std::fs::write(&big, content).unwrap();
+        let big = big.to_str().unwrap();
+        // 1 ファイルあたり約 100KB（READ_FILE_MAX_BYTES）読むため、2 つ目でほぼ合計上限に達する。
+        // 以降は残りの上限分（最低 1 行）だけ読み、使い切った後のファイルはスキップする
+        let result = read_files_inner(&[big, big, big, big, big]);
+        assert!(result.len() < READ_FILES_MAX_TOTAL_BYTES + 10_000);
+        assert!(
+            result.ends_with("... (skipped: total size limit reached; use read_file to read it)\n")
+        );
+    }
+
+    #[test]
+    fn execute_tool_read_files_validates_paths() {
+        let result = execute_tool("read_files", r#"{"paths": []}"#);
+        assert!(result.starts_with("Error"), "{result}");
+        let result = execute_tool("read_files", r#"{"path": "a.txt"}"#);
+        assert!(result.contains("'paths' parameter is required"));
+        let paths: Vec<String> = (0..=READ_FILES_MAX_PATHS)
+            .map(|i| format!("{i}.txt"))
+            .collect();
+        let args = serde_json::json!({ "paths": paths }).to_string();
+        assert!(execute_tool("read_files", &args).contains("too many paths"));
+    }
+
+    // ── search_replace ──
+
     #[test]
     fn search_replace_success() {
         let dir = tempfile::tempdir().unwrap();
@@ -220,6 +711,19 @@ mod tests {
         assert_eq!(content, "aaa\naaa\naaa\n", "file should be unchanged");
     }
 
+    #[test]
+    fn search_replace_returns_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        std::fs::write(&file_path, "a\nlet x = 1;\nb\n").unwrap();
+        let path_str = file_path.to_str().unwrap();
+
+        let result = search_replace_inner(path_str, "x = 1", "x = 2;\nlet y = 3");
+        assert!(result.contains("@@ -2,1 +2,2 @@"));
+        assert!(result.contains("-let x = 1;\n"));
+        assert!(result.contains("+let x = 2;\n+let y = 3;\n"));
+    }
+
     #[test]
     fn search_replace_file_not_found() {
         let result = search_replace_inner("/tmp/nonexistent_file_12345.txt", "a", "b");
@@ -246,4 +750,113 @@ mod tests {
         let content = std::fs::read_to_string(&file_path).unwrap();
         assert_eq!(content, "new content here\n");
     }
+
+    // ── write_file ──
+
+    #[test]
+    fn write_file_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("out.txt");
+        std::fs::write(&file_path, "old\n").unwrap();
+
+        let result = write_file_inner(file_path.to_str().unwrap(), "new\n", WriteMode::Overwrite);
+        assert!(result.contains("Successfully wrote"));
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "new\n");
+    }
+
+    #[test]
+    fn write_file_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("out.txt");
+        std::fs::write(&file_path, "first\n").unwrap();
+
+        let result = write_file_inner(file_path.to_str().unwrap(), "second\n", WriteMode::Append);
+        assert!(result.contains("Successfully appended"));
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "first\nsecond\n"
+        );
+    }
+
+    #[test]
+    fn write_file_append_creates_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("nested/new.txt");
+
+        let result = write_file_inner(file_path.to_str().unwrap(), "hello\n", WriteMode::Append);
+        assert!(result.contains("Successfully"));
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn execute_tool_write_file_invalid_mode() {
+        let args = serde_json::json!({
+            "path": "/tmp/unused.txt",
+            "content": "x",
+            "mode": "prepend"
+        })
+        .to_string();
+
+        let result = execute_tool("write_file", &args);
+        assert!(result.contains("invalid mode 'prepend'"));
+    }
+
+    #[test]
+    fn execute_tool_write_file_replace() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("replace.txt");
+        std::fs::write(&file_path, "alpha\nbeta\n").unwrap();
+
+        let args = serde_json::json!({
+            "path": file_path.to_str().unwrap(),
+            "old_string": "beta",
+            "new_string": "gamma"
+        })
+        .to_string();
+
+        let result = execute_tool("write_file", &args);
+        assert!(result.contains("Successfully"));
+        assert!(result.contains("-beta\n+gamma\n"));
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "alpha\ngamma\n"
+        );
+    }
+
+    #[test]
+    fn execute_tool_write_file_replace_requires_unique_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("replace.txt");
+        std::fs::write(&file_path, "dup\ndup\n").unwrap();
+
+        let args = serde_json::json!({
+            "path": file_path.to_str().unwrap(),
+            "old_string": "dup",
+            "new_string": "x"
+        })
+        .to_string();
+
+        let result = execute_tool("write_file", &args);
+        assert!(result.contains("matches 2 locations"));
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "dup\ndup\n");
+    }
+
+    // ── get_project_tree ──
+
+    #[test]
+    fn execute_tool_get_project_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+
+        let args = serde_json::json!({
+            "path": dir.path().to_str().unwrap(),
+            "depth": 100
+        })
+        .to_string();
+
+        let result = execute_tool("get_project_tree", &args);
+        assert!(result.contains("main.rs"));
+        assert!(result.ends_with(&format!("(depth {TREE_MAX_DEPTH})")));
+    }
 }
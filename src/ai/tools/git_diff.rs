@@ -0,0 +1,150 @@
+//! AI によるファイル変更の git diff 表示
+//!
+//! `[ai] show_git_diff_after_write = true` のとき、エージェントループ中に `write_file` /
+//! `search_replace` で変更したファイルを記録し（[`modified_path`]）、応答の完了後に
+//! git 管理下のファイルの差分をまとめて表示する（[`show_git_diff`]）。
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use tracing::debug;
+
+use crate::cli::jarvis::jarvis_talk;
+
+/// ファイルを変更するツールが成功した場合、変更したファイルのパスを返す。
+pub fn modified_path(function_name: &str, arguments: &str, result: &str) -> Option<PathBuf> {
+    if !matches!(function_name, "write_file" | "search_replace")
+        || !result.starts_with("Successfully")
+    {
+        return None;
+    }
+    let parsed: serde_json::Value = serde_json::from_str(arguments).ok()?;
+    parsed.get("path")?.as_str().map(PathBuf::from)
+}
+
+/// 変更したファイルの `git diff` を「以下の変更を加えました」と添えてまとめて表示する。
+///
+/// git 管理外のファイルや、未追跡（新規作成）で差分の出ないファイルはスキップし、
+/// 表示する差分がなければ何も表示しない。
+pub fn show_git_diff(paths: &[PathBuf]) {
+    let diff = collect_git_diff(paths, true);
+    if diff.is_empty() {
+        return;
+    }
+    jarvis_talk("I've made the following changes, sir:");
+    print!("{diff}");
+    if !diff.ends_with('\n') {
+        println!();
+    }
+}
+
+/// 各ファイルの `git diff` を記録順に連結して返す（同じファイルは 1 回だけ）。
+fn collect_git_diff(paths: &[PathBuf], color: bool) -> String {
+    let mut seen: Vec<&PathBuf> = Vec::new();
+    let mut diff = String::new();
+    for path in paths {
+        if seen.contains(&path) {
+            continue;
+        }
+        seen.push(path);
+        match git_diff_file(path, color) {
+            Some(file_diff) => diff.push_str(&file_diff),
+            None => debug!(path = %path.display(), "Not in a git repository, skipping diff"),
+        }
+    }
+    diff
+}
+
+/// ファイルのあるディレクトリで `git diff -- <file>` を実行し、その出力を返す。
+///
+/// git 管理外（`git diff` が失敗する）なら `None`。
+fn git_diff_file(path: &Path, color: bool) -> Option<String> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .arg("diff")
+        .arg(if color {
+            "--color=always"
+        } else {
+            "--color=never"
+        })
+        .arg("--")
+        .arg(path.file_name()?)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .stdout(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    }
+
+    #[test]
+    fn modified_path_only_for_successful_file_writes() {
+        let args = r#"{"path": "src/main.rs", "content": "x"}"#;
+        assert_eq!(
+            modified_path(
+                "write_file",
+                args,
+                "Successfully wrote 1 bytes to 'src/main.rs'"
+            ),
+            Some(PathBuf::from("src/main.rs"))
+        );
+        assert_eq!(
+            modified_path(
+                "search_replace",
+                args,
+                "Successfully applied search_replace"
+            ),
+            Some(PathBuf::from("src/main.rs"))
+        );
+        assert_eq!(
+            modified_path("write_file", args, "Error writing file 'src/main.rs'"),
+            None
+        );
+        assert_eq!(modified_path("read_file", args, "Successfully"), None);
+    }
+
+    #[test]
+    fn collects_diff_of_tracked_files_once_and_skips_untracked() {
+        let repo = tempfile::tempdir().unwrap();
+        git(repo.path(), &["init", "-q"]);
+        git(repo.path(), &["config", "user.email", "test@example.com"]);
+        git(repo.path(), &["config", "user.name", "test"]);
+        let tracked = repo.path().join("tracked.txt");
+        std::fs::write(&tracked, "old\n").unwrap();
+        git(repo.path(), &["add", "tracked.txt"]);
+        git(repo.path(), &["commit", "-q", "-m", "init"]);
+
+        std::fs::write(&tracked, "new\n").unwrap();
+        let untracked = repo.path().join("untracked.txt");
+        std::fs::write(&untracked, "new file\n").unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let outside_file = outside.path().join("outside.txt");
+        std::fs::write(&outside_file, "x\n").unwrap();
+
+        let diff = collect_git_diff(&[tracked.clone(), untracked, outside_file, tracked], false);
+        assert_eq!(diff.matches("diff --git").count(), 1);
+        assert!(diff.contains("-old\n+new\n"));
+    }
+}
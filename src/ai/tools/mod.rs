@@ -5,15 +5,149 @@
 pub mod call;
 pub mod definitions;
 pub mod executor;
+pub mod git_diff;
+mod tree;
 
 use async_openai::types::ChatCompletionTool;
+use tracing::warn;
 
-/// すべてのツール定義を構築する
-pub fn build_tools() -> Vec<ChatCompletionTool> {
+use crate::config::DEFAULT_ENABLED_TOOLS;
+
+/// 有効なツールの定義を構築する（`[ai] enabled_tools` に含まれないツールは AI に渡さない）
+pub fn build_tools(enabled_tools: &[String]) -> Vec<ChatCompletionTool> {
     vec![
         definitions::shell_command_tool(),
         definitions::read_file_tool(),
+        definitions::read_files_tool(),
         definitions::write_file_tool(),
         definitions::search_replace_tool(),
+        definitions::project_tree_tool(),
     ]
+    .into_iter()
+    .filter(|tool| is_enabled(&tool.function.name, enabled_tools))
+    .collect()
+}
+
+/// `[ai] enabled_tools` に存在しないツール名があれば警告する（設定の読み込み時に呼ぶ）。
+pub fn warn_unknown_tools(enabled_tools: &[String]) {
+    for name in unknown_tools(enabled_tools) {
+        warn!(tool = %name, "Unknown tool in [ai] enabled_tools");
+        eprintln!("jarvish: warning: [ai] enabled_tools: unknown tool '{name}'");
+    }
+}
+
+/// `[ai] enabled_tools` のうち、存在しないツール名を返す。
+fn unknown_tools(enabled_tools: &[String]) -> Vec<&str> {
+    enabled_tools
+        .iter()
+        .map(String::as_str)
+        .filter(|name| !DEFAULT_ENABLED_TOOLS.contains(name))
+        .collect()
+}
+
+/// システムプロンプトに追記する、無効なツールの注記を返す（すべて有効なら `None`）。
+///
+/// 固定のシステムプロンプトはすべてのツールがある前提で書かれているため、
+/// 無効なツールを呼ぼうとしないよう、使えるツールの一覧を AI に伝える。
+pub fn disabled_tools_note(enabled_tools: &[String]) -> Option<String> {
+    let (enabled, disabled): (Vec<&str>, Vec<&str>) = DEFAULT_ENABLED_TOOLS
+        .iter()
+        .partition(|name| is_enabled(name, enabled_tools));
+    if disabled.is_empty() {
+        return None;
+    }
+    let quote = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| format!("`{name}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let available = if enabled.is_empty() {
+        "none".to_string()
+    } else {
+        quote(&enabled)
+    };
+    Some(format!(
+        "### Tool availability\n\
+         The user has disabled these tools in this shell: {}. Never call them, and ignore any \
+         instructions above that refer to them. Available tools: {available}.",
+        quote(&disabled)
+    ))
+}
+
+/// ツールが `[ai] enabled_tools` で有効になっているかを返す。
+pub fn is_enabled(function_name: &str, enabled_tools: &[String]) -> bool {
+    enabled_tools.iter().any(|name| name == function_name)
+}
+
+/// 無効なツールが呼ばれた場合はエラーを返し、それ以外はローカルで実行する。
+///
+/// 定義を渡していないツールでも、AI が会話履歴から推測して呼ぶことがあるため実行前に弾く。
+pub fn execute_enabled_tool(
+    function_name: &str,
+    arguments: &str,
+    enabled_tools: &[String],
+) -> String {
+    if !is_enabled(function_name, enabled_tools) {
+        warn!(tool = %function_name, "Disabled tool called");
+        return format!("Error: tool disabled: '{function_name}' is not enabled in this shell");
+    }
+    executor::execute_tool(function_name, arguments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(tools: &[ChatCompletionTool]) -> Vec<&str> {
+        tools.iter().map(|t| t.function.name.as_str()).collect()
+    }
+
+    #[test]
+    fn build_tools_keeps_only_enabled_tools() {
+        let enabled = vec!["execute_shell_command".to_string(), "read_file".to_string()];
+        assert_eq!(
+            names(&build_tools(&enabled)),
+            vec!["execute_shell_command", "read_file"]
+        );
+        assert!(build_tools(&[]).is_empty());
+    }
+
+    #[test]
+    fn disabled_tools_note_lists_only_available_tools() {
+        let all: Vec<String> = DEFAULT_ENABLED_TOOLS
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(disabled_tools_note(&all).is_none());
+
+        let note = disabled_tools_note(&["read_file".to_string()]).unwrap();
+        assert!(note.contains("disabled these tools in this shell: `execute_shell_command`, `read_files`, `write_file`, `search_replace`, `get_project_tree`"), "{note}");
+        assert!(note.ends_with("Available tools: `read_file`."), "{note}");
+
+        let note = disabled_tools_note(&[]).unwrap();
+        assert!(note.ends_with("Available tools: none."), "{note}");
+    }
+
+    #[test]
+    fn unknown_tools_are_reported() {
+        let enabled = vec!["read_file".to_string(), "delete_file".to_string()];
+        assert_eq!(unknown_tools(&enabled), vec!["delete_file"]);
+    }
+
+    #[test]
+    fn disabled_tool_is_not_executed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let args = serde_json::json!({
+            "path": path.to_string_lossy(),
+            "content": "hello",
+        })
+        .to_string();
+
+        let result = execute_enabled_tool("write_file", &args, &["read_file".to_string()]);
+        assert!(result.contains("tool disabled"), "{result}");
+        assert!(!path.exists());
+    }
 }
@@ -0,0 +1,242 @@
+//! get_project_tree ツールのディレクトリツリー整形
+//!
+//! CWD（または指定サブディレクトリ）以下を深さ制限付きで走査し、`tree` コマンド風の
+//! テキストに整形する。Git リポジトリ内では `.gitignore` で無視されるパスを除外し、
+//! `.git` / `node_modules` / `target` 等の巨大になりがちなディレクトリは常に除外する。
+
+use std::path::{Path, PathBuf};
+
+/// depth 未指定時の走査深さ
+pub(super) const TREE_DEFAULT_DEPTH: usize = 3;
+
+/// 指定可能な最大の走査深さ
+pub(super) const TREE_MAX_DEPTH: usize = 8;
+
+/// 出力に含める最大エントリ数。超過分は件数のみ要約する
+const TREE_MAX_ENTRIES: usize = 300;
+
+/// `.gitignore` に関わらず常に除外するディレクトリ名
+const TREE_EXCLUDED_DIRS: &[&str] = &[
+    ".git",
+    "node_modules",
+    "target",
+    "__pycache__",
+    ".venv",
+    ".tox",
+    ".mypy_cache",
+    ".pytest_cache",
+    ".next",
+];
+
+/// ツリー走査の状態
+struct TreeWalker {
+    /// `.gitignore` 判定に使うリポジトリ（Git 管理外なら `None`）
+    repo: Option<git2::Repository>,
+    max_depth: usize,
+    lines: Vec<String>,
+    dirs: usize,
+    files: usize,
+}
+
+impl TreeWalker {
+    /// 除外対象のパスかどうか
+    fn is_excluded(&self, path: &Path, name: &str, is_dir: bool) -> bool {
+        if is_dir && TREE_EXCLUDED_DIRS.contains(&name) {
+            return true;
+        }
+        let Some(repo) = &self.repo else {
+            return false;
+        };
+        let Some(workdir) = repo.workdir() else {
+            return false;
+        };
+        let Ok(rel) = path.strip_prefix(workdir) else {
+            return false;
+        };
+        // ディレクトリは末尾に `/` を付けて `dist/` のようなパターンにも一致させる
+        if is_dir {
+            repo.is_path_ignored(rel.join("")).unwrap_or(false)
+        } else {
+            repo.is_path_ignored(rel).unwrap_or(false)
+        }
+    }
+
+    /// `dir` 直下のエントリを（ディレクトリ優先・名前順で）返す。除外対象は含めない。
+    fn children(&self, dir: &Path) -> Vec<(String, PathBuf, bool)> {
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut entries: Vec<(String, PathBuf, bool)> = read_dir
+            .flatten()
+            .map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                // シンボリックリンクは辿らない（循環防止）
+                let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                (name, entry.path(), is_dir)
+            })
+            .filter(|(name, path, is_dir)| !self.is_excluded(path, name, *is_dir))
+            .collect();
+        entries.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        entries
+    }
+
+    /// `dir` 以下を再帰的に走査する。出力行は上限まで、件数は全て数える。
+    fn walk(&mut self, dir: &Path, prefix: &str, depth: usize) {
+        let children = self.children(dir);
+        let count = children.len();
+        for (i, (name, path, is_dir)) in children.into_iter().enumerate() {
+            let last = i + 1 == count;
+            if is_dir {
+                self.dirs += 1;
+            } else {
+                self.files += 1;
+            }
+
+            if self.lines.len() < TREE_MAX_ENTRIES {
+                let branch = if last { "└── " } else { "├── " };
+                let suffix = if is_dir { "/" } else { "" };
+                self.lines.push(format!("{prefix}{branch}{name}{suffix}"));
+            }
+
+            if is_dir && depth < self.max_depth {
+                let child_prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+                self.walk(&path, &child_prefix, depth + 1);
+            }
+        }
+    }
+}
+
+/// `root` 以下のディレクトリツリーを整形して返す。
+///
+/// `depth` は 1 で直下のみ、2 でその 1 階層下まで…を表す（`1..=TREE_MAX_DEPTH` に丸める）。
+/// `label` は先頭行に表示するルート名。エントリが [`TREE_MAX_ENTRIES`] を超えた場合は
+/// 先頭部分のみ表示し、末尾に省略件数を付与する。
+pub(super) fn build_project_tree(root: &Path, label: &str, depth: usize) -> String {
+    let root = match root.canonicalize() {
+        Ok(p) => p,
+        Err(e) => return format!("Error: cannot read directory '{label}': {e}"),
+    };
+    if !root.is_dir() {
+        return format!("Error: '{label}' is not a directory");
+    }
+
+    let mut walker = TreeWalker {
+        repo: git2::Repository::discover(&root).ok(),
+        max_depth: depth.clamp(1, TREE_MAX_DEPTH),
+        lines: Vec::new(),
+        dirs: 0,
+        files: 0,
+    };
+    walker.walk(&root, "", 1);
+
+    let total = walker.dirs + walker.files;
+    let mut out = format!("{label}\n");
+    for line in &walker.lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    if total > walker.lines.len() {
+        out.push_str(&format!(
+            "... (truncated: showing {} of {total} entries; request a subdirectory path or a smaller depth for details)\n",
+            walker.lines.len()
+        ));
+    }
+    out.push_str(&format!(
+        "\n{} directories, {} files (depth {})",
+        walker.dirs, walker.files, walker.max_depth
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(path: &Path) {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn renders_tree_with_dirs_first() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(&dir.path().join("Cargo.toml"));
+        touch(&dir.path().join("src/main.rs"));
+        touch(&dir.path().join("src/lib.rs"));
+
+        let tree = build_project_tree(dir.path(), ".", 3);
+        assert_eq!(
+            tree,
+            ".\n\
+             ├── src/\n\
+             │   ├── lib.rs\n\
+             │   └── main.rs\n\
+             └── Cargo.toml\n\
+             \n1 directories, 3 files (depth 3)"
+        );
+    }
+
+    #[test]
+    fn excludes_default_heavy_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(&dir.path().join("node_modules/pkg/index.js"));
+        touch(&dir.path().join("target/debug/app"));
+        touch(&dir.path().join("README.md"));
+
+        let tree = build_project_tree(dir.path(), ".", 3);
+        assert!(!tree.contains("node_modules"));
+        assert!(!tree.contains("target"));
+        assert!(tree.contains("README.md"));
+    }
+
+    #[test]
+    fn respects_gitignore() {
+        let dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "dist/\n*.log\n").unwrap();
+        touch(&dir.path().join("dist/bundle.js"));
+        touch(&dir.path().join("debug.log"));
+        touch(&dir.path().join("src/app.ts"));
+
+        let tree = build_project_tree(dir.path(), ".", 3);
+        assert!(!tree.contains("dist"));
+        assert!(!tree.contains("debug.log"));
+        assert!(!tree.contains(".git/"));
+        assert!(tree.contains("app.ts"));
+        assert!(tree.contains(".gitignore"));
+    }
+
+    #[test]
+    fn depth_limits_recursion() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(&dir.path().join("a/b/c/deep.txt"));
+
+        let tree = build_project_tree(dir.path(), ".", 2);
+        assert!(tree.contains("b/"));
+        assert!(!tree.contains("c/"));
+        assert!(!tree.contains("deep.txt"));
+    }
+
+    #[test]
+    fn truncates_after_max_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..(TREE_MAX_ENTRIES + 5) {
+            touch(&dir.path().join(format!("file{i:04}.txt")));
+        }
+
+        let tree = build_project_tree(dir.path(), ".", 1);
+        assert!(tree.contains(&format!(
+            "truncated: showing {TREE_MAX_ENTRIES} of {} entries",
+            TREE_MAX_ENTRIES + 5
+        )));
+        assert!(!tree.contains(&format!("file{:04}.txt", TREE_MAX_ENTRIES + 4)));
+    }
+
+    #[test]
+    fn missing_directory_is_an_error() {
+        let tree = build_project_tree(Path::new("/nonexistent_jarvish_tree"), "missing", 2);
+        assert!(tree.starts_with("Error"));
+    }
+}
@@ -0,0 +1,175 @@
+//! AI 応答中の先行入力（type-ahead）の退避
+//!
+//! ストリーミング応答の表示中は端末を非カノニカル・エコー無効にし、打鍵が応答の表示に
+//! 混ざったり、Enter で確定した行が次のプロンプトで誤って実行されたりしないようにする。
+//! 応答中に打鍵された入力は [`TypeAheadGuard`] の drop 時に stdin から読み出して退避し、
+//! 次のプロンプトで [`take`] により取り出す（`[ai] type_ahead` に応じて入力行に反映するか破棄する）。
+//!
+//! `ISIG` は維持するため、応答中の Ctrl-C による中断はそのまま機能する。
+
+use std::io;
+use std::os::fd::AsFd;
+use std::sync::Mutex;
+
+use nix::sys::termios::{self, LocalFlags, SetArg, SpecialCharacterIndices, Termios};
+use tracing::debug;
+
+/// 応答中に打鍵され、まだプロンプトに引き継いでいない入力
+static PENDING: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+/// AI 応答の表示中、端末のエコーと行編集を止めて打鍵を退避するガード。
+///
+/// stdin が端末でない場合は何もしない。
+pub(super) struct TypeAheadGuard {
+    saved: Option<Termios>,
+}
+
+impl TypeAheadGuard {
+    /// エコー・カノニカルモードを無効にしてガードを作成する。
+    pub(super) fn new() -> Self {
+        let stdin = io::stdin();
+        let Ok(saved) = termios::tcgetattr(stdin.as_fd()) else {
+            return Self { saved: None };
+        };
+        let mut quiet = saved.clone();
+        quiet
+            .local_flags
+            .remove(LocalFlags::ECHO | LocalFlags::ICANON);
+        // drop 時に溜まった入力をブロックせずに読み出せるようにする
+        quiet.control_chars[SpecialCharacterIndices::VMIN as usize] = 0;
+        quiet.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+        if termios::tcsetattr(stdin.as_fd(), SetArg::TCSANOW, &quiet).is_err() {
+            return Self { saved: None };
+        }
+        Self { saved: Some(saved) }
+    }
+}
+
+impl Drop for TypeAheadGuard {
+    fn drop(&mut self) {
+        let Some(saved) = self.saved.take() else {
+            return;
+        };
+        let typed = drain_stdin();
+        if !typed.is_empty() {
+            debug!(
+                bytes = typed.len(),
+                "Type-ahead input buffered during AI response"
+            );
+            PENDING
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .extend_from_slice(&typed);
+        }
+        let _ = termios::tcsetattr(io::stdin().as_fd(), SetArg::TCSANOW, &saved);
+    }
+}
+
+/// 非カノニカル・VMIN=0 の stdin から、既に届いている入力をすべて読み出す。
+fn drain_stdin() -> Vec<u8> {
+    let mut typed = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        // SAFETY: 有効なバッファとその長さを渡して stdin から読むだけで、メモリ安全性に影響しない。
+        let n = unsafe {
+            libc::read(
+                libc::STDIN_FILENO,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+            )
+        };
+        if n <= 0 {
+            break;
+        }
+        typed.extend_from_slice(&buf[..n as usize]);
+    }
+    typed
+}
+
+/// 退避した先行入力を取り出す（取り出した入力は消える）。
+///
+/// 改行はスペースに置き換えるため、応答中に押した Enter で行が実行されることはない。
+/// 入力がない、または制御文字だけだった場合は `None`。
+pub fn take() -> Option<String> {
+    let typed = std::mem::take(
+        &mut *PENDING
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    );
+    sanitize(&typed)
+}
+
+/// 生の打鍵列を入力行に挿入できる文字列にする。
+///
+/// - Backspace（`DEL` / `BS`）は直前の 1 文字を消し、Ctrl-U は行全体を消す
+/// - 改行（`CR` / `LF`）はスペースにする
+/// - 矢印キー等のエスケープシーケンスとその他の制御文字は捨てる
+fn sanitize(typed: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(typed);
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x7f' | '\x08' => {
+                out.pop();
+            }
+            '\x15' => out.clear(),
+            '\r' | '\n' => out.push(' '),
+            '\x1b' => match chars.next() {
+                // CSI: `ESC [` から終端文字（0x40–0x7E）まで
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // SS3: `ESC O` + 1 文字
+                Some('O') => {
+                    chars.next();
+                }
+                _ => {}
+            },
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+
+    let trimmed = out.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_replaces_newlines_so_enter_never_executes() {
+        assert_eq!(sanitize(b"ls -la\r").as_deref(), Some("ls -la"));
+        assert_eq!(
+            sanitize(b"git status\ngit log\n").as_deref(),
+            Some("git status git log")
+        );
+    }
+
+    #[test]
+    fn sanitize_applies_backspace_and_kill_line() {
+        assert_eq!(sanitize(b"lss\x7f -l").as_deref(), Some("ls -l"));
+        assert_eq!(sanitize(b"rm -rf\x15echo hi").as_deref(), Some("echo hi"));
+    }
+
+    #[test]
+    fn sanitize_drops_escape_sequences_and_controls() {
+        assert_eq!(
+            sanitize(b"\x1b[A\x1bOBcd\x04 /tmp").as_deref(),
+            Some("cd /tmp")
+        );
+        assert_eq!(
+            sanitize("こんにちは\x1b[1;5C".as_bytes()).as_deref(),
+            Some("こんにちは")
+        );
+        assert_eq!(sanitize(b"\r\n\x1b[D"), None);
+        assert_eq!(sanitize(b""), None);
+    }
+}
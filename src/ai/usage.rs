@@ -0,0 +1,123 @@
+//! AI 応答のトークン使用量・所要時間の集計
+//!
+//! `[ai] show_usage = true` のとき、エージェントループ完了後に
+//! `[gpt-4o · 1.2s · 340 tokens]` 形式の一行を表示するための集計を行う。
+//! ストリームから usage が取得できないラウンドは文字数から概算する。
+
+use std::time::Duration;
+
+/// 概算時に 1 トークンあたりとみなす文字数（英文での OpenAI の目安）
+const CHARS_PER_TOKEN: usize = 4;
+
+/// エージェントループ全体（複数ラウンド）のトークン使用量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageStats {
+    /// 入力（プロンプト）トークン数
+    pub prompt_tokens: u32,
+    /// 出力（生成）トークン数
+    pub completion_tokens: u32,
+    /// いずれかのラウンドが文字数からの概算値を含むか
+    pub estimated: bool,
+}
+
+impl UsageStats {
+    /// API から取得した実測値で構築する。
+    pub fn measured(prompt_tokens: u32, completion_tokens: u32) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            estimated: false,
+        }
+    }
+
+    /// 送信テキストと受信テキストの文字数から概算値を構築する。
+    pub fn estimate(prompt_text: &str, completion_text: &str) -> Self {
+        Self {
+            prompt_tokens: estimate_tokens(prompt_text),
+            completion_tokens: estimate_tokens(completion_text),
+            estimated: true,
+        }
+    }
+
+    /// 1 ラウンド分の使用量を加算する。
+    pub fn add(&mut self, other: UsageStats) {
+        self.prompt_tokens = self.prompt_tokens.saturating_add(other.prompt_tokens);
+        self.completion_tokens = self
+            .completion_tokens
+            .saturating_add(other.completion_tokens);
+        self.estimated |= other.estimated;
+    }
+
+    /// 合計トークン数
+    pub fn total_tokens(&self) -> u32 {
+        self.prompt_tokens.saturating_add(self.completion_tokens)
+    }
+}
+
+/// 文字数からトークン数を概算する（端数は切り上げ）。
+fn estimate_tokens(text: &str) -> u32 {
+    let chars = text.chars().count();
+    u32::try_from(chars.div_ceil(CHARS_PER_TOKEN)).unwrap_or(u32::MAX)
+}
+
+/// 使用量の表示行を整形する。
+///
+/// 例: `[gpt-4o · 1.2s · 340 tokens (in 300 / out 40)]`。
+/// 概算値を含む場合はトークン数の前に `~` を付ける。
+pub fn format_usage_line(model: &str, elapsed: Duration, usage: &UsageStats) -> String {
+    let approx = if usage.estimated { "~" } else { "" };
+    format!(
+        "[{model} · {:.1}s · {approx}{} tokens (in {} / out {})]",
+        elapsed.as_secs_f64(),
+        usage.total_tokens(),
+        usage.prompt_tokens,
+        usage.completion_tokens,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_rounds_up_by_chars() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abc"), 1);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        // マルチバイト文字もバイト数ではなく文字数で数える
+        assert_eq!(estimate_tokens("こんにちは"), 2);
+    }
+
+    #[test]
+    fn add_accumulates_and_propagates_estimated_flag() {
+        let mut total = UsageStats::default();
+        total.add(UsageStats::measured(100, 20));
+        assert!(!total.estimated);
+        total.add(UsageStats::estimate("abcdefgh", "abcd"));
+        assert_eq!(total.prompt_tokens, 102);
+        assert_eq!(total.completion_tokens, 21);
+        assert_eq!(total.total_tokens(), 123);
+        assert!(total.estimated);
+    }
+
+    #[test]
+    fn format_measured_usage() {
+        let line = format_usage_line(
+            "gpt-4o",
+            Duration::from_millis(1234),
+            &UsageStats::measured(300, 40),
+        );
+        assert_eq!(line, "[gpt-4o · 1.2s · 340 tokens (in 300 / out 40)]");
+    }
+
+    #[test]
+    fn format_estimated_usage_has_tilde() {
+        let line = format_usage_line(
+            "gpt-4o-mini",
+            Duration::from_millis(500),
+            &UsageStats::estimate("abcd", "abcd"),
+        );
+        assert_eq!(line, "[gpt-4o-mini · 0.5s · ~2 tokens (in 1 / out 1)]");
+    }
+}
@@ -1,8 +1,11 @@
 use chrono::Local;
 use rand::Rng;
+use tracing::warn;
 
 use super::color::{red, yellow};
 use super::jarvis::jarvis_talk;
+use crate::config::BannerConfig;
+use crate::engine::expand::expand_token;
 
 /// 時間帯に応じた挨拶を返す。
 ///  - 5〜11時:  "Good morning"
@@ -17,14 +20,65 @@ fn time_greeting() -> &'static str {
     }
 }
 
+/// `[banner]` のファイル設定（`welcome_file` / `goodbye_file`）を読み込む。
+///
+/// `~` と環境変数を展開してから読む。未指定（空文字列）なら `None`。
+/// 読み込めない場合は警告を表示して `None` を返し、組み込みバナーにフォールバックする。
+fn read_banner_file(path: &str) -> Option<String> {
+    if path.is_empty() {
+        return None;
+    }
+    let expanded = expand_token(path);
+    match std::fs::read_to_string(&expanded) {
+        Ok(content) => Some(content),
+        Err(e) => {
+            warn!(path = %expanded, error = %e, "Failed to read banner file");
+            eprintln!("jarvish: warning: banner: {expanded}: {e}");
+            None
+        }
+    }
+}
+
 /// シェル起動時の Welcome バナーを表示する。
 ///
 /// `offline_systems` にオフラインのサブシステム名を渡すと、
 /// "All systems are operational." の代わりに状態を報告する。
-pub fn print_welcome(offline_systems: &[&str]) {
-    let version = env!("CARGO_PKG_VERSION");
+/// `[banner] enabled = false` なら何も表示しない。`welcome_file` が指定されていれば
+/// 組み込みのアスキーアートの代わりにそのファイルの内容をそのまま表示する。
+pub fn print_welcome(config: &BannerConfig, offline_systems: &[&str]) {
+    if !config.enabled {
+        return;
+    }
     let greeting = time_greeting();
 
+    match read_banner_file(&config.welcome_file) {
+        Some(content) => {
+            println!();
+            print!("{content}");
+            if !content.ends_with('\n') {
+                println!();
+            }
+            println!();
+        }
+        None => print_builtin_art(),
+    }
+
+    if offline_systems.is_empty() {
+        jarvis_talk(&format!("{greeting}, sir. All systems are operational."));
+    } else {
+        let detail = offline_systems.join(", ");
+        jarvis_talk(&format!(
+            "{greeting}, sir. Partially operational — {detail}."
+        ));
+    }
+
+    println!();
+}
+
+/// 組み込みのアスキーアートロゴとバージョン入りセパレータを表示する。
+fn print_builtin_art() {
+    let version = env!("CARGO_PKG_VERSION");
+
     let art_lines: &[&str] = &[
         r#"   _   _   ___ _   _ ___ ___ _  _ "#,
         r#"  | | /_\ | _ \ \ / /_ _/ __| || |"#,
@@ -44,21 +98,25 @@ pub fn print_welcome(offline_systems: &[&str]) {
     }
     println!("{separator}");
     println!();
-
-    if offline_systems.is_empty() {
-        jarvis_talk(&format!("{greeting}, sir. All systems are operational."));
-    } else {
-        let detail = offline_systems.join(", ");
-        jarvis_talk(&format!(
-            "{greeting}, sir. Partially operational — {detail}."
-        ));
-    }
-
-    println!();
 }
 
 /// シェル終了時の Farewell メッセージを表示する。
-pub fn print_goodbye() {
+///
+/// `[banner] enabled = false` なら何も表示しない。`goodbye_file` が指定されていれば
+/// ランダムな組み込みメッセージの代わりにそのファイルの内容を表示する。
+pub fn print_goodbye(config: &BannerConfig) {
+    if !config.enabled {
+        return;
+    }
+    if let Some(content) = read_banner_file(&config.goodbye_file) {
+        print!("{content}");
+        if !content.ends_with('\n') {
+            println!();
+        }
+        println!();
+        return;
+    }
+
     let greeting = time_greeting();
 
     let messages: &[&str] = &[
@@ -90,4 +148,24 @@ mod tests {
             "unexpected greeting: {g}"
         );
     }
+
+    #[test]
+    fn read_banner_file_returns_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("welcome.txt");
+        std::fs::write(&path, "  MY SHELL\n").unwrap();
+        assert_eq!(
+            read_banner_file(path.to_str().unwrap()).as_deref(),
+            Some("  MY SHELL\n")
+        );
+    }
+
+    #[test]
+    fn read_banner_file_unset_or_missing_falls_back() {
+        assert_eq!(read_banner_file(""), None);
+        assert_eq!(
+            read_banner_file("/tmp/__jarvish_nonexistent_banner__"),
+            None
+        );
+    }
 }
@@ -0,0 +1,464 @@
+//! AI 応答中のコードブロックのシンタックスハイライト（`[ai] code_highlight`）
+//!
+//! Markdown レンダリング時に fenced code block（```rust など）の言語タグを見て、
+//! キーワード・文字列・コメント・数値を色付けする簡易ハイライタ。
+//! 応答はストリーミング完了後にまとめてレンダリングされるため、閉じたブロック単位で処理する。
+//! 未知の言語・言語タグなしのブロックは従来どおり termimad に任せる。
+//!
+//! 有効/無効は AI クライアント（`JarvisAI`）が設定値を保持し、レンダリング時に引数で渡す。
+
+use nu_ansi_term::{Color, Style};
+
+/// 言語ごとの字句ルール
+struct Syntax {
+    keywords: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    quotes: &'static [char],
+}
+
+const RUST: Syntax = Syntax {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ],
+    line_comments: &["//"],
+    // `'` はライフタイム（`'a`）と区別できないため文字列として扱わない
+    quotes: &['"'],
+};
+
+const PYTHON: Syntax = Syntax {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "False", "finally", "for", "from", "global", "if", "import",
+        "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return", "True",
+        "try", "while", "with", "yield",
+    ],
+    line_comments: &["#"],
+    quotes: &['"', '\''],
+};
+
+const JAVASCRIPT: Syntax = Syntax {
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "of",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "type",
+        "typeof",
+        "undefined",
+        "var",
+        "void",
+        "while",
+        "yield",
+    ],
+    line_comments: &["//"],
+    quotes: &['"', '\'', '`'],
+};
+
+const GO: Syntax = Syntax {
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "false",
+        "fallthrough",
+        "for",
+        "func",
+        "go",
+        "goto",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "nil",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "true",
+        "type",
+        "var",
+    ],
+    line_comments: &["//"],
+    quotes: &['"', '\'', '`'],
+};
+
+const SHELL: Syntax = Syntax {
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "select", "then", "until", "while",
+    ],
+    line_comments: &["#"],
+    quotes: &['"', '\''],
+};
+
+const C_FAMILY: Syntax = Syntax {
+    keywords: &[
+        "auto",
+        "bool",
+        "break",
+        "case",
+        "char",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "extern",
+        "false",
+        "float",
+        "for",
+        "if",
+        "include",
+        "int",
+        "long",
+        "namespace",
+        "new",
+        "nullptr",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "short",
+        "signed",
+        "sizeof",
+        "static",
+        "struct",
+        "switch",
+        "template",
+        "this",
+        "true",
+        "typedef",
+        "union",
+        "unsigned",
+        "using",
+        "virtual",
+        "void",
+        "while",
+    ],
+    line_comments: &["//"],
+    quotes: &['"', '\''],
+};
+
+const JAVA: Syntax = Syntax {
+    keywords: &[
+        "abstract",
+        "boolean",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "continue",
+        "default",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "extends",
+        "false",
+        "final",
+        "finally",
+        "for",
+        "if",
+        "implements",
+        "import",
+        "instanceof",
+        "int",
+        "interface",
+        "long",
+        "new",
+        "null",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "throws",
+        "true",
+        "try",
+        "var",
+        "void",
+        "while",
+    ],
+    line_comments: &["//"],
+    quotes: &['"', '\''],
+};
+
+const RUBY: Syntax = Syntax {
+    keywords: &[
+        "begin", "class", "def", "do", "else", "elsif", "end", "ensure", "false", "for", "if",
+        "in", "module", "next", "nil", "require", "rescue", "return", "self", "then", "true",
+        "unless", "until", "when", "while", "yield",
+    ],
+    line_comments: &["#"],
+    quotes: &['"', '\''],
+};
+
+/// 言語タグに対応する字句ルールを返す。未知の言語は `None`。
+fn syntax_for(lang: &str) -> Option<&'static Syntax> {
+    match lang.to_ascii_lowercase().as_str() {
+        "rust" | "rs" => Some(&RUST),
+        "python" | "py" | "python3" => Some(&PYTHON),
+        "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" => Some(&JAVASCRIPT),
+        "go" | "golang" => Some(&GO),
+        "sh" | "bash" | "zsh" | "shell" | "console" => Some(&SHELL),
+        "c" | "h" | "cpp" | "c++" | "cc" | "hpp" => Some(&C_FAMILY),
+        "java" => Some(&JAVA),
+        "ruby" | "rb" => Some(&RUBY),
+        _ => None,
+    }
+}
+
+/// Markdown テキストをハイライト対象のコードブロックとそれ以外に分けた断片
+#[derive(Debug, PartialEq)]
+pub enum Segment {
+    /// termimad でレンダリングする Markdown
+    Markdown(String),
+    /// 既知の言語タグ付きで閉じている fenced code block（フェンス行は含まない）
+    Code { lang: String, code: String },
+}
+
+/// テキストを Markdown 部分とハイライト対象のコードブロックに分割する。
+///
+/// 未知の言語・言語タグなし・閉じていないブロックは Markdown 側に残す。
+pub fn split_code_blocks(text: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut markdown = String::new();
+    let mut lines = text.split_inclusive('\n');
+
+    while let Some(line) = lines.next() {
+        let lang = line
+            .trim()
+            .strip_prefix("```")
+            .and_then(|tag| tag.split_whitespace().next())
+            .filter(|tag| syntax_for(tag).is_some());
+        let Some(lang) = lang else {
+            markdown.push_str(line);
+            continue;
+        };
+
+        let mut body: Vec<&str> = Vec::new();
+        let mut closed = false;
+        for inner in lines.by_ref() {
+            if inner.trim() == "```" {
+                closed = true;
+                break;
+            }
+            body.push(inner);
+        }
+        if !closed {
+            markdown.push_str(line);
+            markdown.extend(body);
+            continue;
+        }
+
+        if !markdown.is_empty() {
+            segments.push(Segment::Markdown(std::mem::take(&mut markdown)));
+        }
+        segments.push(Segment::Code {
+            lang: lang.to_string(),
+            code: body.concat(),
+        });
+    }
+
+    if !markdown.is_empty() {
+        segments.push(Segment::Markdown(markdown));
+    }
+    segments
+}
+
+/// コードを言語タグに従って色付けする。未知の言語は `None`。
+pub fn highlight(lang: &str, code: &str) -> Option<String> {
+    let syntax = syntax_for(lang)?;
+    let mut output = String::with_capacity(code.len() * 2);
+    for line in code.lines() {
+        highlight_line(syntax, line, &mut output);
+        output.push('\n');
+    }
+    Some(output)
+}
+
+/// 1 行分を字句ごとに色付けして `output` に追記する。
+fn highlight_line(syntax: &Syntax, line: &str, output: &mut String) {
+    let keyword = Style::new().fg(Color::Rgb(255, 184, 0)).bold();
+    let string = Style::new().fg(Color::Rgb(170, 210, 130));
+    let comment = Style::new().fg(Color::DarkGray).italic();
+    let number = Style::new().fg(Color::Rgb(230, 130, 120));
+
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        let rest = &line[start..];
+
+        if syntax.line_comments.iter().any(|p| rest.starts_with(p)) {
+            output.push_str(&comment.paint(rest).to_string());
+            return;
+        }
+
+        if syntax.quotes.contains(&c) {
+            if let Some(end) = closing_quote(line, start, c) {
+                output.push_str(&string.paint(&line[start..end]).to_string());
+                i = chars.partition_point(|&(idx, _)| idx < end);
+                continue;
+            }
+        }
+
+        let prev_is_word = i > 0 && is_word_char(chars[i - 1].1);
+        if (c.is_alphabetic() || c == '_' || c.is_ascii_digit()) && !prev_is_word {
+            let end = chars[i..]
+                .iter()
+                .find(|&&(_, ch)| !is_word_char(ch))
+                .map_or(line.len(), |&(idx, _)| idx);
+            let word = &line[start..end];
+            if c.is_ascii_digit() {
+                output.push_str(&number.paint(word).to_string());
+            } else if syntax.keywords.contains(&word) {
+                output.push_str(&keyword.paint(word).to_string());
+            } else {
+                output.push_str(word);
+            }
+            i = chars.partition_point(|&(idx, _)| idx < end);
+            continue;
+        }
+
+        output.push(c);
+        i += 1;
+    }
+}
+
+/// `start` の引用符に対応する閉じ引用符の直後の位置を返す（`\` エスケープを考慮）。
+/// 同じ行で閉じていない場合（Rust のライフタイム `'a` 等）は `None`。
+fn closing_quote(line: &str, start: usize, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, c) in line[start + quote.len_utf8()..].char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return Some(start + quote.len_utf8() + idx + c.len_utf8());
+        }
+    }
+    None
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_known_language_blocks() {
+        let text = "Fix:\n\n```rust\nfn main() {}\n```\n\nDone.\n";
+        assert_eq!(
+            split_code_blocks(text),
+            vec![
+                Segment::Markdown("Fix:\n\n".to_string()),
+                Segment::Code {
+                    lang: "rust".to_string(),
+                    code: "fn main() {}\n".to_string(),
+                },
+                Segment::Markdown("\nDone.\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_untagged_and_unclosed_blocks_stay_markdown() {
+        for text in [
+            "```brainfuck\n+++.\n```\n",
+            "```\nplain\n```\n",
+            "```rust\nfn main() {}\n",
+        ] {
+            assert_eq!(
+                split_code_blocks(text),
+                vec![Segment::Markdown(text.to_string())]
+            );
+        }
+    }
+
+    #[test]
+    fn highlights_keywords_strings_comments_and_numbers() {
+        let out = highlight("rust", "let x = \"fn\"; // note 42").unwrap();
+        let keyword = Style::new().fg(Color::Rgb(255, 184, 0)).bold();
+        assert!(out.contains(&keyword.paint("let").to_string()));
+        // 文字列・コメント内のキーワードや数値は色付けしない
+        assert!(!out.contains(&keyword.paint("fn").to_string()));
+        assert!(out.contains(
+            &Style::new()
+                .fg(Color::DarkGray)
+                .italic()
+                .paint("// note 42")
+                .to_string()
+        ));
+        // 識別子の一部はキーワード扱いしない
+        let out = highlight("python", "define = 1").unwrap();
+        assert!(out.starts_with("define = "));
+    }
+
+    #[test]
+    fn unclosed_quote_is_left_plain() {
+        let out = highlight("rust", "fn f<'a>(x: &'a str) {}").unwrap();
+        assert!(out.contains("<'a>"));
+        assert!(out.ends_with("{}\n"));
+    }
+
+    #[test]
+    fn unknown_language_is_not_highlighted() {
+        assert!(highlight("brainfuck", "+++.").is_none());
+    }
+}
@@ -34,6 +34,10 @@ pub fn white(text: &str) -> String {
     styled(Color::LightGray, text, false)
 }
 
+pub fn gray(text: &str) -> String {
+    styled(Color::DarkGray, text, false)
+}
+
 pub fn bold_red(text: &str) -> String {
     styled(Color::LightRed, text, true)
 }
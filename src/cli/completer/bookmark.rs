@@ -0,0 +1,122 @@
+//! ブックマーク補完 — `cd @<Tab>` / `jump <Tab>` でブックマーク名を補完
+//!
+//! 候補は `~/.config/jarvish/bookmarks.toml`（`bookmark` ビルトインで管理）から Tab のたびに
+//! 読み込み、登録先ディレクトリを description として表示する。`cd @name/<Tab>` のように
+//! `/` 以降を入力中の場合は対象外とし、後続のパス補完に委ねる。
+
+use std::path::PathBuf;
+
+use crate::engine::bookmarks;
+
+use super::context::CompletionContext;
+use super::provider::{Candidate, CompletionProvider};
+
+/// ブックマーク名補完プロバイダ。
+///
+/// 先頭コマンドが `cd` で partial が `@` 始まり（`/` を含まない）の場合、または
+/// `jump` の第 1 引数の場合のみ `Some` を返す。それ以外は `None`（次のプロバイダへ）。
+pub(super) struct BookmarkProvider {
+    /// ブックマークの保存ファイル
+    path: PathBuf,
+}
+
+impl BookmarkProvider {
+    pub(super) fn new() -> Self {
+        Self {
+            path: bookmarks::default_path(),
+        }
+    }
+}
+
+impl CompletionProvider for BookmarkProvider {
+    fn provide(&self, ctx: &CompletionContext) -> Option<Vec<Candidate>> {
+        if ctx.is_first_token {
+            return None;
+        }
+        let prefix = match ctx.head_command()? {
+            "cd" if !ctx.partial.contains('/') => ctx.partial.strip_prefix('@')?,
+            "jump" => {
+                // partial を含めて「jump + 引数 1 個」のときのみ（第 2 引数以降は補完しない）
+                let args = ctx.command_words().len() - usize::from(!ctx.partial.is_empty());
+                if args != 1 {
+                    return None;
+                }
+                ctx.partial.as_str()
+            }
+            _ => return None,
+        };
+        let sigil = if ctx.partial.starts_with('@') {
+            "@"
+        } else {
+            ""
+        };
+
+        let entries = bookmarks::load_from(&self.path).ok()?;
+        Some(
+            entries
+                .into_iter()
+                .filter(|(name, _)| name.starts_with(prefix))
+                .map(|(name, dir)| Candidate {
+                    value: format!("{sigil}{name}"),
+                    description: Some(dir.display().to_string()),
+                    append_whitespace: true,
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::super::context::extract_context;
+    use super::*;
+
+    fn provider_with(names: &[(&str, &str)]) -> (tempfile::TempDir, BookmarkProvider) {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("bookmarks.toml");
+        for (name, dir) in names {
+            bookmarks::add(&path, name, Path::new(dir)).unwrap();
+        }
+        (tmp, BookmarkProvider { path })
+    }
+
+    fn values(provider: &BookmarkProvider, line: &str) -> Option<Vec<String>> {
+        let ctx = extract_context(line, line.len());
+        provider
+            .provide(&ctx)
+            .map(|c| c.into_iter().map(|c| c.value).collect())
+    }
+
+    #[test]
+    fn completes_bookmark_names_for_cd_at_and_jump() {
+        let (_tmp, provider) = provider_with(&[("work", "/srv/work"), ("web", "/srv/web")]);
+
+        assert_eq!(
+            values(&provider, "cd @"),
+            Some(vec!["@web".to_string(), "@work".to_string()])
+        );
+        assert_eq!(values(&provider, "cd @wo"), Some(vec!["@work".to_string()]));
+        assert_eq!(
+            values(&provider, "jump "),
+            Some(vec!["web".to_string(), "work".to_string()])
+        );
+        assert_eq!(values(&provider, "jump we"), Some(vec!["web".to_string()]));
+
+        let ctx = extract_context("cd @work", 8);
+        let candidates = provider.provide(&ctx).unwrap();
+        assert_eq!(candidates[0].description.as_deref(), Some("/srv/work"));
+    }
+
+    #[test]
+    fn leaves_other_arguments_to_later_providers() {
+        let (_tmp, provider) = provider_with(&[("work", "/srv/work")]);
+
+        assert_eq!(values(&provider, "cd wo"), None);
+        assert_eq!(values(&provider, "cd @work/sr"), None);
+        assert_eq!(values(&provider, "ls @"), None);
+        assert_eq!(values(&provider, "jump work "), None);
+        assert_eq!(values(&provider, "cd"), None);
+    }
+}
@@ -1,15 +1,22 @@
 //! コマンド名補完 — PATH 走査 + ビルトイン
 //!
-//! `$PATH` の走査はキャッシュレス（fish shell 式）で、Tab 押下ごとに
-//! リアルタイムで走査する。`brew install` 等で新しいバイナリが追加された
-//! 直後でも即座に補完候補に出現する。
+//! `$PATH` の走査結果は直近の 1 件（prefix → 候補）だけをキャッシュし、
+//! 同じ prefix（またはそれを延長した prefix）での連続 Tab 押下では再走査しない。
+//! キャッシュは `$PATH` の値・PATH キャッシュの世代
+//! （[`path_generation`](crate::engine::classifier::path_generation)）・
+//! 各ディレクトリの更新時刻のいずれかが変わると破棄するため、`brew install` 等で
+//! 新しいバイナリが追加された直後でも即座に補完候補に出現する。
 
 use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::os::unix::fs::PermissionsExt;
-use std::sync::{Arc, RwLock};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+
+use nix::unistd::{access, AccessFlags};
 
 use crate::engine::builtins::BUILTIN_COMMANDS;
+use crate::engine::classifier::path_generation;
 
 use super::context::CompletionContext;
 use super::provider::{Candidate, CompletionProvider};
@@ -24,11 +31,87 @@ use super::provider::{Candidate, CompletionProvider};
 /// （description にはエイリアス値を表示する）。
 pub(super) struct CommandProvider {
     aliases: Arc<RwLock<HashMap<String, String>>>,
+    /// 直近の PATH 走査結果
+    scan_cache: Mutex<Option<PathScan>>,
 }
 
 impl CommandProvider {
     pub(super) fn new(aliases: Arc<RwLock<HashMap<String, String>>>) -> Self {
-        Self { aliases }
+        Self {
+            aliases,
+            scan_cache: Mutex::new(None),
+        }
+    }
+
+    /// `prefix` に前方一致する PATH コマンドを、キャッシュが有効なら再利用して返す。
+    fn path_commands(&self, prefix: &str) -> Vec<String> {
+        let path_var = std::env::var("PATH").unwrap_or_default();
+        let key = PathScanKey::current(&path_var);
+
+        if let Ok(cache) = self.scan_cache.lock() {
+            if let Some(commands) = cache.as_ref().and_then(|scan| scan.lookup(prefix, &key)) {
+                return commands;
+            }
+        }
+
+        let commands = scan_path_commands(prefix);
+        if let Ok(mut cache) = self.scan_cache.lock() {
+            *cache = Some(PathScan {
+                prefix: prefix.to_string(),
+                key,
+                commands: commands.clone(),
+            });
+        }
+        commands
+    }
+}
+
+/// PATH 走査結果の有効性を判定するキー
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PathScanKey {
+    /// 走査時の `$PATH` の値
+    path_var: String,
+    /// 走査時の PATH キャッシュの世代
+    generation: u64,
+    /// `$PATH` の各ディレクトリの更新時刻（読めないディレクトリは `None`）
+    dir_mtimes: Vec<Option<SystemTime>>,
+}
+
+impl PathScanKey {
+    /// 現在の `$PATH` からキーを作る。ディレクトリの stat だけで、中身は読まない。
+    fn current(path_var: &str) -> Self {
+        let dir_mtimes = std::env::split_paths(path_var)
+            .map(|dir| fs::metadata(dir).and_then(|m| m.modified()).ok())
+            .collect();
+        Self {
+            path_var: path_var.to_string(),
+            generation: path_generation(),
+            dir_mtimes,
+        }
+    }
+}
+
+/// 直近の PATH 走査結果（prefix → 候補）
+#[derive(Debug, Clone)]
+struct PathScan {
+    prefix: String,
+    key: PathScanKey,
+    commands: Vec<String>,
+}
+
+impl PathScan {
+    /// キーが一致し、`prefix` が走査時の prefix を延長したものなら候補を絞り込んで返す。
+    fn lookup(&self, prefix: &str, key: &PathScanKey) -> Option<Vec<String>> {
+        if self.key != *key || !prefix.starts_with(&self.prefix) {
+            return None;
+        }
+        Some(
+            self.commands
+                .iter()
+                .filter(|name| name.starts_with(prefix))
+                .cloned()
+                .collect(),
+        )
     }
 }
 
@@ -43,7 +126,8 @@ impl CompletionProvider for CommandProvider {
         // 名前をキーにしたマップで統合する。同名が PATH 上の実行ファイルと
         // ビルトインの両方に存在する場合（例: macOS の `/usr/bin/cd`）、
         // ビルトインの説明文を優先する。
-        let mut matches: BTreeMap<String, Option<String>> = scan_path_commands(partial)
+        let mut matches: BTreeMap<String, Option<String>> = self
+            .path_commands(partial)
             .into_iter()
             .map(|name| (name, None))
             .collect();
@@ -88,8 +172,8 @@ pub(super) fn looks_like_path(token: &str) -> bool {
 
 /// `$PATH` 上の実行可能ファイルのうち、`prefix` に前方一致するものを収集する。
 ///
-/// 実行権限チェック (`mode & 0o111 != 0`) を行い、
-/// README 等の非実行ファイルを除外する。PATH コマンドに説明文は付けない。
+/// [`is_executable_file`] で現在のユーザーが実行できるかを確認し、
+/// README 等の非実行ファイルやディレクトリを除外する。PATH コマンドに説明文は付けない。
 fn scan_path_commands(prefix: &str) -> Vec<String> {
     let path_var = match std::env::var("PATH") {
         Ok(p) => p,
@@ -107,10 +191,8 @@ fn scan_path_commands(prefix: &str) -> Vec<String> {
                 if !name.starts_with(prefix) {
                     continue;
                 }
-                if let Ok(metadata) = fs::metadata(entry.path()) {
-                    if metadata.is_file() && metadata.permissions().mode() & 0o111 != 0 {
-                        commands.push(name.to_string());
-                    }
+                if is_executable_file(&entry.path()) {
+                    commands.push(name.to_string());
                 }
             }
         }
@@ -118,6 +200,14 @@ fn scan_path_commands(prefix: &str) -> Vec<String> {
     commands
 }
 
+/// `path` が現在のユーザーが実行できる通常ファイル（シンボリックリンクは辿った先）かを判定する。
+///
+/// 実行ビットの有無（`mode & 0o111`）ではなく `access(2)` の `X_OK` で確認するため、
+/// 所有者のみ実行可能な他ユーザーのファイル等も除外される。
+pub(super) fn is_executable_file(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.is_file()) && access(path, AccessFlags::X_OK).is_ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::context::extract_context;
@@ -159,6 +249,69 @@ mod tests {
         );
     }
 
+    fn scan(prefix: &str, commands: &[&str]) -> PathScan {
+        PathScan {
+            prefix: prefix.to_string(),
+            key: PathScanKey {
+                path_var: "/usr/bin".to_string(),
+                generation: 1,
+                dir_mtimes: vec![None],
+            },
+            commands: commands.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn path_scan_reuses_same_or_extended_prefix() {
+        let cached = scan("ca", &["cargo", "cat", "cal"]);
+        let key = cached.key.clone();
+        assert_eq!(
+            cached.lookup("ca", &key),
+            Some(vec!["cargo".into(), "cat".into(), "cal".into()])
+        );
+        assert_eq!(cached.lookup("car", &key), Some(vec!["cargo".into()]));
+        // 短くした prefix は走査し直す
+        assert_eq!(cached.lookup("c", &key), None);
+    }
+
+    #[test]
+    fn path_scan_invalidated_by_key_change() {
+        let cached = scan("ca", &["cargo"]);
+        let mut key = cached.key.clone();
+        key.generation += 1;
+        assert_eq!(cached.lookup("ca", &key), None);
+
+        let mut key = cached.key.clone();
+        key.path_var = "/usr/local/bin:/usr/bin".to_string();
+        assert_eq!(cached.lookup("ca", &key), None);
+
+        let mut key = cached.key.clone();
+        key.dir_mtimes = vec![Some(SystemTime::UNIX_EPOCH)];
+        assert_eq!(cached.lookup("ca", &key), None);
+    }
+
+    #[test]
+    fn is_executable_file_distinguishes_executables() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let exe = tmp.path().join("tool");
+        let plain = tmp.path().join("README");
+        fs::write(&exe, "").unwrap();
+        fs::write(&plain, "").unwrap();
+        fs::set_permissions(&exe, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::set_permissions(&plain, fs::Permissions::from_mode(0o644)).unwrap();
+        let link = tmp.path().join("tool-link");
+        std::os::unix::fs::symlink(&exe, &link).unwrap();
+
+        assert!(is_executable_file(&exe));
+        assert!(is_executable_file(&link));
+        assert!(!is_executable_file(&plain));
+        // ディレクトリは実行ビットがあってもコマンド候補にしない
+        assert!(!is_executable_file(tmp.path()));
+        assert!(!is_executable_file(&tmp.path().join("missing")));
+    }
+
     #[test]
     fn looks_like_path_true_cases() {
         for token in [
@@ -2,8 +2,9 @@
 //! 外部補完（carapace / zsh ブリッジ）を補完
 //!
 //! - 先頭トークン: PATH 内の実行可能コマンド + ビルトイン (cd, cwd, exit, ...)
-//! - 先頭トークンがパスらしい場合 (`./` `../` `/` `~/`): ファイル / ディレクトリ補完
+//! - 先頭トークンがパスらしい場合 (`./` `../` `/` `~/`): 実行可能ファイル / ディレクトリ補完
 //! - `git <branch系サブコマンド>`: git ブランチ名補完
+//! - `cd @<name>` / `jump <name>`: ブックマーク名補完
 //! - 外部補完対応コマンドの引数: carapace / zsh ブリッジによる外部補完
 //!   （`[completion] external` の方針とバイナリ検出結果に応じて有効・
 //!   無効化・優先順が決まる — [`ExternalCompletionSettings`] 参照）
@@ -11,8 +12,8 @@
 //!
 //! [`CompletionProvider`] トレイトで補完源をプラグイン化しており、
 //! `complete()` は [`Command`](command::CommandProvider) →
-//! [`Git`](git::GitProvider) → **外部補完プロバイダ列**（[`external_provider_chain`]
-//! が [`ExternalCompletionSettings`] の解決済み優先順から動的に組み立てる。
+//! [`Git`](git::GitProvider) → [`Bookmark`](bookmark::BookmarkProvider) →
+//! **外部補完プロバイダ列**（[`external_provider_chain`] が [`ExternalCompletionSettings`] の解決済み優先順から動的に組み立てる。
 //! 既定 `"auto"` では [`Carapace`](carapace::CarapaceProvider) →
 //! [`ZshBridge`](zsh_bridge::ZshBridgeProvider) の順だが、`[completion]
 //! external` を配列（例: `["zsh", "carapace"]`）で指定すると入れ替わる）→
@@ -26,9 +27,10 @@
 //! （carapace が空/未対応だったコマンドのみ zsh の compsys — `_*` 補完関数群
 //! — にフォールバックする）。
 //!
-//! コマンド名補完は fish shell の設計思想に倣い、インメモリキャッシュを持たず、
-//! Tab 押下時にリアルタイムで `$PATH` を走査する（キャッシュレス設計）。
-//! `brew install` 等で新しいバイナリが追加された直後でも即座に補完候補に出現する。
+//! コマンド名補完は Tab 押下時に `$PATH` を走査し、直近の走査結果（prefix → 候補）
+//! だけを再利用する。`$PATH` の値・PATH キャッシュの世代・各ディレクトリの更新時刻の
+//! いずれかが変わればキャッシュを破棄するため、`brew install` 等で新しいバイナリが
+//! 追加された直後でも即座に補完候補に出現する（`command.rs` 参照）。
 //!
 //! シェルエイリアス（`alias` ビルトイン）は先頭トークンではない位置でのみ
 //! 展開する（[`apply_shell_alias`]）。展開結果は各プロバイダ走査前に
@@ -52,6 +54,7 @@
 //! 形で追加できる（既存の `enabled` 優先順リストとは別に、コマンド名 →
 //! 優先種別のマップを resolve() 側に持たせる設計が有力）。
 
+mod bookmark;
 mod carapace;
 mod command;
 mod context;
@@ -71,6 +74,7 @@ use reedline::{Completer, Suggestion};
 
 use crate::engine::expand::{operator_prefix_len, split_quoted};
 
+use bookmark::BookmarkProvider;
 use carapace::{CarapaceProvider, ExternalKind};
 use command::CommandProvider;
 use context::{extract_context, CompletionContext};
@@ -84,6 +88,7 @@ use zsh_bridge::ZshBridgeProvider;
 pub use carapace::{
     format_external_binaries_display, format_external_summary, ExternalCompletionSettings,
 };
+pub(crate) use external::kill_tree;
 pub use zsh_bridge::{
     new_shared_daemon_slot, prewarm_zsh_daemon, shutdown_shared_daemon,
     shutdown_shared_daemon_blocking, DaemonGate, SharedDaemonSlot,
@@ -96,7 +101,7 @@ const DESCRIPTION_LIMIT: usize = 30;
 
 /// Jarvish 用の補完エンジン
 ///
-/// `$PATH` の走査はキャッシュレスだが、Git エイリアスの解決結果は
+/// `$PATH` の走査結果は直近の 1 件だけを再利用し、Git エイリアスの解決結果は
 /// CWD ごとにインメモリキャッシュする（`includeIf` 等のディレクトリ依存設定に対応）。
 ///
 /// `git_branch_commands` は `Shell` と共有され、`source` コマンドで動的に更新される。
@@ -122,6 +127,7 @@ impl JarvishCompleter {
                 Arc::clone(&external_completion),
             )),
             Box::new(GitProvider::new(git_branch_commands)),
+            Box::new(BookmarkProvider::new()),
         ];
         providers.extend(external_provider_chain(&external_completion, &zsh_daemon));
         providers.push(Box::new(PathProvider));
@@ -546,14 +552,14 @@ mod tests {
         let suggestions = completer.complete(&line, pos);
 
         let values: Vec<&str> = suggestions.iter().map(|s| s.value.as_str()).collect();
-        // dirs_only=false の証明: ディレクトリとファイルの両方が候補に出る
+        // コマンド位置ではディレクトリと実行可能ファイルのみが候補に出る
         assert!(
             values.contains(&format!("{path}/Documents/").as_str()),
             "should include Documents/ dir: {values:?}"
         );
         assert!(
-            values.contains(&format!("{path}/readme.txt").as_str()),
-            "should include readme.txt file: {values:?}"
+            !values.contains(&format!("{path}/readme.txt").as_str()),
+            "should not include non-executable readme.txt: {values:?}"
         );
     }
 
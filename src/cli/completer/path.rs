@@ -4,6 +4,7 @@ use std::fs;
 
 use crate::engine::expand;
 
+use super::command::is_executable_file;
 use super::context::CompletionContext;
 use super::provider::{Candidate, CompletionProvider};
 
@@ -11,33 +12,57 @@ use super::provider::{Candidate, CompletionProvider};
 ///
 /// 常に `Some` を返す（担当外という概念がない、最後の砦のプロバイダ）。
 /// `ctx.head_command() == Some("cd")` の場合はディレクトリのみを候補に含める。
-/// 先頭トークンがパスらしく見える場合（`looks_like_path`）も、通常のファイル
-/// 補完（`dirs_only = false`）としてここで処理する（#321 の挙動を維持）。
+/// 先頭トークンがパスらしく見える場合（`looks_like_path`、`./sc<Tab>` 等）はここで処理し、
+/// コマンドとして実行できる実行可能ファイルとディレクトリのみを候補に含める。
+/// 第 2 トークン以降（`cd` 以外）はすべてのファイルを候補に含める。
 pub(super) struct PathProvider;
 
 impl CompletionProvider for PathProvider {
     fn provide(&self, ctx: &CompletionContext) -> Option<Vec<Candidate>> {
-        let dirs_only = if ctx.is_first_token {
-            // 先頭トークンがパスらしくない場合は CommandProvider の担当だが、
-            // 万一ここに到達しても（フォールバック）ディレクトリ限定はしない。
-            false
+        let filter = if ctx.is_first_token {
+            PathFilter::ExecutablesAndDirs
+        } else if ctx.head_command() == Some("cd") {
+            PathFilter::DirsOnly
         } else {
-            ctx.head_command() == Some("cd")
+            PathFilter::All
         };
 
-        Some(complete_path(&ctx.partial, dirs_only))
+        Some(complete_path(&ctx.partial, filter))
     }
 }
 
+/// パス補完で候補に含めるエントリの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathFilter {
+    /// すべてのファイルとディレクトリ
+    All,
+    /// ディレクトリのみ（`cd` 用）
+    DirsOnly,
+    /// 実行可能ファイルとディレクトリのみ（コマンド位置用）
+    ExecutablesAndDirs,
+}
+
 /// パス補完候補を計算する。
 ///
-/// `dirs_only` が true の場合はディレクトリのみを候補に含める（`cd` 用）。
-fn complete_path(partial: &str, dirs_only: bool) -> Vec<Candidate> {
-    let (search_dir, prefix, original_dir) = split_path_prefix(partial);
-
-    let entries = match fs::read_dir(&search_dir) {
-        Ok(e) => e,
-        Err(_) => return vec![],
+/// `filter` に応じて候補に含めるエントリを絞り込む（[`PathFilter`] 参照）。
+///
+/// 中間ディレクトリが存在しない場合（`cd src/cl/ma` で `src/cl` がない等）は、
+/// 存在するディレクトリまで遡り、その直下の成分を途中までの名前として補完する
+/// （例: `src/cli/`）。遡った成分より後ろの入力は候補に含めない。
+fn complete_path(partial: &str, filter: PathFilter) -> Vec<Candidate> {
+    let mut partial = partial.to_string();
+    let (entries, prefix, original_dir) = loop {
+        let (search_dir, prefix, original_dir) = split_path_prefix(&partial);
+        if let Ok(entries) = fs::read_dir(&search_dir) {
+            break (entries, prefix, original_dir);
+        }
+        // 末尾の成分を捨て、ディレクトリ部分の最後の成分をプレフィックスとして再試行する
+        match original_dir.trim_end_matches('/') {
+            parent if !parent.is_empty() && parent.len() < partial.len() => {
+                partial = parent.to_string();
+            }
+            _ => return vec![],
+        }
     };
 
     let mut candidates: Vec<Candidate> = entries
@@ -55,7 +80,15 @@ fn complete_path(partial: &str, dirs_only: bool) -> Vec<Candidate> {
 
             let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
 
-            if dirs_only && !is_dir {
+            let keep = match filter {
+                PathFilter::All => true,
+                PathFilter::DirsOnly => is_dir,
+                // シンボリックリンクは辿った先で判定する（リンク先のディレクトリも候補に残す）
+                PathFilter::ExecutablesAndDirs => {
+                    is_dir || entry.path().is_dir() || is_executable_file(&entry.path())
+                }
+            };
+            if !keep {
                 return None;
             }
 
@@ -201,7 +234,7 @@ mod tests {
         let (_tmpdir, path) = create_test_tree();
         let partial = format!("{path}/");
 
-        let candidates = complete_path(&partial, false);
+        let candidates = complete_path(&partial, PathFilter::All);
 
         let values: Vec<&str> = candidates.iter().map(|c| c.value.as_str()).collect();
         assert!(values.contains(&format!("{path}/Documents/").as_str()));
@@ -216,7 +249,7 @@ mod tests {
         let (_tmpdir, path) = create_test_tree();
         let partial = format!("{path}/Do");
 
-        let candidates = complete_path(&partial, false);
+        let candidates = complete_path(&partial, PathFilter::All);
 
         let values: Vec<&str> = candidates.iter().map(|c| c.value.as_str()).collect();
         assert!(values.contains(&format!("{path}/Documents/").as_str()));
@@ -230,7 +263,7 @@ mod tests {
         let (_tmpdir, path) = create_test_tree();
         let partial = format!("{path}/");
 
-        let candidates = complete_path(&partial, true);
+        let candidates = complete_path(&partial, PathFilter::DirsOnly);
 
         let values: Vec<&str> = candidates.iter().map(|c| c.value.as_str()).collect();
         assert!(values.contains(&format!("{path}/Documents/").as_str()));
@@ -243,18 +276,93 @@ mod tests {
         let (_tmpdir, path) = create_test_tree();
         let partial = format!("{path}/.");
 
-        let candidates = complete_path(&partial, false);
+        let candidates = complete_path(&partial, PathFilter::All);
 
         let values: Vec<&str> = candidates.iter().map(|c| c.value.as_str()).collect();
         assert!(values.contains(&format!("{path}/.hidden_dir/").as_str()));
         assert!(values.contains(&format!("{path}/.dotfile").as_str()));
     }
 
+    #[test]
+    fn complete_path_backtracks_from_partial_intermediate_dir() {
+        let (_tmpdir, path) = create_test_tree();
+        fs::create_dir(format!("{path}/Documents/work")).unwrap();
+
+        // `Docu` は存在しないため、1 つ上まで遡って `Documents/` を補完する
+        let candidates = complete_path(&format!("{path}/Docu/wo"), PathFilter::DirsOnly);
+        let values: Vec<&str> = candidates.iter().map(|c| c.value.as_str()).collect();
+        assert_eq!(values, vec![format!("{path}/Documents/")]);
+
+        // 複数階層が存在しなくても、存在するディレクトリまで遡る
+        let candidates = complete_path(&format!("{path}/De/x/y"), PathFilter::DirsOnly);
+        let values: Vec<&str> = candidates.iter().map(|c| c.value.as_str()).collect();
+        assert_eq!(values, vec![format!("{path}/Desktop/")]);
+
+        // 確定済みの中間ディレクトリはそのまま辿る
+        let candidates = complete_path(&format!("{path}/Documents/wo"), PathFilter::DirsOnly);
+        let values: Vec<&str> = candidates.iter().map(|c| c.value.as_str()).collect();
+        assert_eq!(values, vec![format!("{path}/Documents/work/")]);
+    }
+
+    #[test]
+    fn complete_path_command_position_offers_only_executables_and_dirs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_tmpdir, path) = create_test_tree();
+        fs::write(format!("{path}/script.sh"), "").unwrap();
+        fs::set_permissions(
+            format!("{path}/script.sh"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+        fs::write(format!("{path}/notes.sh"), "").unwrap();
+        fs::set_permissions(
+            format!("{path}/notes.sh"),
+            fs::Permissions::from_mode(0o644),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(format!("{path}/Documents"), format!("{path}/docs_link"))
+            .unwrap();
+
+        let candidates = complete_path(&format!("{path}/"), PathFilter::ExecutablesAndDirs);
+        let values: Vec<&str> = candidates.iter().map(|c| c.value.as_str()).collect();
+        assert!(values.contains(&format!("{path}/script.sh").as_str()));
+        assert!(values.contains(&format!("{path}/Documents/").as_str()));
+        assert!(values.contains(&format!("{path}/docs_link").as_str()));
+        assert!(!values.iter().any(|v| v.contains("notes.sh")));
+        assert!(!values.iter().any(|v| v.contains("readme.txt")));
+
+        // 第 2 トークン以降（PathFilter::All）では非実行ファイルも候補に含める
+        let candidates = complete_path(&format!("{path}/"), PathFilter::All);
+        let values: Vec<&str> = candidates.iter().map(|c| c.value.as_str()).collect();
+        assert!(values.contains(&format!("{path}/notes.sh").as_str()));
+        assert!(values.contains(&format!("{path}/readme.txt").as_str()));
+    }
+
+    #[test]
+    fn provide_filters_by_token_position() {
+        use super::super::context::extract_context;
+
+        let (_tmpdir, path) = create_test_tree();
+        let first = extract_context(&format!("{path}/re"), path.len() + 3);
+        assert_eq!(PathProvider.provide(&first), Some(vec![]));
+
+        let line = format!("cat {path}/re");
+        let second = extract_context(&line, line.len());
+        let values: Vec<String> = PathProvider
+            .provide(&second)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.value)
+            .collect();
+        assert_eq!(values, vec![format!("{path}/readme.txt")]);
+    }
+
     #[test]
     fn complete_nonexistent_dir_returns_empty() {
         let partial = "/nonexistent_dir_12345/";
 
-        let candidates = complete_path(partial, false);
+        let candidates = complete_path(partial, PathFilter::All);
         assert!(candidates.is_empty());
     }
 }
@@ -214,18 +214,17 @@ fn seen_subcommand_from(wanted: &[&str], ctx: &CompletionContext) -> bool {
     words.iter().skip(1).any(|w| wanted.contains(w))
 }
 
-/// リダイレクト演算子（寛容スキャナ/`split_quoted` が単独トークンとして
-/// 認識するもの: `<` `>` `>>`）かどうか。
+/// 直後の 1 語をリダイレクト対象として取るリダイレクト演算子（寛容スキャナ/
+/// `split_quoted` が単独トークンとして認識するもの: `<` `>` `>>` `&>` `&>>`）かどうか。
 ///
 /// `engine/expand/quote.rs::operator_prefix_len` の演算子表と同期を保つ
-/// （B6）。このテーブルは fd 番号プレフィックス付き（`2>` `&>` 等）を単独
-/// トークンとしては扱わない — `2>` は寛容スキャナ上「単語 `2`」+「演算子
-/// `>`」の 2 トークンに分かれる（`operator_prefix_len` が 2 文字演算子として
-/// 認識するのは `&&` `||` `>>` のみ）。そのため fd 番号自体は本関数の対象外
-/// だが、後続のリダイレクト対象語は `>` 単体の直後語として本関数のスキップ
-/// 対象に含まれる。
+/// （B6）。`>&2` も単独の演算子トークンだが、対象語を取らないため対象外。
+/// このテーブルは fd 番号プレフィックス付き（`2>` 等）を単独トークンとしては
+/// 扱わない — `2>` は寛容スキャナ上「単語 `2`」+「演算子 `>`」の 2 トークンに
+/// 分かれる。そのため fd 番号自体は本関数の対象外だが、後続のリダイレクト
+/// 対象語は `>` 単体の直後語として本関数のスキップ対象に含まれる。
 fn is_redirect_operator(op_value: &str) -> bool {
-    matches!(op_value, "<" | ">" | ">>")
+    matches!(op_value, "<" | ">" | ">>" | "&>" | "&>>")
 }
 
 /// `ctx.tokens[skip_from..]` を走査し、演算子トークンとその直後の 1 語
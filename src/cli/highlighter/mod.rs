@@ -70,6 +70,23 @@ impl Highlighter for JarvisHighlighter {
                 chars.next();
                 styled.push((Style::new().fg(Color::Cyan).bold(), "&&".to_string()));
                 is_command = true;
+            } else if c == '&' && chars.peek() == Some(&'>') {
+                // `&>` / `&>>`: stdout と stderr の両方をリダイレクト
+                if !current_word.is_empty() {
+                    style_word(&mut styled, &current_word, &mut is_command);
+                    current_word.clear();
+                }
+                let mut op = String::from("&");
+                if let Some(next_ch) = chars.next() {
+                    op.push(next_ch);
+                }
+                if chars.peek() == Some(&'>') {
+                    if let Some(next_ch) = chars.next() {
+                        op.push(next_ch);
+                    }
+                }
+                styled.push((Style::new().fg(Color::Cyan).bold(), op));
+                is_command = false;
             } else if c == '|' {
                 if !current_word.is_empty() {
                     style_word(&mut styled, &current_word, &mut is_command);
@@ -93,6 +110,9 @@ impl Highlighter for JarvisHighlighter {
                     if let Some(next_ch) = chars.next() {
                         op.push(next_ch);
                     }
+                } else if c == '>' && chars.clone().take(2).eq(['&', '2']) {
+                    // `>&2`: stdout を stderr に複製
+                    op.extend(chars.by_ref().take(2));
                 }
                 styled.push((Style::new().fg(Color::Cyan).bold(), op));
                 is_command = false;
@@ -280,6 +300,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_redirect_both_and_stdout_to_stderr() {
+        let segs = highlight_segments("make &> build.log");
+        assert_eq!(
+            segs,
+            vec![
+                (cmd_style(), "make".into()),
+                (ws(), " ".into()),
+                (redirect_style(), "&>".into()),
+                (ws(), " ".into()),
+                (arg_style(), "build.log".into()),
+            ]
+        );
+
+        let segs = highlight_segments("echo oops >&2");
+        assert_eq!(
+            segs,
+            vec![
+                (cmd_style(), "echo".into()),
+                (ws(), " ".into()),
+                (arg_style(), "oops".into()),
+                (ws(), " ".into()),
+                (redirect_style(), ">&2".into()),
+            ]
+        );
+
+        let segs = highlight_segments("make &>> all.log");
+        assert_eq!(segs[2], (redirect_style(), "&>>".into()));
+    }
+
     #[test]
     fn test_redirect_input() {
         let segs = highlight_segments("sort < data.txt");
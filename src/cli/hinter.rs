@@ -0,0 +1,190 @@
+//! 履歴ベースのオートサジェスト（fish 風ヒント）
+//!
+//! 入力中の行を前方一致で履歴から検索し、最も新しい候補の残り部分を薄色で表示する。
+//! reedline はキー入力のたびにヒンターを呼ぶため、検索結果をキャッシュして
+//! SQLite への問い合わせ回数を抑える。
+
+use nu_ansi_term::{Color, Style};
+use reedline::{Hinter, History, SearchQuery};
+
+/// ヒント検索で取得する履歴の最大件数（最新の 1 件だけを使う）
+const HINT_SEARCH_LIMIT: i64 = 1;
+
+/// ヒントを出し始める最小入力文字数
+const HINT_MIN_CHARS: usize = 2;
+
+/// 直前のヒント検索の結果
+#[derive(Debug, Clone)]
+struct HintCache {
+    /// 検索に使った入力行（前方一致のプレフィックス）
+    prefix: String,
+    /// 見つかった履歴のコマンド行（見つからなければ `None`）
+    candidate: Option<String>,
+}
+
+/// 履歴から前方一致で候補を提示するヒンター。
+///
+/// 入力が前回の検索プレフィックスを延長したものであれば、履歴を再検索せずに
+/// キャッシュから候補を決める。長いプレフィックスに一致する履歴は短いプレフィックスに
+/// 一致する履歴の部分集合なので、キャッシュの候補がまだ一致していればそれが最新の候補であり、
+/// 短いプレフィックスで候補がなければ長いプレフィックスでも候補はない。
+pub struct JarvisHinter {
+    style: Style,
+    /// 現在表示中のヒント（入力済み部分を除いた残り）
+    current_hint: String,
+    cache: Option<HintCache>,
+}
+
+impl Default for JarvisHinter {
+    fn default() -> Self {
+        Self {
+            style: Style::new().fg(Color::DarkGray),
+            current_hint: String::new(),
+            cache: None,
+        }
+    }
+}
+
+impl JarvisHinter {
+    /// `line` に前方一致する最新の履歴コマンドを返す（キャッシュ優先）。
+    fn lookup(&mut self, line: &str, history: &dyn History) -> Option<String> {
+        if let Some(cache) = &self.cache {
+            if line.starts_with(&cache.prefix) {
+                match &cache.candidate {
+                    Some(candidate) if candidate.starts_with(line) => {
+                        return Some(candidate.clone());
+                    }
+                    None => return None,
+                    Some(_) => {}
+                }
+            }
+        }
+
+        // セッションを指定しない（並行して動いている他端末の履歴も候補にする）
+        let mut query = SearchQuery::last_with_prefix(line.to_string(), None);
+        query.limit = Some(HINT_SEARCH_LIMIT);
+        let candidate = history
+            .search(query)
+            .ok()
+            .and_then(|items| items.into_iter().next())
+            .map(|item| item.command_line);
+
+        self.cache = Some(HintCache {
+            prefix: line.to_string(),
+            candidate: candidate.clone(),
+        });
+        candidate
+    }
+}
+
+impl Hinter for JarvisHinter {
+    fn handle(
+        &mut self,
+        line: &str,
+        pos: usize,
+        history: &dyn History,
+        use_ansi_coloring: bool,
+        _cwd: &str,
+    ) -> String {
+        // 行末以外にカーソルがある場合や入力が短い場合はヒントを出さない。
+        // 新しいプロンプト（空行）ではキャッシュを捨て、直前に実行したコマンドも候補に含める。
+        if line.chars().count() < HINT_MIN_CHARS || pos != line.len() {
+            self.current_hint.clear();
+            if line.is_empty() {
+                self.cache = None;
+            }
+            return String::new();
+        }
+
+        self.current_hint = self
+            .lookup(line, history)
+            .and_then(|candidate| candidate.strip_prefix(line).map(str::to_string))
+            .unwrap_or_default();
+
+        if use_ansi_coloring && !self.current_hint.is_empty() {
+            self.style.paint(&self.current_hint).to_string()
+        } else {
+            self.current_hint.clone()
+        }
+    }
+
+    fn complete_hint(&self) -> String {
+        self.current_hint.clone()
+    }
+
+    fn next_hint_token(&self) -> String {
+        // 先頭の空白と、続く 1 単語までを返す
+        let trimmed = self.current_hint.trim_start();
+        let leading = self.current_hint.len() - trimmed.len();
+        let word_len = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        self.current_hint[..leading + word_len].to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::history::history_with;
+    use reedline::HistoryItem;
+
+    #[test]
+    fn suggests_most_recent_prefix_match() {
+        let (_dir, history) = history_with(&["git status", "git stash", "ls -la"]);
+        let mut hinter = JarvisHinter::default();
+        assert_eq!(hinter.handle("git st", 6, &history, false, ""), "ash");
+        assert_eq!(hinter.complete_hint(), "ash");
+    }
+
+    #[test]
+    fn no_hint_for_short_input_or_cursor_in_middle() {
+        let (_dir, history) = history_with(&["git status"]);
+        let mut hinter = JarvisHinter::default();
+        assert_eq!(hinter.handle("g", 1, &history, false, ""), "");
+        assert_eq!(hinter.handle("git", 1, &history, false, ""), "");
+        assert_eq!(hinter.complete_hint(), "");
+    }
+
+    #[test]
+    fn cache_follows_typed_characters() {
+        let (_dir, mut history) = history_with(&["cargo build --release"]);
+        let mut hinter = JarvisHinter::default();
+        assert_eq!(
+            hinter.handle("ca", 2, &history, false, ""),
+            "rgo build --release"
+        );
+
+        // キャッシュが使われることを確認するため、より新しい一致を追加しても
+        // 同じプレフィックスの延長である間は前回の候補が使われる
+        history
+            .save(HistoryItem::from_command_line("cargo test"))
+            .unwrap();
+        assert_eq!(
+            hinter.handle("cargo b", 7, &history, false, ""),
+            "uild --release"
+        );
+
+        // 候補から外れたら再検索する
+        assert_eq!(hinter.handle("cargo t", 7, &history, false, ""), "est");
+    }
+
+    #[test]
+    fn empty_line_resets_cache() {
+        let (_dir, mut history) = history_with(&["echo one"]);
+        let mut hinter = JarvisHinter::default();
+        assert_eq!(hinter.handle("ec", 2, &history, false, ""), "ho one");
+
+        history
+            .save(HistoryItem::from_command_line("echo two"))
+            .unwrap();
+        hinter.handle("", 0, &history, false, "");
+        assert_eq!(hinter.handle("ec", 2, &history, false, ""), "ho two");
+    }
+
+    #[test]
+    fn next_hint_token_takes_one_word() {
+        let (_dir, history) = history_with(&["git commit --amend"]);
+        let mut hinter = JarvisHinter::default();
+        hinter.handle("git", 3, &history, false, "");
+        assert_eq!(hinter.next_hint_token(), " commit");
+    }
+}
@@ -1,11 +1,14 @@
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 use std::time::Duration;
 
 use indicatif::{ProgressBar, ProgressStyle};
 use termimad::crossterm::style::Attribute;
 use termimad::{rgb, CompoundStyle, MadSkin, StyledChar};
 
-use super::color::{red, white};
+use super::code_highlight::{self, Segment};
+use super::color::{bold_yellow, gray, red, white};
+use crate::config::CommandNotice;
+use crate::engine::exec::run_pager;
 
 /// スピナーを生成・開始する共通ヘルパー。
 ///
@@ -30,8 +33,88 @@ pub fn jarvis_talk(message: &str) {
 }
 
 /// Jarvis が Tool Call を受信してコマンドを実行するときに使う共通関数。
-pub fn jarvis_notice(command: &str) {
-    println!("\n👉 {command}\n");
+///
+/// 表示内容は `[ai] command_notice` に従う（[`format_command_notice`] を参照）。
+pub fn jarvis_notice(command: &str, reason: Option<&str>, level: CommandNotice) {
+    if let Some(notice) = format_command_notice(command, reason, level) {
+        println!("{notice}");
+    }
+}
+
+/// コマンド実行前のアナウンスを表示レベルに応じて整形する。
+///
+/// - `verbose`: コマンドに加えて AI が添えた実行理由（あれば）を表示
+/// - `brief`: コマンドのみ表示
+/// - `off`: 表示しない。ただし危険なコマンドは安全のため警告付きで必ず表示する
+fn format_command_notice(
+    command: &str,
+    reason: Option<&str>,
+    level: CommandNotice,
+) -> Option<String> {
+    let dangerous = is_dangerous_command(command);
+    let marker = if dangerous {
+        bold_yellow("⚠️  ")
+    } else {
+        String::new()
+    };
+    match level {
+        CommandNotice::Off if !dangerous => None,
+        CommandNotice::Verbose => match reason {
+            Some(reason) => Some(format!(
+                "\n👉 {marker}{command}\n   {}\n",
+                gray(&format!("↳ {reason}"))
+            )),
+            None => Some(format!("\n👉 {marker}{command}\n")),
+        },
+        _ => Some(format!("\n👉 {marker}{command}\n")),
+    }
+}
+
+/// 取り消しの難しい操作を含むコマンドかどうかを簡易判定する。
+///
+/// `&&` / `||` / `;` / `|` で区切った各コマンドについて、先頭語と引数から判定する。
+/// 誤検知（表示が増える側）は許容し、見逃しを減らす方向に倒している。
+fn is_dangerous_command(command: &str) -> bool {
+    let mut prev_cmd: Option<&str> = None;
+    for segment in command.split(['|', '&', ';']) {
+        let words: Vec<&str> = segment.split_whitespace().collect();
+        let Some((&cmd, args)) = words.split_first() else {
+            continue;
+        };
+        let has_flag = |short: char, long: &str| {
+            args.iter().any(|a| {
+                *a == long || (a.starts_with('-') && !a.starts_with("--") && a.contains(short))
+            })
+        };
+        let dangerous = match cmd {
+            "sudo" | "doas" | "dd" | "shred" | "shutdown" | "reboot" | "halt" | "poweroff" => true,
+            c if c.starts_with("mkfs") => true,
+            "rm" => has_flag('r', "--recursive") || has_flag('f', "--force"),
+            "chmod" | "chown" => has_flag('R', "--recursive"),
+            "git" => match args.first().copied() {
+                Some("push") => {
+                    has_flag('f', "--force") || args.iter().any(|a| a.starts_with("--force"))
+                }
+                Some("reset") => args.contains(&"--hard"),
+                Some("clean") => has_flag('f', "--force"),
+                _ => false,
+            },
+            // `curl ... | sh` のようなリモートスクリプトの直接実行
+            "sh" | "bash" | "zsh" => matches!(prev_cmd, Some("curl" | "wget")),
+            _ => false,
+        };
+        if dangerous {
+            return true;
+        }
+        prev_cmd = Some(cmd);
+    }
+    false
+}
+
+/// AI 応答のトークン使用量・所要時間を控えめな色で表示する。
+/// `[ai] show_usage = true` のときエージェントループ完了後に呼ばれる。
+pub fn jarvis_usage(line: &str) {
+    println!("{}", gray(line));
 }
 
 /// Jarvis がファイルを読み取るときに使う共通関数。
@@ -53,6 +136,12 @@ pub fn jarvis_search_replace(path: &str) -> ProgressBar {
     create_spinner("🔧 {spinner} Patching file: {msg}", path)
 }
 
+/// Jarvis がディレクトリ構造を取得するときに使う共通関数。
+/// 呼び出し元で `finish_and_clear()` を呼んでスピナーを停止すること。
+pub fn jarvis_project_tree(path: &str) -> ProgressBar {
+    create_spinner("🌳 {spinner} Scanning tree: {msg}", path)
+}
+
 /// AI 処理中に表示するスピナーを生成・開始する。
 /// `{msg}` を含むテンプレートにより、進捗メッセージを動的に更新できる。
 pub fn jarvis_spinner() -> ProgressBar {
@@ -105,22 +194,95 @@ fn jarvish_skin() -> MadSkin {
 }
 
 /// termimad を使って Markdown テキストをレンダリングし、ターミナルに表示する。
-pub fn jarvis_render_markdown(text: &str) {
+/// 末尾の空行は除き、最終行の改行で終える（後続の空行はシェル側で 1 行だけ入れる）。
+pub fn jarvis_render_markdown(text: &str, highlight_code: bool) {
     print!("🤵 ");
     let skin = jarvish_skin();
-    skin.print_text(text);
+    print_markdown(&skin, text.trim_end_matches('\n'), highlight_code);
 }
 
-/// Markdown をレンダリングせず、プレーンテキストとしてそのまま表示する。
+/// Markdown をレンダリングせず、プレーンテキストとしてそのまま表示する（末尾の空行は除く）。
 pub fn jarvis_print_plain(text: &str) {
-    println!("🤵 {text}");
+    println!("🤵 {}", text.trim_end_matches('\n'));
 }
 
 /// Jarvis ペルソナなしで Markdown テキストをレンダリングする。
 /// AI パイプなど、🤵 プレフィックスが不要な場面で使用する。
-pub fn render_markdown(text: &str) {
+pub fn render_markdown(text: &str, highlight_code: bool) {
     let skin = jarvish_skin();
-    skin.print_text(text);
+    print_markdown(&skin, text.trim_end_matches('\n'), highlight_code);
+}
+
+/// Markdown を表示する。`highlight_code`（`[ai] code_highlight`）が有効なら、既知の言語タグ付きの
+/// コードブロックだけ termimad を通さずシンタックスハイライトして表示する。
+fn print_markdown(skin: &MadSkin, text: &str, highlight_code: bool) {
+    if !highlight_code {
+        skin.print_text(text);
+        return;
+    }
+    for segment in code_highlight::split_code_blocks(text) {
+        match segment {
+            Segment::Markdown(markdown) => skin.print_text(&markdown),
+            Segment::Code { lang, code } => match code_highlight::highlight(&lang, &code) {
+                Some(highlighted) => print!("{highlighted}"),
+                None => print!("{code}"),
+            },
+        }
+    }
+}
+
+/// 端末の行数が取得できない場合に仮定する行数
+const FALLBACK_TERMINAL_ROWS: usize = 24;
+
+/// 長い AI 応答をページャで開き直すかユーザーに確認する（`[ai] pager` 設定時）。
+///
+/// ストリーミング表示が完了した後に呼ばれる。表示後の行数が端末の高さを超える場合のみ
+/// `Press Enter to page full response` と表示し、Enter / `y` でページャに全文を渡す。
+/// `pager` が空、または stdin / stdout が端末でない場合は何もしない。
+pub fn jarvis_offer_pager(text: &str, pager: &str, as_markdown: bool) {
+    if pager.trim().is_empty() || !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return;
+    }
+
+    let rendered = if as_markdown {
+        jarvish_skin().term_text(text).to_string()
+    } else {
+        text.to_string()
+    };
+    if !exceeds_screen(&rendered, terminal_rows()) {
+        return;
+    }
+
+    print!(
+        "🤵 {}",
+        white("Press Enter to page full response (n to skip): ")
+    );
+    let _ = io::stdout().flush();
+
+    let Some(input) = read_line_ignoring_sigint() else {
+        println!();
+        return;
+    };
+
+    if !matches!(input.trim().to_lowercase().as_str(), "" | "y" | "yes") {
+        return;
+    }
+
+    if let Err(e) = run_pager(pager, &rendered) {
+        eprintln!("jarvish: pager: {pager}: {e}");
+    }
+}
+
+/// 端末の行数を返す（取得できない場合は [`FALLBACK_TERMINAL_ROWS`]）。
+pub fn terminal_rows() -> usize {
+    termimad::crossterm::terminal::size()
+        .map(|(_, rows)| rows as usize)
+        .unwrap_or(FALLBACK_TERMINAL_ROWS)
+}
+
+/// 表示テキストが端末の高さ（プロンプト行を除く）に収まらないかを判定する。
+pub fn exceeds_screen(rendered: &str, rows: usize) -> bool {
+    rendered.lines().count() > rows.saturating_sub(1)
 }
 
 /// タイポ補正に対するユーザーの応答
@@ -134,6 +296,17 @@ pub enum TypoAction {
     Abort,
 }
 
+/// AI に送るコンテキストの確認（`[ai] preview_context = true` 時）に対するユーザーの応答
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextPreviewAction {
+    /// y / Enter: 送信する
+    Send,
+    /// n / Ctrl+C: 送信せずに中止する
+    Cancel,
+    /// a: 送信し、このセッションでは以後確認しない
+    SendAlways,
+}
+
 /// SIGINT を無視した状態で stdin から 1 行読み取る。
 ///
 /// Ctrl+C が押されてもプロセスは終了せず、エラーまたは空文字列として返る。
@@ -192,6 +365,78 @@ pub fn jarvis_ask_typo_correction(typo: &str, suggestion: &str) -> TypoAction {
     }
 }
 
+/// 存在しないコマンドの自動修正を確認する（`[shell] autocorrect = true` 時）。
+///
+/// 「Did you mean `ls`? [Y/n]: 」と表示し、`Y`/`y`/空行（Enter）で `true` を返す。
+/// それ以外（Ctrl+C 含む）は `false`。
+pub fn jarvis_ask_autocorrect(suggestion: &str) -> bool {
+    print!(
+        "🤵 {}",
+        white(&format!("Did you mean `{suggestion}`? [Y/n]: "))
+    );
+    let _ = io::stdout().flush();
+
+    let Some(input) = read_line_ignoring_sigint() else {
+        println!();
+        return false;
+    };
+
+    println!();
+
+    let trimmed = input.trim().to_lowercase();
+    trimmed.is_empty() || trimmed == "y" || trimmed == "yes"
+}
+
+/// AI に送信するコンテキストの要約を表示し、送信の是非を確認する（`[ai] preview_context = true` 時）。
+///
+/// - `y` / Enter (空行): Send
+/// - `a`: SendAlways（このセッションでは以後確認しない）
+/// - それ以外 / Ctrl+C: Cancel
+pub fn jarvis_ask_send_context(summary: &str) -> ContextPreviewAction {
+    println!("🤵 {}", white("Context to be sent to AI:"));
+    for line in summary.lines() {
+        println!("   {line}");
+    }
+    print!(
+        "🤵 {}",
+        white("Send it? [Y/n/a] (a = don't ask again this session): ")
+    );
+    let _ = io::stdout().flush();
+
+    let Some(input) = read_line_ignoring_sigint() else {
+        println!();
+        return ContextPreviewAction::Cancel;
+    };
+
+    println!();
+
+    match input.trim().to_lowercase().as_str() {
+        "" | "y" | "yes" => ContextPreviewAction::Send,
+        "a" | "always" => ContextPreviewAction::SendAlways,
+        _ => ContextPreviewAction::Cancel,
+    }
+}
+
+/// シェル終了前にユーザーへ確認する（`[shell] confirm_exit = true` 時）。
+///
+/// 「Really exit? [y/N]: 」と表示し、ユーザーが `y`/`yes` を入力した場合のみ
+/// `true` を返す。空行（Enter）や Ctrl+C はうっかり終了を防ぐため `false`。
+/// stdin が EOF（Ctrl-D の連打等）の場合は終了意思とみなして `true` を返す。
+pub fn jarvis_ask_exit() -> bool {
+    print!("🤵 {}", white("Really exit? [y/N]: "));
+    let _ = io::stdout().flush();
+
+    let Some(input) = read_line_ignoring_sigint() else {
+        println!();
+        return true;
+    };
+
+    println!();
+
+    let trimmed = input.trim().to_lowercase();
+    trimmed == "y" || trimmed == "yes"
+}
+
 /// コマンド異常終了時にユーザーへ調査の可否を確認する。
 ///
 /// 「調査しますか？ [Y/n]: 」と表示し、ユーザーが `Y`/`y`/空行（Enter）を
@@ -216,3 +461,72 @@ pub fn jarvis_ask_investigate(exit_code: i32) -> bool {
     let trimmed = input.trim().to_lowercase();
     trimmed.is_empty() || trimmed == "y" || trimmed == "yes"
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exceeds_screen_leaves_room_for_prompt() {
+        let text = "line\n".repeat(23);
+        assert!(!exceeds_screen(&text, 24));
+        let text = "line\n".repeat(24);
+        assert!(exceeds_screen(&text, 24));
+    }
+
+    #[test]
+    fn exceeds_screen_handles_zero_rows() {
+        assert!(!exceeds_screen("", 0));
+        assert!(exceeds_screen("one line", 0));
+    }
+
+    #[test]
+    fn detects_dangerous_commands() {
+        assert!(is_dangerous_command("rm -rf build"));
+        assert!(is_dangerous_command("rm -f a.txt"));
+        assert!(is_dangerous_command("sudo apt install jq"));
+        assert!(is_dangerous_command("cd /tmp && rm --recursive cache"));
+        assert!(is_dangerous_command(
+            "git push --force-with-lease origin main"
+        ));
+        assert!(is_dangerous_command("git reset --hard HEAD~1"));
+        assert!(is_dangerous_command("chmod -R 777 ."));
+        assert!(is_dangerous_command(
+            "curl -fsSL https://example.com/install | sh"
+        ));
+        assert!(is_dangerous_command("mkfs.ext4 /dev/sdb1"));
+    }
+
+    #[test]
+    fn safe_commands_are_not_dangerous() {
+        assert!(!is_dangerous_command("rm a.txt"));
+        assert!(!is_dangerous_command("ls -la | grep rf"));
+        assert!(!is_dangerous_command("git push origin main"));
+        assert!(!is_dangerous_command("git reset HEAD~1"));
+        assert!(!is_dangerous_command("chmod +x run.sh"));
+        assert!(!is_dangerous_command("cat install.sh | less"));
+    }
+
+    #[test]
+    fn notice_levels() {
+        let brief = format_command_notice("ls -la", Some("List files"), CommandNotice::Brief);
+        assert_eq!(brief.as_deref(), Some("\n👉 ls -la\n"));
+
+        let verbose =
+            format_command_notice("ls -la", Some("List files"), CommandNotice::Verbose).unwrap();
+        assert!(verbose.contains("ls -la"));
+        assert!(verbose.contains("↳ List files"));
+
+        let verbose_no_reason = format_command_notice("ls -la", None, CommandNotice::Verbose);
+        assert_eq!(verbose_no_reason.as_deref(), Some("\n👉 ls -la\n"));
+
+        assert!(format_command_notice("ls -la", None, CommandNotice::Off).is_none());
+    }
+
+    #[test]
+    fn off_still_shows_dangerous_commands() {
+        let notice = format_command_notice("rm -rf target", None, CommandNotice::Off).unwrap();
+        assert!(notice.contains("rm -rf target"));
+        assert!(notice.contains("⚠️"));
+    }
+}
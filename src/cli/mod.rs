@@ -1,7 +1,10 @@
 pub mod banner;
+pub mod code_highlight;
 pub mod color;
 pub mod completer;
 pub mod highlighter;
+pub mod hinter;
 pub mod jarvis;
 pub mod prompt;
 pub mod update_check;
+pub mod validator;
@@ -4,12 +4,13 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::{Arc, RwLock};
 
-use chrono::Local;
 use reedline::{Color, Prompt, PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus};
 
 use super::git::{current_git_branch_at, format_branch_label, format_git_status_at};
-use crate::cli::color::{cyan, green, red, white, yellow};
-use crate::config::PromptConfig;
+use super::segments::render_right;
+use crate::cli::color::{cyan, green, red, yellow};
+use crate::config::{MaxWidthBehavior, PromptConfig};
+use crate::engine::output_wrap::{display_width, terminal_columns};
 
 /// `last_exit_code` が未設定（コマンド未実行）であることを示すセンチネル値。
 /// `AtomicI32` は `Option<i32>` を直接保持できないため、
@@ -33,6 +34,88 @@ pub fn shorten_path(path: &Path) -> String {
     path.display().to_string()
 }
 
+/// 直前の終了コードに対応するプロンプト先頭の記号と、成功かどうかを返す。
+///
+/// 記号は `[prompt] success_symbol` / `error_symbol` から取る。
+/// コマンド未実行（`EXIT_CODE_NONE`）や記号が空文字列の場合は `None`。
+fn status_symbol(code: i32, config: &PromptConfig) -> Option<(&str, bool)> {
+    let (symbol, success) = match code {
+        EXIT_CODE_NONE => return None,
+        0 => (config.success_symbol.as_str(), true),
+        _ => (config.error_symbol.as_str(), false),
+    };
+    (!symbol.is_empty()).then_some((symbol, success))
+}
+
+/// パス中間の省略記号
+const ELIDED: &str = "...";
+
+/// CWD の表示が `max_width` 桁を超える場合、中間の成分を `...` に置き換えて短くする。
+///
+/// 末尾側の成分を優先して残し、先頭側は最大 2 成分（`~/a`）を残す
+/// （例: `~/a/b/c/project` → `~/a/.../c/project` → `~/a/.../project` → `~/.../project`）。
+/// 最も短くしても収まらない場合は、先頭と末尾の成分だけを残した形を返す。
+fn shorten_middle(path: &str, max_width: usize) -> String {
+    if display_width(path) <= max_width {
+        return path.to_string();
+    }
+    let parts: Vec<&str> = path.split('/').collect();
+    let n = parts.len();
+    if n <= 2 {
+        return path.to_string();
+    }
+    let mut candidate = path.to_string();
+    for kept in (2..n).rev() {
+        let head = if kept >= 3 { 2 } else { 1 };
+        let tail = kept - head;
+        candidate = [&parts[..head], &[ELIDED], &parts[n - tail..]]
+            .concat()
+            .join("/");
+        if display_width(&candidate) <= max_width {
+            break;
+        }
+    }
+    candidate
+}
+
+/// プロンプト 1 行目を端末幅に収めるため、CWD の表示と右プロンプトの有無を決める。
+///
+/// `fixed_width` は 1 行目のうち CWD 以外の部分の表示幅、`right_width` は右プロンプトの
+/// 表示幅（右プロンプトとの間には 1 桁の空白を確保する）。端末幅が取得できない場合は
+/// そのまま表示する。戻り値は `(CWD の表示, 右プロンプトを表示するか)`。
+fn fit_first_line(
+    behavior: MaxWidthBehavior,
+    columns: Option<usize>,
+    fixed_width: usize,
+    cwd: &str,
+    right_width: usize,
+) -> (String, bool) {
+    let Some(columns) = columns else {
+        return (cwd.to_string(), true);
+    };
+    let right_extra = if right_width > 0 { right_width + 1 } else { 0 };
+    let fits_with_right = fixed_width + display_width(cwd) + right_extra <= columns;
+    match behavior {
+        MaxWidthBehavior::Off => (cwd.to_string(), true),
+        MaxWidthBehavior::HideRight => (cwd.to_string(), fits_with_right),
+        MaxWidthBehavior::Shorten => {
+            if fits_with_right {
+                return (cwd.to_string(), true);
+            }
+            // まず右プロンプトを残したまま省略を試み、収まらなければ右プロンプトを省く
+            let budget = columns.saturating_sub(fixed_width + right_extra);
+            let shortened = shorten_middle(cwd, budget);
+            if display_width(&shortened) <= budget {
+                return (shortened, true);
+            }
+            (
+                shorten_middle(cwd, columns.saturating_sub(fixed_width)),
+                false,
+            )
+        }
+    }
+}
+
 /// ホームディレクトリを取得する。
 pub(super) fn dirs_home() -> Option<PathBuf> {
     env::var_os("HOME").map(PathBuf::from)
@@ -179,10 +262,12 @@ impl JarvisPrompt {
             AsyncGitState::Revalidating { stale } => stale.clone(),
         }
     }
-}
 
-impl Prompt for JarvisPrompt {
-    fn render_prompt_left(&self) -> Cow<'_, str> {
+    /// プロンプト 1 行目（改行を含まない）と右プロンプトを描画する。
+    ///
+    /// `[prompt] max_width_behavior` に従い、端末幅に収まるよう CWD を中間省略するか
+    /// 右プロンプトを省く（省いた場合、右プロンプトは空文字列）。
+    fn render_first_line(&self) -> (String, String) {
         let cwd = env::current_dir().unwrap_or_default();
         let cwd_display = shorten_path(&cwd);
 
@@ -190,21 +275,41 @@ impl Prompt for JarvisPrompt {
 
         let code = self.last_exit_code.load(Ordering::Relaxed);
 
-        let label = if code != 0 && code != EXIT_CODE_NONE {
-            red("\u{2717} jarvis")
-        } else if code == 0 {
-            cyan("\u{2714}\u{fe0e} jarvis")
-        } else {
-            cyan("jarvis")
+        let label = match status_symbol(code, &self.config) {
+            Some((symbol, false)) => red(&format!("{symbol} jarvis")),
+            Some((symbol, true)) => cyan(&format!("{symbol} jarvis")),
+            None => cyan("jarvis"),
         };
 
-        let cwd_label = if self.config.nerd_font {
-            yellow(&format!("\u{f4d3} {cwd_display}"))
-        } else {
-            yellow(&cwd_display)
+        let line = |cwd_display: &str| {
+            let cwd_label = if self.config.nerd_font {
+                yellow(&format!("\u{f4d3} {cwd_display}"))
+            } else {
+                yellow(cwd_display)
+            };
+            format!("{label} in {cwd_label} {git_part}")
         };
 
-        Cow::Owned(format!("{label} in {cwd_label} {git_part}\n"))
+        let right = render_right(&self.config.right_segments, self.config.nerd_font);
+        let (cwd_display, show_right) = fit_first_line(
+            self.config.max_width_behavior,
+            terminal_columns(),
+            display_width(&line("")),
+            &cwd_display,
+            display_width(&right),
+        );
+
+        (
+            line(&cwd_display),
+            if show_right { right } else { String::new() },
+        )
+    }
+}
+
+impl Prompt for JarvisPrompt {
+    fn render_prompt_left(&self) -> Cow<'_, str> {
+        let (line, _) = self.render_first_line();
+        Cow::Owned(format!("{line}\n"))
     }
 
     fn get_prompt_color(&self) -> Color {
@@ -212,8 +317,8 @@ impl Prompt for JarvisPrompt {
     }
 
     fn render_prompt_right(&self) -> Cow<'_, str> {
-        let now = Local::now().format("%H:%M:%S").to_string();
-        Cow::Owned(white(&now))
+        let (_, right) = self.render_first_line();
+        Cow::Owned(right)
     }
 
     fn render_prompt_indicator(&self, _edit_mode: PromptEditMode) -> Cow<'_, str> {
@@ -235,3 +340,83 @@ impl Prompt for JarvisPrompt {
         Cow::Owned(format!("{prefix}(search: '{}') ", history_search.term))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_symbol_uses_defaults() {
+        let config = PromptConfig::default();
+        assert_eq!(status_symbol(0, &config), Some(("\u{2714}\u{fe0e}", true)));
+        assert_eq!(status_symbol(1, &config), Some(("\u{2717}", false)));
+        assert_eq!(status_symbol(EXIT_CODE_NONE, &config), None);
+    }
+
+    #[test]
+    fn status_symbol_uses_custom_symbols() {
+        let config = PromptConfig {
+            success_symbol: "[ok]".to_string(),
+            error_symbol: "[FAIL]".to_string(),
+            ..PromptConfig::default()
+        };
+        assert_eq!(status_symbol(0, &config), Some(("[ok]", true)));
+        assert_eq!(status_symbol(130, &config), Some(("[FAIL]", false)));
+    }
+
+    #[test]
+    fn shorten_middle_elides_middle_components() {
+        let path = "~/alpha/beta/gamma/project";
+        assert_eq!(shorten_middle(path, 26), path);
+        assert_eq!(shorten_middle(path, 25), "~/alpha/.../gamma/project");
+        assert_eq!(shorten_middle(path, 20), "~/alpha/.../project");
+        assert_eq!(shorten_middle(path, 15), "~/.../project");
+        // 最も短くしても収まらない場合は先頭と末尾だけ残す
+        assert_eq!(shorten_middle(path, 5), "~/.../project");
+        assert_eq!(shorten_middle("/usr/local/share/doc", 14), "/usr/.../doc");
+        // 省略できる成分がなければそのまま
+        assert_eq!(shorten_middle("~/project", 3), "~/project");
+    }
+
+    #[test]
+    fn fit_first_line_by_behavior() {
+        let cwd = "~/alpha/beta/gamma/project";
+        // 収まる場合はそのまま
+        assert_eq!(
+            fit_first_line(MaxWidthBehavior::Shorten, Some(80), 20, cwd, 8),
+            (cwd.to_string(), true)
+        );
+        // 端末幅が取得できない場合もそのまま
+        assert_eq!(
+            fit_first_line(MaxWidthBehavior::Shorten, None, 20, cwd, 8),
+            (cwd.to_string(), true)
+        );
+        // 右プロンプトを残したまま CWD を省略する
+        assert_eq!(
+            fit_first_line(MaxWidthBehavior::Shorten, Some(50), 20, cwd, 8),
+            ("~/alpha/.../project".to_string(), true)
+        );
+        // 省略しても右プロンプトと並べられなければ右プロンプトを省く
+        assert_eq!(
+            fit_first_line(MaxWidthBehavior::Shorten, Some(40), 20, cwd, 8),
+            ("~/alpha/.../project".to_string(), false)
+        );
+        assert_eq!(
+            fit_first_line(MaxWidthBehavior::HideRight, Some(50), 20, cwd, 8),
+            (cwd.to_string(), false)
+        );
+        assert_eq!(
+            fit_first_line(MaxWidthBehavior::Off, Some(10), 20, cwd, 8),
+            (cwd.to_string(), true)
+        );
+    }
+
+    #[test]
+    fn empty_symbol_hides_marker() {
+        let config = PromptConfig {
+            error_symbol: String::new(),
+            ..PromptConfig::default()
+        };
+        assert_eq!(status_symbol(1, &config), None);
+    }
+}
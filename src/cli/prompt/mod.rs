@@ -1,6 +1,8 @@
 mod git;
 mod jarvis;
+mod segments;
 pub mod starship;
+pub mod title;
 
 use std::borrow::Cow;
 use std::path::PathBuf;
@@ -13,7 +15,7 @@ use crate::config::PromptConfig;
 use jarvis::JarvisPrompt;
 use starship::StarshipPrompt;
 
-pub use jarvis::EXIT_CODE_NONE;
+pub use jarvis::{shorten_path, EXIT_CODE_NONE};
 
 /// ビルトインプロンプトと Starship プロンプトを切り替える列挙型。
 ///
@@ -0,0 +1,216 @@
+//! 右プロンプトのセグメント（`[prompt] right_segments`）
+//!
+//! 右プロンプトは設定で並べたセグメントを左から順に空白区切りで表示する。
+//!
+//! - `time`: 現在時刻（`HH:MM:SS`）
+//! - `battery`: バッテリー残量（Linux は `/sys/class/power_supply`、macOS は `pmset -g batt`）
+//! - `ssh`: SSH 接続中（`SSH_CONNECTION` が設定されている）ならホスト名
+//!
+//! 取得できないセグメント（バッテリーのないマシン等）や未知のセグメント名は静かに省略する。
+
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use tracing::debug;
+
+use crate::cli::color::{green, red, white, yellow};
+
+/// バッテリー情報のキャッシュ有効期間。
+///
+/// 右プロンプトは再描画のたびに呼ばれるため、毎回 sysfs / `pmset` を読まないようにする。
+const BATTERY_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// 残量がこの値（%）以下で放電中なら赤で表示する
+const BATTERY_LOW_PERCENT: u8 = 20;
+
+/// 直近に取得したバッテリー情報（取得時刻と結果）
+static BATTERY_CACHE: Mutex<Option<(Instant, Option<Battery>)>> = Mutex::new(None);
+
+/// バッテリーの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Battery {
+    /// 残量（0〜100 %）
+    percent: u8,
+    /// 充電中（AC 接続中）かどうか
+    charging: bool,
+}
+
+/// 設定されたセグメントを描画し、空白区切りで連結する。
+pub fn render_right(segments: &[String], nerd_font: bool) -> String {
+    segments
+        .iter()
+        .filter_map(|name| render_segment(name, nerd_font))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// セグメント 1 つを描画する（表示するものがなければ `None`）。
+fn render_segment(name: &str, nerd_font: bool) -> Option<String> {
+    match name {
+        "time" => Some(white(&Local::now().format("%H:%M:%S").to_string())),
+        "battery" => battery().map(|b| format_battery(b, nerd_font)),
+        "ssh" => ssh_host().map(|host| format_ssh(&host, nerd_font)),
+        other => {
+            debug!(segment = %other, "Unknown prompt segment, skipping");
+            None
+        }
+    }
+}
+
+fn format_battery(battery: Battery, nerd_font: bool) -> String {
+    let icon = if nerd_font { "\u{f240} " } else { "BAT " };
+    let charging = if battery.charging { "+" } else { "" };
+    let text = format!("{icon}{}%{charging}", battery.percent);
+    if battery.charging {
+        green(&text)
+    } else if battery.percent <= BATTERY_LOW_PERCENT {
+        red(&text)
+    } else {
+        white(&text)
+    }
+}
+
+fn format_ssh(host: &str, nerd_font: bool) -> String {
+    let icon = if nerd_font { "\u{f233} " } else { "ssh:" };
+    yellow(&format!("{icon}{host}"))
+}
+
+/// SSH 接続中であればホスト名（ドメイン部を除く）を返す。
+fn ssh_host() -> Option<String> {
+    if env::var_os("SSH_CONNECTION").map_or(true, |v| v.is_empty()) {
+        return None;
+    }
+    Some(hostname().unwrap_or_else(|| "ssh".to_string()))
+}
+
+/// `gethostname(2)` でホスト名を取得し、最初の `.` より前を返す。
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: 有効なバッファとその長さを渡すだけで、メモリ安全性に影響しない。
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if ret != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    let name = String::from_utf8_lossy(&buf[..len]);
+    let short = name.split('.').next().unwrap_or_default();
+    (!short.is_empty()).then(|| short.to_string())
+}
+
+/// バッテリー情報を返す（[`BATTERY_CACHE_TTL`] の間はキャッシュを使う）。
+fn battery() -> Option<Battery> {
+    let mut cache = BATTERY_CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some((fetched_at, battery)) = *cache {
+        if fetched_at.elapsed() < BATTERY_CACHE_TTL {
+            return battery;
+        }
+    }
+    let battery = read_battery();
+    *cache = Some((Instant::now(), battery));
+    battery
+}
+
+/// Linux: `/sys/class/power_supply/BAT*` の `capacity` / `status` を読む。
+#[cfg(not(target_os = "macos"))]
+fn read_battery() -> Option<Battery> {
+    let entries = std::fs::read_dir("/sys/class/power_supply").ok()?;
+    for entry in entries.flatten() {
+        if !entry.file_name().to_string_lossy().starts_with("BAT") {
+            continue;
+        }
+        let dir = entry.path();
+        let Some(percent) = std::fs::read_to_string(dir.join("capacity"))
+            .ok()
+            .and_then(|s| s.trim().parse::<u8>().ok())
+        else {
+            continue;
+        };
+        let status = std::fs::read_to_string(dir.join("status")).unwrap_or_default();
+        return Some(Battery {
+            percent: percent.min(100),
+            charging: matches!(status.trim(), "Charging" | "Full"),
+        });
+    }
+    None
+}
+
+/// macOS: `pmset -g batt` の出力を解析する。
+#[cfg(target_os = "macos")]
+fn read_battery() -> Option<Battery> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    parse_pmset(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `pmset -g batt` の出力からバッテリー情報を取り出す。
+///
+/// ```text
+/// Now drawing from 'AC Power'
+///  -InternalBattery-0 (id=1234)    85%; charging; 0:42 remaining present: true
+/// ```
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_pmset(output: &str) -> Option<Battery> {
+    let line = output.lines().find(|l| l.contains("InternalBattery"))?;
+    let mut fields = line.split('\t').nth(1)?.split(';').map(str::trim);
+    let percent = fields.next()?.strip_suffix('%')?.parse::<u8>().ok()?;
+    let state = fields.next().unwrap_or_default();
+    Some(Battery {
+        percent: percent.min(100),
+        charging: matches!(state, "charging" | "charged" | "finishing charge"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_and_empty_segments_are_skipped() {
+        assert_eq!(render_right(&[], true), "");
+        assert_eq!(render_right(&["nope".to_string()], true), "");
+        let rendered = render_right(&["nope".to_string(), "time".to_string()], false);
+        assert!(rendered.contains(':'), "{rendered}");
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn ssh_segment_follows_ssh_connection() {
+        env::remove_var("SSH_CONNECTION");
+        assert_eq!(ssh_host(), None);
+
+        env::set_var("SSH_CONNECTION", "10.0.0.1 51234 10.0.0.2 22");
+        let host = ssh_host();
+        env::remove_var("SSH_CONNECTION");
+        assert!(host.is_some_and(|h| !h.is_empty() && !h.contains('.')));
+    }
+
+    #[test]
+    fn parse_pmset_reads_percent_and_state() {
+        let output = "Now drawing from 'AC Power'\n \
+                      -InternalBattery-0 (id=1234)\t85%; charging; 0:42 remaining present: true\n";
+        assert_eq!(
+            parse_pmset(output),
+            Some(Battery {
+                percent: 85,
+                charging: true
+            })
+        );
+
+        let output = " -InternalBattery-0 (id=1)\t12%; discharging; 1:05 remaining\n";
+        assert_eq!(
+            parse_pmset(output),
+            Some(Battery {
+                percent: 12,
+                charging: false
+            })
+        );
+        assert_eq!(parse_pmset("Now drawing from 'AC Power'\n"), None);
+    }
+}
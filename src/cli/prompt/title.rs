@@ -0,0 +1,75 @@
+//! ターミナルタイトルの更新（`[prompt] set_title`）
+//!
+//! コマンド実行中はそのコマンドライン、プロンプト表示中は `jarvish: <cwd>` を
+//! OSC 0 シーケンスでターミナルのタイトル（タブ名）に設定する。
+//! stdout がターミナルでない場合（パイプ・テスト等）は何も出力しない。
+//! 設定が有効かどうかは呼び出し側（`Shell`）が判断する。
+
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+use super::jarvis::shorten_path;
+
+/// タイトルに含める最大文字数（長いワンライナーでタブが埋まらないようにする）
+const MAX_TITLE_CHARS: usize = 80;
+
+/// 実行を開始するコマンドラインをタイトルに設定する。
+pub fn set_command(command: &str) {
+    write_title(command);
+}
+
+/// プロンプト表示前に、カレントディレクトリ（`~` 短縮）をタイトルに設定する。
+pub fn set_prompt(cwd: &Path) {
+    write_title(&format!("jarvish: {}", shorten_path(cwd)));
+}
+
+fn write_title(title: &str) {
+    if !io::stdout().is_terminal() {
+        return;
+    }
+    let mut stdout = io::stdout().lock();
+    let _ = stdout.write_all(title_sequence(title).as_bytes());
+    let _ = stdout.flush();
+}
+
+/// タイトルを設定する OSC 0 シーケンスを組み立てる。
+///
+/// シーケンスを途中で終わらせないよう制御文字（改行・ESC・BEL 等）はスペースに置き換え、
+/// [`MAX_TITLE_CHARS`] を超える分は `…` で切り詰める。
+fn title_sequence(title: &str) -> String {
+    let mut cleaned: String = title
+        .trim()
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .take(MAX_TITLE_CHARS + 1)
+        .collect();
+    if cleaned.chars().count() > MAX_TITLE_CHARS {
+        cleaned = cleaned.chars().take(MAX_TITLE_CHARS - 1).collect();
+        cleaned.push('…');
+    }
+    format!("\x1b]0;{cleaned}\x07")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title_sequence_wraps_text_in_osc0() {
+        assert_eq!(title_sequence("vim foo.rs"), "\x1b]0;vim foo.rs\x07");
+    }
+
+    #[test]
+    fn title_sequence_strips_controls_and_truncates() {
+        assert_eq!(
+            title_sequence("echo a\nprintf '\x1b]0;x\x07'"),
+            "\x1b]0;echo a printf ' ]0;x '\x07"
+        );
+
+        let long = "x".repeat(200);
+        let seq = title_sequence(&long);
+        let body = &seq["\x1b]0;".len()..seq.len() - 1];
+        assert_eq!(body.chars().count(), MAX_TITLE_CHARS);
+        assert!(body.ends_with('…'));
+    }
+}
@@ -0,0 +1,227 @@
+//! 複数行入力の判定（行継続 `\` / 未閉じクオート）
+//!
+//! reedline は Enter 押下時にバリデーターを呼び、`Incomplete` なら確定せずに改行を挿入して
+//! 継続行（`::` インジケータ）の入力に入る。確定した複数行の入力は
+//! [`join_continued_lines`] で行継続の `\` と改行を取り除いてから 1 コマンドとして実行する。
+//!
+//! 確定後、コマンドと分類された入力は [`syntax_error`] で構文だけを検証し、
+//! タイポ補正やコマンド実行に進む前にエラーを表示する。
+
+use std::sync::Arc;
+
+use reedline::{ValidationResult, Validator};
+
+use crate::engine::classifier::{InputClassifier, InputType};
+use crate::engine::{expand, parser};
+
+/// 入力を確定せずに次の行へ継続する理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Continuation {
+    /// 行末がエスケープされていない `\`
+    Backslash,
+    /// シングル/ダブルクオートが閉じていない
+    OpenQuote,
+}
+
+/// 行継続 `\` と未閉じクオートで複数行入力に入るバリデーター。
+///
+/// 自然言語の `I'm tired` のようなアポストロフィで継続行に入らないよう、
+/// 未閉じクオートはコマンドと分類される入力に限って継続する。
+pub struct JarvishValidator {
+    classifier: Arc<InputClassifier>,
+}
+
+impl JarvishValidator {
+    pub fn new(classifier: Arc<InputClassifier>) -> Self {
+        Self { classifier }
+    }
+}
+
+impl Validator for JarvishValidator {
+    fn validate(&self, line: &str) -> ValidationResult {
+        match pending_continuation(line) {
+            Some(Continuation::Backslash) => ValidationResult::Incomplete,
+            Some(Continuation::OpenQuote)
+                if self.classifier.classify(line) == InputType::Command =>
+            {
+                ValidationResult::Incomplete
+            }
+            _ => ValidationResult::Complete,
+        }
+    }
+}
+
+/// 入力が次の行へ継続すべきかを判定する。
+///
+/// - シングルクオート内は `\` を含めて全てリテラル
+/// - ダブルクオート内・クオート外では `\` が次の 1 文字をエスケープする
+/// - 行末の `\`（クオート外）は行継続
+pub fn pending_continuation(input: &str) -> Option<Continuation> {
+    let mut quote: Option<char> = None;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => {
+                let escaped = chars.next();
+                if escaped.is_none() && quote.is_none() {
+                    return Some(Continuation::Backslash);
+                }
+            }
+            (Some('"'), '"') => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            _ => {}
+        }
+    }
+
+    quote.map(|_| Continuation::OpenQuote)
+}
+
+/// 複数行入力から行継続（`\` + 改行）を取り除き、1 コマンドの文字列にする。
+///
+/// シングルクオート内の `\` + 改行は bash と同じくそのまま残す。
+/// クオート内のそれ以外の改行も値の一部として残す。
+pub fn join_continued_lines(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut quote: Option<char> = None;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => match chars.next() {
+                Some('\n') => continue,
+                Some(next) => {
+                    out.push(c);
+                    out.push(next);
+                    continue;
+                }
+                None => {}
+            },
+            (Some('"'), '"') => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            _ => {}
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+/// コマンド入力の構文エラー（演算子の前後のコマンド欠落、リダイレクト先の欠落等）を検出する。
+///
+/// 変数展開・コマンド置換は行わず、分割したトークンをそのままパースするため副作用はない。
+/// 問題がなければ `None`、あればエラーメッセージを返す。
+pub fn syntax_error(line: &str) -> Option<String> {
+    let tokens = match expand::split_quoted(line.trim()) {
+        Ok(tokens) => tokens,
+        Err(e) => return Some(format!("parse error: {e}")),
+    };
+    if tokens.is_empty() {
+        return None;
+    }
+    let words = tokens
+        .into_iter()
+        .map(|token| {
+            if token.is_operator() {
+                parser::Word::from(token.value)
+            } else {
+                parser::Word::literal(token.value)
+            }
+        })
+        .collect();
+    parser::parse_command_list(words)
+        .err()
+        .map(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_backslash_continues() {
+        assert_eq!(
+            pending_continuation("ls -la \\"),
+            Some(Continuation::Backslash)
+        );
+        assert_eq!(pending_continuation("echo a\\\\"), None);
+        assert_eq!(pending_continuation("ls -la"), None);
+    }
+
+    #[test]
+    fn open_quote_continues() {
+        assert_eq!(
+            pending_continuation("echo \"hello"),
+            Some(Continuation::OpenQuote)
+        );
+        assert_eq!(
+            pending_continuation("echo 'it\\"),
+            Some(Continuation::OpenQuote)
+        );
+        assert_eq!(pending_continuation("echo \"a\\\"b\""), None);
+        assert_eq!(pending_continuation("echo 'a\"b'"), None);
+    }
+
+    #[test]
+    fn join_removes_backslash_newline() {
+        assert_eq!(
+            join_continued_lines("git commit \\\n  -m msg"),
+            "git commit   -m msg"
+        );
+        assert_eq!(join_continued_lines("echo \"a\\\nb\""), "echo \"ab\"");
+        assert_eq!(join_continued_lines("echo \"a\nb\""), "echo \"a\nb\"");
+        // シングルクオート内はそのまま
+        assert_eq!(join_continued_lines("echo 'a\\\nb'"), "echo 'a\\\nb'");
+        // 行継続以外のエスケープは残す
+        assert_eq!(join_continued_lines("echo a\\ b"), "echo a\\ b");
+    }
+
+    #[test]
+    fn syntax_error_detects_broken_operators() {
+        assert_eq!(
+            syntax_error("ls | | wc").as_deref(),
+            Some("syntax error near unexpected token '|'")
+        );
+        assert_eq!(
+            syntax_error("echo hi >").as_deref(),
+            Some("syntax error near '>': expected filename")
+        );
+        assert!(syntax_error("&& ls").is_some());
+        assert!(syntax_error("make &&").is_some());
+    }
+
+    #[test]
+    fn syntax_error_accepts_valid_commands() {
+        assert_eq!(syntax_error("ls -la | wc -l > out.txt"), None);
+        assert_eq!(syntax_error("echo \"a | b\" && echo c; echo d"), None);
+        assert_eq!(syntax_error("echo \"|\" '>'"), None);
+        // 展開は行わない（コマンド置換を実行しない）
+        assert_eq!(syntax_error("echo $(touch /nonexistent/x)"), None);
+        assert_eq!(syntax_error("   "), None);
+    }
+
+    #[test]
+    fn validator_continues_open_quote_only_for_commands() {
+        let validator = JarvishValidator::new(Arc::new(InputClassifier::new()));
+        assert!(matches!(
+            validator.validate("echo 'hello"),
+            ValidationResult::Incomplete
+        ));
+        assert!(matches!(
+            validator.validate("I'm tired"),
+            ValidationResult::Complete
+        ));
+        assert!(matches!(
+            validator.validate("make \\"),
+            ValidationResult::Incomplete
+        ));
+        assert!(matches!(
+            validator.validate("make all"),
+            ValidationResult::Complete
+        ));
+    }
+}
@@ -16,10 +16,34 @@ impl super::JarvishConfig {
 # model = "gpt-4o"
 # max_rounds = 10
 # markdown_rendering = true  # false にすると Markdown レンダリングを無効化
+# code_highlight = true      # false にすると Markdown 表示時のコードブロックの色付け（```rust 等）を無効化
 # ai_pipe_max_chars = 50000
 # ai_redirect_max_chars = 50000
-# temperature = 0.5          # 回答のランダム性 (0.0=決定的, 2.0=最大ランダム)
+# temperature = 0.5          # 回答のランダム性 (0.0=決定的, 2.0=最大ランダム)。既定は未設定（送信せずプロバイダの既定値を使う）。0.5 は設定例
+# top_p = 0.9                # nucleus sampling (0.0〜1.0)。未設定ならプロバイダの既定値
+# max_tokens = 2048          # 1 回の応答の最大トークン数。未設定ならプロバイダの既定値
+# show_usage = false         # true にすると応答後に [gpt-4o · 1.2s · 340 tokens] を表示
+# command_notice = "brief"   # AI 実行前のアナウンス: "verbose"（理由も表示）| "brief" | "off"（危険なコマンドは常に表示）
+# verbosity = "concise"      # 回答の詳しさ: "concise"（ターミナル向けの短い回答）| "detailed"（背景説明込み）。入力末尾に --detailed / --concise を付けるとその質問だけ切り替え
+# pager = ""                 # "less -R" 等を指定すると、一画面に収まらない応答を表示後にページャで開ける
+# farewell = false           # true にすると Goodbye 入力時に AI の別れの挨拶を表示してから終了する
+# stream_idle_timeout_secs = 60  # AI の応答がこの秒数途絶えたら打ち切り、受信済みの内容を [timed out] 付きで表示（0 で無効）
+# edit_before_run = false    # true にすると AI が提案したコマンドを入力行に表示し、編集・確定してから実行する（空で確定するとキャンセル）
+# preview_context = false    # true にすると新規会話の前に AI へ送る履歴コンテキストの要約を表示し、送信するか確認する
+# type_ahead = "keep"        # AI 応答中の打鍵: "keep"（次の入力行に反映。Enter でも実行しない）| "discard"（破棄）
 # ignore_auto_investigation_cmds = ["git log", "git diff"]  # 自動調査をスキップするコマンド
+# enabled_tools = ["execute_shell_command", "read_file", "read_files", "write_file", "search_replace", "get_project_tree"]  # AI が使えるツール（write_file / search_replace を外すと読み取り専用）
+# show_git_diff_after_write = false  # true にすると AI がファイルを変更した応答の後に、git 管理下のファイルの git diff をまとめて表示する
+# cache = false              # true にすると同じ質問（入力＋コンテキスト）への自然言語応答をキャッシュし、API を呼ばずに再表示する（コマンド判定は対象外）
+# cache_ttl_secs = 86400     # キャッシュの有効期間（秒、0 で無期限）
+# cache_max_entries = 500    # キャッシュする応答の最大件数（超えたら古いものから削除、0 で無制限）
+# max_tool_output_lines = 200  # AI が実行したコマンドの出力を会話に戻す際の最大行数（超えた分は先頭から省略。0 で無制限）
+# profile = ""               # 起動時に使うプロファイル名（下の [ai.profiles.<name>]。空文字列なら [ai] の値をそのまま使う）
+
+# [ai.profiles.fast]         # `ai use fast` で切り替えられるプロファイル（省略した項目は [ai] の値）
+# model = "gpt-4o-mini"
+# max_rounds = 5
+# temperature = 0.3
 
 [alias]
 # g = "git"
@@ -27,6 +51,7 @@ impl super::JarvishConfig {
 
 [export]
 # PATH = "/usr/local/bin:$PATH"
+# EDITOR = "${EDITOR:-vim}"   # ${VAR:-default} 等のパラメータ展開も使える
 #
 # ⚠️ SHELL = "/usr/local/bin/jarvish" の設定に注意:
 # 外部ツール（Cursor, VS Code 等）がサブシェルとして jarvish を使用するようになり、
@@ -36,6 +61,11 @@ impl super::JarvishConfig {
 [prompt]
 # nerd_font = true  # false にすると NerdFont アイコンを使わない
 # starship = false   # true にすると Starship プロンプトを使用（要: starship コマンド + ~/.config/starship.toml）
+# success_symbol = "✔︎" # 直前のコマンドが成功したときの記号（色で区別しにくい場合は "[ok]" など）
+# error_symbol = "✗"  # 直前のコマンドが失敗したときの記号（例: "[FAIL]" / "!!"）
+# set_title = false  # true にするとターミナルタイトルに実行中のコマンド / カレントディレクトリを表示
+# right_segments = ["time"]  # 右プロンプトの表示内容（"time" / "battery" / "ssh" を並べる。例: ["ssh", "battery", "time"]）
+# max_width_behavior = "shorten"  # 端末幅に収まらないとき: "shorten"（CWD を ~/a/.../project に中間省略）| "hide_right"（右プロンプトを省く）| "off"
 
 [completion]
 # git_branch_commands = ["checkout", "switch", "merge", "rebase", "branch", "diff", "log", "cherry-pick", "reset", "push", "fetch"]
@@ -56,6 +86,50 @@ impl super::JarvishConfig {
 [startup]
 # シェル起動時に順次実行するコマンド（-c オプション実行時はスキップ）
 # commands = ["echo 'Welcome to jarvish!'", "export JAVA_HOME=/usr/lib/jvm/default"]
+
+[shell]
+# confirm_exit = false  # true にすると引数なしの exit / logout や Ctrl-D で終了前に確認する
+#                       # （`exit 0` のような引数付きや `exit --force` は確認しない）
+# dry_run = false       # true にするとコマンドを実行せず、展開後のコマンドを表示するだけにする（--dry-run と同じ）
+# auto_ls_on_cd = false # true にすると cd（cd - / 引数なしを含む）成功後に cd_hook を自動実行する
+# cd_hook = "ls"        # auto_ls_on_cd 有効時に実行するコマンド（例: "ls --color"）。失敗しても cd は成功扱い
+# auto_cd = false       # true にするとディレクトリパスだけの入力（例: ../ や ~/dev）でそのディレクトリへ移動する（zsh の AUTO_CD）
+# url_action = "ai"     # URL だけの入力の扱い: "ai"（AI に委ねる）| "open"（open / xdg-open で開く）| "error"（エラーにする）
+# cd_to_file_parent = false # true にすると cd にファイルを指定したとき、その親ディレクトリへ移動する（リンクはリンク自身の場所）
+# mkdir_on_redirect = false # true にすると `echo x > logs/out.txt` のようなリダイレクトで、存在しない親ディレクトリを作成してから書き込む
+# wrap_output = "off"   # "truncate" にするとパイプ・リダイレクト経由の長い出力行を端で切り詰めて表示（履歴には全文を保存）
+# autocorrect = false   # true にすると見つからないコマンドに近い候補が 1 つだけある場合、確認のうえ修正して実行する
+# force_color = false   # true にすると PTY が使えず pipe で出力を取得する際も CLICOLOR_FORCE=1 / FORCE_COLOR=1 で色出力を維持する（色コードが履歴にも残る）
+# bell_on_error = false # true にするとコマンドが非ゼロで終了したときにベル（BEL）を鳴らす
+# bell_on_ai_response = false # true にすると AI の応答が完了したときにベル（BEL）を鳴らす
+# default_command_timeout = 0 # 秒数を指定すると、超過した外部コマンドを SIGTERM → SIGKILL で終了させる（exit 124）。vim 等の対話コマンドには適用しない
+
+[editor]
+# hints = true          # 入力中に履歴から薄色のヒントを表示（右矢印 / Ctrl-F で確定）。変更は再起動後に反映
+
+[banner]
+# enabled = true        # false にすると起動・終了時のバナーを表示しない（スクリプト用途で静かにしたい場合など）
+# welcome_file = ""     # 組み込みのロゴの代わりに表示するファイル（例: "~/.config/jarvish/welcome.txt"）
+# goodbye_file = ""     # 組み込みの終了メッセージの代わりに表示するファイル
+
+[env]
+# persist = false       # true にすると `export --persist KEY=VALUE` した変数を env.toml（0600）に保存し、次回起動時に設定する
+
+[history]
+# track_env = []        # コマンドごとに値を履歴へ記録する環境変数（例: ["VIRTUAL_ENV", "NODE_ENV"]）。`history --env` で表示
+# record_ai_conversation = true # false にするとコマンドを実行しなかった AI との会話を履歴（矢印キーの履歴を含む）に残さない
+
+[hooks]
+# pre_exec = ""         # 外部コマンドの実行直前に実行するコマンド（$JARVISH_LAST_CMD に実行するコマンド）
+# post_exec = ""        # 外部コマンドの実行直後に実行するコマンド（$JARVISH_LAST_CMD / $JARVISH_LAST_EXIT）。失敗しても本体に影響しない
+
+[logging]
+# level = "debug"       # ログレベル（"info", "jarvish=debug,warn" 等）。環境変数 JARVISH_LOG（なければ RUST_LOG）が優先。変更は次回起動時に反映
+# file = true           # false にするとログファイル（~/.local/share/jarvish/logs/）に出力しない
+# stderr = false        # true にすると標準エラー出力にもログを出す
+# max_files = 0         # 保持する日次ログファイル数（0 で無制限）
+# retention_days = 14   # 起動時にこの日数より古いログファイルを削除する（0 で無効）
+# max_file_size_mb = 100  # 1 ファイルのサイズ上限（MB）。超えたら jarvish_YYYY-MM-DD.1.log に退避する（0 で無制限）
 "#;
 
         if let Some(parent) = path.parent() {
@@ -10,10 +10,34 @@
 //! model = "gpt-4o"
 //! max_rounds = 10
 //! markdown_rendering = true
+//! code_highlight = true         # Markdown 表示時にコードブロックを言語タグに応じて色付けする
 //! ai_pipe_max_chars = 50000
 //! ai_redirect_max_chars = 50000
-//! temperature = 0.5
+//! temperature = 0.5             # 既定は未設定（送信せずプロバイダの既定値を使う）
+//! top_p = 0.9                   # 未設定ならプロバイダの既定値（0.0〜1.0）
+//! max_tokens = 2048             # 1 回の応答の最大トークン数（未設定ならプロバイダの既定値）
+//! show_usage = false
+//! command_notice = "brief"      # "verbose" | "brief" | "off"
+//! verbosity = "concise"         # 回答の詳しさ: "concise"（短く）| "detailed"（背景説明込み）。入力末尾の --detailed / --concise で一時指定
+//! pager = "less -R"             # 一画面に収まらない応答をページャで開き直す（空文字列で無効）
+//! farewell = false              # Goodbye 入力時に AI の別れの挨拶を待つか（false で即終了）
+//! stream_idle_timeout_secs = 60 # 応答が途絶えてからストリームを打ち切るまでの秒数（0 で無効）
+//! edit_before_run = false       # AI が提案したコマンドを入力行に表示し、編集・確定してから実行するか
+//! preview_context = false       # 新規会話の前に AI へ送る履歴コンテキストの要約を表示し、送信を確認するか
+//! type_ahead = "keep"           # AI 応答中の打鍵: "keep"（次の入力行に反映）| "discard"（破棄）
 //! ignore_auto_investigation_cmds = ["git log", "git diff"]
+//! enabled_tools = ["execute_shell_command", "read_file"]  # AI が使えるツール（既定はすべて）
+//! show_git_diff_after_write = false  # AI がファイルを変更したら応答後に git diff を表示する
+//! cache = false                 # 同じ質問（入力＋コンテキスト）への自然言語応答をキャッシュから返す
+//! cache_ttl_secs = 86400        # キャッシュの有効期間（秒、0 で無期限）
+//! cache_max_entries = 500       # キャッシュする応答の最大件数（古いものから削除、0 で無制限）
+//! max_tool_output_lines = 200   # AI が実行したコマンドの出力を会話に戻す際の最大行数（0 で無制限）
+//! profile = "fast"              # 起動時に使う [ai.profiles.<name>]（空文字列で [ai] の値をそのまま使う）
+//!
+//! [ai.profiles.fast]            # `ai use fast` で切り替え（省略した項目は [ai] の値）
+//! model = "gpt-4o-mini"
+//! max_rounds = 5
+//! temperature = 0.3
 //!
 //! [alias]
 //! g = "git"
@@ -25,6 +49,11 @@
 //! [prompt]
 //! nerd_font = true
 //! starship = false
+//! success_symbol = "✔︎"          # 直前のコマンドが成功したときの記号
+//! error_symbol = "✗"            # 直前のコマンドが失敗したときの記号（例: "[FAIL]"）
+//! set_title = false             # ターミナルタイトルに実行中のコマンド / カレントディレクトリを表示
+//! right_segments = ["battery", "time"]  # 右プロンプトの表示内容（"time" / "battery" / "ssh"）
+//! max_width_behavior = "shorten"  # 端末幅に収まらないとき: "shorten" | "hide_right" | "off"
 //!
 //! [completion]
 //! git_branch_commands = ["checkout", "switch", "merge", "rebase", "branch", "diff", "log", "cherry-pick", "reset", "push", "fetch"]
@@ -34,6 +63,49 @@
 //!
 //! [startup]
 //! commands = ["echo 'Welcome to jarvish!'", "export JAVA_HOME=/usr/lib/jvm/default"]
+//!
+//! [shell]
+//! confirm_exit = false
+//! dry_run = false
+//! auto_ls_on_cd = true          # cd 成功後に cd_hook を自動実行する
+//! cd_hook = "ls --color"
+//! auto_cd = false               # ディレクトリパスだけの入力でそのディレクトリへ移動（zsh の AUTO_CD）
+//! url_action = "ai"             # URL だけの入力: "ai"（AI に委ねる）| "open"（ブラウザで開く）| "error"
+//! cd_to_file_parent = false     # `cd path/to/file.txt` でファイルの親ディレクトリへ移動する
+//! mkdir_on_redirect = false     # `echo x > logs/out.txt` でリダイレクト先の親ディレクトリを作成する
+//! wrap_output = "off"           # "off" | "truncate"（長い行を端で切り詰めて表示）
+//! autocorrect = false           # 見つからないコマンドを確認のうえ修正して実行（`sl` → `ls`）
+//! force_color = false           # PTY が使えず pipe で出力を取得する際も子プロセスに色出力を強制
+//! bell_on_error = false         # コマンドが非ゼロで終了したらベル（BEL）を鳴らす
+//! bell_on_ai_response = false   # AI の応答が完了したらベル（BEL）を鳴らす
+//! default_command_timeout = 0   # 非対話コマンドの制限時間（秒、0 で無効）。超過すると終了させ exit 124
+//!
+//! [editor]
+//! hints = true                  # 履歴からのオートサジェスト（右矢印 / Ctrl-F で確定）
+//!
+//! [banner]
+//! enabled = true                # false で起動・終了時のバナーを表示しない
+//! welcome_file = "~/.config/jarvish/welcome.txt"  # 組み込みロゴの代わりに表示するファイル
+//! goodbye_file = ""             # 空文字列なら組み込みの終了メッセージ
+//!
+//! [env]
+//! persist = true                # `export --persist` した変数を env.toml に保存し次回起動時も設定
+//!
+//! [history]
+//! track_env = ["VIRTUAL_ENV", "NODE_ENV"]  # コマンドごとに値を履歴へ記録する環境変数（`history --env` で表示）
+//! record_ai_conversation = true # コマンドを実行しなかった AI との会話も履歴に記録する
+//!
+//! [hooks]
+//! pre_exec = "printf '\\033]0;%s\\007' \"$JARVISH_LAST_CMD\""  # 外部コマンドの実行直前に実行（端末タイトル更新等）
+//! post_exec = "echo \"$JARVISH_LAST_CMD -> $JARVISH_LAST_EXIT\" >> ~/.jarvish_exec.log"  # 実行直後に実行
+//!
+//! [logging]
+//! level = "debug"               # EnvFilter 形式（"info", "jarvish=debug,warn" 等）。環境変数 JARVISH_LOG（なければ RUST_LOG）が優先
+//! file = true                   # ~/.local/share/jarvish/logs/ のログファイルに出力する
+//! stderr = false                # 標準エラー出力にも出力する
+//! max_files = 0                 # 日次ローテーションで保持するログファイル数（0 で無制限）
+//! retention_days = 14           # 起動時にこの日数より古いログファイルを削除（0 で無効）
+//! max_file_size_mb = 100        # 1 ファイルのサイズ上限。超えたら .1.log に退避（0 で無制限）
 //! ```
 
 mod defaults;
@@ -60,6 +132,20 @@ pub struct JarvishConfig {
     pub completion: CompletionConfig,
     /// 起動時に実行するコマンド
     pub startup: StartupConfig,
+    /// シェル本体の挙動に関する設定
+    pub shell: ShellConfig,
+    /// 行エディタ（reedline）の設定
+    pub editor: EditorConfig,
+    /// 起動・終了時のバナー設定
+    pub banner: BannerConfig,
+    /// 環境変数の永続化設定
+    pub env: EnvConfig,
+    /// コマンド履歴の記録設定
+    pub history: HistoryConfig,
+    /// コマンド実行前後のフック
+    pub hooks: HooksConfig,
+    /// ログ出力の設定（起動時にのみ適用）
+    pub logging: LoggingConfig,
 }
 
 /// AI 関連の設定
@@ -72,30 +158,326 @@ pub struct AiConfig {
     pub max_rounds: usize,
     /// AI レスポンスを Markdown としてレンダリングするか
     pub markdown_rendering: bool,
+    /// Markdown レンダリング時、言語タグ付きのコードブロック（```rust 等）をシンタックス
+    /// ハイライトするか（既定 true）。未知の言語はそのまま表示する
+    pub code_highlight: bool,
     /// AI パイプ (`cmd | ai "..."`) の入力テキスト文字数上限
     pub ai_pipe_max_chars: usize,
     /// AI リダイレクト (`cmd > ai "..."`) の入力テキスト文字数上限
     pub ai_redirect_max_chars: usize,
-    /// 回答のランダム性（0.0 = 決定的、2.0 = 最大ランダム）
-    pub temperature: f32,
+    /// 回答のランダム性（0.0 = 決定的、2.0 = 最大ランダム）。
+    /// 未設定ならリクエストに含めずプロバイダの既定値を使う（指定を受け付けないモデルもあるため）
+    pub temperature: Option<f32>,
+    /// nucleus sampling の確率質量（0.0〜1.0）。未設定ならプロバイダの既定値
+    pub top_p: Option<f32>,
+    /// 1 回の応答で生成する最大トークン数。未設定ならプロバイダの既定値
+    pub max_tokens: Option<u32>,
+    /// AI 応答完了後にトークン使用量と所要時間を `[gpt-4o · 1.2s · 340 tokens]` 形式で表示するか
+    pub show_usage: bool,
+    /// AI がコマンドを実行する前のアナウンス（`👉 cmd`）の表示レベル
+    pub command_notice: CommandNotice,
+    /// AI の回答の詳しさ（既定 `"concise"`）。入力末尾の `--detailed` / `--concise` で 1 回だけ上書きできる
+    pub verbosity: Verbosity,
+    /// 長い自然言語応答をストリーミング表示後に開き直すページャコマンド（例: `less -R`）。
+    /// 空文字列の場合は無効
+    pub pager: String,
+    /// Goodbye 入力時に AI に別れの挨拶をさせてから終了するか。
+    /// `false` の場合は AI を呼ばずにバナーの goodbye メッセージのみ表示して即終了する
+    pub farewell: bool,
+    /// AI の応答（接続・チャンク受信）がこの秒数途絶えたらストリームを打ち切り、
+    /// 受信済みのテキストを `[timed out]` 付きで返す。0 の場合は無効
+    pub stream_idle_timeout_secs: u64,
+    /// AI が実行しようとするコマンドを即実行せず、入力行にプリフィルして編集・確定させてから
+    /// 実行するか。空で確定するとキャンセルする（対話モードのみ）
+    pub edit_before_run: bool,
+    /// 新規会話で AI にリクエストする前に、送信するコンテキスト（システムプロンプトを除く
+    /// 履歴部分）の要約を表示して送信の是非を確認するか（対話モードのみ、既定 false）
+    pub preview_context: bool,
+    /// AI 応答中に打鍵された入力の扱い（応答中はエコーせず、改行でも実行しない）
+    pub type_ahead: TypeAhead,
     /// 異常終了時に自動調査をスキップするコマンドの前方一致パターン
     pub ignore_auto_investigation_cmds: Vec<String>,
+    /// AI が使用できるツール名（既定は [`DEFAULT_ENABLED_TOOLS`] のすべて）。
+    /// 含まれないツールは AI に定義を渡さず、呼ばれても「tool disabled」を返す
+    pub enabled_tools: Vec<String>,
+    /// AI が `write_file` / `search_replace` でファイルを変更した場合、応答の完了後に
+    /// git 管理下のファイルの `git diff` をまとめて表示するか（既定 false）
+    pub show_git_diff_after_write: bool,
+    /// 同じ入力＋コンテキストへの自然言語応答をローカル（`ai_cache.db`）にキャッシュし、
+    /// 有効期間内は API を呼ばずにキャッシュから返すか（既定 false）。
+    /// コマンド判定やツールを使った応答は環境に依存するためキャッシュしない
+    pub cache: bool,
+    /// キャッシュの有効期間（秒、既定 86400）。0 の場合は期限切れにしない
+    pub cache_ttl_secs: u64,
+    /// キャッシュする応答の最大件数（既定 500）。超えた分は古いものから削除する。0 の場合は無制限
+    pub cache_max_entries: usize,
+    /// AI が実行したコマンドの stdout / stderr を会話に戻す際の最大行数（既定 200）。
+    /// 超えた分は先頭から省略して末尾を残す。0 の場合は行数では切り詰めない
+    pub max_tool_output_lines: usize,
+    /// 起動時に使うプロファイル名（`[ai.profiles.<name>]`）。空文字列の場合は `[ai]` の値をそのまま使う
+    pub profile: String,
+    /// `ai use <name>` で切り替えられるプロファイル（`[ai.profiles.<name>]`）
+    pub profiles: HashMap<String, AiProfile>,
 }
 
+/// `[ai.profiles.<name>]` で定義する AI プロファイル。
+///
+/// 省略した項目は `[ai]` の値を使う。
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AiProfile {
+    /// 使用する AI モデル名
+    pub model: Option<String>,
+    /// エージェントループの最大ラウンド数
+    pub max_rounds: Option<usize>,
+    /// 回答のランダム性
+    pub temperature: Option<f32>,
+}
+
+impl AiConfig {
+    /// プロファイル `name` の値で `model` / `max_rounds` / `temperature` を上書きした設定を返す。
+    /// プロファイルが定義されていなければ `None`。
+    pub fn with_profile(&self, name: &str) -> Option<AiConfig> {
+        let profile = self.profiles.get(name)?;
+        let mut config = self.clone();
+        if let Some(ref model) = profile.model {
+            config.model = model.clone();
+        }
+        if let Some(max_rounds) = profile.max_rounds {
+            config.max_rounds = max_rounds;
+        }
+        if profile.temperature.is_some() {
+            config.temperature = profile.temperature;
+        }
+        Some(config)
+    }
+
+    /// 範囲外の値を既定値に戻し、警告メッセージを返す。
+    ///
+    /// - `temperature`: 0.0〜2.0（範囲外は未設定。プロファイルでは `[ai]` の値を使う）
+    /// - `top_p`: 0.0〜1.0（範囲外は未設定）
+    /// - `max_tokens`: 1 以上（0 は未設定）
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(temperature) = self.temperature.filter(|t| !TEMPERATURE_RANGE.contains(t)) {
+            warnings.push(format!(
+                "[ai] temperature must be between 0.0 and 2.0 (got {temperature}); using the provider default"
+            ));
+            self.temperature = None;
+        }
+        if let Some(top_p) = self.top_p.filter(|p| !TOP_P_RANGE.contains(p)) {
+            warnings.push(format!(
+                "[ai] top_p must be between 0.0 and 1.0 (got {top_p}); using the provider default"
+            ));
+            self.top_p = None;
+        }
+        if self.max_tokens == Some(0) {
+            warnings
+                .push("[ai] max_tokens must be at least 1; using the provider default".to_string());
+            self.max_tokens = None;
+        }
+
+        let mut names: Vec<&String> = self.profiles.keys().collect();
+        names.sort();
+        let invalid: Vec<String> = names
+            .into_iter()
+            .filter(|name| {
+                self.profiles[*name]
+                    .temperature
+                    .is_some_and(|t| !TEMPERATURE_RANGE.contains(&t))
+            })
+            .cloned()
+            .collect();
+        for name in invalid {
+            warnings.push(format!(
+                "[ai.profiles.{name}] temperature must be between 0.0 and 2.0; using [ai] temperature"
+            ));
+            if let Some(profile) = self.profiles.get_mut(&name) {
+                profile.temperature = None;
+            }
+        }
+        warnings
+    }
+}
+
+/// `temperature` の有効範囲
+const TEMPERATURE_RANGE: std::ops::RangeInclusive<f32> = 0.0..=2.0;
+
+/// `top_p` の有効範囲
+const TOP_P_RANGE: std::ops::RangeInclusive<f32> = 0.0..=1.0;
+
+/// `[ai] enabled_tools` の既定値（AI が使用できるすべてのツール）
+pub const DEFAULT_ENABLED_TOOLS: &[&str] = &[
+    "execute_shell_command",
+    "read_file",
+    "read_files",
+    "write_file",
+    "search_replace",
+    "get_project_tree",
+];
+
 impl Default for AiConfig {
     fn default() -> Self {
         Self {
             model: "gpt-4o".to_string(),
             max_rounds: 10,
             markdown_rendering: true,
+            code_highlight: true,
             ai_pipe_max_chars: 50_000,
             ai_redirect_max_chars: 50_000,
-            temperature: 0.5,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            show_usage: false,
+            command_notice: CommandNotice::default(),
+            verbosity: Verbosity::default(),
+            pager: String::new(),
+            farewell: false,
+            stream_idle_timeout_secs: 60,
+            edit_before_run: false,
+            preview_context: false,
+            type_ahead: TypeAhead::default(),
             ignore_auto_investigation_cmds: Vec::new(),
+            enabled_tools: DEFAULT_ENABLED_TOOLS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            show_git_diff_after_write: false,
+            cache: false,
+            cache_ttl_secs: 86400,
+            cache_max_entries: 500,
+            max_tool_output_lines: 200,
+            profile: String::new(),
+            profiles: HashMap::new(),
         }
     }
 }
 
+/// `[ai] command_notice` の表示レベル。
+///
+/// いずれのレベルでも、危険と判定したコマンド（`rm -rf` / `sudo` 等）は
+/// 安全のため必ず表示する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandNotice {
+    /// コマンドに加えて、AI が添えた実行理由も表示する
+    Verbose,
+    /// コマンドのみ表示する（既定）
+    #[default]
+    Brief,
+    /// 表示しない（危険なコマンドを除く）
+    Off,
+}
+
+impl std::fmt::Display for CommandNotice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            CommandNotice::Verbose => "verbose",
+            CommandNotice::Brief => "brief",
+            CommandNotice::Off => "off",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// `[ai] verbosity`: AI の回答の詳しさ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    /// ターミナル向けの短い回答（既定）
+    #[default]
+    Concise,
+    /// 背景や理由の説明を含む詳しい回答
+    Detailed,
+}
+
+impl std::fmt::Display for Verbosity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Verbosity::Concise => "concise",
+            Verbosity::Detailed => "detailed",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// `[shell] wrap_output`: ターミナル幅を超えるコマンド出力行の表示方法
+///
+/// pipe 経路（リダイレクト・パイプライン・PTY フォールバック）での表示にのみ適用し、
+/// キャプチャ（履歴・AI に渡す出力）は常に元の全文を保持する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputWrap {
+    /// 加工せずそのまま表示する（ターミナルの折り返しに任せる、既定）
+    #[default]
+    Off,
+    /// ターミナル幅で切り詰め、末尾を `…` にする
+    Truncate,
+}
+
+impl std::fmt::Display for OutputWrap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            OutputWrap::Off => "off",
+            OutputWrap::Truncate => "truncate",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// `[shell] url_action`: URL（`http://` / `https://`）だけが入力されたときの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlAction {
+    /// 自然言語として AI に委ね、何をするか尋ねさせる（既定）
+    #[default]
+    Ai,
+    /// `open`（macOS）/ `xdg-open`（その他）で開く
+    Open,
+    /// 実行せずにエラーを表示する
+    Error,
+}
+
+impl std::fmt::Display for UrlAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            UrlAction::Ai => "ai",
+            UrlAction::Open => "open",
+            UrlAction::Error => "error",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// `[ai] type_ahead`: AI 応答中に打鍵された入力の扱い。
+///
+/// いずれの場合も応答中の打鍵はエコーせず、Enter で次のコマンドが実行されることはない。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TypeAhead {
+    /// 次のプロンプトの入力行に反映する（改行はスペースに置き換える、既定）
+    #[default]
+    Keep,
+    /// 破棄する
+    Discard,
+}
+
+impl std::fmt::Display for TypeAhead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            TypeAhead::Keep => "keep",
+            TypeAhead::Discard => "discard",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// `[prompt] success_symbol` の既定値（テキスト表示を強制する異体字セレクタ付きの ✔）
+pub const DEFAULT_SUCCESS_SYMBOL: &str = "\u{2714}\u{fe0e}";
+
+/// `[prompt] error_symbol` の既定値
+pub const DEFAULT_ERROR_SYMBOL: &str = "\u{2717}";
+
 /// プロンプト表示の設定
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
@@ -104,17 +486,65 @@ pub struct PromptConfig {
     pub nerd_font: bool,
     /// Starship プロンプトを使用するか（要: starship コマンド + starship.toml）
     pub starship: bool,
+    /// 直前のコマンドが成功したときにプロンプト先頭に表示する記号（既定 `✔︎`）
+    pub success_symbol: String,
+    /// 直前のコマンドが失敗したときにプロンプト先頭に表示する記号（既定 `✗`）
+    pub error_symbol: String,
+    /// ターミナルタイトルに実行中のコマンド / カレントディレクトリを表示するか（既定 false）
+    pub set_title: bool,
+    /// 右プロンプトに並べるセグメント（`"time"` / `"battery"` / `"ssh"`、既定は時刻のみ）。
+    ///
+    /// 取得できないセグメントや未知の名前は表示時に省略する。
+    pub right_segments: Vec<String>,
+    /// 左プロンプトが端末幅に収まらないときの挙動（既定 `"shorten"`）
+    pub max_width_behavior: MaxWidthBehavior,
 }
 
+/// `[prompt] right_segments` の既定値（時刻のみ）
+pub const DEFAULT_RIGHT_SEGMENTS: &[&str] = &["time"];
+
 impl Default for PromptConfig {
     fn default() -> Self {
         Self {
             nerd_font: true,
             starship: false,
+            success_symbol: DEFAULT_SUCCESS_SYMBOL.to_string(),
+            error_symbol: DEFAULT_ERROR_SYMBOL.to_string(),
+            set_title: false,
+            right_segments: DEFAULT_RIGHT_SEGMENTS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            max_width_behavior: MaxWidthBehavior::default(),
         }
     }
 }
 
+/// `[prompt] max_width_behavior`: ビルトインプロンプトの 1 行目（CWD・git ブランチ等）と
+/// 右プロンプトが端末幅に収まらないときの挙動
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaxWidthBehavior {
+    /// CWD を中間省略（`~/a/.../project`）し、それでも収まらなければ右プロンプトを省く（既定）
+    #[default]
+    Shorten,
+    /// CWD はそのまま表示し、右プロンプトを省く
+    HideRight,
+    /// 何もしない（端末の折り返しに任せる）
+    Off,
+}
+
+impl std::fmt::Display for MaxWidthBehavior {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            MaxWidthBehavior::Shorten => "shorten",
+            MaxWidthBehavior::HideRight => "hide_right",
+            MaxWidthBehavior::Off => "off",
+        };
+        write!(f, "{s}")
+    }
+}
+
 /// 補完に関する設定
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
@@ -257,7 +687,204 @@ pub struct StartupConfig {
     pub commands: Vec<String>,
 }
 
+/// シェル本体の挙動に関する設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ShellConfig {
+    /// 引数なしの `exit` / `logout` や Ctrl-D で終了する前に確認するか
+    /// （既定 false: 確認なしで即終了）
+    pub confirm_exit: bool,
+    /// コマンドを実行せず、展開・パース後の最終的なコマンドを表示するだけにするか
+    /// （既定 false。起動フラグ `--dry-run` でも有効化できる）
+    pub dry_run: bool,
+    /// `cd` で移動に成功した後、`cd_hook` のコマンドを自動実行するか（既定 false）
+    pub auto_ls_on_cd: bool,
+    /// `auto_ls_on_cd` 有効時に `cd` 成功後に実行するコマンド（既定 `ls`）
+    pub cd_hook: String,
+    /// 既存ディレクトリのパスだけの入力（コマンドでないもの）を `cd` として扱うか（既定 false）
+    pub auto_cd: bool,
+    /// URL だけの入力（`https://example.com` 等）の扱い（既定 `ai`）
+    pub url_action: UrlAction,
+    /// `cd` にファイルを指定したとき、エラーにせず親ディレクトリへ移動するか（既定 false）
+    pub cd_to_file_parent: bool,
+    /// 出力リダイレクト（`>`, `>>`, `&>`, `&>>`）先の親ディレクトリが存在しなければ
+    /// 作成してから開くか（既定 false: bash と同様にエラー）
+    pub mkdir_on_redirect: bool,
+    /// ターミナル幅を超えるコマンド出力行の表示方法（既定 `off`）
+    pub wrap_output: OutputWrap,
+    /// 見つからないコマンドに編集距離 1 の候補が 1 つだけある場合、確認のうえ
+    /// 修正して実行するか（既定 false）
+    pub autocorrect: bool,
+    /// PTY 作成に失敗して pipe で出力を取得する際、`CLICOLOR_FORCE=1` / `FORCE_COLOR=1` を
+    /// 子プロセスに注入して色出力を維持するか（既定 false）
+    pub force_color: bool,
+    /// コマンドが非ゼロ終了したときにベル（`\x07`）を鳴らすか（既定 false）
+    pub bell_on_error: bool,
+    /// AI の応答が完了したときにベル（`\x07`）を鳴らすか（既定 false）
+    pub bell_on_ai_response: bool,
+    /// 外部コマンドの制限時間（秒）。超過したら `SIGTERM` → `SIGKILL` で終了させ、
+    /// 終了コード 124 とする。対話コマンド（PTY セッション）には適用しない（既定 0: 無効）
+    pub default_command_timeout: u64,
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        Self {
+            confirm_exit: false,
+            dry_run: false,
+            auto_ls_on_cd: false,
+            cd_hook: "ls".to_string(),
+            auto_cd: false,
+            url_action: UrlAction::default(),
+            cd_to_file_parent: false,
+            mkdir_on_redirect: false,
+            wrap_output: OutputWrap::default(),
+            autocorrect: false,
+            force_color: false,
+            bell_on_error: false,
+            bell_on_ai_response: false,
+            default_command_timeout: 0,
+        }
+    }
+}
+
+/// 行エディタ（reedline）の設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EditorConfig {
+    /// 入力中に履歴から fish 風のヒント（オートサジェスト）を表示するか（既定 true）。
+    /// エディタ構築時にのみ反映されるため、変更は再起動後に有効になる
+    pub hints: bool,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self { hints: true }
+    }
+}
+
+/// 起動・終了時のバナーの設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BannerConfig {
+    /// Welcome / Farewell を表示するか（false なら何も表示しない）
+    pub enabled: bool,
+    /// 組み込みのアスキーアートの代わりに表示するファイル（空文字列なら組み込み）
+    pub welcome_file: String,
+    /// 組み込みの終了メッセージの代わりに表示するファイル（空文字列なら組み込み）
+    pub goodbye_file: String,
+}
+
+impl Default for BannerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            welcome_file: String::new(),
+            goodbye_file: String::new(),
+        }
+    }
+}
+
+/// 環境変数の永続化の設定
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EnvConfig {
+    /// `export --persist` した変数を `~/.config/jarvish/env.toml`（パーミッション 0600）に保存し、
+    /// 起動時に読み込むか（既定 false）。`unset` した変数は保存ファイルからも削除する
+    pub persist: bool,
+}
+
+/// コマンド履歴の記録設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// コマンドごとに値のスナップショットを履歴に記録する環境変数名（既定は空 = 記録しない）。
+    /// 未設定の変数は記録しない。`history --env` で表示できる
+    pub track_env: Vec<String>,
+    /// コマンドを実行しなかった AI との会話（自然言語の質問と応答）を履歴に記録するか（既定 true）。
+    /// false にすると矢印キーの履歴にも残らない
+    pub record_ai_conversation: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            track_env: Vec::new(),
+            record_ai_conversation: true,
+        }
+    }
+}
+
+/// コマンド実行前後のフック設定（`[hooks]`）
+///
+/// 外部コマンドの実行前後に実行するコマンド。空文字列なら実行しない。
+/// フックには環境変数 `JARVISH_LAST_CMD`（実行するコマンド）と、`post_exec` のみ
+/// `JARVISH_LAST_EXIT`（終了コード）を渡す。フックの失敗はコマンド本体に影響しない
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// 外部コマンドの実行直前に実行するコマンド
+    pub pre_exec: String,
+    /// 外部コマンドの実行直後に実行するコマンド
+    pub post_exec: String,
+}
+
+/// ログ出力の設定
+///
+/// ログの初期化は設定ファイルの通常の読み込み（`Shell::new`）より前に行うため、
+/// [`JarvishConfig::load_logging`] でこのセクションだけを先に読む。変更は次回起動時に反映される。
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// ログレベル（`tracing_subscriber::EnvFilter` の書式。既定 `debug`）。
+    /// 環境変数 `JARVISH_LOG`（なければ `RUST_LOG`）が設定されていればそちらを優先する
+    pub level: String,
+    /// ログファイル（日次ローテーション）に出力するか（既定 true）
+    pub file: bool,
+    /// 標準エラー出力にも出力するか（既定 false）
+    pub stderr: bool,
+    /// 日次ローテーションで保持するログファイル数。古いものから削除する（0 で無制限、既定 0）
+    pub max_files: usize,
+    /// 起動時・日付の切り替わり時に、この日数より古いログファイルを削除する（0 で無効、既定 14）
+    pub retention_days: u64,
+    /// 1 ファイルのサイズ上限（MB）。超える前に `jarvish_YYYY-MM-DD.1.log` に退避して
+    /// 新しいファイルに切り替える（0 で無制限、既定 100）
+    pub max_file_size_mb: u64,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "debug".to_string(),
+            file: true,
+            stderr: false,
+            max_files: 0,
+            retention_days: 14,
+            max_file_size_mb: 100,
+        }
+    }
+}
+
+/// `[logging]` セクションだけを読むための部分設定
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct LoggingOnly {
+    logging: LoggingConfig,
+}
+
 impl JarvishConfig {
+    /// ログ初期化前に `[logging]` セクションだけを読み込む。
+    ///
+    /// ログがまだ使えないため、ファイルがない・読めない・パースできない場合は
+    /// 何も出力せずにデフォルト値を返す（エラーはその後の [`Self::load`] で報告される）。
+    pub fn load_logging() -> LoggingConfig {
+        std::fs::read_to_string(Self::config_path())
+            .ok()
+            .and_then(|content| toml::from_str::<LoggingOnly>(&content).ok())
+            .map(|partial| partial.logging)
+            .unwrap_or_default()
+    }
+
     /// 設定ファイルを読み込む。
     ///
     /// `~/.config/jarvish/config.toml` が存在すればパースし、
@@ -274,21 +901,77 @@ impl JarvishConfig {
         match std::fs::read_to_string(&path) {
             Ok(content) => match toml::from_str::<JarvishConfig>(&content) {
                 Ok(config) => {
+                    let config = config.validated();
                     info!(
                         path = %path.display(),
                         model = %config.ai.model,
                         max_rounds = config.ai.max_rounds,
                         markdown_rendering = config.ai.markdown_rendering,
+                        code_highlight = config.ai.code_highlight,
+                        show_usage = config.ai.show_usage,
+                        command_notice = %config.ai.command_notice,
+                        verbosity = %config.ai.verbosity,
+                        pager = %config.ai.pager,
+                        farewell = config.ai.farewell,
+                        stream_idle_timeout_secs = config.ai.stream_idle_timeout_secs,
+                        edit_before_run = config.ai.edit_before_run,
+                        preview_context = config.ai.preview_context,
+                        type_ahead = %config.ai.type_ahead,
                         ignore_auto_investigation_cmds = config.ai.ignore_auto_investigation_cmds.len(),
+                        enabled_tools = ?config.ai.enabled_tools,
+                        show_git_diff_after_write = config.ai.show_git_diff_after_write,
+                        cache = config.ai.cache,
+                        cache_ttl_secs = config.ai.cache_ttl_secs,
+                        cache_max_entries = config.ai.cache_max_entries,
+                        max_tool_output_lines = config.ai.max_tool_output_lines,
+                        temperature = ?config.ai.temperature,
+                        top_p = ?config.ai.top_p,
+                        max_tokens = ?config.ai.max_tokens,
+                        profile = %config.ai.profile,
+                        profiles = config.ai.profiles.len(),
                         alias_count = config.alias.len(),
                         export_count = config.export.len(),
                         nerd_font = config.prompt.nerd_font,
                         starship = config.prompt.starship,
+                        success_symbol = %config.prompt.success_symbol,
+                        error_symbol = %config.prompt.error_symbol,
+                        set_title = config.prompt.set_title,
+                        right_segments = ?config.prompt.right_segments,
+                        max_width_behavior = %config.prompt.max_width_behavior,
                         git_branch_commands = config.completion.git_branch_commands.len(),
                         completion_external = %config.completion.external,
                         completion_external_timeout_ms = config.completion.external_timeout_ms,
                         completion_external_zsh_daemon = config.completion.external_zsh_daemon,
                         startup_commands = config.startup.commands.len(),
+                        confirm_exit = config.shell.confirm_exit,
+                        dry_run = config.shell.dry_run,
+                        auto_ls_on_cd = config.shell.auto_ls_on_cd,
+                        cd_hook = %config.shell.cd_hook,
+                        auto_cd = config.shell.auto_cd,
+                        url_action = %config.shell.url_action,
+                        cd_to_file_parent = config.shell.cd_to_file_parent,
+                        mkdir_on_redirect = config.shell.mkdir_on_redirect,
+                        wrap_output = %config.shell.wrap_output,
+                        autocorrect = config.shell.autocorrect,
+                        force_color = config.shell.force_color,
+                        bell_on_error = config.shell.bell_on_error,
+                        bell_on_ai_response = config.shell.bell_on_ai_response,
+                        default_command_timeout = config.shell.default_command_timeout,
+                        editor_hints = config.editor.hints,
+                        banner_enabled = config.banner.enabled,
+                        banner_welcome_file = %config.banner.welcome_file,
+                        banner_goodbye_file = %config.banner.goodbye_file,
+                        env_persist = config.env.persist,
+                        history_track_env = ?config.history.track_env,
+                        history_record_ai_conversation = config.history.record_ai_conversation,
+                        hooks_pre_exec = %config.hooks.pre_exec,
+                        hooks_post_exec = %config.hooks.post_exec,
+                        logging_level = %config.logging.level,
+                        logging_file = config.logging.file,
+                        logging_stderr = config.logging.stderr,
+                        logging_max_files = config.logging.max_files,
+                        logging_retention_days = config.logging.retention_days,
+                        logging_max_file_size_mb = config.logging.max_file_size_mb,
                         "Config loaded successfully"
                     );
                     config
@@ -312,7 +995,8 @@ impl JarvishConfig {
         let content = std::fs::read_to_string(path)
             .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
         let config = toml::from_str::<JarvishConfig>(&content)
-            .map_err(|e| format!("failed to parse {}: {e}", path.display()))?;
+            .map_err(|e| format!("failed to parse {}: {e}", path.display()))?
+            .validated();
         info!(
             path = %path.display(),
             model = %config.ai.model,
@@ -323,6 +1007,15 @@ impl JarvishConfig {
         Ok(config)
     }
 
+    /// 範囲外の値を既定値に戻し、警告を表示する（設定ファイルは読み込みを継続する）。
+    fn validated(mut self) -> Self {
+        for warning in self.ai.validate() {
+            warn!(warning = %warning, "Invalid config value");
+            eprintln!("jarvish: warning: {warning}");
+        }
+        self
+    }
+
     /// 設定ファイルのパスを返す。
     pub fn config_path() -> PathBuf {
         std::env::var("HOME")
@@ -346,11 +1039,37 @@ mod tests {
         assert_eq!(config.ai.model, "gpt-4o");
         assert_eq!(config.ai.max_rounds, 10);
         assert!(config.ai.markdown_rendering);
+        assert!(config.ai.code_highlight);
+        assert!(!config.ai.show_usage);
+        assert_eq!(config.ai.command_notice, CommandNotice::Brief);
+        assert_eq!(config.ai.verbosity, Verbosity::Concise);
+        assert!(config.ai.pager.is_empty());
+        assert!(!config.ai.farewell);
+        assert_eq!(config.ai.stream_idle_timeout_secs, 60);
+        assert!(!config.ai.edit_before_run);
+        assert!(!config.ai.preview_context);
+        assert_eq!(config.ai.type_ahead, TypeAhead::Keep);
         assert!(config.ai.ignore_auto_investigation_cmds.is_empty());
+        assert_eq!(config.ai.enabled_tools, DEFAULT_ENABLED_TOOLS);
+        assert!(!config.ai.show_git_diff_after_write);
+        assert!(!config.ai.cache);
+        assert_eq!(config.ai.cache_ttl_secs, 86400);
+        assert_eq!(config.ai.cache_max_entries, 500);
+        assert_eq!(config.ai.max_tool_output_lines, 200);
+        assert_eq!(config.ai.temperature, None);
+        assert_eq!(config.ai.top_p, None);
+        assert_eq!(config.ai.max_tokens, None);
+        assert!(config.ai.profile.is_empty());
+        assert!(config.ai.profiles.is_empty());
         assert!(config.alias.is_empty());
         assert!(config.export.is_empty());
         assert!(config.prompt.nerd_font);
         assert!(!config.prompt.starship);
+        assert_eq!(config.prompt.success_symbol, DEFAULT_SUCCESS_SYMBOL);
+        assert_eq!(config.prompt.error_symbol, DEFAULT_ERROR_SYMBOL);
+        assert!(!config.prompt.set_title);
+        assert_eq!(config.prompt.right_segments, DEFAULT_RIGHT_SEGMENTS);
+        assert_eq!(config.prompt.max_width_behavior, MaxWidthBehavior::Shorten);
         assert!(config
             .completion
             .git_branch_commands
@@ -363,6 +1082,292 @@ mod tests {
         assert_eq!(config.completion.external, "auto");
         assert_eq!(config.completion.external_timeout_ms, 400);
         assert!(config.completion.external_zsh_daemon);
+        assert!(!config.shell.auto_ls_on_cd);
+        assert_eq!(config.shell.cd_hook, "ls");
+        assert!(!config.shell.auto_cd);
+        assert!(!config.shell.cd_to_file_parent);
+        assert!(!config.shell.mkdir_on_redirect);
+        assert_eq!(config.shell.wrap_output, OutputWrap::Off);
+        assert_eq!(config.shell.url_action, UrlAction::Ai);
+        assert!(!config.shell.autocorrect);
+        assert!(!config.shell.force_color);
+        assert!(!config.shell.bell_on_error);
+        assert!(!config.shell.bell_on_ai_response);
+        assert_eq!(config.shell.default_command_timeout, 0);
+        assert!(config.editor.hints);
+        assert!(config.banner.enabled);
+        assert!(config.banner.welcome_file.is_empty());
+        assert!(config.banner.goodbye_file.is_empty());
+        assert!(!config.env.persist);
+        assert!(config.history.track_env.is_empty());
+        assert!(config.history.record_ai_conversation);
+        assert!(config.hooks.pre_exec.is_empty());
+        assert!(config.hooks.post_exec.is_empty());
+        assert_eq!(config.logging, LoggingConfig::default());
+        assert_eq!(config.logging.level, "debug");
+        assert!(config.logging.file);
+        assert!(!config.logging.stderr);
+        assert_eq!(config.logging.max_files, 0);
+        assert_eq!(config.logging.retention_days, 14);
+        assert_eq!(config.logging.max_file_size_mb, 100);
+    }
+
+    #[test]
+    fn parse_prompt_symbols() {
+        let config =
+            load_from_str("[prompt]\nsuccess_symbol = \"[ok]\"\nerror_symbol = \"[FAIL]\"\n");
+        assert_eq!(config.prompt.success_symbol, "[ok]");
+        assert_eq!(config.prompt.error_symbol, "[FAIL]");
+        // 未指定の項目は既定値のまま
+        assert!(config.prompt.nerd_font);
+    }
+
+    #[test]
+    fn parse_prompt_set_title() {
+        let config = load_from_str("[prompt]\nset_title = true\n");
+        assert!(config.prompt.set_title);
+        assert!(!config.prompt.starship);
+    }
+
+    #[test]
+    fn parse_prompt_right_segments() {
+        let config = load_from_str("[prompt]\nright_segments = [\"ssh\", \"battery\", \"time\"]\n");
+        assert_eq!(config.prompt.right_segments, ["ssh", "battery", "time"]);
+        assert!(load_from_str("[prompt]\nright_segments = []\n")
+            .prompt
+            .right_segments
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_prompt_max_width_behavior() {
+        let config = load_from_str("[prompt]\nmax_width_behavior = \"hide_right\"\n");
+        assert_eq!(
+            config.prompt.max_width_behavior,
+            MaxWidthBehavior::HideRight
+        );
+        let config = load_from_str("[prompt]\nmax_width_behavior = \"off\"\n");
+        assert_eq!(config.prompt.max_width_behavior, MaxWidthBehavior::Off);
+    }
+
+    #[test]
+    fn parse_cd_hook_config() {
+        let config = load_from_str("[shell]\nauto_ls_on_cd = true\ncd_hook = \"ls --color\"\n");
+        assert!(config.shell.auto_ls_on_cd);
+        assert_eq!(config.shell.cd_hook, "ls --color");
+    }
+
+    #[test]
+    fn parse_wrap_output_truncate() {
+        let config = load_from_str("[shell]\nwrap_output = \"truncate\"\n");
+        assert_eq!(config.shell.wrap_output, OutputWrap::Truncate);
+    }
+
+    #[test]
+    fn parse_url_action() {
+        let config = load_from_str("[shell]\nurl_action = \"open\"\n");
+        assert_eq!(config.shell.url_action, UrlAction::Open);
+        assert_eq!(config.shell.url_action.to_string(), "open");
+        let config = load_from_str("[shell]\nurl_action = \"error\"\n");
+        assert_eq!(config.shell.url_action, UrlAction::Error);
+        assert!(toml::from_str::<JarvishConfig>("[shell]\nurl_action = \"browse\"\n").is_err());
+    }
+
+    #[test]
+    fn parse_autocorrect_enabled() {
+        let config = load_from_str("[shell]\nautocorrect = true\n");
+        assert!(config.shell.autocorrect);
+    }
+
+    #[test]
+    fn parse_logging_section() {
+        let config = load_from_str(
+            "[logging]\nlevel = \"info\"\nfile = false\nstderr = true\nmax_files = 7\n\
+             retention_days = 30\nmax_file_size_mb = 0\n",
+        );
+        assert_eq!(config.logging.level, "info");
+        assert!(!config.logging.file);
+        assert!(config.logging.stderr);
+        assert_eq!(config.logging.max_files, 7);
+        assert_eq!(config.logging.retention_days, 30);
+        assert_eq!(config.logging.max_file_size_mb, 0);
+    }
+
+    #[test]
+    fn logging_section_parses_despite_errors_elsewhere() {
+        let partial: LoggingOnly =
+            toml::from_str("[ai]\nmax_rounds = \"many\"\n\n[logging]\nlevel = \"warn\"\n").unwrap();
+        assert_eq!(partial.logging.level, "warn");
+    }
+
+    #[test]
+    fn parse_auto_cd_enabled() {
+        let config = load_from_str("[shell]\nauto_cd = true\n");
+        assert!(config.shell.auto_cd);
+    }
+
+    #[test]
+    fn parse_cd_to_file_parent_enabled() {
+        let config = load_from_str("[shell]\ncd_to_file_parent = true\n");
+        assert!(config.shell.cd_to_file_parent);
+    }
+
+    #[test]
+    fn parse_mkdir_on_redirect_enabled() {
+        let config = load_from_str("[shell]\nmkdir_on_redirect = true\n");
+        assert!(config.shell.mkdir_on_redirect);
+    }
+
+    #[test]
+    fn parse_default_command_timeout() {
+        let config = load_from_str("[shell]\ndefault_command_timeout = 30\n");
+        assert_eq!(config.shell.default_command_timeout, 30);
+    }
+
+    #[test]
+    fn parse_force_color_enabled() {
+        let config = load_from_str("[shell]\nforce_color = true\n");
+        assert!(config.shell.force_color);
+    }
+
+    #[test]
+    fn parse_bell_settings() {
+        let config = load_from_str("[shell]\nbell_on_error = true\nbell_on_ai_response = true\n");
+        assert!(config.shell.bell_on_error);
+        assert!(config.shell.bell_on_ai_response);
+    }
+
+    #[test]
+    fn parse_env_persist_enabled() {
+        let config = load_from_str("[env]\npersist = true\n");
+        assert!(config.env.persist);
+    }
+
+    #[test]
+    fn parse_code_highlight_disabled() {
+        let config = load_from_str("[ai]\ncode_highlight = false\n");
+        assert!(!config.ai.code_highlight);
+        assert!(config.ai.markdown_rendering);
+    }
+
+    #[test]
+    fn parse_history_record_ai_conversation() {
+        let config = load_from_str("[history]\nrecord_ai_conversation = false\n");
+        assert!(!config.history.record_ai_conversation);
+        assert!(config.history.track_env.is_empty());
+    }
+
+    #[test]
+    fn parse_history_track_env() {
+        let config = load_from_str("[history]\ntrack_env = [\"VIRTUAL_ENV\", \"NODE_ENV\"]\n");
+        assert_eq!(config.history.track_env, vec!["VIRTUAL_ENV", "NODE_ENV"]);
+    }
+
+    #[test]
+    fn parse_hooks() {
+        let config = load_from_str("[hooks]\npre_exec = \"echo start\"\n");
+        assert_eq!(config.hooks.pre_exec, "echo start");
+        assert!(config.hooks.post_exec.is_empty());
+    }
+
+    #[test]
+    fn parse_editor_hints_disabled() {
+        let config = load_from_str("[editor]\nhints = false\n");
+        assert!(!config.editor.hints);
+    }
+
+    #[test]
+    fn parse_banner_config() {
+        let config = load_from_str(
+            "[banner]\nenabled = false\nwelcome_file = \"~/welcome.txt\"\ngoodbye_file = \"/etc/bye\"\n",
+        );
+        assert!(!config.banner.enabled);
+        assert_eq!(config.banner.welcome_file, "~/welcome.txt");
+        assert_eq!(config.banner.goodbye_file, "/etc/bye");
+    }
+
+    #[test]
+    fn parse_max_tool_output_lines() {
+        let config = load_from_str("[ai]\nmax_tool_output_lines = 50\n");
+        assert_eq!(config.ai.max_tool_output_lines, 50);
+        assert_eq!(config.ai.max_rounds, 10);
+    }
+
+    #[test]
+    fn parse_sampling_parameters() {
+        let mut config = load_from_str("[ai]\ntemperature = 0.2\ntop_p = 0.9\nmax_tokens = 2048\n");
+        assert!(config.ai.validate().is_empty());
+        assert_eq!(config.ai.temperature, Some(0.2));
+        assert_eq!(config.ai.top_p, Some(0.9));
+        assert_eq!(config.ai.max_tokens, Some(2048));
+    }
+
+    #[test]
+    fn validate_resets_out_of_range_sampling_parameters() {
+        let mut config = load_from_str(
+            "[ai]\ntemperature = 3.0\ntop_p = 1.5\nmax_tokens = 0\n\
+             [ai.profiles.hot]\ntemperature = -1.0\n",
+        );
+        let warnings = config.ai.validate();
+        assert_eq!(warnings.len(), 4, "{warnings:?}");
+        assert_eq!(config.ai.temperature, None);
+        assert_eq!(config.ai.top_p, None);
+        assert_eq!(config.ai.max_tokens, None);
+        assert_eq!(config.ai.profiles["hot"].temperature, None);
+    }
+
+    #[test]
+    fn parse_ai_profiles() {
+        let config = load_from_str(
+            "[ai]\nmodel = \"gpt-4o\"\nprofile = \"fast\"\n\n\
+             [ai.profiles.fast]\nmodel = \"gpt-4o-mini\"\ntemperature = 0.2\n\n\
+             [ai.profiles.smart]\nmax_rounds = 20\n",
+        );
+        assert_eq!(config.ai.profile, "fast");
+        assert_eq!(config.ai.profiles.len(), 2);
+
+        let fast = config.ai.with_profile("fast").unwrap();
+        assert_eq!(fast.model, "gpt-4o-mini");
+        assert_eq!(fast.temperature, Some(0.2));
+        assert_eq!(fast.max_rounds, 10);
+
+        // 省略した項目は [ai] の値を使う
+        let smart = config.ai.with_profile("smart").unwrap();
+        assert_eq!(smart.model, "gpt-4o");
+        assert_eq!(smart.max_rounds, 20);
+
+        assert!(config.ai.with_profile("missing").is_none());
+    }
+
+    #[test]
+    fn parse_enabled_tools_read_only() {
+        let config =
+            load_from_str("[ai]\nenabled_tools = [\"execute_shell_command\", \"read_file\"]\n");
+        assert_eq!(
+            config.ai.enabled_tools,
+            vec!["execute_shell_command", "read_file"]
+        );
+        // 空にするとツールをすべて無効にする
+        assert!(load_from_str("[ai]\nenabled_tools = []\n")
+            .ai
+            .enabled_tools
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_type_ahead_discard() {
+        let config = load_from_str("[ai]\ntype_ahead = \"discard\"\n");
+        assert_eq!(config.ai.type_ahead, TypeAhead::Discard);
+        assert_eq!(config.ai.type_ahead.to_string(), "discard");
+        assert!(toml::from_str::<JarvishConfig>("[ai]\ntype_ahead = \"replay\"\n").is_err());
+    }
+
+    #[test]
+    fn parse_command_notice_off() {
+        let config = load_from_str("[ai]\ncommand_notice = \"off\"\n");
+        assert_eq!(config.ai.command_notice, CommandNotice::Off);
+        assert_eq!(config.ai.command_notice.to_string(), "off");
+        assert!(toml::from_str::<JarvishConfig>("[ai]\ncommand_notice = \"loud\"\n").is_err());
     }
 
     #[test]
@@ -372,7 +1377,19 @@ mod tests {
 model = "gpt-4o-mini"
 max_rounds = 5
 markdown_rendering = false
+show_usage = true
+command_notice = "verbose"
+verbosity = "detailed"
+pager = "less -R"
+farewell = true
+stream_idle_timeout_secs = 0
+edit_before_run = true
+preview_context = true
 ignore_auto_investigation_cmds = ["git log", "git diff"]
+show_git_diff_after_write = true
+cache = true
+cache_ttl_secs = 3600
+cache_max_entries = 0
 
 [alias]
 g = "git"
@@ -384,20 +1401,34 @@ EDITOR = "vim"
 [prompt]
 nerd_font = false
 starship = true
+max_width_behavior = "off"
 "#;
         let config = load_from_str(toml);
         assert_eq!(config.ai.model, "gpt-4o-mini");
         assert_eq!(config.ai.max_rounds, 5);
         assert!(!config.ai.markdown_rendering);
+        assert!(config.ai.show_usage);
+        assert_eq!(config.ai.command_notice, CommandNotice::Verbose);
+        assert_eq!(config.ai.verbosity, Verbosity::Detailed);
+        assert_eq!(config.ai.pager, "less -R");
+        assert!(config.ai.farewell);
+        assert_eq!(config.ai.stream_idle_timeout_secs, 0);
+        assert!(config.ai.edit_before_run);
+        assert!(config.ai.preview_context);
         assert_eq!(
             config.ai.ignore_auto_investigation_cmds,
             vec!["git log", "git diff"]
         );
+        assert!(config.ai.show_git_diff_after_write);
+        assert!(config.ai.cache);
+        assert_eq!(config.ai.cache_ttl_secs, 3600);
+        assert_eq!(config.ai.cache_max_entries, 0);
         assert_eq!(config.alias.get("g").unwrap(), "git");
         assert_eq!(config.alias.get("ll").unwrap(), "ls -la");
         assert_eq!(config.export.get("EDITOR").unwrap(), "vim");
         assert!(!config.prompt.nerd_font);
         assert!(config.prompt.starship);
+        assert_eq!(config.prompt.max_width_behavior, MaxWidthBehavior::Off);
     }
 
     #[test]
@@ -435,6 +1466,35 @@ ignore_auto_investigation_cmds = ["git"]
         assert_eq!(config.ai.ignore_auto_investigation_cmds, vec!["git"]);
     }
 
+    #[test]
+    fn shell_confirm_exit_defaults_to_false() {
+        let config = load_from_str("");
+        assert!(!config.shell.confirm_exit);
+        assert!(!config.shell.dry_run);
+    }
+
+    #[test]
+    fn parse_shell_confirm_exit() {
+        let toml = r#"
+[shell]
+confirm_exit = true
+"#;
+        let config = load_from_str(toml);
+        assert!(config.shell.confirm_exit);
+        assert!(!config.shell.dry_run);
+    }
+
+    #[test]
+    fn parse_shell_dry_run() {
+        let toml = r#"
+[shell]
+dry_run = true
+"#;
+        let config = load_from_str(toml);
+        assert!(config.shell.dry_run);
+        assert!(!config.shell.confirm_exit);
+    }
+
     #[test]
     fn config_path_contains_expected_components() {
         let path = JarvishConfig::config_path();
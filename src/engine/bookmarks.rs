@@ -0,0 +1,156 @@
+//! ディレクトリのブックマーク
+//!
+//! `bookmark add <name> [dir]` で名前を付けたディレクトリを `~/.config/jarvish/bookmarks.toml`
+//! に保存し、`cd @name`（`cd @name/sub` も可）または `jump name` で移動できるようにする。
+//! `cd @<Tab>` ではブックマーク名を補完候補に出す。
+//!
+//! ファイル形式:
+//!
+//! ```toml
+//! dotfiles = "/home/user/dotfiles"
+//! work = "/home/user/src/project"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tracing::debug;
+
+/// 保存ファイルのデフォルトパスを返す。
+///
+/// `JarvishConfig::config_path()` と同じく `$HOME/.config/jarvish/` 配下に置く。
+pub fn default_path() -> PathBuf {
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join(".config/jarvish/bookmarks.toml")
+}
+
+/// ブックマーク名として使えるかを検証する（英数字と `_` `-` `.` のみ）。
+///
+/// `cd @name/sub` の区切りと衝突しないよう `/` は許可しない。
+pub(crate) fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("bookmark name must not be empty".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(format!(
+            "{name}: invalid bookmark name (use letters, digits, '_', '-' or '.')"
+        ));
+    }
+    Ok(())
+}
+
+/// 保存ファイルを読み込む。存在しない場合は空のマップを返す。
+pub(crate) fn load_from(path: &Path) -> Result<BTreeMap<String, PathBuf>, String> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    toml::from_str(&content).map_err(|e| format!("failed to parse {}: {e}", path.display()))
+}
+
+/// 保存ファイルを書き出す（親ディレクトリがなければ作成する）。
+fn save_to(path: &Path, bookmarks: &BTreeMap<String, PathBuf>) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("failed to create {}: {e}", parent.display()))?;
+    }
+    let content =
+        toml::to_string(bookmarks).map_err(|e| format!("failed to serialize bookmarks: {e}"))?;
+    std::fs::write(path, content).map_err(|e| format!("failed to write {}: {e}", path.display()))
+}
+
+/// ブックマークを追加（同名の既存ブックマークは上書き）する。
+pub(crate) fn add(path: &Path, name: &str, dir: &Path) -> Result<(), String> {
+    validate_name(name)?;
+    let mut bookmarks = load_from(path)?;
+    bookmarks.insert(name.to_string(), dir.to_path_buf());
+    save_to(path, &bookmarks)?;
+    debug!(path = %path.display(), name = %name, dir = %dir.display(), "Bookmark added");
+    Ok(())
+}
+
+/// ブックマークを削除する。存在しなかった場合は `Ok(false)`。
+pub(crate) fn remove(path: &Path, name: &str) -> Result<bool, String> {
+    let mut bookmarks = load_from(path)?;
+    if bookmarks.remove(name).is_none() {
+        return Ok(false);
+    }
+    save_to(path, &bookmarks)?;
+    debug!(path = %path.display(), name = %name, "Bookmark removed");
+    Ok(true)
+}
+
+/// `@name` / `@name/sub` 形式の引数をブックマークのディレクトリに解決する。
+///
+/// `@` で始まらない引数や未登録の名前は `None`。読み込みに失敗した場合も `None` とし、
+/// 呼び出し元（`cd`）は引数をそのままパスとして扱う。
+pub(crate) fn resolve(path: &Path, arg: &str) -> Option<PathBuf> {
+    let rest = arg.strip_prefix('@')?;
+    let (name, sub) = match rest.split_once('/') {
+        Some((name, sub)) => (name, Some(sub)),
+        None => (rest, None),
+    };
+    let dir = load_from(path).ok()?.remove(name)?;
+    Some(match sub {
+        Some(sub) if !sub.is_empty() => dir.join(sub),
+        _ => dir,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_resolve_and_remove_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("nested/bookmarks.toml");
+
+        add(&path, "work", Path::new("/srv/project")).unwrap();
+        add(&path, "dots", Path::new("/home/user/dotfiles")).unwrap();
+        add(&path, "work", Path::new("/srv/other")).unwrap();
+
+        let bookmarks = load_from(&path).unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(resolve(&path, "@work"), Some(PathBuf::from("/srv/other")));
+        assert_eq!(
+            resolve(&path, "@work/src/lib"),
+            Some(PathBuf::from("/srv/other/src/lib"))
+        );
+        assert_eq!(resolve(&path, "@work/"), Some(PathBuf::from("/srv/other")));
+        assert_eq!(resolve(&path, "@missing"), None);
+        assert_eq!(resolve(&path, "work"), None);
+
+        assert!(remove(&path, "work").unwrap());
+        assert!(!remove(&path, "work").unwrap());
+        assert_eq!(resolve(&path, "@work"), None);
+        assert_eq!(
+            resolve(&path, "@dots"),
+            Some(PathBuf::from("/home/user/dotfiles"))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        for valid in ["work", "my-proj", "v1.2_x"] {
+            assert!(validate_name(valid).is_ok(), "{valid}");
+        }
+        for invalid in ["", "a/b", "@work", "two words", "~"] {
+            assert!(validate_name(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn missing_file_loads_empty() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(load_from(&tmp.path().join("bookmarks.toml"))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+//! ai ビルトイン — AI プロファイル（`[ai.profiles.<name>]`）の切り替え
+//!
+//! 現在のプロファイルは `Shell` が保持するため、`Shell` のビルトインインターセプト
+//! （`try_shell_builtins`）からのみ実行できる。
+//!
+//! `ai explain this error` のような AI への呼びかけと衝突しないよう、ビルトインとして扱うのは
+//! 引数なしの `ai` と `ai use <name>` / `ai profiles` / `ai --help` の形（[`is_profile_command`]）に限る。
+
+use clap::{Parser, Subcommand};
+
+use crate::config::AiConfig;
+use crate::engine::CommandResult;
+
+/// プロファイルを使わず `[ai]` の値をそのまま使うことを表す名前
+/// （同名のプロファイルが定義されていればそちらを使う）
+pub(crate) const DEFAULT_PROFILE: &str = "default";
+
+/// ai: AI プロファイルを切り替える・一覧表示する。
+#[derive(Parser)]
+#[command(
+    name = "ai",
+    about = "Switch or list AI profiles ([ai.profiles.<name>])"
+)]
+struct AiArgs {
+    #[command(subcommand)]
+    command: Option<AiCommand>,
+}
+
+#[derive(Subcommand)]
+enum AiCommand {
+    /// Switch to a profile (`default` uses the plain [ai] settings)
+    Use {
+        /// Profile name defined as [ai.profiles.<name>]
+        name: String,
+    },
+    /// List the configured profiles (default when no subcommand is given)
+    Profiles,
+}
+
+/// `ai` に続く引数がプロファイル操作か（引数なし / `use <name>` / `profiles` / `--help`）。
+///
+/// 引数なしの `ai` は `ai profiles` と同じく一覧を表示する。
+/// それ以外の `ai ...` はビルトインとして扱わず、従来どおり AI への入力とする。
+pub(crate) fn is_profile_command(args: &[&str]) -> bool {
+    matches!(args, [] | ["use", _] | ["profiles"] | ["--help" | "-h"])
+}
+
+/// `try_shell_builtins` から呼ばれる本体。
+///
+/// `current` は現在のプロファイル名（`None` は `[ai]` の値そのまま）で、
+/// `ai use` が成功した場合のみ書き換える。AI クライアントへの反映は呼び出し元が行う。
+pub(crate) fn execute_with_profiles(
+    args: &[&str],
+    ai_config: &AiConfig,
+    current: &mut Option<String>,
+) -> CommandResult {
+    let parsed = match super::parse_args::<AiArgs>("ai", args) {
+        Ok(a) => a,
+        Err(result) => return result,
+    };
+
+    match parsed.command {
+        None | Some(AiCommand::Profiles) => list_profiles(ai_config, current.as_deref()),
+        Some(AiCommand::Use { name }) => use_profile(&name, ai_config, current),
+    }
+}
+
+/// `dispatch_builtin` 経由（パイプライン内等）で呼ばれたときのエントリポイント。
+///
+/// `Shell` の現在のプロファイルにアクセスできないため、`--help` 以外はエラーにする。
+pub(crate) fn execute_standalone_only(args: &[&str]) -> CommandResult {
+    if args == ["--help"] || args == ["-h"] {
+        return execute_with_profiles(args, &AiConfig::default(), &mut None);
+    }
+    let msg = "jarvish: ai: can only be used as a standalone command\n".to_string();
+    eprint!("{msg}");
+    CommandResult::error(msg, 1)
+}
+
+/// プロファイルを切り替える。
+fn use_profile(name: &str, ai_config: &AiConfig, current: &mut Option<String>) -> CommandResult {
+    let (profile, config) = match ai_config.with_profile(name) {
+        Some(config) => (Some(name.to_string()), config),
+        None if name == DEFAULT_PROFILE => (None, ai_config.clone()),
+        None => {
+            let msg = format!("jarvish: ai: no such profile: {name}\n");
+            eprint!("{msg}");
+            return CommandResult::error(msg, 1);
+        }
+    };
+    *current = profile;
+
+    let output = format!(
+        "AI profile: {name} ({})\n",
+        describe(&config.model, config.max_rounds, config.temperature)
+    );
+    CommandResult::success(output)
+}
+
+/// プロファイルの一覧を表示する（現在のプロファイルに `*` を付ける）。
+fn list_profiles(ai_config: &AiConfig, current: Option<&str>) -> CommandResult {
+    let mut rows: Vec<(String, AiConfig)> = Vec::new();
+    if !ai_config.profiles.contains_key(DEFAULT_PROFILE) {
+        rows.push((DEFAULT_PROFILE.to_string(), ai_config.clone()));
+    }
+    let mut names: Vec<&String> = ai_config.profiles.keys().collect();
+    names.sort();
+    for name in names {
+        if let Some(config) = ai_config.with_profile(name) {
+            rows.push((name.clone(), config));
+        }
+    }
+
+    let current = current.unwrap_or(DEFAULT_PROFILE);
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut output = String::from("AI profiles:\n");
+    for (name, config) in &rows {
+        let marker = if name == current { '*' } else { ' ' };
+        output.push_str(&format!(
+            "{marker} {name:<width$}  {}\n",
+            describe(&config.model, config.max_rounds, config.temperature)
+        ));
+    }
+
+    CommandResult::success(output)
+}
+
+fn describe(model: &str, max_rounds: usize, temperature: Option<f32>) -> String {
+    let temperature = temperature.map_or_else(|| "default".to_string(), |t| t.to_string());
+    format!("model: {model}, max_rounds: {max_rounds}, temperature: {temperature}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AiProfile;
+
+    fn config_with_fast() -> AiConfig {
+        let mut config = AiConfig::default();
+        config.profiles.insert(
+            "fast".to_string(),
+            AiProfile {
+                model: Some("gpt-4o-mini".to_string()),
+                max_rounds: Some(3),
+                temperature: None,
+            },
+        );
+        config
+    }
+
+    #[test]
+    fn use_switches_profile_and_back_to_default() {
+        let config = config_with_fast();
+        let mut current = None;
+
+        let result = execute_with_profiles(&["use", "fast"], &config, &mut current);
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(current.as_deref(), Some("fast"));
+        assert!(result
+            .stdout
+            .contains("model: gpt-4o-mini, max_rounds: 3, temperature: default"));
+
+        let result = execute_with_profiles(&["use", "default"], &config, &mut current);
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(current, None);
+    }
+
+    #[test]
+    fn use_unknown_profile_keeps_current() {
+        let config = config_with_fast();
+        let mut current = Some("fast".to_string());
+
+        let result = execute_with_profiles(&["use", "smart"], &config, &mut current);
+        assert_eq!(result.exit_code, 1);
+        assert!(result.stderr.contains("no such profile: smart"));
+        assert_eq!(current.as_deref(), Some("fast"));
+    }
+
+    #[test]
+    fn list_marks_current_profile() {
+        let config = config_with_fast();
+
+        let result = execute_with_profiles(&[], &config, &mut Some("fast".to_string()));
+        assert_eq!(result.exit_code, 0);
+        assert!(result.stdout.contains("  default  model: gpt-4o,"));
+        assert!(result.stdout.contains("* fast     model: gpt-4o-mini,"));
+    }
+
+    #[test]
+    fn only_exact_subcommands_are_profile_commands() {
+        assert!(is_profile_command(&["use", "fast"]));
+        assert!(is_profile_command(&["profiles"]));
+        assert!(is_profile_command(&["--help"]));
+
+        assert!(is_profile_command(&[]));
+        assert!(!is_profile_command(&["explain", "this", "error"]));
+        assert!(!is_profile_command(&["why did this fail"]));
+        assert!(!is_profile_command(&["use", "git", "to", "undo", "this"]));
+        assert!(!is_profile_command(&["profiles", "please"]));
+    }
+
+    #[test]
+    fn standalone_only_allows_help() {
+        assert_eq!(execute_standalone_only(&["--help"]).exit_code, 0);
+        assert_eq!(execute_standalone_only(&["use", "fast"]).exit_code, 1);
+    }
+}
@@ -49,7 +49,6 @@ pub(crate) fn execute_with_aliases(
             match aliases.get(assignment.as_str()) {
                 Some(value) => {
                     let line = format!("alias {assignment}='{value}'\n");
-                    print!("{line}");
                     output.push_str(&line);
                 }
                 None => {
@@ -74,7 +73,6 @@ fn list_all_aliases(aliases: &HashMap<String, String>) -> CommandResult {
         let line = format!("alias {name}='{value}'\n");
         output.push_str(&line);
     }
-    print!("{output}");
 
     CommandResult::success(output)
 }
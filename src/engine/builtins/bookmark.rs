@@ -0,0 +1,174 @@
+//! bookmark / jump: よく使うディレクトリに名前を付けて移動する
+//!
+//! - `bookmark add <name> [dir]`: `dir`（省略時はカレントディレクトリ）を `name` で登録
+//! - `bookmark list`（または引数なし）: 登録済みのブックマークを `name<TAB>path` で一覧表示
+//! - `bookmark remove <name>`: ブックマークを削除
+//! - `jump <name>`: `cd @name` と同じく登録ディレクトリへ移動
+//!
+//! 保存先は `~/.config/jarvish/bookmarks.toml`（[`bookmarks`] 参照）。
+//! `jump` は `dir_stack` を更新するため、`Shell::try_shell_builtins` 経由で実行する。
+
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+
+use crate::engine::bookmarks;
+use crate::engine::builtins::cd;
+use crate::engine::CommandResult;
+
+/// bookmark: ディレクトリのブックマークを管理する。
+#[derive(Parser)]
+#[command(name = "bookmark", about = "Add, list, or remove directory bookmarks")]
+struct BookmarkArgs {
+    #[command(subcommand)]
+    command: Option<BookmarkCommand>,
+}
+
+#[derive(Subcommand)]
+enum BookmarkCommand {
+    /// Bookmark a directory (defaults to the current directory)
+    Add {
+        /// Bookmark name (letters, digits, '_', '-' or '.')
+        name: String,
+        /// Directory to bookmark
+        dir: Option<String>,
+    },
+    /// List bookmarks
+    List,
+    /// Remove a bookmark
+    Remove {
+        /// Bookmark name
+        name: String,
+    },
+}
+
+/// jump: ブックマークしたディレクトリへ移動する。
+#[derive(Parser)]
+#[command(name = "jump", about = "Change to a bookmarked directory")]
+struct JumpArgs {
+    /// Bookmark name (same as `cd @name`)
+    name: String,
+}
+
+/// bookmark: `~/.config/jarvish/bookmarks.toml` のブックマークを操作する。
+pub(super) fn execute(args: &[&str]) -> CommandResult {
+    execute_with_path(args, &bookmarks::default_path())
+}
+
+fn execute_with_path(args: &[&str], path: &Path) -> CommandResult {
+    let parsed = match super::parse_args::<BookmarkArgs>("bookmark", args) {
+        Ok(a) => a,
+        Err(result) => return result,
+    };
+
+    match parsed.command.unwrap_or(BookmarkCommand::List) {
+        BookmarkCommand::Add { name, dir } => add(path, &name, dir.as_deref()),
+        BookmarkCommand::List => list(path),
+        BookmarkCommand::Remove { name } => match bookmarks::remove(path, &name) {
+            Ok(true) => CommandResult::success(String::new()),
+            Ok(false) => error("bookmark", format!("{name}: no such bookmark")),
+            Err(e) => error("bookmark", e),
+        },
+    }
+}
+
+/// `dir` を正規化してから登録する（相対パスで登録しても別の場所から移動できるように）。
+fn add(path: &Path, name: &str, dir: Option<&str>) -> CommandResult {
+    let dir = match dir {
+        Some(dir) => PathBuf::from(dir),
+        None => match std::env::current_dir() {
+            Ok(cwd) => cwd,
+            Err(e) => return error("bookmark", format!("cannot get current directory: {e}")),
+        },
+    };
+    let dir = match dir.canonicalize() {
+        Ok(dir) if dir.is_dir() => dir,
+        Ok(_) => return error("bookmark", format!("{}: Not a directory", dir.display())),
+        Err(e) => return error("bookmark", format!("{}: {e}", dir.display())),
+    };
+    match bookmarks::add(path, name, &dir) {
+        Ok(()) => CommandResult::success(String::new()),
+        Err(e) => error("bookmark", e),
+    }
+}
+
+fn list(path: &Path) -> CommandResult {
+    let entries = match bookmarks::load_from(path) {
+        Ok(entries) => entries,
+        Err(e) => return error("bookmark", e),
+    };
+    let output: String = entries
+        .iter()
+        .map(|(name, dir)| format!("{name}\t{}\n", dir.display()))
+        .collect();
+    CommandResult::success(output)
+}
+
+/// jump: `name` のブックマークへ `cd` する（成功時は移動前のディレクトリを `dir_stack` に積む）。
+///
+/// `to_file_parent` は `cd` と同じく `[shell] cd_to_file_parent` を渡す。
+pub(crate) fn execute_jump(
+    args: &[&str],
+    dir_stack: &mut Vec<PathBuf>,
+    to_file_parent: bool,
+) -> CommandResult {
+    let parsed = match super::parse_args::<JumpArgs>("jump", args) {
+        Ok(a) => a,
+        Err(result) => return result,
+    };
+    match bookmarks::resolve(&bookmarks::default_path(), &format!("@{}", parsed.name)) {
+        Some(dir) => cd::execute(&[&dir.to_string_lossy()], dir_stack, to_file_parent),
+        None => error("jump", format!("{}: no such bookmark", parsed.name)),
+    }
+}
+
+/// `jarvish: {cmd}: {msg}` を stderr に出力し、エラー結果を返す。
+fn error(cmd: &str, msg: String) -> CommandResult {
+    let msg = format!("jarvish: {cmd}: {msg}\n");
+    eprint!("{msg}");
+    CommandResult::error(msg, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_list_and_remove() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("bookmarks.toml");
+        let target = tmp.path().join("project");
+        std::fs::create_dir(&target).unwrap();
+
+        let result = execute_with_path(&["add", "work", target.to_str().unwrap()], &path);
+        assert_eq!(result.exit_code, 0);
+
+        let result = execute_with_path(&[], &path);
+        assert_eq!(
+            result.stdout,
+            format!("work\t{}\n", target.canonicalize().unwrap().display())
+        );
+
+        let result = execute_with_path(&["remove", "work"], &path);
+        assert_eq!(result.exit_code, 0);
+        let result = execute_with_path(&["list"], &path);
+        assert_eq!(result.stdout, "");
+
+        let result = execute_with_path(&["remove", "work"], &path);
+        assert_eq!(result.stderr, "jarvish: bookmark: work: no such bookmark\n");
+    }
+
+    #[test]
+    fn add_rejects_missing_directory_and_invalid_name() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("bookmarks.toml");
+
+        let missing = tmp.path().join("missing");
+        let result = execute_with_path(&["add", "work", missing.to_str().unwrap()], &path);
+        assert_eq!(result.exit_code, 1);
+
+        let result = execute_with_path(&["add", "a/b", tmp.path().to_str().unwrap()], &path);
+        assert_eq!(result.exit_code, 1);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,176 @@
+//! capture: コマンドの標準出力をシェル変数に取り込む
+//!
+//! `capture VAR=command args...` で `command args...` を実行し、stdout（末尾の改行を除く）を
+//! シェル変数 `VAR` に格納する。出力はターミナルに表示しない（stderr はそのまま表示する）。
+//! 終了コードは `capture` 自身の終了コードとして返す。
+//!
+//! コマンドは通常の入力と同じく `dispatch::execute()` で実行するため、
+//! エイリアス・ビルトイン・シェル変数の代入もそのまま使える。
+//! 実行コンテキストの `capture_stdout` を立て、出力は表示させずに `CommandResult.stdout` で受け取る。
+
+use std::collections::HashMap;
+
+use clap::Parser;
+
+use crate::engine::{dispatch, expand, shell_vars, CommandResult, ExecContext, LoopAction};
+
+/// capture: コマンドの出力をシェル変数に格納する。
+///
+/// clap には `VAR=command` だけを渡し、以降の引数はオプションとして解釈しない
+/// （`capture x=ls --help` の `--help` は `ls` の引数）。
+#[derive(Parser)]
+#[command(
+    name = "capture",
+    about = "Run a command and store its output in a shell variable",
+    override_usage = "capture <VAR=COMMAND> [ARGS]..."
+)]
+struct CaptureArgs {
+    /// Variable and command to run, as VAR=command
+    assignment: String,
+}
+
+/// capture: エイリアスなしで実行する（エンジンのディスパッチから呼ばれる場合）。
+pub(super) fn execute(args: &[&str], ctx: &ExecContext) -> CommandResult {
+    execute_with_aliases(args, &HashMap::new(), ctx)
+}
+
+/// capture: `VAR=command args...` を実行し、stdout を `VAR` に格納する。
+///
+/// - `command` がエイリアスなら展開する
+/// - 引数はシェルで展開済みのため、クォートして再展開させずにそのまま渡す
+/// - コマンドが失敗しても出力は格納し、終了コードをそのまま返す
+/// - ビルトインの出力も格納し、実行中は stdout をターミナルに表示しない
+///
+/// Shell 側から `&aliases` を渡して呼び出す。
+pub(crate) fn execute_with_aliases(
+    args: &[&str],
+    aliases: &HashMap<String, String>,
+    ctx: &ExecContext,
+) -> CommandResult {
+    let (parsed, command_args) = match super::parse_leading_args::<CaptureArgs>("capture", args, 1)
+    {
+        Ok(a) => a,
+        Err(result) => return result,
+    };
+
+    let Some((name, command)) = shell_vars::parse_assignment(&parsed.assignment) else {
+        return error(format!("{}: expected VAR=command", parsed.assignment));
+    };
+    if command.is_empty() {
+        return error(format!("{name}: missing command"));
+    }
+
+    let mut line = expand::expand_alias(command, aliases).unwrap_or_else(|| quote(command));
+    for arg in command_args {
+        line.push(' ');
+        line.push_str(&quote(arg));
+    }
+
+    let capture_ctx = ExecContext {
+        capture_stdout: true,
+        ..ctx.clone()
+    };
+    let result = dispatch::execute(&line, &capture_ctx);
+    ctx.variables
+        .assign(name, result.stdout.trim_end_matches('\n'));
+
+    // `capture x=exit` 等でもシェル自体は終了しない
+    CommandResult {
+        stdout: String::new(),
+        action: LoopAction::Continue,
+        ..result
+    }
+}
+
+/// 語をシングルクォートで囲み、ディスパッチで再展開・演算子解釈されないようにする。
+fn quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+/// `jarvish: capture: {msg}` を stderr に出力し、エラー結果を返す。
+fn error(msg: String) -> CommandResult {
+    let msg = format!("jarvish: capture: {msg}\n");
+    eprint!("{msg}");
+    CommandResult::error(msg, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn stores_stdout_without_trailing_newline() {
+        let ctx = ExecContext::default();
+        let result = execute(&["x=echo", "hello", "world"], &ctx);
+        assert_eq!(result.exit_code, 0);
+        assert!(result.stdout.is_empty());
+        assert_eq!(ctx.variables.lookup("x").as_deref(), Some("hello world"));
+    }
+
+    #[test]
+    fn passes_hyphen_args_and_exit_code_through() {
+        let ctx = ExecContext::default();
+        let result = execute(&["x=sh", "-c", "printf 'a\\n\\n'; exit 3"], &ctx);
+        assert_eq!(result.exit_code, 3);
+        assert_eq!(ctx.variables.lookup("x").as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn passes_help_flags_to_the_command() {
+        let ctx = ExecContext::default();
+        let result = execute(&["x=sh", "-c", "echo \"$1\"", "sh", "--help"], &ctx);
+        assert_eq!(result.exit_code, 0);
+        assert!(result.stdout.is_empty());
+        assert_eq!(ctx.variables.lookup("x").as_deref(), Some("--help"));
+
+        let result = execute(&["x=echo", "-h", "hi"], &ctx);
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(ctx.variables.lookup("x").as_deref(), Some("-h hi"));
+
+        // capture 自身のヘルプは先頭の --help のみ
+        let result = execute(&["--help"], &ctx);
+        assert!(result.stdout.contains("Usage: capture <VAR=COMMAND>"));
+    }
+
+    #[test]
+    #[serial]
+    fn runs_builtins_and_aliases_through_dispatch() {
+        let ctx = ExecContext::default();
+        let result = execute(&["x=cwd"], &ctx);
+        assert_eq!(result.exit_code, 0);
+        assert!(result.stdout.is_empty());
+        assert_eq!(ctx.variables.lookup("x"), std::env::var("PWD").ok());
+
+        let aliases = HashMap::from([("greet".to_string(), "echo hello".to_string())]);
+        let result = execute_with_aliases(&["x=greet", "a b;c"], &aliases, &ctx);
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(ctx.variables.lookup("x").as_deref(), Some("hello a b;c"));
+    }
+
+    #[test]
+    fn captures_builtin_output() {
+        let ctx = ExecContext::default();
+        let result = execute(&["x=help", "keys"], &ctx);
+        assert_eq!(result.exit_code, 0);
+        assert!(result.stdout.is_empty());
+        assert!(ctx
+            .variables
+            .lookup("x")
+            .is_some_and(|v| v.starts_with("Key bindings")));
+    }
+
+    #[test]
+    fn rejects_invalid_assignment() {
+        let ctx = ExecContext::default();
+        let result = execute(&["echo", "hello"], &ctx);
+        assert_eq!(result.exit_code, 1);
+        assert_eq!(
+            result.stderr,
+            "jarvish: capture: echo: expected VAR=command\n"
+        );
+
+        let result = execute(&["x="], &ctx);
+        assert_eq!(result.stderr, "jarvish: capture: x: missing command\n");
+    }
+}
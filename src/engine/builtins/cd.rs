@@ -1,9 +1,35 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 
-use crate::engine::CommandResult;
+use crate::engine::{bookmarks, CommandResult};
+
+/// ファイルを指すパスなら、移動先とする親ディレクトリを返す。
+///
+/// シンボリックリンクは辿ってファイルかどうかを判定するが、移動先はリンク先ではなく
+/// リンク自身が置かれたディレクトリとする（`ln -s` 元への移動は行わない）。
+/// 親が空（`file.txt` のような相対パス）の場合はカレントディレクトリ `.` を返す。
+fn file_parent(target: &Path) -> Option<PathBuf> {
+    if !target.is_file() {
+        return None;
+    }
+    match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => Some(parent.to_path_buf()),
+        _ => Some(PathBuf::from(".")),
+    }
+}
+
+/// `@name` / `@name/sub` 形式の引数をブックマークのディレクトリに解決する。
+///
+/// `@` で始まらない引数や、`@` で始まる名前のディレクトリが実在する場合はそのままパスとして扱う。
+fn resolve_bookmark(arg: &str, bookmarks_path: &Path) -> Result<PathBuf, String> {
+    if !arg.starts_with('@') || Path::new(arg).exists() {
+        return Ok(PathBuf::from(arg));
+    }
+    bookmarks::resolve(bookmarks_path, arg)
+        .ok_or_else(|| format!("jarvish: cd: {arg}: no such bookmark\n"))
+}
 
 /// cd: カレントディレクトリを変更する。
 #[derive(Parser)]
@@ -17,45 +43,76 @@ struct CdArgs {
 /// - 引数なし → `$HOME` へ移動
 /// - 引数あり → 指定パスへ移動
 ///   展開は execute 側で実施済み
+/// - `-` → `$OLDPWD` へ移動し、移動先を表示する（bash 互換）
+/// - `@name` / `@name/sub` → ブックマーク（`bookmark add`）のディレクトリへ移動
+/// - ファイル → `to_file_parent`（`[shell] cd_to_file_parent`）が真なら親ディレクトリへ移動し、
+///   移動先を表示する（偽なら従来通り `Not a directory` エラー）
 ///
 /// cd 成功時、変更前のカレントディレクトリを `dir_stack` に push する。
-pub(crate) fn execute(args: &[&str], dir_stack: &mut Vec<PathBuf>) -> CommandResult {
+pub(crate) fn execute(
+    args: &[&str],
+    dir_stack: &mut Vec<PathBuf>,
+    to_file_parent: bool,
+) -> CommandResult {
     let parsed = match super::parse_args::<CdArgs>("cd", args) {
         Ok(a) => a,
         Err(result) => return result,
     };
 
-    let target: PathBuf = if let Some(path) = parsed.path {
-        PathBuf::from(path)
-    } else {
-        // 引数なしの場合は $HOME へ
-        match env::var_os("HOME") {
-            Some(home) => PathBuf::from(home),
+    let mut print_target = parsed.path.as_deref() == Some("-");
+    let mut target: PathBuf = match parsed.path.as_deref() {
+        Some("-") => match env::var_os("OLDPWD") {
+            Some(old) => PathBuf::from(old),
             None => {
-                let msg = "jarvish: cd: HOME not set\n".to_string();
+                let msg = "jarvish: cd: OLDPWD not set\n".to_string();
                 eprint!("{msg}");
                 return CommandResult::error(msg, 1);
             }
+        },
+        Some(path) => match resolve_bookmark(path, &bookmarks::default_path()) {
+            Ok(target) => target,
+            Err(msg) => {
+                eprint!("{msg}");
+                return CommandResult::error(msg, 1);
+            }
+        },
+        None => {
+            // 引数なしの場合は $HOME へ
+            match env::var_os("HOME") {
+                Some(home) => PathBuf::from(home),
+                None => {
+                    let msg = "jarvish: cd: HOME not set\n".to_string();
+                    eprint!("{msg}");
+                    return CommandResult::error(msg, 1);
+                }
+            }
         }
     };
 
-    // 変更前の PWD を保存（OLDPWD 用）
-    let old_pwd = env::var("PWD").ok().or_else(|| {
-        env::current_dir()
-            .ok()
-            .map(|p| p.to_string_lossy().into_owned())
-    });
+    if to_file_parent {
+        if let Some(parent) = file_parent(&target) {
+            target = parent;
+            print_target = true;
+        }
+    }
+
+    // 変更前の PWD を保存（OLDPWD 用）。古い $PWD は使わず CWD と同期した値を使う
+    let old_pwd = super::cwd::sync_pwd().ok();
 
     match env::set_current_dir(&target) {
         Ok(()) => {
             if let Some(old) = old_pwd {
-                dir_stack.push(PathBuf::from(&old));
                 env::set_var("OLDPWD", &old);
+                dir_stack.push(old);
             }
+            let mut output = String::new();
             if let Ok(new_pwd) = env::current_dir() {
                 env::set_var("PWD", &new_pwd);
+                if print_target {
+                    output = format!("{}\n", new_pwd.display());
+                }
             }
-            CommandResult::success(String::new())
+            CommandResult::success(output)
         }
         Err(e) => {
             let msg = format!("jarvish: cd: {}: {e}\n", target.display());
@@ -81,7 +138,7 @@ mod tests {
         let tmpdir = tempfile::tempdir().expect("failed to create tempdir");
         let target = tmpdir.path().to_path_buf();
 
-        let result = execute(&[target.to_str().unwrap()], &mut Vec::new());
+        let result = execute(&[target.to_str().unwrap()], &mut Vec::new(), false);
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.action, LoopAction::Continue);
 
@@ -95,7 +152,7 @@ mod tests {
     fn cd_no_args_goes_home() {
         let _guard = CwdGuard::new();
         if let Some(home) = env::var_os("HOME") {
-            let result = execute(&[], &mut Vec::new());
+            let result = execute(&[], &mut Vec::new(), false);
             assert_eq!(result.exit_code, 0);
 
             let cwd = env::current_dir().unwrap();
@@ -110,14 +167,40 @@ mod tests {
     #[serial]
     fn cd_nonexistent_path_returns_error() {
         let _guard = CwdGuard::new();
-        let result = execute(&["/nonexistent_path_that_does_not_exist"], &mut Vec::new());
+        let result = execute(
+            &["/nonexistent_path_that_does_not_exist"],
+            &mut Vec::new(),
+            false,
+        );
         assert_ne!(result.exit_code, 0);
         assert!(result.stderr.contains("cd:"));
     }
 
+    #[test]
+    #[serial]
+    fn cd_dash_returns_to_previous_directory() {
+        let _guard = CwdGuard::new();
+        let first = tempfile::tempdir().expect("failed to create tempdir");
+        let second = tempfile::tempdir().expect("failed to create tempdir");
+        let mut stack = Vec::new();
+
+        execute(&[first.path().to_str().unwrap()], &mut stack, false);
+        execute(&[second.path().to_str().unwrap()], &mut stack, false);
+        let result = execute(&["-"], &mut stack, false);
+        assert_eq!(result.exit_code, 0);
+
+        let cwd = env::current_dir().unwrap();
+        assert_eq!(
+            cwd.canonicalize().unwrap(),
+            first.path().canonicalize().unwrap()
+        );
+        // 移動先のパスを表示する
+        assert!(!result.stdout.is_empty());
+    }
+
     #[test]
     fn cd_help_returns_success() {
-        let result = execute(&["--help"], &mut Vec::new());
+        let result = execute(&["--help"], &mut Vec::new(), false);
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("cd"));
     }
@@ -129,7 +212,7 @@ mod tests {
         let tmpdir = tempfile::tempdir().expect("failed to create tempdir");
         let target = tmpdir.path().to_path_buf();
 
-        let result = execute(&[target.to_str().unwrap()], &mut Vec::new());
+        let result = execute(&[target.to_str().unwrap()], &mut Vec::new(), false);
         assert_eq!(result.exit_code, 0);
 
         // $PWD が新しいディレクトリに更新されていること
@@ -151,7 +234,7 @@ mod tests {
         let tmpdir = tempfile::tempdir().expect("failed to create tempdir");
         let target = tmpdir.path().to_path_buf();
 
-        let result = execute(&[target.to_str().unwrap()], &mut Vec::new());
+        let result = execute(&[target.to_str().unwrap()], &mut Vec::new(), false);
         assert_eq!(result.exit_code, 0);
 
         // $OLDPWD が変更前のディレクトリを保持していること
@@ -161,4 +244,92 @@ mod tests {
             original_pwd.canonicalize().unwrap()
         );
     }
+
+    #[test]
+    #[serial]
+    fn cd_with_stale_pwd_sets_oldpwd_to_actual_directory() {
+        let _guard = CwdGuard::new();
+        let first = tempfile::tempdir().expect("failed to create tempdir");
+        let second = tempfile::tempdir().expect("failed to create tempdir");
+        env::set_current_dir(first.path()).unwrap();
+        env::set_var("PWD", "/nonexistent_stale_pwd");
+
+        let result = execute(&[second.path().to_str().unwrap()], &mut Vec::new(), false);
+        assert_eq!(result.exit_code, 0);
+
+        let oldpwd = env::var("OLDPWD").expect("OLDPWD should be set after cd");
+        assert_eq!(
+            PathBuf::from(&oldpwd).canonicalize().unwrap(),
+            first.path().canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn cd_to_file_errors_by_default() {
+        let _guard = CwdGuard::new();
+        let tmpdir = tempfile::tempdir().expect("failed to create tempdir");
+        let file = tmpdir.path().join("file.txt");
+        std::fs::write(&file, "x").unwrap();
+
+        let result = execute(&[file.to_str().unwrap()], &mut Vec::new(), false);
+        assert_ne!(result.exit_code, 0);
+        assert!(result.stderr.contains("cd:"));
+    }
+
+    #[test]
+    #[serial]
+    fn cd_to_file_moves_to_parent_when_enabled() {
+        let _guard = CwdGuard::new();
+        let tmpdir = tempfile::tempdir().expect("failed to create tempdir");
+        let sub = tmpdir.path().join("sub");
+        std::fs::create_dir(&sub).unwrap();
+        let file = sub.join("file.txt");
+        std::fs::write(&file, "x").unwrap();
+
+        let result = execute(&[file.to_str().unwrap()], &mut Vec::new(), true);
+        assert_eq!(result.exit_code, 0);
+        let cwd = env::current_dir().unwrap();
+        assert_eq!(cwd.canonicalize().unwrap(), sub.canonicalize().unwrap());
+        // 移動先の親ディレクトリを表示する
+        assert!(!result.stdout.is_empty());
+    }
+
+    #[test]
+    fn file_parent_keeps_symlink_location() {
+        let tmpdir = tempfile::tempdir().expect("failed to create tempdir");
+        let real_dir = tmpdir.path().join("real");
+        let link_dir = tmpdir.path().join("links");
+        std::fs::create_dir(&real_dir).unwrap();
+        std::fs::create_dir(&link_dir).unwrap();
+        let file = real_dir.join("file.txt");
+        std::fs::write(&file, "x").unwrap();
+        let link = link_dir.join("file.txt");
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        assert_eq!(file_parent(&link), Some(link_dir));
+        assert_eq!(file_parent(&real_dir), None);
+        assert_eq!(file_parent(Path::new("no_such_file.txt")), None);
+    }
+
+    #[test]
+    fn resolves_bookmark_arguments() {
+        let tmpdir = tempfile::tempdir().expect("failed to create tempdir");
+        let path = tmpdir.path().join("bookmarks.toml");
+        bookmarks::add(&path, "work", Path::new("/srv/project")).unwrap();
+
+        assert_eq!(
+            resolve_bookmark("@work", &path),
+            Ok(PathBuf::from("/srv/project"))
+        );
+        assert_eq!(
+            resolve_bookmark("@work/src", &path),
+            Ok(PathBuf::from("/srv/project/src"))
+        );
+        assert_eq!(resolve_bookmark("work", &path), Ok(PathBuf::from("work")));
+        assert_eq!(
+            resolve_bookmark("@missing", &path),
+            Err("jarvish: cd: @missing: no such bookmark\n".to_string())
+        );
+    }
 }
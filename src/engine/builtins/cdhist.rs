@@ -52,7 +52,6 @@ pub(crate) fn execute_with_db_path(args: &[&str], db_path: &Path) -> CommandResu
         output.push_str(dir);
         output.push('\n');
     }
-    print!("{output}");
 
     CommandResult::success(output)
 }
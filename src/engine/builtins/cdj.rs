@@ -97,7 +97,7 @@ fn pick_and_cd(filtered: Vec<String>, dir_stack: &mut Vec<PathBuf>) -> CommandRe
         }
         1 => {
             let target = filtered.into_iter().next().expect("len == 1");
-            cd::execute(&[target.as_str()], dir_stack)
+            cd::execute(&[target.as_str()], dir_stack, false)
         }
         _ => {
             let fzf = Fzf::new().enable_preview();
@@ -111,7 +111,7 @@ fn pick_and_cd(filtered: Vec<String>, dir_stack: &mut Vec<PathBuf>) -> CommandRe
             };
 
             match child.run(&filtered) {
-                Ok(Some(selected)) => cd::execute(&[selected.as_str()], dir_stack),
+                Ok(Some(selected)) => cd::execute(&[selected.as_str()], dir_stack, false),
                 Ok(None) => {
                     // キャンセル / no-match — 静かに終了 (cwd 不変、exit 130)
                     CommandResult::error(String::new(), 130)
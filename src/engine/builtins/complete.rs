@@ -68,7 +68,7 @@ struct CompleteArgs {
 /// すべて拒否**し、実データに触れず明確なエラーを返す。
 ///
 /// `--help` だけは `help complete`（`help.rs` が
-/// `dispatch_builtin(cmd, ["--help"])` に委譲する）で使われるため、
+/// `run_builtin(cmd, ["--help"])` に委譲する）で使われるため、
 /// 副作用なしに動き続ける必要がある。
 pub(crate) fn execute_standalone_only(args: &[&str]) -> CommandResult {
     if is_help_only(args) {
@@ -253,7 +253,6 @@ fn list_all(registry: &CompletionRegistry) -> CommandResult {
             output.push('\n');
         }
     }
-    print!("{output}");
     CommandResult::success(output)
 }
 
@@ -309,7 +308,7 @@ fn format_spec_line(command: &str, spec: &CompletionSpec) -> String {
 /// このシングルクォートラップは `split_quoted` の逆関数になる
 /// （改行 / NUL は `validate_round_trippable` により登録時点で既に拒否済み
 /// のため、ここでは扱わない）。
-fn quote_if_needed(value: &str) -> String {
+pub(crate) fn quote_if_needed(value: &str) -> String {
     if !value.is_empty() && value.chars().all(is_safe_unquoted_char) {
         return value.to_string();
     }
@@ -1,24 +1,48 @@
 use std::env;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path, PathBuf};
 
 use clap::Parser;
 
+use crate::cli::prompt::shorten_path;
 use crate::engine::CommandResult;
 
 /// cwd: 現在のカレントディレクトリを表示する。
 #[derive(Parser)]
 #[command(name = "cwd", about = "Print the current working directory")]
-struct CwdArgs {}
+struct CwdArgs {
+    /// Abbreviate the home directory as `~`
+    #[arg(short, long, conflicts_with = "relative")]
+    short: bool,
+
+    /// Print the path relative to BASE (which must exist)
+    #[arg(short, long, value_name = "BASE")]
+    relative: Option<PathBuf>,
+}
 
 /// cwd: 現在のカレントディレクトリを出力する。
+///
+/// 出力は `$PWD` と一致させる（[`sync_pwd`] 参照）。
+/// `--short` で `~` 短縮、`--relative BASE` で BASE からの相対パスを出力する。
 pub(super) fn execute(args: &[&str]) -> CommandResult {
-    if let Err(result) = super::parse_args::<CwdArgs>("cwd", args) {
-        return result;
-    }
+    let parsed = match super::parse_args::<CwdArgs>("cwd", args) {
+        Ok(a) => a,
+        Err(result) => return result,
+    };
+
+    let formatted = sync_pwd().and_then(|path| {
+        if parsed.short {
+            Ok(shorten_path(&path))
+        } else if let Some(base) = &parsed.relative {
+            relative_display(&path, base)
+        } else {
+            Ok(path.display().to_string())
+        }
+    });
 
-    match env::current_dir() {
+    match formatted {
         Ok(path) => {
-            let output = format!("{}\n", path.display());
-            print!("{output}");
+            let output = format!("{path}\n");
             CommandResult::success(output)
         }
         Err(e) => {
@@ -29,6 +53,64 @@ pub(super) fn execute(args: &[&str]) -> CommandResult {
     }
 }
 
+/// `path` を `base` からの相対パスとして表示用の文字列にする。
+///
+/// シンボリックリンクの違いで `..` が余計に並ばないよう、両方を正規化してから比較する。
+/// `base` が存在しない場合はエラー。
+fn relative_display(path: &Path, base: &Path) -> std::io::Result<String> {
+    let base = base
+        .canonicalize()
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {e}", base.display())))?;
+    let path = path.canonicalize()?;
+    let relative = relative_path(&path, &base);
+    Ok(if relative.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        relative.display().to_string()
+    })
+}
+
+/// 絶対パス `path` を絶対パス `base` からの相対パスにする（同じなら空のパス）。
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+/// `$PWD` を現在のカレントディレクトリと同期し、その値を返す。
+///
+/// `$PWD` が現在のディレクトリを指していればそのまま使い（親シェルから受け継いだ
+/// シンボリックリンク経由のパスを保つ）、未設定・相対パス・別ディレクトリを指す場合は
+/// 実際の CWD で上書きする。起動時と `cd` / `cwd` から呼び、
+/// 子プロセスが正しい `$PWD` を見られるようにする。
+pub(crate) fn sync_pwd() -> std::io::Result<PathBuf> {
+    let cwd = env::current_dir()?;
+    if let Some(pwd) = env::var_os("PWD").map(PathBuf::from) {
+        if pwd.is_absolute() && is_same_dir(&pwd, &cwd) {
+            return Ok(pwd);
+        }
+    }
+    env::set_var("PWD", &cwd);
+    Ok(cwd)
+}
+
+/// 2 つのパスが同じディレクトリ（同じデバイス・inode）を指すか。
+fn is_same_dir(a: &Path, b: &Path) -> bool {
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
 /// テスト用ヘルパー（兄弟モジュールからも参照可能）
 #[cfg(test)]
 pub(crate) mod test_helpers {
@@ -92,6 +174,100 @@ mod tests {
         );
     }
 
+    #[test]
+    #[serial]
+    fn stale_pwd_is_replaced_with_current_directory() {
+        let _guard = CwdGuard::new();
+        let dir = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        env::set_current_dir(dir.path()).unwrap();
+        env::set_var("PWD", other.path());
+
+        let result = execute(&[]);
+        let cwd = env::current_dir().unwrap();
+        assert_eq!(result.stdout, format!("{}\n", cwd.display()));
+        assert_eq!(env::var_os("PWD"), Some(cwd.into_os_string()));
+    }
+
+    #[test]
+    #[serial]
+    fn pwd_through_symlink_is_kept() {
+        let _guard = CwdGuard::new();
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().join("real");
+        let link = dir.path().join("link");
+        std::fs::create_dir(&real).unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        env::set_current_dir(&link).unwrap();
+        env::set_var("PWD", &link);
+
+        let result = execute(&[]);
+        assert_eq!(result.stdout, format!("{}\n", link.display()));
+        assert_eq!(env::var_os("PWD"), Some(link.into_os_string()));
+    }
+
+    #[test]
+    #[serial]
+    fn cwd_short_abbreviates_home() {
+        let _guard = CwdGuard::new();
+        let home = tempfile::tempdir().unwrap();
+        let original_home = env::var_os("HOME");
+        let sub = home.path().join("dev");
+        std::fs::create_dir(&sub).unwrap();
+        env::set_var("HOME", home.path());
+        env::set_current_dir(&sub).unwrap();
+        env::set_var("PWD", &sub);
+
+        let result = execute(&["--short"]);
+        match original_home {
+            Some(h) => env::set_var("HOME", h),
+            None => env::remove_var("HOME"),
+        }
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout, "~/dev\n");
+    }
+
+    #[test]
+    #[serial]
+    fn cwd_relative_to_base() {
+        let _guard = CwdGuard::new();
+        let dir = tempfile::tempdir().unwrap();
+        let work = dir.path().join("a").join("b");
+        let other = dir.path().join("c");
+        std::fs::create_dir_all(&work).unwrap();
+        std::fs::create_dir(&other).unwrap();
+        env::set_current_dir(&work).unwrap();
+
+        let base = dir.path().to_string_lossy().to_string();
+        assert_eq!(execute(&["--relative", &base]).stdout, "a/b\n");
+        let base = other.to_string_lossy().to_string();
+        assert_eq!(execute(&["-r", &base]).stdout, "../a/b\n");
+        let base = work.to_string_lossy().to_string();
+        assert_eq!(execute(&["-r", &base]).stdout, ".\n");
+    }
+
+    #[test]
+    #[serial]
+    fn cwd_relative_to_missing_base_is_error() {
+        let _guard = CwdGuard::new();
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing").to_string_lossy().to_string();
+
+        let result = execute(&["--relative", &missing]);
+        assert_eq!(result.exit_code, 1);
+        assert!(
+            result.stderr.starts_with("jarvish: cwd: "),
+            "{}",
+            result.stderr
+        );
+        assert!(result.stderr.contains("missing"));
+    }
+
+    #[test]
+    fn cwd_short_and_relative_conflict() {
+        assert_eq!(execute(&["--short", "--relative", "/"]).exit_code, 2);
+    }
+
     #[test]
     fn cwd_help_returns_success() {
         let result = execute(&["--help"]);
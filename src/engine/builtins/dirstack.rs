@@ -128,17 +128,12 @@ pub(crate) fn execute_dirs(args: &[&str], dir_stack: &mut Vec<PathBuf>) -> Comma
         output.push_str(&format!("  {}. {e}\n", i + 1));
     }
 
-    print!("{output}");
     CommandResult::success(output)
 }
 
 /// ディレクトリを変更し、PWD / OLDPWD 環境変数を更新する。
 fn change_dir(target: &Path) -> Result<(), CommandResult> {
-    let old_pwd = env::var("PWD").ok().or_else(|| {
-        env::current_dir()
-            .ok()
-            .map(|p| p.to_string_lossy().into_owned())
-    });
+    let old_pwd = super::cwd::sync_pwd().ok();
 
     env::set_current_dir(target).map_err(|e| {
         let msg = format!("jarvish: {}: {e}\n", target.display());
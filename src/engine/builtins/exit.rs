@@ -6,13 +6,18 @@ use crate::engine::CommandResult;
 #[derive(Parser)]
 #[command(name = "exit", about = "Exit the shell")]
 struct ExitArgs {
+    /// Exit without confirmation even if `[shell] confirm_exit = true`
+    #[arg(short, long)]
+    force: bool,
+
     /// Exit code (0-255, default: 0)
     #[arg(allow_hyphen_values = true)]
     code: Option<String>,
 }
 
-/// exit: REPL ループを終了する。
+/// exit: REPL ループを終了する（`logout` も同じ実装を使う）。
 /// - 引数なし → 終了コード 0
+/// - `exit --force` / `exit -f` → 終了コード 0（確認プロンプトをスキップ）
 /// - `exit N` → 終了コード N（0〜255。範囲外は 255 にクランプ）
 /// - `exit foo` → エラー（数値でない引数）
 /// - `exit --help` → ヘルプ表示（シェルは終了しない）
@@ -40,6 +45,18 @@ pub(super) fn execute(args: &[&str]) -> CommandResult {
     }
 }
 
+/// `[shell] confirm_exit = true` のとき、この引数での終了に確認が必要かを判定する。
+///
+/// 引数なしの `exit` / `logout` のみ確認対象とする。終了コード指定（`exit 0`）や
+/// `--force` は明示的な終了意思とみなしてスキップする。`--help` や不正な
+/// オプションは終了しないため確認不要。
+pub(crate) fn requires_confirmation(args: &[&str]) -> bool {
+    match ExitArgs::try_parse_from(std::iter::once("exit").chain(args.iter().copied())) {
+        Ok(parsed) => !parsed.force && parsed.code.is_none(),
+        Err(_) => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,6 +102,26 @@ mod tests {
         assert_eq!(result.exit_code, 2);
     }
 
+    #[test]
+    fn exit_force_returns_exit_action() {
+        let result = execute(&["--force"]);
+        assert_eq!(result.action, LoopAction::Exit);
+        assert_eq!(result.exit_code, 0);
+
+        let result = execute(&["-f", "3"]);
+        assert_eq!(result.action, LoopAction::Exit);
+        assert_eq!(result.exit_code, 3);
+    }
+
+    #[test]
+    fn requires_confirmation_only_for_bare_exit() {
+        assert!(requires_confirmation(&[]));
+        assert!(!requires_confirmation(&["0"]));
+        assert!(!requires_confirmation(&["--force"]));
+        assert!(!requires_confirmation(&["-f"]));
+        assert!(!requires_confirmation(&["--help"]));
+    }
+
     #[test]
     fn exit_help_does_not_exit() {
         let result = execute(&["--help"]);
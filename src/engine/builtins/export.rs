@@ -2,12 +2,21 @@ use std::env;
 
 use clap::Parser;
 
-use crate::engine::CommandResult;
+use crate::engine::shell_vars::ShellVariables;
+use crate::engine::{persist_env, CommandResult};
 
 /// export: 環境変数を設定・表示する。
 #[derive(Parser)]
 #[command(name = "export", about = "Set or display environment variables")]
 struct ExportArgs {
+    /// Interpret backslash escapes in VALUE (\n, \t, \r, \\)
+    #[arg(short = 'e', long = "escapes")]
+    escapes: bool,
+
+    /// Also save the variable to ~/.config/jarvish/env.toml (requires [env] persist = true)
+    #[arg(long = "persist")]
+    persist: bool,
+
     /// Variable assignment in KEY=VALUE format, or variable name to display
     assignments: Vec<String>,
 }
@@ -15,13 +24,26 @@ struct ExportArgs {
 /// export: 環境変数を設定・表示する。
 /// - 引数なし → 全環境変数をソート済みで表示
 /// - `export KEY=VALUE` → 環境変数を設定
-/// - `export KEY` → 該当変数の値を表示
-pub(super) fn execute(args: &[&str]) -> CommandResult {
+/// - `export -e KEY=VALUE` → VALUE 内の `\n` 等のエスケープを解釈して設定
+/// - `export KEY` → 該当変数の値を表示（シェル変数 `KEY` があれば環境変数に昇格させる）
+/// - `export --persist KEY=VALUE` → 設定に加えて `env.toml` に保存し、次回起動時も設定する
+///   （`export --persist KEY` は現在の値を保存する。`[env] persist = true` が必要）
+///
+/// VALUE のクォート除去と `$OTHER` の展開は呼び出し元のトークナイズ・展開
+/// （`split_quoted` / `expand_token_globs`）で済んでいるため、
+/// `export MSG="hello world"` は空白を含む 1 つの引数として渡ってくる。
+pub(super) fn execute(args: &[&str], vars: &ShellVariables) -> CommandResult {
     let parsed = match super::parse_args::<ExportArgs>("export", args) {
         Ok(a) => a,
         Err(result) => return result,
     };
 
+    if parsed.persist && !persist_env::is_enabled() {
+        let msg = "jarvish: export: --persist requires `[env] persist = true` in config.toml\n";
+        eprint!("{msg}");
+        return CommandResult::error(msg.to_string(), 1);
+    }
+
     // 引数なし → 全環境変数を表示
     if parsed.assignments.is_empty() {
         return list_all_vars();
@@ -33,7 +55,12 @@ pub(super) fn execute(args: &[&str]) -> CommandResult {
         if let Some(eq_pos) = assignment.find('=') {
             // KEY=VALUE 形式 → 環境変数を設定
             let key = &assignment[..eq_pos];
-            let value = &assignment[eq_pos + 1..];
+            let raw_value = &assignment[eq_pos + 1..];
+            let value = if parsed.escapes {
+                interpret_escapes(raw_value)
+            } else {
+                raw_value.to_string()
+            };
 
             if key.is_empty() {
                 let msg = format!("jarvish: export: `{assignment}`: not a valid identifier\n");
@@ -41,16 +68,40 @@ pub(super) fn execute(args: &[&str]) -> CommandResult {
                 return CommandResult::error(msg, 1);
             }
 
+            if parsed.persist {
+                if let Err(result) = persist(key, &value) {
+                    return result;
+                }
+            }
+
+            // 同名のシェル変数が残っていると展開で優先されてしまうため削除する
+            vars.remove(key);
+
             // SAFETY: シェルプロセス内でシングルスレッドで呼ばれるため安全
             unsafe {
                 env::set_var(key, value);
             }
+        } else if let Some(value) = vars.remove(assignment) {
+            // シェル変数 → 環境変数に昇格（以降は子プロセスにも渡る）
+            if parsed.persist {
+                if let Err(result) = persist(assignment, &value) {
+                    return result;
+                }
+            }
+            // SAFETY: シェルプロセス内でシングルスレッドで呼ばれるため安全
+            unsafe {
+                env::set_var(assignment, value);
+            }
         } else {
-            // KEY のみ → 該当変数の値を表示
+            // KEY のみ → 該当変数の値を表示（--persist なら現在の値を保存）
             match env::var(assignment) {
+                Ok(value) if parsed.persist => {
+                    if let Err(result) = persist(assignment, &value) {
+                        return result;
+                    }
+                }
                 Ok(value) => {
-                    let line = format!("{assignment}={value}\n");
-                    print!("{line}");
+                    let line = format!("{assignment}={}\n", escape_for_display(&value));
                     output.push_str(&line);
                 }
                 Err(_) => {
@@ -65,6 +116,15 @@ pub(super) fn execute(args: &[&str]) -> CommandResult {
     CommandResult::success(output)
 }
 
+/// 変数を永続化ファイルに保存する。失敗した場合はエラー結果を返す。
+fn persist(key: &str, value: &str) -> Result<(), CommandResult> {
+    persist_env::persist_var(&persist_env::default_path(), key, value).map_err(|e| {
+        let msg = format!("jarvish: export: {key}: {e}\n");
+        eprint!("{msg}");
+        CommandResult::error(msg, 1)
+    })
+}
+
 /// 全環境変数をソート済みで `KEY=VALUE` 形式で表示する。
 fn list_all_vars() -> CommandResult {
     let mut vars: Vec<(String, String)> = env::vars().collect();
@@ -72,14 +132,52 @@ fn list_all_vars() -> CommandResult {
 
     let mut output = String::new();
     for (key, value) in &vars {
-        let line = format!("{key}={value}\n");
+        let line = format!("{key}={}\n", escape_for_display(value));
         output.push_str(&line);
     }
-    print!("{output}");
 
     CommandResult::success(output)
 }
 
+/// `\n` `\t` `\r` `\\` のエスケープシーケンスを対応する文字に置き換える。
+/// 未知のシーケンスはバックスラッシュごとそのまま残す。
+fn interpret_escapes(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            _ => {
+                out.push('\\');
+                continue;
+            }
+        }
+        chars.next();
+    }
+    out
+}
+
+/// 表示用に値の改行・タブ・復帰をエスケープする。
+///
+/// 複数行の値をそのまま出力すると `KEY=VALUE` の 1 行 1 変数の形式が崩れ、
+/// `export | grep KEY` のような行単位の処理で値が途切れるため。
+fn escape_for_display(value: &str) -> String {
+    if !value.contains(['\n', '\t', '\r']) {
+        return value.to_string();
+    }
+    value
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,7 +216,7 @@ mod tests {
     #[serial]
     fn export_set_variable() {
         let _guard = EnvGuard::new("JARVISH_TEST_EXPORT");
-        let result = execute(&["JARVISH_TEST_EXPORT=hello"]);
+        let result = execute(&["JARVISH_TEST_EXPORT=hello"], &ShellVariables::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(env::var("JARVISH_TEST_EXPORT").unwrap(), "hello");
     }
@@ -127,7 +225,7 @@ mod tests {
     #[serial]
     fn export_set_empty_value() {
         let _guard = EnvGuard::new("JARVISH_TEST_EMPTY");
-        let result = execute(&["JARVISH_TEST_EMPTY="]);
+        let result = execute(&["JARVISH_TEST_EMPTY="], &ShellVariables::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(env::var("JARVISH_TEST_EMPTY").unwrap(), "");
     }
@@ -139,7 +237,7 @@ mod tests {
         unsafe {
             env::set_var("JARVISH_TEST_SHOW", "world");
         }
-        let result = execute(&["JARVISH_TEST_SHOW"]);
+        let result = execute(&["JARVISH_TEST_SHOW"], &ShellVariables::default());
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("JARVISH_TEST_SHOW=world"));
     }
@@ -151,14 +249,26 @@ mod tests {
         unsafe {
             env::remove_var("JARVISH_TEST_UNSET_VAR");
         }
-        let result = execute(&["JARVISH_TEST_UNSET_VAR"]);
+        let result = execute(&["JARVISH_TEST_UNSET_VAR"], &ShellVariables::default());
         assert_ne!(result.exit_code, 0);
         assert!(result.stderr.contains("not set"));
     }
 
+    #[test]
+    #[serial]
+    fn export_promotes_shell_variable() {
+        let _guard = EnvGuard::new("jarvish_test_promote");
+        let vars = ShellVariables::default();
+        vars.set("jarvish_test_promote", "local");
+        let result = execute(&["jarvish_test_promote"], &vars);
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(env::var("jarvish_test_promote").unwrap(), "local");
+        assert!(vars.remove("jarvish_test_promote").is_none());
+    }
+
     #[test]
     fn export_no_args_lists_all() {
-        let result = execute(&[]);
+        let result = execute(&[], &ShellVariables::default());
         assert_eq!(result.exit_code, 0);
         // PATH は必ず存在するはず
         assert!(result.stdout.contains("PATH="));
@@ -166,14 +276,81 @@ mod tests {
 
     #[test]
     fn export_invalid_identifier() {
-        let result = execute(&["=value"]);
+        let result = execute(&["=value"], &ShellVariables::default());
         assert_ne!(result.exit_code, 0);
         assert!(result.stderr.contains("not a valid identifier"));
     }
 
+    #[test]
+    #[serial]
+    fn export_value_with_spaces_is_kept_whole() {
+        let _guard = EnvGuard::new("JARVISH_TEST_SPACES");
+        // `export JARVISH_TEST_SPACES="hello world"` はクォート除去後 1 引数で渡る
+        let result = execute(
+            &["JARVISH_TEST_SPACES=hello world"],
+            &ShellVariables::default(),
+        );
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(env::var("JARVISH_TEST_SPACES").unwrap(), "hello world");
+    }
+
+    #[test]
+    #[serial]
+    fn export_without_escapes_keeps_backslashes() {
+        let _guard = EnvGuard::new("JARVISH_TEST_RAW");
+        let result = execute(
+            &["JARVISH_TEST_RAW=line1\\nline2"],
+            &ShellVariables::default(),
+        );
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(env::var("JARVISH_TEST_RAW").unwrap(), "line1\\nline2");
+    }
+
+    #[test]
+    #[serial]
+    fn export_with_escapes_interprets_sequences() {
+        let _guard = EnvGuard::new("JARVISH_TEST_MULTI");
+        let result = execute(
+            &["-e", "JARVISH_TEST_MULTI=line1\\nline2\\tend\\\\x\\q"],
+            &ShellVariables::default(),
+        );
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(
+            env::var("JARVISH_TEST_MULTI").unwrap(),
+            "line1\nline2\tend\\x\\q"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn export_show_multiline_value_is_escaped() {
+        let _guard = EnvGuard::new("JARVISH_TEST_SHOW_MULTI");
+        unsafe {
+            env::set_var("JARVISH_TEST_SHOW_MULTI", "a\nb");
+        }
+        let result = execute(&["JARVISH_TEST_SHOW_MULTI"], &ShellVariables::default());
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout, "JARVISH_TEST_SHOW_MULTI=a\\nb\n");
+    }
+
+    #[test]
+    #[serial]
+    fn export_persist_requires_env_persist_enabled() {
+        let _guard = EnvGuard::new("JARVISH_TEST_PERSIST");
+        persist_env::set_enabled(false);
+        let result = execute(
+            &["--persist", "JARVISH_TEST_PERSIST=1"],
+            &ShellVariables::default(),
+        );
+        assert_eq!(result.exit_code, 1);
+        assert!(result.stderr.contains("[env] persist = true"));
+        // 保存できない場合は変数も設定しない
+        assert!(env::var("JARVISH_TEST_PERSIST").is_err());
+    }
+
     #[test]
     fn export_help_returns_success() {
-        let result = execute(&["--help"]);
+        let result = execute(&["--help"], &ShellVariables::default());
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("export"));
     }
@@ -8,22 +8,65 @@ use super::BUILTIN_COMMANDS;
 #[derive(Parser)]
 #[command(name = "help", about = "Display help for builtin commands")]
 struct HelpArgs {
-    /// Command name to show help for
+    /// Show the key bindings of the line editor (same as `help keys`)
+    #[arg(long)]
+    keys: bool,
+
+    /// Command name to show help for (or `keys` for key bindings)
     command: Option<String>,
 }
 
+/// 行エディタのキーバインド一覧（キー, 説明）。
+///
+/// `shell::editor::build_editor` で設定するキーバインド（reedline の Emacs 既定 +
+/// jarvish 独自の割り当て）と、シェル本体が扱う Ctrl-C / Ctrl-D の挙動。
+/// editor.rs のキーバインドを変更したらこの一覧も更新すること。
+const KEY_BINDINGS: &[(&str, &str)] = &[
+    (
+        "Tab",
+        "Open the completion menu / select the next candidate",
+    ),
+    ("Ctrl-R", "Search history (press again for the next page)"),
+    ("Up / Down", "Previous / next history entry"),
+    (
+        "Right / Ctrl-F",
+        "Accept the history suggestion (or move right)",
+    ),
+    (
+        "Enter",
+        "Run the line (continues on trailing `\\` or open quote)",
+    ),
+    ("Ctrl-A / Ctrl-E", "Move to the start / end of the line"),
+    ("Alt-B / Alt-F", "Move one word backward / forward"),
+    ("Ctrl-W", "Delete the word before the cursor"),
+    ("Ctrl-U / Ctrl-K", "Cut to the start / end of the line"),
+    ("Ctrl-Y", "Paste the last cut text"),
+    ("Ctrl-L", "Clear the screen"),
+    ("Esc", "Close the completion / history menu"),
+    (
+        "Ctrl-C",
+        "Clear the line, or interrupt the running command / AI response",
+    ),
+    ("Ctrl-D", "Exit the shell (on an empty line)"),
+];
+
 /// help: ビルトインコマンドのヘルプを表示する。
 /// - 引数なし → 全ビルトインコマンドの一覧を表示
 /// - `help <command>` → 指定コマンドの詳細ヘルプを表示
+/// - `help keys` / `help --keys` → キーバインド一覧を表示
 pub(super) fn execute(args: &[&str]) -> CommandResult {
     let parsed = match super::parse_args::<HelpArgs>("help", args) {
         Ok(a) => a,
         Err(result) => return result,
     };
 
-    match parsed.command {
+    if parsed.keys {
+        return list_key_bindings();
+    }
+    match parsed.command.as_deref() {
         None => list_builtins(),
-        Some(cmd) => show_command_help(&cmd),
+        Some("keys") => list_key_bindings(),
+        Some(cmd) => show_command_help(cmd),
     }
 }
 
@@ -35,12 +78,24 @@ fn list_builtins() -> CommandResult {
         output.push_str(&format!("  {name:<10}{desc}\n"));
     }
 
-    print!("{output}");
+    output.push_str("\nRun `help keys` to list key bindings.\n");
+
+    CommandResult::success(output)
+}
+
+/// キーバインド一覧を表示する。
+fn list_key_bindings() -> CommandResult {
+    let mut output = String::from("Key bindings (emacs mode):\n");
+
+    for (key, desc) in KEY_BINDINGS {
+        output.push_str(&format!("  {key:<18}{desc}\n"));
+    }
+
     CommandResult::success(output)
 }
 
 /// 指定コマンドの詳細ヘルプを表示する。
-/// ビルトインコマンドの場合は `dispatch_builtin(cmd, &["--help"])` に委譲する。
+/// ビルトインコマンドの場合は `builtin_help(cmd)`（`cmd --help`）に委譲する。
 fn show_command_help(cmd: &str) -> CommandResult {
     if !super::is_builtin(cmd) {
         let msg = format!("jarvish: help: no such builtin: {cmd}\n");
@@ -49,7 +104,7 @@ fn show_command_help(cmd: &str) -> CommandResult {
     }
 
     // 対象コマンドの --help を呼び出して詳細ヘルプを表示
-    super::dispatch_builtin(cmd, &["--help"]).unwrap_or_else(|| {
+    super::builtin_help(cmd).unwrap_or_else(|| {
         CommandResult::error(format!("jarvish: help: {cmd}: unexpected error\n"), 1)
     })
 }
@@ -76,6 +131,18 @@ mod tests {
         assert!(result.stdout.contains("pwd"));
     }
 
+    #[test]
+    fn help_keys_lists_key_bindings() {
+        for args in [&["keys"][..], &["--keys"][..]] {
+            let result = execute(args);
+            assert_eq!(result.exit_code, 0);
+            assert!(result.stdout.contains("Key bindings"));
+            assert!(result.stdout.contains("Ctrl-R"));
+            assert!(result.stdout.contains("Tab"));
+            assert!(result.stdout.contains("Ctrl-D"));
+        }
+    }
+
     #[test]
     fn help_specific_command_shows_detail() {
         let result = execute(&["cd"]);
@@ -1,9 +1,27 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Display;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Timelike, Utc};
 use clap::{Parser, Subcommand};
 use rusqlite::Connection;
+use tracing::debug;
 
-use crate::engine::CommandResult;
+use crate::cli::jarvis::{exceeds_screen, terminal_rows};
+use crate::engine::exec::run_pager;
+use crate::engine::{CommandResult, ExecContext};
+use crate::storage::blob::{BlobStore, GcStats};
 use crate::storage::BlackBox;
 
+/// 孤立 Blob を削除するまでの猶予期間。
+/// 並行する別セッションが Blob を保存してから履歴行を書き込むまでの間に消さないようにする。
+const GC_MIN_AGE: Duration = Duration::from_secs(60);
+
+/// `history --page` で `$PAGER` が未設定のときに使うページャ
+const DEFAULT_PAGER: &str = "less";
+
 /// history: コマンド履歴を表示・管理する。
 #[derive(Parser)]
 #[command(name = "history", about = "Display or manage command history")]
@@ -18,19 +36,112 @@ struct HistoryArgs {
     /// Show working directory for each entry
     #[arg(short = 'd', long = "dirs")]
     dirs: bool,
+
+    /// Show when each entry was run (local time)
+    #[arg(short = 't', long = "time")]
+    time: bool,
+
+    /// Show the environment variables recorded with each entry ([history] track_env)
+    #[arg(short = 'e', long = "env")]
+    env: bool,
+
+    /// Open the list in a pager ($PAGER, default: less) when it does not fit on the screen
+    #[arg(long)]
+    page: bool,
 }
 
 #[derive(Subcommand)]
 enum HistoryCommand {
     /// Clear all history
     Clear,
+    /// Delete stored outputs (blobs) no longer referenced by any history entry
+    Gc,
+    /// Show the slowest commands by average/max duration
+    Profile {
+        /// Number of commands to display (default: 10)
+        #[arg(short = 'n', long, default_value = "10")]
+        count: usize,
+
+        /// Sort by maximum duration instead of average
+        #[arg(long)]
+        max: bool,
+    },
+    /// Show usage statistics (success rate, top commands, runs by hour)
+    Stats {
+        /// Number of commands to display in each ranking (default: 10)
+        #[arg(short = 'n', long, default_value = "10")]
+        count: usize,
+
+        /// Only count commands run on or after this date (YYYY-MM-DD, local time)
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+    },
+    /// Re-run the command with the given history ID
+    Run {
+        /// History ID shown by `history`
+        id: i64,
+    },
+}
+
+/// `history` の一覧表示 1 行分
+#[derive(Debug, PartialEq)]
+struct HistoryRow {
+    id: i64,
+    command: String,
+    cwd: String,
+    created_at: String,
+    env_json: Option<String>,
+}
+
+/// `history` の一覧表示で併記する項目
+#[derive(Debug, Clone, Copy, Default)]
+struct ListOptions {
+    /// 実行ディレクトリ（`--dirs`）
+    dirs: bool,
+    /// 実行日時（`--time`）
+    time: bool,
+    /// 環境変数のスナップショット（`--env`）
+    env: bool,
+}
+
+/// `history stats` の集計結果
+#[derive(Debug, PartialEq)]
+struct UsageStats {
+    total: usize,
+    failed: usize,
+    /// コマンド名（先頭の単語）ごとの実行回数（多い順）
+    top_commands: Vec<(String, usize)>,
+    /// コマンドライン全体ごとの (失敗回数, 実行回数)（失敗の多い順）
+    top_failures: Vec<(String, usize, usize)>,
+    /// 時間帯（ローカル時刻の 0〜23 時）ごとの実行回数
+    by_hour: [usize; 24],
+}
+
+/// `history profile` の集計結果 1 行分
+#[derive(Debug, PartialEq)]
+struct ProfileRow {
+    command: String,
+    runs: i64,
+    avg_ms: f64,
+    max_ms: i64,
 }
 
 /// history: コマンド履歴を表示・管理する。
 /// - `history` → 直近 50 件を表示
 /// - `history -n 100` → 直近 100 件を表示
-/// - `history clear` → 全履歴をクリア
-pub(super) fn execute(args: &[&str]) -> CommandResult {
+/// - `history --time` → 実行日時（ローカル時刻）も表示
+/// - `history --env` → 記録された環境変数（`[history] track_env`）も表示
+/// - `history --page` → 画面に収まらなければページャ（`$PAGER`、既定は `less`）で表示
+/// - `history clear` → 全履歴をクリア（参照されなくなった出力の Blob も削除）
+/// - `history gc` → どの履歴からも参照されていない出力の Blob を削除
+/// - `history profile` → 平均所要時間の大きいコマンド上位 10 件を表示
+/// - `history stats [--since DATE]` → 成功率・よく使うコマンド・時間帯別の実行数を表示
+/// - `history run N` → ID が N の履歴のコマンドをエコー表示してから再実行
+///   （Shell の入力処理が [`parse_run_request`] で受け取る。ここに来るのは他のコマンドと
+///   組み合わされた場合のみで、エラーにする）
+///
+/// 出力をキャプチャする場合（`ctx.capture_stdout`）は `--page` を無視する。
+pub(super) fn execute(args: &[&str], ctx: &ExecContext) -> CommandResult {
     let parsed = match super::parse_args::<HistoryArgs>("history", args) {
         Ok(a) => a,
         Err(result) => return result,
@@ -38,139 +149,592 @@ pub(super) fn execute(args: &[&str]) -> CommandResult {
 
     match parsed.command {
         Some(HistoryCommand::Clear) => clear_history(),
-        None => list_history(parsed.count, parsed.dirs),
+        Some(HistoryCommand::Gc) => gc_history(),
+        Some(HistoryCommand::Profile { count, max }) => show_profile(count, max),
+        Some(HistoryCommand::Stats { count, since }) => show_stats(count, since.as_deref()),
+        Some(HistoryCommand::Run { .. }) => {
+            let msg = "jarvish: history: run: must be used on its own\n".to_string();
+            eprint!("{msg}");
+            CommandResult::error(msg, 1)
+        }
+        None => list_history(
+            parsed.count,
+            ListOptions {
+                dirs: parsed.dirs,
+                time: parsed.time,
+                env: parsed.env,
+            },
+            parsed.page && !ctx.capture_stdout,
+        ),
     }
 }
 
-/// 直近 N 件の履歴を表示する。
-/// `dirs` が true の場合、各エントリに実行ディレクトリを付加する。
-fn list_history(count: usize, dirs: bool) -> CommandResult {
+/// 直近 N 件の履歴を、`options` で指定された項目を併記して表示する。
+///
+/// `page` が真で stdout が端末かつ画面に収まらない場合はページャで表示する。
+fn list_history(count: usize, options: ListOptions, page: bool) -> CommandResult {
     let conn = match open_history_db() {
         Ok(c) => c,
         Err(result) => return result,
     };
 
-    let sql = if dirs {
-        "SELECT id, command, cwd FROM command_history ORDER BY id DESC LIMIT ?1"
-    } else {
-        "SELECT id, command FROM command_history ORDER BY id DESC LIMIT ?1"
-    };
-
-    let mut stmt = match conn.prepare(sql) {
-        Ok(s) => s,
+    let mut entries = match query_history(&conn, count) {
+        Ok(entries) => entries,
         Err(e) => {
             let msg = format!("jarvish: history: failed to query: {e}\n");
             eprint!("{msg}");
             return CommandResult::error(msg, 1);
         }
     };
+    entries.reverse();
 
-    if dirs {
-        list_history_with_dirs(&mut stmt, count)
-    } else {
-        list_history_simple(&mut stmt, count)
+    let output = format_history(&entries, options);
+    if page && io::stdout().is_terminal() && exceeds_screen(&output, terminal_rows()) {
+        return page_history(&output);
     }
+
+    CommandResult::success(output)
 }
 
-/// ディレクトリなしの通常表示
-fn list_history_simple(stmt: &mut rusqlite::Statement, count: usize) -> CommandResult {
-    let rows = match stmt.query_map(rusqlite::params![count as i64], |row| {
-        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
-    }) {
-        Ok(r) => r,
-        Err(e) => {
-            let msg = format!("jarvish: history: failed to query: {e}\n");
-            eprint!("{msg}");
-            return CommandResult::error(msg, 1);
+/// 履歴の一覧をページャで表示する。
+///
+/// ページャは Alternate Screen を使うため、結果は `used_alt_screen` として返す
+/// （Black Box に出力を保存しない）。ページャを起動できなければ簡易ページングで表示する。
+/// 表示はここで済んでいるため、stdout は空で返す。
+fn page_history(output: &str) -> CommandResult {
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    if let Err(e) = run_pager(&pager, output) {
+        debug!(pager = %pager, error = %e, "Pager unavailable, paging history in-process");
+        let _ = page_simple(
+            output,
+            terminal_rows(),
+            &mut io::stdin().lock(),
+            &mut io::stdout(),
+        );
+    }
+
+    CommandResult {
+        used_alt_screen: true,
+        ..CommandResult::success(String::new())
+    }
+}
+
+/// 外部ページャがない場合の簡易ページング。
+///
+/// 1 画面分（プロンプト行を除く）ずつ表示し、`input` から 1 行読むごとに次の画面へ進む。
+/// `q` の入力または EOF で打ち切る。
+fn page_simple(
+    text: &str,
+    rows: usize,
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let page_size = rows.saturating_sub(1).max(1);
+    let lines: Vec<&str> = text.lines().collect();
+    let mut pages = lines.chunks(page_size).peekable();
+    while let Some(page) = pages.next() {
+        for line in page {
+            writeln!(out, "{line}")?;
         }
-    };
+        if pages.peek().is_none() {
+            break;
+        }
+        write!(out, "-- More -- (Enter: next page, q: quit) ")?;
+        out.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            writeln!(out)?;
+            break;
+        }
+        if answer.trim().eq_ignore_ascii_case("q") {
+            break;
+        }
+    }
+    out.flush()
+}
 
-    let mut entries: Vec<(i64, String)> = Vec::new();
-    for row in rows {
-        match row {
-            Ok(entry) => entries.push(entry),
-            Err(e) => {
-                let msg = format!("jarvish: history: failed to read row: {e}\n");
-                eprint!("{msg}");
-                return CommandResult::error(msg, 1);
+/// 直近 N 件の履歴を新しい順に取得する。
+fn query_history(conn: &Connection, count: usize) -> rusqlite::Result<Vec<HistoryRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, command, cwd, created_at, env_json FROM command_history \
+         ORDER BY id DESC LIMIT ?1",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![count as i64], |row| {
+        Ok(HistoryRow {
+            id: row.get(0)?,
+            command: row.get(1)?,
+            cwd: row.get(2)?,
+            created_at: row.get(3)?,
+            env_json: row.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// 履歴エントリを `  ID  [TIME  ][CWD  ]COMMAND` 形式に整形する。
+/// `--env` 指定時は記録された環境変数を各エントリの下の行に表示する。
+fn format_history(entries: &[HistoryRow], options: ListOptions) -> String {
+    let mut output = String::new();
+    for entry in entries {
+        output.push_str(&format!("{:>6}  ", entry.id));
+        if options.time {
+            output.push_str(&format!(
+                "{}  ",
+                format_created_at(&entry.created_at, &Local)
+            ));
+        }
+        if options.dirs {
+            output.push_str(&format!("{}  ", entry.cwd));
+        }
+        output.push_str(&entry.command);
+        output.push('\n');
+        if let Some(vars) = options
+            .env
+            .then(|| format_env_snapshot(entry.env_json.as_deref()))
+        {
+            if !vars.is_empty() {
+                output.push_str(&format!("{:>6}  {vars}\n", ""));
             }
         }
     }
+    output
+}
 
-    entries.reverse();
+/// RFC3339 の `created_at` を指定タイムゾーンの `YYYY-MM-DD HH:MM:SS` に整形する。
+/// 解析できない場合は保存値をそのまま返す。
+fn format_created_at<Tz>(created_at: &str, tz: &Tz) -> String
+where
+    Tz: TimeZone,
+    Tz::Offset: Display,
+{
+    DateTime::parse_from_rfc3339(created_at)
+        .map(|dt| dt.with_timezone(tz).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| created_at.to_string())
+}
 
-    let mut output = String::new();
-    for (id, command) in &entries {
-        let line = format!("{id:>6}  {command}\n");
-        output.push_str(&line);
+/// `env_json` に保存されたスナップショットを `KEY=VALUE` の空白区切りにする。
+/// 記録がない・解析できない場合は空文字列を返す。
+fn format_env_snapshot(env_json: Option<&str>) -> String {
+    let Some(json) = env_json else {
+        return String::new();
+    };
+    let vars: BTreeMap<String, String> = serde_json::from_str(json).unwrap_or_default();
+    vars.iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 全履歴をクリアする。
+fn clear_history() -> CommandResult {
+    let conn = match open_history_db() {
+        Ok(c) => c,
+        Err(result) => return result,
+    };
+
+    match conn.execute("DELETE FROM command_history", []) {
+        Ok(_) => {
+            // 出力の Blob は履歴から参照されなくなるため、あわせて削除する
+            let msg = match collect_orphan_blobs(&conn, &blob_dir(), GC_MIN_AGE) {
+                Ok(stats) if stats.removed > 0 => {
+                    format!("history cleared ({})\n", format_gc_stats(&stats))
+                }
+                Ok(_) => "history cleared\n".to_string(),
+                Err(e) => {
+                    eprintln!("jarvish: history: failed to remove stored outputs: {e:#}");
+                    "history cleared\n".to_string()
+                }
+            };
+            CommandResult::success(msg)
+        }
+        Err(e) => {
+            let msg = format!("jarvish: history: failed to clear: {e}\n");
+            eprint!("{msg}");
+            CommandResult::error(msg, 1)
+        }
     }
-    print!("{output}");
+}
 
-    CommandResult::success(output)
+/// 入力が単独の `history run N` なら `N` を返す。
+///
+/// Shell はこの入力をユーザーが `N` のコマンドを入力し直したものとして扱い、
+/// エイリアス展開・AI 判定・履歴記録を含む通常の入力処理に通す。
+pub fn parse_run_request(input: &str) -> Option<i64> {
+    match input.split_whitespace().collect::<Vec<_>>()[..] {
+        ["history", "run", id] => id.parse().ok(),
+        _ => None,
+    }
 }
 
-/// ディレクトリ付きの表示
-fn list_history_with_dirs(stmt: &mut rusqlite::Statement, count: usize) -> CommandResult {
-    let rows = match stmt.query_map(rusqlite::params![count as i64], |row| {
-        Ok((
-            row.get::<_, i64>(0)?,
-            row.get::<_, String>(1)?,
-            row.get::<_, String>(2)?,
-        ))
-    }) {
-        Ok(r) => r,
+/// `history run N` で再実行するコマンドを ID から引き、エコー表示して返す。
+///
+/// 存在しない ID や `history run` 自身のエントリはエラーを表示してエラー結果を返す。
+pub fn resolve_run_entry(id: i64) -> Result<String, CommandResult> {
+    let conn = open_history_db()?;
+
+    let command = match query_command(&conn, id) {
+        Ok(Some(command)) => command,
+        Ok(None) => {
+            let msg = format!("jarvish: history: run: no such entry: {id}\n");
+            eprint!("{msg}");
+            return Err(CommandResult::error(msg, 1));
+        }
         Err(e) => {
             let msg = format!("jarvish: history: failed to query: {e}\n");
             eprint!("{msg}");
-            return CommandResult::error(msg, 1);
+            return Err(CommandResult::error(msg, 1));
         }
     };
+    drop(conn);
 
-    let mut entries: Vec<(i64, String, String)> = Vec::new();
-    for row in rows {
-        match row {
-            Ok(entry) => entries.push(entry),
-            Err(e) => {
-                let msg = format!("jarvish: history: failed to read row: {e}\n");
-                eprint!("{msg}");
-                return CommandResult::error(msg, 1);
-            }
-        }
+    // `history run` 自身を再実行すると同じエントリを辿って終わらない可能性があるため拒否する
+    if is_history_run(&command) {
+        let msg = format!("jarvish: history: run: refusing to re-run '{command}'\n");
+        eprint!("{msg}");
+        return Err(CommandResult::error(msg, 1));
     }
 
-    entries.reverse();
+    println!("{command}");
+    Ok(command)
+}
 
-    let mut output = String::new();
-    for (id, command, cwd) in &entries {
-        let line = format!("{id:>6}  {cwd}  {command}\n");
-        output.push_str(&line);
+/// ID が `id` の履歴のコマンド文字列を取得する（存在しなければ `None`）。
+fn query_command(conn: &Connection, id: i64) -> rusqlite::Result<Option<String>> {
+    match conn.query_row(
+        "SELECT command FROM command_history WHERE id = ?1",
+        rusqlite::params![id],
+        |row| row.get(0),
+    ) {
+        Ok(command) => Ok(Some(command)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
     }
-    print!("{output}");
+}
 
-    CommandResult::success(output)
+/// コマンドが `history run ...` かどうかを判定する。
+fn is_history_run(command: &str) -> bool {
+    let mut words = command.split_whitespace();
+    words.next() == Some("history") && words.next() == Some("run")
 }
 
-/// 全履歴をクリアする。
-fn clear_history() -> CommandResult {
+/// どの履歴からも参照されていない出力の Blob を削除し、件数とサイズを報告する。
+fn gc_history() -> CommandResult {
     let conn = match open_history_db() {
         Ok(c) => c,
         Err(result) => return result,
     };
 
-    match conn.execute("DELETE FROM command_history", []) {
-        Ok(_) => {
-            let msg = "history cleared\n".to_string();
-            print!("{msg}");
+    match collect_orphan_blobs(&conn, &blob_dir(), GC_MIN_AGE) {
+        Ok(stats) => {
+            let msg = format!("history gc: {}\n", format_gc_stats(&stats));
             CommandResult::success(msg)
         }
         Err(e) => {
-            let msg = format!("jarvish: history: failed to clear: {e}\n");
+            let msg = format!("jarvish: history: gc failed: {e:#}\n");
             eprint!("{msg}");
             CommandResult::error(msg, 1)
         }
     }
 }
 
+/// 出力の Blob の保存先ディレクトリ
+fn blob_dir() -> std::path::PathBuf {
+    BlackBox::data_dir().join("blobs")
+}
+
+/// `command_history` から参照されていない Blob を `blob_dir` から削除する。
+fn collect_orphan_blobs(
+    conn: &Connection,
+    blob_dir: &Path,
+    min_age: Duration,
+) -> anyhow::Result<GcStats> {
+    let referenced = query_referenced_hashes(conn)?;
+    BlobStore::new(blob_dir.to_path_buf())?.collect_garbage(&referenced, min_age)
+}
+
+/// 履歴から参照されている Blob のハッシュ（stdout / stderr）を取得する。
+fn query_referenced_hashes(conn: &Connection) -> rusqlite::Result<HashSet<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT stdout_hash FROM command_history WHERE stdout_hash IS NOT NULL \
+         UNION SELECT stderr_hash FROM command_history WHERE stderr_hash IS NOT NULL",
+    )?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// GC の結果を `removed 3 blobs (1.2 KB)` 形式に整形する。
+fn format_gc_stats(stats: &GcStats) -> String {
+    let noun = if stats.removed == 1 { "blob" } else { "blobs" };
+    format!(
+        "removed {} {noun} ({})",
+        stats.removed,
+        format_bytes(stats.freed_bytes)
+    )
+}
+
+/// バイト数を `512 B` / `1.5 KB` / `2.0 MB` 形式に整形する。
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// 所要時間の大きいコマンドを実行回数とともに表示する。
+fn show_profile(count: usize, by_max: bool) -> CommandResult {
+    let conn = match open_history_db() {
+        Ok(c) => c,
+        Err(result) => return result,
+    };
+
+    let rows = match query_profile(&conn, count, by_max) {
+        Ok(rows) => rows,
+        Err(e) => {
+            let msg = format!("jarvish: history: failed to query: {e}\n");
+            eprint!("{msg}");
+            return CommandResult::error(msg, 1);
+        }
+    };
+
+    let output = format_profile(&rows);
+    CommandResult::success(output)
+}
+
+/// コマンド文字列ごとに所要時間を GROUP BY で集計する。
+/// 所要時間が記録されていない行（ビルトイン・AI 応答・旧バージョンの履歴）は除外する。
+fn query_profile(
+    conn: &Connection,
+    count: usize,
+    by_max: bool,
+) -> rusqlite::Result<Vec<ProfileRow>> {
+    let sql = if by_max {
+        "SELECT command, COUNT(*), AVG(duration_ms), MAX(duration_ms) FROM command_history \
+         WHERE duration_ms IS NOT NULL GROUP BY command \
+         ORDER BY MAX(duration_ms) DESC, AVG(duration_ms) DESC LIMIT ?1"
+    } else {
+        "SELECT command, COUNT(*), AVG(duration_ms), MAX(duration_ms) FROM command_history \
+         WHERE duration_ms IS NOT NULL GROUP BY command \
+         ORDER BY AVG(duration_ms) DESC, MAX(duration_ms) DESC LIMIT ?1"
+    };
+
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(rusqlite::params![count as i64], |row| {
+        Ok(ProfileRow {
+            command: row.get(0)?,
+            runs: row.get(1)?,
+            avg_ms: row.get(2)?,
+            max_ms: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// 集計結果を表形式に整形する。
+fn format_profile(rows: &[ProfileRow]) -> String {
+    if rows.is_empty() {
+        return "no timing data recorded yet\n".to_string();
+    }
+
+    let mut output = format!("{:>6}  {:>10}  {:>10}  COMMAND\n", "RUNS", "AVG", "MAX");
+    for row in rows {
+        output.push_str(&format!(
+            "{:>6}  {:>10}  {:>10}  {}\n",
+            row.runs,
+            format_duration_ms(row.avg_ms),
+            format_duration_ms(row.max_ms as f64),
+            row.command
+        ));
+    }
+    output
+}
+
+/// ミリ秒を `850ms` / `12.3s` / `2m05s` 形式に整形する。
+fn format_duration_ms(ms: f64) -> String {
+    if ms < 1000.0 {
+        format!("{}ms", ms.round() as u64)
+    } else if ms < 60_000.0 {
+        format!("{:.1}s", ms / 1000.0)
+    } else {
+        let secs = (ms / 1000.0).round() as u64;
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+/// 履歴の利用統計を表示する。
+fn show_stats(count: usize, since: Option<&str>) -> CommandResult {
+    let since_bound = match since.map(parse_since_date).transpose() {
+        Ok(bound) => bound,
+        Err(msg) => {
+            eprint!("{msg}");
+            return CommandResult::error(msg, 1);
+        }
+    };
+
+    let conn = match open_history_db() {
+        Ok(c) => c,
+        Err(result) => return result,
+    };
+
+    let stats = match query_stats(&conn, since_bound.as_deref(), count, &Local) {
+        Ok(stats) => stats,
+        Err(e) => {
+            let msg = format!("jarvish: history: failed to query: {e}\n");
+            eprint!("{msg}");
+            return CommandResult::error(msg, 1);
+        }
+    };
+
+    let output = format_stats(&stats, since);
+    CommandResult::success(output)
+}
+
+/// `--since` の日付（ローカル時刻の 0 時）を `created_at` と比較できる UTC の RFC3339 に変換する。
+fn parse_since_date(date: &str) -> Result<String, String> {
+    let invalid = || format!("jarvish: history: invalid date '{date}': expected YYYY-MM-DD\n");
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| invalid())?;
+    let midnight = day.and_hms_opt(0, 0, 0).ok_or_else(invalid)?;
+    let local = Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .ok_or_else(invalid)?;
+    Ok(local.with_timezone(&Utc).to_rfc3339())
+}
+
+/// 履歴の利用統計を SQL で集計する。時間帯は `tz` のローカル時刻で数える。
+///
+/// `created_at` は UTC の RFC3339 で保存されているため、文字列比較で期間を絞れる。
+/// 時間帯は UTC の 15 分単位の区間で GROUP BY し、各区間をローカル時刻に変換して数える
+/// （夏時間のあるタイムゾーンや、+05:30 のような 30 分・45 分単位の時差でも正しく振り分けるため）。
+fn query_stats<Tz: TimeZone>(
+    conn: &Connection,
+    since: Option<&str>,
+    count: usize,
+    tz: &Tz,
+) -> rusqlite::Result<UsageStats> {
+    const FILTER: &str = "?1 IS NULL OR created_at >= ?1";
+    let limit = count as i64;
+
+    let (total, failed): (i64, i64) = conn.query_row(
+        &format!(
+            "SELECT COUNT(*), COALESCE(SUM(exit_code != 0), 0) FROM command_history \
+             WHERE {FILTER}"
+        ),
+        rusqlite::params![since],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    // コマンド名はコマンドラインの先頭の単語
+    let mut stmt = conn.prepare(&format!(
+        "SELECT name, COUNT(*) AS runs FROM ( \
+             SELECT CASE WHEN instr(line, ' ') > 0 \
+                         THEN substr(line, 1, instr(line, ' ') - 1) ELSE line END AS name \
+             FROM (SELECT ltrim(command) AS line FROM command_history WHERE {FILTER}) \
+         ) WHERE name != '' GROUP BY name ORDER BY runs DESC, name LIMIT ?2"
+    ))?;
+    let top_commands = stmt
+        .query_map(rusqlite::params![since, limit], |row| {
+            Ok((row.get(0)?, row.get::<_, i64>(1)? as usize))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT command, SUM(exit_code != 0) AS fails, COUNT(*) FROM command_history \
+         WHERE {FILTER} GROUP BY command HAVING fails > 0 \
+         ORDER BY fails DESC, command LIMIT ?2"
+    ))?;
+    let top_failures = stmt
+        .query_map(rusqlite::params![since, limit], |row| {
+            Ok((
+                row.get(0)?,
+                row.get::<_, i64>(1)? as usize,
+                row.get::<_, i64>(2)? as usize,
+            ))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut by_hour = [0; 24];
+    // `YYYY-MM-DDTHH:` + 分を 15 分単位に切り捨てた 2 桁
+    const QUARTER: &str = "substr(created_at, 1, 14) \
+         || printf('%02d', (CAST(substr(created_at, 15, 2) AS INTEGER) / 15) * 15)";
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {QUARTER}, COUNT(*) FROM command_history \
+         WHERE {FILTER} GROUP BY {QUARTER}"
+    ))?;
+    let quarters = stmt.query_map(rusqlite::params![since], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+    for quarter in quarters {
+        let (utc_quarter, runs) = quarter?;
+        if let Ok(dt) = DateTime::parse_from_rfc3339(&format!("{utc_quarter}:00+00:00")) {
+            by_hour[dt.with_timezone(tz).hour() as usize] += runs as usize;
+        }
+    }
+
+    Ok(UsageStats {
+        total: total as usize,
+        failed: failed as usize,
+        top_commands,
+        top_failures,
+        by_hour,
+    })
+}
+
+/// 時間帯別グラフの最大幅（文字数）
+const HOUR_BAR_WIDTH: usize = 30;
+
+/// 利用統計をテキストの表に整形する。
+fn format_stats(stats: &UsageStats, since: Option<&str>) -> String {
+    if stats.total == 0 {
+        return "no history recorded yet\n".to_string();
+    }
+
+    let succeeded = stats.total - stats.failed;
+    let mut output = match since {
+        Some(date) => format!("Commands: {} (since {date})\n", stats.total),
+        None => format!("Commands: {}\n", stats.total),
+    };
+    output.push_str(&format!(
+        "Success rate: {:.1}% ({succeeded} succeeded, {} failed)\n",
+        succeeded as f64 * 100.0 / stats.total as f64,
+        stats.failed
+    ));
+
+    output.push_str("\nTOP COMMANDS\n");
+    output.push_str(&format!("{:>6}  COMMAND\n", "RUNS"));
+    for (name, runs) in &stats.top_commands {
+        output.push_str(&format!("{runs:>6}  {name}\n"));
+    }
+
+    if !stats.top_failures.is_empty() {
+        output.push_str("\nTOP FAILURES\n");
+        output.push_str(&format!("{:>6}  {:>6}  COMMAND\n", "FAILS", "RUNS"));
+        for (line, fails, runs) in &stats.top_failures {
+            output.push_str(&format!("{fails:>6}  {runs:>6}  {line}\n"));
+        }
+    }
+
+    output.push_str("\nBY HOUR\n");
+    let max = stats.by_hour.iter().copied().max().unwrap_or(0).max(1);
+    for (hour, runs) in stats.by_hour.iter().enumerate() {
+        let width = (runs * HOUR_BAR_WIDTH).div_ceil(max);
+        output.push_str(&format!(
+            "{hour:02}:00  {:<HOUR_BAR_WIDTH$}  {runs}\n",
+            "█".repeat(width)
+        ));
+    }
+    output
+}
+
 /// BlackBox の history.db への接続を開く。
 fn open_history_db() -> Result<Connection, CommandResult> {
     let db_path = BlackBox::data_dir().join("history.db");
@@ -205,13 +769,34 @@ mod tests {
                 exit_code: 0,
                 action: crate::engine::LoopAction::Continue,
                 used_alt_screen: false,
+                binary_output: false,
             };
-            bb.record(cmd, &result).unwrap();
+            bb.record(cmd, &result, None).unwrap();
         }
 
         tmp
     }
 
+    #[test]
+    fn page_simple_shows_one_screen_per_enter() {
+        let text = "1\n2\n3\n4\n5\n";
+        let mut out = Vec::new();
+        page_simple(text, 3, &mut io::Cursor::new("\n\n"), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches("-- More --").count(), 2);
+        assert!(out.starts_with("1\n2\n-- More --"));
+        assert!(out.ends_with("5\n"));
+    }
+
+    #[test]
+    fn page_simple_stops_on_quit() {
+        let text = "1\n2\n3\n4\n5\n";
+        let mut out = Vec::new();
+        page_simple(text, 3, &mut io::Cursor::new("q\n"), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(!out.contains('3'));
+    }
+
     #[test]
     fn history_list_shows_entries() {
         let tmp = setup_test_db(&["echo hello", "ls -la", "git status"]);
@@ -260,7 +845,7 @@ mod tests {
 
     #[test]
     fn history_help_returns_success() {
-        let result = execute(&["--help"]);
+        let result = execute(&["--help"], &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("history"));
     }
@@ -284,6 +869,435 @@ mod tests {
         assert_eq!(args.count, 20);
     }
 
+    #[test]
+    fn history_clap_parses_env() {
+        let args = HistoryArgs::try_parse_from(["history", "--env", "-d"]).unwrap();
+        assert!(args.env);
+        assert!(args.dirs);
+
+        let args = HistoryArgs::try_parse_from(["history", "-e"]).unwrap();
+        assert!(args.env);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn history_env_snapshot_is_recorded_and_formatted() {
+        let tmp = TempDir::new().unwrap();
+        let mut bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
+        bb.set_track_env(vec![
+            "JARVISH_TEST_TRACK_B".to_string(),
+            "JARVISH_TEST_TRACK_A".to_string(),
+            "JARVISH_TEST_TRACK_UNSET".to_string(),
+        ]);
+        unsafe {
+            std::env::set_var("JARVISH_TEST_TRACK_A", "production");
+            std::env::set_var("JARVISH_TEST_TRACK_B", "/venv");
+            std::env::remove_var("JARVISH_TEST_TRACK_UNSET");
+        }
+        let result = crate::engine::CommandResult::success(String::new());
+        bb.record("npm start", &result, None).unwrap();
+        bb.set_track_env(Vec::new());
+        bb.record("ls", &result, None).unwrap();
+        unsafe {
+            std::env::remove_var("JARVISH_TEST_TRACK_A");
+            std::env::remove_var("JARVISH_TEST_TRACK_B");
+        }
+
+        let conn = Connection::open(tmp.path().join("history.db")).unwrap();
+        let mut entries = query_history(&conn, 10).unwrap();
+        entries.reverse();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[1].env_json.is_none());
+
+        let env_only = ListOptions {
+            env: true,
+            ..ListOptions::default()
+        };
+        let output = format_history(&entries, env_only);
+        assert_eq!(
+            output,
+            "     1  npm start\n        JARVISH_TEST_TRACK_A=production JARVISH_TEST_TRACK_B=/venv\n     2  ls\n"
+        );
+        // --env なしではスナップショットを表示しない
+        assert_eq!(
+            format_history(&entries, ListOptions::default()),
+            "     1  npm start\n     2  ls\n"
+        );
+    }
+
+    #[test]
+    fn history_clap_parses_time() {
+        let args = HistoryArgs::try_parse_from(["history", "--time"]).unwrap();
+        assert!(args.time);
+        assert!(!args.dirs);
+
+        let args = HistoryArgs::try_parse_from(["history", "-t", "-d"]).unwrap();
+        assert!(args.time);
+        assert!(args.dirs);
+    }
+
+    #[test]
+    fn format_created_at_converts_to_timezone() {
+        let jst = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        assert_eq!(
+            format_created_at("2026-03-01T15:04:05+00:00", &jst),
+            "2026-03-02 00:04:05"
+        );
+        assert_eq!(
+            format_created_at("2026-03-01T15:04:05.123456+00:00", &chrono::Utc),
+            "2026-03-01 15:04:05"
+        );
+        // 解析できない値はそのまま表示する
+        assert_eq!(format_created_at("yesterday", &jst), "yesterday");
+    }
+
+    #[test]
+    fn history_list_shows_time_and_dirs() {
+        let created_at = "2026-03-01T15:04:05+00:00";
+        let entries = vec![HistoryRow {
+            id: 7,
+            command: "cargo test".to_string(),
+            cwd: "/work".to_string(),
+            created_at: created_at.to_string(),
+            env_json: None,
+        }];
+        let time = format_created_at(created_at, &Local);
+
+        let options = ListOptions {
+            time: true,
+            ..ListOptions::default()
+        };
+        assert_eq!(
+            format_history(&entries, options),
+            format!("     7  {time}  cargo test\n")
+        );
+
+        let options = ListOptions {
+            dirs: true,
+            time: true,
+            env: false,
+        };
+        assert_eq!(
+            format_history(&entries, options),
+            format!("     7  {time}  /work  cargo test\n")
+        );
+    }
+
+    #[test]
+    fn format_env_snapshot_handles_missing_and_invalid() {
+        assert_eq!(format_env_snapshot(None), "");
+        assert_eq!(format_env_snapshot(Some("not json")), "");
+        assert_eq!(
+            format_env_snapshot(Some(r#"{"NODE_ENV":"dev","A":"1"}"#)),
+            "A=1 NODE_ENV=dev"
+        );
+    }
+
+    /// 所要時間付きで履歴を記録する。
+    ///
+    /// `BlackBox::record` は同じコマンドの直近の行を更新するため、
+    /// 同じコマンドを複数回実行した状態は行を直接挿入して作る。
+    fn record_timed(tmp: &TempDir, entries: &[(&str, Option<u64>)]) {
+        let _bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
+        let conn = Connection::open(tmp.path().join("history.db")).unwrap();
+        for (cmd, duration_ms) in entries {
+            conn.execute(
+                "INSERT INTO command_history (command, cwd, exit_code, created_at, session_id, duration_ms) \
+                 VALUES (?1, '/tmp', 0, '2026-01-01T00:00:00+00:00', 1, ?2)",
+                rusqlite::params![cmd, duration_ms.map(|ms| ms as i64)],
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn profile_aggregates_by_command() {
+        let tmp = TempDir::new().unwrap();
+        record_timed(
+            &tmp,
+            &[
+                ("cargo build", Some(4000)),
+                ("ls", Some(10)),
+                ("cargo build", Some(2000)),
+                ("sleep 5", Some(5000)),
+                ("cd /tmp", None),
+            ],
+        );
+        let conn = Connection::open(tmp.path().join("history.db")).unwrap();
+
+        let rows = query_profile(&conn, 10, false).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].command, "sleep 5");
+        assert_eq!(rows[1].command, "cargo build");
+        assert_eq!(rows[1].runs, 2);
+        assert_eq!(rows[1].avg_ms, 3000.0);
+        assert_eq!(rows[1].max_ms, 4000);
+        assert_eq!(rows[2].command, "ls");
+    }
+
+    #[test]
+    fn profile_sorts_by_max_and_limits() {
+        let tmp = TempDir::new().unwrap();
+        record_timed(
+            &tmp,
+            &[
+                ("make", Some(100)),
+                ("make", Some(9000)),
+                ("sleep 5", Some(5000)),
+                ("ls", Some(10)),
+            ],
+        );
+        let conn = Connection::open(tmp.path().join("history.db")).unwrap();
+
+        let rows = query_profile(&conn, 2, true).unwrap();
+        let commands: Vec<&str> = rows.iter().map(|r| r.command.as_str()).collect();
+        assert_eq!(commands, vec!["make", "sleep 5"]);
+    }
+
+    #[test]
+    fn profile_format_table() {
+        let rows = vec![ProfileRow {
+            command: "cargo build".to_string(),
+            runs: 3,
+            avg_ms: 12_345.0,
+            max_ms: 125_000,
+        }];
+        let output = format_profile(&rows);
+        assert!(output.starts_with("  RUNS         AVG         MAX  COMMAND\n"));
+        assert!(output.contains("     3       12.3s       2m05s  cargo build\n"));
+        assert_eq!(format_profile(&[]), "no timing data recorded yet\n");
+    }
+
+    #[test]
+    fn format_duration_units() {
+        assert_eq!(format_duration_ms(850.4), "850ms");
+        assert_eq!(format_duration_ms(1500.0), "1.5s");
+        assert_eq!(format_duration_ms(61_000.0), "1m01s");
+    }
+
+    #[test]
+    fn history_clap_parses_profile() {
+        let args = HistoryArgs::try_parse_from(["history", "profile"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(HistoryCommand::Profile {
+                count: 10,
+                max: false
+            })
+        ));
+
+        let args = HistoryArgs::try_parse_from(["history", "profile", "-n", "5", "--max"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(HistoryCommand::Profile {
+                count: 5,
+                max: true
+            })
+        ));
+    }
+
+    /// (コマンド, 終了コード, UTC の時刻) の行を入れた履歴 DB を作る。
+    fn stats_db(entries: &[(&str, i32, &str)]) -> (TempDir, Connection) {
+        let tmp = TempDir::new().unwrap();
+        BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
+        let conn = Connection::open(tmp.path().join("history.db")).unwrap();
+        for (cmd, code, at) in entries {
+            conn.execute(
+                "INSERT INTO command_history (command, cwd, exit_code, created_at) \
+                 VALUES (?1, '/', ?2, ?3)",
+                rusqlite::params![cmd, code, at],
+            )
+            .unwrap();
+        }
+        (tmp, conn)
+    }
+
+    #[test]
+    fn stats_aggregates_commands_failures_and_hours() {
+        let (_tmp, conn) = stats_db(&[
+            ("git status", 0, "2026-03-01T09:10:00+00:00"),
+            ("git push", 1, "2026-03-01T09:20:00+00:00"),
+            ("git push", 1, "2026-03-01T09:30:00+00:00"),
+            ("cargo test", 101, "2026-03-01T22:00:00+00:00"),
+            ("ls", 0, "2026-03-01T22:05:00+00:00"),
+            ("git push", 0, "2026-03-01T23:00:00+00:00"),
+        ]);
+        let stats = query_stats(&conn, None, 10, &Utc).unwrap();
+
+        assert_eq!(stats.total, 6);
+        assert_eq!(stats.failed, 3);
+        assert_eq!(
+            stats.top_commands,
+            vec![
+                ("git".to_string(), 4),
+                ("cargo".to_string(), 1),
+                ("ls".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            stats.top_failures,
+            vec![
+                ("git push".to_string(), 2, 3),
+                ("cargo test".to_string(), 1, 1)
+            ]
+        );
+        assert_eq!(stats.by_hour[9], 3);
+        assert_eq!(stats.by_hour[22], 2);
+        assert_eq!(stats.by_hour[23], 1);
+
+        // 時間帯はタイムゾーンのローカル時刻で数える
+        let jst = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        let stats = query_stats(&conn, None, 1, &jst).unwrap();
+        assert_eq!(stats.by_hour[18], 3);
+        assert_eq!(stats.top_commands, vec![("git".to_string(), 4)]);
+
+        // 30 分単位の時差: 09:30 UTC は IST (+05:30) では 15:00
+        let ist = chrono::FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        let stats = query_stats(&conn, None, 1, &ist).unwrap();
+        assert_eq!(stats.by_hour[14], 2);
+        assert_eq!(stats.by_hour[15], 1);
+        assert_eq!(stats.by_hour[3], 2);
+        assert_eq!(stats.by_hour[4], 1);
+    }
+
+    #[test]
+    fn stats_format_table() {
+        let (_tmp, conn) = stats_db(&[
+            ("make", 0, "2026-03-01T10:00:00+00:00"),
+            ("make", 2, "2026-03-01T10:30:00.123456+00:00"),
+        ]);
+        let output = format_stats(
+            &query_stats(&conn, None, 10, &Utc).unwrap(),
+            Some("2026-03-01"),
+        );
+        assert!(output.starts_with(
+            "Commands: 2 (since 2026-03-01)\nSuccess rate: 50.0% (1 succeeded, 1 failed)\n"
+        ));
+        assert!(output.contains("\nTOP COMMANDS\n  RUNS  COMMAND\n     2  make\n"));
+        assert!(output.contains("\nTOP FAILURES\n FAILS    RUNS  COMMAND\n     1       2  make\n"));
+        assert!(output.contains(&format!("10:00  {}  2\n", "█".repeat(HOUR_BAR_WIDTH))));
+        let (_tmp, empty) = stats_db(&[]);
+        assert_eq!(
+            format_stats(&query_stats(&empty, None, 10, &Utc).unwrap(), None),
+            "no history recorded yet\n"
+        );
+    }
+
+    #[test]
+    fn stats_since_filters_by_date() {
+        let (_tmp, conn) = stats_db(&[
+            ("old", 1, "2025-12-31T00:00:00+00:00"),
+            ("new", 0, "2026-03-02T00:00:00+00:00"),
+        ]);
+
+        let stats = query_stats(&conn, Some("2026-01-01T00:00:00+00:00"), 10, &Utc).unwrap();
+        assert_eq!(stats.total, 1);
+        assert_eq!(stats.failed, 0);
+        assert_eq!(stats.top_commands, vec![("new".to_string(), 1)]);
+        assert!(stats.top_failures.is_empty());
+        assert_eq!(query_stats(&conn, None, 10, &Utc).unwrap().total, 2);
+
+        assert!(parse_since_date("2026-01-01").is_ok());
+        assert_eq!(
+            parse_since_date("01/01/2026").unwrap_err(),
+            "jarvish: history: invalid date '01/01/2026': expected YYYY-MM-DD\n"
+        );
+    }
+
+    #[test]
+    fn history_clap_parses_stats() {
+        let args =
+            HistoryArgs::try_parse_from(["history", "stats", "--since", "2026-01-01"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(HistoryCommand::Stats { count: 10, since: Some(ref d) }) if d == "2026-01-01"
+        ));
+    }
+
+    #[test]
+    fn history_clap_parses_run() {
+        let args = HistoryArgs::try_parse_from(["history", "run", "42"]).unwrap();
+        assert!(matches!(args.command, Some(HistoryCommand::Run { id: 42 })));
+        assert!(HistoryArgs::try_parse_from(["history", "run"]).is_err());
+        assert!(HistoryArgs::try_parse_from(["history", "run", "abc"]).is_err());
+    }
+
+    #[test]
+    fn query_command_finds_entry_by_id() {
+        let tmp = setup_test_db(&["echo hello", "ls -la"]);
+        let conn = Connection::open(tmp.path().join("history.db")).unwrap();
+
+        assert_eq!(query_command(&conn, 2).unwrap().as_deref(), Some("ls -la"));
+        assert_eq!(query_command(&conn, 99).unwrap(), None);
+    }
+
+    #[test]
+    fn parse_run_request_accepts_only_standalone_form() {
+        assert_eq!(parse_run_request("history run 42"), Some(42));
+        assert_eq!(parse_run_request("  history  run 7 "), Some(7));
+        assert_eq!(parse_run_request("history run abc"), None);
+        assert_eq!(parse_run_request("history run 3 && ls"), None);
+        assert_eq!(parse_run_request("history -n 3"), None);
+    }
+
+    #[test]
+    fn history_run_entries_are_not_re_run() {
+        assert!(is_history_run("history run 3"));
+        assert!(is_history_run("  history   run 3"));
+        assert!(!is_history_run("history -n 3"));
+        assert!(!is_history_run("echo history run"));
+    }
+
+    #[test]
+    fn collect_orphan_blobs_keeps_referenced_outputs() {
+        let tmp = TempDir::new().unwrap();
+        let bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
+        for (cmd, stdout) in [("echo kept", "kept\n"), ("echo dropped", "dropped\n")] {
+            let result = crate::engine::CommandResult::success(stdout.to_string());
+            bb.record(cmd, &result, None).unwrap();
+        }
+        let conn = Connection::open(tmp.path().join("history.db")).unwrap();
+        let blob_dir = tmp.path().join("blobs");
+
+        // すべて参照されている間は何も消さない
+        let stats = collect_orphan_blobs(&conn, &blob_dir, Duration::ZERO).unwrap();
+        assert_eq!(stats.removed, 0);
+
+        conn.execute(
+            "DELETE FROM command_history WHERE command = 'echo dropped'",
+            [],
+        )
+        .unwrap();
+        let stats = collect_orphan_blobs(&conn, &blob_dir, Duration::ZERO).unwrap();
+        assert_eq!(stats.removed, 1);
+        assert!(stats.freed_bytes > 0);
+
+        let store = BlobStore::new(blob_dir).unwrap();
+        let remaining = store.list_hashes().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(store.load(&remaining[0]).unwrap(), "kept\n");
+    }
+
+    #[test]
+    fn format_gc_stats_and_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MB");
+        assert_eq!(
+            format_gc_stats(&GcStats {
+                removed: 1,
+                freed_bytes: 100,
+            }),
+            "removed 1 blob (100 B)"
+        );
+    }
+
+    #[test]
+    fn history_clap_parses_gc() {
+        let args = HistoryArgs::try_parse_from(["history", "gc"]).unwrap();
+        assert!(matches!(args.command, Some(HistoryCommand::Gc)));
+    }
+
     #[test]
     fn history_clap_parses_clear() {
         let args = HistoryArgs::try_parse_from(["history", "clear"]).unwrap();
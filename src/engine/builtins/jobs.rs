@@ -0,0 +1,74 @@
+//! jobs / fg: Ctrl+Z で停止したジョブの一覧表示と再開
+//!
+//! 停止したジョブは [`crate::engine::exec::jobs`] のジョブテーブルが保持する。
+
+use clap::Parser;
+
+use crate::engine::exec::{self, jobs};
+use crate::engine::CommandResult;
+
+/// jobs: 停止中のジョブを一覧表示する。
+#[derive(Parser)]
+#[command(name = "jobs", about = "List stopped jobs")]
+struct JobsArgs {}
+
+/// fg: 停止中のジョブをフォアグラウンドで再開する。
+#[derive(Parser)]
+#[command(name = "fg", about = "Resume a stopped job in the foreground")]
+struct FgArgs {
+    /// Job to resume: %N, N, %%, %+, %- or %<command prefix> (default: current job)
+    job: Option<String>,
+}
+
+/// jobs: `[1]+  Stopped  vim foo.rs` 形式で停止中のジョブを出力する。
+pub(super) fn execute_jobs(args: &[&str]) -> CommandResult {
+    if let Err(result) = super::parse_args::<JobsArgs>("jobs", args) {
+        return result;
+    }
+
+    let output: String = jobs::list()
+        .iter()
+        .map(|job| format!("[{}]{}  Stopped  {}\n", job.id, job.marker, job.command))
+        .collect();
+    CommandResult::success(output)
+}
+
+/// fg: 指定したジョブ（省略時はカレントジョブ）を再開し、終了または再停止まで待つ。
+pub(super) fn execute_fg(args: &[&str]) -> CommandResult {
+    let parsed = match super::parse_args::<FgArgs>("fg", args) {
+        Ok(a) => a,
+        Err(result) => return result,
+    };
+
+    match jobs::take(parsed.job.as_deref()) {
+        Ok(session) => exec::resume(session),
+        Err(e) => {
+            let msg = format!("jarvish: fg: {e}\n");
+            eprint!("{msg}");
+            CommandResult::error(msg, 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_is_empty_without_stopped_jobs() {
+        let result = execute_jobs(&[]);
+        assert_eq!(result.exit_code, 0);
+        assert!(result.stdout.is_empty());
+    }
+
+    #[test]
+    fn fg_without_jobs_is_error() {
+        let result = execute_fg(&[]);
+        assert_eq!(result.exit_code, 1);
+        assert_eq!(result.stderr, "jarvish: fg: no current job\n");
+
+        let result = execute_fg(&["%3"]);
+        assert_eq!(result.exit_code, 1);
+        assert_eq!(result.stderr, "jarvish: fg: %3: no such job\n");
+    }
+}
@@ -1,30 +1,41 @@
+pub(crate) mod ai;
 pub(crate) mod alias;
+pub(crate) mod bookmark;
+pub(crate) mod capture;
 pub(crate) mod cd;
 pub(crate) mod cdhist;
 pub(crate) mod cdj;
 pub(crate) mod complete;
-mod cwd;
+pub(crate) mod cwd;
 pub(crate) mod dirstack;
-mod exit;
+pub(crate) mod exit;
 mod export;
 mod help;
-mod history;
+pub(crate) mod history;
+mod jobs;
 mod restart;
 pub(crate) mod source;
+mod timeout;
 pub(crate) mod unalias;
 mod unset;
 pub(crate) mod update;
 pub(crate) mod which_type;
 mod wrapper;
 
-use super::CommandResult;
+use super::{CommandResult, ExecContext};
 
 /// ビルトインコマンドの名前と説明の一覧（アルファベット順）。
 ///
 /// `is_builtin` の受理判定・`help` の一覧表示・補完エンジンが共通で参照する
 /// 単一の情報源（single source of truth）。
 pub(crate) const BUILTIN_COMMANDS: &[(&str, &str)] = &[
+    ("ai", "Switch or list AI profiles"),
     ("alias", "Set or display aliases"),
+    ("bookmark", "Add, list, or remove directory bookmarks"),
+    (
+        "capture",
+        "Run a command and store its output in a shell variable",
+    ),
     ("cd", "Change the current directory"),
     ("cdhist", "Print recently visited directories (LRU)"),
     ("cdj", "Jump to a directory from cd history via fzf"),
@@ -33,8 +44,12 @@ pub(crate) const BUILTIN_COMMANDS: &[(&str, &str)] = &[
     ("dirs", "Display directory stack"),
     ("exit", "Exit the shell"),
     ("export", "Set or display environment variables"),
+    ("fg", "Resume a stopped job in the foreground"),
     ("help", "Display help for builtin commands"),
     ("history", "Display or manage command history"),
+    ("jobs", "List stopped jobs"),
+    ("jump", "Change to a bookmarked directory"),
+    ("logout", "Exit the shell (alias of exit)"),
     ("popd", "Pop directory from stack and change to it"),
     ("pushd", "Push directory onto stack and change to it"),
     ("pwd", "Print the current working directory (alias of cwd)"),
@@ -43,17 +58,21 @@ pub(crate) const BUILTIN_COMMANDS: &[(&str, &str)] = &[
         "source",
         "Reload a config file (.toml) or run a script (rc-style, any other extension)",
     ),
+    (
+        "timeout",
+        "Run a command with a time limit (exit 124 on timeout)",
+    ),
     ("type", "Display information about command type"),
     ("unalias", "Remove aliases"),
     ("unset", "Remove environment variables"),
     ("update", "Update jarvish to the latest version"),
-    ("which", "Locate a command (builtin, alias, or external)"),
+    ("which", "Locate an external command in PATH"),
 ];
 
 /// clap の `try_parse_from` を使って引数をパースする共通ヘルパー。
 ///
 /// - パース成功 → `Ok(T)`
-/// - `--help` → stdout に出力し `Err(CommandResult::success(...))`
+/// - `--help` → ヘルプを stdout として `Err(CommandResult::success(...))`
 /// - 引数エラー → stderr に出力し `Err(CommandResult::error(..., 2))`
 fn parse_args<T: clap::Parser>(cmd: &str, args: &[&str]) -> Result<T, CommandResult> {
     T::try_parse_from(std::iter::once(cmd).chain(args.iter().copied())).map_err(|e| {
@@ -62,33 +81,115 @@ fn parse_args<T: clap::Parser>(cmd: &str, args: &[&str]) -> Result<T, CommandRes
             eprint!("{msg}");
             CommandResult::error(msg, 2)
         } else {
-            print!("{msg}");
             CommandResult::success(msg)
         }
     })
 }
 
+/// `args` の先頭 `count` 個だけを clap でパースし、残りの引数は解釈せずにそのまま返す。
+///
+/// コマンドをラップするビルトイン（`timeout` / `capture`）で、ラップしたコマンドの
+/// `-h` / `--help` 等を自身のオプションとして解釈しないようにする。
+fn parse_leading_args<'a, T: clap::Parser>(
+    cmd: &str,
+    args: &'a [&'a str],
+    count: usize,
+) -> Result<(T, &'a [&'a str]), CommandResult> {
+    let (head, rest) = args.split_at(args.len().min(count));
+    parse_args(cmd, head).map(|parsed| (parsed, rest))
+}
+
 /// 指定されたコマンド名がビルトインかどうかを判定する（軽量チェック用）。
 pub fn is_builtin(cmd: &str) -> bool {
     BUILTIN_COMMANDS.iter().any(|(name, _)| *name == cmd)
 }
 
-/// ビルトインコマンドを振り分ける。
+/// `cmd args...` がビルトインの呼び出しか。
+///
+/// `ai` は `ai use <name>` 等のプロファイル操作（[`ai::is_profile_command`]）のときだけ
+/// ビルトインとし、`ai explain this error` のような入力は AI への呼びかけとして扱う。
+pub fn is_builtin_call(cmd: &str, args: &[&str]) -> bool {
+    match cmd {
+        "ai" => ai::is_profile_command(args),
+        "timeout" => timeout::is_plain_form(args),
+        _ => is_builtin(cmd),
+    }
+}
+
+/// パイプラインの一段として使われたときは外部コマンドとして実行するビルトインかどうか。
+///
+/// `timeout` は単独で使われたときだけビルトインとし、パイプライン中では
+/// 出力を逐次流せるよう coreutils の `timeout` を使う。
+pub fn defers_to_external_in_pipeline(cmd: &str) -> bool {
+    cmd == "timeout"
+}
+
+/// パイプラインの 2 段目以降でも実行できる（stdin を読まず、出力だけを持つ）ビルトインかどうか。
+///
+/// パイプライン中のビルトインは先行実行して出力を `printf` に置き換えるため、
+/// シェルの状態を変更するもの（`cd`, `export KEY=VALUE`, `history clear` / `history run` / `history gc`,
+/// `bookmark add` 等）や
+/// Shell 側の状態（エイリアス・ディレクトリスタック）に依存するものは対象外とする。
+pub fn is_pipe_output_builtin(cmd: &str, args: &[&str]) -> bool {
+    match cmd {
+        "cwd" | "pwd" | "cdhist" | "help" | "which" => true,
+        "bookmark" => !args.iter().any(|a| matches!(*a, "add" | "remove")),
+        "history" => !args.iter().any(|a| matches!(*a, "clear" | "run" | "gc")),
+        "export" => !args.iter().any(|a| a.contains('=') || *a == "--persist"),
+        _ => false,
+    }
+}
+
+/// ビルトインの `--help` を実行する（`help <command>` 用）。
+///
+/// `timeout --help` は外部の `timeout` に任せるため、ビルトインのヘルプは直接呼び出す。
+pub(super) fn builtin_help(cmd: &str) -> Option<CommandResult> {
+    let ctx = ExecContext::default();
+    match cmd {
+        "timeout" => Some(timeout::execute(&["--help"], &ctx)),
+        _ => run_builtin(cmd, &["--help"], &ctx),
+    }
+}
+
+/// ビルトインコマンドを振り分けて実行し、stdout を表示する。
 /// ビルトインでない場合は `None` を返し、呼び出し元が外部コマンドとして実行する。
-pub fn dispatch_builtin(cmd: &str, args: &[&str]) -> Option<CommandResult> {
+///
+/// ビルトインは出力を `CommandResult.stdout` に返すだけで自身では表示しない（エラーは
+/// 各ビルトインが stderr に表示する）。`ctx.capture_stdout` なら表示せずに返す。
+/// 外部コマンドを実行する `timeout` は、出力を実行中に表示済みのため再表示しない。
+pub fn dispatch_builtin(cmd: &str, args: &[&str], ctx: &ExecContext) -> Option<CommandResult> {
+    let result = run_builtin(cmd, args, ctx)?;
+    if !ctx.capture_stdout && cmd != "timeout" {
+        print!("{}", result.stdout);
+    }
+    Some(result)
+}
+
+/// ビルトインコマンドを振り分けて実行する（stdout は表示しない）。
+fn run_builtin(cmd: &str, args: &[&str], ctx: &ExecContext) -> Option<CommandResult> {
     match cmd {
+        "ai" if ai::is_profile_command(args) => Some(ai::execute_standalone_only(args)),
         "alias" => Some(alias::execute_with_aliases(
             args,
             &mut std::collections::HashMap::new(),
         )),
-        "cd" => Some(cd::execute(args, &mut Vec::new())),
+        "bookmark" => Some(bookmark::execute(args)),
+        "capture" => Some(capture::execute(args, ctx)),
+        "cd" => Some(cd::execute(args, &mut Vec::new(), ctx.cd_to_file_parent)),
         "cdhist" => Some(cdhist::execute(args)),
         "cdj" => Some(cdj::execute_stub(args)),
         "complete" => Some(complete::execute_standalone_only(args)),
         "cwd" | "pwd" => Some(cwd::execute(args)),
         "dirs" => Some(dirstack::execute_dirs(args, &mut Vec::new())),
-        "exit" => Some(exit::execute(args)),
-        "export" => Some(export::execute(args)),
+        "exit" | "logout" => Some(exit::execute(args)),
+        "export" => Some(export::execute(args, &ctx.variables)),
+        "fg" => Some(jobs::execute_fg(args)),
+        "jobs" => Some(jobs::execute_jobs(args)),
+        "jump" => Some(bookmark::execute_jump(
+            args,
+            &mut Vec::new(),
+            ctx.cd_to_file_parent,
+        )),
         "help" => Some(help::execute(args)),
         "unalias" => Some(unalias::execute_with_aliases(
             args,
@@ -99,14 +200,12 @@ pub fn dispatch_builtin(cmd: &str, args: &[&str]) -> Option<CommandResult> {
         }
         "pushd" => Some(dirstack::execute_pushd(args, &mut Vec::new())),
         "popd" => Some(dirstack::execute_popd(args, &mut Vec::new())),
-        "unset" => Some(unset::execute(args)),
-        "history" => Some(history::execute(args)),
+        "timeout" if timeout::is_plain_form(args) => Some(timeout::execute(args, ctx)),
+        "unset" => Some(unset::execute(args, &ctx.variables)),
+        "history" => Some(history::execute(args, ctx)),
         "restart" => Some(restart::execute(args)),
         "update" => Some(update::execute(args)),
-        "which" => Some(which_type::execute_which(
-            args,
-            &std::collections::HashMap::new(),
-        )),
+        "which" => Some(which_type::execute_which(args, None)),
         "type" => Some(which_type::execute_type(
             args,
             &std::collections::HashMap::new(),
@@ -123,10 +222,42 @@ mod tests {
     use std::env;
     use std::path::PathBuf;
 
+    #[test]
+    fn pipe_output_builtins_exclude_state_changes() {
+        assert!(is_pipe_output_builtin("cwd", &[]));
+        assert!(is_pipe_output_builtin("history", &["-n", "10"]));
+        assert!(is_pipe_output_builtin("export", &["PATH"]));
+        assert!(!is_pipe_output_builtin("history", &["clear"]));
+        assert!(!is_pipe_output_builtin("history", &["run", "3"]));
+        assert!(!is_pipe_output_builtin("history", &["gc"]));
+        assert!(is_pipe_output_builtin("bookmark", &["list"]));
+        assert!(!is_pipe_output_builtin("bookmark", &["add", "work"]));
+        assert!(!is_pipe_output_builtin("export", &["FOO=1"]));
+        assert!(!is_pipe_output_builtin("export", &["--persist", "FOO"]));
+        assert!(!is_pipe_output_builtin("cd", &["/tmp"]));
+        assert!(!is_pipe_output_builtin("alias", &[]));
+    }
+
+    #[test]
+    fn timeout_with_options_is_left_to_external_command() {
+        assert!(is_builtin_call("timeout", &["5", "sleep", "1"]));
+        assert!(!is_builtin_call(
+            "timeout",
+            &["-s", "KILL", "5", "sleep", "1"]
+        ));
+        assert!(run_builtin(
+            "timeout",
+            &["--preserve-status", "5", "true"],
+            &ExecContext::default()
+        )
+        .is_none());
+        assert!(builtin_help("timeout").is_some());
+    }
+
     #[test]
     fn unknown_command_returns_none() {
-        assert!(dispatch_builtin("ls", &[]).is_none());
-        assert!(dispatch_builtin("git", &["status"]).is_none());
+        assert!(dispatch_builtin("ls", &[], &ExecContext::default()).is_none());
+        assert!(dispatch_builtin("git", &["status"], &ExecContext::default()).is_none());
     }
 
     // ── cd + cwd 結合テスト ──
@@ -139,11 +270,12 @@ mod tests {
         let target = tmpdir.path().to_path_buf();
 
         // cd で移動
-        let cd_result = dispatch_builtin("cd", &[target.to_str().unwrap()]).unwrap();
+        let cd_result =
+            dispatch_builtin("cd", &[target.to_str().unwrap()], &ExecContext::default()).unwrap();
         assert_eq!(cd_result.exit_code, 0);
 
         // cwd が移動先を返すことを検証
-        let cwd_result = dispatch_builtin("cwd", &[]).unwrap();
+        let cwd_result = dispatch_builtin("cwd", &[], &ExecContext::default()).unwrap();
         assert_eq!(cwd_result.exit_code, 0);
         assert_eq!(
             PathBuf::from(cwd_result.stdout.trim())
@@ -160,11 +292,16 @@ mod tests {
         let before = env::current_dir().unwrap();
 
         // 存在しないパスへの cd は失敗する
-        let cd_result = dispatch_builtin("cd", &["/nonexistent_path_that_does_not_exist"]).unwrap();
+        let cd_result = dispatch_builtin(
+            "cd",
+            &["/nonexistent_path_that_does_not_exist"],
+            &ExecContext::default(),
+        )
+        .unwrap();
         assert_ne!(cd_result.exit_code, 0);
 
         // cwd は cd 前と同じディレクトリを返すことを検証
-        let cwd_result = dispatch_builtin("cwd", &[]).unwrap();
+        let cwd_result = dispatch_builtin("cwd", &[], &ExecContext::default()).unwrap();
         assert_eq!(cwd_result.exit_code, 0);
         assert_eq!(
             PathBuf::from(cwd_result.stdout.trim())
@@ -182,16 +319,26 @@ mod tests {
         let dir2 = tempfile::tempdir().expect("failed to create tempdir");
 
         // 1回目の cd
-        dispatch_builtin("cd", &[dir1.path().to_str().unwrap()]).unwrap();
-        let cwd1 = dispatch_builtin("cwd", &[]).unwrap();
+        dispatch_builtin(
+            "cd",
+            &[dir1.path().to_str().unwrap()],
+            &ExecContext::default(),
+        )
+        .unwrap();
+        let cwd1 = dispatch_builtin("cwd", &[], &ExecContext::default()).unwrap();
         assert_eq!(
             PathBuf::from(cwd1.stdout.trim()).canonicalize().unwrap(),
             dir1.path().canonicalize().unwrap()
         );
 
         // 2回目の cd（別のディレクトリへ）
-        dispatch_builtin("cd", &[dir2.path().to_str().unwrap()]).unwrap();
-        let cwd2 = dispatch_builtin("cwd", &[]).unwrap();
+        dispatch_builtin(
+            "cd",
+            &[dir2.path().to_str().unwrap()],
+            &ExecContext::default(),
+        )
+        .unwrap();
+        let cwd2 = dispatch_builtin("cwd", &[], &ExecContext::default()).unwrap();
         assert_eq!(
             PathBuf::from(cwd2.stdout.trim()).canonicalize().unwrap(),
             dir2.path().canonicalize().unwrap()
@@ -205,9 +352,9 @@ mod tests {
     fn pwd_is_alias_for_cwd() {
         let _guard = CwdGuard::new();
         assert!(is_builtin("pwd"));
-        let pwd_result = dispatch_builtin("pwd", &[]).unwrap();
+        let pwd_result = dispatch_builtin("pwd", &[], &ExecContext::default()).unwrap();
         assert_eq!(pwd_result.exit_code, 0);
-        let cwd_result = dispatch_builtin("cwd", &[]).unwrap();
+        let cwd_result = dispatch_builtin("cwd", &[], &ExecContext::default()).unwrap();
         assert_eq!(pwd_result.stdout, cwd_result.stdout);
     }
 
@@ -228,9 +375,9 @@ mod tests {
     #[test]
     fn new_builtins_dispatch_returns_some() {
         // export（引数なし → 全変数表示、正常終了するはず）
-        assert!(dispatch_builtin("export", &[]).is_some());
+        assert!(dispatch_builtin("export", &[], &ExecContext::default()).is_some());
         // history --help → 正常終了
-        assert!(dispatch_builtin("history", &["--help"]).is_some());
+        assert!(dispatch_builtin("history", &["--help"], &ExecContext::default()).is_some());
     }
 
     #[test]
@@ -243,7 +390,7 @@ mod tests {
         // dispatch_builtin 経由（pipeline / command list / ai_pipe 等）では
         // 実レジストリへのアクセスがないため、引数なし（一覧表示相当）は
         // 使い捨てレジストリを黙って操作せず、明確なエラーを返す（#89 A1）。
-        let result = dispatch_builtin("complete", &[]).unwrap();
+        let result = dispatch_builtin("complete", &[], &ExecContext::default()).unwrap();
         assert_ne!(result.exit_code, 0);
         assert!(result.stderr.contains("standalone command"));
         assert_eq!(result.stdout, "");
@@ -251,22 +398,28 @@ mod tests {
 
     #[test]
     fn complete_dispatch_stub_rejects_register() {
-        let result = dispatch_builtin("complete", &["-c", "mycmd", "-s", "v"]).unwrap();
+        let result = dispatch_builtin(
+            "complete",
+            &["-c", "mycmd", "-s", "v"],
+            &ExecContext::default(),
+        )
+        .unwrap();
         assert_ne!(result.exit_code, 0);
         assert!(result.stderr.contains("standalone command"));
     }
 
     #[test]
     fn complete_dispatch_stub_rejects_erase() {
-        let result = dispatch_builtin("complete", &["-e", "-c", "mycmd"]).unwrap();
+        let result =
+            dispatch_builtin("complete", &["-e", "-c", "mycmd"], &ExecContext::default()).unwrap();
         assert_ne!(result.exit_code, 0);
         assert!(result.stderr.contains("standalone command"));
     }
 
     #[test]
     fn complete_dispatch_stub_help_still_works() {
-        // help.rs の `dispatch_builtin(cmd, ["--help"])` 委譲が壊れないことを保証。
-        let result = dispatch_builtin("complete", &["--help"]).unwrap();
+        // help.rs の `run_builtin(cmd, ["--help"])` 委譲が壊れないことを保証。
+        let result = dispatch_builtin("complete", &["--help"], &ExecContext::default()).unwrap();
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("complete"));
     }
@@ -282,7 +435,7 @@ mod tests {
     #[test]
     fn dispatch_cdhist_returns_some() {
         // --help は確実に成功するため、それで Some が返ることを確認
-        let result = dispatch_builtin("cdhist", &["--help"]);
+        let result = dispatch_builtin("cdhist", &["--help"], &ExecContext::default());
         assert!(result.is_some());
         assert_eq!(result.unwrap().exit_code, 0);
     }
@@ -290,7 +443,8 @@ mod tests {
     #[test]
     fn dispatch_cdj_returns_interactive_required_stub() {
         // dispatch 経由では cdj はスタブのエラーを返す
-        let result = dispatch_builtin("cdj", &[]).expect("cdj should be registered");
+        let result = dispatch_builtin("cdj", &[], &ExecContext::default())
+            .expect("cdj should be registered");
         assert_ne!(result.exit_code, 0);
         assert!(result.stderr.contains("requires interactive shell"));
     }
@@ -298,13 +452,28 @@ mod tests {
     #[test]
     fn dispatch_cdj_help_still_works() {
         // --help はスタブ前に clap で処理されるため成功する
-        let result = dispatch_builtin("cdj", &["--help"]).expect("cdj should be registered");
+        let result = dispatch_builtin("cdj", &["--help"], &ExecContext::default())
+            .expect("cdj should be registered");
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("cdj"));
     }
 
     // ── BUILTIN_COMMANDS 一元化テーブルの検証 ──
 
+    #[test]
+    fn is_builtin_call_limits_ai_to_profile_subcommands() {
+        assert!(is_builtin_call("ai", &["use", "fast"]));
+        assert!(is_builtin_call("ai", &["profiles"]));
+        assert!(is_builtin_call("ai", &[]));
+        assert!(!is_builtin_call("ai", &["explain", "this", "error"]));
+        assert!(
+            dispatch_builtin("ai", &["explain", "this", "error"], &ExecContext::default())
+                .is_none()
+        );
+        assert!(is_builtin_call("cd", &["/tmp"]));
+        assert!(!is_builtin_call("ls", &[]));
+    }
+
     #[test]
     fn is_builtin_accepts_exact_previous_name_list() {
         // is_builtin が旧来受理していた21コマンドすべてを引き続き受理することを確認
@@ -329,7 +498,7 @@ mod tests {
 
     #[test]
     fn builtin_commands_table_is_sorted_and_unique() {
-        assert_eq!(BUILTIN_COMMANDS.len(), 21);
+        assert_eq!(BUILTIN_COMMANDS.len(), 29);
 
         let mut names: Vec<&str> = BUILTIN_COMMANDS.iter().map(|(name, _)| *name).collect();
         let sorted_names = {
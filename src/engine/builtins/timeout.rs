@@ -0,0 +1,189 @@
+//! timeout: 制限時間を超えたコマンドを終了させる
+//!
+//! `timeout <duration> <command...>` で `command...` を実行し、`duration` を超えたら
+//! `SIGTERM` →（猶予後も終了しなければ）`SIGKILL` で終了させて終了コード 124 を返す
+//! （GNU `timeout` の慣例）。`[shell] default_command_timeout` と異なり、vim / less 等の
+//! 対話コマンド（PTY セッション）にも適用する。
+//!
+//! ビルトインとして扱うのは単独の `timeout DURATION COMMAND...` の形（[`is_plain_form`]）だけ。
+//! `-s KILL` / `-k 5` / `--preserve-status` 等のオプション付きや、パイプラインの一段・
+//! リダイレクト付きで使われた場合は coreutils の `timeout` を外部コマンドとして実行する。
+
+use std::time::Duration;
+
+use clap::Parser;
+
+use crate::engine::parser::{self, Word};
+use crate::engine::{exec, CommandResult, ExecContext};
+
+/// timeout: 制限時間付きでコマンドを実行する。
+///
+/// clap には DURATION だけを渡し、COMMAND 以降はオプションとして解釈しない
+/// （`timeout 5 grep -h` の `-h` は `grep` の引数）。
+#[derive(Parser)]
+#[command(
+    name = "timeout",
+    about = "Run a command with a time limit (exit 124 on timeout)",
+    override_usage = "timeout <DURATION> <COMMAND> [ARGS]..."
+)]
+struct TimeoutArgs {
+    /// Time limit: seconds, or a number with an s/m/h/d suffix (e.g. 1.5, 30s, 2m)
+    duration: String,
+}
+
+/// timeout: `command args...` を `duration` の制限時間付きで実行する。
+///
+/// - 引数はシェルで展開済みのため、再展開せずにそのまま渡す
+/// - ビルトインは子プロセスを持たず終了させられないため対象外（エラーにする）
+/// - 出力をキャプチャする場合（`ctx.capture_stdout`）は表示せずに実行する（制限時間は適用しない）
+pub(super) fn execute(args: &[&str], ctx: &ExecContext) -> CommandResult {
+    let (parsed, command) = match super::parse_leading_args::<TimeoutArgs>("timeout", args, 1) {
+        Ok(a) => a,
+        Err(result) => return result,
+    };
+
+    let Some(limit) = parse_duration(&parsed.duration) else {
+        return error(format!("invalid time interval '{}'", parsed.duration));
+    };
+    let Some(name) = command.first() else {
+        return error("missing command".to_string());
+    };
+    if super::is_builtin(name) {
+        return error(format!("{name}: builtins cannot be run with a timeout"));
+    }
+
+    let words: Vec<Word> = command.iter().map(|arg| Word::literal(*arg)).collect();
+    let pipeline = match parser::parse_pipeline(words) {
+        Ok(pipeline) => pipeline,
+        Err(e) => return error(format!("parse error: {e}")),
+    };
+
+    if ctx.capture_stdout {
+        return exec::run_pipeline_captured(&pipeline, ctx);
+    }
+    let timeout_ctx = ExecContext {
+        explicit_timeout: Some(limit),
+        ..ctx.clone()
+    };
+    exec::run_pipeline(&pipeline, &timeout_ctx)
+}
+
+/// `timeout DURATION COMMAND...`（オプションなし）の形か。
+///
+/// `false` の場合はビルトインとして扱わず、外部の `timeout` に任せる。
+pub(super) fn is_plain_form(args: &[&str]) -> bool {
+    matches!(args, [duration, _, ..] if parse_duration(duration).is_some())
+}
+
+/// 制限時間をパースする（`1.5` / `30s` / `2m` / `1h` / `1d`）。0 以下・不正な値は `None`。
+fn parse_duration(s: &str) -> Option<Duration> {
+    let (number, unit) = match s.char_indices().last()? {
+        (i, 's') => (&s[..i], 1.0),
+        (i, 'm') => (&s[..i], 60.0),
+        (i, 'h') => (&s[..i], 3600.0),
+        (i, 'd') => (&s[..i], 86400.0),
+        _ => (s, 1.0),
+    };
+    let secs = number.parse::<f64>().ok()? * unit;
+    if secs <= 0.0 {
+        return None;
+    }
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+/// `jarvish: timeout: {msg}` を stderr に出力し、エラー結果を返す。
+fn error(msg: String) -> CommandResult {
+    let msg = format!("jarvish: timeout: {msg}\n");
+    eprint!("{msg}");
+    CommandResult::error(msg, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::time::Instant;
+
+    fn run(args: &[&str]) -> CommandResult {
+        execute(args, &ExecContext::default())
+    }
+
+    #[test]
+    fn parse_duration_accepts_suffixes_and_fractions() {
+        assert_eq!(parse_duration("5"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_duration("1.5"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("1d"), Some(Duration::from_secs(86400)));
+        for invalid in ["", "0", "-1", "abc", "5x", "s", "NaN", "inf"] {
+            assert_eq!(parse_duration(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn only_plain_form_is_handled_as_builtin() {
+        assert!(is_plain_form(&["5", "sleep", "1"]));
+        assert!(is_plain_form(&["1.5m", "make", "-j4"]));
+        assert!(!is_plain_form(&["-s", "KILL", "5", "sleep", "1"]));
+        assert!(!is_plain_form(&["-k", "5", "10", "make"]));
+        assert!(!is_plain_form(&["--preserve-status", "5", "make"]));
+        assert!(!is_plain_form(&["5"]));
+        assert!(!is_plain_form(&["--help"]));
+    }
+
+    #[test]
+    #[serial]
+    fn kills_command_exceeding_limit_with_exit_code_124() {
+        let started = Instant::now();
+        let result = run(&["0.2", "sleep", "5"]);
+        assert_eq!(result.exit_code, 124);
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    #[serial]
+    fn passes_output_and_exit_code_through_within_limit() {
+        let result = run(&["5", "sh", "-c", "echo done; exit 3"]);
+        assert_eq!(result.exit_code, 3);
+        assert_eq!(result.stdout.trim(), "done");
+    }
+
+    #[test]
+    #[serial]
+    fn wrapped_command_help_flags_are_passed_through() {
+        let result = run(&["5", "echo", "-h", "hi"]);
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout.trim(), "-h hi");
+
+        let result = run(&["5", "sh", "-c", "echo \"$1\"", "sh", "--help"]);
+        assert_eq!(result.stdout.trim(), "--help");
+
+        // パターンのない `grep -h` は grep 自身の使い方エラーになる
+        let result = run(&["5", "grep", "-h"]);
+        assert_eq!(result.exit_code, 2);
+        assert!(!result.stdout.contains("Usage: timeout"));
+
+        // timeout 自身のヘルプは先頭の --help のみ
+        let result = run(&["--help"]);
+        assert!(result
+            .stdout
+            .contains("Usage: timeout <DURATION> <COMMAND>"));
+    }
+
+    #[test]
+    fn rejects_invalid_duration_and_builtins() {
+        let result = run(&["soon", "sleep", "1"]);
+        assert_eq!(result.exit_code, 1);
+        assert_eq!(
+            result.stderr,
+            "jarvish: timeout: invalid time interval 'soon'\n"
+        );
+
+        let result = run(&["5", "cd", "/"]);
+        assert_eq!(
+            result.stderr,
+            "jarvish: timeout: cd: builtins cannot be run with a timeout\n"
+        );
+    }
+}
@@ -2,7 +2,8 @@ use std::env;
 
 use clap::Parser;
 
-use crate::engine::CommandResult;
+use crate::engine::shell_vars::ShellVariables;
+use crate::engine::{persist_env, CommandResult};
 
 /// unset: 環境変数を削除する。
 #[derive(Parser)]
@@ -14,22 +15,32 @@ struct UnsetArgs {
 }
 
 /// unset: 環境変数を削除する。
-/// - `unset VAR [VAR2 ...]` → 指定された変数を削除
+/// - `unset VAR [VAR2 ...]` → 指定された変数を削除（シェル変数・環境変数の両方）
 /// - `unset` (引数なし) → clap がエラー表示
 /// - 存在しない変数の unset はサイレントに成功 (bash 互換)
-pub(super) fn execute(args: &[&str]) -> CommandResult {
+/// - `[env] persist = true` なら永続化ファイル（`env.toml`）からも削除する
+pub(super) fn execute(args: &[&str], vars: &ShellVariables) -> CommandResult {
     let parsed = match super::parse_args::<UnsetArgs>("unset", args) {
         Ok(a) => a,
         Err(result) => return result,
     };
 
     for name in &parsed.names {
+        vars.remove(name);
         // SAFETY: シェルプロセス内でシングルスレッドで呼ばれるため安全
         unsafe {
             env::remove_var(name);
         }
     }
 
+    if persist_env::is_enabled() {
+        if let Err(e) = persist_env::forget_vars(&persist_env::default_path(), &parsed.names) {
+            let msg = format!("jarvish: unset: {e}\n");
+            eprint!("{msg}");
+            return CommandResult::error(msg, 1);
+        }
+    }
+
     CommandResult::success(String::new())
 }
 
@@ -76,11 +87,20 @@ mod tests {
         }
         assert!(env::var("JARVISH_TEST_UNSET").is_ok());
 
-        let result = execute(&["JARVISH_TEST_UNSET"]);
+        let result = execute(&["JARVISH_TEST_UNSET"], &ShellVariables::default());
         assert_eq!(result.exit_code, 0);
         assert!(env::var("JARVISH_TEST_UNSET").is_err());
     }
 
+    #[test]
+    fn unset_removes_shell_variable() {
+        let vars = ShellVariables::default();
+        vars.set("jarvish_test_unset_local", "v");
+        let result = execute(&["jarvish_test_unset_local"], &vars);
+        assert_eq!(result.exit_code, 0);
+        assert!(vars.lookup("jarvish_test_unset_local").is_none());
+    }
+
     #[test]
     #[serial]
     fn unset_multiple_variables() {
@@ -91,7 +111,10 @@ mod tests {
             env::set_var("JARVISH_TEST_MULTI_B", "b");
         }
 
-        let result = execute(&["JARVISH_TEST_MULTI_A", "JARVISH_TEST_MULTI_B"]);
+        let result = execute(
+            &["JARVISH_TEST_MULTI_A", "JARVISH_TEST_MULTI_B"],
+            &ShellVariables::default(),
+        );
         assert_eq!(result.exit_code, 0);
         assert!(env::var("JARVISH_TEST_MULTI_A").is_err());
         assert!(env::var("JARVISH_TEST_MULTI_B").is_err());
@@ -105,19 +128,19 @@ mod tests {
         unsafe {
             env::remove_var("JARVISH_TEST_NONEXISTENT");
         }
-        let result = execute(&["JARVISH_TEST_NONEXISTENT"]);
+        let result = execute(&["JARVISH_TEST_NONEXISTENT"], &ShellVariables::default());
         assert_eq!(result.exit_code, 0);
     }
 
     #[test]
     fn unset_no_args_returns_error() {
-        let result = execute(&[]);
+        let result = execute(&[], &ShellVariables::default());
         assert_ne!(result.exit_code, 0);
     }
 
     #[test]
     fn unset_help_returns_success() {
-        let result = execute(&["--help"]);
+        let result = execute(&["--help"], &ShellVariables::default());
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("unset"));
     }
@@ -70,13 +70,11 @@ fn handle_homebrew_update(check_only: bool) -> CommandResult {
     if check_only {
         let msg = "jarvish is installed via Homebrew.\n\
                    Run `brew outdated jarvish` to check for updates.\n";
-        print!("{msg}");
         return CommandResult::success(msg.to_string());
     }
 
     let msg = "jarvish is installed via Homebrew.\n\
                Run `brew upgrade jarvish` to update, then `restart` to reload.\n";
-    print!("{msg}");
     CommandResult::success(msg.to_string())
 }
 
@@ -94,11 +92,9 @@ fn check_for_updates() -> CommandResult {
                     "New version available: v{latest_clean} (current: v{current})\n\
                      Run `update` to install.\n"
                 );
-                print!("{msg}");
                 CommandResult::success(msg)
             } else {
                 let msg = format!("jarvish v{current} is up to date.\n");
-                print!("{msg}");
                 CommandResult::success(msg)
             }
         }
@@ -165,7 +161,6 @@ fn perform_update() -> CommandResult {
         CommandResult::restart()
     } else {
         let msg = format!("jarvish v{current} is already up to date.\n");
-        print!("{msg}");
         CommandResult::success(msg)
     }
 }
@@ -247,12 +242,10 @@ fn check_for_local_updates(binary_path: &Path) -> CommandResult {
                     "Local binary is newer: v{local_clean} (current: v{current})\n\
                      Run `update --local` to install.\n"
                 );
-                print!("{msg}");
                 CommandResult::success(msg)
             } else {
                 let msg =
                     format!("Local binary v{local_clean} is not newer than current v{current}.\n");
-                print!("{msg}");
                 CommandResult::success(msg)
             }
         }
@@ -315,7 +308,6 @@ fn perform_local_update_to(binary_path: &Path, dest: &Path) -> CommandResult {
             "Local binary v{new_clean} is not newer than current v{current}. \
              No update performed.\n"
         );
-        print!("{msg}");
         return CommandResult::success(msg);
     }
 
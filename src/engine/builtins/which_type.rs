@@ -1,22 +1,19 @@
 use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 
+use crate::engine::classifier::InputClassifier;
 use crate::engine::CommandResult;
 
-/// `which` / `type` の出力フォーマットを切り替えるモード。
-pub(super) enum WhichMode {
-    Which,
-    Type,
-}
-
-/// which: コマンドの場所・種類を表示する。
+/// which: PATH 上の外部コマンドの場所を表示する。
 #[derive(Parser)]
-#[command(
-    name = "which",
-    about = "Locate a command (builtin, alias, or external)"
-)]
+#[command(name = "which", about = "Locate an external command in PATH")]
 struct WhichArgs {
+    /// Print all matching executables in PATH, not just the first
+    #[arg(short = 'a', long = "all")]
+    all: bool,
     /// Command names to look up
     #[arg(required = true)]
     commands: Vec<String>,
@@ -31,11 +28,11 @@ struct TypeArgs {
     commands: Vec<String>,
 }
 
-/// `which` / `type` 共通のコマンド解決結果。
+/// `type` のコマンド解決結果。
 enum Resolution {
     Alias(String),
     Builtin,
-    External(std::path::PathBuf),
+    External(PathBuf),
     NotFound,
 }
 
@@ -53,16 +50,6 @@ fn resolve(cmd: &str, aliases: &HashMap<String, String>) -> Resolution {
     }
 }
 
-/// 解決結果を `which` 形式でフォーマットする。
-fn format_which(cmd: &str, resolution: &Resolution) -> String {
-    match resolution {
-        Resolution::Alias(value) => format!("{cmd}: aliased to '{value}'\n"),
-        Resolution::Builtin => format!("{cmd}: jarvish built-in command\n"),
-        Resolution::External(path) => format!("{}\n", path.display()),
-        Resolution::NotFound => format!("{cmd} not found\n"),
-    }
-}
-
 /// 解決結果を `type` 形式でフォーマットする。
 fn format_type(cmd: &str, resolution: &Resolution) -> String {
     match resolution {
@@ -73,63 +60,136 @@ fn format_type(cmd: &str, resolution: &Resolution) -> String {
     }
 }
 
-/// `which` / `type` の共通実行ロジック。
-fn run(mode: &WhichMode, commands: &[String], aliases: &HashMap<String, String>) -> CommandResult {
+/// 実行可能な通常ファイルかどうか。
+fn is_executable_file(path: &Path) -> bool {
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+/// `cmd` に一致する実行可能ファイルを `$PATH` の先頭から順に探索し、絶対パスを返す。
+///
+/// `all` が `false` なら最初の 1 件で打ち切る。`/` を含む名前は PATH を探索せず、
+/// そのパス自体が実行可能ファイルかどうかだけを調べる。
+fn find_in_path(cmd: &str, all: bool) -> Vec<PathBuf> {
+    let cwd = std::env::current_dir().unwrap_or_default();
+
+    if cmd.contains('/') {
+        let path = cwd.join(cmd);
+        return if is_executable_file(&path) {
+            vec![path]
+        } else {
+            Vec::new()
+        };
+    }
+
+    let mut found = Vec::new();
+    let Some(path_var) = std::env::var_os("PATH") else {
+        return found;
+    };
+    for dir in std::env::split_paths(&path_var) {
+        // 空要素はカレントディレクトリを意味する（POSIX）
+        let candidate = cwd.join(dir).join(cmd);
+        if is_executable_file(&candidate) {
+            found.push(candidate);
+            if !all {
+                break;
+            }
+        }
+    }
+    found
+}
+
+/// `which` ビルトインを実行する。
+///
+/// 対象は PATH 上の外部実行ファイルのみ（エイリアス・ビルトインは `type` を使う）。
+/// `classifier` が渡された場合は、その PATH キャッシュで存在しないと分かっている
+/// コマンドの探索を省略する。1 つでも見つからなければ終了コード 1。
+pub(crate) fn execute_which(args: &[&str], classifier: Option<&InputClassifier>) -> CommandResult {
+    let parsed = match super::parse_args::<WhichArgs>("which", args) {
+        Ok(a) => a,
+        Err(result) => return result,
+    };
+
     let mut stdout = String::new();
     let mut stderr = String::new();
-    let mut any_not_found = false;
-
-    for cmd in commands {
-        let resolution = resolve(cmd, aliases);
-        let line = match mode {
-            WhichMode::Which => format_which(cmd, &resolution),
-            WhichMode::Type => format_type(cmd, &resolution),
+    for cmd in &parsed.commands {
+        let known_missing =
+            !cmd.contains('/') && classifier.is_some_and(|c| !c.is_command_in_path(cmd));
+        let paths = if known_missing {
+            Vec::new()
+        } else {
+            find_in_path(cmd, parsed.all)
         };
 
-        if matches!(resolution, Resolution::NotFound) {
-            any_not_found = true;
+        if paths.is_empty() {
+            let line = format!("{cmd} not found\n");
             eprint!("{line}");
             stderr.push_str(&line);
-        } else {
-            print!("{line}");
+            continue;
+        }
+        for path in paths {
+            let line = format!("{}\n", path.display());
             stdout.push_str(&line);
         }
     }
 
-    if any_not_found {
+    if stderr.is_empty() {
+        CommandResult::success(stdout)
+    } else {
         CommandResult {
             stdout,
             stderr,
             exit_code: 1,
             action: crate::engine::LoopAction::Continue,
             used_alt_screen: false,
+            binary_output: false,
         }
-    } else {
-        CommandResult::success(stdout)
     }
 }
 
-/// `which` ビルトインを実行する。
-pub(crate) fn execute_which(args: &[&str], aliases: &HashMap<String, String>) -> CommandResult {
-    let parsed = match super::parse_args::<WhichArgs>("which", args) {
-        Ok(a) => a,
-        Err(result) => return result,
-    };
-    run(&WhichMode::Which, &parsed.commands, aliases)
-}
-
 /// `type` ビルトインを実行する。
 pub(crate) fn execute_type(args: &[&str], aliases: &HashMap<String, String>) -> CommandResult {
     let parsed = match super::parse_args::<TypeArgs>("type", args) {
         Ok(a) => a,
         Err(result) => return result,
     };
-    run(&WhichMode::Type, &parsed.commands, aliases)
+
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    let mut any_not_found = false;
+
+    for cmd in &parsed.commands {
+        let resolution = resolve(cmd, aliases);
+        let line = format_type(cmd, &resolution);
+
+        if matches!(resolution, Resolution::NotFound) {
+            any_not_found = true;
+            eprint!("{line}");
+            stderr.push_str(&line);
+        } else {
+            stdout.push_str(&line);
+        }
+    }
+
+    if any_not_found {
+        CommandResult {
+            stdout,
+            stderr,
+            exit_code: 1,
+            action: crate::engine::LoopAction::Continue,
+            used_alt_screen: false,
+            binary_output: false,
+        }
+    } else {
+        CommandResult::success(stdout)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serial_test::serial;
 
     fn make_aliases() -> HashMap<String, String> {
         let mut m = HashMap::new();
@@ -140,26 +200,17 @@ mod tests {
 
     // ── which ──
 
-    #[test]
-    fn which_builtin_command() {
-        let aliases = HashMap::new();
-        let result = execute_which(&["cd"], &aliases);
-        assert_eq!(result.exit_code, 0);
-        assert_eq!(result.stdout, "cd: jarvish built-in command\n");
-    }
-
-    #[test]
-    fn which_alias() {
-        let aliases = make_aliases();
-        let result = execute_which(&["g"], &aliases);
-        assert_eq!(result.exit_code, 0);
-        assert_eq!(result.stdout, "g: aliased to 'git'\n");
+    /// `dir` に実行可能なスクリプト `name` を作成する。
+    fn make_executable(dir: &Path, name: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
     }
 
     #[test]
     fn which_external_command() {
-        let aliases = HashMap::new();
-        let result = execute_which(&["ls"], &aliases);
+        let result = execute_which(&["ls"], None);
         assert_eq!(result.exit_code, 0);
         assert!(
             result.stdout.contains("/ls\n"),
@@ -168,44 +219,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn which_ignores_builtins_and_aliases() {
+        // `cdj` は jarvish のビルトインのみで、PATH 上の実行ファイルは存在しない
+        let result = execute_which(&["cdj"], None);
+        assert_eq!(result.exit_code, 1);
+        assert_eq!(result.stderr, "cdj not found\n");
+    }
+
     #[test]
     fn which_not_found() {
-        let aliases = HashMap::new();
-        let result = execute_which(&["__nonexistent_command_xyz__"], &aliases);
+        let result = execute_which(&["__nonexistent_command_xyz__"], None);
         assert_eq!(result.exit_code, 1);
         assert!(result.stderr.contains("not found"));
     }
 
     #[test]
     fn which_multiple_mixed() {
-        let aliases = make_aliases();
-        let result = execute_which(&["g", "cd", "__nonexistent_command_xyz__"], &aliases);
+        let result = execute_which(&["ls", "__nonexistent_command_xyz__"], None);
         assert_eq!(result.exit_code, 1);
-        assert!(result.stdout.contains("g: aliased to 'git'"));
-        assert!(result.stdout.contains("cd: jarvish built-in command"));
-        assert!(result.stderr.contains("not found"));
+        assert!(result.stdout.contains("/ls"));
+        assert!(result
+            .stderr
+            .contains("__nonexistent_command_xyz__ not found"));
     }
 
     #[test]
-    fn which_alias_shadows_builtin() {
-        let mut aliases = HashMap::new();
-        aliases.insert("cd".to_string(), "my-cd-wrapper".to_string());
-        let result = execute_which(&["cd"], &aliases);
+    #[serial]
+    fn find_in_path_first_or_all_matches() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let a = make_executable(first.path(), "jarvish-which-test");
+        let b = make_executable(second.path(), "jarvish-which-test");
+        // 実行権限のないファイルはスキップされる
+        std::fs::write(first.path().join("jarvish-which-plain"), "").unwrap();
+
+        let original = std::env::var_os("PATH");
+        let path = std::env::join_paths([first.path(), second.path()]).unwrap();
+        std::env::set_var("PATH", &path);
+        let only_first = find_in_path("jarvish-which-test", false);
+        let every = find_in_path("jarvish-which-test", true);
+        let plain = find_in_path("jarvish-which-plain", true);
+        match original {
+            Some(p) => std::env::set_var("PATH", p),
+            None => std::env::remove_var("PATH"),
+        }
+
+        assert_eq!(only_first, vec![a.clone()]);
+        assert_eq!(every, vec![a, b]);
+        assert!(plain.is_empty());
+    }
+
+    #[test]
+    fn which_path_with_slash_checks_file_itself() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = make_executable(dir.path(), "tool");
+        let result = execute_which(&[script.to_str().unwrap()], None);
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout, format!("{}\n", script.display()));
+    }
+
+    #[test]
+    fn which_uses_classifier_cache_for_missing_commands() {
+        let classifier = InputClassifier::new();
+        let result = execute_which(&["__nonexistent_command_xyz__"], Some(&classifier));
+        assert_eq!(result.exit_code, 1);
+        let result = execute_which(&["ls"], Some(&classifier));
         assert_eq!(result.exit_code, 0);
-        assert_eq!(result.stdout, "cd: aliased to 'my-cd-wrapper'\n");
     }
 
     #[test]
     fn which_no_args_is_error() {
-        let aliases = HashMap::new();
-        let result = execute_which(&[], &aliases);
+        let result = execute_which(&[], None);
         assert_ne!(result.exit_code, 0);
     }
 
     #[test]
     fn which_help_returns_success() {
-        let aliases = HashMap::new();
-        let result = execute_which(&["--help"], &aliases);
+        let result = execute_which(&["--help"], None);
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("which"));
     }
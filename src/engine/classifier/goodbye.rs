@@ -1,4 +1,20 @@
 //! AI 応答の Goodbye 検出
+//!
+//! 自然言語応答では、AI が会話を終えるターンの末尾に [`GOODBYE_MARKER`] を付ける
+//! （システムプロンプトで指示する）。応答の言語や言い回しに依存しないため、
+//! マーカーを指示していない応答（AI パイプ/リダイレクト）のみ末尾のフレーズで判定する。
+
+/// AI が会話を終えるときに応答の末尾に付けるマーカー（表示前に取り除く）
+pub const GOODBYE_MARKER: &str = "<<goodbye>>";
+
+/// 応答テキストの末尾にある会話終了マーカーを取り除く。
+///
+/// マーカーがあれば取り除いたテキストを、なければ `None` を返す。
+/// 文中でマーカーに言及しただけの場合はトリガーしないよう、末尾（空白を除く）のみを見る。
+pub fn strip_goodbye_marker(text: &str) -> Option<String> {
+    let body = text.trim_end().strip_suffix(GOODBYE_MARKER)?;
+    Some(body.trim_end().to_string())
+}
 
 /// AI の応答テキストが Goodbye（別れの挨拶）を含むかを判定する。
 ///
@@ -44,6 +60,23 @@ pub fn is_ai_goodbye_response(text: &str) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn strip_goodbye_marker_only_at_end() {
+        assert_eq!(
+            strip_goodbye_marker("では、また。\n<<goodbye>>\n").as_deref(),
+            Some("では、また。")
+        );
+        assert_eq!(
+            strip_goodbye_marker("Until we meet again, sir. <<goodbye>>").as_deref(),
+            Some("Until we meet again, sir.")
+        );
+        assert_eq!(strip_goodbye_marker("Goodbye, sir."), None);
+        assert_eq!(
+            strip_goodbye_marker("I append <<goodbye>> when you leave.\nAnything else?"),
+            None
+        );
+    }
+
     #[test]
     fn ai_goodbye_response_english() {
         assert!(is_ai_goodbye_response("Goodbye, sir. It was a pleasure."));
@@ -6,18 +6,30 @@
 //! `which` クレートを用いて `$PATH` を走査し、短寿命 TTL キャッシュで
 //! 同一トークンの重複走査を排除する。`brew install` 等で新しいバイナリが
 //! 追加された場合でも TTL 経過後に自動で反映される。
+//! `export PATH=$PATH:/new` のように `$PATH` の値が変わった場合は、
+//! 増減したディレクトリだけを走査してキャッシュを差分更新する
+//! （[`InputClassifier::reload_path_cache_incremental`]）。
 
 mod goodbye;
+mod overrides;
 mod patterns;
 
-pub use goodbye::is_ai_goodbye_response;
+pub use goodbye::{is_ai_goodbye_response, strip_goodbye_marker, GOODBYE_MARKER};
+pub use overrides::{ClassifierOverrides, OverrideKind, OverrideScope};
 
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
 use std::time::Instant;
 
+use nix::unistd::{access, AccessFlags};
 use tracing::{debug, info};
 
+use super::{builtins, expand};
+
 /// 入力の分類結果
 #[derive(Debug, Clone, PartialEq)]
 pub enum InputType {
@@ -29,9 +41,81 @@ pub enum InputType {
     Goodbye,
 }
 
+/// 分類の判定理由（[`InputClassifier::classify_with_reason`] が返す）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassifyReason {
+    /// 空入力
+    Empty,
+    /// ユーザー訂正オーバーライド（`:ai` / `:cmd` で学習）
+    UserOverride,
+    /// AI との会話中の Goodbye パターン（AI への発話として扱う）
+    GoodbyeInConversation,
+    /// Goodbye パターン
+    GoodbyePattern,
+    /// Jarvis への呼びかけ
+    JarvisTrigger,
+    /// 自然言語パターン（疑問詞、依頼表現 等）
+    NlPattern,
+    /// パス実行パターン（`./foo` 等）
+    PathExecution,
+    /// 先頭トークンが `$PATH` 上のコマンド
+    PathLookup,
+    /// シェル構文シグナル（パイプ、代入 等）
+    ShellSyntax,
+    /// どのルールにも当たらない（デフォルトで自然言語）
+    Default,
+}
+
+impl ClassifyReason {
+    /// ログ・`:explain` で表示する識別名。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::UserOverride => "user_override",
+            Self::GoodbyeInConversation => "goodbye_in_conversation",
+            Self::GoodbyePattern => "goodbye_pattern",
+            Self::JarvisTrigger => "jarvis_trigger",
+            Self::NlPattern => "nl_pattern",
+            Self::PathExecution => "path_execution",
+            Self::PathLookup => "path_lookup",
+            Self::ShellSyntax => "shell_syntax",
+            Self::Default => "default",
+        }
+    }
+
+    /// 判定理由の説明文。
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Empty => "empty input",
+            Self::UserOverride => "matches a correction learned with :ai / :cmd",
+            Self::GoodbyeInConversation => {
+                "goodbye phrase during an AI conversation is sent to Jarvis"
+            }
+            Self::GoodbyePattern => "matches a goodbye phrase",
+            Self::JarvisTrigger => "addressed to Jarvis (\"jarvis, ...\")",
+            Self::NlPattern => "looks like natural language (question word, request phrase)",
+            Self::PathExecution => "first word is a path (./foo, ../foo, /usr/bin/foo, ~/foo)",
+            Self::PathLookup => "first word is a command found in $PATH",
+            Self::ShellSyntax => "contains shell syntax (pipe, &&, ;, $VAR, assignment)",
+            Self::Default => "no command found, treated as natural language",
+        }
+    }
+}
+
 /// PATH lookup キャッシュの TTL（秒）。
 const PATH_CACHE_TTL_SECS: u64 = 5;
 
+/// PATH キャッシュの世代。[`InputClassifier::reload_path_cache`] のたびに増える。
+static PATH_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 現在の PATH キャッシュの世代を返す。
+///
+/// PATH 走査の結果をキャッシュする側（コマンド名補完）は、世代が変わったら
+/// キャッシュを破棄して走査し直す。
+pub fn path_generation() -> u64 {
+    PATH_GENERATION.load(Ordering::Relaxed)
+}
+
 /// アルゴリズムベースの入力分類器（TTL キャッシュ付き PATH 解決）
 ///
 /// `which::which()` を用いて `$PATH` 上の実行可能ファイルを検索する。
@@ -40,6 +124,12 @@ const PATH_CACHE_TTL_SECS: u64 = 5;
 pub struct InputClassifier {
     /// PATH lookup キャッシュ: コマンド名 → (存在するか, キャッシュ時刻)
     path_cache: Mutex<HashMap<String, (bool, Instant)>>,
+    /// 最後にキャッシュへ反映した `$PATH`（差分更新用）
+    path_state: Mutex<PathState>,
+    /// ユーザー訂正による分類オーバーライド（最優先ルール）
+    overrides: RwLock<ClassifierOverrides>,
+    /// オーバーライドの保存先。`None` の場合は学習結果をメモリ上にのみ保持する。
+    overrides_path: Option<PathBuf>,
 }
 
 impl Default for InputClassifier {
@@ -55,71 +145,175 @@ impl InputClassifier {
         );
         Self {
             path_cache: Mutex::new(HashMap::new()),
+            path_state: Mutex::new(PathState::current()),
+            overrides: RwLock::new(ClassifierOverrides::default()),
+            overrides_path: None,
         }
     }
 
-    /// ユーザー入力を分類する。
+    /// オーバーライドファイルを読み込んだ分類器を作成する。
+    ///
+    /// `learn()` で追加された訂正は同じファイルに書き戻される。
+    pub fn with_overrides_file(path: PathBuf) -> Self {
+        let overrides = ClassifierOverrides::load_from(&path);
+        info!(
+            path = %path.display(),
+            exact = overrides.exact.len(),
+            first_token = overrides.first_token.len(),
+            "Classifier overrides applied"
+        );
+        Self {
+            overrides: RwLock::new(overrides),
+            overrides_path: Some(path),
+            ..Self::new()
+        }
+    }
+
+    /// 入力に一致するユーザー訂正オーバーライドを返す。
+    pub fn override_for(&self, input: &str) -> Option<InputType> {
+        self.overrides.read().ok()?.lookup(input)
+    }
+
+    /// ユーザーの訂正を学習し、以後の分類で最優先に適用する。
+    ///
+    /// オーバーライドファイルが設定されている場合はファイルにも保存する。
+    /// 保存に失敗してもメモリ上の学習結果はセッション中有効なまま残る。
+    pub fn learn(
+        &self,
+        input: &str,
+        kind: OverrideKind,
+        scope: OverrideScope,
+    ) -> Result<(), String> {
+        let snapshot = {
+            let mut guard = self
+                .overrides
+                .write()
+                .map_err(|_| "internal error: lock poisoned".to_string())?;
+            guard.insert(input, kind, scope);
+            guard.clone()
+        };
+        info!(input = %input, kind = ?kind, scope = ?scope, "Classifier override learned");
+        match self.overrides_path {
+            Some(ref path) => snapshot.save_to(path),
+            None => Ok(()),
+        }
+    }
+
+    /// ユーザー入力を分類する（AI との会話コンテキストなし）。
+    ///
+    /// 判定ロジックは [`classify_with_context`](Self::classify_with_context) を参照。
+    pub fn classify(&self, input: &str) -> InputType {
+        self.classify_with_context(input, false)
+    }
+
+    /// 会話コンテキストを考慮してユーザー入力を分類する。
+    ///
+    /// `has_conversation` が `true`（AI との会話が継続中）の場合、Goodbye パターンは
+    /// シェル終了ではなく AI への発話（NaturalLanguage）として扱う。
     ///
     /// 判定ロジック（優先順位順）:
-    /// 0. Goodbye パターン → Goodbye（最優先）
+    /// -. ユーザー訂正オーバーライド（`:ai` / `:cmd` で学習）→ 指定の分類（最優先）
+    /// 0. Goodbye パターン → Goodbye
+    ///    （コマンド呼び出しに見える場合は 1. 以降へ、会話中なら NaturalLanguage）
     /// 1. Jarvis トリガー → NaturalLanguage
     /// 2. 自然言語パターン → NaturalLanguage
     /// 3. パス実行パターン → Command
     /// 4. PATH 内コマンド → Command
     /// 5. シェル構文シグナル → Command
     /// 6. デフォルト → NaturalLanguage
-    pub fn classify(&self, input: &str) -> InputType {
+    pub fn classify_with_context(&self, input: &str, has_conversation: bool) -> InputType {
+        self.classify_with_reason(input, has_conversation).0
+    }
+
+    /// [`classify_with_context`](Self::classify_with_context) と同じ判定を行い、
+    /// 分類結果とあわせて判定理由を返す（`:explain` で表示する）。
+    pub fn classify_with_reason(
+        &self,
+        input: &str,
+        has_conversation: bool,
+    ) -> (InputType, ClassifyReason) {
         let trimmed = input.trim();
         if trimmed.is_empty() {
-            return InputType::Command;
+            return (InputType::Command, ClassifyReason::Empty);
+        }
+
+        if let Some(input_type) = self.override_for(trimmed) {
+            debug!(input = %trimmed, classification = ?input_type, reason = "user_override", "Classified by override");
+            return (input_type, ClassifyReason::UserOverride);
         }
 
         if Self::is_goodbye_pattern(trimmed) {
-            debug!(input = %trimmed, reason = "goodbye_pattern", "Classified as Goodbye");
-            return InputType::Goodbye;
+            if self.looks_like_command_invocation(trimmed) {
+                debug!(input = %trimmed, "Goodbye pattern looks like a command, skipping");
+            } else if has_conversation {
+                debug!(input = %trimmed, reason = "goodbye_in_conversation", "Classified as NaturalLanguage");
+                return (
+                    InputType::NaturalLanguage,
+                    ClassifyReason::GoodbyeInConversation,
+                );
+            } else {
+                debug!(input = %trimmed, reason = "goodbye_pattern", "Classified as Goodbye");
+                return (InputType::Goodbye, ClassifyReason::GoodbyePattern);
+            }
         }
 
         if self.is_jarvis_trigger(trimmed) {
             debug!(input = %trimmed, reason = "jarvis_trigger", "Classified as NaturalLanguage");
-            return InputType::NaturalLanguage;
+            return (InputType::NaturalLanguage, ClassifyReason::JarvisTrigger);
         }
 
         if self.is_natural_language_pattern(trimmed) {
             debug!(input = %trimmed, reason = "nl_pattern", "Classified as NaturalLanguage");
-            return InputType::NaturalLanguage;
+            return (InputType::NaturalLanguage, ClassifyReason::NlPattern);
         }
 
         let first_token = Self::first_token(trimmed);
+        let first_token = first_token.as_ref();
 
         if Self::is_path_execution(first_token) {
             debug!(input = %trimmed, first_token = %first_token, reason = "path_execution", "Classified as Command");
-            return InputType::Command;
+            return (InputType::Command, ClassifyReason::PathExecution);
         }
 
         if self.is_command_in_path(first_token) {
             debug!(input = %trimmed, first_token = %first_token, reason = "path_lookup", "Classified as Command");
-            return InputType::Command;
+            return (InputType::Command, ClassifyReason::PathLookup);
         }
 
         if Self::has_shell_syntax(trimmed) {
             debug!(input = %trimmed, reason = "shell_syntax", "Classified as Command");
-            return InputType::Command;
+            return (InputType::Command, ClassifyReason::ShellSyntax);
         }
 
         debug!(input = %trimmed, reason = "default", "Classified as NaturalLanguage");
-        InputType::NaturalLanguage
+        (InputType::NaturalLanguage, ClassifyReason::Default)
     }
 
-    /// 入力文字列から先頭トークン（空白前の最初の語）を取得する。
-    fn first_token(input: &str) -> &str {
-        input.split_whitespace().next().unwrap_or("")
+    /// 入力文字列から先頭トークンを取得する。
+    ///
+    /// `"my program" --flag` のようなクオート付きの語は、クオートを外した 1 トークン
+    /// （`my program`）として扱う。クオートの対応が取れない入力（`I'm tired` 等）は
+    /// 従来どおり最初の空白までを返す。
+    fn first_token(input: &str) -> Cow<'_, str> {
+        let word = input.split_whitespace().next().unwrap_or("");
+        if !word.contains(['"', '\'', '\\']) {
+            return Cow::Borrowed(word);
+        }
+        match expand::split_quoted(input) {
+            Ok(tokens) => match tokens.into_iter().next() {
+                Some(token) => Cow::Owned(token.value),
+                None => Cow::Borrowed(word),
+            },
+            Err(_) => Cow::Borrowed(word),
+        }
     }
 
     /// 先頭トークンが `$PATH` 上の実行可能ファイルとして存在するか。
     ///
     /// TTL キャッシュにより、同一トークンに対する `which::which()` の
     /// 重複呼び出しを排除する。TTL 経過後は自動で再走査される。
-    fn is_command_in_path(&self, token: &str) -> bool {
+    /// `which` ビルトインも存在確認にこのキャッシュを共有する。
+    pub fn is_command_in_path(&self, token: &str) -> bool {
         let now = Instant::now();
 
         if let Ok(cache) = self.path_cache.lock() {
@@ -139,15 +333,159 @@ impl InputClassifier {
         result
     }
 
-    /// PATH lookup キャッシュをクリアする。
-    #[cfg(test)]
-    fn clear_path_cache(&self) {
+    /// AUTO_CD（`[shell] auto_cd`）の対象なら、`cd` に渡す引数を返す。
+    ///
+    /// 入力全体が 1 語で、ビルトインでも `$PATH` 上のコマンドでもなく、チルダ・変数展開後に
+    /// 既存のディレクトリを指す場合に限る（`../`, `~/dev` 等）。ディレクトリの存在確認を
+    /// 自然言語判定より優先するため、分類（[`classify`](Self::classify)）より先に呼ぶ。
+    /// 返す引数はクオートを含む入力そのままで、展開は `cd` の実行時に行う。
+    pub fn auto_cd_target<'a>(&self, input: &'a str) -> Option<&'a str> {
+        let trimmed = input.trim();
+        let tokens = expand::split_quoted(trimmed).ok()?;
+        // コマンド置換を含む語は判定のために実行しない
+        let [token] = tokens.as_slice() else {
+            return None;
+        };
+        if token.has_subst {
+            return None;
+        }
+        if builtins::is_builtin(&token.value) || self.is_command_in_path(&token.value) {
+            return None;
+        }
+        let path = if token.quoted {
+            token.value.clone()
+        } else {
+            expand::expand_token(&token.value)
+        };
+        Path::new(&path).is_dir().then_some(trimmed)
+    }
+
+    /// 入力全体が 1 つの URL（`http://` / `https://`）なら、その URL を返す（`[shell] url_action`）。
+    ///
+    /// コマンドとして実行しても失敗するだけの入力を、開く・AI に委ねる・エラーにするのいずれかで
+    /// 扱うため、分類（[`classify`](Self::classify)）より先に呼ぶ。
+    /// シングルクオートを含む URL はそのまま引数に埋め込めないため対象外とする。
+    pub fn url_target(input: &str) -> Option<&str> {
+        let trimmed = input.trim();
+        if trimmed.contains(char::is_whitespace) || trimmed.contains('\'') {
+            return None;
+        }
+        let lower = trimmed.to_ascii_lowercase();
+        let rest = lower
+            .strip_prefix("https://")
+            .or_else(|| lower.strip_prefix("http://"))?;
+        (!rest.is_empty()).then_some(trimmed)
+    }
+
+    /// PATH lookup キャッシュをクリアし、PATH キャッシュの世代を進める。
+    ///
+    /// 世代（[`path_generation`]）を参照する補完候補のキャッシュもあわせて無効になる。
+    /// `$PATH` のディレクトリの増減だけを反映すればよい場合は
+    /// [`reload_path_cache_incremental`](Self::reload_path_cache_incremental) を使う。
+    pub fn reload_path_cache(&self) {
+        if let Ok(mut path_state) = self.path_state.lock() {
+            *path_state = PathState::current();
+        }
         if let Ok(mut cache) = self.path_cache.lock() {
             cache.clear();
         }
+        let generation = PATH_GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+        debug!(generation, "PATH cache reloaded");
+    }
+
+    /// `$PATH` の値が前回から変わっていれば、追加・削除されたディレクトリだけを走査して
+    /// PATH lookup キャッシュを差分更新する。
+    ///
+    /// 追加されたディレクトリのコマンドは「存在する」としてキャッシュし、削除された
+    /// ディレクトリのコマンドはキャッシュから外して次回の参照時に解決し直す
+    /// （ディレクトリ自体が消えていれば TTL の経過で反映される）。
+    /// `$PATH` の値が変わっていなければ環境変数を読むだけで何もしないため、
+    /// コマンドの実行ごとに呼んでよい。
+    ///
+    /// 世代は進めない（補完候補のキャッシュは `$PATH` の値の変化で無効になる）。
+    pub fn reload_path_cache_incremental(&self) {
+        let Ok(mut path_state) = self.path_state.lock() else {
+            return;
+        };
+        let current = PathState::current();
+        if current.value == path_state.value {
+            return;
+        }
+        let removed: Vec<PathBuf> = path_state
+            .dirs
+            .iter()
+            .filter(|dir| !current.dirs.contains(dir))
+            .cloned()
+            .collect();
+        let added: Vec<PathBuf> = current
+            .dirs
+            .iter()
+            .filter(|dir| !path_state.dirs.contains(dir))
+            .cloned()
+            .collect();
+        *path_state = current;
+        drop(path_state);
+        if removed.is_empty() && added.is_empty() {
+            return;
+        }
+
+        let now = Instant::now();
+        let Ok(mut cache) = self.path_cache.lock() else {
+            return;
+        };
+        // 削除 → 追加の順に反映し、両方にあるコマンドは「存在する」で終わらせる
+        for dir in &removed {
+            for name in scan_dir_commands(dir) {
+                cache.remove(&name);
+            }
+        }
+        for dir in &added {
+            for name in scan_dir_commands(dir) {
+                cache.insert(name, (true, now));
+            }
+        }
+        debug!(added = ?added, removed = ?removed, "PATH cache updated incrementally");
     }
 }
 
+/// 最後に PATH lookup キャッシュへ反映した `$PATH`
+#[derive(Debug)]
+struct PathState {
+    /// `$PATH` の値
+    value: OsString,
+    /// `$PATH` のディレクトリ（重複は先に現れたものだけを残す）
+    dirs: Vec<PathBuf>,
+}
+
+impl PathState {
+    /// 現在の `$PATH` を読む（ディレクトリの走査はしない）。
+    fn current() -> Self {
+        let value = std::env::var_os("PATH").unwrap_or_default();
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for dir in std::env::split_paths(&value) {
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        Self { value, dirs }
+    }
+}
+
+/// ディレクトリ直下の実行可能ファイル名を返す（読めないディレクトリは空）。
+fn scan_dir_commands(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            let path = entry.path();
+            path.is_file() && access(&path, AccessFlags::X_OK).is_ok()
+        })
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,6 +495,90 @@ mod tests {
         InputClassifier::new()
     }
 
+    // ── 判定理由 ──
+
+    #[test]
+    fn classify_with_reason_reports_rule() {
+        let c = test_classifier();
+        assert_eq!(
+            c.classify_with_reason("ls -la", false),
+            (InputType::Command, ClassifyReason::PathLookup)
+        );
+        assert_eq!(
+            c.classify_with_reason("./build.sh", false),
+            (InputType::Command, ClassifyReason::PathExecution)
+        );
+        assert_eq!(
+            c.classify_with_reason("jarvis, status report", false),
+            (InputType::NaturalLanguage, ClassifyReason::JarvisTrigger)
+        );
+        assert_eq!(
+            c.classify_with_reason("goodbye", false),
+            (InputType::Goodbye, ClassifyReason::GoodbyePattern)
+        );
+        assert_eq!(
+            c.classify_with_reason("goodbye", true),
+            (
+                InputType::NaturalLanguage,
+                ClassifyReason::GoodbyeInConversation
+            )
+        );
+        assert_eq!(
+            c.classify_with_reason("   ", false),
+            (InputType::Command, ClassifyReason::Empty)
+        );
+    }
+
+    #[test]
+    fn classify_with_reason_reports_override() {
+        let c = test_classifier();
+        c.learn("ls", OverrideKind::Ai, OverrideScope::Exact)
+            .unwrap();
+        assert_eq!(
+            c.classify_with_reason("ls", false),
+            (InputType::NaturalLanguage, ClassifyReason::UserOverride)
+        );
+    }
+
+    // ── ユーザー訂正オーバーライド ──
+
+    #[test]
+    fn learned_override_takes_precedence() {
+        let c = test_classifier();
+        assert_eq!(c.classify("ls"), InputType::Command);
+        c.learn("ls", OverrideKind::Ai, OverrideScope::Exact)
+            .unwrap();
+        assert_eq!(c.classify("ls"), InputType::NaturalLanguage);
+        // 完全一致のみなので引数付きは従来どおり
+        assert_eq!(c.classify("ls -la"), InputType::Command);
+    }
+
+    #[test]
+    fn learned_first_token_override() {
+        let c = test_classifier();
+        c.learn(
+            "frobnicate the widgets",
+            OverrideKind::Cmd,
+            OverrideScope::FirstToken,
+        )
+        .unwrap();
+        assert_eq!(c.classify("frobnicate --all"), InputType::Command);
+    }
+
+    #[test]
+    fn learn_persists_to_overrides_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("classifier_overrides.toml");
+
+        let c = InputClassifier::with_overrides_file(path.clone());
+        c.learn("make coffee", OverrideKind::Ai, OverrideScope::Exact)
+            .unwrap();
+
+        // 新しい分類器（= 次回起動）でも学習結果が適用される
+        let reloaded = InputClassifier::with_overrides_file(path);
+        assert_eq!(reloaded.classify("make coffee"), InputType::NaturalLanguage);
+    }
+
     #[test]
     fn classify_simple_command() {
         let c = test_classifier();
@@ -164,6 +586,103 @@ mod tests {
         assert_eq!(c.classify("ls -la"), InputType::Command);
     }
 
+    #[test]
+    fn classify_quoted_executable_path_with_space() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let c = test_classifier();
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("my program");
+        std::fs::write(&program, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let input = format!("\"{}\" --flag", program.display());
+        assert_eq!(c.classify(&input), InputType::Command);
+        let input = format!("'{}' --flag", program.display());
+        assert_eq!(c.classify(&input), InputType::Command);
+    }
+
+    #[test]
+    fn classify_ai_prefixed_requests_as_natural_language() {
+        // `ai use <name>` 等のプロファイル操作以外の `ai ...` は AI への呼びかけ
+        let c = test_classifier();
+        assert_eq!(
+            c.classify("ai explain this error"),
+            InputType::NaturalLanguage
+        );
+        assert_eq!(
+            c.classify("ai \"why did this fail\""),
+            InputType::NaturalLanguage
+        );
+        assert_eq!(c.classify("ai 直しといて"), InputType::NaturalLanguage);
+    }
+
+    #[test]
+    fn classify_quoted_command_name() {
+        let c = test_classifier();
+        assert_eq!(c.classify("\"ls\" -la"), InputType::Command);
+        assert_eq!(c.classify("'git' status"), InputType::Command);
+    }
+
+    #[test]
+    fn auto_cd_target_accepts_existing_directories_only() {
+        let c = test_classifier();
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("my dir");
+        std::fs::create_dir(&sub).unwrap();
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, "x").unwrap();
+
+        let input = dir.path().display().to_string();
+        assert_eq!(c.auto_cd_target(&input), Some(input.as_str()));
+        let input = format!("'{}'", sub.display());
+        assert_eq!(c.auto_cd_target(&input), Some(input.as_str()));
+        assert_eq!(c.auto_cd_target(".."), Some(".."));
+        assert_eq!(c.auto_cd_target(&file.display().to_string()), None);
+        assert_eq!(c.auto_cd_target("no_such_directory_xyz"), None);
+        // 引数付き・コマンド・ビルトインは対象外
+        assert_eq!(
+            c.auto_cd_target(&format!("{} extra", dir.path().display())),
+            None
+        );
+        assert_eq!(c.auto_cd_target("ls"), None);
+        assert_eq!(c.auto_cd_target("cd"), None);
+    }
+
+    #[test]
+    fn url_target_accepts_single_http_urls_only() {
+        assert_eq!(
+            InputClassifier::url_target("  https://example.com/a?b=1&c=2 "),
+            Some("https://example.com/a?b=1&c=2")
+        );
+        assert_eq!(
+            InputClassifier::url_target("HTTP://example.com"),
+            Some("HTTP://example.com")
+        );
+        assert_eq!(InputClassifier::url_target("https://"), None);
+        assert_eq!(
+            InputClassifier::url_target("curl https://example.com"),
+            None
+        );
+        assert_eq!(InputClassifier::url_target("ftp://example.com"), None);
+        assert_eq!(InputClassifier::url_target("./notes.md"), None);
+        assert_eq!(
+            InputClassifier::url_target("https://example.com/it's"),
+            None
+        );
+    }
+
+    #[test]
+    fn first_token_unquotes_and_falls_back_on_parse_error() {
+        assert_eq!(
+            InputClassifier::first_token("\"my program\" --flag"),
+            "my program"
+        );
+        assert_eq!(InputClassifier::first_token("ls -la"), "ls");
+        // クオートの対応が取れない場合は空白分割
+        assert_eq!(InputClassifier::first_token("I'm tired"), "I'm");
+    }
+
     #[test]
     fn classify_git_commands() {
         let c = test_classifier();
@@ -203,6 +722,14 @@ mod tests {
         assert_eq!(c.classify("$HOME/bin/tool"), InputType::Command);
     }
 
+    #[test]
+    fn classify_shell_variable_assignment() {
+        let c = test_classifier();
+        assert_eq!(c.classify("name=value"), InputType::Command);
+        assert_eq!(c.classify("FOO=bar"), InputType::Command);
+        assert_eq!(c.classify("dir=\"my docs\""), InputType::Command);
+    }
+
     #[test]
     fn classify_jarvis_trigger() {
         let c = test_classifier();
@@ -277,6 +804,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn classify_command_word_with_arguments_as_command() {
+        let c = test_classifier();
+        assert_eq!(c.classify("which ls"), InputType::Command);
+        assert_eq!(c.classify("which -a ls"), InputType::Command);
+        assert_eq!(c.classify("test -f foo"), InputType::Command);
+        assert_eq!(c.classify("test \"$x\" = y"), InputType::Command);
+        assert_eq!(c.classify("test foo"), InputType::Command);
+    }
+
+    #[test]
+    fn classify_command_word_question_as_natural_language() {
+        let c = test_classifier();
+        assert_eq!(c.classify("which is better"), InputType::NaturalLanguage);
+        assert_eq!(c.classify("which is better?"), InputType::NaturalLanguage);
+        assert_eq!(
+            c.classify("which one should I use"),
+            InputType::NaturalLanguage
+        );
+        assert_eq!(c.classify("test me"), InputType::NaturalLanguage);
+        assert_eq!(c.classify("who are you"), InputType::NaturalLanguage);
+        assert_eq!(
+            c.classify("who wrote this function"),
+            InputType::NaturalLanguage
+        );
+    }
+
+    #[test]
+    fn command_word_question_boundaries() {
+        let c = test_classifier();
+        // ビルトインや PATH 上のコマンドを引数に取る呼び出しはコマンド
+        assert!(!c.is_command_word_question("help cd"));
+        assert!(!c.is_command_word_question("type ls"));
+        assert!(!c.is_command_word_question("time ls -la"));
+        assert!(!c.is_command_word_question("who am i"));
+        // 単語 1 つ、対象外の先頭語
+        assert!(!c.is_command_word_question("which"));
+        assert!(!c.is_command_word_question("ls is fine"));
+        // 文末の句読点は引数とみなさない
+        assert!(c.is_command_word_question("which is better."));
+        assert!(c.is_command_word_question("help me debug this"));
+        // `ai` ビルトインのサブコマンドはコマンド、`ai` への呼びかけは自然言語
+        assert!(!c.is_command_word_question("ai use fast"));
+        assert!(c.is_command_word_question("ai what is rust"));
+        // シェル構文を含めばコマンド
+        assert!(!c.is_command_word_question("which is | cat"));
+    }
+
     #[test]
     fn classify_japanese_patterns() {
         let c = test_classifier();
@@ -289,6 +864,45 @@ mod tests {
         assert_eq!(c.classify("これはなんですか"), InputType::NaturalLanguage);
     }
 
+    #[test]
+    fn classify_japanese_imperative_patterns() {
+        let c = test_classifier();
+        assert_eq!(c.classify("コミットしといて"), InputType::NaturalLanguage);
+        assert_eq!(
+            c.classify("ビルドを実行しておいてね"),
+            InputType::NaturalLanguage
+        );
+        // シェル構文に見える記号を含んでも、先頭が PATH 上のコマンドでなければ命令文
+        assert_eq!(
+            c.classify("DEBUG=1 でビルドを実行しといて"),
+            InputType::NaturalLanguage
+        );
+        assert_eq!(
+            c.classify("テストを流して; 落ちたら直しといて"),
+            InputType::NaturalLanguage
+        );
+    }
+
+    #[test]
+    fn imperative_verbs_in_commands_stay_commands() {
+        let c = test_classifier();
+        assert_eq!(
+            c.classify("git commit -m 'バグを直した'"),
+            InputType::Command
+        );
+        assert_eq!(c.classify("echo 作っといて | cat"), InputType::Command);
+        assert_eq!(c.classify("./fix.sh 直して.txt"), InputType::Command);
+        // 代入だけの行・環境変数付きのコマンドも命令文とはみなさない
+        assert_eq!(c.classify("MSG=バグを修正した"), InputType::Command);
+        assert_eq!(c.classify("LANG=C grep 確認し log"), InputType::Command);
+        assert_eq!(
+            c.classify("MSG=バグを直した DEBUG=1 make"),
+            InputType::Command
+        );
+        // ビルトインも命令文とはみなさない（分類より先にビルトインとして実行される）
+        assert!(!c.is_natural_language_pattern("cd 調べ物"));
+    }
+
     #[test]
     fn classify_empty_input() {
         let c = test_classifier();
@@ -327,7 +941,7 @@ mod tests {
             std::env::set_var("PATH", &new_path);
         }
 
-        c.clear_path_cache();
+        c.reload_path_cache();
 
         assert_eq!(
             c.classify(fake_cmd),
@@ -340,11 +954,63 @@ mod tests {
         }
         let _ = fs::remove_dir_all(&tmp_dir);
 
-        c.clear_path_cache();
+        c.reload_path_cache();
 
         assert_eq!(c.classify(fake_cmd), InputType::NaturalLanguage);
     }
 
+    #[test]
+    #[serial]
+    fn incremental_reload_only_updates_added_and_removed_dirs() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let c = test_classifier();
+        let fake_cmd = "zzz_jarvish_test_incremental_cmd";
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let fake_bin = tmp_dir.path().join(fake_cmd);
+        fs::write(&fake_bin, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&fake_bin, fs::Permissions::from_mode(0o755)).unwrap();
+
+        // TTL 内の「存在しない」をキャッシュさせておく
+        assert!(!c.is_command_in_path(fake_cmd));
+        // `$PATH` が変わっていなければ何もしない
+        c.reload_path_cache_incremental();
+        assert!(c.path_cache.lock().unwrap().contains_key(fake_cmd));
+
+        let original_path = std::env::var("PATH").unwrap();
+        unsafe {
+            std::env::set_var(
+                "PATH",
+                format!("{original_path}:{}", tmp_dir.path().display()),
+            );
+        }
+        // 増減と無関係なエントリはキャッシュに残る（全再構築ならクリアされる）
+        assert!(c.is_command_in_path("sh"));
+        c.reload_path_cache_incremental();
+        assert!(c.path_cache.lock().unwrap().contains_key("sh"));
+        assert!(
+            c.is_command_in_path(fake_cmd),
+            "added dir is reflected within the TTL"
+        );
+        assert!(c
+            .path_state
+            .lock()
+            .unwrap()
+            .dirs
+            .contains(&tmp_dir.path().to_path_buf()));
+
+        unsafe {
+            std::env::set_var("PATH", &original_path);
+        }
+        c.reload_path_cache_incremental();
+        assert!(!c.path_cache.lock().unwrap().contains_key(fake_cmd));
+        assert!(
+            !c.is_command_in_path(fake_cmd),
+            "removed dir is reflected within the TTL"
+        );
+    }
+
     #[test]
     fn classify_apostrophe_input() {
         let c = test_classifier();
@@ -413,6 +1079,29 @@ mod tests {
         assert_eq!(c.classify("see you later"), InputType::Goodbye);
     }
 
+    #[test]
+    fn classify_goodbye_in_conversation_goes_to_ai() {
+        let c = test_classifier();
+        assert_eq!(
+            c.classify_with_context("またね", true),
+            InputType::NaturalLanguage
+        );
+        assert_eq!(
+            c.classify_with_context("jarvis, bye", true),
+            InputType::NaturalLanguage
+        );
+        assert_eq!(c.classify_with_context("またね", false), InputType::Goodbye);
+    }
+
+    #[test]
+    fn classify_goodbye_looking_like_command() {
+        let c = test_classifier();
+        assert_eq!(c.classify("echo またね"), InputType::Command);
+        assert_ne!(c.classify("bye \"bug\""), InputType::Goodbye);
+        // 挨拶フレーズの先頭語はコマンドとして存在しても Goodbye のまま
+        assert_eq!(c.classify("see you"), InputType::Goodbye);
+    }
+
     #[test]
     fn classify_goodbye_false_positives() {
         let c = test_classifier();
@@ -0,0 +1,248 @@
+//! ユーザー訂正による分類オーバーライド
+//!
+//! `:ai` / `:cmd` プレフィックスでユーザーが訂正した分類結果を
+//! `~/.config/jarvish/classifier_overrides.toml` に保存し、
+//! `InputClassifier::classify` が最優先ルールとして参照する。
+//!
+//! ファイル形式:
+//!
+//! ```toml
+//! # 入力全体が完全一致した場合に適用
+//! [exact]
+//! "make coffee" = "ai"
+//!
+//! # 先頭トークンが一致した場合に適用（exact より低優先）
+//! [first_token]
+//! say = "ai"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::InputType;
+
+/// オーバーライドで指定する分類先
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverrideKind {
+    /// 自然言語として AI に送る
+    Ai,
+    /// シェルコマンドとして実行する
+    Cmd,
+}
+
+impl OverrideKind {
+    /// 対応する `InputType` を返す。
+    pub fn input_type(self) -> InputType {
+        match self {
+            OverrideKind::Ai => InputType::NaturalLanguage,
+            OverrideKind::Cmd => InputType::Command,
+        }
+    }
+}
+
+/// オーバーライドの適用範囲
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverrideScope {
+    /// 入力全体（前後の空白を除く）の完全一致
+    Exact,
+    /// 先頭トークンの一致
+    FirstToken,
+}
+
+/// 分類オーバーライドの一覧（TOML ファイルと 1:1 対応）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassifierOverrides {
+    /// 入力全体 → 分類先
+    pub exact: BTreeMap<String, OverrideKind>,
+    /// 先頭トークン → 分類先
+    pub first_token: BTreeMap<String, OverrideKind>,
+}
+
+impl ClassifierOverrides {
+    /// オーバーライドファイルのデフォルトパスを返す。
+    ///
+    /// `JarvishConfig::config_path()` と同じく `$HOME/.config/jarvish/` 配下に置く。
+    pub fn default_path() -> PathBuf {
+        std::env::var("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join(".config/jarvish/classifier_overrides.toml")
+    }
+
+    /// ファイルからオーバーライドを読み込む。
+    ///
+    /// ファイルが存在しない場合は空のオーバーライドを返す。
+    /// 読み込み・パースに失敗した場合は警告を出して空のオーバーライドを返す
+    /// （分類器自体は従来のヒューリスティックで動作し続ける）。
+    pub fn load_from(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        let content = match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to read classifier overrides");
+                eprintln!("jarvish: warning: failed to read classifier overrides: {e}");
+                return Self::default();
+            }
+        };
+        match toml::from_str::<Self>(&content) {
+            Ok(overrides) => {
+                debug!(
+                    path = %path.display(),
+                    exact = overrides.exact.len(),
+                    first_token = overrides.first_token.len(),
+                    "Classifier overrides loaded"
+                );
+                overrides
+            }
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to parse classifier overrides");
+                eprintln!("jarvish: warning: failed to parse classifier overrides: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// オーバーライドをファイルに保存する（親ディレクトリがなければ作成する）。
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("failed to create {}: {e}", parent.display()))?;
+        }
+        let content = toml::to_string(self)
+            .map_err(|e| format!("failed to serialize classifier overrides: {e}"))?;
+        std::fs::write(path, content)
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))
+    }
+
+    /// 入力に一致するオーバーライドを返す。
+    ///
+    /// 完全一致を先頭トークン一致より優先する。
+    pub fn lookup(&self, input: &str) -> Option<InputType> {
+        let trimmed = input.trim();
+        if let Some(kind) = self.exact.get(trimmed) {
+            return Some(kind.input_type());
+        }
+        let first_token = trimmed.split_whitespace().next()?;
+        self.first_token.get(first_token).map(|k| k.input_type())
+    }
+
+    /// オーバーライドを追加（既存のものは上書き）する。
+    ///
+    /// 同じキーで逆の分類が登録されていた場合も、最新の訂正が優先される。
+    pub fn insert(&mut self, input: &str, kind: OverrideKind, scope: OverrideScope) {
+        let trimmed = input.trim();
+        match scope {
+            OverrideScope::Exact => {
+                self.exact.insert(trimmed.to_string(), kind);
+            }
+            OverrideScope::FirstToken => {
+                if let Some(token) = trimmed.split_whitespace().next() {
+                    self.first_token.insert(token.to_string(), kind);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_exact_match() {
+        let mut o = ClassifierOverrides::default();
+        o.insert("make coffee", OverrideKind::Ai, OverrideScope::Exact);
+        assert_eq!(o.lookup("make coffee"), Some(InputType::NaturalLanguage));
+        assert_eq!(
+            o.lookup("  make coffee  "),
+            Some(InputType::NaturalLanguage)
+        );
+        assert_eq!(o.lookup("make build"), None);
+    }
+
+    #[test]
+    fn lookup_first_token_match() {
+        let mut o = ClassifierOverrides::default();
+        o.insert("say hello", OverrideKind::Ai, OverrideScope::FirstToken);
+        assert_eq!(o.lookup("say goodbye"), Some(InputType::NaturalLanguage));
+        assert_eq!(o.lookup("said hello"), None);
+    }
+
+    #[test]
+    fn exact_takes_precedence_over_first_token() {
+        let mut o = ClassifierOverrides::default();
+        o.insert("say", OverrideKind::Ai, OverrideScope::FirstToken);
+        o.insert("say -v Alex hi", OverrideKind::Cmd, OverrideScope::Exact);
+        assert_eq!(o.lookup("say -v Alex hi"), Some(InputType::Command));
+        assert_eq!(o.lookup("say something"), Some(InputType::NaturalLanguage));
+    }
+
+    #[test]
+    fn insert_overwrites_previous_kind() {
+        let mut o = ClassifierOverrides::default();
+        o.insert("foo bar", OverrideKind::Ai, OverrideScope::Exact);
+        o.insert("foo bar", OverrideKind::Cmd, OverrideScope::Exact);
+        assert_eq!(o.lookup("foo bar"), Some(InputType::Command));
+    }
+
+    #[test]
+    fn save_and_load_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("nested/classifier_overrides.toml");
+
+        let mut o = ClassifierOverrides::default();
+        o.insert("make coffee", OverrideKind::Ai, OverrideScope::Exact);
+        o.insert("fd", OverrideKind::Cmd, OverrideScope::FirstToken);
+        o.save_to(&path).unwrap();
+
+        let loaded = ClassifierOverrides::load_from(&path);
+        assert_eq!(loaded, o);
+    }
+
+    #[test]
+    fn load_from_parses_documented_format() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("classifier_overrides.toml");
+        std::fs::write(
+            &path,
+            r#"
+[exact]
+"make coffee" = "ai"
+
+[first_token]
+say = "ai"
+fd = "cmd"
+"#,
+        )
+        .unwrap();
+
+        let o = ClassifierOverrides::load_from(&path);
+        assert_eq!(o.lookup("make coffee"), Some(InputType::NaturalLanguage));
+        assert_eq!(o.lookup("say hi"), Some(InputType::NaturalLanguage));
+        assert_eq!(o.lookup("fd pattern"), Some(InputType::Command));
+    }
+
+    #[test]
+    fn load_from_missing_file_returns_empty() {
+        let o =
+            ClassifierOverrides::load_from(Path::new("/tmp/nonexistent_jarvish_overrides.toml"));
+        assert_eq!(o, ClassifierOverrides::default());
+    }
+
+    #[test]
+    fn load_from_invalid_file_returns_empty() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("classifier_overrides.toml");
+        std::fs::write(&path, "[exact]\nfoo = \"maybe\"\n").unwrap();
+
+        let o = ClassifierOverrides::load_from(&path);
+        assert_eq!(o, ClassifierOverrides::default());
+    }
+}
@@ -1,5 +1,66 @@
 //! パターン検出 — Goodbye / Jarvis トリガー / 自然言語 / パス実行 / シェル構文
 
+use std::borrow::Cow;
+
+use crate::engine::{builtins, shell_vars};
+
+/// 英語の別れの挨拶フレーズ（入力全体、または先頭に一致させる）
+const GOODBYE_PHRASES: &[&str] = &[
+    "bye",
+    "bye bye",
+    "bye-bye",
+    "byebye",
+    "goodbye",
+    "good bye",
+    "good-bye",
+    "see you",
+    "see ya",
+    "good night",
+    "goodnight",
+    "farewell",
+    "ciao",
+];
+
+/// コマンド（PATH 上の実行ファイルやビルトイン）としても存在する疑問詞・依頼語
+/// （`ai` は `ai use fast` 等のビルトインと `ai what is ...` のような呼びかけの両方がありうる）
+const COMMAND_LIKE_STARTERS: &[&str] = &[
+    "which", "who", "whatis", "test", "help", "type", "time", "ai",
+];
+
+/// 自然言語の 2 語目に現れやすい機能語（`which is ...` / `test me` / `help me ...`）
+const NL_FUNCTION_WORDS: &[&str] = &[
+    "is", "are", "was", "were", "do", "does", "did", "can", "could", "should", "would", "will",
+    "me", "you", "we", "us", "it", "the", "a", "an", "my", "your", "our", "this", "that", "these",
+    "those", "one", "of", "to", "for",
+];
+
+/// AI へのタスク依頼を示す日本語動詞の語幹（`作って` / `直しといて` / `調べておいて` 等に一致させる）
+const AI_TASK_VERB_STEMS: &[&str] = &[
+    "作っ",
+    "直し",
+    "調べ",
+    "教え",
+    "見せ",
+    "探し",
+    "書い",
+    "まとめ",
+    "説明し",
+    "修正し",
+    "実行し",
+    "コミットし",
+    "確認し",
+    "追加し",
+    "要約し",
+    "翻訳し",
+    "整理し",
+    "作成し",
+    "生成し",
+    "削除し",
+    "レビューし",
+    "デバッグし",
+    "インストールし",
+];
+
 impl super::InputClassifier {
     /// ユーザー入力が Goodbye パターンにマッチするかを判定する。
     ///
@@ -14,23 +75,7 @@ impl super::InputClassifier {
             return false;
         }
 
-        let goodbye_phrases = [
-            "bye",
-            "bye bye",
-            "bye-bye",
-            "byebye",
-            "goodbye",
-            "good bye",
-            "good-bye",
-            "see you",
-            "see ya",
-            "good night",
-            "goodnight",
-            "farewell",
-            "ciao",
-        ];
-
-        for phrase in &goodbye_phrases {
+        for phrase in GOODBYE_PHRASES {
             if body == *phrase || body.starts_with(&format!("{phrase} ")) {
                 return true;
             }
@@ -65,6 +110,29 @@ impl super::InputClassifier {
         false
     }
 
+    /// Goodbye パターンにマッチした入力が、実際にはコマンド呼び出しに見えるかを判定する。
+    ///
+    /// `echo またね` や `say "bye"` のように、先頭トークンが `$PATH` 上のコマンドである場合や
+    /// クオートを含む場合はシェル終了ではなくコマンドとして扱うべき入力とみなす。
+    /// ただし `see you` の `see` のように挨拶フレーズの先頭語そのものはコマンド扱いしない。
+    /// Jarvis への呼びかけ（`jarvis, bye` 等）は常にコマンドではない。
+    pub(super) fn looks_like_command_invocation(&self, input: &str) -> bool {
+        let lower = input.to_lowercase();
+        if Self::strip_jarvis_prefix(&lower).len() != lower.len() {
+            return false;
+        }
+        if input.contains(['"', '\'']) {
+            return true;
+        }
+
+        let first_token = Self::first_token(input);
+        let first_word = Self::first_token(&lower);
+        let is_greeting_word = GOODBYE_PHRASES
+            .iter()
+            .any(|phrase| phrase.split_whitespace().next() == Some(first_word.as_ref()));
+        !is_greeting_word && self.is_command_in_path(&first_token)
+    }
+
     /// Jarvis 呼びかけプレフィックス（"jarvis, ", "hey jarvis, ", "j, " 等）を除去する。
     pub(super) fn strip_jarvis_prefix(input: &str) -> &str {
         let prefixes = [
@@ -120,6 +188,10 @@ impl super::InputClassifier {
             return true;
         }
 
+        if self.is_command_word_question(input) {
+            return true;
+        }
+
         let first_word = lower.split_whitespace().next().unwrap_or("");
 
         let has_multiple_words = lower.contains(' ');
@@ -147,9 +219,73 @@ impl super::InputClassifier {
             return true;
         }
 
+        if self.is_imperative_request(input) {
+            return true;
+        }
+
         false
     }
 
+    /// 文中に AI へのタスク依頼を示す動詞（[`AI_TASK_VERB_STEMS`]）を含む命令文か。
+    ///
+    /// 末尾が `して` でない言い回し（`コミットしといて`）や、`=` / `;` を含むため
+    /// シェル構文と判定されてしまう依頼（`DEBUG=1 でビルドを実行しといて`）を拾う。
+    /// 誤検出を避けるため、先頭トークンが PATH 上のコマンド・ビルトイン・パス実行なら
+    /// コマンドを優先する（`git commit -m 直した`）。
+    /// 先頭の変数代入は読み飛ばしてその後のコマンドで判定し、代入だけの行
+    /// （`MSG=バグを修正した`）や環境変数付きのコマンド（`LANG=C grep 確認し log`）はコマンドとする。
+    fn is_imperative_request(&self, input: &str) -> bool {
+        if !AI_TASK_VERB_STEMS.iter().any(|stem| input.contains(stem)) {
+            return false;
+        }
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let assignments = words
+            .iter()
+            .take_while(|word| shell_vars::parse_assignment(word).is_some())
+            .count();
+        let first_token = match words.get(assignments) {
+            None => return false,
+            Some(_) if assignments == 0 => Self::first_token(input),
+            Some(word) => Cow::Borrowed(*word),
+        };
+        let first_token = first_token.as_ref();
+        let rest = &words[assignments + 1..];
+        !(Self::is_path_execution(first_token)
+            || builtins::is_builtin_call(first_token, rest)
+            || self.is_command_in_path(first_token))
+    }
+
+    /// 先頭語がコマンドとしても存在する疑問詞・依頼語（`which` / `test` / `help` 等）の入力を、
+    /// 2 語目以降の内容から自然言語と判定できるか。
+    ///
+    /// - オプション・パス・比較演算子・シェル構文を含む、または 2 語目が PATH 上のコマンドや
+    ///   ビルトインならコマンド（`which python`, `test -f foo`, `help cd`, `time make`）
+    /// - 2 語目が機能語なら自然言語（`which is better`, `test me`, `help me debug this`）
+    /// - 4 語以上の平文も自然言語（`who wrote this code`）
+    ///
+    /// ビルトインより先に判定する必要があるため、シェルからも参照する。
+    pub fn is_command_word_question(&self, input: &str) -> bool {
+        let lower = input.to_lowercase();
+        let words: Vec<&str> = lower.split_whitespace().collect();
+        let [first, second, ..] = words.as_slice() else {
+            return false;
+        };
+        if !COMMAND_LIKE_STARTERS.contains(first) {
+            return false;
+        }
+        // `ai use <name>` 等の ai ビルトインの呼び出しはコマンド
+        if *first == "ai" && builtins::is_builtin_call("ai", &words[1..]) {
+            return false;
+        }
+        if Self::has_shell_syntax(input) || words[1..].iter().any(|w| looks_like_argument(w)) {
+            return false;
+        }
+        if builtins::is_builtin(second) || self.is_command_in_path(second) {
+            return false;
+        }
+        NL_FUNCTION_WORDS.contains(second) || words.len() >= 4
+    }
+
     /// 先頭トークンがパス実行パターン（./foo, ../foo, /usr/bin/foo, ~/foo）か。
     pub(super) fn is_path_execution(first_token: &str) -> bool {
         first_token.starts_with("./")
@@ -159,13 +295,28 @@ impl super::InputClassifier {
     }
 
     /// 入力にシェル構文（パイプ、論理演算子、セミコロン、変数展開、代入）が含まれるか。
+    ///
+    /// 代入は `KEY=value`（大文字始まり）ならどの位置でも、小文字の `name=value` は
+    /// 先頭トークンの場合のみ（シェル変数の代入）とみなす。
     pub(super) fn has_shell_syntax(input: &str) -> bool {
         input.contains('|')
             || input.contains("&&")
             || input.contains(';')
             || input.starts_with('$')
+            || input
+                .split_whitespace()
+                .next()
+                .is_some_and(|token| shell_vars::parse_assignment(token).is_some())
             || input.split_whitespace().any(|token| {
                 token.contains('=') && token.chars().next().is_some_and(|c| c.is_ascii_uppercase())
             })
     }
 }
+
+/// コマンド引数らしい語か（オプション、パス、ファイル名、比較演算子、変数参照、グロブ）。
+///
+/// 文末の句読点（`which is better.` の `.` 等）は無視する。
+fn looks_like_argument(word: &str) -> bool {
+    let word = word.trim_end_matches(['.', ',', '!', '?']);
+    word.starts_with('-') || word.starts_with('~') || word.contains(['/', '.', '=', '$', '*'])
+}
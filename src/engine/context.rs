@@ -0,0 +1,31 @@
+//! コマンド実行コンテキスト
+//!
+//! Shell の設定のうち、コマンドの実行経路（dispatch → exec → tee スレッド）で参照するものを
+//! まとめる。Shell が保持し、`execute()` 等に参照で渡す。
+
+use std::time::Duration;
+
+use super::shell_vars::ShellVariables;
+
+/// コマンド実行時に参照する Shell の設定
+#[derive(Debug, Clone, Default)]
+pub struct ExecContext {
+    /// pipe + tee 経路でターミナルに表示する長い行を端で切り詰めるか（`[shell] wrap_output`）
+    pub truncate_output: bool,
+    /// pipe フォールバック時に子プロセスへ色出力を強制する環境変数を渡すか（`[shell] force_color`）
+    pub force_color: bool,
+    /// `cd` にファイルを指定したとき親ディレクトリへ移動するか（`[shell] cd_to_file_parent`）
+    pub cd_to_file_parent: bool,
+    /// 出力リダイレクト先の親ディレクトリがなければ作成するか（`[shell] mkdir_on_redirect`）
+    pub mkdir_on_redirect: bool,
+    /// 非対話コマンドに適用する既定の制限時間（`[shell] default_command_timeout`、`None` で無効）
+    pub default_timeout: Option<Duration>,
+    /// `timeout` ビルトインで明示された制限時間。既定より優先し、対話コマンドにも適用する
+    pub explicit_timeout: Option<Duration>,
+    /// stdout を表示せずにキャプチャするか（`capture` ビルトイン・AI パイプの入力）。
+    ///
+    /// 外部コマンドは `exec::run_pipeline_captured()` で実行し、ビルトインの出力も表示しない。
+    pub capture_stdout: bool,
+    /// シェル変数（`name=value`）の表。展開と `export` / `unset` が参照する
+    pub variables: ShellVariables,
+}
@@ -5,7 +5,7 @@
 
 use tracing::debug;
 
-use crate::engine::{builtins, exec, expand, parser};
+use crate::engine::{builtins, exec, expand, parser, CommandResult, ExecContext};
 
 /// AI パイプ / リダイレクトの動作モード
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -34,43 +34,66 @@ pub struct AiPipeRequest {
 /// 該当する場合は手前のパイプラインを実行して stdout をキャプチャする。
 ///
 /// v1 制約: 接続演算子（`&&`, `||`, `;`）との組み合わせは非対応。
-pub fn try_execute_ai_pipe(input: &str) -> Option<AiPipeRequest> {
+pub fn try_execute_ai_pipe(input: &str, ctx: &ExecContext) -> Option<AiPipeRequest> {
     let input = input.trim();
     if input.is_empty() {
         return None;
     }
 
-    let tokens = expand::split_quoted(input).ok()?;
+    let tokens = expand::split_quoted_with_vars(input, &ctx.variables).ok()?;
     if tokens.is_empty() {
         return None;
     }
 
     if tokens
         .iter()
-        .any(|t| matches!(t.value.as_str(), "&&" | "||" | ";"))
+        .any(|t| t.is_operator() && matches!(t.value.as_str(), "&&" | "||" | ";"))
     {
         return None;
     }
 
-    let mut expanded: Vec<String> = Vec::with_capacity(tokens.len());
+    // `| ai` / `> ai` を含まない入力は展開しない（判定のためだけにコマンド置換・
+    // プロセス置換の内部コマンドを実行しないよう、展開前に絞り込む）
+    if !tokens.windows(2).any(|pair| {
+        pair[0].is_operator()
+            && matches!(pair[0].value.as_str(), "|" | ">")
+            && pair[1].value == "ai"
+    }) {
+        return None;
+    }
+
+    // ソースパイプライン内の `<(...)` は実行後に片付ける
+    let _subst_scope = expand::ProcessSubstScope::enter();
+
+    let mut expanded: Vec<parser::Word> = Vec::with_capacity(tokens.len());
     for tok in tokens {
-        if matches!(tok.value.as_str(), "|" | ">" | ">>" | "<") {
-            expanded.push(tok.value);
+        if tok.is_operator() {
+            expanded.push(parser::Word::from(tok.value));
             continue;
         }
         if tok.quoted && !tok.has_subst {
-            expanded.push(tok.value);
+            expanded.push(parser::Word::literal(tok.value));
             continue;
         }
         let expanded_result = if tok.quoted && tok.has_subst {
-            expand::expand_token_subst_only(&tok.value, tok.subst_quoting)
+            expand::expand_token_subst_only(
+                &tok.value,
+                tok.subst_quoting,
+                &tok.process_subst,
+                &ctx.variables,
+            )
         } else if tok.has_subst {
-            expand::expand_token_globs_with_quoting(&tok.value, tok.subst_quoting)
+            expand::expand_token_globs_with_quoting(
+                &tok.value,
+                tok.subst_quoting,
+                &tok.process_subst,
+                &ctx.variables,
+            )
         } else {
-            expand::expand_token_globs(&tok.value)
+            expand::expand_token_globs(&tok.value, &ctx.variables)
         };
         match expanded_result {
-            Ok(parts) => expanded.extend(parts),
+            Ok(parts) => expanded.extend(parts.into_iter().map(parser::Word::literal)),
             Err(_) => {
                 // AI ルーティング判定中の no-match / 置換失敗は通常パスへフォールスルー
                 return None;
@@ -79,12 +102,12 @@ pub fn try_execute_ai_pipe(input: &str) -> Option<AiPipeRequest> {
     }
 
     // 1. `| ai "prompt"` パターン（フィルタモード）
-    if let Some(req) = try_pipe_ai(&expanded) {
+    if let Some(req) = try_pipe_ai(&expanded, ctx) {
         return Some(req);
     }
 
     // 2. `> ai "prompt"` パターン（リダイレクトモード）
-    if let Some(req) = try_redirect_ai(&expanded) {
+    if let Some(req) = try_redirect_ai(&expanded, ctx) {
         return Some(req);
     }
 
@@ -92,21 +115,31 @@ pub fn try_execute_ai_pipe(input: &str) -> Option<AiPipeRequest> {
 }
 
 /// `| ai "prompt"` パターンの検出と実行
-fn try_pipe_ai(expanded: &[String]) -> Option<AiPipeRequest> {
+fn try_pipe_ai(expanded: &[parser::Word], ctx: &ExecContext) -> Option<AiPipeRequest> {
     let pipeline = parser::parse_pipeline(expanded.to_vec()).ok()?;
     let (prompt, remaining) = pipeline.extract_ai_filter()?;
 
     debug!(prompt = %prompt, "AI pipe detected, executing source pipeline");
-    Some(run_source_pipeline(prompt, remaining, AiPipeMode::Filter))
+    Some(run_source_pipeline(
+        prompt,
+        remaining,
+        AiPipeMode::Filter,
+        ctx,
+    ))
 }
 
 /// `> ai "prompt"` パターンの検出と実行
-fn try_redirect_ai(expanded: &[String]) -> Option<AiPipeRequest> {
+fn try_redirect_ai(expanded: &[parser::Word], ctx: &ExecContext) -> Option<AiPipeRequest> {
     let (prompt, source_tokens) = try_extract_ai_redirect(expanded)?;
     let remaining = parser::parse_pipeline(source_tokens).ok()?;
 
     debug!(prompt = %prompt, "AI redirect detected, executing source pipeline");
-    Some(run_source_pipeline(prompt, remaining, AiPipeMode::Redirect))
+    Some(run_source_pipeline(
+        prompt,
+        remaining,
+        AiPipeMode::Redirect,
+        ctx,
+    ))
 }
 
 /// トークン列から `> ai "prompt"` パターンを検出する。
@@ -114,10 +147,11 @@ fn try_redirect_ai(expanded: &[String]) -> Option<AiPipeRequest> {
 /// 末尾から `>` + `ai` のペアを探し、`ai` の後ろにプロンプトがあれば
 /// `(prompt, source_tokens)` を返す。プロンプトが空、またはソースコマンドが
 /// ない場合は通常のファイルリダイレクトとして `None` を返す。
-fn try_extract_ai_redirect(tokens: &[String]) -> Option<(String, Vec<String>)> {
+fn try_extract_ai_redirect(tokens: &[parser::Word]) -> Option<(String, Vec<parser::Word>)> {
     for i in (0..tokens.len().saturating_sub(1)).rev() {
-        if tokens[i] == ">" && tokens.get(i + 1).map(|s| s.as_str()) == Some("ai") {
-            let prompt_parts: Vec<&str> = tokens[i + 2..].iter().map(|s| s.as_str()).collect();
+        if tokens[i].is_operator(">") && tokens.get(i + 1).map(|w| w.value.as_str()) == Some("ai") {
+            let prompt_parts: Vec<&str> =
+                tokens[i + 2..].iter().map(|w| w.value.as_str()).collect();
             let prompt = prompt_parts.join(" ");
             if prompt.is_empty() {
                 return None;
@@ -132,19 +166,35 @@ fn try_extract_ai_redirect(tokens: &[String]) -> Option<(String, Vec<String>)> {
     None
 }
 
+/// ソースパイプラインの先頭をビルトインとして実行する（ビルトインでなければ `None`）。
+///
+/// パイプライン中では外部コマンドに任せるビルトイン（`timeout`）は実行しない。
+fn dispatch_source_builtin(cmd: &str, args: &[&str], ctx: &ExecContext) -> Option<CommandResult> {
+    if builtins::defers_to_external_in_pipeline(cmd) {
+        return None;
+    }
+    builtins::dispatch_builtin(cmd, args, ctx)
+}
+
 /// ソースパイプラインを実行し、`AiPipeRequest` を構築する。
 ///
 /// パイプライン先頭がビルトインの場合はシェル内で実行し、
 /// その出力を後続パイプラインの stdin として注入する。
+/// 外部コマンドと同じく、ビルトインの出力もターミナルには表示しない。
 fn run_source_pipeline(
     prompt: String,
     remaining: parser::Pipeline,
     mode: AiPipeMode,
+    ctx: &ExecContext,
 ) -> AiPipeRequest {
+    let builtin_ctx = ExecContext {
+        capture_stdout: true,
+        ..ctx.clone()
+    };
     let remaining = if remaining.commands.len() > 1 {
         let first = &remaining.commands[0];
         let args: Vec<&str> = first.args.iter().map(|s| s.as_str()).collect();
-        if let Some(result) = builtins::dispatch_builtin(&first.cmd, &args) {
+        if let Some(result) = dispatch_source_builtin(&first.cmd, &args, &builtin_ctx) {
             if result.exit_code != 0 {
                 return AiPipeRequest {
                     prompt,
@@ -168,7 +218,7 @@ fn run_source_pipeline(
     } else {
         let first = &remaining.commands[0];
         let args: Vec<&str> = first.args.iter().map(|s| s.as_str()).collect();
-        if let Some(result) = builtins::dispatch_builtin(&first.cmd, &args) {
+        if let Some(result) = dispatch_source_builtin(&first.cmd, &args, &builtin_ctx) {
             return AiPipeRequest {
                 prompt,
                 stdin_text: result.stdout,
@@ -179,7 +229,7 @@ fn run_source_pipeline(
         remaining
     };
 
-    let result = exec::run_pipeline_captured(&remaining);
+    let result = exec::run_pipeline_captured(&remaining, ctx);
 
     AiPipeRequest {
         prompt,
@@ -195,42 +245,46 @@ mod tests {
 
     // ── try_extract_ai_redirect ──
 
+    fn values(words: &[parser::Word]) -> Vec<&str> {
+        words.iter().map(|w| w.value.as_str()).collect()
+    }
+
     #[test]
     fn redirect_ai_simple() {
-        let tokens: Vec<String> = vec!["echo", "hello", ">", "ai", "要約して"]
+        let tokens: Vec<parser::Word> = vec!["echo", "hello", ">", "ai", "要約して"]
             .into_iter()
             .map(Into::into)
             .collect();
         let (prompt, source) = try_extract_ai_redirect(&tokens).unwrap();
         assert_eq!(prompt, "要約して");
-        assert_eq!(source, vec!["echo", "hello"]);
+        assert_eq!(values(&source), vec!["echo", "hello"]);
     }
 
     #[test]
     fn redirect_ai_with_pipe_before() {
-        let tokens: Vec<String> = vec!["cmd1", "|", "cmd2", ">", "ai", "分析して"]
+        let tokens: Vec<parser::Word> = vec!["cmd1", "|", "cmd2", ">", "ai", "分析して"]
             .into_iter()
             .map(Into::into)
             .collect();
         let (prompt, source) = try_extract_ai_redirect(&tokens).unwrap();
         assert_eq!(prompt, "分析して");
-        assert_eq!(source, vec!["cmd1", "|", "cmd2"]);
+        assert_eq!(values(&source), vec!["cmd1", "|", "cmd2"]);
     }
 
     #[test]
     fn redirect_ai_multi_word_prompt() {
-        let tokens: Vec<String> = vec!["ls", "-la", ">", "ai", "translate", "to", "Japanese"]
+        let tokens: Vec<parser::Word> = vec!["ls", "-la", ">", "ai", "translate", "to", "Japanese"]
             .into_iter()
             .map(Into::into)
             .collect();
         let (prompt, source) = try_extract_ai_redirect(&tokens).unwrap();
         assert_eq!(prompt, "translate to Japanese");
-        assert_eq!(source, vec!["ls", "-la"]);
+        assert_eq!(values(&source), vec!["ls", "-la"]);
     }
 
     #[test]
     fn redirect_ai_no_prompt_returns_none() {
-        let tokens: Vec<String> = vec!["echo", "hello", ">", "ai"]
+        let tokens: Vec<parser::Word> = vec!["echo", "hello", ">", "ai"]
             .into_iter()
             .map(Into::into)
             .collect();
@@ -239,7 +293,7 @@ mod tests {
 
     #[test]
     fn redirect_ai_no_source_returns_none() {
-        let tokens: Vec<String> = vec![">", "ai", "prompt"]
+        let tokens: Vec<parser::Word> = vec![">", "ai", "prompt"]
             .into_iter()
             .map(Into::into)
             .collect();
@@ -248,7 +302,7 @@ mod tests {
 
     #[test]
     fn redirect_to_file_not_ai() {
-        let tokens: Vec<String> = vec!["echo", "hello", ">", "ai_log.txt"]
+        let tokens: Vec<parser::Word> = vec!["echo", "hello", ">", "ai_log.txt"]
             .into_iter()
             .map(Into::into)
             .collect();
@@ -257,16 +311,27 @@ mod tests {
 
     #[test]
     fn redirect_to_normal_file() {
-        let tokens: Vec<String> = vec!["echo", "hello", ">", "output.txt"]
+        let tokens: Vec<parser::Word> = vec!["echo", "hello", ">", "output.txt"]
             .into_iter()
             .map(Into::into)
             .collect();
         assert!(try_extract_ai_redirect(&tokens).is_none());
     }
 
+    #[test]
+    fn quoted_redirect_is_not_ai_redirect() {
+        let tokens = vec![
+            parser::Word::from("echo"),
+            parser::Word::literal(">"),
+            parser::Word::from("ai"),
+            parser::Word::from("prompt"),
+        ];
+        assert!(try_extract_ai_redirect(&tokens).is_none());
+    }
+
     #[test]
     fn append_redirect_not_matched() {
-        let tokens: Vec<String> = vec!["echo", "hello", ">>", "ai", "prompt"]
+        let tokens: Vec<parser::Word> = vec!["echo", "hello", ">>", "ai", "prompt"]
             .into_iter()
             .map(Into::into)
             .collect();
@@ -308,7 +373,7 @@ mod tests {
         env::set_current_dir(dir.path()).unwrap();
         fs::write(dir.path().join("a.txt"), "hello").unwrap();
 
-        let req = try_execute_ai_pipe("cat *.txt | ai \"要約して\"");
+        let req = try_execute_ai_pipe("cat *.txt | ai \"要約して\"", &ExecContext::default());
         assert!(req.is_some(), "AI pipe should be detected and executed");
         let req = req.unwrap();
         assert_eq!(req.prompt, "要約して");
@@ -325,7 +390,10 @@ mod tests {
         let _guard = CwdGuard::new();
         env::set_current_dir(dir.path()).unwrap();
 
-        let req = try_execute_ai_pipe("cat *.nonexistent_xyz | ai \"prompt\"");
+        let req = try_execute_ai_pipe(
+            "cat *.nonexistent_xyz | ai \"prompt\"",
+            &ExecContext::default(),
+        );
         assert!(req.is_none());
     }
 }
@@ -10,7 +10,8 @@ pub use ai_pipe::{try_execute_ai_pipe, AiPipeMode, AiPipeRequest};
 
 use tracing::debug;
 
-use super::{builtins, exec, expand, parser, CommandResult};
+use super::shell_vars::{self, ShellVariables};
+use super::{builtins, dry_run, exec, expand, parser, CommandResult, ExecContext};
 
 /// ビルトインコマンドのみを試行する。
 /// ビルトインでなければ None を返す（AI ルーティング前のチェック用）。
@@ -18,14 +19,15 @@ use super::{builtins, exec, expand, parser, CommandResult};
 /// 先頭ワードがビルトインキーワード（cd, cwd, exit）でない場合は
 /// パースを行わず即座に None を返す。これにより、自然言語中の
 /// アポストロフィ等によるパースエラーが AI ルーティングをブロックしない。
-pub fn try_builtin(input: &str) -> Option<CommandResult> {
+pub fn try_builtin(input: &str, ctx: &ExecContext) -> Option<CommandResult> {
     let input = input.trim();
     if input.is_empty() {
         return Some(CommandResult::success(String::new()));
     }
 
-    let first_word = input.split_whitespace().next().unwrap_or("");
-    if !builtins::is_builtin(first_word) {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let first_word = words[0];
+    if !builtins::is_builtin_call(first_word, &words[1..]) {
         debug!(
             command = %first_word,
             is_builtin = false,
@@ -34,7 +36,15 @@ pub fn try_builtin(input: &str) -> Option<CommandResult> {
         return None;
     }
 
-    let tokens = match expand::split_quoted(input) {
+    // dry-run 中はビルトインも実行せず表示のみ（exit / source 等の一部を除く）
+    if dry_run::is_enabled() && !dry_run::is_exempt(first_word) {
+        return Some(dry_run::preview(input, &ctx.variables));
+    }
+
+    // 引数の `<(...)` はビルトインの実行後に片付ける
+    let _subst_scope = expand::ProcessSubstScope::enter();
+
+    let tokens = match expand::split_quoted_with_vars(input, &ctx.variables) {
         Ok(tokens) => tokens,
         Err(e) => {
             let msg = format!("jarvish: parse error: {e}\n");
@@ -47,10 +57,7 @@ pub fn try_builtin(input: &str) -> Option<CommandResult> {
         return Some(CommandResult::success(String::new()));
     }
 
-    if tokens
-        .iter()
-        .any(|t| matches!(t.value.as_str(), "|" | ">" | ">>" | "<" | "&&" | "||" | ";"))
-    {
+    if tokens.iter().any(|t| t.is_operator()) {
         debug!(
             command = %first_word,
             "try_builtin: contains pipe/redirect/connector, deferring to execute()"
@@ -69,11 +76,21 @@ pub fn try_builtin(input: &str) -> Option<CommandResult> {
         }
         let expanded_result = if tok.quoted && tok.has_subst {
             // クォート内の置換: 置換のみ行い glob/brace は適用しない（bash 準拠）。
-            expand::expand_token_subst_only(&tok.value, tok.subst_quoting)
+            expand::expand_token_subst_only(
+                &tok.value,
+                tok.subst_quoting,
+                &tok.process_subst,
+                &ctx.variables,
+            )
         } else if tok.has_subst {
-            expand::expand_token_globs_with_quoting(&tok.value, tok.subst_quoting)
+            expand::expand_token_globs_with_quoting(
+                &tok.value,
+                tok.subst_quoting,
+                &tok.process_subst,
+                &ctx.variables,
+            )
         } else {
-            expand::expand_token_globs(&tok.value)
+            expand::expand_token_globs(&tok.value, &ctx.variables)
         };
         match expanded_result {
             Ok(parts) => expanded.extend(parts),
@@ -82,7 +99,7 @@ pub fn try_builtin(input: &str) -> Option<CommandResult> {
                 eprint!("{msg}");
                 return Some(CommandResult::error(msg, 1));
             }
-            Err(expand::ExpandError::Substitution(m)) => {
+            Err(expand::ExpandError::Substitution(m) | expand::ExpandError::Parameter(m)) => {
                 let msg = format!("jarvish: {m}\n");
                 eprint!("{msg}");
                 return Some(CommandResult::error(msg, 1));
@@ -95,7 +112,7 @@ pub fn try_builtin(input: &str) -> Option<CommandResult> {
     let cmd = &expanded[0];
     let args: Vec<&str> = expanded[1..].iter().map(|s| s.as_str()).collect();
 
-    let result = builtins::dispatch_builtin(cmd, &args);
+    let result = builtins::dispatch_builtin(cmd, &args, ctx);
     debug!(
         command = %cmd,
         is_builtin = result.is_some(),
@@ -109,13 +126,24 @@ pub fn try_builtin(input: &str) -> Option<CommandResult> {
 /// パイプライン（`|`）やリダイレクト（`>`, `>>`, `<`）を含むコマンドに対応。
 /// 単一コマンドでビルトインの場合はビルトインとして処理し、
 /// それ以外は `exec::run_pipeline()` でパイプライン実行する。
-pub fn execute(input: &str) -> CommandResult {
+///
+/// `ctx.capture_stdout` なら stdout を表示せずに `CommandResult.stdout` にだけ返す
+/// （`capture` ビルトイン用。stderr はそのまま表示する）。
+pub fn execute(input: &str, ctx: &ExecContext) -> CommandResult {
     let input = input.trim();
     if input.is_empty() {
         return CommandResult::success(String::new());
     }
 
-    let tokens = match expand::split_quoted(input) {
+    // dry-run 中は run_pipeline の代わりに展開・パース結果を表示する
+    if dry_run::is_enabled() {
+        return dry_run::preview(input, &ctx.variables);
+    }
+
+    // 展開で起動した `<(...)` の内部コマンドは、コマンド行全体の実行後に片付ける
+    let _subst_scope = expand::ProcessSubstScope::enter();
+
+    let tokens = match expand::split_quoted_with_vars(input, &ctx.variables) {
         Ok(tokens) => tokens,
         Err(e) => {
             let msg = format!("jarvish: parse error: {e}\n");
@@ -128,35 +156,43 @@ pub fn execute(input: &str) -> CommandResult {
         return CommandResult::success(String::new());
     }
 
-    let mut expanded: Vec<String> = Vec::with_capacity(tokens.len());
+    // クォート由来・展開結果の語は literal とし、パーサーで演算子扱いしない
+    let mut expanded: Vec<parser::Word> = Vec::with_capacity(tokens.len());
     for tok in tokens {
-        if matches!(
-            tok.value.as_str(),
-            "|" | ">" | ">>" | "<" | "&&" | "||" | ";"
-        ) {
-            expanded.push(tok.value);
+        if tok.is_operator() {
+            expanded.push(parser::Word::from(tok.value));
             continue;
         }
         if tok.quoted && !tok.has_subst {
-            expanded.push(tok.value);
+            expanded.push(parser::Word::literal(tok.value));
             continue;
         }
         let expanded_result = if tok.quoted && tok.has_subst {
             // クォート内の置換: 置換のみ行い glob/brace は適用しない（bash 準拠）。
-            expand::expand_token_subst_only(&tok.value, tok.subst_quoting)
+            expand::expand_token_subst_only(
+                &tok.value,
+                tok.subst_quoting,
+                &tok.process_subst,
+                &ctx.variables,
+            )
         } else if tok.has_subst {
-            expand::expand_token_globs_with_quoting(&tok.value, tok.subst_quoting)
+            expand::expand_token_globs_with_quoting(
+                &tok.value,
+                tok.subst_quoting,
+                &tok.process_subst,
+                &ctx.variables,
+            )
         } else {
-            expand::expand_token_globs(&tok.value)
+            expand::expand_token_globs(&tok.value, &ctx.variables)
         };
         match expanded_result {
-            Ok(parts) => expanded.extend(parts),
+            Ok(parts) => expanded.extend(parts.into_iter().map(parser::Word::literal)),
             Err(expand::ExpandError::NoMatches(p)) => {
                 let msg = format!("jarvish: no matches found: {p}\n");
                 eprint!("{msg}");
                 return CommandResult::error(msg, 1);
             }
-            Err(expand::ExpandError::Substitution(m)) => {
+            Err(expand::ExpandError::Substitution(m) | expand::ExpandError::Parameter(m)) => {
                 let msg = format!("jarvish: {m}\n");
                 eprint!("{msg}");
                 return CommandResult::error(msg, 1);
@@ -164,6 +200,11 @@ pub fn execute(input: &str) -> CommandResult {
         }
     }
 
+    // 展開結果が空（`$UNSET_VAR` のみ等）の場合は何もしない（bash 互換）
+    if expanded.is_empty() {
+        return CommandResult::success(String::new());
+    }
+
     let command_list = match parser::parse_command_list(expanded) {
         Ok(cl) => cl,
         Err(e) => {
@@ -175,63 +216,158 @@ pub fn execute(input: &str) -> CommandResult {
 
     debug!(
         pipeline_count = command_list.rest.len() + 1,
-        first_cmd = %command_list.first.commands[0].cmd,
+        first_cmd = %command_list.first.commands.first().map_or("", |c| c.cmd.as_str()),
         "execute() parsed command list"
     );
 
     if command_list.rest.is_empty() {
         let pipeline = &command_list.first;
-        return execute_pipeline(pipeline);
+        return execute_pipeline(pipeline, ctx);
     }
 
-    run_command_list_with_builtins(&command_list)
+    run_command_list_with_builtins(&command_list, ctx)
 }
 
 /// 単一パイプラインを実行する（ビルトイン最適化パス付き）。
-fn execute_pipeline(pipeline: &parser::Pipeline) -> CommandResult {
+///
+/// 複数コマンドのパイプラインに含まれるビルトインは、パイプライン起動前に先行実行し、
+/// その出力を `printf '%s' <output>` に置き換えて外部コマンドと合成する。
+/// - 先頭: 全てのビルトインが対象（`history | grep git`）
+/// - 2 段目以降: stdin を読まない出力専用ビルトインのみ対象（`grep x | history`）。
+///   前段の出力は bash と同じく読み捨てられる。それ以外のビルトインはエラーにする
+///
+/// ビルトインを含む場合は先に全段を検査し（パイプ中で使えるビルトインか・外部コマンドが
+/// 存在するか）、すべて通ったときだけ実行する。検査に失敗した場合はどの段も実行しない。
+/// 先行実行するビルトインの出力は `printf` で後段に渡すため、ここでは表示しない。
+fn execute_pipeline(pipeline: &parser::Pipeline, ctx: &ExecContext) -> CommandResult {
     if pipeline.commands.len() == 1 && pipeline.commands[0].redirects.is_empty() {
         let simple = &pipeline.commands[0];
+        if let Some(result) = try_assign_shell_vars(simple, &ctx.variables) {
+            return result;
+        }
         let args: Vec<&str> = simple.args.iter().map(|s| s.as_str()).collect();
-        if let Some(result) = builtins::dispatch_builtin(&simple.cmd, &args) {
+        if let Some(result) = builtins::dispatch_builtin(&simple.cmd, &args, ctx) {
             debug!(command = %simple.cmd, "Dispatched as builtin command");
             return result;
         }
     }
 
     if pipeline.commands.len() > 1 {
-        let first = &pipeline.commands[0];
-        let args: Vec<&str> = first.args.iter().map(|s| s.as_str()).collect();
-        if let Some(result) = builtins::dispatch_builtin(&first.cmd, &args) {
+        let builtin_stages: Vec<bool> = pipeline
+            .commands
+            .iter()
+            .map(|simple| {
+                let args: Vec<&str> = simple.args.iter().map(|s| s.as_str()).collect();
+                builtins::is_builtin_call(&simple.cmd, &args)
+                    && !builtins::defers_to_external_in_pipeline(&simple.cmd)
+            })
+            .collect();
+        if !builtin_stages.contains(&true) {
+            return run_external(pipeline, ctx);
+        }
+        if let Err(result) = check_pipeline_stages(pipeline, &builtin_stages) {
+            return result;
+        }
+
+        let stage_ctx = ExecContext {
+            capture_stdout: true,
+            ..ctx.clone()
+        };
+        let mut new_commands = pipeline.commands.clone();
+        for (i, simple) in new_commands.iter_mut().enumerate() {
+            if !builtin_stages[i] {
+                continue;
+            }
+            let args: Vec<&str> = simple.args.iter().map(|s| s.as_str()).collect();
+            let Some(result) = builtins::dispatch_builtin(&simple.cmd, &args, &stage_ctx) else {
+                continue;
+            };
             debug!(
-                command = %first.cmd,
+                command = %simple.cmd,
+                stage = i + 1,
                 exit_code = result.exit_code,
-                "Builtin at pipeline head, replacing with printf"
+                "Builtin in pipeline, replacing with printf"
             );
             if result.exit_code != 0 {
                 return result;
             }
-            let mut new_commands = pipeline.commands.clone();
-            new_commands[0] = parser::SimpleCommand {
+            *simple = parser::SimpleCommand {
                 cmd: "printf".to_string(),
                 args: vec!["%s".to_string(), result.stdout],
-                redirects: vec![],
+                redirects: std::mem::take(&mut simple.redirects),
             };
-            let new_pipeline = parser::Pipeline {
-                commands: new_commands,
-            };
-            return exec::run_pipeline(&new_pipeline);
         }
+        let new_pipeline = parser::Pipeline {
+            commands: new_commands,
+        };
+        return run_external(&new_pipeline, ctx);
     }
 
-    exec::run_pipeline(pipeline)
+    run_external(pipeline, ctx)
+}
+
+/// 外部コマンドのパイプラインを実行する（`ctx.capture_stdout` なら stdout を表示せずキャプチャ）。
+fn run_external(pipeline: &parser::Pipeline, ctx: &ExecContext) -> CommandResult {
+    if ctx.capture_stdout {
+        exec::run_pipeline_captured(pipeline, ctx)
+    } else {
+        exec::run_pipeline(pipeline, ctx)
+    }
+}
+
+/// ビルトインを含むパイプラインの全段を実行前に検査する。
+///
+/// - 2 段目以降のビルトインは出力専用のもの（[`builtins::is_pipe_output_builtin`]）に限る
+/// - 外部コマンドの段は `$PATH` 上（またはパス指定）に実行ファイルが存在すること
+fn check_pipeline_stages(
+    pipeline: &parser::Pipeline,
+    builtin_stages: &[bool],
+) -> Result<(), CommandResult> {
+    for (i, (simple, &is_builtin)) in pipeline.commands.iter().zip(builtin_stages).enumerate() {
+        let (msg, exit_code) = if is_builtin {
+            let args: Vec<&str> = simple.args.iter().map(|s| s.as_str()).collect();
+            if i == 0 || builtins::is_pipe_output_builtin(&simple.cmd, &args) {
+                continue;
+            }
+            let msg = format!(
+                "jarvish: {}: builtin cannot read from a pipe (only output-only builtins such as history, export, cwd may follow `|`)\n",
+                simple.cmd
+            );
+            (msg, 1)
+        } else if which::which(&simple.cmd).is_err() {
+            (format!("jarvish: {}: command not found\n", simple.cmd), 127)
+        } else {
+            continue;
+        };
+        eprint!("{msg}");
+        return Err(CommandResult::error(msg, exit_code));
+    }
+    Ok(())
+}
+
+/// `name=value` の代入語だけからなるコマンドを `vars` への代入として実行する。
+///
+/// 代入語以外を含む場合（`FOO=bar cmd` 等）は `None` を返し、通常のコマンドとして扱う。
+fn try_assign_shell_vars(
+    simple: &parser::SimpleCommand,
+    vars: &ShellVariables,
+) -> Option<CommandResult> {
+    let assignments: Vec<(&str, &str)> = std::iter::once(&simple.cmd)
+        .chain(&simple.args)
+        .map(|word| shell_vars::parse_assignment(word))
+        .collect::<Option<_>>()?;
+    for (name, value) in assignments {
+        vars.assign(name, value);
+    }
+    Some(CommandResult::success(String::new()))
 }
 
 /// コマンドリストをビルトイン対応で実行する。
-fn run_command_list_with_builtins(list: &parser::CommandList) -> CommandResult {
+fn run_command_list_with_builtins(list: &parser::CommandList, ctx: &ExecContext) -> CommandResult {
     use super::LoopAction;
     use parser::Connector;
 
-    let mut result = execute_pipeline(&list.first);
+    let mut result = execute_pipeline(&list.first, ctx);
 
     if result.action == LoopAction::Exit {
         return result;
@@ -245,7 +381,7 @@ fn run_command_list_with_builtins(list: &parser::CommandList) -> CommandResult {
         };
 
         if should_run {
-            let next = execute_pipeline(pipeline);
+            let next = execute_pipeline(pipeline, ctx);
             result.stdout.push_str(&next.stdout);
             result.stderr.push_str(&next.stderr);
             result.exit_code = next.exit_code;
@@ -289,21 +425,21 @@ mod tests {
 
     #[test]
     fn try_builtin_apostrophe_returns_none() {
-        assert!(try_builtin("I'm tired, Jarvis.").is_none());
+        assert!(try_builtin("I'm tired, Jarvis.", &ExecContext::default()).is_none());
     }
 
     #[test]
     fn try_builtin_natural_language_returns_none() {
-        assert!(try_builtin("jarvis, how are you doing?").is_none());
-        assert!(try_builtin("J, please commit").is_none());
-        assert!(try_builtin("What's the error?").is_none());
+        assert!(try_builtin("jarvis, how are you doing?", &ExecContext::default()).is_none());
+        assert!(try_builtin("J, please commit", &ExecContext::default()).is_none());
+        assert!(try_builtin("What's the error?", &ExecContext::default()).is_none());
     }
 
     #[test]
     #[serial]
     fn try_builtin_cd_still_works() {
         let _guard = CwdGuard::new();
-        let result = try_builtin("cd /tmp");
+        let result = try_builtin("cd /tmp", &ExecContext::default());
         assert!(result.is_some());
         let result = result.unwrap();
         assert_eq!(result.exit_code, 0);
@@ -311,7 +447,7 @@ mod tests {
 
     #[test]
     fn try_builtin_exit_still_works() {
-        let result = try_builtin("exit");
+        let result = try_builtin("exit", &ExecContext::default());
         assert!(result.is_some());
         let result = result.unwrap();
         assert_eq!(result.action, LoopAction::Exit);
@@ -319,45 +455,88 @@ mod tests {
 
     #[test]
     fn try_builtin_non_builtin_command_returns_none() {
-        assert!(try_builtin("git status").is_none());
-        assert!(try_builtin("ls -la").is_none());
-        assert!(try_builtin("echo hello").is_none());
+        assert!(try_builtin("git status", &ExecContext::default()).is_none());
+        assert!(try_builtin("ls -la", &ExecContext::default()).is_none());
+        assert!(try_builtin("echo hello", &ExecContext::default()).is_none());
     }
 
     #[test]
     fn try_builtin_with_pipe_returns_none() {
-        assert!(try_builtin("history | less").is_none());
-        assert!(try_builtin("export | grep PATH").is_none());
-        assert!(try_builtin("cwd | cat").is_none());
+        assert!(try_builtin("history | less", &ExecContext::default()).is_none());
+        assert!(try_builtin("export | grep PATH", &ExecContext::default()).is_none());
+        assert!(try_builtin("cwd | cat", &ExecContext::default()).is_none());
+    }
+
+    #[test]
+    fn try_builtin_with_quoted_operator_is_not_deferred() {
+        // クォートされた `>` はリダイレクトではないため、ビルトインとして処理される
+        assert!(try_builtin("cwd \">\"", &ExecContext::default()).is_some());
     }
 
     #[test]
     fn try_builtin_with_redirect_returns_none() {
-        assert!(try_builtin("history > /tmp/hist.txt").is_none());
-        assert!(try_builtin("export >> /tmp/env.txt").is_none());
+        assert!(try_builtin("history > /tmp/hist.txt", &ExecContext::default()).is_none());
+        assert!(try_builtin("export >> /tmp/env.txt", &ExecContext::default()).is_none());
     }
 
     #[test]
     fn execute_pipe_two_commands() {
-        let result = execute("echo hello | cat");
+        let result = execute("echo hello | cat", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "hello");
     }
 
     #[test]
     fn execute_pipe_with_grep() {
-        let result = execute("printf 'aaa\\nbbb\\nccc\\n' | grep bbb");
+        let result = execute(
+            "printf 'aaa\\nbbb\\nccc\\n' | grep bbb",
+            &ExecContext::default(),
+        );
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "bbb");
     }
 
+    #[test]
+    fn execute_process_substitution() {
+        let result = execute(
+            "diff <(printf 'a\\nb\\n') <(printf 'b\\na\\n' | sort)",
+            &ExecContext::default(),
+        );
+        assert_eq!(result.exit_code, 0);
+
+        let result = execute("diff <(echo a) <(echo b)", &ExecContext::default());
+        assert_eq!(result.exit_code, 1);
+
+        let result = execute("cat <(cat <(echo nested)) | cat", &ExecContext::default());
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout.trim(), "nested");
+
+        let result = execute(
+            "wc -l < <(printf 'x\\ny\\n') | tr -d ' '",
+            &ExecContext::default(),
+        );
+        assert_eq!(result.stdout.trim(), "2");
+    }
+
+    #[test]
+    fn quoted_process_substitution_stays_literal() {
+        // 同じトークンに他の置換があっても、クォート内の `<(` はプロセス置換にしない
+        let result = execute("echo \"$(echo a) <(ls)\"", &ExecContext::default());
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout.trim(), "a <(ls)");
+
+        let result = execute("echo '<(ls)'$(echo x)", &ExecContext::default());
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout.trim(), "<(ls)x");
+    }
+
     #[test]
     fn execute_redirect_stdout_overwrite() {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("out.txt");
         let cmd = format!("echo redirected > {}", path.display());
 
-        let result = execute(&cmd);
+        let result = execute(&cmd, &ExecContext::default());
         assert_eq!(result.exit_code, 0);
 
         let contents = std::fs::read_to_string(&path).unwrap();
@@ -371,7 +550,7 @@ mod tests {
         std::fs::write(&path, "first\n").unwrap();
 
         let cmd = format!("echo second >> {}", path.display());
-        let result = execute(&cmd);
+        let result = execute(&cmd, &ExecContext::default());
         assert_eq!(result.exit_code, 0);
 
         let contents = std::fs::read_to_string(&path).unwrap();
@@ -386,7 +565,7 @@ mod tests {
         std::fs::write(&path, "from_file\n").unwrap();
 
         let cmd = format!("cat < {}", path.display());
-        let result = execute(&cmd);
+        let result = execute(&cmd, &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "from_file");
     }
@@ -395,13 +574,28 @@ mod tests {
     #[serial]
     fn execute_cd_still_works() {
         let _guard = CwdGuard::new();
-        let result = execute("cd /tmp");
+        let result = execute("cd /tmp", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
     }
 
+    #[test]
+    fn execute_quoted_operators_are_plain_arguments() {
+        let result = execute("echo \">\"", &ExecContext::default());
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout.trim(), ">");
+
+        let result = execute("printf 'a|b\\nc\\n' | grep 'a|b'", &ExecContext::default());
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout.trim(), "a|b");
+
+        let result = execute("echo '&&' \\; \"|\"", &ExecContext::default());
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout.trim(), "&& ; |");
+    }
+
     #[test]
     fn execute_simple_command() {
-        let result = execute("echo test123");
+        let result = execute("echo test123", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "test123");
     }
@@ -411,7 +605,7 @@ mod tests {
     fn execute_builtin_pipe_to_cat() {
         let _guard = CwdGuard::new();
         let expected = env::current_dir().unwrap();
-        let result = execute("cwd | cat");
+        let result = execute("cwd | cat", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), expected.display().to_string());
     }
@@ -419,7 +613,7 @@ mod tests {
     #[test]
     #[serial]
     fn execute_builtin_pipe_to_grep() {
-        let result = execute("export | grep PATH");
+        let result = execute("export | grep PATH", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert!(
             result.stdout.contains("PATH"),
@@ -428,9 +622,168 @@ mod tests {
         );
     }
 
+    #[test]
+    #[serial]
+    fn execute_builtin_after_pipe() {
+        let _guard = CwdGuard::new();
+        let expected = env::current_dir().unwrap();
+        let result = execute("echo ignored | cwd", &ExecContext::default());
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout.trim(), expected.display().to_string());
+    }
+
+    #[test]
+    #[serial]
+    fn execute_builtin_in_middle_of_pipeline() {
+        let result = execute(
+            "echo ignored | export | grep -c PATH",
+            &ExecContext::default(),
+        );
+        assert_eq!(result.exit_code, 0);
+        assert_ne!(result.stdout.trim(), "0");
+    }
+
+    #[test]
+    #[serial]
+    fn execute_stateful_builtin_after_pipe_is_error() {
+        let _guard = CwdGuard::new();
+        let before = env::current_dir().unwrap();
+        let result = execute("echo /tmp | cd /", &ExecContext::default());
+        assert_ne!(result.exit_code, 0);
+        assert!(result.stderr.contains("cannot read from a pipe"));
+        assert_eq!(env::current_dir().unwrap(), before);
+    }
+
+    #[test]
+    #[serial]
+    fn invalid_later_stage_runs_no_builtin() {
+        let _guard = CwdGuard::new();
+        let result = execute(
+            "export JARVISH_TEST_PIPE_CHECK=1 | cd /",
+            &ExecContext::default(),
+        );
+        assert_eq!(result.exit_code, 1);
+        assert!(env::var_os("JARVISH_TEST_PIPE_CHECK").is_none());
+
+        let result = execute(
+            "export JARVISH_TEST_PIPE_CHECK=1 | jarvish_no_such_command_xyz",
+            &ExecContext::default(),
+        );
+        assert_eq!(result.exit_code, 127);
+        assert!(result.stderr.contains("command not found"));
+        assert!(env::var_os("JARVISH_TEST_PIPE_CHECK").is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn timeout_in_pipeline_or_with_options_runs_external_timeout() {
+        if which::which("timeout").is_err() {
+            return;
+        }
+        let ctx = ExecContext::default();
+        let result = execute("printf 'a\\n' | timeout 5 cat", &ctx);
+        assert_eq!(result.exit_code, 0, "{}", result.stderr);
+        let result = execute("timeout -s KILL -k 1 5 true", &ctx);
+        assert_eq!(result.exit_code, 0, "{}", result.stderr);
+    }
+
+    #[test]
+    #[serial]
+    fn shell_variable_is_expanded_but_not_exported() {
+        let ctx = ExecContext::default();
+        let result = execute("jarvish_test_local=\"hello world\"", &ctx);
+        assert_eq!(result.exit_code, 0);
+        assert!(env::var("jarvish_test_local").is_err());
+
+        let result = execute("echo \"$jarvish_test_local\"", &ctx);
+        assert_eq!(result.stdout.trim(), "hello world");
+
+        // 子プロセスの環境には含まれない
+        let result = execute("sh -c 'echo \"[${jarvish_test_local}]\"'", &ctx);
+        assert_eq!(result.stdout.trim(), "[]");
+
+        // 別のコンテキストからは見えない
+        let result = execute("echo \"[$jarvish_test_local]\"", &ExecContext::default());
+        assert_eq!(result.stdout.trim(), "[]");
+    }
+
+    #[test]
+    #[serial]
+    fn shell_variable_is_expanded_in_command_substitution() {
+        let ctx = ExecContext::default();
+        execute("jarvish_test_subst=inner", &ctx);
+        let result = execute("echo $(echo $jarvish_test_subst)", &ctx);
+        assert_eq!(result.stdout.trim(), "inner");
+    }
+
+    #[test]
+    #[serial]
+    fn assignment_to_exported_variable_updates_environment() {
+        let ctx = ExecContext::default();
+        unsafe {
+            env::set_var("JARVISH_TEST_EXPORTED", "old");
+        }
+        let result = execute("JARVISH_TEST_EXPORTED=new", &ctx);
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(env::var("JARVISH_TEST_EXPORTED").as_deref(), Ok("new"));
+
+        // シェル変数のコピーは作らず、子プロセスにも新しい値が渡る
+        assert!(ctx.variables.remove("JARVISH_TEST_EXPORTED").is_none());
+        let result = execute("sh -c 'echo \"[$JARVISH_TEST_EXPORTED]\"'", &ctx);
+        assert_eq!(result.stdout.trim(), "[new]");
+        unsafe {
+            env::remove_var("JARVISH_TEST_EXPORTED");
+        }
+    }
+
+    #[test]
+    fn execute_blank_input_is_noop() {
+        for input in ["", "   ", "\t \n"] {
+            let result = execute(input, &ExecContext::default());
+            assert_eq!(result.exit_code, 0, "input: {input:?}");
+            assert!(result.stderr.is_empty(), "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn execute_degenerate_operators_are_syntax_errors() {
+        let cases = [
+            ("|", "jarvish: syntax error near unexpected token '|'\n"),
+            (";", "jarvish: syntax error near unexpected token ';'\n"),
+            ("  ;  ", "jarvish: syntax error near unexpected token ';'\n"),
+            ("&&", "jarvish: syntax error near unexpected token '&&'\n"),
+            ("||", "jarvish: syntax error near unexpected token '||'\n"),
+            (
+                "| echo hi",
+                "jarvish: syntax error near unexpected token '|'\n",
+            ),
+            (
+                "; echo hi",
+                "jarvish: syntax error near unexpected token ';'\n",
+            ),
+            (
+                "echo hi |",
+                "jarvish: syntax error near '|': unexpected end of command\n",
+            ),
+            (
+                "echo hi &&",
+                "jarvish: syntax error near '&&': unexpected end of command\n",
+            ),
+            (
+                "echo hi >",
+                "jarvish: syntax error near '>': expected filename\n",
+            ),
+        ];
+        for (input, expected) in cases {
+            let result = execute(input, &ExecContext::default());
+            assert_eq!(result.exit_code, 1, "input: {input:?}");
+            assert_eq!(result.stderr, expected, "input: {input:?}");
+        }
+    }
+
     #[test]
     fn execute_and_both_succeed() {
-        let result = execute("echo hello && echo world");
+        let result = execute("echo hello && echo world", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("hello"));
         assert!(result.stdout.contains("world"));
@@ -438,14 +791,14 @@ mod tests {
 
     #[test]
     fn execute_and_first_fails() {
-        let result = execute("false && echo skipped");
+        let result = execute("false && echo skipped", &ExecContext::default());
         assert_eq!(result.exit_code, 1);
         assert!(!result.stdout.contains("skipped"));
     }
 
     #[test]
     fn execute_and_three_commands() {
-        let result = execute("echo a && echo b && echo c");
+        let result = execute("echo a && echo b && echo c", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("a"));
         assert!(result.stdout.contains("b"));
@@ -454,28 +807,28 @@ mod tests {
 
     #[test]
     fn execute_or_first_fails() {
-        let result = execute("false || echo fallback");
+        let result = execute("false || echo fallback", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("fallback"));
     }
 
     #[test]
     fn execute_or_first_succeeds() {
-        let result = execute("true || echo skipped");
+        let result = execute("true || echo skipped", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert!(!result.stdout.contains("skipped"));
     }
 
     #[test]
     fn execute_semi_always_runs() {
-        let result = execute("false ; echo always");
+        let result = execute("false ; echo always", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("always"));
     }
 
     #[test]
     fn execute_and_then_or() {
-        let result = execute("false && echo skip || echo rescue");
+        let result = execute("false && echo skip || echo rescue", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert!(!result.stdout.contains("skip"));
         assert!(result.stdout.contains("rescue"));
@@ -483,24 +836,24 @@ mod tests {
 
     #[test]
     fn try_builtin_with_and_returns_none() {
-        assert!(try_builtin("cd /tmp && echo done").is_none());
+        assert!(try_builtin("cd /tmp && echo done", &ExecContext::default()).is_none());
     }
 
     #[test]
     fn try_builtin_with_or_returns_none() {
-        assert!(try_builtin("cd /nonexistent || echo fail").is_none());
+        assert!(try_builtin("cd /nonexistent || echo fail", &ExecContext::default()).is_none());
     }
 
     #[test]
     fn try_builtin_with_semi_returns_none() {
-        assert!(try_builtin("cd /tmp ; echo done").is_none());
+        assert!(try_builtin("cd /tmp ; echo done", &ExecContext::default()).is_none());
     }
 
     #[test]
     #[serial]
     fn execute_builtin_and_command() {
         let _guard = CwdGuard::new();
-        let result = execute("cd /tmp && echo done");
+        let result = execute("cd /tmp && echo done", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("done"));
     }
@@ -509,14 +862,14 @@ mod tests {
 
     #[test]
     fn execute_brace_expansion_via_echo() {
-        let result = execute("echo {a,b,c}");
+        let result = execute("echo {a,b,c}", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "a b c");
     }
 
     #[test]
     fn execute_brace_numeric_range_via_echo() {
-        let result = execute("echo {1..3}");
+        let result = execute("echo {1..3}", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "1 2 3");
     }
@@ -531,7 +884,7 @@ mod tests {
         std::fs::write(dir.path().join("b.txt"), "").unwrap();
         std::fs::write(dir.path().join("c.md"), "").unwrap();
 
-        let result = execute("ls *.txt");
+        let result = execute("ls *.txt", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("a.txt"));
         assert!(result.stdout.contains("b.txt"));
@@ -548,7 +901,7 @@ mod tests {
         std::fs::write(dir.path().join("a.md"), "").unwrap();
         std::fs::write(dir.path().join("b.log"), "").unwrap();
 
-        let result = execute("ls *.{txt,md}");
+        let result = execute("ls *.{txt,md}", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert!(result.stdout.contains("a.txt"));
         assert!(result.stdout.contains("a.md"));
@@ -561,16 +914,43 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let _guard = CwdGuard::new();
         env::set_current_dir(dir.path()).unwrap();
-        let result = execute("echo '*'");
+        let result = execute("echo '*'", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "*");
     }
 
+    #[test]
+    #[serial]
+    fn export_quoted_value_expands_in_double_quoted_echo() {
+        let original = env::var("JARVISH_TEST_DQ_MSG").ok();
+        let result = try_builtin(
+            "export JARVISH_TEST_DQ_MSG=\"hello   world\"",
+            &ExecContext::default(),
+        )
+        .unwrap();
+        assert_eq!(result.exit_code, 0);
+
+        let result = execute("echo \"$JARVISH_TEST_DQ_MSG\"", &ExecContext::default());
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout.trim(), "hello   world");
+
+        // シングルクォート内は展開しない
+        let result = execute("echo '$JARVISH_TEST_DQ_MSG'", &ExecContext::default());
+        assert_eq!(result.stdout.trim(), "$JARVISH_TEST_DQ_MSG");
+
+        unsafe {
+            match original {
+                Some(v) => env::set_var("JARVISH_TEST_DQ_MSG", v),
+                None => env::remove_var("JARVISH_TEST_DQ_MSG"),
+            }
+        }
+    }
+
     #[test]
     #[serial]
     fn execute_quoted_brace_not_expanded() {
         let _guard = CwdGuard::new();
-        let result = execute("echo \"{a,b}\"");
+        let result = execute("echo \"{a,b}\"", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "{a,b}");
     }
@@ -582,7 +962,7 @@ mod tests {
         let _guard = CwdGuard::new();
         env::set_current_dir(dir.path()).unwrap();
 
-        let result = execute("ls *.nonexistent_xyz && echo OK");
+        let result = execute("ls *.nonexistent_xyz && echo OK", &ExecContext::default());
         assert_ne!(result.exit_code, 0);
         assert!(!result.stdout.contains("OK"));
         // stderr もしくは stdout に no matches found が含まれる
@@ -604,7 +984,7 @@ mod tests {
         std::fs::write(dir.path().join("a.txt"), "").unwrap();
         std::fs::write(dir.path().join("b.txt"), "").unwrap();
 
-        let result = execute("ls *.txt | head -n 1");
+        let result = execute("ls *.txt | head -n 1", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         let lines: Vec<&str> = result.stdout.lines().collect();
         assert_eq!(lines.len(), 1);
@@ -620,7 +1000,7 @@ mod tests {
         let out_path = dir.path().join("out.dat");
 
         let cmd = format!("cat *.txt > {}", out_path.display());
-        let result = execute(&cmd);
+        let result = execute(&cmd, &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         let contents = std::fs::read_to_string(&out_path).unwrap();
         assert_eq!(contents, "hello");
@@ -629,35 +1009,35 @@ mod tests {
     #[test]
     fn try_builtin_brace_expansion_for_echo_returns_none() {
         // echo はビルトインではない（externalにフォールスルー）
-        assert!(try_builtin("echo {a,b}").is_none());
+        assert!(try_builtin("echo {a,b}", &ExecContext::default()).is_none());
     }
 
     // ── コマンド置換 E2E テスト (#266) ──
 
     #[test]
     fn cmdsubst_basic() {
-        let result = execute("echo $(echo hello)");
+        let result = execute("echo $(echo hello)", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "hello");
     }
 
     #[test]
     fn cmdsubst_word_split_multiple_args() {
-        let result = execute("echo $(echo a b c)");
+        let result = execute("echo $(echo a b c)", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "a b c");
     }
 
     #[test]
     fn cmdsubst_backtick() {
-        let result = execute("echo `echo hi`");
+        let result = execute("echo `echo hi`", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "hi");
     }
 
     #[test]
     fn cmdsubst_double_quoted_preserves_whitespace() {
-        let result = execute("echo \"$(printf 'a   b')\"");
+        let result = execute("echo \"$(printf 'a   b')\"", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         // ダブルクォート内なので内部の連続空白が保持される
         assert_eq!(result.stdout.trim_end_matches('\n'), "a   b");
@@ -665,7 +1045,7 @@ mod tests {
 
     #[test]
     fn cmdsubst_unquoted_collapses_whitespace() {
-        let result = execute("echo $(printf 'a   b')");
+        let result = execute("echo $(printf 'a   b')", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         // クォート外なので単語分割され、echo が 1 空白で連結する
         assert_eq!(result.stdout.trim(), "a b");
@@ -673,34 +1053,37 @@ mod tests {
 
     #[test]
     fn cmdsubst_single_quoted_is_literal() {
-        let result = execute("echo '$(echo X)'");
+        let result = execute("echo '$(echo X)'", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "$(echo X)");
     }
 
     #[test]
     fn cmdsubst_embedded_in_word() {
-        let result = execute("echo prefix-$(echo mid)-suffix");
+        let result = execute("echo prefix-$(echo mid)-suffix", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "prefix-mid-suffix");
     }
 
     #[test]
     fn cmdsubst_nested() {
-        let result = execute("echo $(echo $(echo deep))");
+        let result = execute("echo $(echo $(echo deep))", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "deep");
     }
 
     #[test]
     fn cmdsubst_nonexistent_command_no_panic_nonzero_exit() {
-        let result = execute("echo $(this_command_does_not_exist_zzz)");
+        let result = execute(
+            "echo $(this_command_does_not_exist_zzz)",
+            &ExecContext::default(),
+        );
         assert_ne!(result.exit_code, 0);
     }
 
     #[test]
     fn cmdsubst_unterminated_is_parse_error() {
-        let result = execute("echo $(echo unclosed");
+        let result = execute("echo $(echo unclosed", &ExecContext::default());
         assert_ne!(result.exit_code, 0);
         let combined = format!("{}{}", result.stdout, result.stderr);
         assert!(
@@ -715,7 +1098,7 @@ mod tests {
 
     #[test]
     fn cmdsubst_with_pipe() {
-        let result = execute("echo $(echo foo) | cat");
+        let result = execute("echo $(echo foo) | cat", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "foo");
     }
@@ -724,7 +1107,7 @@ mod tests {
     fn cmdsubst_trailing_newlines_stripped() {
         // ダブルクォートで囲み glob（`[...]`）を抑止しつつ、末尾改行の全除去を検証する。
         // unquoted 版だと `[x]` がグロブパターンとして解釈され no-match になるため。
-        let result = execute("echo \"[$(printf 'x\\n\\n')]\"");
+        let result = execute("echo \"[$(printf 'x\\n\\n')]\"", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         // 末尾改行が全除去され、`[x]` になる
         assert_eq!(result.stdout.trim(), "[x]");
@@ -733,7 +1116,7 @@ mod tests {
     #[test]
     fn cmdsubst_operator_inside_span_is_a_pipeline() {
         // span 内の `|` はサブシェルのパイプとして実行される
-        let result = execute("echo $(echo foo | cat)");
+        let result = execute("echo $(echo foo | cat)", &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "foo");
     }
@@ -753,7 +1136,7 @@ mod tests {
         // スタブ経由になる。修正前はここで register が「成功」し
         // データが消えるだけだった（観測不能な data loss）。
         // 修正後は complete 呼び出し自体がエラー終了として観測できる。
-        let result = execute("complete -c x -a y ; echo after");
+        let result = execute("complete -c x -a y ; echo after", &ExecContext::default());
         // 最終的な終了コードは最後のコマンド（echo）の結果で上書きされるが、
         // complete 単体の失敗は stderr に必ず現れる（無音の成功ではない）。
         assert!(
@@ -768,7 +1151,10 @@ mod tests {
 
     #[test]
     fn complete_erase_in_and_list_surfaces_error() {
-        let result = execute("complete -e -c x && echo unreachable");
+        let result = execute(
+            "complete -e -c x && echo unreachable",
+            &ExecContext::default(),
+        );
         assert!(result.stderr.contains("standalone command"));
         assert_ne!(result.exit_code, 0);
         // -c 付き -e はスタブ経路でエラー終了するため && の後続は実行されない。
@@ -780,7 +1166,7 @@ mod tests {
         // パイプライン内（複数コマンド）の先頭ビルトインとして complete が
         // 呼ばれるケース。dispatch_builtin スタブ経由になるため、
         // 一覧表示相当の「空文字列で成功」ではなくエラーになる。
-        let result = execute("complete | cat");
+        let result = execute("complete | cat", &ExecContext::default());
         assert_ne!(
             result.exit_code, 0,
             "complete at pipeline head must not silently succeed with empty output"
@@ -790,8 +1176,8 @@ mod tests {
     #[test]
     fn complete_help_still_works_through_command_list() {
         // --help は standalone 経路を経由しない状況でも動き続ける必要がある
-        // （help.rs の `dispatch_builtin(cmd, ["--help"])` 委譲との整合）。
-        let result = execute("complete --help ; echo after");
+        // （help.rs の `run_builtin(cmd, ["--help"])` 委譲との整合）。
+        let result = execute("complete --help ; echo after", &ExecContext::default());
         assert!(result.stdout.contains("complete"));
         assert!(result.stdout.contains("after"));
     }
@@ -0,0 +1,222 @@
+//! dry-run モード
+//!
+//! `[shell] dry_run = true` または `--dry-run` 起動時、外部コマンド・ビルトインを
+//! 実行せず、トークン分割・展開・パース後の最終的なコマンド（引数・リダイレクト・
+//! 接続演算子を含む）を表示するだけにする。AI が提案したコマンドも
+//! `dispatch::execute` を経由するため同様にプレビューされる。
+//!
+//! フラグはプロセス全体で共有する（`dispatch` のフリー関数群から参照するため）。
+//! コマンド置換 `$(...)` もコマンドの実行にあたるため、dry-run 中は展開せず
+//! そのまま表示する。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::debug;
+
+use super::builtins::complete::quote_if_needed;
+use super::parser::{self, Connector, Pipeline, Redirect, Word};
+use super::shell_vars::ShellVariables;
+use super::{expand, CommandResult};
+
+/// dry-run モードが有効かどうか
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// dry-run 中でも実際に実行するビルトイン。
+///
+/// シェルを終了する手段（`exit` / `logout`）と、設定を再読み込みして
+/// dry-run を解除する手段（`source ~/.config/jarvish/config.toml`）は残しておく。
+/// `source` でスクリプトを読み込んだ場合も、各行は通常どおり dry-run の対象になる。
+const EXEMPT_BUILTINS: &[&str] = &["exit", "help", "logout", "source"];
+
+/// dry-run モードの有効/無効を切り替える。
+pub fn set_enabled(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+/// dry-run モードが有効かどうかを返す。
+pub fn is_enabled() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// dry-run 中でも実行するビルトインかどうか。
+pub fn is_exempt(cmd: &str) -> bool {
+    EXEMPT_BUILTINS.contains(&cmd)
+}
+
+/// 入力を実行せず、展開・パース結果を `[dry-run] ...` 形式で表示する。
+///
+/// パイプライン 1 本につき 1 行を出力し、2 本目以降は接続演算子
+/// （`&&` / `||` / `;`）を先頭に付ける。終了コードは常に 0。
+/// トークン分割・構文エラーのみ通常どおりエラーとして返す。
+pub fn preview(input: &str, vars: &ShellVariables) -> CommandResult {
+    let tokens = match expand::split_quoted_with_vars(input.trim(), vars) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let msg = format!("jarvish: parse error: {e}\n");
+            eprint!("{msg}");
+            return CommandResult::error(msg, 1);
+        }
+    };
+    if tokens.is_empty() {
+        return CommandResult::success(String::new());
+    }
+
+    let expanded = expand_without_side_effects(tokens, vars);
+    let list = match parser::parse_command_list(expanded) {
+        Ok(list) => list,
+        Err(e) => {
+            let msg = format!("jarvish: {e}\n");
+            eprint!("{msg}");
+            return CommandResult::error(msg, 1);
+        }
+    };
+
+    let mut output = format!("[dry-run] {}\n", format_pipeline(&list.first));
+    for (connector, pipeline) in &list.rest {
+        let op = match connector {
+            Connector::And => "&&",
+            Connector::Or => "||",
+            Connector::Semi => ";",
+        };
+        output.push_str(&format!("[dry-run] {op} {}\n", format_pipeline(pipeline)));
+    }
+    debug!(input = %input, preview = %output, "Dry-run preview");
+    print!("{output}");
+    CommandResult::success(output)
+}
+
+/// `dispatch::execute` と同じ展開を行うが、コマンド置換は実行せず元の文字列のまま残す。
+/// グロブが何にもマッチしない場合もエラーにせず、パターンをそのまま表示する。
+fn expand_without_side_effects(tokens: Vec<expand::Token>, vars: &ShellVariables) -> Vec<Word> {
+    let mut expanded = Vec::with_capacity(tokens.len());
+    for tok in tokens {
+        if tok.is_operator() {
+            expanded.push(Word::from(tok.value));
+            continue;
+        }
+        if tok.quoted || tok.has_subst {
+            expanded.push(Word::literal(tok.value));
+            continue;
+        }
+        match expand::expand_token_globs(&tok.value, vars) {
+            Ok(parts) => expanded.extend(parts.into_iter().map(Word::literal)),
+            Err(_) => expanded.push(Word::literal(tok.value)),
+        }
+    }
+    expanded
+}
+
+/// パイプラインを `cmd arg ... > file | cmd2 ...` の形に整形する。
+/// 引数は再入力可能な形でクォートする。
+fn format_pipeline(pipeline: &Pipeline) -> String {
+    pipeline
+        .commands
+        .iter()
+        .map(|command| {
+            let mut parts = vec![quote_if_needed(&command.cmd)];
+            parts.extend(command.args.iter().map(|a| quote_if_needed(a)));
+            for redirect in &command.redirects {
+                let (op, target) = match redirect {
+                    Redirect::StdoutOverwrite(path) => (">", Some(path)),
+                    Redirect::StdoutAppend(path) => (">>", Some(path)),
+                    Redirect::StdinFrom(path) => ("<", Some(path)),
+                    Redirect::BothToFile(path) => ("&>", Some(path)),
+                    Redirect::BothAppend(path) => ("&>>", Some(path)),
+                    Redirect::StdoutToStderr => (">&2", None),
+                };
+                match target {
+                    Some(path) => parts.push(format!("{op} {}", quote_if_needed(path))),
+                    None => parts.push(op.to_string()),
+                }
+            }
+            parts.join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_simple_command() {
+        let result = preview("git log --oneline", &ShellVariables::default());
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout, "[dry-run] git log --oneline\n");
+    }
+
+    #[test]
+    fn preview_pipeline_with_redirects() {
+        let result = preview(
+            "sort < in.txt | uniq -c >> 'out file.txt'",
+            &ShellVariables::default(),
+        );
+        assert_eq!(
+            result.stdout,
+            "[dry-run] sort < in.txt | uniq -c >> 'out file.txt'\n"
+        );
+    }
+
+    #[test]
+    fn preview_fd_redirects() {
+        let result = preview(
+            "make &> build.log && echo failed >&2",
+            &ShellVariables::default(),
+        );
+        assert_eq!(
+            result.stdout,
+            "[dry-run] make &> build.log\n[dry-run] && echo failed >&2\n"
+        );
+    }
+
+    #[test]
+    fn preview_command_list_shows_connectors() {
+        let result = preview(
+            "make && make install || echo failed ; echo done",
+            &ShellVariables::default(),
+        );
+        assert_eq!(
+            result.stdout,
+            "[dry-run] make\n\
+             [dry-run] && make install\n\
+             [dry-run] || echo failed\n\
+             [dry-run] ; echo done\n"
+        );
+    }
+
+    #[test]
+    fn preview_does_not_run_command_substitution() {
+        let result = preview("echo $(rm -rf /tmp/never)", &ShellVariables::default());
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout, "[dry-run] echo '$(rm -rf /tmp/never)'\n");
+    }
+
+    #[test]
+    fn preview_keeps_unmatched_glob() {
+        let result = preview(
+            "rm /nonexistent_jarvish_dir/*.tmp",
+            &ShellVariables::default(),
+        );
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(
+            result.stdout,
+            "[dry-run] rm '/nonexistent_jarvish_dir/*.tmp'\n"
+        );
+    }
+
+    #[test]
+    fn preview_parse_error_is_reported() {
+        let result = preview("echo 'unclosed", &ShellVariables::default());
+        assert_eq!(result.exit_code, 1);
+        assert!(result.stderr.contains("parse error"));
+    }
+
+    #[test]
+    fn exempt_builtins() {
+        assert!(is_exempt("exit"));
+        assert!(is_exempt("source"));
+        assert!(!is_exempt("cd"));
+        assert!(!is_exempt("export"));
+    }
+}
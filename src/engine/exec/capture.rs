@@ -8,19 +8,19 @@ use std::process::{Command, Stdio};
 
 use tracing::debug;
 
-use crate::engine::job_control::{
-    job_control_enabled, pipeline_pgid, pre_exec_setpgid, TerminalForegroundGuard,
-};
+use crate::engine::job_control::{job_control_enabled, pre_exec_setpgid, TerminalForegroundGuard};
 use crate::engine::parser::{Pipeline, SimpleCommand};
-use crate::engine::redirect::find_stdin_redirect;
-use crate::engine::{CommandResult, LoopAction};
+use crate::engine::redirect::open_redirects;
+use crate::engine::{CommandResult, ExecContext};
+
+use super::PipelineChildren;
 
 /// パイプラインを実行し、stdout をターミナルに表示せずキャプチャして返す。
 ///
 /// UNIX パイプのセマンティクスに従い:
 /// - stdout: `Stdio::piped()` でキャプチャ（ターミナルに表示しない）
 /// - stderr: `Stdio::inherit()` でターミナルに直接表示
-pub(super) fn run_pipeline_captured(pipeline: &Pipeline) -> CommandResult {
+pub(super) fn run_pipeline_captured(pipeline: &Pipeline, ctx: &ExecContext) -> CommandResult {
     let n = pipeline.commands.len();
     debug!(pipeline_length = n, "Running pipeline (captured mode)");
 
@@ -29,22 +29,23 @@ pub(super) fn run_pipeline_captured(pipeline: &Pipeline) -> CommandResult {
     }
 
     if n == 1 {
-        return run_single_command_captured(&pipeline.commands[0]);
+        return run_single_command_captured(&pipeline.commands[0], ctx);
     }
 
-    run_piped_commands_captured(&pipeline.commands)
+    run_piped_commands_captured(&pipeline.commands, ctx)
 }
 
 /// 単一コマンドを stdout キャプチャモードで実行する。
-fn run_single_command_captured(simple: &SimpleCommand) -> CommandResult {
+fn run_single_command_captured(simple: &SimpleCommand, ctx: &ExecContext) -> CommandResult {
     let cmd = &simple.cmd;
     let args: Vec<&str> = simple.args.iter().map(|s| s.as_str()).collect();
 
     debug!(command = %cmd, args = ?args, "Spawning external command (captured mode)");
 
-    let stdin_cfg: Stdio = match find_stdin_redirect(&simple.redirects) {
-        Ok(Some(file)) => file.into(),
-        Ok(None) => Stdio::inherit(),
+    // stdout はキャプチャするため、リダイレクトは stdin のみ接続する
+    // （`>` の出力先ファイルは記述順どおり作成される）
+    let stdin_cfg: Stdio = match open_redirects(&simple.redirects, ctx.mkdir_on_redirect) {
+        Ok(targets) => targets.stdin.map_or_else(Stdio::inherit, Stdio::from),
         Err(e) => return e,
     };
 
@@ -56,6 +57,7 @@ fn run_single_command_captured(simple: &SimpleCommand) -> CommandResult {
         .stdin(stdin_cfg)
         .stdout(Stdio::piped())
         .stderr(Stdio::inherit());
+    super::set_child_cwd(&mut command, super::child_cwd().as_deref());
 
     if enable_job_control {
         unsafe {
@@ -79,13 +81,7 @@ fn run_single_command_captured(simple: &SimpleCommand) -> CommandResult {
                         stdout_size = output.stdout.len(),
                         "External command completed (captured mode)"
                     );
-                    CommandResult {
-                        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                        stderr: String::new(),
-                        exit_code,
-                        action: LoopAction::Continue,
-                        used_alt_screen: false,
-                    }
+                    super::captured_result(&output.stdout, &[], exit_code, false)
                 }
                 Err(e) => {
                     let msg = format!("jarvish: wait error: {e}\n");
@@ -99,16 +95,16 @@ fn run_single_command_captured(simple: &SimpleCommand) -> CommandResult {
 }
 
 /// 複数コマンドのパイプラインを stdout キャプチャモードで実行する。
-fn run_piped_commands_captured(commands: &[SimpleCommand]) -> CommandResult {
+fn run_piped_commands_captured(commands: &[SimpleCommand], ctx: &ExecContext) -> CommandResult {
     let n = commands.len();
-    let mut children: Vec<std::process::Child> = Vec::new();
+    // ジョブ制御: 全段を 1 つのプロセスグループにまとめ、端末フォアグラウンドを委譲する。
+    // 先頭プロセスの pid をジョブ pgid とし、後続段は同じ pgid に join する。
+    // 途中で return した場合はグループごと終了させる。
+    let mut children = PipelineChildren::new(job_control_enabled());
     let mut prev_stdout: Option<std::process::ChildStdout> = None;
-
-    // ジョブ制御: 全段を 1 つのプロセスグループにまとめる。先頭プロセスの
-    // pid をジョブ pgid とし、後続段は同じ pgid に join する。
-    let enable_job_control = job_control_enabled();
-    let mut job_pgid: Option<libc::pid_t> = None;
     let mut fg_guard: Option<TerminalForegroundGuard> = None;
+    // 全段を同じディレクトリで実行する
+    let cwd = super::child_cwd();
 
     for (i, simple) in commands.iter().enumerate() {
         let is_last = i == n - 1;
@@ -118,15 +114,9 @@ fn run_piped_commands_captured(commands: &[SimpleCommand]) -> CommandResult {
         let stdin_cfg: Stdio = if let Some(prev) = prev_stdout.take() {
             prev.into()
         } else {
-            match find_stdin_redirect(&simple.redirects) {
-                Ok(Some(file)) => file.into(),
-                Ok(None) => Stdio::inherit(),
-                Err(e) => {
-                    for mut c in children {
-                        super::kill_and_wait(&mut c);
-                    }
-                    return e;
-                }
+            match open_redirects(&simple.redirects, ctx.mkdir_on_redirect) {
+                Ok(targets) => targets.stdin.map_or_else(Stdio::inherit, Stdio::from),
+                Err(e) => return e,
             }
         };
 
@@ -136,19 +126,13 @@ fn run_piped_commands_captured(commands: &[SimpleCommand]) -> CommandResult {
             .stdin(stdin_cfg)
             .stdout(Stdio::piped())
             .stderr(Stdio::inherit());
+        super::set_child_cwd(&mut command, cwd.as_deref());
 
-        if enable_job_control {
-            let pgid = job_pgid.unwrap_or(0);
-            unsafe {
-                command.pre_exec(move || pre_exec_setpgid(pgid));
-            }
-        }
+        children.prepare(&mut command);
 
         match command.spawn() {
             Ok(mut child) => {
-                if enable_job_control && job_pgid.is_none() {
-                    let pgid = pipeline_pgid(child.id() as libc::pid_t);
-                    job_pgid = Some(pgid);
+                if let (Some(pgid), None) = (children.join(&child), &fg_guard) {
                     fg_guard = TerminalForegroundGuard::new(pgid);
                 }
                 if is_last {
@@ -156,9 +140,7 @@ fn run_piped_commands_captured(commands: &[SimpleCommand]) -> CommandResult {
                     let _fg_guard = fg_guard.take();
                     match child.wait_with_output() {
                         Ok(output) => {
-                            for mut c in children {
-                                let _ = c.wait();
-                            }
+                            children.wait_all();
                             let exit_code = output.status.code().unwrap_or(1);
                             debug!(
                                 command = %cmd,
@@ -166,18 +148,9 @@ fn run_piped_commands_captured(commands: &[SimpleCommand]) -> CommandResult {
                                 stdout_size = output.stdout.len(),
                                 "Pipeline final stage completed (captured mode)"
                             );
-                            return CommandResult {
-                                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                                stderr: String::new(),
-                                exit_code,
-                                action: LoopAction::Continue,
-                                used_alt_screen: false,
-                            };
+                            return super::captured_result(&output.stdout, &[], exit_code, false);
                         }
                         Err(e) => {
-                            for mut c in children {
-                                super::kill_and_wait(&mut c);
-                            }
                             let msg = format!("jarvish: wait error: {e}\n");
                             eprint!("{msg}");
                             return CommandResult::error(msg, 1);
@@ -188,12 +161,7 @@ fn run_piped_commands_captured(commands: &[SimpleCommand]) -> CommandResult {
                     children.push(child);
                 }
             }
-            Err(e) => {
-                for mut c in children {
-                    super::kill_and_wait(&mut c);
-                }
-                return super::spawn_error(cmd, e);
-            }
+            Err(e) => return super::spawn_error(cmd, e),
         }
     }
 
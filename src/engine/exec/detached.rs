@@ -0,0 +1,146 @@
+//! 完了を待たないパイプライン実行（プロセス置換用）
+//!
+//! `<(cmd)` の内部パイプラインを、メインのコマンドと並行して動くよう起動だけして返す。
+//! 最終段の stdout は呼び出し元が渡したパイプの書き込み側に接続する。
+//! 端末のフォアグラウンドはメインのコマンドが使うため委譲せず、全段を独立した
+//! プロセスグループにまとめる。
+
+use std::os::fd::OwnedFd;
+use std::process::{Command, Stdio};
+
+use tracing::debug;
+
+use crate::engine::parser::Pipeline;
+use crate::engine::redirect::open_redirects;
+use crate::engine::CommandResult;
+
+use super::PipelineChildren;
+
+/// 起動だけして完了を待っていないパイプライン。
+///
+/// drop 時に全段が終了していれば回収のみ行い、まだ動いている段があれば
+/// プロセスグループごと終了させる（[`PipelineChildren`] の drop）。
+pub(crate) struct DetachedPipeline {
+    children: PipelineChildren,
+}
+
+impl Drop for DetachedPipeline {
+    fn drop(&mut self) {
+        // 先頭段を回収済みでも、動いている段が残る間はグループ ID が再利用されないため
+        // killpg で残りの段を終了させられる
+        let finished = self
+            .children
+            .children
+            .iter_mut()
+            .all(|child| matches!(child.try_wait(), Ok(Some(_))));
+        if finished {
+            self.children.children.clear();
+        }
+    }
+}
+
+/// パイプラインを完了を待たずに起動し、最終段の stdout を `stdout` に接続する。
+///
+/// - stdin: 先頭段は `<` リダイレクトがなければ `/dev/null`（メインのコマンドと端末入力を取り合わない）
+/// - stderr: `Stdio::inherit()` でターミナルに直接表示
+/// - リダイレクト: 展開は変数表しか受け取らないため `[shell] mkdir_on_redirect` は適用しない
+///
+/// 起動に失敗した場合は起動済みの段を終了させ、エラーの `CommandResult` を返す。
+pub(crate) fn spawn_pipeline_detached(
+    pipeline: &Pipeline,
+    stdout: OwnedFd,
+) -> Result<DetachedPipeline, CommandResult> {
+    let n = pipeline.commands.len();
+    debug!(pipeline_length = n, "Spawning pipeline (detached mode)");
+
+    // 端末を読まない（stdin は `/dev/null`）ため、ジョブ制御の有無に関わらずグループにまとめ、
+    // drop 時に孫プロセスごと終了させられるようにする
+    let mut children = PipelineChildren::new(true);
+    let mut prev_stdout: Option<std::process::ChildStdout> = None;
+    let mut stdout = Some(stdout);
+    // 全段を同じディレクトリで実行する
+    let cwd = super::child_cwd();
+
+    for (i, simple) in pipeline.commands.iter().enumerate() {
+        let is_last = i == n - 1;
+
+        let stdin_cfg: Stdio = match prev_stdout.take() {
+            Some(prev) => prev.into(),
+            None => open_redirects(&simple.redirects, false)?
+                .stdin
+                .map_or_else(Stdio::null, Stdio::from),
+        };
+        let stdout_cfg: Stdio = if is_last {
+            stdout.take().map_or_else(Stdio::piped, Stdio::from)
+        } else {
+            Stdio::piped()
+        };
+
+        let mut command = Command::new(&simple.cmd);
+        command
+            .args(&simple.args)
+            .stdin(stdin_cfg)
+            .stdout(stdout_cfg)
+            .stderr(Stdio::inherit());
+        super::set_child_cwd(&mut command, cwd.as_deref());
+
+        children.prepare(&mut command);
+
+        match command.spawn() {
+            Ok(mut child) => {
+                children.join(&child);
+                if !is_last {
+                    prev_stdout = child.stdout.take();
+                }
+                debug!(command = %simple.cmd, pid = child.id(), stage = i + 1, "Detached stage spawned");
+                children.push(child);
+            }
+            Err(e) => return Err(super::spawn_error(&simple.cmd, e)),
+        }
+    }
+
+    Ok(DetachedPipeline { children })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::engine::parser::SimpleCommand;
+
+    fn simple(cmd: &str, args: &[&str]) -> SimpleCommand {
+        SimpleCommand {
+            cmd: cmd.to_string(),
+            args: args.iter().map(|s| s.to_string()).collect(),
+            redirects: vec![],
+        }
+    }
+
+    #[test]
+    fn last_stage_writes_to_the_given_pipe() {
+        let (mut reader, writer) = os_pipe::pipe().unwrap();
+        let pipeline = Pipeline {
+            commands: vec![simple("printf", &["b\\na\\n"]), simple("sort", &[])],
+        };
+        let detached = spawn_pipeline_detached(&pipeline, writer.into()).unwrap();
+
+        let mut output = String::new();
+        reader.read_to_string(&mut output).unwrap();
+        assert_eq!(output, "a\nb\n");
+        drop(detached);
+    }
+
+    #[test]
+    fn drop_terminates_stages_that_are_still_running() {
+        let (reader, writer) = os_pipe::pipe().unwrap();
+        let pipeline = Pipeline {
+            commands: vec![simple("sleep", &["30"])],
+        };
+        let detached = spawn_pipeline_detached(&pipeline, writer.into()).unwrap();
+        let started = std::time::Instant::now();
+        drop(detached);
+        drop(reader);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    }
+}
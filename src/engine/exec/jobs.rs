@@ -0,0 +1,171 @@
+//! ジョブテーブル（Ctrl+Z で停止した PTY セッション）
+//!
+//! 停止したセッションに 1 から始まるジョブ番号を振って保持する。最後に停止したジョブが
+//! カレントジョブ（`%+`）、その 1 つ前が `%-` になる。
+//!
+//! テーブルはプロセス全体で共有する（コマンド実行経路とビルトインのフリー関数から参照するため）。
+
+use std::sync::{Mutex, MutexGuard};
+
+use super::pty_session::PtySession;
+use crate::engine::job_control::{reap_if_exited, signal_session};
+
+/// 停止中のジョブ
+struct Job {
+    id: usize,
+    session: PtySession,
+}
+
+/// ジョブテーブル（停止した順）
+static JOBS: Mutex<Vec<Job>> = Mutex::new(Vec::new());
+
+/// ジョブテーブルのロックを取得する（poison 時もそのまま使う）。
+fn jobs() -> MutexGuard<'static, Vec<Job>> {
+    JOBS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// `jobs` の 1 行分の情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct JobInfo {
+    pub(crate) id: usize,
+    pub(crate) command: String,
+    /// `+`（カレント）/ `-`（1 つ前）/ ` `
+    pub(crate) marker: char,
+}
+
+/// 停止したセッションを登録し、ジョブ番号を返す。
+///
+/// 番号は登録中のジョブの最大値 + 1（bash と同じく、空いた番号は詰めない）。
+pub(crate) fn push(session: PtySession) -> usize {
+    let mut jobs = jobs();
+    let id = jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+    jobs.push(Job { id, session });
+    id
+}
+
+/// ジョブ指定（`%1`, `1`, `%%`, `%+`, `%-`, `%vim`）に一致するジョブを取り出す。
+///
+/// `spec` が `None` ならカレントジョブ。見つからなければエラーメッセージを返す。
+pub(crate) fn take(spec: Option<&str>) -> Result<PtySession, String> {
+    reap_finished();
+    let mut jobs = jobs();
+    let index = find_index(&jobs, spec)?;
+    Ok(jobs.remove(index).session)
+}
+
+/// 停止中のジョブ一覧を返す（終了済みのジョブは取り除く）。
+pub(crate) fn list() -> Vec<JobInfo> {
+    reap_finished();
+    let jobs = jobs();
+    let len = jobs.len();
+    jobs.iter()
+        .enumerate()
+        .map(|(i, job)| JobInfo {
+            id: job.id,
+            command: job.session.command.clone(),
+            marker: marker(i, len),
+        })
+        .collect()
+}
+
+/// シェル終了時に、停止中のジョブへ SIGHUP と SIGCONT を送る（停止したまま残さない）。
+pub fn hangup_stopped_jobs() {
+    for job in jobs().drain(..) {
+        let fd = job.session.master_fd();
+        signal_session(fd, job.session.pid, libc::SIGHUP);
+        signal_session(fd, job.session.pid, libc::SIGCONT);
+    }
+}
+
+/// 外部から kill された等で終了したジョブを取り除く。
+fn reap_finished() {
+    jobs().retain(|job| !reap_if_exited(job.session.pid));
+}
+
+/// 一覧表示での位置 `i`（全 `len` 件、末尾が最新）に対応するマーカー。
+fn marker(i: usize, len: usize) -> char {
+    if i + 1 == len {
+        '+'
+    } else if i + 2 == len {
+        '-'
+    } else {
+        ' '
+    }
+}
+
+/// ジョブ指定に一致するジョブのインデックスを返す。
+fn find_index(jobs: &[Job], spec: Option<&str>) -> Result<usize, String> {
+    let ids: Vec<(usize, &str)> = jobs
+        .iter()
+        .map(|job| (job.id, job.session.command.as_str()))
+        .collect();
+    resolve_spec(&ids, spec)
+}
+
+/// `(ジョブ番号, コマンドライン)` の一覧（停止した順）からジョブ指定に一致する位置を返す。
+fn resolve_spec(jobs: &[(usize, &str)], spec: Option<&str>) -> Result<usize, String> {
+    let current = || {
+        jobs.len()
+            .checked_sub(1)
+            .ok_or_else(|| "no current job".to_string())
+    };
+    let Some(spec) = spec else {
+        return current();
+    };
+    let body = spec.strip_prefix('%').unwrap_or(spec);
+    match body {
+        "" | "%" | "+" => current(),
+        "-" => jobs
+            .len()
+            .checked_sub(2)
+            .ok_or_else(|| "no previous job".to_string()),
+        _ => {
+            let found = match body.parse::<usize>() {
+                Ok(id) => jobs.iter().position(|(job_id, _)| *job_id == id),
+                Err(_) => jobs
+                    .iter()
+                    .rposition(|(_, command)| command.starts_with(body)),
+            };
+            found.ok_or_else(|| format!("{spec}: no such job"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &[(usize, &str)] = &[(1, "vim a.txt"), (3, "less log"), (4, "vim b.txt")];
+
+    #[test]
+    fn resolve_spec_defaults_to_current_job() {
+        assert_eq!(resolve_spec(TABLE, None), Ok(2));
+        assert_eq!(resolve_spec(TABLE, Some("%%")), Ok(2));
+        assert_eq!(resolve_spec(TABLE, Some("%+")), Ok(2));
+        assert_eq!(resolve_spec(TABLE, Some("%-")), Ok(1));
+        assert_eq!(resolve_spec(&[], None), Err("no current job".to_string()));
+    }
+
+    #[test]
+    fn resolve_spec_by_number_and_prefix() {
+        assert_eq!(resolve_spec(TABLE, Some("%1")), Ok(0));
+        assert_eq!(resolve_spec(TABLE, Some("3")), Ok(1));
+        assert_eq!(resolve_spec(TABLE, Some("%vim")), Ok(2));
+        assert_eq!(resolve_spec(TABLE, Some("%less")), Ok(1));
+        assert_eq!(
+            resolve_spec(TABLE, Some("%2")),
+            Err("%2: no such job".to_string())
+        );
+        assert_eq!(
+            resolve_spec(TABLE, Some("%top")),
+            Err("%top: no such job".to_string())
+        );
+    }
+
+    #[test]
+    fn marker_flags_current_and_previous() {
+        assert_eq!(marker(2, 3), '+');
+        assert_eq!(marker(1, 3), '-');
+        assert_eq!(marker(0, 3), ' ');
+    }
+}
@@ -3,29 +3,33 @@
 //! リダイレクト対応、および PTY セッションのフォールバック先。
 //! 旧来の PTY + tee キャプチャ方式で stdin は inherit する。
 
+use std::io::{self, IsTerminal};
 use std::os::unix::process::CommandExt;
 use std::process::{Command, Stdio};
-use std::thread;
+use std::time::Instant;
 
 use tracing::debug;
 
-use crate::engine::io::tee_to_terminal;
+use crate::engine::io::{spawn_tee, CAPTURE_JOIN_TIMEOUT};
 use crate::engine::job_control::{job_control_enabled, pre_exec_setpgid, TerminalForegroundGuard};
-use crate::engine::parser::{Redirect, SimpleCommand};
+use crate::engine::parser::SimpleCommand;
 use crate::engine::pty::create_capture_pair;
-use crate::engine::redirect::{find_stdin_redirect, find_stdout_redirect};
-use crate::engine::{CommandResult, LoopAction};
+use crate::engine::redirect::open_redirects;
+use crate::engine::{CommandResult, ExecContext};
 
 /// レガシー方式で単一コマンドを実行する（リダイレクト対応、PTY セッションのフォールバック）。
 /// 旧来の PTY + tee キャプチャ方式。stdin は inherit。
-pub(super) fn run_single_command_legacy(simple: &SimpleCommand) -> CommandResult {
+pub(super) fn run_single_command_legacy(
+    simple: &SimpleCommand,
+    ctx: &ExecContext,
+) -> CommandResult {
     let cmd = &simple.cmd;
     let args: Vec<&str> = simple.args.iter().map(|s| s.as_str()).collect();
 
     debug!(command = %cmd, args = ?args, "Spawning external command (legacy mode)");
 
     // stdout キャプチャ: PTY (色出力保持) / pipe (フォールバック)
-    let (stdout_reader, stdout_writer) = match create_capture_pair() {
+    let (stdout_reader, stdout_writer, stdout_is_pty) = match create_capture_pair() {
         Ok(pair) => pair,
         Err(e) => {
             let msg = format!("jarvish: pipe error: {e}\n");
@@ -35,7 +39,7 @@ pub(super) fn run_single_command_legacy(simple: &SimpleCommand) -> CommandResult
     };
 
     // stderr キャプチャ: PTY (色出力保持) / pipe (フォールバック)
-    let (stderr_reader, stderr_writer) = match create_capture_pair() {
+    let (stderr_reader, stderr_writer, _) = match create_capture_pair() {
         Ok(pair) => pair,
         Err(e) => {
             let msg = format!("jarvish: pipe error: {e}\n");
@@ -44,33 +48,26 @@ pub(super) fn run_single_command_legacy(simple: &SimpleCommand) -> CommandResult
         }
     };
 
-    // リダイレクト: stdin
-    let final_stdin: Stdio = match find_stdin_redirect(&simple.redirects) {
-        Ok(Some(file)) => file.into(),
-        Ok(None) => Stdio::inherit(),
+    // リダイレクト: 記述順に適用する（同じストリームへの指定は最後のものが優先）
+    let mut targets = match open_redirects(&simple.redirects, ctx.mkdir_on_redirect) {
+        Ok(targets) => targets,
         Err(e) => return e,
     };
-
-    // リダイレクト: stdout
-    let has_stdout_redirect = simple
-        .redirects
-        .iter()
-        .any(|r| matches!(r, Redirect::StdoutOverwrite(_) | Redirect::StdoutAppend(_)));
-
-    let final_stdout: Stdio = if has_stdout_redirect {
-        match find_stdout_redirect(&simple.redirects) {
-            Some(file) => {
-                drop(stdout_writer);
-                file.into()
-            }
-            None => {
-                let msg = "jarvish: internal error: stdout redirect not found\n".to_string();
-                eprint!("{msg}");
-                return CommandResult::error(msg, 1);
-            }
+    let stdout_redirected = targets.stdout.is_some();
+    // 端末の stdin を引き継ぐコマンドは入力待ちの可能性があるため対話コマンドとして扱う
+    let interactive = targets.stdin.is_none() && io::stdin().is_terminal();
+    let final_stdin: Stdio = match targets.stdin.take() {
+        Some(file) => file.into(),
+        None => Stdio::inherit(),
+    };
+    let (final_stdout, final_stderr) = match targets.into_output_stdio(stdout_writer, stderr_writer)
+    {
+        Ok(pair) => pair,
+        Err(e) => {
+            let msg = format!("jarvish: redirect error: {e}\n");
+            eprint!("{msg}");
+            return CommandResult::error(msg, 1);
         }
-    } else {
-        stdout_writer
     };
 
     // ジョブ制御: 子を独立プロセスグループに分離し、Ctrl+C が jarvish 本体に
@@ -83,7 +80,13 @@ pub(super) fn run_single_command_legacy(simple: &SimpleCommand) -> CommandResult
             .args(&args)
             .stdin(final_stdin)
             .stdout(final_stdout)
-            .stderr(stderr_writer);
+            .stderr(final_stderr)
+            .envs(
+                super::force_color_env(ctx.force_color, stdout_is_pty, stdout_redirected)
+                    .iter()
+                    .copied(),
+            );
+        super::set_child_cwd(&mut command, super::child_cwd().as_deref());
 
         if enable_job_control {
             // pgid == 0: 子自身の pid を pgid とする新規プロセスグループを作る。
@@ -107,19 +110,25 @@ pub(super) fn run_single_command_legacy(simple: &SimpleCommand) -> CommandResult
         None
     };
 
-    let stdout_handle = thread::spawn(move || tee_to_terminal(stdout_reader, false));
-    let stderr_handle = thread::spawn(move || tee_to_terminal(stderr_reader, true));
+    let truncate = ctx.truncate_output;
+    let stdout_handle = spawn_tee("stdout", stdout_reader, false, truncate);
+    let stderr_handle = spawn_tee("stderr", stderr_reader, true, truncate);
 
-    let exit_code = match child.wait() {
-        Ok(status) => status.code().unwrap_or(1),
+    // ジョブ制御時は子がグループリーダー（pgid = pid）のため、タイムアウト時は孫プロセスごと終了させる
+    let pgid = enable_job_control.then_some(child.id() as libc::pid_t);
+    let limit = super::timeout::current_limit(ctx, interactive);
+    let exit_code = match super::timeout::wait_child(&mut child, pgid, limit) {
+        Ok(code) => code,
         Err(e) => {
             eprintln!("jarvish: wait error: {e}");
             1
         }
     };
 
-    let stdout_bytes = stdout_handle.join().unwrap_or_default();
-    let stderr_bytes = stderr_handle.join().unwrap_or_default();
+    // 子が終了しても孫プロセスが出力を開いたままだと EOF が届かないため、待機に上限を設ける
+    let deadline = Instant::now() + CAPTURE_JOIN_TIMEOUT;
+    let (stdout_bytes, _) = stdout_handle.join(deadline);
+    let (stderr_bytes, _) = stderr_handle.join(deadline);
 
     debug!(
         command = %cmd,
@@ -129,11 +138,5 @@ pub(super) fn run_single_command_legacy(simple: &SimpleCommand) -> CommandResult
         "External command completed (legacy)"
     );
 
-    CommandResult {
-        stdout: String::from_utf8_lossy(&stdout_bytes).to_string(),
-        stderr: String::from_utf8_lossy(&stderr_bytes).to_string(),
-        exit_code,
-        action: LoopAction::Continue,
-        used_alt_screen: false,
-    }
+    super::captured_result(&stdout_bytes, &stderr_bytes, exit_code, false)
 }
@@ -3,20 +3,118 @@
 //! 単一コマンドやパイプラインの実行を管理する。
 //! PTY セッション（vim/less 等の対話コマンド対応）とレガシーモード（tee キャプチャ）を
 //! 使い分け、stdout/stderr をキャプチャしつつターミナルに表示する。
+//! PTY セッションで Ctrl+Z により停止したコマンドはジョブテーブル（[`jobs`]）に登録する。
+//! 制限時間（[`timeout`]）を超えたコマンドは `SIGTERM` → `SIGKILL` で終了させる。
+//! プロセス置換 `<(...)` の内部パイプラインは完了を待たずに起動する（[`detached`]）。
+//! 子プロセスの実行ディレクトリは実行開始時のシェルの CWD（[`child_cwd`]）を明示的に設定し、
+//! パイプラインの全段を同じディレクトリで実行する。
 
 mod capture;
+mod detached;
+pub(crate) mod jobs;
 mod legacy;
+mod pager;
 mod pipeline;
 mod pty_session;
+mod timeout;
 
 use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use tracing::debug;
 
+use super::job_control::{pipeline_pgid, pre_exec_setpgid, SigintForwarder};
 use super::parser::{Pipeline, SimpleCommand};
-use super::CommandResult;
+use super::{CommandResult, ExecContext};
+use crate::cli::completer::kill_tree;
 use crate::cli::jarvis::jarvis_talk;
 
+pub(crate) use detached::{spawn_pipeline_detached, DetachedPipeline};
+pub use jobs::hangup_stopped_jobs;
+pub use pager::run_pager;
+pub(crate) use pty_session::{resume, STOPPED_EXIT_CODE};
+
+/// 子プロセスに色出力を強制させる環境変数（BSD 系 / Node.js 系 CLI の慣習）
+const FORCE_COLOR_ENV: &[(&str, &str)] = &[("CLICOLOR_FORCE", "1"), ("FORCE_COLOR", "1")];
+
+/// 子プロセスに注入する色出力強制の環境変数を返す。
+///
+/// stdout のキャプチャが PTY 作成に失敗して pipe にフォールバックした場合、
+/// 子プロセスは `isatty()=false` を見て色を無効化するため、`force_color`
+/// （`[shell] force_color`）が有効なら環境変数で色出力を強制する。PTY が使える場合と、stdout をファイルへ
+/// リダイレクトしている場合（色コードをファイルに書き込まないため）は何もしない。
+fn force_color_env(
+    force_color: bool,
+    stdout_is_pty: bool,
+    stdout_redirected: bool,
+) -> &'static [(&'static str, &'static str)] {
+    if force_color && !stdout_is_pty && !stdout_redirected {
+        FORCE_COLOR_ENV
+    } else {
+        &[]
+    }
+}
+
+// ── 実行ディレクトリ ──
+
+/// 子プロセスを実行するディレクトリ（シェルの CWD の物理パス）を返す。
+///
+/// `$PWD` はシンボリックリンク経由の論理パスのことがあるため使わず、`getcwd` が返す
+/// 物理パスを使う（`$PWD` 自体は環境変数として子プロセスにそのまま引き継ぐ）。
+/// パイプラインでは実行開始時に 1 回だけ取得して全段に渡し、途中で CWD が変わっても
+/// 全段が同じディレクトリで動くようにする。CWD が削除されている等で取得できない場合は
+/// `None`（子プロセスは親の CWD をそのまま継承する）。
+fn child_cwd() -> Option<PathBuf> {
+    match std::env::current_dir() {
+        Ok(dir) => Some(dir),
+        Err(e) => {
+            debug!(error = %e, "Failed to get current directory, child inherits it as-is");
+            None
+        }
+    }
+}
+
+/// 子プロセスの実行ディレクトリを `cwd` に設定する（`None` なら何もしない）。
+fn set_child_cwd(command: &mut Command, cwd: Option<&Path>) {
+    if let Some(dir) = cwd {
+        command.current_dir(dir);
+    }
+}
+
+// ── 実行結果 ──
+
+/// キャプチャした stdout / stderr のバイト列から実行結果を組み立てる。
+///
+/// 出力は UTF-8 として（不正なバイトは `�` に置き換えて）保持し、どちらかがバイナリ
+/// （[`is_binary_output`]）なら `binary_output` を立てて AI コンテキストから除外させる。
+fn captured_result(
+    stdout: &[u8],
+    stderr: &[u8],
+    exit_code: i32,
+    used_alt_screen: bool,
+) -> CommandResult {
+    CommandResult {
+        stdout: String::from_utf8_lossy(stdout).to_string(),
+        stderr: String::from_utf8_lossy(stderr).to_string(),
+        exit_code,
+        action: super::LoopAction::Continue,
+        used_alt_screen,
+        binary_output: is_binary_output(stdout) || is_binary_output(stderr),
+    }
+}
+
+/// 出力がバイナリ（有効な UTF-8 でない、または NUL を含む）かどうか。
+///
+/// 末尾で途切れたマルチバイト文字（読み取り中断時等）だけならテキストとみなす。
+fn is_binary_output(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.contains('\0'),
+        Err(e) => e.error_len().is_some(),
+    }
+}
+
 // ── パイプライン実行 ──
 
 /// パイプラインを実行する。
@@ -25,34 +123,35 @@ use crate::cli::jarvis::jarvis_talk;
 /// - 複数コマンド: 前段の stdout を次段の stdin にパイプで接続し、
 ///   最終段の stdout/stderr のみ tee でキャプチャ
 /// - リダイレクト: `>`, `>>`, `<` を処理
-pub fn run_pipeline(pipeline: &Pipeline) -> CommandResult {
+pub fn run_pipeline(pipeline: &Pipeline, ctx: &ExecContext) -> CommandResult {
     let n = pipeline.commands.len();
     debug!(pipeline_length = n, "Running pipeline");
 
     if n == 1 {
-        return run_single_command(&pipeline.commands[0]);
+        return run_single_command(&pipeline.commands[0], ctx);
     }
 
     // 複数コマンドのパイプライン
-    pipeline::run_piped_commands(&pipeline.commands)
+    pipeline::run_piped_commands(&pipeline.commands, ctx)
 }
 
 /// 単一コマンドの実行エントリポイント。
 /// リダイレクトがある場合はレガシー（pipe + tee）方式にフォールバック。
 /// リダイレクトがない場合はフル PTY セッションで実行する。
-fn run_single_command(simple: &SimpleCommand) -> CommandResult {
+fn run_single_command(simple: &SimpleCommand, ctx: &ExecContext) -> CommandResult {
     let has_redirect = !simple.redirects.is_empty();
 
     if has_redirect {
-        return legacy::run_single_command_legacy(simple);
+        return legacy::run_single_command_legacy(simple, ctx);
     }
 
     // フル PTY セッションを試行。ターミナル取得に失敗した場合はレガシーにフォールバック。
-    match pty_session::run_single_command_pty_session(simple) {
+    let limit = timeout::current_limit(ctx, true);
+    match pty_session::run_single_command_pty_session(simple, limit) {
         Ok(result) => result,
         Err(e) => {
             debug!("PTY session failed ({e}), falling back to legacy mode");
-            legacy::run_single_command_legacy(simple)
+            legacy::run_single_command_legacy(simple, ctx)
         }
     }
 }
@@ -63,8 +162,8 @@ fn run_single_command(simple: &SimpleCommand) -> CommandResult {
 /// UNIX パイプのセマンティクスに従い:
 /// - stdout: `Stdio::piped()` でキャプチャ（ターミナルに表示しない）
 /// - stderr: `Stdio::inherit()` でターミナルに直接表示
-pub fn run_pipeline_captured(pipeline: &Pipeline) -> CommandResult {
-    capture::run_pipeline_captured(pipeline)
+pub fn run_pipeline_captured(pipeline: &Pipeline, ctx: &ExecContext) -> CommandResult {
+    capture::run_pipeline_captured(pipeline, ctx)
 }
 
 // ── エラーヘルパー ──
@@ -87,6 +186,111 @@ fn kill_and_wait(child: &mut std::process::Child) {
     let _ = child.wait();
 }
 
+// ── パイプラインのプロセスグループ ──
+
+/// パイプラインの子プロセス群。`grouped` なら全段を 1 つのプロセスグループにまとめる。
+///
+/// 先頭段の pid をグループ ID とし、後続段は同じグループに join する（子の `pre_exec` と
+/// 親の両側で `setpgid` する）。グループは端末フォアグラウンドを委譲するジョブ制御時
+/// （[`job_control_enabled`](crate::engine::job_control::job_control_enabled)）にだけ作る。
+/// 委譲しないままバックグラウンドのグループに置くと、`/dev/tty` を読む段（`less` / `fzf` /
+/// `sudo` 等）が SIGTTIN で止まるため、それ以外では全段を jarvish と同じグループで動かす。
+/// 委譲できずに jarvish 側へ届いた Ctrl+C は [`SigintForwarder`] がグループへ転送する。
+///
+/// 途中の段の起動失敗等で [`wait_all`](Self::wait_all) せずに drop された場合は、
+/// グループ全体に `SIGKILL` を送ってから各子を回収する。子が起動した孫プロセスも
+/// 同じグループに属するため、孤児プロセスが残らない。グループを作らない場合は、
+/// 各段の子孫プロセスを辿って個別に終了させる（[`kill_tree`]）。
+struct PipelineChildren {
+    children: Vec<std::process::Child>,
+    /// 段をまとめるプロセスグループを作るか
+    grouped: bool,
+    pgid: Option<libc::pid_t>,
+    /// グループの作成後、jarvish に届いた SIGINT をグループへ転送する
+    sigint_forwarder: Option<SigintForwarder>,
+}
+
+impl PipelineChildren {
+    fn new(grouped: bool) -> Self {
+        Self {
+            children: Vec::new(),
+            grouped,
+            pgid: None,
+            sigint_forwarder: None,
+        }
+    }
+
+    /// 次に起動する段の `pre_exec` を設定する。先頭段は子自身の新規グループを作り、
+    /// 後続段は確定済みのグループに join する。グループを作らない場合は何もしない。
+    fn prepare(&self, command: &mut Command) {
+        if !self.grouped {
+            return;
+        }
+        let pgid = self.pgid.unwrap_or(0);
+        unsafe {
+            command.pre_exec(move || pre_exec_setpgid(pgid));
+        }
+    }
+
+    /// 起動した子をグループに加え、グループ ID を返す。先頭の子ならその pid をグループ ID とし、
+    /// SIGINT の転送を開始する。グループを作らない場合は `None`。
+    fn join(&mut self, child: &std::process::Child) -> Option<libc::pid_t> {
+        if !self.grouped {
+            return None;
+        }
+        let pid = child.id() as libc::pid_t;
+        let pgid = *self.pgid.get_or_insert_with(|| pipeline_pgid(pid));
+        // SAFETY: setpgid は pid と pgid のみを取り、メモリ安全性に影響しない。
+        // 子が既に exec 済み・終了済みで失敗しても、子側の pre_exec で設定済みのため無視する。
+        unsafe {
+            libc::setpgid(pid, pgid);
+        }
+        if self.sigint_forwarder.is_none() {
+            self.sigint_forwarder = Some(SigintForwarder::new(pgid));
+        }
+        Some(pgid)
+    }
+
+    /// 中間段の子を保持する（最終段の wait 後に [`wait_all`](Self::wait_all) で回収する）。
+    fn push(&mut self, child: std::process::Child) {
+        self.children.push(child);
+    }
+
+    /// 保持している全ての子の終了を待つ。
+    fn wait_all(mut self) {
+        for mut child in self.children.drain(..) {
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for PipelineChildren {
+    fn drop(&mut self) {
+        if self.children.is_empty() {
+            return;
+        }
+        if let Some(pgid) = self.pgid {
+            debug!(pgid, "Killing pipeline process group");
+            // SAFETY: killpg は pgid とシグナル番号のみを取る。グループリーダー（先頭段）は
+            // まだ回収していないため、pgid が別のプロセスに再利用されていることはない。
+            unsafe {
+                libc::killpg(pgid, libc::SIGKILL);
+            }
+        } else {
+            // 全段が jarvish と同じグループにいるため、グループ単位では終了させられない。
+            // 各段の子孫を辿って終了させ、孫プロセスを孤児として残さない
+            debug!("Killing descendants of ungrouped pipeline stages");
+            for child in &self.children {
+                kill_tree(child.id());
+            }
+        }
+        // setpgid に失敗してグループ外にいる子も個別に終了させ、ゾンビを残さない
+        for mut child in self.children.drain(..) {
+            kill_and_wait(&mut child);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,33 +309,52 @@ mod tests {
 
     #[test]
     fn echo_stdout_capture() {
-        let result = run_single_command(&simple("echo", &["hello"]));
+        let result = run_single_command(&simple("echo", &["hello"]), &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "hello");
     }
 
     #[test]
     fn exit_code_success() {
-        let result = run_single_command(&simple("true", &[]));
+        let result = run_single_command(&simple("true", &[]), &ExecContext::default());
         assert_eq!(result.exit_code, 0);
     }
 
     #[test]
     fn exit_code_failure() {
-        let result = run_single_command(&simple("false", &[]));
+        let result = run_single_command(&simple("false", &[]), &ExecContext::default());
         assert_eq!(result.exit_code, 1);
     }
 
     #[test]
     #[serial_test::serial]
     fn stderr_capture() {
-        let result = run_single_command(&simple("sh", &["-c", "echo err >&2"]));
+        let result = run_single_command(
+            &simple("sh", &["-c", "echo err >&2"]),
+            &ExecContext::default(),
+        );
         assert_eq!(result.stderr.trim(), "err");
     }
 
+    #[test]
+    fn truncate_output_keeps_full_line_in_capture() {
+        // 切り詰めは表示だけに適用され、キャプチャには全文が残る
+        let ctx = ExecContext {
+            truncate_output: true,
+            ..Default::default()
+        };
+        let long = "x".repeat(500);
+        let result = run_single_command(&simple("echo", &[&long]), &ctx);
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(result.stdout.trim(), long);
+    }
+
     #[test]
     fn nonexistent_command_returns_error() {
-        let result = run_single_command(&simple("__jarvish_nonexistent_command__", &[]));
+        let result = run_single_command(
+            &simple("__jarvish_nonexistent_command__", &[]),
+            &ExecContext::default(),
+        );
         assert_ne!(result.exit_code, 0);
         assert!(!result.stderr.is_empty());
     }
@@ -154,7 +377,7 @@ mod tests {
                 },
             ],
         };
-        let result = run_pipeline(&pipeline);
+        let result = run_pipeline(&pipeline, &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "hello");
     }
@@ -180,7 +403,7 @@ mod tests {
                 },
             ],
         };
-        let result = run_pipeline(&pipeline);
+        let result = run_pipeline(&pipeline, &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "bbb");
     }
@@ -201,10 +424,153 @@ mod tests {
                 },
             ],
         };
-        let result = run_pipeline(&pipeline);
+        let result = run_pipeline(&pipeline, &ExecContext::default());
         assert_eq!(result.exit_code, 1);
     }
 
+    #[test]
+    fn pipeline_spawn_failure_mid_way_returns_promptly() {
+        // 先頭段は孫プロセス（sleep）を起動して動き続けるが、2 段目の起動失敗で
+        // 先頭段ごと終了させ、孫も孤児として残さない
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("grandchild.pid");
+        let fifo = dir.path().join("ready");
+        let fifo_c = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        // SAFETY: mkfifo は NUL 終端のパスとモードのみを取る。
+        assert_eq!(unsafe { libc::mkfifo(fifo_c.as_ptr(), 0o600) }, 0);
+
+        // 2 段目の `<` リダイレクトは先頭段が FIFO を書き込みで開くまで待つため、
+        // 2 段目の起動失敗は孫の pid を書き出した後に起きる
+        let script = format!(
+            "sleep 30 & echo $! > {}; exec 3> {}; wait",
+            pid_file.display(),
+            fifo.display()
+        );
+        let started = std::time::Instant::now();
+        let pipeline = Pipeline {
+            commands: vec![
+                simple("sh", &["-c", &script]),
+                SimpleCommand {
+                    cmd: "jarvish_nonexistent_stage_xyz".into(),
+                    args: vec![],
+                    redirects: vec![Redirect::StdinFrom(fifo.to_str().unwrap().into())],
+                },
+                simple("cat", &[]),
+            ],
+        };
+        let result = run_pipeline(&pipeline, &ExecContext::default());
+        assert_eq!(result.exit_code, 127);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        let grandchild: libc::pid_t = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        let gone = (0..100).any(|_| {
+            // SAFETY: シグナル 0 は存在確認のみで、プロセスには何も送らない。
+            if unsafe { libc::kill(grandchild, 0) } == -1 {
+                return true;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            false
+        });
+        assert!(gone, "grandchild {grandchild} was left running");
+    }
+
+    #[test]
+    fn default_timeout_applies_when_stdin_is_not_the_terminal() {
+        // stdin をリダイレクトした段は入力待ちにならないため、既定のタイムアウトで終了させる
+        let ctx = ExecContext {
+            default_timeout: Some(std::time::Duration::from_millis(200)),
+            ..Default::default()
+        };
+        let started = std::time::Instant::now();
+
+        let legacy = Pipeline {
+            commands: vec![SimpleCommand {
+                cmd: "sleep".into(),
+                args: vec!["5".into()],
+                redirects: vec![Redirect::StdinFrom("/dev/null".into())],
+            }],
+        };
+        let result = run_pipeline(&legacy, &ctx);
+        assert_eq!(result.exit_code, timeout::TIMEOUT_EXIT_CODE);
+
+        let piped = Pipeline {
+            commands: vec![
+                SimpleCommand {
+                    cmd: "cat".into(),
+                    args: vec![],
+                    redirects: vec![Redirect::StdinFrom("/dev/null".into())],
+                },
+                simple("sleep", &["5"]),
+            ],
+        };
+        let result = run_pipeline(&piped, &ctx);
+        assert_eq!(result.exit_code, timeout::TIMEOUT_EXIT_CODE);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    // ── PipelineChildren テスト ──
+
+    #[test]
+    fn ungrouped_pipeline_children_stay_in_shell_group() {
+        let mut children = PipelineChildren::new(false);
+        let mut command = std::process::Command::new("sleep");
+        command.arg("30");
+        children.prepare(&mut command);
+        let child = command.spawn().unwrap();
+        assert_eq!(children.join(&child), None);
+        // SAFETY: getpgid / getpgrp はプロセスグループ ID を読むだけ。
+        let (child_pgid, own_pgid) =
+            unsafe { (libc::getpgid(child.id() as libc::pid_t), libc::getpgrp()) };
+        children.push(child);
+        assert_eq!(child_pgid, own_pgid);
+    }
+
+    #[test]
+    fn dropped_pipeline_children_kill_grandchildren() {
+        use std::io::{BufRead, BufReader, Read};
+
+        let (reader, writer) = os_pipe::pipe().unwrap();
+        let mut children = PipelineChildren::new(true);
+        let mut command = std::process::Command::new("sh");
+        // 孫プロセス（sleep）も stdout のパイプを保持する
+        command
+            .args(["-c", "sleep 30 & echo $!; wait"])
+            .stdout(writer);
+        children.prepare(&mut command);
+        let child = command.spawn().unwrap();
+        // 親側の書き込み端を閉じ、子と孫だけが保持している状態にする
+        drop(command);
+        children.join(&child);
+        children.push(child);
+
+        let mut reader = BufReader::new(reader);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let grandchild: libc::pid_t = line.trim().parse().unwrap();
+
+        // wait_all せずに drop → グループ全体を SIGKILL。孫も終了してパイプが EOF になる
+        let started = std::time::Instant::now();
+        drop(children);
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        // 孤児になった孫は init に回収され、プロセスとして残らない
+        let gone = (0..100).any(|_| {
+            // SAFETY: シグナル 0 は存在確認のみで、プロセスには何も送らない。
+            if unsafe { libc::kill(grandchild, 0) } == -1 {
+                return true;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            false
+        });
+        assert!(gone, "grandchild {grandchild} was not reaped");
+    }
+
     // ── run_pipeline テスト: リダイレクト ──
 
     #[test]
@@ -220,7 +586,7 @@ mod tests {
                 redirects: vec![Redirect::StdoutOverwrite(path_str)],
             }],
         };
-        let result = run_pipeline(&pipeline);
+        let result = run_pipeline(&pipeline, &ExecContext::default());
         assert_eq!(result.exit_code, 0);
 
         let contents = std::fs::read_to_string(&path).unwrap();
@@ -242,7 +608,7 @@ mod tests {
                 redirects: vec![Redirect::StdoutAppend(path_str)],
             }],
         };
-        let result = run_pipeline(&pipeline);
+        let result = run_pipeline(&pipeline, &ExecContext::default());
         assert_eq!(result.exit_code, 0);
 
         let contents = std::fs::read_to_string(&path).unwrap();
@@ -265,11 +631,55 @@ mod tests {
                 redirects: vec![Redirect::StdinFrom(path_str)],
             }],
         };
-        let result = run_pipeline(&pipeline);
+        let result = run_pipeline(&pipeline, &ExecContext::default());
         assert_eq!(result.exit_code, 0);
         assert_eq!(result.stdout.trim(), "from_file");
     }
 
+    #[test]
+    fn redirect_multiple_stdout_last_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+
+        let pipeline = Pipeline {
+            commands: vec![SimpleCommand {
+                cmd: "echo".into(),
+                args: vec!["twice".into()],
+                redirects: vec![
+                    Redirect::StdoutOverwrite(a.to_str().unwrap().into()),
+                    Redirect::StdoutOverwrite(b.to_str().unwrap().into()),
+                ],
+            }],
+        };
+        let result = run_pipeline(&pipeline, &ExecContext::default());
+        assert_eq!(result.exit_code, 0);
+
+        // 記述順に開かれ、最後の `> b.txt` にだけ出力される
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "");
+        assert_eq!(std::fs::read_to_string(&b).unwrap().trim(), "twice");
+    }
+
+    #[test]
+    fn redirect_stdout_open_failure_skips_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("marker");
+
+        let pipeline = Pipeline {
+            commands: vec![SimpleCommand {
+                cmd: "touch".into(),
+                args: vec![marker.to_str().unwrap().into()],
+                redirects: vec![Redirect::StdoutOverwrite(
+                    "/nonexistent_jarvish_dir/out.txt".into(),
+                )],
+            }],
+        };
+        let result = run_pipeline(&pipeline, &ExecContext::default());
+        assert_eq!(result.exit_code, 1);
+        assert!(result.stderr.contains("/nonexistent_jarvish_dir/out.txt"));
+        assert!(!marker.exists());
+    }
+
     #[test]
     fn redirect_stdin_nonexistent_file_returns_error() {
         let pipeline = Pipeline {
@@ -281,7 +691,112 @@ mod tests {
                 )],
             }],
         };
-        let result = run_pipeline(&pipeline);
+        let result = run_pipeline(&pipeline, &ExecContext::default());
         assert_ne!(result.exit_code, 0);
     }
+
+    #[test]
+    fn mkdir_on_redirect_creates_parent_in_pipeline() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("logs/nested/out.txt");
+
+        let pipeline = Pipeline {
+            commands: vec![
+                SimpleCommand {
+                    cmd: "echo".into(),
+                    args: vec!["piped".into()],
+                    redirects: vec![],
+                },
+                SimpleCommand {
+                    cmd: "cat".into(),
+                    args: vec![],
+                    redirects: vec![Redirect::StdoutOverwrite(out.to_str().unwrap().into())],
+                },
+            ],
+        };
+        // 既定（無効）では親ディレクトリを作らずエラーになる
+        let result = run_pipeline(&pipeline, &ExecContext::default());
+        assert_eq!(result.exit_code, 1);
+        assert!(!out.parent().unwrap().exists());
+
+        let ctx = ExecContext {
+            mkdir_on_redirect: true,
+            ..Default::default()
+        };
+        let result = run_pipeline(&pipeline, &ctx);
+        assert_eq!(result.exit_code, 0);
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "piped\n");
+    }
+
+    // ── force_color ──
+
+    #[test]
+    fn force_color_env_only_for_pipe_fallback() {
+        assert_eq!(force_color_env(true, false, false), FORCE_COLOR_ENV);
+        assert!(force_color_env(true, true, false).is_empty());
+        assert!(force_color_env(true, false, true).is_empty());
+        assert!(force_color_env(false, false, false).is_empty());
+    }
+
+    // ── バイナリ出力判定 ──
+
+    #[test]
+    fn is_binary_output_detects_invalid_utf8_and_nul() {
+        assert!(!is_binary_output(b"hello\n"));
+        assert!(!is_binary_output("こんにちは".as_bytes()));
+        assert!(is_binary_output(b"\x89PNG\r\n\x1a\n\xff\xfe"));
+        assert!(is_binary_output(b"ELF\0\0\0"));
+        // 末尾で途切れたマルチバイト文字はテキスト扱い
+        assert!(!is_binary_output(&"あ".as_bytes()[..2]));
+    }
+
+    #[test]
+    fn binary_pipeline_output_is_flagged() {
+        let pipeline = Pipeline {
+            commands: vec![
+                simple("printf", &["\\377\\376\\000\\n"]),
+                simple("cat", &[]),
+            ],
+        };
+        let result = run_pipeline(&pipeline, &ExecContext::default());
+        assert_eq!(result.exit_code, 0);
+        assert!(result.binary_output);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn children_run_in_physical_cwd_for_every_stage() {
+        let _guard = crate::engine::builtins::cwd::test_helpers::CwdGuard::new();
+        let tmpdir = tempfile::tempdir().unwrap();
+        let real = tmpdir.path().join("real");
+        let link = tmpdir.path().join("link");
+        std::fs::create_dir(&real).unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        std::env::set_current_dir(&link).unwrap();
+
+        let expected = real.canonicalize().unwrap();
+        assert_eq!(child_cwd(), Some(expected.clone()));
+
+        let ctx = ExecContext::default();
+        let result = run_single_command(&simple("pwd", &["-P"]), &ctx);
+        assert_eq!(PathBuf::from(result.stdout.trim()), expected);
+
+        let pipeline = Pipeline {
+            commands: vec![
+                simple("sh", &["-c", "pwd -P"]),
+                simple("sh", &["-c", "cat; pwd -P"]),
+            ],
+        };
+        for result in [
+            run_pipeline(&pipeline, &ctx),
+            run_pipeline_captured(&pipeline, &ctx),
+        ] {
+            let dirs: Vec<PathBuf> = result
+                .stdout
+                .lines()
+                .map(|line| PathBuf::from(line.trim()))
+                .collect();
+            assert_eq!(dirs, vec![expected.clone(), expected.clone()]);
+        }
+    }
 }
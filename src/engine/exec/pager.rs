@@ -0,0 +1,96 @@
+//! ページャ実行
+//!
+//! テキストを stdin に流し込んでページャ（`less -R` 等）を起動する。
+//! 通常の外部コマンドと同様に独立プロセスグループへ分離して端末フォアグラウンドを
+//! 委譲し、ページャ内の Ctrl+C が jarvish 本体に届かないようにする。
+
+use std::io::{self, Write};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+
+use tracing::debug;
+
+use crate::engine::job_control::{job_control_enabled, pre_exec_setpgid, TerminalForegroundGuard};
+
+/// `command_line`（例: `less -R`）をページャとして起動し、`content` を stdin に渡す。
+///
+/// コマンドラインは空白で分割する（クォートは解釈しない）。
+/// ページャが途中で終了した場合（`q` で閉じた等）の書き込みエラーは無視する。
+pub fn run_pager(command_line: &str, content: &str) -> io::Result<()> {
+    let mut parts = command_line.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| io::Error::other("empty pager command"))?;
+    let args: Vec<&str> = parts.collect();
+
+    debug!(pager = %program, args = ?args, content_len = content.len(), "Spawning pager");
+
+    let enable_job_control = job_control_enabled();
+    let mut command = Command::new(program);
+    command.args(&args).stdin(Stdio::piped());
+    if enable_job_control {
+        // SAFETY: pre_exec 内では async-signal-safe な setpgid のみを呼ぶ。
+        unsafe {
+            command.pre_exec(|| pre_exec_setpgid(0));
+        }
+    }
+
+    let mut child = command.spawn()?;
+    let _fg_guard = if enable_job_control {
+        TerminalForegroundGuard::new(child.id() as libc::pid_t)
+    } else {
+        None
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        if let Err(e) = stdin.write_all(content.as_bytes()) {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+        }
+        // stdin を閉じて EOF を伝える
+    }
+
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pager_receives_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("paged.txt");
+        // 受け取った内容をファイルに書き出すスクリプトをページャとして使う
+        let wrapper = dir.path().join("pager.sh");
+        std::fs::write(&wrapper, format!("#!/bin/sh\ncat > {}\n", out.display())).unwrap();
+        std::fs::set_permissions(
+            &wrapper,
+            std::os::unix::fs::PermissionsExt::from_mode(0o755),
+        )
+        .unwrap();
+
+        run_pager(wrapper.to_str().unwrap(), "line 1\nline 2\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "line 1\nline 2\n");
+    }
+
+    #[test]
+    fn pager_that_exits_early_is_not_an_error() {
+        let long = "x\n".repeat(200_000);
+        run_pager("true", &long).unwrap();
+    }
+
+    #[test]
+    fn empty_pager_command_is_an_error() {
+        assert!(run_pager("   ", "text").is_err());
+    }
+
+    #[test]
+    fn missing_pager_is_an_error() {
+        assert!(run_pager("__jarvish_nonexistent_pager__", "text").is_err());
+    }
+}
@@ -1,37 +1,39 @@
 //! パイプライン接続実行
 //!
 //! 複数コマンドをパイプで接続し、全ステージの stdout/stderr を tee でキャプチャする。
+//! 全ステージは 1 つのプロセスグループにまとめ、途中で失敗した場合はグループごと終了させる。
 
-use std::os::unix::process::CommandExt;
+use std::io::{self, IsTerminal};
+use std::os::fd::{AsFd, OwnedFd};
 use std::process::{Command, Stdio};
-use std::thread;
+use std::time::Instant;
 
 use tracing::debug;
 
-use crate::engine::io::tee_to_terminal;
-use crate::engine::job_control::{
-    job_control_enabled, pipeline_pgid, pre_exec_setpgid, TerminalForegroundGuard,
-};
-use crate::engine::parser::{Redirect, SimpleCommand};
+use crate::engine::io::{spawn_tee, CAPTURE_JOIN_TIMEOUT};
+use crate::engine::job_control::{job_control_enabled, TerminalForegroundGuard};
+use crate::engine::parser::SimpleCommand;
 use crate::engine::pty::create_capture_pair;
-use crate::engine::redirect::{find_stdin_redirect, find_stdout_redirect};
-use crate::engine::{CommandResult, LoopAction};
+use crate::engine::redirect::open_redirects;
+use crate::engine::{CommandResult, ExecContext};
+
+use super::PipelineChildren;
 
 /// 複数コマンドをパイプで接続して実行する。
 /// 全ステージの stdout/stderr を tee でキャプチャする。
-pub(super) fn run_piped_commands(commands: &[SimpleCommand]) -> CommandResult {
+pub(super) fn run_piped_commands(commands: &[SimpleCommand], ctx: &ExecContext) -> CommandResult {
     let n = commands.len();
-    let mut children = Vec::new();
+    // ジョブ制御: パイプライン全段を 1 つのプロセスグループにまとめ、端末フォアグラウンドを
+    // 委譲する。先頭プロセスの pid をジョブ pgid とし、後続段は同じ pgid に join する。
+    // 途中で return した場合は drop 時にグループごと終了させる。
+    // テストビルド / 非 tty では無効化され、全段を jarvish と同じグループで動かす。
+    let mut children = PipelineChildren::new(job_control_enabled());
     let mut prev_stdout: Option<os_pipe::PipeReader> = None;
-
-    // ジョブ制御: パイプライン全段を 1 つのプロセスグループにまとめ、
-    // そのグループに端末フォアグラウンドを委譲する。先頭プロセスの pid を
-    // ジョブ pgid とし、後続段は同じ pgid に join する。
-    // テストビルド / 非 tty では無効化される。
-    let enable_job_control = job_control_enabled();
-    // 先頭プロセス spawn 後に確定するジョブ pgid。確定するまで端末は委譲しない。
-    let mut job_pgid: Option<libc::pid_t> = None;
     let mut fg_guard: Option<TerminalForegroundGuard> = None;
+    // 端末の stdin を引き継ぐ段があれば入力待ちの可能性があるため、既定のタイムアウトを適用しない
+    let mut interactive = false;
+    // 全段を同じディレクトリで実行する
+    let cwd = super::child_cwd();
 
     // 中間ステージの stderr を共有パイプでキャプチャする。
     // Option でラップし、is_last ブロックで take() → drop して EOF を伝播させる。
@@ -58,13 +60,21 @@ pub(super) fn run_piped_commands(commands: &[SimpleCommand]) -> CommandResult {
             "Pipeline stage"
         );
 
+        // リダイレクトは記述順に適用する（同じストリームへの指定は最後のものが優先）
+        let mut targets = match open_redirects(&simple.redirects, ctx.mkdir_on_redirect) {
+            Ok(targets) => targets,
+            Err(e) => return e,
+        };
+
         let stdin_cfg: Stdio = if let Some(prev) = prev_stdout.take() {
             prev.into()
         } else {
-            match find_stdin_redirect(&simple.redirects) {
-                Ok(Some(file)) => file.into(),
-                Ok(None) => Stdio::inherit(),
-                Err(e) => return e,
+            match targets.stdin.take() {
+                Some(file) => file.into(),
+                None => {
+                    interactive = io::stdin().is_terminal();
+                    Stdio::inherit()
+                }
             }
         };
 
@@ -72,7 +82,7 @@ pub(super) fn run_piped_commands(commands: &[SimpleCommand]) -> CommandResult {
             // 全中間ステージのクローン作成後、オリジナルを drop して EOF を伝播
             mid_stderr_writer.take();
 
-            let (stdout_reader, stdout_writer) = match create_capture_pair() {
+            let (stdout_reader, stdout_writer, stdout_is_pty) = match create_capture_pair() {
                 Ok(pair) => pair,
                 Err(e) => {
                     let msg = format!("jarvish: pipe error: {e}\n");
@@ -81,7 +91,7 @@ pub(super) fn run_piped_commands(commands: &[SimpleCommand]) -> CommandResult {
                 }
             };
 
-            let (stderr_reader, stderr_writer) = match create_capture_pair() {
+            let (stderr_reader, stderr_writer, _) = match create_capture_pair() {
                 Ok(pair) => pair,
                 Err(e) => {
                     let msg = format!("jarvish: pipe error: {e}\n");
@@ -90,27 +100,16 @@ pub(super) fn run_piped_commands(commands: &[SimpleCommand]) -> CommandResult {
                 }
             };
 
-            let has_stdout_redirect = simple
-                .redirects
-                .iter()
-                .any(|r| matches!(r, Redirect::StdoutOverwrite(_) | Redirect::StdoutAppend(_)));
-
-            let final_stdout: Stdio = if has_stdout_redirect {
-                match find_stdout_redirect(&simple.redirects) {
-                    Some(file) => {
-                        drop(stdout_writer);
-                        file.into()
-                    }
-                    None => {
-                        let msg =
-                            "jarvish: internal error: stdout redirect not found\n".to_string();
+            let stdout_redirected = targets.stdout.is_some();
+            let (final_stdout, final_stderr) =
+                match targets.into_output_stdio(stdout_writer, stderr_writer) {
+                    Ok(pair) => pair,
+                    Err(e) => {
+                        let msg = format!("jarvish: redirect error: {e}\n");
                         eprint!("{msg}");
                         return CommandResult::error(msg, 1);
                     }
-                }
-            } else {
-                stdout_writer
-            };
+                };
 
             let mut child = {
                 let mut command = Command::new(cmd);
@@ -118,60 +117,55 @@ pub(super) fn run_piped_commands(commands: &[SimpleCommand]) -> CommandResult {
                     .args(&args)
                     .stdin(stdin_cfg)
                     .stdout(final_stdout)
-                    .stderr(stderr_writer);
-
-                if enable_job_control {
-                    // この関数は n>=2 のパイプラインでのみ呼ばれる
-                    // （n==1 は run_pipeline 側で別経路へ分岐）。
-                    // 先頭段は job_pgid 未設定 → pgid=0 で子自身を先頭とする
-                    // 新規グループを作る。後続段は先頭で確定済みの job_pgid に
-                    // join する。
-                    let pgid = job_pgid.unwrap_or(0);
-                    unsafe {
-                        command.pre_exec(move || pre_exec_setpgid(pgid));
-                    }
-                }
+                    .stderr(final_stderr)
+                    .envs(
+                        super::force_color_env(ctx.force_color, stdout_is_pty, stdout_redirected)
+                            .iter()
+                            .copied(),
+                    );
+                super::set_child_cwd(&mut command, cwd.as_deref());
+
+                // この関数は n>=2 のパイプラインでのみ呼ばれる
+                // （n==1 は run_pipeline 側で別経路へ分岐）。
+                // 後続段は先頭で確定済みのグループに join する。
+                children.prepare(&mut command);
 
                 match command.spawn() {
                     Ok(child) => child,
-                    Err(e) => {
-                        for mut c in children {
-                            super::kill_and_wait(&mut c);
-                        }
-                        return super::spawn_error(cmd, e);
-                    }
+                    Err(e) => return super::spawn_error(cmd, e),
                 }
             };
 
-            // 最終段。job_pgid 未確定（＝先頭段が即終了して pgid を確定
-            // できなかった等）ならこの子の pid をジョブ pgid とし、端末
-            // フォアグラウンドを委譲する。通常は先頭段で確定済みのはず。
-            if enable_job_control && fg_guard.is_none() {
-                let pgid = job_pgid.unwrap_or_else(|| pipeline_pgid(child.id() as libc::pid_t));
+            // 最終段。端末フォアグラウンドが未委譲（先頭段で委譲できなかった等）なら
+            // ここで委譲する。通常は先頭段で委譲済みのはず。
+            let pgid = children.join(&child);
+            if let (Some(pgid), None) = (pgid, &fg_guard) {
                 fg_guard = TerminalForegroundGuard::new(pgid);
             }
             // RAII ガードは child.wait() 完了後にドロップさせる。
             let _fg_guard = fg_guard;
 
-            let stdout_handle = thread::spawn(move || tee_to_terminal(stdout_reader, false));
-            let stderr_handle = thread::spawn(move || tee_to_terminal(stderr_reader, true));
-            let mid_stderr_handle = thread::spawn(move || tee_to_terminal(mid_stderr_reader, true));
+            let truncate = ctx.truncate_output;
+            let stdout_handle = spawn_tee("stdout", stdout_reader, false, truncate);
+            let stderr_handle = spawn_tee("stderr", stderr_reader, true, truncate);
+            let mid_stderr_handle = spawn_tee("pipeline stderr", mid_stderr_reader, true, truncate);
 
-            let exit_code = match child.wait() {
-                Ok(status) => status.code().unwrap_or(1),
+            let limit = super::timeout::current_limit(ctx, interactive);
+            let exit_code = match super::timeout::wait_child(&mut child, pgid, limit) {
+                Ok(code) => code,
                 Err(e) => {
                     eprintln!("jarvish: wait error: {e}");
                     1
                 }
             };
 
-            for mut c in children {
-                let _ = c.wait();
-            }
+            children.wait_all();
 
-            let stdout_bytes = stdout_handle.join().unwrap_or_default();
-            let stderr_bytes = stderr_handle.join().unwrap_or_default();
-            let mid_stderr_bytes = mid_stderr_handle.join().unwrap_or_default();
+            // 孫プロセスが出力を開いたままだと EOF が届かないため、待機に上限を設ける
+            let deadline = Instant::now() + CAPTURE_JOIN_TIMEOUT;
+            let (stdout_bytes, _) = stdout_handle.join(deadline);
+            let (stderr_bytes, _) = stderr_handle.join(deadline);
+            let (mid_stderr_bytes, _) = mid_stderr_handle.join(deadline);
 
             // 中間ステージ + 最終ステージの stderr を結合
             let mut combined_stderr = mid_stderr_bytes;
@@ -185,13 +179,7 @@ pub(super) fn run_piped_commands(commands: &[SimpleCommand]) -> CommandResult {
                 "Pipeline final stage completed"
             );
 
-            return CommandResult {
-                stdout: String::from_utf8_lossy(&stdout_bytes).to_string(),
-                stderr: String::from_utf8_lossy(&combined_stderr).to_string(),
-                exit_code,
-                action: LoopAction::Continue,
-                used_alt_screen: false,
-            };
+            return super::captured_result(&stdout_bytes, &combined_stderr, exit_code, false);
         }
 
         // 中間段
@@ -204,45 +192,52 @@ pub(super) fn run_piped_commands(commands: &[SimpleCommand]) -> CommandResult {
             }
         };
 
-        // 中間ステージの stderr を共有パイプに流してキャプチャする
-        let mid_stderr: Stdio = mid_stderr_writer
+        // 中間ステージの stderr を共有パイプに流してキャプチャする。
+        // `&> file` / `>&2` 等のリダイレクトはパイプ接続の上に重ねて適用する。
+        let mid_stderr: OwnedFd = match mid_stderr_writer
             .as_ref()
             .and_then(|w| w.try_clone().ok())
-            .map(|w| -> Stdio { w.into() })
-            .unwrap_or_else(Stdio::inherit);
+            .map(OwnedFd::from)
+            .map_or_else(|| io::stderr().as_fd().try_clone_to_owned(), Ok)
+        {
+            Ok(fd) => fd,
+            Err(e) => {
+                let msg = format!("jarvish: pipe error: {e}\n");
+                eprint!("{msg}");
+                return CommandResult::error(msg, 1);
+            }
+        };
+        let (stage_stdout, stage_stderr) =
+            match targets.into_output_stdio(OwnedFd::from(pipe_write), mid_stderr) {
+                Ok(pair) => pair,
+                Err(e) => {
+                    let msg = format!("jarvish: redirect error: {e}\n");
+                    eprint!("{msg}");
+                    return CommandResult::error(msg, 1);
+                }
+            };
 
         let child = {
             let mut command = Command::new(cmd);
             command
                 .args(&args)
                 .stdin(stdin_cfg)
-                .stdout(pipe_write)
-                .stderr(mid_stderr);
-
-            if enable_job_control {
-                // 先頭段（job_pgid 未確定）は pgid=0 で新規グループ。
-                // 後続段は確定済み job_pgid に join する。
-                let pgid = job_pgid.unwrap_or(0);
-                unsafe {
-                    command.pre_exec(move || pre_exec_setpgid(pgid));
-                }
-            }
+                .stdout(stage_stdout)
+                .stderr(stage_stderr);
+            super::set_child_cwd(&mut command, cwd.as_deref());
+
+            // 先頭段はグループ未確定 → 新規グループ。
+            // 後続段は確定済みのグループに join する。
+            children.prepare(&mut command);
 
             match command.spawn() {
                 Ok(child) => child,
-                Err(e) => {
-                    for mut c in children {
-                        super::kill_and_wait(&mut c);
-                    }
-                    return super::spawn_error(cmd, e);
-                }
+                Err(e) => return super::spawn_error(cmd, e),
             }
         };
 
-        // 先頭段でジョブ pgid を確定し、端末フォアグラウンドを委譲する。
-        if enable_job_control && job_pgid.is_none() {
-            let pgid = pipeline_pgid(child.id() as libc::pid_t);
-            job_pgid = Some(pgid);
+        // 先頭段でジョブ pgid が確定したら、端末フォアグラウンドを委譲する。
+        if let (Some(pgid), None) = (children.join(&child), &fg_guard) {
             fg_guard = TerminalForegroundGuard::new(pgid);
         }
 
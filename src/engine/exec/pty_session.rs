@@ -2,25 +2,78 @@
 //!
 //! 子プロセスをセッションリーダーとして起動し、PTY を制御端末として割り当てる。
 //! stdin は PTY 経由で転送し、stdout は PTY 経由でキャプチャする。
+//!
+//! 実行中に Ctrl+Z が押されるとセッションを一時停止させ（[`crate::engine::job_control`] 参照）、
+//! ジョブテーブル（[`super::jobs`]）に登録してプロンプトに戻る。`fg` で [`resume`] すると、
+//! 停止前と同じ PTY・キャプチャスレッドのまま前面での実行を再開する。
 
+use std::fs::File;
 use std::io::{self, IsTerminal, Write};
-use std::os::fd::{AsFd, AsRawFd, FromRawFd};
+use std::os::fd::{AsFd, AsRawFd};
 use std::os::unix::process::CommandExt;
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
 use tracing::debug;
 
-use crate::engine::io::{capture_pty_output, forward_stdin, tee_stderr};
+use super::jobs;
+use super::timeout::{self, TIMEOUT_EXIT_CODE};
+use crate::engine::io::{
+    capture_pty_output, forward_stdin, tee_stderr, CaptureResult, CaptureThread, StoppableReader,
+    CAPTURE_JOIN_TIMEOUT,
+};
+use crate::engine::job_control::{
+    signal_session, try_wait_foreground, wait_foreground, ChildStatus,
+};
 use crate::engine::parser::SimpleCommand;
-use crate::engine::pty::create_session_pty;
-use crate::engine::terminal::TerminalStateGuard;
-use crate::engine::{CommandResult, LoopAction};
+use crate::engine::pty::{create_session_pty, get_terminal_winsize, ALT_SCREEN_ENABLE};
+use crate::engine::terminal::{reset_terminal_modes, TerminalStateGuard};
+use crate::engine::CommandResult;
+
+/// 停止したコマンドの終了コード（bash と同じく 128 + SIGTSTP）
+pub(crate) const STOPPED_EXIT_CODE: i32 = 128 + libc::SIGTSTP;
+
+/// 停止後、子プロセスが後片付けで出力したシーケンスが表示されるのを待つ時間
+const STOP_SETTLE: Duration = Duration::from_millis(50);
+
+/// 起動済みの PTY セッション（実行中または停止中）。
+pub(crate) struct PtySession {
+    /// 表示用のコマンドライン
+    pub(crate) command: String,
+    /// 子プロセス（セッションリーダー、pgid = pid）の pid
+    pub(crate) pid: libc::pid_t,
+    /// stdin 転送用の PTY master（出力キャプチャスレッドとは別の複製）
+    master: File,
+    output: CaptureThread<CaptureResult>,
+    stderr: CaptureThread<()>,
+    /// Alternate Screen が現在有効か（出力キャプチャスレッドが更新する）
+    alt_screen: Arc<AtomicBool>,
+}
+
+impl PtySession {
+    /// PTY master の fd（シグナル送信先のフォアグラウンドグループ取得用）
+    pub(crate) fn master_fd(&self) -> std::os::fd::RawFd {
+        self.master.as_raw_fd()
+    }
+}
+
+/// 前面実行の結果
+enum Foreground {
+    Finished(CommandResult),
+    Stopped(PtySession),
+}
 
 /// フル PTY セッション方式で単一コマンドを実行する。
 /// 子プロセスをセッションリーダーとして起動し、PTY を制御端末として割り当てる。
 /// stdin は PTY 経由で転送し、stdout は PTY 経由でキャプチャする。
-pub(super) fn run_single_command_pty_session(simple: &SimpleCommand) -> io::Result<CommandResult> {
+/// `limit` を指定すると、超過時に子プロセスを終了させる（明示的な `timeout` 用）。
+pub(super) fn run_single_command_pty_session(
+    simple: &SimpleCommand,
+    limit: Option<Duration>,
+) -> io::Result<CommandResult> {
     // テストビルドでは PTY セッションモードを使用しない。
     // PTY セッションは親ターミナルを raw mode（OPOST 無効）に変更するため、
     // 複数テストが並列実行されるとターミナル状態のレースコンディションが発生し、
@@ -29,6 +82,20 @@ pub(super) fn run_single_command_pty_session(simple: &SimpleCommand) -> io::Resu
         return Err(io::Error::other("PTY session not available"));
     }
 
+    // ターミナル状態ガードを作成（RAII で確実に復元）
+    let terminal_guard = TerminalStateGuard::new()?;
+    run_pty_session(simple, Some(terminal_guard), limit)
+}
+
+/// PTY セッションを作成してコマンドを前面で実行する。
+///
+/// `terminal_guard` が `None` の場合は親ターミナルを raw mode にしない（テストで端末なしに
+/// セッションの fd の後片付けを確かめるため）。
+fn run_pty_session(
+    simple: &SimpleCommand,
+    terminal_guard: Option<TerminalStateGuard>,
+    limit: Option<Duration>,
+) -> io::Result<CommandResult> {
     let cmd = &simple.cmd;
     let args: Vec<&str> = simple.args.iter().map(|s| s.as_str()).collect();
 
@@ -36,30 +103,28 @@ pub(super) fn run_single_command_pty_session(simple: &SimpleCommand) -> io::Resu
 
     // 1. セッション PTY ペアを作成 (stdin + stdout 共用)
     let (master, slave) = create_session_pty()?;
-    let master_raw_fd = master.as_raw_fd();
 
     // 2. stderr 用パイプを作成
     let (stderr_read, stderr_write) = os_pipe::pipe()?;
 
-    // 3. ターミナル状態ガードを作成（RAII で確実に復元）
-    let mut terminal_guard = TerminalStateGuard::new()?;
-
-    // 4. PTY slave fd を複製して stdin / stdout に割り当てる
-    let slave_raw_fd = slave.as_raw_fd();
-    let stdin_fd = unsafe { libc::dup(slave_raw_fd) };
-    let stdout_fd = unsafe { libc::dup(slave_raw_fd) };
-    if stdin_fd < 0 || stdout_fd < 0 {
-        return Err(io::Error::last_os_error());
-    }
+    // 3. PTY slave fd を複製して stdin / stdout に割り当てる。
+    //    起動後に fd の確保で失敗すると子プロセスが回収されずに残るため、
+    //    キャプチャ用の fd（master の複製・停止パイプ）もここで確保しておく
+    let stdin = slave.try_clone()?;
+    let stdout = slave.try_clone()?;
+    let master_for_stdin = master.try_clone()?;
+    let (master_reader, output_stop) = StoppableReader::new(master)?;
+    let (stderr_reader, stderr_stop) = StoppableReader::new(stderr_read)?;
 
-    // 5. 子プロセスを起動
-    let mut child = {
+    // 4. 子プロセスを起動
+    let child = {
         let mut command = Command::new(cmd);
         command
             .args(&args)
-            .stdin(unsafe { Stdio::from_raw_fd(stdin_fd) })
-            .stdout(unsafe { Stdio::from_raw_fd(stdout_fd) })
+            .stdin(Stdio::from(stdin))
+            .stdout(Stdio::from(stdout))
             .stderr(Stdio::from(stderr_write));
+        super::set_child_cwd(&mut command, super::child_cwd().as_deref());
 
         // 新しいセッションを作成し、PTY を制御端末に設定
         unsafe {
@@ -83,48 +148,227 @@ pub(super) fn run_single_command_pty_session(simple: &SimpleCommand) -> io::Resu
         }
     };
 
-    // 6. 親側の PTY slave fd を閉じる
+    // 5. 親側の PTY slave fd を閉じる
     drop(slave);
 
-    // 7. 親ターミナルを raw mode に設定（ガードが自動復元を保証）
-    if let Err(e) = terminal_guard.activate_raw_mode() {
-        debug!("Failed to set raw mode: {e}");
+    // 6. 出力キャプチャスレッドを起動 (Alternate Screen 検出付き)
+    let alt_screen = Arc::new(AtomicBool::new(false));
+    let alt_screen_for_capture = Arc::clone(&alt_screen);
+    let output = CaptureThread::spawn("pty output", move |buf| {
+        capture_pty_output(master_reader, buf, &alt_screen_for_capture)
+    })
+    .with_stop(output_stop);
+
+    // 7. stderr tee スレッドを起動
+    let stderr = CaptureThread::spawn("stderr", move |buf| tee_stderr(stderr_reader, buf))
+        .with_stop(stderr_stop);
+
+    let session = PtySession {
+        command: std::iter::once(cmd.as_str())
+            .chain(args.iter().copied())
+            .collect::<Vec<_>>()
+            .join(" "),
+        // std の `Child` は停止を待てないため、以降は pid で waitpid する
+        pid: child.id() as libc::pid_t,
+        master: master_for_stdin,
+        output,
+        stderr,
+        alt_screen,
+    };
+    Ok(finish_or_suspend(run_foreground(
+        session,
+        terminal_guard,
+        limit,
+    )))
+}
+
+/// 停止中のセッション（`fg`）を前面で再開し、終了または再停止まで待つ。
+pub(crate) fn resume(session: PtySession) -> CommandResult {
+    let terminal_guard = match TerminalStateGuard::new() {
+        Ok(guard) => guard,
+        Err(e) => {
+            // 端末を扱えなければ再開しない（停止したままジョブテーブルに戻す）
+            let msg = format!("jarvish: fg: {e}\n");
+            eprint!("{msg}");
+            jobs::push(session);
+            return CommandResult::error(msg, 1);
+        }
+    };
+
+    let master_fd = session.master_fd();
+    // 停止中に変わったかもしれないウィンドウサイズを反映する
+    let ws = get_terminal_winsize();
+    // SAFETY: 有効な fd と winsize 構造体を渡すだけで、メモリ安全性に影響しない。
+    unsafe {
+        libc::ioctl(master_fd, libc::TIOCSWINSZ, &ws);
     }
+    // alt screen のまま停止したプログラムは画面を戻してから再開する
+    if session.alt_screen.load(Ordering::Relaxed) {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(ALT_SCREEN_ENABLE);
+        let _ = stdout.flush();
+    }
+    println!("{}", session.command);
 
-    // 8. stdin 転送スレッドを起動 (停止パイプ付き)
-    let (shutdown_read, shutdown_write) = os_pipe::pipe()?;
-    let master_for_stdin = master.try_clone()?;
-    let stdin_handle = thread::spawn(move || {
-        forward_stdin(master_for_stdin, shutdown_read, master_raw_fd);
-    });
+    debug!(command = %session.command, pid = session.pid, "Resuming stopped job");
+    signal_session(master_fd, session.pid, libc::SIGCONT);
+    // TUI プログラムに画面を再描画させる
+    signal_session(master_fd, session.pid, libc::SIGWINCH);
 
-    // 9. 出力キャプチャスレッドを起動 (Alternate Screen 検出付き)
-    let output_handle = thread::spawn(move || capture_pty_output(master));
+    finish_or_suspend(run_foreground(session, Some(terminal_guard), None))
+}
 
-    // 10. stderr tee スレッドを起動
-    let stderr_handle = thread::spawn(move || tee_stderr(stderr_read));
+/// 制限時間付きで子プロセスの終了または停止を待つ。
+///
+/// 超過したらセッションのフォアグラウンドグループに `SIGTERM` → `SIGKILL` を送り、
+/// 終了コード [`TIMEOUT_EXIT_CODE`] の終了として扱う。
+fn wait_foreground_timeout(session: &PtySession, limit: Duration) -> io::Result<ChildStatus> {
+    let (master_fd, pid) = (session.master_fd(), session.pid);
+    let terminate = |sig| {
+        signal_session(master_fd, pid, sig);
+        // 停止中のプロセスもシグナルを処理できるよう再開させる
+        signal_session(master_fd, pid, libc::SIGCONT);
+    };
+    match timeout::wait_or_terminate(limit, || try_wait_foreground(pid), terminate)? {
+        (status, false) => Ok(status),
+        (ChildStatus::Stopped, true) => {
+            // SIGTERM 後の猶予中に停止した場合は強制終了して回収する
+            terminate(libc::SIGKILL);
+            wait_foreground(pid)?;
+            Ok(ChildStatus::Exited(TIMEOUT_EXIT_CODE))
+        }
+        (ChildStatus::Exited(_), true) => Ok(ChildStatus::Exited(TIMEOUT_EXIT_CODE)),
+    }
+}
 
-    // 11. 子プロセスの終了を待機
-    let exit_code = match child.wait() {
-        Ok(status) => status.code().unwrap_or(1),
+/// 停止したセッションをジョブテーブルに登録し、停止を表す結果を返す。
+fn finish_or_suspend(outcome: Foreground) -> CommandResult {
+    match outcome {
+        Foreground::Finished(result) => result,
+        Foreground::Stopped(session) => {
+            let command = session.command.clone();
+            let id = jobs::push(session);
+            let msg = format!("[{id}]+  Stopped  {command}\n");
+            eprint!("\n{msg}");
+            CommandResult::error(msg, STOPPED_EXIT_CODE)
+        }
+    }
+}
+
+/// stdin 転送スレッド（停止パイプ付き）。
+struct StdinForwarder {
+    shutdown: os_pipe::PipeWriter,
+    handle: thread::JoinHandle<()>,
+}
+
+impl StdinForwarder {
+    /// 実 stdin → PTY master の転送スレッドを起動する。
+    fn start(session: &PtySession) -> io::Result<Self> {
+        let (shutdown_read, shutdown) = os_pipe::pipe()?;
+        let master_for_stdin = session.master.try_clone()?;
+        let (master_fd, pid) = (session.master_fd(), session.pid);
+        let handle = thread::spawn(move || {
+            forward_stdin(master_for_stdin, shutdown_read, master_fd, pid);
+        });
+        Ok(Self { shutdown, handle })
+    }
+
+    /// 転送を止め、スレッドの終了（PTY master の複製のクローズ）を待つ。
+    fn stop(self) {
+        drop(self.shutdown);
+        let _ = self.handle.join();
+    }
+}
+
+/// セッションを前面で実行し、子プロセスの終了または停止まで待つ。
+///
+/// 親ターミナルを raw mode にして stdin を PTY に転送する。終了した場合はキャプチャスレッドを
+/// join して結果を返し、停止した場合は stdin 転送だけを止めてセッションを返す
+/// （キャプチャスレッドは PTY を読み続け、再開後の出力も同じバッファに蓄積する）。
+///
+/// 子プロセスは起動済みのため、途中で失敗してもエラーで抜けず（呼び出し元が再実行したり、
+/// 子プロセスが回収されずに残ったりしないよう）必ず終了を待って回収する。
+/// 終了後の後片付けは「子プロセスの回収 → stdin 転送の停止 → PTY master のクローズ →
+/// キャプチャスレッドの join」の順に行い、キャプチャスレッドが EOF を受けられるようにする。
+/// `limit` を指定すると制限時間付きで待機する。
+fn run_foreground(
+    session: PtySession,
+    mut terminal_guard: Option<TerminalStateGuard>,
+    limit: Option<Duration>,
+) -> Foreground {
+    // 1. stdin 転送スレッドを起動。起動できなければ子プロセスを操作できないため終了させる
+    let pid = session.pid;
+    let forwarder = match StdinForwarder::start(&session) {
+        Ok(forwarder) => Some(forwarder),
+        Err(e) => {
+            eprintln!("jarvish: failed to forward stdin: {e}");
+            signal_session(session.master_fd(), pid, libc::SIGKILL);
+            signal_session(session.master_fd(), pid, libc::SIGCONT);
+            None
+        }
+    };
+
+    // 2. 親ターミナルを raw mode に設定（ガードが自動復元を保証）
+    if let Some(Err(e)) = terminal_guard.as_mut().map(|g| g.activate_raw_mode()) {
+        debug!("Failed to set raw mode: {e}");
+    }
+
+    // 3. 子プロセスの終了または停止を待機（明示的な `timeout` 実行中は制限時間付き）
+    let status = match limit {
+        Some(limit) => wait_foreground_timeout(&session, limit),
+        None => wait_foreground(pid),
+    };
+    let exit_code = match status {
+        Ok(ChildStatus::Exited(code)) => code,
+        Ok(ChildStatus::Stopped) => {
+            if let Some(forwarder) = forwarder {
+                forwarder.stop();
+            }
+            // 後片付けの出力（alt screen の終了等）が表示されるのを待ってから端末を戻す
+            thread::sleep(STOP_SETTLE);
+            drop(terminal_guard);
+            reset_terminal_modes(session.alt_screen.load(Ordering::Relaxed));
+            debug!(command = %session.command, pid = pid, "External command stopped (PTY session)");
+            return Foreground::Stopped(session);
+        }
         Err(e) => {
             eprintln!("jarvish: wait error: {e}");
             1
         }
     };
 
-    // 12. stdin 転送スレッドを停止
-    drop(shutdown_write);
+    // 4. stdin 転送スレッドを停止し、終了を待つ（スレッドが持つ master の複製を閉じる）
+    if let Some(forwarder) = forwarder {
+        forwarder.stop();
+    }
 
-    // 13. スレッドを join
-    let _ = stdin_handle.join();
-    let capture = output_handle.join().unwrap_or_default();
-    let stderr_bytes = stderr_handle.join().unwrap_or_default();
+    // 5. PTY master を閉じてキャプチャスレッドを join（子が終了しても孫プロセスが PTY を
+    //    開いたままだと EOF が届かないため、待機には上限を設け、超えたら読み取りを打ち切らせて
+    //    部分キャプチャで続行する。スレッドは終了時に自身の master / stderr の fd を閉じる）
+    let PtySession {
+        command,
+        master,
+        output,
+        stderr,
+        ..
+    } = session;
+    drop(master);
+    let deadline = Instant::now() + CAPTURE_JOIN_TIMEOUT;
+    let (stdout_bytes, capture) = output.join(deadline);
+    let capture = capture.unwrap_or_default();
+    let (stderr_bytes, _) = stderr.join(deadline);
 
-    // 14. ターミナル状態を明示的に復元
+    // 6. ターミナル状態を明示的に復元
     drop(terminal_guard);
 
-    // 15. Alt screen プログラム (less, vim 等) 終了後、ターミナルに残る
+    // 7. termios では戻らない端末モード（カーソル非表示・色・alt screen 等）をリセット。
+    // alt screen を使ったコマンドがシグナル等で異常終了した場合も画面を元に戻す。
+    if capture.alt_screen_active {
+        debug!(command = %command, "Command exited while still in alternate screen, leaving it");
+    }
+    reset_terminal_modes(capture.alt_screen_active);
+
+    // 8. Alt screen プログラム (less, vim 等) 終了後、ターミナルに残る
     // エスケープシーケンスの処理完了を待ち、stdin の残留 DSR 応答を破棄する。
     // stdout.flush() で全シーケンスをターミナルに送出し、短い遅延で
     // ターミナルの処理・応答生成を待ってから tcflush する。
@@ -136,19 +380,67 @@ pub(super) fn run_single_command_pty_session(simple: &SimpleCommand) -> io::Resu
     }
 
     debug!(
-        command = %cmd,
+        command = %command,
         exit_code = exit_code,
-        stdout_size = capture.bytes.len(),
+        stdout_size = stdout_bytes.len(),
         stderr_size = stderr_bytes.len(),
         used_alt_screen = capture.used_alt_screen,
+        alt_screen_active = capture.alt_screen_active,
         "External command completed (PTY session)"
     );
 
-    Ok(CommandResult {
-        stdout: String::from_utf8_lossy(&capture.bytes).to_string(),
-        stderr: String::from_utf8_lossy(&stderr_bytes).to_string(),
+    Foreground::Finished(super::captured_result(
+        &stdout_bytes,
+        &stderr_bytes,
         exit_code,
-        action: LoopAction::Continue,
-        used_alt_screen: capture.used_alt_screen,
-    })
+        capture.used_alt_screen,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `/proc/self/fd` のうち PTY（`/dev/ptmx` / `/dev/pts/*`）を指す fd 数。
+    /// 並行するテストが開くファイルやパイプの影響を受けないよう、セッションが開く PTY だけを数える。
+    #[cfg(target_os = "linux")]
+    fn open_pty_fd_count() -> usize {
+        std::fs::read_dir("/proc/self/fd")
+            .unwrap()
+            .filter_map(|entry| std::fs::read_link(entry.ok()?.path()).ok())
+            .filter(|target| {
+                target == std::path::Path::new("/dev/ptmx") || target.starts_with("/dev/pts")
+            })
+            .count()
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn pty_session_does_not_leak_fds() {
+        const RUNS: usize = 20;
+        let command = SimpleCommand {
+            cmd: "true".to_string(),
+            args: vec![],
+            redirects: vec![],
+        };
+        // 1 回目はスレッド等の初期化で fd が増えることがあるため数えない
+        run_pty_session(&command, None, None).unwrap();
+
+        let before = open_pty_fd_count();
+        for _ in 0..RUNS {
+            let result = run_pty_session(&command, None, None).unwrap();
+            assert_eq!(result.exit_code, 0);
+        }
+        // 並行するテストが一時的に開いた PTY は閉じられるまで待つ（漏れていれば減らない）
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let mut after = open_pty_fd_count();
+        while after > before && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+            after = open_pty_fd_count();
+        }
+        assert!(
+            after <= before,
+            "PTY fd count grew from {before} to {after} after {RUNS} PTY sessions"
+        );
+    }
 }
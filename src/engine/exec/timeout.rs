@@ -0,0 +1,164 @@
+//! コマンド実行のタイムアウト
+//!
+//! `timeout <secs> <command...>` ビルトインで明示した制限時間（`ExecContext.explicit_timeout`）と
+//! `[shell] default_command_timeout`（`ExecContext.default_timeout`）から待機の制限時間を決め、
+//! 超過した子プロセスを `SIGTERM` → `SIGKILL` で終了させる（[`wait_or_terminate`]）。
+//!
+//! 既定のタイムアウトは、ユーザーが操作中の対話コマンド（vim / less 等）を終了させないよう
+//! PTY セッションと、端末の stdin を引き継ぐパイプライン / リダイレクト付きコマンドには適用しない。
+//! これらには明示的な `timeout` のみ適用する。
+
+use std::io;
+use std::process::Child;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+use crate::engine::ExecContext;
+
+/// タイムアウトで終了させたコマンドの終了コード（GNU `timeout` の慣例）
+pub(crate) const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// `SIGTERM` を送ってから `SIGKILL` に切り替えるまでの猶予
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// 子プロセスの終了を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// これから待機するコマンドに適用する制限時間を返す。
+///
+/// 明示的な `timeout` があればそれを、なければ対話コマンド（`interactive`）以外にだけ
+/// 既定のタイムアウトを適用する。
+pub(super) fn current_limit(ctx: &ExecContext, interactive: bool) -> Option<Duration> {
+    ctx.explicit_timeout
+        .or(ctx.default_timeout.filter(|_| !interactive))
+}
+
+/// 制限時間まで `poll` で子プロセスの終了を待ち、超過したら `signal` で `SIGTERM` を、
+/// 猶予（[`KILL_GRACE`]）内に終了しなければ `SIGKILL` を送る。
+///
+/// `poll` は子プロセスが終了（または停止）していれば `Some` を返す。戻り値は最後に得た状態と、
+/// タイムアウトでシグナルを送ったかどうか。
+pub(super) fn wait_or_terminate<T>(
+    limit: Duration,
+    mut poll: impl FnMut() -> io::Result<Option<T>>,
+    signal: impl Fn(libc::c_int),
+) -> io::Result<(T, bool)> {
+    if let Some(status) = poll_until(Some(Instant::now() + limit), &mut poll)? {
+        return Ok((status, false));
+    }
+
+    debug!(limit = ?limit, "Command timed out, sending SIGTERM");
+    signal(libc::SIGTERM);
+    if let Some(status) = poll_until(Some(Instant::now() + KILL_GRACE), &mut poll)? {
+        return Ok((status, true));
+    }
+
+    debug!("Command did not exit after SIGTERM, sending SIGKILL");
+    signal(libc::SIGKILL);
+    let status = poll_until(None, &mut poll)?.expect("polling without deadline always completes");
+    Ok((status, true))
+}
+
+/// `deadline` まで（`None` なら無期限に）`poll` が `Some` を返すのを待つ。
+fn poll_until<T>(
+    deadline: Option<Instant>,
+    poll: &mut impl FnMut() -> io::Result<Option<T>>,
+) -> io::Result<Option<T>> {
+    loop {
+        if let Some(status) = poll()? {
+            return Ok(Some(status));
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// 子プロセスの終了を待ち、終了コードを返す（`std::process::Child` 用）。
+///
+/// 制限時間（`limit`）があれば超過時に終了させ、[`TIMEOUT_EXIT_CODE`] を返す。
+/// `pgid` を指定するとシグナルをプロセスグループ全体（パイプラインの全段・孫プロセス）に送る。
+pub(super) fn wait_child(
+    child: &mut Child,
+    pgid: Option<libc::pid_t>,
+    limit: Option<Duration>,
+) -> io::Result<i32> {
+    let Some(limit) = limit else {
+        return Ok(child.wait()?.code().unwrap_or(1));
+    };
+
+    let pid = child.id() as libc::pid_t;
+    let signal = |sig| {
+        // SAFETY: kill / killpg は pid とシグナル番号のみを取る。子はまだ回収していないため、
+        // pid（= グループリーダーの pgid）が別のプロセスに再利用されていることはない。
+        unsafe {
+            match pgid {
+                Some(pgid) => libc::killpg(pgid, sig),
+                None => libc::kill(pid, sig),
+            };
+        }
+    };
+    match wait_or_terminate(limit, || child.try_wait(), signal)? {
+        (status, false) => Ok(status.code().unwrap_or(1)),
+        (_, true) => Ok(TIMEOUT_EXIT_CODE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::process::Command;
+
+    #[test]
+    fn default_timeout_applies_only_to_non_interactive_commands() {
+        let ctx = ExecContext {
+            default_timeout: Some(Duration::from_secs(5)),
+            ..Default::default()
+        };
+        assert_eq!(current_limit(&ctx, false), Some(Duration::from_secs(5)));
+        assert_eq!(current_limit(&ctx, true), None);
+        assert_eq!(current_limit(&ExecContext::default(), false), None);
+    }
+
+    #[test]
+    fn explicit_timeout_overrides_default() {
+        let limit = Duration::from_millis(300);
+        let ctx = ExecContext {
+            default_timeout: Some(Duration::from_secs(5)),
+            explicit_timeout: Some(limit),
+            ..Default::default()
+        };
+        assert_eq!(current_limit(&ctx, false), Some(limit));
+        assert_eq!(current_limit(&ctx, true), Some(limit));
+    }
+
+    #[test]
+    fn escalates_to_sigkill_when_sigterm_is_ignored() {
+        let sent = RefCell::new(Vec::new());
+        let ((), timed_out) = wait_or_terminate(
+            Duration::ZERO,
+            || Ok((sent.borrow().last() == Some(&libc::SIGKILL)).then_some(())),
+            |sig| sent.borrow_mut().push(sig),
+        )
+        .unwrap();
+        assert!(timed_out);
+        assert_eq!(*sent.borrow(), vec![libc::SIGTERM, libc::SIGKILL]);
+    }
+
+    #[test]
+    fn wait_child_kills_command_exceeding_limit() {
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let started = Instant::now();
+        let exit_code = wait_child(&mut child, None, Some(Duration::from_millis(100))).unwrap();
+        assert_eq!(exit_code, TIMEOUT_EXIT_CODE);
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let mut child = Command::new("true").spawn().unwrap();
+        let exit_code = wait_child(&mut child, None, Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(exit_code, 0);
+    }
+}
@@ -1,14 +1,51 @@
 //! 基本的なシェル展開（チルダ・環境変数）
 //!
 //! - チルダ展開: `~` → `$HOME`
-//! - 環境変数展開: `$VAR`, `${VAR}`
+//! - 変数展開: `$VAR`, `${VAR}`（シェル変数 → 環境変数の順に参照）
+//! - パラメータ展開: `${VAR:-word}`, `${VAR:=word}`, `${VAR:+word}`, `${VAR:?word}`,
+//!   コロンなしの `-` `=` `+` `?`（未設定のときだけ作用）、長さ `${#VAR}`
+//!
+//! パラメータ展開の `word` 部分は使われるときだけ展開する（`${VAR:-$OTHER}` のネスト可）。
+//! `word` 内ではクォート（`'...'` / `"..."`）と `\` エスケープを解釈する。
 
 use std::env;
 
-/// トークンに対してチルダ・環境変数展開を適用する
+use crate::engine::shell_vars::{self, ShellVariables};
+
+/// パラメータ展開の失敗（`${VAR:?message}` で `VAR` が未設定または空）。
+///
+/// 値は `VAR: message` 形式のエラーメッセージ。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamError(pub String);
+
+impl std::fmt::Display for ParamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// トークンに対してチルダ・環境変数展開を適用する（シェル変数は参照しない）
+///
+/// `${VAR:?message}` が失敗した場合は空文字列を返す。
+/// エラーを扱いたい場合は [`try_expand_token`] を使う。
 pub fn expand_token(token: &str) -> String {
+    try_expand_token(token).unwrap_or_default()
+}
+
+/// [`expand_token`] のエラーを返す版（`${VAR:?message}` の失敗を [`ParamError`] で返す）。
+pub fn try_expand_token(token: &str) -> Result<String, ParamError> {
+    try_expand_token_with_vars(token, &ShellVariables::default())
+}
+
+/// トークンに対してチルダ・変数展開を適用する（`vars` のシェル変数 → 環境変数の順に参照）
+///
+/// `${VAR:=word}` の代入は `vars` に対して行う。
+pub(super) fn try_expand_token_with_vars(
+    token: &str,
+    vars: &ShellVariables,
+) -> Result<String, ParamError> {
     let expanded = expand_tilde(token);
-    expand_env_vars(&expanded)
+    expand_env_vars(&expanded, vars)
 }
 
 /// チルダ展開: `~` を `$HOME` に置き換える
@@ -27,45 +64,178 @@ fn expand_tilde(path: &str) -> String {
     }
 }
 
-/// 環境変数展開: `$VAR` や `${VAR}` を展開する
-fn expand_env_vars(input: &str) -> String {
-    let mut result = String::with_capacity(input.len());
-    let mut chars = input.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        if c == '$' {
-            // `${VAR}` 形式
-            if chars.peek() == Some(&'{') {
-                chars.next(); // '{' をスキップ
-                let var_name: String = chars.by_ref().take_while(|&ch| ch != '}').collect();
-                if let Ok(value) = env::var(&var_name) {
-                    result.push_str(&value);
+/// `chars[start]` の `$` から始まる `$VAR` / `${...}` 参照を展開する。
+///
+/// 戻り値は `(展開後の値, 参照直後のインデックス)`。未定義の変数は空文字列に展開する。
+/// `$` の後が識別子文字でも `{` でもない場合や、`${` が閉じられていない場合は
+/// 変数参照ではないとみなして `None` を返す（呼び出し側で `$` をリテラル扱いする）。
+/// ダブルクォート内の変数展開（[`super::quote::split_quoted`]）でも使う。
+pub(super) fn expand_var_at(
+    chars: &[char],
+    start: usize,
+    vars: &ShellVariables,
+) -> Option<(Result<String, ParamError>, usize)> {
+    let mut i = start + 1;
+    if chars.get(i) == Some(&'{') {
+        let close = find_param_close(chars, i + 1)?;
+        let inner: String = chars[i + 1..close].iter().collect();
+        return Some((expand_param(&inner, vars), close + 1));
+    }
+    let name_start = i;
+    while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+        i += 1;
+    }
+    if i == name_start {
+        return None;
+    }
+    let name: String = chars[name_start..i].iter().collect();
+    Some((Ok(vars.lookup(&name).unwrap_or_default()), i))
+}
+
+/// `${` の直後（`start`）から対応する `}` のインデックスを探す。
+///
+/// ネストした `{...}`、クォート内、`\` でエスケープされた文字の `}` は対象外。
+/// 閉じられていなければ `None`。
+pub(super) fn find_param_close(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '\'' => i += chars[i + 1..].iter().position(|&c| c == '\'')? + 1,
+            '"' => {
+                i += 1;
+                while chars.get(i)? != &'"' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
                 }
+            }
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// `${...}` の中身（`inner`）を展開する。
+fn expand_param(inner: &str, vars: &ShellVariables) -> Result<String, ParamError> {
+    // `${#VAR}`: 値の文字数
+    if let Some(name) = inner.strip_prefix('#') {
+        if shell_vars::is_valid_name(name) {
+            let len = vars.lookup(name).unwrap_or_default().chars().count();
+            return Ok(len.to_string());
+        }
+    }
+
+    let name_len = inner
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(inner.len());
+    let (name, rest) = inner.split_at(name_len);
+    let (colon, rest) = match rest.strip_prefix(':') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let mut op_chars = rest.chars();
+    let op = op_chars.next();
+    let word = op_chars.as_str();
+    if name.is_empty() || !matches!(op, Some('-' | '=' | '+' | '?')) {
+        // 演算子なし（`${VAR}`）または未対応の構文は名前全体の参照として扱う
+        return Ok(vars.lookup(inner).unwrap_or_default());
+    }
+
+    let value = vars.lookup(name);
+    // コロン付きは空文字列も未設定とみなす
+    let is_set = value.as_deref().is_some_and(|v| !colon || !v.is_empty());
+    match op {
+        Some('-') if !is_set => expand_word(word, vars),
+        Some('=') if !is_set => {
+            // 既に環境変数として存在する（空の）変数は環境変数を、それ以外はシェル変数を更新する
+            let word = expand_word(word, vars)?;
+            vars.assign(name, &word);
+            Ok(word)
+        }
+        Some('+') if is_set => expand_word(word, vars),
+        Some('+') => Ok(String::new()),
+        Some('?') if !is_set => {
+            let message = expand_word(word, vars)?;
+            let message = if message.is_empty() {
+                "parameter null or not set".to_string()
             } else {
-                // `$VAR` 形式: 英数字とアンダースコアのみ
-                let mut var_name = String::new();
-                while let Some(&ch) = chars.peek() {
-                    if ch.is_ascii_alphanumeric() || ch == '_' {
-                        var_name.push(ch);
-                        chars.next();
-                    } else {
-                        break;
-                    }
+                message
+            };
+            Err(ParamError(format!("{name}: {message}")))
+        }
+        _ => Ok(value.unwrap_or_default()),
+    }
+}
+
+/// パラメータ展開の `word` 部分を展開する（クォート除去・エスケープ・変数展開・チルダ展開）。
+fn expand_word(word: &str, vars: &ShellVariables) -> Result<String, ParamError> {
+    let chars: Vec<char> = expand_tilde(word).chars().collect();
+    let mut result = String::with_capacity(chars.len());
+    let mut in_double = false;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\'' if !in_double => {
+                let close = chars[i + 1..]
+                    .iter()
+                    .position(|&c| c == '\'')
+                    .map_or(chars.len(), |p| i + 1 + p);
+                result.extend(&chars[i + 1..close]);
+                i = close + 1;
+            }
+            '"' => {
+                in_double = !in_double;
+                i += 1;
+            }
+            '\\' if i + 1 < chars.len() => {
+                result.push(chars[i + 1]);
+                i += 2;
+            }
+            '$' => match expand_var_at(&chars, i, vars) {
+                Some((value, end)) => {
+                    result.push_str(&value?);
+                    i = end;
                 }
-                if !var_name.is_empty() {
-                    if let Ok(value) = env::var(&var_name) {
-                        result.push_str(&value);
-                    }
-                } else {
+                None => {
                     result.push('$');
+                    i += 1;
                 }
+            },
+            ch => {
+                result.push(ch);
+                i += 1;
             }
-        } else {
-            result.push(c);
         }
     }
+    Ok(result)
+}
+
+/// 変数展開: `$VAR` や `${VAR}`、`${VAR:-word}` 等のパラメータ展開を行う
+fn expand_env_vars(input: &str, vars: &ShellVariables) -> Result<String, ParamError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut result = String::with_capacity(input.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == '$' {
+            if let Some((value, end)) = expand_var_at(&chars, i, vars) {
+                result.push_str(&value?);
+                i = end;
+                continue;
+            }
+        }
+        result.push(chars[i]);
+        i += 1;
+    }
 
-    result
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -98,7 +268,10 @@ mod tests {
     #[serial]
     fn expand_env_var_simple() {
         env::set_var("JARVISH_TEST_VAR", "testvalue");
-        assert_eq!(expand_env_vars("$JARVISH_TEST_VAR"), "testvalue");
+        assert_eq!(
+            expand_env_vars("$JARVISH_TEST_VAR", &ShellVariables::default()).unwrap(),
+            "testvalue"
+        );
         env::remove_var("JARVISH_TEST_VAR");
     }
 
@@ -106,7 +279,10 @@ mod tests {
     #[serial]
     fn expand_env_var_braces() {
         env::set_var("JARVISH_TEST_VAR2", "bracevalue");
-        assert_eq!(expand_env_vars("${JARVISH_TEST_VAR2}"), "bracevalue");
+        assert_eq!(
+            expand_env_vars("${JARVISH_TEST_VAR2}", &ShellVariables::default()).unwrap(),
+            "bracevalue"
+        );
         env::remove_var("JARVISH_TEST_VAR2");
     }
 
@@ -116,7 +292,7 @@ mod tests {
         // `${VAR}/path` 形式で閉じブレースの後に文字が続くケースを検証
         env::set_var("JARVISH_TEST_VAR3", "/home/user");
         assert_eq!(
-            expand_env_vars("${JARVISH_TEST_VAR3}/file"),
+            expand_env_vars("${JARVISH_TEST_VAR3}/file", &ShellVariables::default()).unwrap(),
             "/home/user/file"
         );
         env::remove_var("JARVISH_TEST_VAR3");
@@ -126,7 +302,10 @@ mod tests {
     #[serial]
     fn expand_env_var_in_path() {
         let home = env::var("HOME").unwrap();
-        assert_eq!(expand_env_vars("$HOME/foo"), format!("{}/foo", home));
+        assert_eq!(
+            expand_env_vars("$HOME/foo", &ShellVariables::default()).unwrap(),
+            format!("{}/foo", home)
+        );
     }
 
     #[test]
@@ -139,4 +318,122 @@ mod tests {
         assert_eq!(expand_token("$HOME/bar"), format!("{}/bar", home));
         env::remove_var("JARVISH_SUBDIR");
     }
+
+    #[test]
+    #[serial]
+    fn shell_variable_takes_precedence_over_environment() {
+        let vars = ShellVariables::default();
+        env::set_var("JARVISH_TEST_VAR4", "env");
+        vars.set("JARVISH_TEST_VAR4", "shell");
+        assert_eq!(
+            try_expand_token_with_vars("$JARVISH_TEST_VAR4", &vars).unwrap(),
+            "shell"
+        );
+        // シェル変数を参照しない展開では環境変数の値になる
+        assert_eq!(expand_token("$JARVISH_TEST_VAR4"), "env");
+        env::remove_var("JARVISH_TEST_VAR4");
+    }
+
+    #[test]
+    #[serial]
+    fn param_default_value() {
+        env::remove_var("JARVISH_PE_UNSET");
+        env::set_var("JARVISH_PE_EMPTY", "");
+        env::set_var("JARVISH_PE_SET", "value");
+        assert_eq!(expand_token("${JARVISH_PE_UNSET:-fallback}"), "fallback");
+        assert_eq!(expand_token("${JARVISH_PE_EMPTY:-fallback}"), "fallback");
+        assert_eq!(expand_token("${JARVISH_PE_SET:-fallback}"), "value");
+        // コロンなしは未設定のときだけ
+        assert_eq!(expand_token("${JARVISH_PE_EMPTY-fallback}"), "");
+        assert_eq!(expand_token("${JARVISH_PE_UNSET-fallback}"), "fallback");
+        env::remove_var("JARVISH_PE_EMPTY");
+        env::remove_var("JARVISH_PE_SET");
+    }
+
+    #[test]
+    #[serial]
+    fn param_assign_default() {
+        env::remove_var("JARVISH_PE_ASSIGN");
+        let vars = ShellVariables::default();
+        let expand = |token: &str| try_expand_token_with_vars(token, &vars).unwrap();
+        assert_eq!(expand("${JARVISH_PE_ASSIGN:=first}"), "first");
+        assert_eq!(expand("${JARVISH_PE_ASSIGN:=second}"), "first");
+        assert_eq!(expand("$JARVISH_PE_ASSIGN"), "first");
+        // 未 export の変数はシェル変数として設定され、環境変数には置かない
+        assert_eq!(env::var_os("JARVISH_PE_ASSIGN"), None);
+        assert_eq!(vars.lookup("JARVISH_PE_ASSIGN").as_deref(), Some("first"));
+    }
+
+    #[test]
+    #[serial]
+    fn param_alternate_value() {
+        env::remove_var("JARVISH_PE_UNSET");
+        env::set_var("JARVISH_PE_SET", "value");
+        assert_eq!(expand_token("${JARVISH_PE_SET:+alt}"), "alt");
+        assert_eq!(expand_token("${JARVISH_PE_UNSET:+alt}"), "");
+        env::remove_var("JARVISH_PE_SET");
+    }
+
+    #[test]
+    #[serial]
+    fn param_error_if_unset() {
+        env::remove_var("JARVISH_PE_UNSET");
+        env::set_var("JARVISH_PE_SET", "value");
+        assert_eq!(
+            try_expand_token("${JARVISH_PE_UNSET:?must be set}"),
+            Err(ParamError("JARVISH_PE_UNSET: must be set".to_string()))
+        );
+        assert_eq!(
+            try_expand_token("${JARVISH_PE_UNSET:?}"),
+            Err(ParamError(
+                "JARVISH_PE_UNSET: parameter null or not set".to_string()
+            ))
+        );
+        assert_eq!(
+            try_expand_token("${JARVISH_PE_SET:?must be set}"),
+            Ok("value".to_string())
+        );
+        // エラーを返さない版は空文字列
+        assert_eq!(expand_token("${JARVISH_PE_UNSET:?must be set}"), "");
+        env::remove_var("JARVISH_PE_SET");
+    }
+
+    #[test]
+    #[serial]
+    fn param_length() {
+        env::set_var("JARVISH_PE_LEN", "héllo");
+        env::remove_var("JARVISH_PE_UNSET");
+        assert_eq!(expand_token("${#JARVISH_PE_LEN}"), "5");
+        assert_eq!(expand_token("${#JARVISH_PE_UNSET}"), "0");
+        env::remove_var("JARVISH_PE_LEN");
+    }
+
+    #[test]
+    #[serial]
+    fn param_word_is_expanded_recursively() {
+        env::remove_var("JARVISH_PE_UNSET");
+        env::set_var("JARVISH_PE_OTHER", "other");
+        assert_eq!(
+            expand_token("${JARVISH_PE_UNSET:-$JARVISH_PE_OTHER}"),
+            "other"
+        );
+        assert_eq!(
+            expand_token("${JARVISH_PE_UNSET:-${JARVISH_PE_UNSET:-deep}}/x"),
+            "deep/x"
+        );
+        assert_eq!(expand_token("${JARVISH_PE_UNSET:-\"a }b\"}"), "a }b");
+        assert_eq!(
+            expand_token("${JARVISH_PE_UNSET:-'$JARVISH_PE_OTHER'}"),
+            "$JARVISH_PE_OTHER"
+        );
+        env::remove_var("JARVISH_PE_OTHER");
+    }
+
+    #[test]
+    fn unterminated_param_is_literal() {
+        assert_eq!(
+            expand_token("${JARVISH_PE_UNSET:-x"),
+            "${JARVISH_PE_UNSET:-x"
+        );
+    }
 }
@@ -6,6 +6,10 @@
 //! 展開順序（pipeline）では **最初** に適用される段であり、
 //! ここで得たテキストはその後 basic(tilde/env) → brace → glob に流れる。
 //!
+//! `<(...)` のプロセス置換も同じ段で、トークナイザが記録したクォート外の位置
+//! （[`super::Token::process_subst`]）に限って [`super::process_subst`] に委ね、
+//! `/dev/fd/N` のパスに置き換える（クォート内の `<(` はリテラルのまま残す）。
+//!
 //! ## word-split の挙動
 //! - クォート外（[`SubstQuoting::Unquoted`]）の置換結果は空白
 //!   （` \t\n`）で単語分割する（連続空白は畳み、空要素は除去）。
@@ -34,6 +38,8 @@ use std::cell::Cell;
 
 use crate::engine::expand;
 use crate::engine::parser;
+use crate::engine::shell_vars::ShellVariables;
+use crate::engine::ExecContext;
 
 /// 置換結果に適用するクォート文脈
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -53,6 +59,8 @@ pub enum CmdSubstError {
     NestingTooDeep,
     /// 内部コマンドの実行に失敗した（起動失敗・非ゼロ終了など）
     Exec(String),
+    /// プロセス置換 `<(...)` の準備・起動に失敗した
+    ProcessSubst(String),
 }
 
 impl std::fmt::Display for CmdSubstError {
@@ -63,6 +71,7 @@ impl std::fmt::Display for CmdSubstError {
             }
             CmdSubstError::NestingTooDeep => write!(f, "command substitution nested too deep"),
             CmdSubstError::Exec(msg) => write!(f, "command substitution failed: {msg}"),
+            CmdSubstError::ProcessSubst(msg) => write!(f, "process substitution failed: {msg}"),
         }
     }
 }
@@ -78,11 +87,12 @@ thread_local! {
 
 /// `SUBST_DEPTH` を RAII でインクリメント/デクリメントするガード。
 /// パニック時も `Drop` で確実に元に戻す。
-struct DepthGuard;
+/// プロセス置換（[`super::process_subst`]）も同じ深さを共有する。
+pub(super) struct DepthGuard;
 
 impl DepthGuard {
     /// 深さをインクリメントしてガードを返す。上限超過時は `None`。
-    fn enter() -> Option<Self> {
+    pub(super) fn enter() -> Option<Self> {
         SUBST_DEPTH.with(|d| {
             let next = d.get() + 1;
             if next > MAX_SUBST_DEPTH {
@@ -103,17 +113,23 @@ impl Drop for DepthGuard {
 
 /// トークンに含まれる `$(...)` / backtick のコマンド置換を展開する。
 ///
+/// `process_subst` は token 内でプロセス置換として扱う `<(` の位置（文字単位）。
 /// 置換が含まれない場合は高速パスで `vec![token]` を即返す。
 /// 置換を実行して 1 本の文字列を組み立てた後、`ctx` に応じて
 /// 単語分割（[`SubstQuoting::Unquoted`]）または非分割
 /// （[`SubstQuoting::DoubleQuoted`]）で結果を返す。
-pub fn expand_command_subst(token: &str, ctx: SubstQuoting) -> Result<Vec<String>, CmdSubstError> {
+pub fn expand_command_subst(
+    token: &str,
+    ctx: SubstQuoting,
+    process_subst: &[usize],
+    vars: &ShellVariables,
+) -> Result<Vec<String>, CmdSubstError> {
     // 高速パス: 置換構文を含まなければそのまま返す。
-    if !token.contains("$(") && !token.contains('`') {
+    if !token.contains("$(") && process_subst.is_empty() && !token.contains('`') {
         return Ok(vec![token.to_string()]);
     }
 
-    let assembled = substitute_spans(token)?;
+    let assembled = substitute_spans(token, process_subst, vars)?;
 
     match ctx {
         SubstQuoting::Unquoted => Ok(word_split(&assembled)),
@@ -123,7 +139,11 @@ pub fn expand_command_subst(token: &str, ctx: SubstQuoting) -> Result<Vec<String
 
 /// token を走査し、各置換 span をその実行結果テキストに差し替えた
 /// 1 本の文字列を組み立てる。
-fn substitute_spans(token: &str) -> Result<String, CmdSubstError> {
+fn substitute_spans(
+    token: &str,
+    process_subst: &[usize],
+    vars: &ShellVariables,
+) -> Result<String, CmdSubstError> {
     let chars: Vec<char> = token.chars().collect();
     let mut out = String::with_capacity(token.len());
     let mut i = 0;
@@ -134,16 +154,24 @@ fn substitute_spans(token: &str) -> Result<String, CmdSubstError> {
         // `$(...)` 形式
         if c == '$' && i + 1 < chars.len() && chars[i + 1] == '(' {
             let (inner, next) = take_paren_span(&chars, i + 2)?;
-            let output = capture_subshell(&inner)?;
+            let output = capture_subshell(&inner, vars)?;
             out.push_str(output.trim_end_matches('\n'));
             i = next;
             continue;
         }
 
+        // `<(...)` 形式（プロセス置換）: 内部コマンドを起動し、読み取り用のパスに置き換える
+        if c == '<' && process_subst.contains(&i) {
+            let (inner, next) = take_paren_span(&chars, i + 2)?;
+            out.push_str(&super::process_subst::open(&inner, vars)?);
+            i = next;
+            continue;
+        }
+
         // backtick 形式
         if c == '`' {
             let (inner, next) = take_backtick_span(&chars, i + 1)?;
-            let output = capture_subshell(&inner)?;
+            let output = capture_subshell(&inner, vars)?;
             out.push_str(output.trim_end_matches('\n'));
             i = next;
             continue;
@@ -217,63 +245,85 @@ fn word_split(s: &str) -> Vec<String> {
 
 /// 内側コマンド文字列をサブシェルとして実行し、stdout を返す。
 ///
-/// 1. クォート対応トークナイズ（[`expand::split_quoted`]）
-/// 2. 各トークンを pipeline 展開（[`expand::expand_token_globs`]）
-///    — ここでネストした `$(...)` も再帰的に解決される（常に Unquoted 文脈）
-/// 3. [`parser::parse_pipeline`] で AST 化
-/// 4. [`crate::engine::exec::run_pipeline_captured`] で stdout を取得
+/// 1. [`parse_subshell`] でトークナイズ・展開・AST 化
+/// 2. [`crate::engine::exec::run_pipeline_captured`] で stdout を取得
 ///
 /// 非ゼロ終了は [`CmdSubstError::Exec`] としてエラー化する。
-fn capture_subshell(inner: &str) -> Result<String, CmdSubstError> {
+/// 展開は変数表しか受け取らないため、内側のリダイレクトには `[shell] mkdir_on_redirect` を適用しない。
+fn capture_subshell(inner: &str, vars: &ShellVariables) -> Result<String, CmdSubstError> {
     // 再帰ガード: ネストが深すぎる場合は中断。
     let _guard = DepthGuard::enter().ok_or(CmdSubstError::NestingTooDeep)?;
 
-    let tokens = expand::split_quoted(inner)
+    let Some(pipeline) = parse_subshell(inner, vars)? else {
+        return Ok(String::new());
+    };
+
+    let ctx = ExecContext {
+        variables: vars.clone(),
+        ..Default::default()
+    };
+    let result = crate::engine::exec::run_pipeline_captured(&pipeline, &ctx);
+    if result.exit_code != 0 {
+        return Err(CmdSubstError::Exec(format!(
+            "command exited with status {}",
+            result.exit_code
+        )));
+    }
+
+    Ok(result.stdout)
+}
+
+/// 内側コマンド文字列をパイプラインにパースする。展開結果が空なら `None`。
+///
+/// 1. クォート対応トークナイズ（[`expand::split_quoted_with_vars`]）
+/// 2. 各トークンを pipeline 展開（[`expand::expand_token_globs`]）
+///    — ここでネストした `$(...)` / `<(...)` も再帰的に解決される（常に Unquoted 文脈）
+/// 3. [`parser::parse_pipeline`] で AST 化
+///
+/// 失敗は [`CmdSubstError::Exec`] として返す。
+pub(super) fn parse_subshell(
+    inner: &str,
+    vars: &ShellVariables,
+) -> Result<Option<parser::Pipeline>, CmdSubstError> {
+    let tokens = expand::split_quoted_with_vars(inner, vars)
         .map_err(|e| CmdSubstError::Exec(format!("parse error: {e}")))?;
 
-    let mut expanded: Vec<String> = Vec::with_capacity(tokens.len());
+    let mut expanded: Vec<parser::Word> = Vec::with_capacity(tokens.len());
     for tok in tokens {
-        if matches!(
-            tok.value.as_str(),
-            "|" | ">" | ">>" | "<" | "&&" | "||" | ";"
-        ) {
-            expanded.push(tok.value);
+        if tok.is_operator() {
+            expanded.push(parser::Word::from(tok.value));
             continue;
         }
         if tok.quoted && !tok.has_subst {
-            expanded.push(tok.value);
+            expanded.push(parser::Word::literal(tok.value));
             continue;
         }
         let expanded_result = if tok.quoted && tok.has_subst {
             // クォート内の置換: 置換のみ行い glob/brace は適用しない（bash 準拠）。
-            expand::expand_token_subst_only(&tok.value, tok.subst_quoting)
+            expand::expand_token_subst_only(&tok.value, tok.subst_quoting, &tok.process_subst, vars)
         } else if tok.has_subst {
-            expand::expand_token_globs_with_quoting(&tok.value, tok.subst_quoting)
+            expand::expand_token_globs_with_quoting(
+                &tok.value,
+                tok.subst_quoting,
+                &tok.process_subst,
+                vars,
+            )
         } else {
-            expand::expand_token_globs(&tok.value)
+            expand::expand_token_globs(&tok.value, vars)
         };
         match expanded_result {
-            Ok(parts) => expanded.extend(parts),
+            Ok(parts) => expanded.extend(parts.into_iter().map(parser::Word::literal)),
             Err(e) => return Err(CmdSubstError::Exec(e.to_string())),
         }
     }
 
     if expanded.is_empty() {
-        return Ok(String::new());
+        return Ok(None);
     }
 
-    let pipeline = parser::parse_pipeline(expanded)
-        .map_err(|e| CmdSubstError::Exec(format!("parse error: {e}")))?;
-
-    let result = crate::engine::exec::run_pipeline_captured(&pipeline);
-    if result.exit_code != 0 {
-        return Err(CmdSubstError::Exec(format!(
-            "command exited with status {}",
-            result.exit_code
-        )));
-    }
-
-    Ok(result.stdout)
+    parser::parse_pipeline(expanded)
+        .map(Some)
+        .map_err(|e| CmdSubstError::Exec(format!("parse error: {e}")))
 }
 
 #[cfg(test)]
@@ -285,14 +335,26 @@ mod tests {
 
     #[test]
     fn no_substitution_fast_path() {
-        let result = expand_command_subst("hello", SubstQuoting::Unquoted).unwrap();
+        let result = expand_command_subst(
+            "hello",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec!["hello".to_string()]);
     }
 
     #[test]
     fn no_substitution_with_dollar_only() {
         // `$VAR` のような env 参照は置換構文ではないので素通し（高速パス）。
-        let result = expand_command_subst("$VAR", SubstQuoting::Unquoted).unwrap();
+        let result = expand_command_subst(
+            "$VAR",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec!["$VAR".to_string()]);
     }
 
@@ -300,13 +362,25 @@ mod tests {
 
     #[test]
     fn basic_command_subst() {
-        let result = expand_command_subst("$(echo hello)", SubstQuoting::Unquoted).unwrap();
+        let result = expand_command_subst(
+            "$(echo hello)",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec!["hello".to_string()]);
     }
 
     #[test]
     fn word_split_multiple_words() {
-        let result = expand_command_subst("$(echo a b c)", SubstQuoting::Unquoted).unwrap();
+        let result = expand_command_subst(
+            "$(echo a b c)",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(
             result,
             vec!["a".to_string(), "b".to_string(), "c".to_string()]
@@ -315,13 +389,25 @@ mod tests {
 
     #[test]
     fn word_split_collapses_consecutive_whitespace() {
-        let result = expand_command_subst("$(printf 'a   b')", SubstQuoting::Unquoted).unwrap();
+        let result = expand_command_subst(
+            "$(printf 'a   b')",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec!["a".to_string(), "b".to_string()]);
     }
 
     #[test]
     fn double_quoted_no_split() {
-        let result = expand_command_subst("$(printf 'a   b')", SubstQuoting::DoubleQuoted).unwrap();
+        let result = expand_command_subst(
+            "$(printf 'a   b')",
+            SubstQuoting::DoubleQuoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec!["a   b".to_string()]);
     }
 
@@ -330,16 +416,26 @@ mod tests {
     #[test]
     fn trailing_newlines_all_stripped() {
         // printf 'x\n\n' → 末尾改行を全除去 → "x"
-        let result =
-            expand_command_subst("$(printf 'x\\n\\n')", SubstQuoting::DoubleQuoted).unwrap();
+        let result = expand_command_subst(
+            "$(printf 'x\\n\\n')",
+            SubstQuoting::DoubleQuoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec!["x".to_string()]);
     }
 
     #[test]
     fn internal_newline_preserved_in_double_quote() {
         // 中間の改行は保持される（末尾のみ除去）。
-        let result =
-            expand_command_subst("$(printf 'a\\nb\\n')", SubstQuoting::DoubleQuoted).unwrap();
+        let result = expand_command_subst(
+            "$(printf 'a\\nb\\n')",
+            SubstQuoting::DoubleQuoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec!["a\nb".to_string()]);
     }
 
@@ -349,7 +445,13 @@ mod tests {
     #[serial]
     fn empty_output_unquoted_yields_no_words() {
         // unquoted な空出力は単語分割で 0 語になる（bash 準拠）。
-        let result = expand_command_subst("$(true)", SubstQuoting::Unquoted).unwrap();
+        let result = expand_command_subst(
+            "$(true)",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, Vec::<String>::new());
     }
 
@@ -357,7 +459,13 @@ mod tests {
     #[serial]
     fn empty_output_double_quoted_yields_one_empty_word() {
         // ダブルクォート内の空出力は分割されず 1 つの空文字列語になる（bash 準拠）。
-        let result = expand_command_subst("$(true)", SubstQuoting::DoubleQuoted).unwrap();
+        let result = expand_command_subst(
+            "$(true)",
+            SubstQuoting::DoubleQuoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec![String::new()]);
     }
 
@@ -365,8 +473,13 @@ mod tests {
 
     #[test]
     fn embedded_concatenation() {
-        let result =
-            expand_command_subst("prefix-$(echo mid)-suffix", SubstQuoting::Unquoted).unwrap();
+        let result = expand_command_subst(
+            "prefix-$(echo mid)-suffix",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec!["prefix-mid-suffix".to_string()]);
     }
 
@@ -374,7 +487,13 @@ mod tests {
 
     #[test]
     fn backtick_basic() {
-        let result = expand_command_subst("`echo hi`", SubstQuoting::Unquoted).unwrap();
+        let result = expand_command_subst(
+            "`echo hi`",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec!["hi".to_string()]);
     }
 
@@ -382,7 +501,13 @@ mod tests {
 
     #[test]
     fn nested_command_subst() {
-        let result = expand_command_subst("$(echo $(echo deep))", SubstQuoting::Unquoted).unwrap();
+        let result = expand_command_subst(
+            "$(echo $(echo deep))",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec!["deep".to_string()]);
     }
 
@@ -390,13 +515,25 @@ mod tests {
 
     #[test]
     fn unterminated_paren_errors() {
-        let err = expand_command_subst("$(echo unclosed", SubstQuoting::Unquoted).unwrap_err();
+        let err = expand_command_subst(
+            "$(echo unclosed",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap_err();
         assert!(matches!(err, CmdSubstError::Unterminated(_)));
     }
 
     #[test]
     fn unterminated_backtick_errors() {
-        let err = expand_command_subst("`echo unclosed", SubstQuoting::Unquoted).unwrap_err();
+        let err = expand_command_subst(
+            "`echo unclosed",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap_err();
         assert!(matches!(err, CmdSubstError::Unterminated(_)));
     }
 
@@ -407,7 +544,13 @@ mod tests {
         // 含まれるリテラル `)` で span が早期クローズする（`$(echo ")` で閉じ、
         // 残りの `")` がサブシェルのパース時に未終端ダブルクォートになる）。
         // panic せず安全にエラー停止することを固定する。
-        let err = expand_command_subst("$(echo \")\")", SubstQuoting::Unquoted).unwrap_err();
+        let err = expand_command_subst(
+            "$(echo \")\")",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap_err();
         assert!(matches!(err, CmdSubstError::Exec(_)));
     }
 
@@ -416,15 +559,25 @@ mod tests {
     #[test]
     #[serial]
     fn nonexistent_command_returns_exec_error_without_panic() {
-        let err =
-            expand_command_subst("$(this_command_does_not_exist_zzz)", SubstQuoting::Unquoted)
-                .unwrap_err();
+        let err = expand_command_subst(
+            "$(this_command_does_not_exist_zzz)",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap_err();
         assert!(matches!(err, CmdSubstError::Exec(_)));
     }
 
     #[test]
     fn nonzero_exit_returns_exec_error() {
-        let err = expand_command_subst("$(false)", SubstQuoting::Unquoted).unwrap_err();
+        let err = expand_command_subst(
+            "$(false)",
+            SubstQuoting::Unquoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap_err();
         assert!(matches!(err, CmdSubstError::Exec(_)));
     }
 
@@ -455,7 +608,8 @@ mod tests {
         for _ in 0..(MAX_SUBST_DEPTH + 1) {
             s = format!("$(echo {s})");
         }
-        let err = expand_command_subst(&s, SubstQuoting::Unquoted).unwrap_err();
+        let err = expand_command_subst(&s, SubstQuoting::Unquoted, &[], &ShellVariables::default())
+            .unwrap_err();
         match err {
             CmdSubstError::NestingTooDeep => {}
             CmdSubstError::Exec(msg) => assert!(
@@ -2,7 +2,8 @@
 //!
 //! - エイリアス展開 (`alias`): 先頭トークン置換
 //! - コマンド置換 (`command_subst`): `$(...)` / backtick
-//! - 基本展開 (`basic`): チルダ + 環境変数
+//! - プロセス置換 (`process_subst`): `<(...)` → `/dev/fd/N`
+//! - 基本展開 (`basic`): チルダ + 環境変数 + パラメータ展開（`${VAR:-word}` 等）
 //! - ブレース展開 (`brace`): `{a,b}` `{1..5}` 等
 //! - グロブ展開 (`glob`): `*` `?` `[abc]`
 //! - パイプライン (`pipeline`): command-subst → basic → brace → glob の統合 API
@@ -10,10 +11,13 @@
 //! 公開 API:
 //! - [`expand_alias`] — 先頭トークンのエイリアス置換
 //! - [`expand_token`] — チルダ/env のみ（1 出力）。`apply_exports` 等の単一値展開用
+//! - [`try_expand_token`] — 上記の `${VAR:?message}` の失敗を [`ParamError`] で返す版
+//! - [`split_quoted`] / [`split_quoted_with_vars`] — クォート対応トークナイズ（後者はシェル変数も展開）
 //! - [`expand_token_globs`] — command-subst + basic + brace + glob の統合（複数出力）。dispatch 用
 //! - [`expand_token_globs_with_quoting`] — 上記のコマンド置換クォート文脈指定版
 //! - [`ExpandError`] — グロブ no-match / コマンド置換失敗 等の展開失敗
 //! - [`CmdSubstError`] / [`SubstQuoting`] — コマンド置換のエラー / クォート文脈
+//! - [`ProcessSubstScope`] — プロセス置換の寿命（コマンド行の実行後に片付ける）
 
 mod alias;
 mod basic;
@@ -21,13 +25,15 @@ mod brace;
 mod command_subst;
 mod glob;
 mod pipeline;
+mod process_subst;
 mod quote;
 
 pub use alias::expand_alias;
-pub use basic::expand_token;
+pub use basic::{expand_token, try_expand_token, ParamError};
 pub use command_subst::{CmdSubstError, SubstQuoting};
 pub use pipeline::{
     expand_token_globs, expand_token_globs_with_quoting, expand_token_subst_only, ExpandError,
 };
+pub use process_subst::ProcessSubstScope;
 pub(crate) use quote::operator_prefix_len;
-pub use quote::{split_quoted, SplitError, Token};
+pub use quote::{split_quoted, split_quoted_with_vars, SplitError, Token};
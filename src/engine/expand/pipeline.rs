@@ -10,10 +10,11 @@
 //! グロブ展開で 1 件もマッチしなければ `ExpandError::NoMatches` を返す
 //! （zsh 互換）。呼び出し側は終了コード 1 でエラーメッセージを表示すること。
 
-use super::basic::expand_token;
+use super::basic::{try_expand_token_with_vars, ParamError};
 use super::brace::expand_braces;
 use super::command_subst::{expand_command_subst, CmdSubstError, SubstQuoting};
 use super::glob::{expand_glob, has_glob_meta, NoMatches};
+use crate::engine::shell_vars::ShellVariables;
 
 /// 展開エラー
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -22,6 +23,8 @@ pub enum ExpandError {
     NoMatches(String),
     /// コマンド置換の実行・パースに失敗した
     Substitution(String),
+    /// パラメータ展開（`${VAR:?message}`）が失敗した
+    Parameter(String),
 }
 
 impl From<NoMatches> for ExpandError {
@@ -30,6 +33,12 @@ impl From<NoMatches> for ExpandError {
     }
 }
 
+impl From<ParamError> for ExpandError {
+    fn from(e: ParamError) -> Self {
+        ExpandError::Parameter(e.0)
+    }
+}
+
 impl From<CmdSubstError> for ExpandError {
     fn from(e: CmdSubstError) -> Self {
         ExpandError::Substitution(e.to_string())
@@ -40,17 +49,18 @@ impl std::fmt::Display for ExpandError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ExpandError::NoMatches(p) => write!(f, "no matches found: {p}"),
-            ExpandError::Substitution(msg) => write!(f, "{msg}"),
+            ExpandError::Substitution(msg) | ExpandError::Parameter(msg) => write!(f, "{msg}"),
         }
     }
 }
 
 /// トークンに対してコマンド置換 → チルダ/env → ブレース → グロブの順で展開を行う。
 ///
-/// コマンド置換のクォート文脈は [`SubstQuoting::Unquoted`] 固定。
+/// コマンド置換のクォート文脈は [`SubstQuoting::Unquoted`] 固定で、プロセス置換は展開しない。
 /// ダブルクォート内のトークンは [`expand_token_globs_with_quoting`] を使うこと。
-pub fn expand_token_globs(token: &str) -> Result<Vec<String>, ExpandError> {
-    expand_token_globs_with_quoting(token, SubstQuoting::Unquoted)
+/// `$VAR` は `vars` のシェル変数 → 環境変数の順に引く。
+pub fn expand_token_globs(token: &str, vars: &ShellVariables) -> Result<Vec<String>, ExpandError> {
+    expand_token_globs_with_quoting(token, SubstQuoting::Unquoted, &[], vars)
 }
 
 /// [`expand_token_globs`] のコマンド置換クォート文脈指定版。
@@ -58,17 +68,20 @@ pub fn expand_token_globs(token: &str) -> Result<Vec<String>, ExpandError> {
 /// `q` はトークン内のコマンド置換 span に適用する文脈
 /// （[`SubstQuoting::Unquoted`] なら結果を単語分割、
 /// [`SubstQuoting::DoubleQuoted`] なら分割しない）。
+/// `process_subst` はプロセス置換として展開する `<(` の位置（[`super::Token::process_subst`]）。
 pub fn expand_token_globs_with_quoting(
     token: &str,
     q: SubstQuoting,
+    process_subst: &[usize],
+    vars: &ShellVariables,
 ) -> Result<Vec<String>, ExpandError> {
     // 0. command substitution（最初に適用）
-    let words = expand_command_subst(token, q)?;
+    let words = expand_command_subst(token, q, process_subst, vars)?;
 
     // 各置換結果語に対して basic → brace → glob を適用して flatten する。
     let mut results: Vec<String> = Vec::new();
     for word in words {
-        results.extend(expand_basic_brace_glob(&word)?);
+        results.extend(expand_basic_brace_glob(&word, vars)?);
     }
 
     Ok(results)
@@ -79,14 +92,19 @@ pub fn expand_token_globs_with_quoting(
 /// ダブルクォート内に置換 span を含むトークン（例: `"[$(...)]"`）用。
 /// 引用符内のリテラル文字（`[`, `*` 等）をグロブ/ブレースとして解釈させない
 /// ため、置換結果を含むテキストをそのまま返す（bash 準拠）。
-pub fn expand_token_subst_only(token: &str, q: SubstQuoting) -> Result<Vec<String>, ExpandError> {
-    Ok(expand_command_subst(token, q)?)
+pub fn expand_token_subst_only(
+    token: &str,
+    q: SubstQuoting,
+    process_subst: &[usize],
+    vars: &ShellVariables,
+) -> Result<Vec<String>, ExpandError> {
+    Ok(expand_command_subst(token, q, process_subst, vars)?)
 }
 
 /// 単一の語に対してチルダ/env → ブレース → グロブの順で展開を行う。
-fn expand_basic_brace_glob(token: &str) -> Result<Vec<String>, ExpandError> {
+fn expand_basic_brace_glob(token: &str, vars: &ShellVariables) -> Result<Vec<String>, ExpandError> {
     // 1. tilde + env
-    let basic = expand_token(token);
+    let basic = try_expand_token_with_vars(token, vars)?;
 
     // 2. brace
     let after_brace = expand_braces(&basic);
@@ -133,7 +151,7 @@ mod tests {
 
     #[test]
     fn plain_text_returns_single_element() {
-        let result = expand_token_globs("hello").unwrap();
+        let result = expand_token_globs("hello", &ShellVariables::default()).unwrap();
         assert_eq!(result, vec!["hello".to_string()]);
     }
 
@@ -142,7 +160,7 @@ mod tests {
     fn tilde_then_brace() {
         // `~/{foo,bar}` → `$HOME/foo`, `$HOME/bar`
         let home = env::var("HOME").unwrap();
-        let result = expand_token_globs("~/{foo,bar}").unwrap();
+        let result = expand_token_globs("~/{foo,bar}", &ShellVariables::default()).unwrap();
         assert_eq!(result, vec![format!("{home}/foo"), format!("{home}/bar")]);
     }
 
@@ -150,7 +168,8 @@ mod tests {
     #[serial]
     fn env_var_then_brace() {
         env::set_var("JARVISH_PIPELINE_TEST", "/tmp/jp");
-        let result = expand_token_globs("$JARVISH_PIPELINE_TEST/{a,b}").unwrap();
+        let result =
+            expand_token_globs("$JARVISH_PIPELINE_TEST/{a,b}", &ShellVariables::default()).unwrap();
         assert_eq!(
             result,
             vec!["/tmp/jp/a".to_string(), "/tmp/jp/b".to_string()]
@@ -168,7 +187,7 @@ mod tests {
         fs::write(dir.path().join("a.md"), "").unwrap();
         fs::write(dir.path().join("b.log"), "").unwrap();
 
-        let mut result = expand_token_globs("*.{txt,md}").unwrap();
+        let mut result = expand_token_globs("*.{txt,md}", &ShellVariables::default()).unwrap();
         result.sort();
         assert_eq!(result, vec!["a.md".to_string(), "a.txt".to_string()]);
     }
@@ -180,7 +199,7 @@ mod tests {
         let _guard = CwdGuard::new();
         env::set_current_dir(dir.path()).unwrap();
 
-        let err = expand_token_globs("*.nonexistent_xyz").unwrap_err();
+        let err = expand_token_globs("*.nonexistent_xyz", &ShellVariables::default()).unwrap_err();
         assert_eq!(err, ExpandError::NoMatches("*.nonexistent_xyz".to_string()));
     }
 
@@ -194,7 +213,8 @@ mod tests {
         env::set_current_dir(dir.path()).unwrap();
         fs::write(dir.path().join("a.txt"), "").unwrap();
 
-        let err = expand_token_globs("*.{txt,nonexistent_xyz}").unwrap_err();
+        let err =
+            expand_token_globs("*.{txt,nonexistent_xyz}", &ShellVariables::default()).unwrap_err();
         match err {
             ExpandError::NoMatches(p) => assert_eq!(p, "*.nonexistent_xyz"),
             other => panic!("expected NoMatches, got {other:?}"),
@@ -203,7 +223,7 @@ mod tests {
 
     #[test]
     fn numeric_brace_range_alone() {
-        let result = expand_token_globs("{1..3}").unwrap();
+        let result = expand_token_globs("{1..3}", &ShellVariables::default()).unwrap();
         assert_eq!(
             result,
             vec!["1".to_string(), "2".to_string(), "3".to_string()]
@@ -214,7 +234,7 @@ mod tests {
 
     #[test]
     fn command_subst_then_word_split() {
-        let result = expand_token_globs("$(echo a b c)").unwrap();
+        let result = expand_token_globs("$(echo a b c)", &ShellVariables::default()).unwrap();
         assert_eq!(
             result,
             vec!["a".to_string(), "b".to_string(), "c".to_string()]
@@ -223,24 +243,45 @@ mod tests {
 
     #[test]
     fn command_subst_double_quoted_no_split() {
-        let result =
-            expand_token_globs_with_quoting("$(printf 'a   b')", SubstQuoting::DoubleQuoted)
-                .unwrap();
+        let result = expand_token_globs_with_quoting(
+            "$(printf 'a   b')",
+            SubstQuoting::DoubleQuoted,
+            &[],
+            &ShellVariables::default(),
+        )
+        .unwrap();
         assert_eq!(result, vec!["a   b".to_string()]);
     }
 
     #[test]
     fn command_subst_nested_resolves_via_pipeline() {
-        let result = expand_token_globs("$(echo $(echo deep))").unwrap();
+        let result =
+            expand_token_globs("$(echo $(echo deep))", &ShellVariables::default()).unwrap();
         assert_eq!(result, vec!["deep".to_string()]);
     }
 
     #[test]
     fn command_subst_error_maps_to_substitution() {
-        let err = expand_token_globs("$(false)").unwrap_err();
+        let err = expand_token_globs("$(false)", &ShellVariables::default()).unwrap_err();
         assert!(matches!(err, ExpandError::Substitution(_)));
     }
 
+    #[test]
+    #[serial]
+    fn param_expansion_error_maps_to_parameter() {
+        env::remove_var("JARVISH_PIPE_UNSET");
+        let err = expand_token_globs("${JARVISH_PIPE_UNSET:?not set}", &ShellVariables::default())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ExpandError::Parameter("JARVISH_PIPE_UNSET: not set".to_string())
+        );
+        assert_eq!(
+            expand_token_globs("${JARVISH_PIPE_UNSET:-a b}", &ShellVariables::default()).unwrap(),
+            vec!["a b".to_string()]
+        );
+    }
+
     #[test]
     #[serial]
     fn command_subst_result_then_glob() {
@@ -252,7 +293,8 @@ mod tests {
         fs::write(dir.path().join("b.txt"), "").unwrap();
 
         // `$(printf '*.txt')` → `*.txt` → glob → a.txt b.txt
-        let mut result = expand_token_globs("$(printf '*.txt')").unwrap();
+        let mut result =
+            expand_token_globs("$(printf '*.txt')", &ShellVariables::default()).unwrap();
         result.sort();
         assert_eq!(result, vec!["a.txt".to_string(), "b.txt".to_string()]);
     }
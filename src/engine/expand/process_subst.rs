@@ -0,0 +1,213 @@
+//! プロセス置換展開（`<(...)`）
+//!
+//! `diff <(ls dir1) <(ls dir2)` のように、トークン内に現れる `<(cmd)` の内部コマンドを
+//! メインのコマンドと並行して別プロセスで起動し（[`exec::spawn_pipeline_detached`]）、
+//! その stdout につながるパイプの読み取り側を `/dev/fd/N` のパスとして引数に埋め込む。
+//! span の検出はトークナイザ（[`super::quote`]）、置換はコマンド置換と同じ段
+//! （[`super::command_subst`]）で行う。
+//!
+//! 起動した内部コマンドとパイプは [`ProcessSubstScope`] の寿命の間だけ保持する。
+//! コマンド行の実行後にスコープを drop するとパイプを閉じ、内部コマンドを回収する
+//! （メインのコマンドが読み切らずに終了し、まだ動いている内部コマンドは終了させる）。
+//!
+//! ## 対応範囲
+//! - 引数（`diff <(a) <(b)`）・語の一部（`--file=<(cmd)`）・リダイレクト先（`wc -l < <(cmd)`）
+//! - 1 行に複数の置換。置換ごとに独立したパイプと内部コマンドを持つ
+//! - ネスト（`cat <(sort <(ls))`）。内側の置換は外側の内部コマンドの展開時に起動し、
+//!   そのパイプは外側の内部コマンドに引き継がれる
+//! - 内部コマンド中のパイプライン（`<(ls | sort)`）・コマンド置換・`<` リダイレクト
+//!
+//! ## V1 の限界（既知の非対応事項）
+//! - 書き込み側の `>(...)` は未対応（従来どおり `>` リダイレクトとして解釈される）。
+//! - 内部コマンドは外部コマンドのみ（ビルトイン・エイリアス・`&&` 等の接続演算子は不可）。
+//! - 内部コマンドの終了ステータスは伝播しない（起動に失敗した場合のみエラーにする）。
+//! - 内部コマンドの stdin は `/dev/null`（`<` リダイレクトで指定した場合を除く）。
+//! - `/dev/fd` を持つ OS（Linux / macOS）のみ。名前付きパイプ（FIFO）へのフォールバックはない。
+//! - 読み取り側は close-on-exec を外して子プロセスに渡すため、置換の有効な間に起動した
+//!   他の子プロセス（同じ行の他の内部コマンド等）にも引き継がれる。
+//! - dry-run（`[shell] dry_run`）では実行せず `<(...)` のまま表示する。
+//! - `source <(cmd)` は使えない。`source` は通常ファイル以外（FIFO・`/dev/fd/N` のパイプ）を
+//!   読み込まないため、"not a regular file" エラーになる。
+
+use std::cell::RefCell;
+use std::os::fd::{AsRawFd, OwnedFd};
+
+use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+use tracing::debug;
+
+use super::command_subst::{parse_subshell, CmdSubstError, DepthGuard};
+use crate::engine::exec::{self, DetachedPipeline};
+use crate::engine::shell_vars::ShellVariables;
+
+/// 有効なプロセス置換 1 つ分（メインのコマンドが読むパイプと内部コマンド）
+///
+/// drop はフィールドの宣言順に行われるため、先にパイプを閉じてから内部コマンドを回収する。
+struct ActiveSubst {
+    /// パイプの読み取り側（`/dev/fd/N` として子プロセスに引き継ぐ）
+    _reader: OwnedFd,
+    /// 内部コマンド（`<()` のように空なら `None`）
+    _pipeline: Option<DetachedPipeline>,
+}
+
+thread_local! {
+    /// 有効なプロセス置換。展開は pipeline 経由の再入で引数が分断されるため
+    /// thread-local に保持し、[`ProcessSubstScope`] が寿命を区切る。
+    static ACTIVE: RefCell<Vec<ActiveSubst>> = const { RefCell::new(Vec::new()) };
+}
+
+/// プロセス置換の寿命を区切るガード。
+///
+/// コマンド行の展開前に作成し、実行後に drop する。drop 時は作成以降に起動した
+/// プロセス置換のパイプを閉じて内部コマンドを回収する。`source` のように実行中に別の
+/// コマンド行を実行する場合も、内側のスコープは自身の置換だけを片付ける。
+#[must_use]
+pub struct ProcessSubstScope {
+    /// 作成時点の [`ACTIVE`] の件数
+    start: usize,
+}
+
+impl ProcessSubstScope {
+    /// これ以降に起動するプロセス置換を受け持つスコープを開始する。
+    pub fn enter() -> Self {
+        Self {
+            start: ACTIVE.with(|active| active.borrow().len()),
+        }
+    }
+}
+
+impl Drop for ProcessSubstScope {
+    fn drop(&mut self) {
+        let finished: Vec<ActiveSubst> = ACTIVE.with(|active| {
+            let mut active = active.borrow_mut();
+            let start = self.start.min(active.len());
+            active.drain(start..).collect()
+        });
+        if !finished.is_empty() {
+            debug!(count = finished.len(), "Closing process substitutions");
+        }
+    }
+}
+
+/// `<(inner)` の内部コマンドを起動し、その出力を読むための `/dev/fd/N` のパスを返す。
+///
+/// 内部コマンドのパース・展開は [`parse_subshell`] に委ね、ネストした `<(...)` /
+/// `$(...)` もそこで再帰的に解決される。
+pub(super) fn open(inner: &str, vars: &ShellVariables) -> Result<String, CmdSubstError> {
+    // 再帰ガード: コマンド置換と深さを共有する。
+    let _guard = DepthGuard::enter().ok_or(CmdSubstError::NestingTooDeep)?;
+
+    let pipeline = parse_subshell(inner, vars)?;
+
+    let (reader, writer) =
+        os_pipe::pipe().map_err(|e| CmdSubstError::ProcessSubst(format!("pipe error: {e}")))?;
+    let reader = OwnedFd::from(reader);
+    // 読み取り側はメインのコマンドに引き継ぐため close-on-exec を外す
+    // （書き込み側は内部コマンドの stdout にのみ接続する）
+    fcntl(&reader, FcntlArg::F_SETFD(FdFlag::empty()))
+        .map_err(|e| CmdSubstError::ProcessSubst(format!("fcntl error: {e}")))?;
+
+    let pipeline = match pipeline {
+        Some(pipeline) => Some(
+            exec::spawn_pipeline_detached(&pipeline, writer.into()).map_err(|result| {
+                CmdSubstError::ProcessSubst(format!(
+                    "command exited with status {}",
+                    result.exit_code
+                ))
+            })?,
+        ),
+        // 空の `<()` は即 EOF になるパイプ（書き込み側はここで閉じる）
+        None => None,
+    };
+
+    let path = format!("/dev/fd/{}", reader.as_raw_fd());
+    debug!(inner = %inner, path = %path, "Process substitution opened");
+    ACTIVE.with(|active| {
+        active.borrow_mut().push(ActiveSubst {
+            _reader: reader,
+            _pipeline: pipeline,
+        })
+    });
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::command_subst::{expand_command_subst, SubstQuoting};
+    use super::*;
+
+    fn vars() -> ShellVariables {
+        ShellVariables::default()
+    }
+
+    fn read(path: &str) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn expands_to_readable_dev_fd_path() {
+        let _scope = ProcessSubstScope::enter();
+        let words =
+            expand_command_subst("<(echo hi)", SubstQuoting::Unquoted, &[0], &vars()).unwrap();
+        assert_eq!(words.len(), 1);
+        assert!(words[0].starts_with("/dev/fd/"), "{words:?}");
+        assert_eq!(read(&words[0]), "hi\n");
+    }
+
+    #[test]
+    fn multiple_nested_and_embedded_substitutions() {
+        let _scope = ProcessSubstScope::enter();
+        let a = expand_command_subst(
+            "<(printf 'b\\na\\n' | sort)",
+            SubstQuoting::Unquoted,
+            &[0],
+            &vars(),
+        )
+        .unwrap()
+        .remove(0);
+        let b = expand_command_subst(
+            "<(cat <(echo nested))",
+            SubstQuoting::Unquoted,
+            &[0],
+            &vars(),
+        )
+        .unwrap()
+        .remove(0);
+        assert_ne!(a, b);
+        assert_eq!(read(&a), "a\nb\n");
+        assert_eq!(read(&b), "nested\n");
+
+        let embedded =
+            expand_command_subst("--file=<(true)", SubstQuoting::Unquoted, &[7], &vars()).unwrap();
+        assert!(embedded[0].starts_with("--file=/dev/fd/"), "{embedded:?}");
+        assert_eq!(read(embedded[0].trim_start_matches("--file=")), "");
+    }
+
+    #[test]
+    fn scope_drop_closes_only_its_own_substitutions() {
+        let outer = ProcessSubstScope::enter();
+        expand_command_subst("<(echo outer)", SubstQuoting::Unquoted, &[0], &vars()).unwrap();
+        let count = || ACTIVE.with(|active| active.borrow().len());
+        let before = count();
+        {
+            let _inner = ProcessSubstScope::enter();
+            expand_command_subst("<(sleep 30)", SubstQuoting::Unquoted, &[0], &vars()).unwrap();
+            assert_eq!(count(), before + 1);
+        }
+        assert_eq!(count(), before);
+        drop(outer);
+        assert_eq!(count(), before - 1);
+    }
+
+    #[test]
+    fn missing_command_is_an_error() {
+        let _scope = ProcessSubstScope::enter();
+        let err = expand_command_subst(
+            "<(this_command_does_not_exist_zzz)",
+            SubstQuoting::Unquoted,
+            &[0],
+            &vars(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, CmdSubstError::ProcessSubst(_)));
+    }
+}
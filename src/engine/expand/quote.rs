@@ -7,6 +7,8 @@
 //! このモジュールは入力文字列を 1 トークンずつ走査し、
 //! 各トークンに対して `(value, quoted)` を返す。
 //! `quoted = true` のトークンはシェル展開の対象外とする。
+//! ただしダブルクォート内の `$VAR` / `${VAR}` は bash と同様に
+//! トークナイズ時点で展開する（シングルクォート内・`\$` はリテラル）。
 //!
 //! POSIX 互換の制御演算子（`|`, `>`, `>>`, `<`, `&&`, `||`, `;`）は
 //! 専用トークンとして分離する。
@@ -14,8 +16,12 @@
 //! また、`$(...)` / backtick `` `...` `` のコマンド置換 span は
 //! トークンの一部としてアトミックに取り込む（内部空白や `|` 等の演算子で
 //! トークンを分断しない）。span の実展開は [`super::command_subst`] が担う。
+//! プロセス置換 `<(...)` も同様に 1 つの span として取り込み、`<` リダイレクトとは
+//! 区別する（`< (...)` のように空白を挟めば従来どおりリダイレクト）。
 
+use super::basic::{expand_var_at, find_param_close};
 use super::command_subst::SubstQuoting;
+use crate::engine::shell_vars::ShellVariables;
 
 /// 1 つのトークンとそのクォート状態
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -29,6 +35,19 @@ pub struct Token {
     /// unquoted span を 1 つでも含めば `Unquoted`、全 span が
     /// ダブルクォート内なら `DoubleQuoted`。
     pub subst_quoting: SubstQuoting,
+    /// `value` 内でプロセス置換として展開する `<(` の位置（文字単位のインデックス）。
+    /// クォート内・エスケープされた `<(` はリテラルのため含まない。
+    pub process_subst: Vec<usize>,
+}
+
+impl Token {
+    /// トークナイザが分離した制御演算子（クォートされていない `|`, `>` 等）かどうか。
+    ///
+    /// `">"` のようにクォートされたトークンは値が演算子と一致しても false。
+    pub fn is_operator(&self) -> bool {
+        let len = operator_prefix_len(&self.value);
+        !self.quoted && len > 0 && len == self.value.len()
+    }
 }
 
 /// パースエラー
@@ -39,6 +58,8 @@ pub enum SplitError {
     DanglingBackslash,
     /// `$(...)` または backtick が閉じられていない
     UnterminatedSubstitution,
+    /// ダブルクォート内の `${VAR:?message}` が失敗した（値は `VAR: message`）
+    Parameter(String),
 }
 
 impl std::fmt::Display for SplitError {
@@ -50,6 +71,7 @@ impl std::fmt::Display for SplitError {
             SplitError::UnterminatedSubstitution => {
                 write!(f, "unterminated command substitution")
             }
+            SplitError::Parameter(msg) => write!(f, "{msg}"),
         }
     }
 }
@@ -59,9 +81,24 @@ impl std::fmt::Display for SplitError {
 /// shell_words::split と同じ意味論で、
 /// - シングルクォート内は完全にリテラル（エスケープなし）
 /// - ダブルクォート内は `\` で `"` `\` `$` `\`` をエスケープ可能
+/// - ダブルクォート内の `$VAR` / `${VAR}` / `${VAR:-word}` 等は変数の値に展開する
+/// - クォート外の `${...}` は空白を含んでも 1 トークンの一部として取り込む
 /// - クォート外は `\` で次の 1 文字をエスケープ
 /// - 制御演算子 `|`, `>`, `>>`, `<`, `&&`, `||`, `;` は単独トークンに分離
+/// - クォート外の `$(...)` / backtick / `<(...)` は空白・演算子を含んでも 1 トークンの一部
+///
+/// シェル変数は参照しない。コマンドとして実行する入力には [`split_quoted_with_vars`] を使うこと。
 pub fn split_quoted(input: &str) -> Result<Vec<Token>, SplitError> {
+    split_quoted_with_vars(input, &ShellVariables::default())
+}
+
+/// [`split_quoted`] のシェル変数参照版。
+///
+/// ダブルクォート内の `$VAR` / `${VAR}` を `vars` のシェル変数 → 環境変数の順に引いて展開する。
+pub fn split_quoted_with_vars(
+    input: &str,
+    vars: &ShellVariables,
+) -> Result<Vec<Token>, SplitError> {
     let mut tokens: Vec<Token> = Vec::new();
     let mut current = String::new();
     let mut in_token = false;
@@ -70,6 +107,8 @@ pub fn split_quoted(input: &str) -> Result<Vec<Token>, SplitError> {
     let mut has_subst = false;
     // unquoted な span を 1 つでも含んだか（含めば最終的に Unquoted 文脈）
     let mut has_unquoted_subst = false;
+    // 現トークン内のクォート外 `<(` の位置
+    let mut process_subst: Vec<usize> = Vec::new();
 
     let chars: Vec<char> = input.chars().collect();
     let mut i = 0;
@@ -91,6 +130,7 @@ pub fn split_quoted(input: &str) -> Result<Vec<Token>, SplitError> {
                 quoted,
                 has_subst,
                 subst_quoting,
+                process_subst: std::mem::take(&mut process_subst),
             });
         }};
     }
@@ -115,6 +155,18 @@ pub fn split_quoted(input: &str) -> Result<Vec<Token>, SplitError> {
             continue;
         }
 
+        // プロセス置換 `<(...)`: `<` 演算子より先に判定し、span をアトミックに取り込む。
+        if c == '<' && chars.get(i + 1) == Some(&'(') {
+            in_token = true;
+            has_subst = true;
+            has_unquoted_subst = true;
+            process_subst.push(current.chars().count());
+            let end = scan_paren_span(&chars, i + 2)?;
+            current.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+
         // 演算子: 既存トークンを flush してから演算子を 1 トークンとして追加。
         // ただしコマンド置換 span 内ではここに到達しない（span は下で
         // アトミックに取り込まれるため）。
@@ -129,11 +181,23 @@ pub fn split_quoted(input: &str) -> Result<Vec<Token>, SplitError> {
                 quoted: false,
                 has_subst: false,
                 subst_quoting: SubstQuoting::Unquoted,
+                process_subst: Vec::new(),
             });
             i += op_len;
             continue;
         }
 
+        // unquoted な `${...}` は `word` 内の空白・演算子で分断しないよう丸ごと取り込み、
+        // 展開は後段（`expand_token`）に任せる。閉じられていなければ `$` はリテラル。
+        if c == '$' && chars.get(i + 1) == Some(&'{') {
+            if let Some(close) = find_param_close(&chars, i + 2) {
+                in_token = true;
+                current.extend(&chars[i..=close]);
+                i = close + 1;
+                continue;
+            }
+        }
+
         match c {
             // unquoted コンテキストでのコマンド置換 span をアトミックに取り込む。
             '$' if i + 1 < chars.len() && chars[i + 1] == '(' => {
@@ -198,6 +262,13 @@ pub fn split_quoted(input: &str) -> Result<Vec<Token>, SplitError> {
                         i = end;
                         continue;
                     }
+                    if ch == '$' {
+                        if let Some((value, end)) = expand_var_at(&chars, i, vars) {
+                            current.push_str(&value.map_err(|e| SplitError::Parameter(e.0))?);
+                            i = end;
+                            continue;
+                        }
+                    }
                     if ch == '\\' && i + 1 < chars.len() {
                         let next = chars[i + 1];
                         if matches!(next, '"' | '\\' | '$' | '`') {
@@ -291,20 +362,25 @@ fn operator_at(chars: &[char], i: usize) -> usize {
     if i >= chars.len() {
         return 0;
     }
-    // 演算子は最大 2 文字（ASCII）なので先頭 2 文字だけ切り出せば十分。
-    let end = (i + 2).min(chars.len());
+    // 演算子は最大 3 文字（ASCII）なので先頭 3 文字だけ切り出せば十分。
+    let end = (i + 3).min(chars.len());
     let head: String = chars[i..end].iter().collect();
     operator_prefix_len(&head)
 }
 
 /// `s` の先頭が演算子トークンなら、そのバイト長を返す（なければ 0）。
 ///
-/// 対応演算子: `&&` `||` `>>`（2 バイト）、`|` `<` `>` `;`（1 バイト）。
+/// 対応演算子: `&>>` `>&2`（3 バイト）、`&&` `||` `>>` `&>`（2 バイト）、
+/// `|` `<` `>` `;`（1 バイト）。
 /// 補完系の寛容スキャナ（`cli/completer/context.rs`）と実行系の
 /// [`split_quoted`] が同一の演算子表を参照するための共有関数。
 pub(crate) fn operator_prefix_len(s: &str) -> usize {
-    // 2 文字演算子（ASCII のみなのでバイト長 == 文字数）
-    if s.starts_with("&&") || s.starts_with("||") || s.starts_with(">>") {
+    // 3 文字演算子（ASCII のみなのでバイト長 == 文字数）。2 文字の `&>` / `>>` より先に判定する
+    if s.starts_with("&>>") || s.starts_with(">&2") {
+        return 3;
+    }
+    // 2 文字演算子
+    if s.starts_with("&&") || s.starts_with("||") || s.starts_with(">>") || s.starts_with("&>") {
         return 2;
     }
     // 1 文字演算子
@@ -325,6 +401,7 @@ mod tests {
             quoted,
             has_subst: false,
             subst_quoting: SubstQuoting::Unquoted,
+            process_subst: Vec::new(),
         }
     }
 
@@ -335,6 +412,15 @@ mod tests {
             quoted,
             has_subst: true,
             subst_quoting,
+            process_subst: Vec::new(),
+        }
+    }
+
+    /// 先頭にプロセス置換 `<(...)` を 1 つ含むトークンを生成するヘルパ。
+    fn tp(value: &str) -> Token {
+        Token {
+            process_subst: vec![0],
+            ..ts(value, false, SubstQuoting::Unquoted)
         }
     }
 
@@ -359,6 +445,72 @@ mod tests {
         assert_eq!(toks, vec![t("echo", false), t("{a,b}", true)]);
     }
 
+    #[test]
+    #[serial_test::serial]
+    fn double_quoted_env_var_is_expanded() {
+        std::env::set_var("JARVISH_TEST_QUOTE_MSG", "hello world");
+        let toks = split_quoted("echo \"$JARVISH_TEST_QUOTE_MSG!\" \"${JARVISH_TEST_QUOTE_MSG}x\"")
+            .unwrap();
+        std::env::remove_var("JARVISH_TEST_QUOTE_MSG");
+        assert_eq!(
+            toks,
+            vec![
+                t("echo", false),
+                t("hello world!", true),
+                t("hello worldx", true)
+            ]
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn single_quoted_and_escaped_env_var_stay_literal() {
+        std::env::set_var("JARVISH_TEST_QUOTE_LIT", "value");
+        let toks =
+            split_quoted("echo '$JARVISH_TEST_QUOTE_LIT' \"\\$JARVISH_TEST_QUOTE_LIT\"").unwrap();
+        std::env::remove_var("JARVISH_TEST_QUOTE_LIT");
+        assert_eq!(
+            toks,
+            vec![
+                t("echo", false),
+                t("$JARVISH_TEST_QUOTE_LIT", true),
+                t("$JARVISH_TEST_QUOTE_LIT", true)
+            ]
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn double_quoted_param_expansion_is_expanded() {
+        std::env::remove_var("JARVISH_TEST_QUOTE_UNSET");
+        let toks = split_quoted("echo \"${JARVISH_TEST_QUOTE_UNSET:-a \"b\" c}!\"").unwrap();
+        assert_eq!(toks, vec![t("echo", false), t("a b c!", true)]);
+
+        let err = split_quoted("echo \"${JARVISH_TEST_QUOTE_UNSET:?required}\"").unwrap_err();
+        assert_eq!(
+            err,
+            SplitError::Parameter("JARVISH_TEST_QUOTE_UNSET: required".to_string())
+        );
+    }
+
+    #[test]
+    fn unquoted_param_expansion_span_is_atomic() {
+        let toks = split_quoted("echo ${VAR:-a b|c} x").unwrap();
+        assert_eq!(
+            toks,
+            vec![t("echo", false), t("${VAR:-a b|c}", false), t("x", false)]
+        );
+        // 閉じられていない `${` は通常の文字として扱う
+        let toks = split_quoted("echo ${VAR").unwrap();
+        assert_eq!(toks, vec![t("echo", false), t("${VAR", false)]);
+    }
+
+    #[test]
+    fn double_quoted_lone_dollar_is_literal() {
+        let toks = split_quoted("echo \"costs $ 5\"").unwrap();
+        assert_eq!(toks, vec![t("echo", false), t("costs $ 5", true)]);
+    }
+
     #[test]
     fn backslash_escape_marks_token_as_quoted() {
         let toks = split_quoted("echo \\*").unwrap();
@@ -393,6 +545,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn fd_redirect_operators() {
+        let toks = split_quoted("make &>build.log; echo done >&2").unwrap();
+        assert_eq!(
+            toks,
+            vec![
+                t("make", false),
+                t("&>", false),
+                t("build.log", false),
+                t(";", false),
+                t("echo", false),
+                t("done", false),
+                t(">&2", false),
+            ]
+        );
+        let toks = split_quoted("make &>> all.log").unwrap();
+        assert_eq!(toks[1], t("&>>", false));
+    }
+
     #[test]
     fn and_or_semi_operators() {
         let toks = split_quoted("a && b || c ; d").unwrap();
@@ -540,6 +711,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn process_subst_span_is_atomic_and_not_a_redirect() {
+        // `<(...)` は `<` 演算子に分離せず、span 内の空白・`|` でも分断しない。
+        let toks = split_quoted("diff <(ls a | sort) <(ls b)").unwrap();
+        assert_eq!(
+            toks,
+            vec![t("diff", false), tp("<(ls a | sort)"), tp("<(ls b)")]
+        );
+        // 空白を挟んだ `< <(cmd)` は stdin リダイレクト + プロセス置換
+        let toks = split_quoted("wc -l < <(ls)").unwrap();
+        assert_eq!(toks[2], t("<", false));
+        assert_eq!(toks[3], tp("<(ls)"));
+        // クォート内・エスケープされた `<(` はリテラル（他の置換と同じトークンでも位置に含めない）
+        let toks = split_quoted("echo '<(ls)'").unwrap();
+        assert_eq!(toks, vec![t("echo", false), t("<(ls)", true)]);
+        let toks = split_quoted("echo \"$(echo a) <(ls)\" '<(ls)'$(echo x) a<(ls)").unwrap();
+        assert_eq!(
+            toks[1],
+            ts("$(echo a) <(ls)", true, SubstQuoting::DoubleQuoted)
+        );
+        assert_eq!(toks[2], ts("<(ls)$(echo x)", true, SubstQuoting::Unquoted));
+        assert_eq!(toks[3].process_subst, vec![1]);
+        assert_eq!(
+            split_quoted("cat <(echo unclosed"),
+            Err(SplitError::UnterminatedSubstitution)
+        );
+    }
+
     #[test]
     fn plain_dollar_paren_not_treated_as_subst() {
         // `$VAR` は置換構文ではないので通常トークン（has_subst=false）。
@@ -564,6 +763,11 @@ mod tests {
         assert_eq!(operator_prefix_len("&"), 0);
         assert_eq!(operator_prefix_len("|foo"), 1);
         assert_eq!(operator_prefix_len(">>foo"), 2);
+        assert_eq!(operator_prefix_len("&>"), 2);
+        assert_eq!(operator_prefix_len("&>>"), 3);
+        assert_eq!(operator_prefix_len(">&2"), 3);
+        assert_eq!(operator_prefix_len("&>log"), 2);
+        assert_eq!(operator_prefix_len(">&1"), 1);
     }
 
     #[test]
@@ -581,6 +785,8 @@ mod tests {
             "a&b",
             "a&&&b",
             ">>>",
+            "make &>build.log",
+            "make &>> log >&2",
             "",
             "   ",
             "echo 'a && b'",
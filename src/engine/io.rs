@@ -2,23 +2,217 @@
 //!
 //! stdin → PTY master への転送、PTY master からの出力キャプチャ、
 //! tee パターンによるターミナル表示とバッファ蓄積を提供する。
+//!
+//! キャプチャスレッドは [`CaptureThread`] で起動し、読み取ったバイト列を共有バッファに
+//! 逐次蓄積する。子プロセスが起動したデーモン等が PTY / パイプを開いたままにして EOF が
+//! 届かない場合でも、子プロセス終了後 [`CAPTURE_JOIN_TIMEOUT`] で待機を打ち切り、
+//! それまでの部分キャプチャを返す（シェルが固まらないようにする）。
+//! 読み取り元を [`StoppableReader`] で包んだスレッドは、打ち切り時に停止させて fd を閉じる
+//! （切り離したスレッドが PTY master 等を開いたまま残り、fd がリークしないようにする）。
 
 use std::fs::File;
 use std::io::{self, BufRead, Read, Write};
-use std::os::fd::{AsRawFd, BorrowedFd, RawFd};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+use tracing::warn;
+
+use super::job_control::suspend_session;
+use super::output_wrap;
+use super::pty::{
+    alt_screen_active_after, contains_alt_screen_seq, get_terminal_winsize, ALT_SCREEN_SEQ_CARRY,
+};
+
+// ── キャプチャスレッド ──
+
+/// 子プロセス終了後、キャプチャスレッドが読み取りを終えるのを待つ上限
+pub(super) const CAPTURE_JOIN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 停止を要求したキャプチャスレッドが終了するのを待つ上限
+const CAPTURE_STOP_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 出力キャプチャスレッドのハンドル。
+///
+/// スレッドはキャプチャしたバイト列を共有バッファに逐次追記し、終了時に戻り値を
+/// チャネルで送る。`JoinHandle` にはタイムアウト付きの join がないため、
+/// `recv_timeout` で完了を待つ。
+pub(super) struct CaptureThread<T> {
+    name: &'static str,
+    buffer: Arc<Mutex<Vec<u8>>>,
+    done: mpsc::Receiver<T>,
+    /// 読み取りを打ち切らせる停止パイプ（[`StoppableReader`] で読むスレッドのみ）
+    stop: Option<os_pipe::PipeWriter>,
+}
+
+impl<T: Send + 'static> CaptureThread<T> {
+    /// `f` を別スレッドで実行する。`f` には蓄積先の共有バッファが渡される。
+    pub(super) fn spawn<F>(name: &'static str, f: F) -> Self
+    where
+        F: FnOnce(&Mutex<Vec<u8>>) -> T + Send + 'static,
+    {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let shared = Arc::clone(&buffer);
+        let (tx, done) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(f(&shared));
+        });
+        Self {
+            name,
+            buffer,
+            done,
+            stop: None,
+        }
+    }
+
+    /// [`join`](Self::join) が期限切れになったとき、`stop`（[`StoppableReader::new`] の停止側）を
+    /// 閉じてスレッドの読み取りを打ち切らせる（join せずに drop した場合も停止パイプが閉じ、
+    /// スレッドは終了する）。
+    pub(super) fn with_stop(mut self, stop: os_pipe::PipeWriter) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    /// `deadline` までスレッドの完了を待ち、キャプチャしたバイト列と戻り値を返す。
+    ///
+    /// 期限までに終わらなければ、停止パイプがあれば読み取りを打ち切らせてスレッドの終了
+    /// （読み取り元の fd のクローズ）を待ち、なければスレッドを切り離す。いずれもそれまでに
+    /// 蓄積した部分キャプチャを返す（スレッドが終了しなかった・パニックした場合の戻り値は `None`）。
+    pub(super) fn join(mut self, deadline: Instant) -> (Vec<u8>, Option<T>) {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let result = match self.done.recv_timeout(timeout) {
+            Ok(value) => Some(value),
+            Err(RecvTimeoutError::Timeout) => {
+                warn!(
+                    thread = self.name,
+                    timeout_ms = CAPTURE_JOIN_TIMEOUT.as_millis() as u64,
+                    "Capture thread did not reach EOF in time, returning partial output"
+                );
+                self.stop.take().and_then(|stop| {
+                    drop(stop);
+                    self.done.recv_timeout(CAPTURE_STOP_TIMEOUT).ok()
+                })
+            }
+            Err(RecvTimeoutError::Disconnected) => None,
+        };
+        let bytes = std::mem::take(&mut *lock_buffer(&self.buffer));
+        (bytes, result)
+    }
+}
 
-use super::pty::{contains_alt_screen_seq, get_terminal_winsize};
+/// `read` を [`tee_to_terminal`] で tee するキャプチャスレッドを起動する。
+///
+/// 読み取り元は [`StoppableReader`] で包み、[`join`](CaptureThread::join) が期限切れになれば
+/// 読み取りを打ち切らせる（切り離したスレッドがタイムアウト後も端末・バッファへ書き続けない
+/// ようにする）。停止パイプを作れなければ、停止できないスレッドとして起動する。
+pub(super) fn spawn_tee<R>(
+    name: &'static str,
+    read: R,
+    is_stderr: bool,
+    truncate: bool,
+) -> CaptureThread<()>
+where
+    R: Read + AsFd + Send + 'static,
+{
+    match os_pipe::pipe() {
+        Ok((stop, stop_write)) => {
+            let reader = StoppableReader { inner: read, stop };
+            CaptureThread::spawn(name, move |buf| {
+                tee_to_terminal(reader, is_stderr, truncate, buf)
+            })
+            .with_stop(stop_write)
+        }
+        Err(e) => {
+            warn!(thread = name, error = %e, "Failed to create capture stop pipe");
+            CaptureThread::spawn(name, move |buf| {
+                tee_to_terminal(read, is_stderr, truncate, buf)
+            })
+        }
+    }
+}
+
+/// 共有バッファのロックを取得する（poison 時もそのまま使う）。
+fn lock_buffer(buffer: &Mutex<Vec<u8>>) -> std::sync::MutexGuard<'_, Vec<u8>> {
+    buffer
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 停止パイプが閉じられたら EOF を返す reader。
+///
+/// ブロッキング中の `read` は別スレッドから fd を閉じても起きないため、読み取り元と停止パイプを
+/// `poll` で同時に監視する。孫プロセスが PTY / パイプを開いたままで EOF が届かない場合も、
+/// 停止側（[`new`](Self::new) が返す `PipeWriter`）を drop すればスレッドを終了させられる。
+pub(super) struct StoppableReader<R> {
+    inner: R,
+    stop: os_pipe::PipeReader,
+}
+
+impl<R: Read + AsFd> StoppableReader<R> {
+    /// `inner` を包み、読み取りを打ち切るための停止側と組で返す。
+    pub(super) fn new(inner: R) -> io::Result<(Self, os_pipe::PipeWriter)> {
+        let (stop, stop_write) = os_pipe::pipe()?;
+        Ok((Self { inner, stop }, stop_write))
+    }
+}
+
+impl<R: Read + AsFd> Read for StoppableReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let mut fds = [
+                PollFd::new(self.inner.as_fd(), PollFlags::POLLIN),
+                PollFd::new(self.stop.as_fd(), PollFlags::POLLIN),
+            ];
+            match poll(&mut fds, PollTimeout::NONE) {
+                Ok(_) => {}
+                Err(nix::errno::Errno::EINTR) => continue,
+                Err(e) => return Err(e.into()),
+            }
+            let [readable, stopped] = fds.map(|fd| fd.revents().is_some_and(|r| !r.is_empty()));
+            // 出力が続いていても停止要求を優先する
+            if stopped {
+                return Ok(0);
+            }
+            if readable {
+                return self.inner.read(buf);
+            }
+        }
+    }
+}
 
 // ── stdin 転送 ──
 
+/// ジョブ制御用に横取りするキー（Ctrl+Z）
+const SUSPEND_KEY: u8 = 0x1a;
+
+/// PTY の端末設定で Ctrl+Z が停止キーとして働くか（`ISIG` が有効で `VSUSP` が Ctrl+Z）。
+///
+/// `ssh`・入れ子のシェル・tmux・エディタ等は PTY を raw モードにして Ctrl+Z を自分で扱うため、
+/// その間は横取りせずにそのまま転送する。設定を読めなければ横取りしない。
+fn suspend_key_active(pty_master_fd: RawFd) -> bool {
+    // SAFETY: termios はゼロ初期化で有効な値になり、tcgetattr は fd とその書き込み先のみを扱う。
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(pty_master_fd, &mut termios) } != 0 {
+        return false;
+    }
+    termios.c_lflag & libc::ISIG != 0 && termios.c_cc[libc::VSUSP] == SUSPEND_KEY
+}
+
 /// 実 stdin → PTY master へのキーストローク転送。
 /// poll ベースで停止パイプとウィンドウサイズ変更を監視する。
+///
+/// Ctrl+Z は子プロセスへ送らず、セッション（`session_pid`）を一時停止させる
+/// （[`suspend_session`]）。停止後の処理はメインスレッドが行い、停止パイプで本スレッドを止める。
+/// 子が PTY を raw モードにしている間（[`suspend_key_active`] が `false`）は横取りしない。
 pub(super) fn forward_stdin(
     mut master_write: File,
     shutdown_read: os_pipe::PipeReader,
     pty_master_fd: RawFd,
+    session_pid: libc::pid_t,
 ) {
     let stdin_fd = io::stdin().as_raw_fd();
     let shutdown_fd = shutdown_read.as_raw_fd();
@@ -73,7 +267,17 @@ pub(super) fn forward_stdin(
                 if n <= 0 {
                     break;
                 }
-                let _ = master_write.write_all(&read_buf[..n as usize]);
+                let input = &read_buf[..n as usize];
+                if !suspend_key_active(pty_master_fd) {
+                    let _ = master_write.write_all(input);
+                    continue;
+                }
+                for (i, chunk) in input.split(|&b| b == SUSPEND_KEY).enumerate() {
+                    if i > 0 {
+                        suspend_session(pty_master_fd, session_pid);
+                    }
+                    let _ = master_write.write_all(chunk);
+                }
             }
             // stdin 側が EOF/HUP した場合も終了
             if revents.contains(PollFlags::POLLHUP) {
@@ -85,18 +289,29 @@ pub(super) fn forward_stdin(
 
 // ── 出力キャプチャ ──
 
-/// PTY master から読み取った出力の結果。
+/// PTY master から読み取った出力の結果（キャプチャしたバイト列は共有バッファ側）。
 #[derive(Default)]
 pub(super) struct CaptureResult {
-    pub bytes: Vec<u8>,
     pub used_alt_screen: bool,
+    /// 出力終了時点で Alternate Screen が有効なままか
+    /// （alt screen を使うコマンドが異常終了して復帰シーケンスを出さなかった場合に `true`）
+    pub alt_screen_active: bool,
 }
 
 /// PTY master から読み取った出力をターミナルに表示しつつキャプチャする。
 /// Alternate Screen の使用を検出し、使用された場合はキャプチャを停止する。
-pub(super) fn capture_pty_output(mut master: File) -> CaptureResult {
+///
+/// `alt_screen` には Alternate Screen が現在有効かを逐次書き込む
+/// （ジョブ停止時に画面を戻すかの判定に使う）。
+pub(super) fn capture_pty_output(
+    mut master: impl Read,
+    buf: &Mutex<Vec<u8>>,
+    alt_screen: &AtomicBool,
+) -> CaptureResult {
     let mut result = CaptureResult::default();
     let mut read_buf = [0u8; 4096];
+    // チャンク境界をまたぐ alt screen シーケンス検出用に、直前チャンクの末尾を保持する
+    let mut scan_buf: Vec<u8> = Vec::with_capacity(read_buf.len() + ALT_SCREEN_SEQ_CARRY);
 
     loop {
         match master.read(&mut read_buf) {
@@ -105,9 +320,15 @@ pub(super) fn capture_pty_output(mut master: File) -> CaptureResult {
                 let chunk = &read_buf[..n];
 
                 // Alternate Screen 検出
-                if !result.used_alt_screen && contains_alt_screen_seq(chunk) {
+                let carry_start = scan_buf.len().saturating_sub(ALT_SCREEN_SEQ_CARRY);
+                scan_buf.drain(..carry_start);
+                scan_buf.extend_from_slice(chunk);
+                if !result.used_alt_screen && contains_alt_screen_seq(&scan_buf) {
                     result.used_alt_screen = true;
                 }
+                result.alt_screen_active =
+                    alt_screen_active_after(&scan_buf, result.alt_screen_active);
+                alt_screen.store(result.alt_screen_active, Ordering::Relaxed);
 
                 // ターミナルに表示 (常に行う)
                 let mut out = io::stdout().lock();
@@ -116,7 +337,7 @@ pub(super) fn capture_pty_output(mut master: File) -> CaptureResult {
 
                 // キャプチャバッファに蓄積 (alt screen 未使用時のみ)
                 if !result.used_alt_screen {
-                    result.bytes.extend_from_slice(chunk);
+                    lock_buffer(buf).extend_from_slice(chunk);
                 }
             }
             Err(e) => {
@@ -136,8 +357,8 @@ pub(super) fn capture_pty_output(mut master: File) -> CaptureResult {
 
 /// 読み取りソースからデータを読み、ターミナルに表示しつつバッファに蓄積する（tee パターン）。
 /// レガシーモードおよびパイプライン用。
-pub(super) fn tee_to_terminal<R: Read>(read: R, is_stderr: bool) -> Vec<u8> {
-    let mut buf = Vec::new();
+/// `truncate` が true なら、表示する行だけをターミナル幅で切り詰める。
+fn tee_to_terminal<R: Read>(read: R, is_stderr: bool, truncate: bool, buf: &Mutex<Vec<u8>>) {
     let reader = io::BufReader::new(read);
 
     for line in reader.split(b'\n') {
@@ -145,18 +366,20 @@ pub(super) fn tee_to_terminal<R: Read>(read: R, is_stderr: bool) -> Vec<u8> {
             Ok(mut bytes) => {
                 // バッファには \n のみ保存（キャプチャ用）
                 bytes.push(b'\n');
-                buf.extend_from_slice(&bytes);
+                lock_buffer(buf).extend_from_slice(&bytes);
 
                 // ターミナル出力時は \r\n で行頭復帰させる
                 // （OPOST 無効の PTY から読み取るため \n → \r\n 変換が行われない）
+                // `[shell] wrap_output = "truncate"` なら表示だけ端で切り詰める
+                let display = output_wrap::for_display(&bytes[..bytes.len() - 1], truncate);
                 if is_stderr {
                     let mut err = io::stderr().lock();
-                    let _ = err.write_all(&bytes[..bytes.len() - 1]); // 内容（\n なし）
+                    let _ = err.write_all(&display); // 内容（\n なし）
                     let _ = err.write_all(b"\r\n"); // \r\n で終端
                     let _ = err.flush();
                 } else {
                     let mut out = io::stdout().lock();
-                    let _ = out.write_all(&bytes[..bytes.len() - 1]); // 内容（\n なし）
+                    let _ = out.write_all(&display); // 内容（\n なし）
                     let _ = out.write_all(b"\r\n"); // \r\n で終端
                     let _ = out.flush();
                 }
@@ -164,7 +387,6 @@ pub(super) fn tee_to_terminal<R: Read>(read: R, is_stderr: bool) -> Vec<u8> {
             Err(_) => break,
         }
     }
-    buf
 }
 
 /// stderr パイプからデータを読み取り、ターミナルに表示しつつバッファに蓄積する。
@@ -173,8 +395,7 @@ pub(super) fn tee_to_terminal<R: Read>(read: R, is_stderr: bool) -> Vec<u8> {
 /// raw mode では OPOST が無効のため `\n` → `\r\n` 自動変換が行われない。
 /// stderr は os_pipe 経由なので、ターミナル出力時に手動で変換する。
 /// キャプチャバッファには生データを保存する。
-pub(super) fn tee_stderr(read: os_pipe::PipeReader) -> Vec<u8> {
-    let mut buf = Vec::new();
+pub(super) fn tee_stderr(read: impl Read, buf: &Mutex<Vec<u8>>) {
     let mut reader = io::BufReader::new(read);
     let mut read_buf = [0u8; 4096];
 
@@ -183,7 +404,7 @@ pub(super) fn tee_stderr(read: os_pipe::PipeReader) -> Vec<u8> {
             Ok(0) => break,
             Ok(n) => {
                 let chunk = &read_buf[..n];
-                buf.extend_from_slice(chunk);
+                lock_buffer(buf).extend_from_slice(chunk);
 
                 // ターミナル出力時は bare \n → \r\n に変換
                 let converted = convert_lf_to_crlf(chunk);
@@ -194,7 +415,6 @@ pub(super) fn tee_stderr(read: os_pipe::PipeReader) -> Vec<u8> {
             Err(_) => break,
         }
     }
-    buf
 }
 
 /// bare `\n` を `\r\n` に変換する（ターミナル raw mode 出力用）。
@@ -211,3 +431,94 @@ fn convert_lf_to_crlf(input: &[u8]) -> Vec<u8> {
     }
     output
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_thread_returns_output_and_result() {
+        let thread = CaptureThread::spawn("test", |buf| {
+            lock_buffer(buf).extend_from_slice(b"hello\n");
+            42
+        });
+        let (bytes, result) = thread.join(Instant::now() + CAPTURE_JOIN_TIMEOUT);
+        assert_eq!(bytes, b"hello\n");
+        assert_eq!(result, Some(42));
+    }
+
+    #[test]
+    fn capture_thread_times_out_with_partial_output() {
+        let (_keep_open, blocked) = mpsc::channel::<()>();
+        let thread = CaptureThread::spawn("test", move |buf| {
+            lock_buffer(buf).extend_from_slice(b"partial");
+            // EOF が届かずに読み取りが終わらない状態を再現する
+            let _ = blocked.recv();
+        });
+        // 部分出力が書き込まれるまで待つ
+        while lock_buffer(&thread.buffer).is_empty() {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let started = Instant::now();
+        let (bytes, result) = thread.join(Instant::now() + Duration::from_millis(50));
+        assert_eq!(bytes, b"partial");
+        assert_eq!(result, None);
+        assert!(started.elapsed() < CAPTURE_JOIN_TIMEOUT);
+    }
+
+    #[test]
+    fn capture_thread_stops_reader_and_closes_fd_on_timeout() {
+        // 孫プロセスが書き込み側を開いたままにしている状態を再現する
+        let (read, mut write) = os_pipe::pipe().unwrap();
+        let (mut reader, stop) = StoppableReader::new(read).unwrap();
+        let thread = CaptureThread::spawn("test", move |buf| {
+            let mut chunk = [0u8; 64];
+            while let Ok(n @ 1..) = reader.read(&mut chunk) {
+                lock_buffer(buf).extend_from_slice(&chunk[..n]);
+            }
+        })
+        .with_stop(stop);
+        write.write_all(b"partial").unwrap();
+        while lock_buffer(&thread.buffer).is_empty() {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let (bytes, result) = thread.join(Instant::now() + Duration::from_millis(50));
+        assert_eq!(bytes, b"partial");
+        assert_eq!(result, Some(()));
+        // スレッドが読み取り側を閉じているため、書き込みは EPIPE になる
+        let err = write.write_all(b"more").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn suspend_key_follows_pty_terminal_mode() {
+        let pty = nix::pty::openpty(None, None).unwrap();
+        let master_fd = pty.master.as_raw_fd();
+        assert!(suspend_key_active(master_fd));
+
+        // raw モード（ssh・エディタ等）では Ctrl+Z を横取りしない
+        let mut termios = nix::sys::termios::tcgetattr(&pty.slave).unwrap();
+        nix::sys::termios::cfmakeraw(&mut termios);
+        nix::sys::termios::tcsetattr(&pty.slave, nix::sys::termios::SetArg::TCSANOW, &termios)
+            .unwrap();
+        assert!(!suspend_key_active(master_fd));
+    }
+
+    #[test]
+    fn tee_thread_stops_writing_after_timeout() {
+        let (read, mut write) = os_pipe::pipe().unwrap();
+        let thread = spawn_tee("test", read, true, false);
+        write.write_all(b"partial\n").unwrap();
+        while lock_buffer(&thread.buffer).is_empty() {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let (bytes, result) = thread.join(Instant::now() + Duration::from_millis(50));
+        assert_eq!(bytes, b"partial\n");
+        assert_eq!(result, Some(()));
+        let err = write.write_all(b"more\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
@@ -18,8 +18,28 @@
 //! AI ストリーム中断・プロンプト中の中断挙動を壊すおそれがある。
 //! そのため本実装では SIGINT 無視は採用せず、ジョブ制御（tcsetpgrp）のみで
 //! 「子コマンドだけが Ctrl+C で停止し jarvish は生存する」挙動を実現する。
+//!
+//! # パイプラインのプロセスグループへの SIGINT 転送
+//!
+//! パイプラインはジョブ制御の有無に関わらず独立したプロセスグループにまとめるため、
+//! 端末フォアグラウンドを委譲できない場合（ジョブ制御が無効な場合など）に jarvish 側へ
+//! 届いた Ctrl+C は子のプロセスグループには届かない。パイプラインの待機中は
+//! [`SigintForwarder`] が SIGINT を受けて `killpg(pgid, SIGINT)` でグループへ転送する。
+
+//!
+//! # PTY セッションの一時停止（Ctrl+Z）
+//!
+//! PTY セッション方式の子プロセスは `setsid` で別セッションになるため、そのプロセスグループは
+//! POSIX の「孤立したプロセスグループ」にあたり、端末由来の SIGTSTP の既定動作（停止）は
+//! カーネルに破棄される。そこで jarvish が Ctrl+Z を横取りし、まず SIGTSTP を送って
+//! ハンドラを持つプログラム（vim 等）に端末を片付ける猶予を与えてから、SIGSTOP で確実に停止させる
+//! （[`suspend_session`]）。停止の検出は `waitpid(WUNTRACED)`（[`wait_foreground`]）で行う。
 
 use std::io::{self, IsTerminal};
+use std::os::fd::RawFd;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
 
 use libc::pid_t;
 
@@ -157,6 +177,181 @@ fn with_sigttou_ignored<F: FnOnce()>(f: F) {
     }
 }
 
+/// [`SigintForwarder`] が SIGINT を転送するプロセスグループ（0 なら転送しない）
+static SIGINT_FORWARD_PGID: AtomicI32 = AtomicI32::new(0);
+
+/// 有効な [`SigintForwarder`] の数と、ハンドラを差し替える前の SIGINT の扱い
+static SIGINT_FORWARDERS: Mutex<(usize, Option<libc::sigaction>)> = Mutex::new((0, None));
+
+/// SIGINT ハンドラ。転送先のプロセスグループがあれば SIGINT を送る。
+extern "C" fn forward_sigint(_sig: libc::c_int) {
+    let pgid = SIGINT_FORWARD_PGID.load(Ordering::Relaxed);
+    if pgid > 0 {
+        // SAFETY: killpg は async-signal-safe で、整数引数のみを取る。
+        unsafe {
+            libc::killpg(pgid, libc::SIGINT);
+        }
+    }
+}
+
+/// jarvish に届いた SIGINT（Ctrl+C）を子のプロセスグループへ転送するガード。
+///
+/// 最初のガードの生成時に SIGINT ハンドラを差し替え、最後のガードの drop 時に元に戻す。
+/// 入れ子になった場合は内側（後に生成した方）のグループへ転送し、内側の drop 後は
+/// 外側のグループへの転送に戻る。
+pub(crate) struct SigintForwarder {
+    previous_pgid: pid_t,
+}
+
+impl SigintForwarder {
+    /// `pgid` への SIGINT 転送を開始する。
+    pub(crate) fn new(pgid: pid_t) -> Self {
+        let mut forwarders = lock_forwarders();
+        let previous_pgid = SIGINT_FORWARD_PGID.swap(pgid, Ordering::Relaxed);
+        if forwarders.0 == 0 {
+            // SAFETY: sigaction はシグナルハンドラの設定のみを行い、メモリ安全性に影響しない。
+            // forward_sigint は async-signal-safe な操作（atomic の読み取りと killpg）のみを行う。
+            forwarders.1 = Some(unsafe {
+                let mut old_action: libc::sigaction = std::mem::zeroed();
+                let mut new_action: libc::sigaction = std::mem::zeroed();
+                new_action.sa_sigaction = forward_sigint as extern "C" fn(libc::c_int) as usize;
+                new_action.sa_flags = libc::SA_RESTART;
+                libc::sigemptyset(&mut new_action.sa_mask);
+                libc::sigaction(libc::SIGINT, &new_action, &mut old_action);
+                old_action
+            });
+        }
+        forwarders.0 += 1;
+        Self { previous_pgid }
+    }
+}
+
+impl Drop for SigintForwarder {
+    fn drop(&mut self) {
+        let mut forwarders = lock_forwarders();
+        forwarders.0 = forwarders.0.saturating_sub(1);
+        if forwarders.0 > 0 {
+            SIGINT_FORWARD_PGID.store(self.previous_pgid, Ordering::Relaxed);
+            return;
+        }
+        SIGINT_FORWARD_PGID.store(0, Ordering::Relaxed);
+        if let Some(action) = forwarders.1.take() {
+            // SAFETY: 差し替え前に保存したハンドラを復元するだけで、メモリ安全性に影響しない。
+            unsafe {
+                libc::sigaction(libc::SIGINT, &action, std::ptr::null_mut());
+            }
+        }
+    }
+}
+
+fn lock_forwarders() -> std::sync::MutexGuard<'static, (usize, Option<libc::sigaction>)> {
+    SIGINT_FORWARDERS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Ctrl+Z で SIGTSTP を送ってから SIGSTOP を送るまでの猶予
+const SUSPEND_GRACE: Duration = Duration::from_millis(100);
+
+/// フォアグラウンドで待機している子プロセスの状態変化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChildStatus {
+    /// 終了した（シグナルによる終了は 1）
+    Exited(i32),
+    /// SIGSTOP / SIGTSTP で停止した
+    Stopped,
+}
+
+/// 子プロセスが終了するか停止するまで待つ（`waitpid(pid, WUNTRACED)`）。
+pub(crate) fn wait_foreground(pid: pid_t) -> io::Result<ChildStatus> {
+    loop {
+        let mut status = 0;
+        // SAFETY: 有効な status ポインタを渡すだけで、メモリ安全性に影響しない。
+        let ret = unsafe { libc::waitpid(pid, &mut status, libc::WUNTRACED) };
+        if ret == -1 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err);
+        }
+        if let Some(child_status) = decode_status(status) {
+            return Ok(child_status);
+        }
+    }
+}
+
+/// 子プロセスが終了または停止していれば、その状態を返す（`waitpid(pid, WUNTRACED | WNOHANG)`）。
+///
+/// 実行中なら `None`。タイムアウト付きの待機（ポーリング）に使用する。
+pub(crate) fn try_wait_foreground(pid: pid_t) -> io::Result<Option<ChildStatus>> {
+    loop {
+        let mut status = 0;
+        // SAFETY: 有効な status ポインタを渡すだけで、メモリ安全性に影響しない。
+        let ret = unsafe { libc::waitpid(pid, &mut status, libc::WUNTRACED | libc::WNOHANG) };
+        if ret == -1 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err);
+        }
+        if ret == 0 {
+            return Ok(None);
+        }
+        return Ok(decode_status(status));
+    }
+}
+
+/// `waitpid` の status を [`ChildStatus`] に変換する（終了・停止以外なら `None`）。
+fn decode_status(status: libc::c_int) -> Option<ChildStatus> {
+    if libc::WIFSTOPPED(status) {
+        Some(ChildStatus::Stopped)
+    } else if libc::WIFEXITED(status) {
+        Some(ChildStatus::Exited(libc::WEXITSTATUS(status)))
+    } else if libc::WIFSIGNALED(status) {
+        Some(ChildStatus::Exited(1))
+    } else {
+        None
+    }
+}
+
+/// 終了済みの子プロセスを回収する（`waitpid(pid, WNOHANG)`）。
+///
+/// 終了していれば `true`（停止中・実行中なら `false`）。既に回収済みの場合も `true`。
+pub(crate) fn reap_if_exited(pid: pid_t) -> bool {
+    let mut status = 0;
+    // SAFETY: 有効な status ポインタを渡すだけで、メモリ安全性に影響しない。
+    let ret = unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) };
+    ret == -1 || (ret == pid && (libc::WIFEXITED(status) || libc::WIFSIGNALED(status)))
+}
+
+/// PTY セッションのフォアグラウンドプロセスグループ（とセッションリーダーのグループ）に
+/// シグナルを送る。
+///
+/// フォアグラウンドグループは PTY master から取得し、取得できなければ
+/// セッションリーダー（`session_pid`、pgid = pid）のグループのみに送る。
+pub(crate) fn signal_session(pty_master_fd: RawFd, session_pid: pid_t, sig: libc::c_int) {
+    // SAFETY: tcgetpgrp / kill は整数引数のみを取り、メモリ安全性に影響しない。
+    unsafe {
+        let fg = libc::tcgetpgrp(pty_master_fd);
+        if fg > 0 && fg != session_pid {
+            libc::kill(-fg, sig);
+        }
+        libc::kill(-session_pid, sig);
+    }
+}
+
+/// Ctrl+Z を受けて PTY セッションを一時停止させる。
+///
+/// SIGTSTP で後片付け（alt screen の終了等）の猶予を与えた後、SIGSTOP で確実に停止させる
+/// （モジュールドキュメント参照）。停止の検出は呼び出し元の [`wait_foreground`] が行う。
+pub(crate) fn suspend_session(pty_master_fd: RawFd, session_pid: pid_t) {
+    signal_session(pty_master_fd, session_pid, libc::SIGTSTP);
+    std::thread::sleep(SUSPEND_GRACE);
+    signal_session(pty_master_fd, session_pid, libc::SIGSTOP);
+}
+
 /// 端末フォアグラウンドの委譲・回収を RAII で管理するガード。
 ///
 /// 生成時に指定プロセスグループへフォアグラウンドを委譲し、
@@ -213,6 +408,31 @@ mod tests {
         assert!(!should_enable_job_control(true, true));
     }
 
+    #[test]
+    fn sigint_forwarder_sends_sigint_to_the_group() {
+        use std::os::unix::process::CommandExt;
+        use std::os::unix::process::ExitStatusExt;
+
+        let mut command = std::process::Command::new("sleep");
+        command.arg("30");
+        unsafe {
+            command.pre_exec(|| pre_exec_setpgid(0));
+        }
+        let mut child = command.spawn().unwrap();
+        let pgid = child.id() as pid_t;
+        // SAFETY: 子側の pre_exec と同じ setpgid を親側でも行い、レースを避ける。
+        unsafe {
+            libc::setpgid(pgid, pgid);
+        }
+
+        let forwarder = SigintForwarder::new(pgid);
+        // Ctrl+C を受けたときと同じくハンドラを呼ぶ（テストプロセスに SIGINT は送らない）
+        forward_sigint(libc::SIGINT);
+        let status = child.wait().unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGINT));
+        drop(forwarder);
+    }
+
     // ── pipeline_pgid 純粋関数 ──
 
     #[test]
@@ -1,16 +1,23 @@
+pub mod bookmarks;
 pub mod builtins;
 pub mod classifier;
+mod context;
 pub mod dispatch;
+pub mod dry_run;
 pub mod exec;
 pub mod expand;
 mod io;
 mod job_control;
+pub mod output_wrap;
 pub mod parser;
+pub mod persist_env;
 mod pty;
 mod redirect;
+pub mod shell_vars;
 mod terminal;
 pub mod typo;
 
+pub use context::ExecContext;
 pub use dispatch::{execute, try_builtin, try_execute_ai_pipe};
 
 /// REPL ループの制御アクション
@@ -41,6 +48,9 @@ pub struct CommandResult {
     /// true の場合、stdout は TUI の画面制御シーケンスであり、
     /// Black Box への保存をスキップすべきことを示す。
     pub used_alt_screen: bool,
+    /// 出力がバイナリ（有効な UTF-8 でない）だったかどうか。
+    /// true の場合、出力は Black Box に保存するが AI コンテキストには含めない。
+    pub binary_output: bool,
 }
 
 impl CommandResult {
@@ -52,6 +62,7 @@ impl CommandResult {
             exit_code: 0,
             action: LoopAction::Continue,
             used_alt_screen: false,
+            binary_output: false,
         }
     }
 
@@ -63,6 +74,7 @@ impl CommandResult {
             exit_code,
             action: LoopAction::Continue,
             used_alt_screen: false,
+            binary_output: false,
         }
     }
 
@@ -74,6 +86,7 @@ impl CommandResult {
             exit_code,
             action: LoopAction::Exit,
             used_alt_screen: false,
+            binary_output: false,
         }
     }
 
@@ -85,6 +98,7 @@ impl CommandResult {
             exit_code: 0,
             action: LoopAction::Restart,
             used_alt_screen: false,
+            binary_output: false,
         }
     }
 }
@@ -0,0 +1,236 @@
+//! コマンド出力の表示幅調整
+//!
+//! `[shell] wrap_output = "truncate"` のとき、pipe + tee 経路（リダイレクト・パイプライン・
+//! PTY 取得失敗時のフォールバック）でターミナルに表示する長い行をターミナル幅で切り詰める。
+//! PTY セッションでは子プロセスが winsize を知っているため対象外。
+//! キャプチャバッファには常に元の全文を保持し、加工するのは表示だけである。
+
+use std::borrow::Cow;
+
+use super::pty::query_terminal_winsize;
+
+/// タブ文字の桁揃え幅
+const TAB_WIDTH: usize = 8;
+
+/// 切り詰めた行の末尾に付ける印
+const ELLIPSIS: &str = "…";
+
+/// 表示用に 1 行（改行を含まない）を加工する。
+///
+/// `truncate` が false、またはターミナル幅が取得できない場合はそのまま返す。
+pub(super) fn for_display(line: &[u8], truncate: bool) -> Cow<'_, [u8]> {
+    if !truncate {
+        return Cow::Borrowed(line);
+    }
+    match terminal_columns() {
+        Some(columns) => truncate_line(line, columns),
+        None => Cow::Borrowed(line),
+    }
+}
+
+/// stdout が接続されたターミナルの桁数を返す。
+pub fn terminal_columns() -> Option<usize> {
+    query_terminal_winsize().map(|ws| ws.ws_col as usize)
+}
+
+/// 表示幅が `columns` を超える行を切り詰め、末尾を `…` にする。
+///
+/// ANSI エスケープシーケンス（CSI / OSC）は幅 0 として数え、切り詰めた後ろの部分に
+/// あるものも出力に残す（色のリセット等を失わないため）。
+fn truncate_line(line: &[u8], columns: usize) -> Cow<'_, [u8]> {
+    // 1 桁に 1 バイト以上を要するため、タブがなければバイト数以下の幅に必ず収まる
+    if columns == 0 || (line.len() <= columns && !line.contains(&b'\t')) {
+        return Cow::Borrowed(line);
+    }
+
+    let text = String::from_utf8_lossy(line);
+    let limit = columns - 1; // 末尾の `…` の 1 桁を確保する
+    let mut out = String::with_capacity(text.len());
+    let mut width = 0;
+    let mut truncated = false;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c == '\x1b' {
+            let end = escape_sequence_end(&text, start);
+            out.push_str(&text[start..end]);
+            while chars.peek().is_some_and(|&(i, _)| i < end) {
+                chars.next();
+            }
+            continue;
+        }
+        if truncated {
+            continue;
+        }
+
+        let w = if c == '\t' {
+            TAB_WIDTH - width % TAB_WIDTH
+        } else {
+            char_width(c)
+        };
+        if width + w > limit && !fits_rest(&text[start..], columns - width) {
+            out.push_str(ELLIPSIS);
+            truncated = true;
+            continue;
+        }
+        width += w;
+        out.push(c);
+    }
+
+    if truncated {
+        Cow::Owned(out.into_bytes())
+    } else {
+        Cow::Borrowed(line)
+    }
+}
+
+/// 文字列の表示幅（桁数）を返す。ANSI エスケープシーケンスは幅 0 として数える。
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c == '\x1b' {
+            let end = escape_sequence_end(text, start);
+            while chars.peek().is_some_and(|&(i, _)| i < end) {
+                chars.next();
+            }
+            continue;
+        }
+        width += char_width(c);
+    }
+    width
+}
+
+/// 残りの文字列が `remaining` 桁にちょうど収まるか（最後の 1 桁を `…` にせずに済むか）。
+fn fits_rest(rest: &str, remaining: usize) -> bool {
+    let mut width = 0;
+    let mut chars = rest.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c == '\x1b' {
+            let end = escape_sequence_end(rest, start);
+            while chars.peek().is_some_and(|&(i, _)| i < end) {
+                chars.next();
+            }
+            continue;
+        }
+        // タブは位置に依存するため、安全側に倒して収まらないものとみなす
+        if c == '\t' {
+            return false;
+        }
+        width += char_width(c);
+        if width > remaining {
+            return false;
+        }
+    }
+    true
+}
+
+/// `start` の ESC から始まるエスケープシーケンスの終端（次の文字のバイト位置）を返す。
+fn escape_sequence_end(text: &str, start: usize) -> usize {
+    let bytes = text.as_bytes();
+    match bytes.get(start + 1) {
+        // CSI: ESC [ ... 終端バイト (0x40..=0x7E)
+        Some(b'[') => bytes[start + 2..]
+            .iter()
+            .position(|b| (0x40..=0x7e).contains(b))
+            .map_or(bytes.len(), |p| start + 2 + p + 1),
+        // OSC: ESC ] ... BEL または ESC \
+        Some(b']') => {
+            let body = &bytes[start + 2..];
+            match body
+                .iter()
+                .enumerate()
+                .find(|&(i, &b)| b == 0x07 || (b == 0x1b && body.get(i + 1) == Some(&b'\\')))
+            {
+                Some((i, 0x07)) => start + 2 + i + 1,
+                Some((i, _)) => start + 2 + i + 2,
+                None => bytes.len(),
+            }
+        }
+        // その他の 2 バイトシーケンス（ESC = 等）
+        Some(&b) if b.is_ascii() => start + 2,
+        _ => start + 1,
+    }
+}
+
+/// 文字の表示幅（制御文字は 0、東アジアの全角文字・絵文字は 2）。
+fn char_width(c: char) -> usize {
+    let cp = c as u32;
+    if c.is_control() || (0x300..=0x36f).contains(&cp) || cp == 0x200b || cp == 0xfe0e {
+        return 0;
+    }
+    let wide = matches!(
+        cp,
+        0x1100..=0x115f
+            | 0x2e80..=0x303e
+            | 0x3041..=0x33ff
+            | 0x3400..=0x4dbf
+            | 0x4e00..=0x9fff
+            | 0xa000..=0xa4cf
+            | 0xac00..=0xd7a3
+            | 0xf900..=0xfaff
+            | 0xfe30..=0xfe4f
+            | 0xff00..=0xff60
+            | 0xffe0..=0xffe6
+            | 0x1f300..=0x1f64f
+            | 0x1f900..=0x1f9ff
+            | 0x20000..=0x3fffd
+    );
+    if wide {
+        2
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn truncate(line: &str, columns: usize) -> String {
+        String::from_utf8(truncate_line(line.as_bytes(), columns).into_owned()).unwrap()
+    }
+
+    #[test]
+    fn short_line_is_unchanged() {
+        assert!(matches!(truncate_line(b"hello", 10), Cow::Borrowed(_)));
+        assert_eq!(truncate("0123456789", 10), "0123456789");
+    }
+
+    #[test]
+    fn long_line_is_cut_with_ellipsis() {
+        assert_eq!(truncate("0123456789abc", 10), "012345678…");
+    }
+
+    #[test]
+    fn escape_sequences_are_zero_width_and_kept() {
+        let line = "\x1b[31m0123456789abc\x1b[0m";
+        assert_eq!(truncate(line, 10), "\x1b[31m012345678…\x1b[0m");
+        // 色付きでも幅に収まっていれば加工しない
+        assert_eq!(truncate("\x1b[1mabc\x1b[0m", 5), "\x1b[1mabc\x1b[0m");
+    }
+
+    #[test]
+    fn wide_characters_count_as_two_columns() {
+        assert_eq!(truncate("日本語のテキスト", 8), "日本語…");
+        assert_eq!(truncate("日本語", 6), "日本語");
+    }
+
+    #[test]
+    fn display_width_ignores_escapes_and_counts_wide_chars() {
+        assert_eq!(display_width("\x1b[33m~/dev\x1b[0m"), 5);
+        assert_eq!(display_width("日本語 ok"), 9);
+        assert_eq!(display_width(""), 0);
+    }
+
+    #[test]
+    fn tabs_expand_to_tab_stops() {
+        assert_eq!(truncate("a\tb\tc", 12), "a\tb…");
+    }
+
+    #[test]
+    fn unchanged_when_disabled() {
+        let long = "x".repeat(1000);
+        assert_eq!(for_display(long.as_bytes(), false).len(), 1000);
+    }
+}
@@ -1,7 +1,8 @@
 //! シェル構文パーサー
 //!
-//! `shell_words::split()` で得たトークン列を、パイプライン（`|`）と
+//! `split_quoted()` で得たトークン列を、パイプライン（`|`）と
 //! リダイレクト（`>`, `>>`, `<`）を含む構造化された `Pipeline` に変換する。
+//! 各トークンは [`Word`] として渡し、クォート由来の語（`">"` 等）は演算子として扱わない。
 
 mod types;
 
@@ -9,81 +10,88 @@ pub use types::*;
 
 /// トークン列をコマンドリストにパースする。
 ///
-/// `shell_words::split()` で分割済みのトークンを受け取り、
+/// 分割済みのトークンを受け取り、
 /// `&&`, `||`, `;` で分割した後、各セグメントを `parse_pipeline()` でパースする。
-pub fn parse_command_list(tokens: Vec<String>) -> Result<CommandList, ParseError> {
+/// 演算子だけの入力（`|`, `;` 等）や演算子の前後にコマンドがない入力は
+/// `syntax error near ...` のエラーを返す。
+pub fn parse_command_list(tokens: Vec<Word>) -> Result<CommandList, ParseError> {
     if tokens.is_empty() {
         return Err(ParseError("empty command".to_string()));
     }
 
     let (segments, connectors) = split_by_connector(&tokens)?;
 
-    let first = parse_pipeline(segments[0].clone())?;
+    // split_by_connector は segments.len() == connectors.len() + 1 を保証する
+    let mut segments = segments.into_iter();
+    let first = parse_pipeline(
+        segments
+            .next()
+            .ok_or_else(|| ParseError("empty command".to_string()))?,
+    )?;
     let mut rest = Vec::new();
-    for (i, conn) in connectors.into_iter().enumerate() {
-        let pipeline = parse_pipeline(segments[i + 1].clone())?;
-        rest.push((conn, pipeline));
+    for (conn, segment) in connectors.into_iter().zip(segments) {
+        rest.push((conn, parse_pipeline(segment)?));
     }
 
     Ok(CommandList { first, rest })
 }
 
 /// トークン列を `&&`, `||`, `;` で分割する。
-fn split_by_connector(tokens: &[String]) -> Result<(Vec<Vec<String>>, Vec<Connector>), ParseError> {
-    let mut segments: Vec<Vec<String>> = Vec::new();
+fn split_by_connector(tokens: &[Word]) -> Result<(Vec<Vec<Word>>, Vec<Connector>), ParseError> {
+    let mut segments: Vec<Vec<Word>> = Vec::new();
     let mut connectors: Vec<Connector> = Vec::new();
-    let mut current: Vec<String> = Vec::new();
+    let mut current: Vec<Word> = Vec::new();
+    let mut last_connector = "";
 
     for token in tokens {
-        match token.as_str() {
-            "&&" => {
-                if current.is_empty() {
-                    return Err(ParseError(
-                        "syntax error: unexpected token '&&'".to_string(),
-                    ));
-                }
-                segments.push(std::mem::take(&mut current));
-                connectors.push(Connector::And);
-            }
-            "||" => {
-                if current.is_empty() {
-                    return Err(ParseError(
-                        "syntax error: unexpected token '||'".to_string(),
-                    ));
-                }
-                segments.push(std::mem::take(&mut current));
-                connectors.push(Connector::Or);
-            }
-            ";" => {
-                if current.is_empty() {
-                    return Err(ParseError("syntax error: unexpected token ';'".to_string()));
-                }
-                segments.push(std::mem::take(&mut current));
-                connectors.push(Connector::Semi);
-            }
+        let connector = match token.value.as_str() {
+            "&&" if !token.literal => Connector::And,
+            "||" if !token.literal => Connector::Or,
+            ";" if !token.literal => Connector::Semi,
             _ => {
                 current.push(token.clone());
+                continue;
             }
+        };
+        // 直前のセグメントが空、またはパイプで終わっている（`; ls` / `ls | && pwd`）
+        if current.last().map_or(true, |t| t.is_operator("|")) {
+            return Err(unexpected_token(&token.value));
         }
+        segments.push(std::mem::take(&mut current));
+        connectors.push(connector);
+        last_connector = &token.value;
     }
 
-    if current.is_empty() && !connectors.is_empty() {
-        return Err(ParseError(
-            "syntax error: unexpected end of command after connector".to_string(),
-        ));
-    }
-    if !current.is_empty() {
-        segments.push(current);
+    if current.is_empty() {
+        return Err(unexpected_end(last_connector));
     }
+    segments.push(current);
 
     Ok((segments, connectors))
 }
 
+/// 予期しない演算子トークンのエラー（bash の `syntax error near unexpected token` 相当）。
+fn unexpected_token(token: &str) -> ParseError {
+    ParseError(format!("syntax error near unexpected token '{token}'"))
+}
+
+/// 演算子の後ろでコマンドが終わってしまった場合のエラー。
+fn unexpected_end(operator: &str) -> ParseError {
+    ParseError(format!(
+        "syntax error near '{operator}': unexpected end of command"
+    ))
+}
+
+/// リダイレクト演算子の後ろにファイル名がない場合のエラー。
+fn missing_filename(operator: &str) -> ParseError {
+    ParseError(format!("syntax error near '{operator}': expected filename"))
+}
+
 /// トークン列をパイプラインにパースする。
 ///
-/// `shell_words::split()` で分割済みのトークンを受け取り、
+/// 分割済みのトークンを受け取り、
 /// `|` でパイプライン分割し、各セグメントからリダイレクト演算子を抽出する。
-pub fn parse_pipeline(tokens: Vec<String>) -> Result<Pipeline, ParseError> {
+pub fn parse_pipeline(tokens: Vec<Word>) -> Result<Pipeline, ParseError> {
     if tokens.is_empty() {
         return Err(ParseError("empty command".to_string()));
     }
@@ -100,14 +108,14 @@ pub fn parse_pipeline(tokens: Vec<String>) -> Result<Pipeline, ParseError> {
 }
 
 /// トークン列を `|` で分割し、各セグメントを返す。
-fn split_by_pipe(tokens: &[String]) -> Result<Vec<&[String]>, ParseError> {
-    let mut segments: Vec<&[String]> = Vec::new();
+fn split_by_pipe(tokens: &[Word]) -> Result<Vec<&[Word]>, ParseError> {
+    let mut segments: Vec<&[Word]> = Vec::new();
     let mut start = 0;
 
     for (i, token) in tokens.iter().enumerate() {
-        if token == "|" {
+        if token.is_operator("|") {
             if i == start {
-                return Err(ParseError("syntax error: unexpected token '|'".to_string()));
+                return Err(unexpected_token(&token.value));
             }
             segments.push(&tokens[start..i]);
             start = i + 1;
@@ -115,9 +123,7 @@ fn split_by_pipe(tokens: &[String]) -> Result<Vec<&[String]>, ParseError> {
     }
 
     if start >= tokens.len() {
-        return Err(ParseError(
-            "syntax error: unexpected end of command after '|'".to_string(),
-        ));
+        return Err(unexpected_end("|"));
     }
     segments.push(&tokens[start..]);
 
@@ -125,33 +131,44 @@ fn split_by_pipe(tokens: &[String]) -> Result<Vec<&[String]>, ParseError> {
 }
 
 /// トークンのスライスからリダイレクトを抽出し、SimpleCommand を構築する。
-fn parse_simple_command(tokens: &[String]) -> Result<SimpleCommand, ParseError> {
+fn parse_simple_command(tokens: &[Word]) -> Result<SimpleCommand, ParseError> {
     let mut args: Vec<String> = Vec::new();
     let mut redirects: Vec<Redirect> = Vec::new();
-    let mut iter = tokens.iter().peekable();
+    let mut iter = tokens.iter();
 
     while let Some(token) = iter.next() {
-        match token.as_str() {
+        // クォート由来の語は演算子と同じ文字列でも通常の引数
+        let operator = if token.literal {
+            ""
+        } else {
+            token.value.as_str()
+        };
+        match operator {
             ">>" => {
-                let target = iter.next().ok_or_else(|| {
-                    ParseError("syntax error: expected filename after '>>'".to_string())
-                })?;
-                redirects.push(Redirect::StdoutAppend(target.clone()));
+                let target = iter.next().ok_or_else(|| missing_filename(">>"))?;
+                redirects.push(Redirect::StdoutAppend(target.value.clone()));
             }
             ">" => {
-                let target = iter.next().ok_or_else(|| {
-                    ParseError("syntax error: expected filename after '>'".to_string())
-                })?;
-                redirects.push(Redirect::StdoutOverwrite(target.clone()));
+                let target = iter.next().ok_or_else(|| missing_filename(">"))?;
+                redirects.push(Redirect::StdoutOverwrite(target.value.clone()));
             }
             "<" => {
-                let target = iter.next().ok_or_else(|| {
-                    ParseError("syntax error: expected filename after '<'".to_string())
-                })?;
-                redirects.push(Redirect::StdinFrom(target.clone()));
+                let target = iter.next().ok_or_else(|| missing_filename("<"))?;
+                redirects.push(Redirect::StdinFrom(target.value.clone()));
+            }
+            "&>>" => {
+                let target = iter.next().ok_or_else(|| missing_filename("&>>"))?;
+                redirects.push(Redirect::BothAppend(target.value.clone()));
+            }
+            "&>" => {
+                let target = iter.next().ok_or_else(|| missing_filename("&>"))?;
+                redirects.push(Redirect::BothToFile(target.value.clone()));
+            }
+            ">&2" => {
+                redirects.push(Redirect::StdoutToStderr);
             }
             _ => {
-                args.push(token.clone());
+                args.push(token.value.clone());
             }
         }
     }
@@ -258,6 +275,77 @@ mod tests {
         );
     }
 
+    #[test]
+    fn multiple_redirects_keep_written_order() {
+        let tokens = vec![
+            "sort".into(),
+            "<".into(),
+            "in.txt".into(),
+            ">".into(),
+            "a.txt".into(),
+            ">>".into(),
+            "b.txt".into(),
+        ];
+        let pipeline = parse_pipeline(tokens).unwrap();
+        assert_eq!(
+            pipeline.commands[0].redirects,
+            vec![
+                Redirect::StdinFrom("in.txt".into()),
+                Redirect::StdoutOverwrite("a.txt".into()),
+                Redirect::StdoutAppend("b.txt".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn fd_redirects() {
+        let tokens = vec![
+            "make".into(),
+            "&>".into(),
+            "build.log".into(),
+            ">&2".into(),
+            "&>>".into(),
+            "all.log".into(),
+        ];
+        let pipeline = parse_pipeline(tokens).unwrap();
+        assert_eq!(pipeline.commands[0].args, Vec::<String>::new());
+        assert_eq!(
+            pipeline.commands[0].redirects,
+            vec![
+                Redirect::BothToFile("build.log".into()),
+                Redirect::StdoutToStderr,
+                Redirect::BothAppend("all.log".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn literal_words_are_not_operators() {
+        let tokens = vec![
+            "echo".into(),
+            Word::literal(">"),
+            Word::literal("|"),
+            Word::literal("&&"),
+            ">".into(),
+            "out.txt".into(),
+        ];
+        let list = parse_command_list(tokens).unwrap();
+        assert!(list.rest.is_empty());
+        let command = &list.first.commands[0];
+        assert_eq!(command.args, vec![">", "|", "&&"]);
+        assert_eq!(
+            command.redirects,
+            vec![Redirect::StdoutOverwrite("out.txt".into())]
+        );
+    }
+
+    #[test]
+    fn both_redirect_without_target_returns_error() {
+        let tokens = vec!["make".into(), "&>".into()];
+        let err = parse_pipeline(tokens).unwrap_err();
+        assert!(err.0.contains("'&>'"));
+    }
+
     #[test]
     fn pipe_with_redirect() {
         let tokens = vec![
@@ -457,6 +545,75 @@ mod tests {
         assert!(result.is_err());
     }
 
+    fn parse_error(tokens: &[&str]) -> String {
+        let tokens = tokens.iter().map(|t| Word::from(*t)).collect();
+        parse_command_list(tokens).unwrap_err().0
+    }
+
+    #[test]
+    fn operator_only_input_reports_unexpected_token() {
+        assert_eq!(
+            parse_error(&["|"]),
+            "syntax error near unexpected token '|'"
+        );
+        assert_eq!(
+            parse_error(&[";"]),
+            "syntax error near unexpected token ';'"
+        );
+        assert_eq!(
+            parse_error(&["&&"]),
+            "syntax error near unexpected token '&&'"
+        );
+        assert_eq!(
+            parse_error(&[";", ";"]),
+            "syntax error near unexpected token ';'"
+        );
+        assert_eq!(
+            parse_error(&["|", "|"]),
+            "syntax error near unexpected token '|'"
+        );
+    }
+
+    #[test]
+    fn connector_after_pipe_reports_connector() {
+        assert_eq!(
+            parse_error(&["ls", "|", "&&", "pwd"]),
+            "syntax error near unexpected token '&&'"
+        );
+        assert_eq!(
+            parse_error(&["ls", ";", ";", "pwd"]),
+            "syntax error near unexpected token ';'"
+        );
+    }
+
+    #[test]
+    fn trailing_operator_reports_unexpected_end() {
+        assert_eq!(
+            parse_error(&["ls", "|"]),
+            "syntax error near '|': unexpected end of command"
+        );
+        assert_eq!(
+            parse_error(&["ls", "||"]),
+            "syntax error near '||': unexpected end of command"
+        );
+        assert_eq!(
+            parse_error(&["ls", "&&", "pwd", "|"]),
+            "syntax error near '|': unexpected end of command"
+        );
+    }
+
+    #[test]
+    fn redirect_without_target_reports_operator() {
+        assert_eq!(
+            parse_error(&["ls", ">"]),
+            "syntax error near '>': expected filename"
+        );
+        assert_eq!(
+            parse_error(&["ls", ">", ";", "pwd"]),
+            "syntax error near '>': expected filename"
+        );
+    }
+
     #[test]
     fn extract_ai_filter_single_command_pipe_ai() {
         let tokens = vec![
@@ -1,5 +1,47 @@
 //! AST 型定義 — パイプライン構造の構造化表現
 
+/// パーサーに渡す 1 語
+///
+/// クォートや展開に由来する語は `literal = true` とし、値が `|` や `>` と一致しても
+/// 演算子として扱わない（`echo ">"` は `>` を出力する）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    pub value: String,
+    /// 演算子として解釈しない語なら true
+    pub literal: bool,
+}
+
+impl Word {
+    /// 演算子として解釈しない語を作成する。
+    pub fn literal(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            literal: true,
+        }
+    }
+
+    /// この語が演算子 `op` かどうか。
+    pub fn is_operator(&self, op: &str) -> bool {
+        !self.literal && self.value == op
+    }
+}
+
+/// クォートされていない語（演算子として解釈されうる）。
+impl From<String> for Word {
+    fn from(value: String) -> Self {
+        Self {
+            value,
+            literal: false,
+        }
+    }
+}
+
+impl From<&str> for Word {
+    fn from(value: &str) -> Self {
+        Self::from(value.to_string())
+    }
+}
+
 /// I/O リダイレクト
 #[derive(Debug, Clone, PartialEq)]
 pub enum Redirect {
@@ -9,6 +51,12 @@ pub enum Redirect {
     StdoutAppend(String),
     /// `< file` — stdin をファイルから読み込み
     StdinFrom(String),
+    /// `&> file` — stdout と stderr の両方を上書き
+    BothToFile(String),
+    /// `&>> file` — stdout と stderr の両方を追記
+    BothAppend(String),
+    /// `>&2` — stdout を stderr に複製
+    StdoutToStderr,
 }
 
 /// パイプラインの 1 セグメント（単一コマンド）
@@ -25,6 +73,7 @@ pub struct SimpleCommand {
 /// パイプ（`|`）で接続された一連のコマンド
 #[derive(Debug, Clone, PartialEq)]
 pub struct Pipeline {
+    /// パイプで接続されたコマンド（`parse_pipeline()` の結果では常に 1 つ以上）
     pub commands: Vec<SimpleCommand>,
 }
 
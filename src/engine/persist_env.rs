@@ -0,0 +1,175 @@
+//! 環境変数の永続化（`[env] persist = true`）
+//!
+//! `export --persist KEY=VALUE` で指定した環境変数を `~/.config/jarvish/env.toml` に保存し、
+//! 次回起動時に読み込んで設定する。セッション中の `export` を全て保存するのではなく、
+//! `--persist` を付けたものだけを対象にする。`unset` した変数は保存ファイルからも削除する。
+//!
+//! 機密情報（API キー等）を含む可能性があるため、ファイルのパーミッションは 0600 にする。
+//!
+//! ファイル形式:
+//!
+//! ```toml
+//! EDITOR = "nvim"
+//! GOPATH = "/home/user/go"
+//! ```
+//!
+//! フラグはプロセス全体で共有する（ビルトインのフリー関数から参照するため）。
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::{debug, info, warn};
+
+/// 永続化が有効かどうか
+static PERSIST: AtomicBool = AtomicBool::new(false);
+
+/// 保存ファイルのパーミッション（所有者のみ読み書き可）
+const ENV_FILE_MODE: u32 = 0o600;
+
+/// 永続化の有効/無効を切り替える。
+pub fn set_enabled(enabled: bool) {
+    PERSIST.store(enabled, Ordering::Relaxed);
+}
+
+/// 永続化が有効かどうかを返す。
+pub fn is_enabled() -> bool {
+    PERSIST.load(Ordering::Relaxed)
+}
+
+/// 保存ファイルのデフォルトパスを返す。
+///
+/// `JarvishConfig::config_path()` と同じく `$HOME/.config/jarvish/` 配下に置く。
+pub fn default_path() -> PathBuf {
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join(".config/jarvish/env.toml")
+}
+
+/// 保存ファイルを読み込む。存在しない場合は空のマップを返す。
+pub(crate) fn load_from(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    toml::from_str(&content).map_err(|e| format!("failed to parse {}: {e}", path.display()))
+}
+
+/// 保存ファイルを書き出す（親ディレクトリがなければ作成し、パーミッションを 0600 にする）。
+fn save_to(path: &Path, vars: &BTreeMap<String, String>) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("failed to create {}: {e}", parent.display()))?;
+    }
+    let content = toml::to_string(vars)
+        .map_err(|e| format!("failed to serialize persisted environment: {e}"))?;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(ENV_FILE_MODE)
+        .open(path)
+        .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+    // 既存ファイルは mode() が適用されないため明示的に絞る
+    file.set_permissions(std::fs::Permissions::from_mode(ENV_FILE_MODE))
+        .map_err(|e| format!("failed to set permissions on {}: {e}", path.display()))?;
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("failed to write {}: {e}", path.display()))
+}
+
+/// 変数を保存ファイルに追加（既存の値は上書き）する。
+pub(crate) fn persist_var(path: &Path, key: &str, value: &str) -> Result<(), String> {
+    let mut vars = load_from(path)?;
+    vars.insert(key.to_string(), value.to_string());
+    save_to(path, &vars)?;
+    debug!(path = %path.display(), key = %key, "Environment variable persisted");
+    Ok(())
+}
+
+/// 保存ファイルから変数を削除する。保存されていない変数は無視する。
+pub(crate) fn forget_vars(path: &Path, names: &[String]) -> Result<(), String> {
+    let mut vars = load_from(path)?;
+    let before = vars.len();
+    vars.retain(|key, _| !names.contains(key));
+    if vars.len() == before {
+        return Ok(());
+    }
+    save_to(path, &vars)?;
+    debug!(path = %path.display(), removed = before - vars.len(), "Persisted environment variables removed");
+    Ok(())
+}
+
+/// 保存された環境変数をプロセスに設定する（起動時に呼ぶ）。
+///
+/// 読み込みに失敗した場合は警告を表示して何もしない。
+pub fn apply_saved(path: &Path) {
+    let vars = match load_from(path) {
+        Ok(vars) => vars,
+        Err(e) => {
+            warn!(error = %e, "Failed to load persisted environment");
+            eprintln!("jarvish: warning: {e}");
+            return;
+        }
+    };
+    for (key, value) in &vars {
+        // SAFETY: シェル起動時のシングルスレッド初期化で呼ばれるため安全
+        unsafe {
+            std::env::set_var(key, value);
+        }
+    }
+    info!(path = %path.display(), count = vars.len(), "Persisted environment applied");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persist_and_forget_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("nested/env.toml");
+
+        persist_var(&path, "EDITOR", "nvim").unwrap();
+        persist_var(&path, "GOPATH", "/home/user/go").unwrap();
+        persist_var(&path, "EDITOR", "vim").unwrap();
+
+        let vars = load_from(&path).unwrap();
+        assert_eq!(vars.get("EDITOR").map(String::as_str), Some("vim"));
+        assert_eq!(vars.len(), 2);
+
+        forget_vars(&path, &["EDITOR".to_string(), "NOT_SAVED".to_string()]).unwrap();
+        let vars = load_from(&path).unwrap();
+        assert!(!vars.contains_key("EDITOR"));
+        assert!(vars.contains_key("GOPATH"));
+    }
+
+    #[test]
+    fn saved_file_is_owner_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("env.toml");
+        std::fs::write(&path, "").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        persist_var(&path, "API_TOKEN", "secret").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, ENV_FILE_MODE);
+    }
+
+    #[test]
+    fn missing_file_loads_empty() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(load_from(&tmp.path().join("env.toml")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn forget_without_file_does_not_create_it() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("env.toml");
+        forget_vars(&path, &["FOO".to_string()]).unwrap();
+        assert!(!path.exists());
+    }
+}
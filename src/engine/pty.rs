@@ -6,7 +6,6 @@
 use std::fs::File;
 use std::io;
 use std::os::fd::{AsFd, OwnedFd};
-use std::process::Stdio;
 
 use nix::pty::openpty;
 use nix::sys::termios::{self, OutputFlags, SetArg};
@@ -15,7 +14,13 @@ use tracing::debug;
 // ── Alternate Screen 検出 ──
 
 /// Alternate Screen Buffer 有効化シーケンス: ESC [ ? 1 0 4 9 h
-const ALT_SCREEN_ENABLE: &[u8] = b"\x1b[?1049h";
+pub(super) const ALT_SCREEN_ENABLE: &[u8] = b"\x1b[?1049h";
+
+/// Alternate Screen Buffer 無効化シーケンス: ESC [ ? 1 0 4 9 l
+const ALT_SCREEN_DISABLE: &[u8] = b"\x1b[?1049l";
+
+/// チャンク境界をまたぐシーケンスを検出するために、次のチャンクへ持ち越すバイト数
+pub(super) const ALT_SCREEN_SEQ_CARRY: usize = ALT_SCREEN_ENABLE.len() - 1;
 
 /// バイトスライス内に Alternate Screen 有効化シーケンスが含まれているかチェックする。
 pub(super) fn contains_alt_screen_seq(data: &[u8]) -> bool {
@@ -23,23 +28,39 @@ pub(super) fn contains_alt_screen_seq(data: &[u8]) -> bool {
         .any(|w| w == ALT_SCREEN_ENABLE)
 }
 
+/// `data` を出力した後に Alternate Screen が有効なままかを返す。
+///
+/// `active` は `data` を出力する前の状態。`data` 内で最後に現れた
+/// 有効化 / 無効化シーケンスで状態が決まり、どちらも含まなければ `active` のまま。
+pub(super) fn alt_screen_active_after(data: &[u8], active: bool) -> bool {
+    let last_pos = |seq: &[u8]| data.windows(seq.len()).rposition(|w| w == seq);
+    match (last_pos(ALT_SCREEN_ENABLE), last_pos(ALT_SCREEN_DISABLE)) {
+        (Some(enable), Some(disable)) => enable > disable,
+        (Some(_), None) => true,
+        (None, Some(_)) => false,
+        (None, None) => active,
+    }
+}
+
 // ── PTY ヘルパー ──
 
 /// 現在のターミナルサイズを取得する。
 /// 取得に失敗した場合はデフォルト値 (80x24) を返す。
 pub(super) fn get_terminal_winsize() -> libc::winsize {
+    query_terminal_winsize().unwrap_or(libc::winsize {
+        ws_row: 24,
+        ws_col: 80,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    })
+}
+
+/// stdout が接続されたターミナルのサイズを取得する。
+/// ターミナルでない・サイズが 0 の場合は `None` を返す。
+pub(super) fn query_terminal_winsize() -> Option<libc::winsize> {
     let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
     let ret = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ws) };
-    if ret == 0 && ws.ws_col > 0 && ws.ws_row > 0 {
-        ws
-    } else {
-        libc::winsize {
-            ws_row: 24,
-            ws_col: 80,
-            ws_xpixel: 0,
-            ws_ypixel: 0,
-        }
-    }
+    (ret == 0 && ws.ws_col > 0 && ws.ws_row > 0).then_some(ws)
 }
 
 /// PTY slave の OPOST フラグを無効にし、出力時の `\n` → `\r\n` 変換を抑制する。
@@ -74,16 +95,19 @@ fn create_legacy_pty() -> io::Result<(File, OwnedFd)> {
     Ok((master_file, pty.slave))
 }
 
-/// stdout/stderr キャプチャ用の (reader, writer Stdio) ペアを作成する。
+/// stdout/stderr キャプチャ用の (reader, writer) ペアを作成する。
+/// writer は `>&2` の fd 複製に使えるよう `OwnedFd` で返す（`Stdio::from` で変換可能）。
 /// PTY を優先して使用し、子プロセスが `isatty()=true` と判定するようにする。
 /// PTY 作成に失敗した場合は os_pipe にフォールバック。
-pub(super) fn create_capture_pair() -> io::Result<(Box<dyn std::io::Read + Send>, Stdio)> {
+///
+/// 3 番目の要素は writer が PTY かどうか（`false` ならパイプへのフォールバック）。
+pub(super) fn create_capture_pair() -> io::Result<(File, OwnedFd, bool)> {
     match create_legacy_pty() {
-        Ok((master, slave)) => Ok((Box::new(master), slave.into())),
+        Ok((master, slave)) => Ok((master, slave, true)),
         Err(e) => {
             debug!("PTY creation failed, falling back to pipe: {e}");
             let (read, write) = os_pipe::pipe()?;
-            Ok((Box::new(read), write.into()))
+            Ok((File::from(OwnedFd::from(read)), write.into(), false))
         }
     }
 }
@@ -99,4 +123,18 @@ mod tests {
         assert!(!contains_alt_screen_seq(b"no alt screen here"));
         assert!(!contains_alt_screen_seq(b"\x1b[?1049")); // incomplete
     }
+
+    #[test]
+    fn alt_screen_state_follows_last_sequence() {
+        assert!(alt_screen_active_after(b"\x1b[?1049hvim", false));
+        assert!(!alt_screen_active_after(
+            b"\x1b[?1049h...\x1b[?1049l",
+            false
+        ));
+        assert!(alt_screen_active_after(b"\x1b[?1049l...\x1b[?1049h", false));
+        assert!(!alt_screen_active_after(b"bye\x1b[?1049l", true));
+        // シーケンスを含まないチャンクでは状態が変わらない
+        assert!(alt_screen_active_after(b"plain output", true));
+        assert!(!alt_screen_active_after(b"plain output", false));
+    }
 }
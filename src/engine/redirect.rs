@@ -1,42 +1,421 @@
 //! リダイレクトヘルパー
 //!
-//! `>`, `>>`, `<` リダイレクトの処理を提供する。
+//! `>`, `>>`, `<`, `&>`, `&>>`, `>&2` リダイレクトの処理を提供する。
+//!
+//! `[shell] mkdir_on_redirect` のフラグは呼び出し元が [`ExecContext`](super::ExecContext) から渡す。
 
-use std::fs::{File, OpenOptions};
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::fd::OwnedFd;
+use std::path::Path;
+use std::process::Stdio;
 
 use super::parser::Redirect;
 use super::CommandResult;
 
-/// リダイレクトリストから stdout リダイレクト先ファイルを開く。
-pub(super) fn find_stdout_redirect(redirects: &[Redirect]) -> Option<File> {
-    let mut result = None;
+/// リダイレクト後の stdout の接続先
+#[derive(Debug)]
+pub(super) enum StdoutTarget {
+    /// ファイルに書き込む
+    File(File),
+    /// リダイレクト前の（元の）stderr に書き込む（`>&2`）
+    Stderr,
+}
+
+/// リダイレクトを記述順に適用した結果、各ストリームに最終的に接続される先。
+/// `None` のストリームはリダイレクトされていない（呼び出し元の既定の接続を使う）。
+#[derive(Debug, Default)]
+pub(super) struct RedirectTargets {
+    pub stdin: Option<File>,
+    pub stdout: Option<StdoutTarget>,
+    pub stderr: Option<File>,
+}
+
+impl RedirectTargets {
+    /// 既定の stdout / stderr の接続先にリダイレクトを重ね、子プロセスに渡す
+    /// `(stdout, stderr)` を返す。
+    ///
+    /// 使われなかった既定の接続先はここで drop されるため、キャプチャ用パイプの
+    /// 読み取り側には EOF が届く。
+    pub(super) fn into_output_stdio(
+        self,
+        default_stdout: OwnedFd,
+        default_stderr: OwnedFd,
+    ) -> io::Result<(Stdio, Stdio)> {
+        let stdout: OwnedFd = match self.stdout {
+            None => default_stdout,
+            Some(StdoutTarget::File(file)) => file.into(),
+            Some(StdoutTarget::Stderr) => default_stderr.try_clone()?,
+        };
+        let stderr: OwnedFd = match self.stderr {
+            None => default_stderr,
+            Some(file) => file.into(),
+        };
+        Ok((stdout.into(), stderr.into()))
+    }
+}
+
+/// リダイレクトリストを記述順（左から右）に適用する。
+///
+/// bash と同様に全てのリダイレクト先を順に開くため、`cmd > a.txt > b.txt` では
+/// `a.txt` も作成（切り詰め）され、出力は最後の `b.txt` に書き込まれる。
+/// 同じストリームへの指定は後に書いたものが優先される。
+/// `>&2` はその時点の stderr の接続先を複製するため、`&> log >&2` では stdout も
+/// `log` に、`>&2 &> log` では両方が `log` に向かう。
+/// いずれかのファイルが開けなければ、その時点でエラーを返しコマンドは実行しない。
+/// `mkdir`（`[shell] mkdir_on_redirect`）が `true` の場合、出力先（`>`, `>>`, `&>`, `&>>`）の
+/// 親ディレクトリが存在しなければ作成してから開く。
+pub(super) fn open_redirects(
+    redirects: &[Redirect],
+    mkdir: bool,
+) -> Result<RedirectTargets, CommandResult> {
+    let mut targets = RedirectTargets::default();
     for r in redirects {
         match r {
             Redirect::StdoutOverwrite(path) => {
-                result = File::create(path).ok();
+                let file = open_or_error(path, open_truncate(path, mkdir))?;
+                targets.stdout = Some(StdoutTarget::File(file));
             }
             Redirect::StdoutAppend(path) => {
-                result = OpenOptions::new().create(true).append(true).open(path).ok();
+                let file = open_or_error(path, open_append(path, mkdir))?;
+                targets.stdout = Some(StdoutTarget::File(file));
+            }
+            Redirect::StdinFrom(path) => {
+                targets.stdin = Some(open_or_error(path, File::open(path))?);
+            }
+            Redirect::BothToFile(path) => {
+                let file = open_or_error(path, open_truncate(path, mkdir))?;
+                let dup = open_or_error(path, file.try_clone())?;
+                targets.stdout = Some(StdoutTarget::File(file));
+                targets.stderr = Some(dup);
+            }
+            Redirect::BothAppend(path) => {
+                let file = open_or_error(path, open_append(path, mkdir))?;
+                let dup = open_or_error(path, file.try_clone())?;
+                targets.stdout = Some(StdoutTarget::File(file));
+                targets.stderr = Some(dup);
+            }
+            Redirect::StdoutToStderr => {
+                targets.stdout = Some(match &targets.stderr {
+                    Some(file) => StdoutTarget::File(open_or_error("&2", file.try_clone())?),
+                    None => StdoutTarget::Stderr,
+                });
             }
-            _ => {}
         }
     }
-    result
+    Ok(targets)
 }
 
-/// リダイレクトリストから stdin リダイレクト元ファイルを開く。
-pub(super) fn find_stdin_redirect(redirects: &[Redirect]) -> Result<Option<File>, CommandResult> {
-    for r in redirects {
-        if let Redirect::StdinFrom(path) = r {
-            return match File::open(path) {
-                Ok(f) => Ok(Some(f)),
-                Err(e) => {
-                    let msg = format!("jarvish: {path}: {e}\n");
-                    eprint!("{msg}");
-                    Err(CommandResult::error(msg, 1))
-                }
-            };
+/// 書き込み用にファイルを開き、内容を切り詰める（存在しなければ作成）。
+fn open_truncate(path: &str, mkdir: bool) -> io::Result<File> {
+    if mkdir {
+        create_parent_dirs(path)?;
+    }
+    File::create(path)
+}
+
+/// 追記モードでファイルを開く（存在しなければ作成）。
+fn open_append(path: &str, mkdir: bool) -> io::Result<File> {
+    if mkdir {
+        create_parent_dirs(path)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// リダイレクト先の親ディレクトリを（存在しなければ）作成する。
+fn create_parent_dirs(path: &str) -> io::Result<()> {
+    match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+/// ファイルオープンの失敗を `jarvish: <path>: <error>` 形式のエラー結果に変換する。
+fn open_or_error(path: &str, file: io::Result<File>) -> Result<File, CommandResult> {
+    file.map_err(|e| {
+        let msg = format!("jarvish: {path}: {e}\n");
+        eprint!("{msg}");
+        CommandResult::error(msg, 1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    fn stdout_file(targets: &mut RedirectTargets) -> &mut File {
+        match targets.stdout.as_mut() {
+            Some(StdoutTarget::File(file)) => file,
+            other => panic!("expected stdout file, got {other:?}"),
         }
     }
-    Ok(None)
+
+    fn path_str(dir: &tempfile::TempDir, name: &str) -> String {
+        dir.path().join(name).to_str().unwrap().to_string()
+    }
+
+    /// 既定の stdout / stderr を別々のファイルとして `into_output_stdio` を通し、
+    /// echo 相当の書き込みを行った後の (stdout 既定, stderr 既定) の内容を返す。
+    fn write_through(targets: RedirectTargets, dir: &tempfile::TempDir) -> (String, String) {
+        let out_path = dir.path().join("__default_out");
+        let err_path = dir.path().join("__default_err");
+        let default_out = File::create(&out_path).unwrap();
+        let default_err = File::create(&err_path).unwrap();
+        let (stdout, stderr) = targets
+            .into_output_stdio(default_out.into(), default_err.into())
+            .unwrap();
+        let status = std::process::Command::new("sh")
+            .args(["-c", "echo out; echo err >&2"])
+            .stdout(stdout)
+            .stderr(stderr)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        (
+            std::fs::read_to_string(out_path).unwrap(),
+            std::fs::read_to_string(err_path).unwrap(),
+        )
+    }
+
+    #[test]
+    fn last_stdout_redirect_wins_and_earlier_files_are_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        std::fs::write(&a, "stale\n").unwrap();
+
+        let mut targets = open_redirects(
+            &[
+                Redirect::StdoutOverwrite(a.to_str().unwrap().into()),
+                Redirect::StdoutOverwrite(b.to_str().unwrap().into()),
+            ],
+            false,
+        )
+        .unwrap();
+        stdout_file(&mut targets).write_all(b"hello\n").unwrap();
+        drop(targets);
+
+        // 途中の `> a.txt` も bash と同様に切り詰められる
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "");
+        assert_eq!(std::fs::read_to_string(&b).unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn overwrite_then_append_writes_to_append_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        std::fs::write(&b, "first\n").unwrap();
+
+        let mut targets = open_redirects(
+            &[
+                Redirect::StdoutOverwrite(a.to_str().unwrap().into()),
+                Redirect::StdoutAppend(b.to_str().unwrap().into()),
+            ],
+            false,
+        )
+        .unwrap();
+        stdout_file(&mut targets).write_all(b"second\n").unwrap();
+        drop(targets);
+
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "");
+        assert_eq!(std::fs::read_to_string(&b).unwrap(), "first\nsecond\n");
+    }
+
+    #[test]
+    fn missing_parent_directory_is_created_only_when_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("logs/out.txt");
+        let appended = dir.path().join("logs/nested/app.log");
+
+        // 既定（bash と同じ）では親ディレクトリがなければエラー
+        let err = open_redirects(
+            &[Redirect::StdoutOverwrite(out.to_str().unwrap().into())],
+            false,
+        )
+        .unwrap_err();
+        assert!(err.stderr.contains("No such file or directory"));
+        assert!(!out.parent().unwrap().exists());
+
+        let mut targets = open_redirects(
+            &[
+                Redirect::StdoutOverwrite(out.to_str().unwrap().into()),
+                Redirect::BothAppend(appended.to_str().unwrap().into()),
+            ],
+            true,
+        )
+        .unwrap();
+        stdout_file(&mut targets).write_all(b"hello\n").unwrap();
+        drop(targets);
+
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "");
+        assert_eq!(std::fs::read_to_string(&appended).unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn mkdir_on_redirect_reports_error_when_parent_cannot_be_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, "x").unwrap();
+        let target = file.join("out.txt");
+
+        let err = open_redirects(
+            &[Redirect::StdoutOverwrite(target.to_str().unwrap().into())],
+            true,
+        )
+        .unwrap_err();
+        assert_eq!(err.exit_code, 1);
+        assert!(err.stderr.starts_with("jarvish: "));
+    }
+
+    #[test]
+    fn last_stdin_redirect_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        std::fs::write(&a, "from a\n").unwrap();
+        std::fs::write(&b, "from b\n").unwrap();
+
+        let targets = open_redirects(
+            &[
+                Redirect::StdinFrom(a.to_str().unwrap().into()),
+                Redirect::StdinFrom(b.to_str().unwrap().into()),
+            ],
+            false,
+        )
+        .unwrap();
+        let mut contents = String::new();
+        targets
+            .stdin
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "from b\n");
+        assert!(targets.stdout.is_none());
+    }
+
+    #[test]
+    fn failure_stops_before_later_redirects() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.txt");
+
+        let err = open_redirects(
+            &[
+                Redirect::StdinFrom("/tmp/__jarvish_nonexistent_input__".into()),
+                Redirect::StdoutOverwrite(out.to_str().unwrap().into()),
+            ],
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(err.exit_code, 1);
+        assert!(err.stderr.contains("__jarvish_nonexistent_input__"));
+        // 失敗したリダイレクトより後ろは適用されない
+        assert!(!out.exists());
+    }
+
+    #[test]
+    fn no_redirects_leaves_streams_untouched() {
+        let targets = open_redirects(&[], false).unwrap();
+        assert!(targets.stdin.is_none());
+        assert!(targets.stdout.is_none());
+    }
+
+    #[test]
+    fn both_to_file_captures_stdout_and_stderr() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = path_str(&dir, "build.log");
+
+        let targets = open_redirects(&[Redirect::BothToFile(log.clone())], false).unwrap();
+        let (out, err) = write_through(targets, &dir);
+
+        assert_eq!(out, "");
+        assert_eq!(err, "");
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "out\nerr\n");
+    }
+
+    #[test]
+    fn both_append_keeps_existing_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = path_str(&dir, "build.log");
+        std::fs::write(&log, "previous\n").unwrap();
+
+        let targets = open_redirects(&[Redirect::BothAppend(log.clone())], false).unwrap();
+        write_through(targets, &dir);
+
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "previous\nout\nerr\n"
+        );
+    }
+
+    #[test]
+    fn stdout_to_stderr_uses_original_stderr() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let targets = open_redirects(&[Redirect::StdoutToStderr], false).unwrap();
+        let (out, err) = write_through(targets, &dir);
+
+        assert_eq!(out, "");
+        assert_eq!(err, "out\nerr\n");
+    }
+
+    #[test]
+    fn stdout_to_stderr_after_both_follows_file() {
+        // `&> log >&2`: >&2 の時点で stderr は log なので stdout も log
+        let dir = tempfile::tempdir().unwrap();
+        let log = path_str(&dir, "log");
+
+        let targets = open_redirects(
+            &[Redirect::BothToFile(log.clone()), Redirect::StdoutToStderr],
+            false,
+        )
+        .unwrap();
+        let (out, err) = write_through(targets, &dir);
+
+        assert_eq!((out.as_str(), err.as_str()), ("", ""));
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "out\nerr\n");
+    }
+
+    #[test]
+    fn stdout_file_after_both_splits_streams() {
+        // `&> all.log > out.txt`: stdout は後勝ちで out.txt、stderr は all.log のまま
+        let dir = tempfile::tempdir().unwrap();
+        let all = path_str(&dir, "all.log");
+        let only_out = path_str(&dir, "out.txt");
+
+        let targets = open_redirects(
+            &[
+                Redirect::BothToFile(all.clone()),
+                Redirect::StdoutOverwrite(only_out.clone()),
+            ],
+            false,
+        )
+        .unwrap();
+        write_through(targets, &dir);
+
+        assert_eq!(std::fs::read_to_string(&all).unwrap(), "err\n");
+        assert_eq!(std::fs::read_to_string(&only_out).unwrap(), "out\n");
+    }
+
+    #[test]
+    fn stdout_to_stderr_then_file_redirect_overrides() {
+        // `>&2 > out.txt`: 後に書いた > out.txt が優先される
+        let dir = tempfile::tempdir().unwrap();
+        let only_out = path_str(&dir, "out.txt");
+
+        let targets = open_redirects(
+            &[
+                Redirect::StdoutToStderr,
+                Redirect::StdoutOverwrite(only_out.clone()),
+            ],
+            false,
+        )
+        .unwrap();
+        let (out, err) = write_through(targets, &dir);
+
+        assert_eq!(out, "");
+        assert_eq!(err, "err\n");
+        assert_eq!(std::fs::read_to_string(&only_out).unwrap(), "out\n");
+    }
 }
@@ -0,0 +1,162 @@
+//! シェル変数（export されていないシェルローカル変数）
+//!
+//! `name=value`（export なし）で設定し、`$name` / `${name}` で参照できる bash のシェル変数。
+//! 値はプロセス環境には置かないため子プロセスには渡らない（渡るのは `export` したものだけ）。
+//! 変数展開では同名の環境変数より優先する。
+//!
+//! - `name=value` → 既に環境変数として存在する名前（`PATH` 等）は環境変数を更新する（[`ShellVariables::assign`]）
+//! - `export name` → シェル変数を環境変数に昇格させる
+//! - `export name=value` → 同名のシェル変数は削除し、環境変数として設定する
+//! - `unset name` → シェル変数・環境変数の両方から削除する
+//!
+//! 変数表（[`ShellVariables`]）は `Shell` が [`ExecContext`](super::ExecContext) に持たせて所有し、
+//! 展開処理・ビルトインには引数で渡す。
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use tracing::debug;
+
+/// シェル変数の表。
+///
+/// クローンは同じ表を共有する（`Shell` が保持する実行コンテキストのクローンからの
+/// 代入も `Shell` の表に反映される）。
+#[derive(Debug, Clone, Default)]
+pub struct ShellVariables(Arc<RwLock<HashMap<String, String>>>);
+
+impl ShellVariables {
+    /// シェル変数を設定する（既存の値は上書き）。
+    pub fn set(&self, name: &str, value: &str) {
+        debug!(name = %name, "Shell variable set");
+        self.0
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.to_string(), value.to_string());
+    }
+
+    /// 代入文 `name=value` を実行する。
+    ///
+    /// 既に環境変数として存在する名前は環境変数を更新する（bash と同じく export 済みの変数は
+    /// export されたまま値が変わる）。それ以外はシェル変数に設定する。
+    pub fn assign(&self, name: &str, value: &str) {
+        if std::env::var_os(name).is_some() {
+            debug!(name = %name, "Exported variable assigned");
+            // SAFETY: シェルプロセス内でシングルスレッドで呼ばれるため安全
+            unsafe {
+                std::env::set_var(name, value);
+            }
+        } else {
+            self.set(name, value);
+        }
+    }
+
+    /// シェル変数を削除し、設定されていた値を返す。
+    pub fn remove(&self, name: &str) -> Option<String> {
+        self.0
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(name)
+    }
+
+    /// 変数展開用に値を引く。シェル変数を優先し、なければ環境変数を返す。
+    pub fn lookup(&self, name: &str) -> Option<String> {
+        let shell_value = self
+            .0
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(name)
+            .cloned();
+        shell_value.or_else(|| std::env::var(name).ok())
+    }
+}
+
+/// 変数名として妥当か（英字または `_` で始まり、英数字と `_` のみ。小文字も可）。
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `name=value` 形式の代入語を `(name, value)` に分解する。代入語でなければ `None`。
+pub fn parse_assignment(word: &str) -> Option<(&str, &str)> {
+    let (name, value) = word.split_once('=')?;
+    is_valid_name(name).then_some((name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn parse_assignment_accepts_lowercase_names() {
+        assert_eq!(parse_assignment("name=value"), Some(("name", "value")));
+        assert_eq!(parse_assignment("_x1="), Some(("_x1", "")));
+        assert_eq!(parse_assignment("URL=a=b"), Some(("URL", "a=b")));
+        assert_eq!(parse_assignment("1x=a"), None);
+        assert_eq!(parse_assignment("--opt=a"), None);
+        assert_eq!(parse_assignment("=a"), None);
+        assert_eq!(parse_assignment("plain"), None);
+    }
+
+    #[test]
+    #[serial]
+    fn shell_variable_shadows_environment() {
+        let vars = ShellVariables::default();
+        unsafe {
+            std::env::set_var("JARVISH_TEST_SHADOW", "env");
+        }
+        vars.set("JARVISH_TEST_SHADOW", "shell");
+        assert_eq!(vars.lookup("JARVISH_TEST_SHADOW").as_deref(), Some("shell"));
+        // シェル変数はプロセス環境には書き込まない
+        assert_eq!(std::env::var("JARVISH_TEST_SHADOW").as_deref(), Ok("env"));
+
+        assert_eq!(vars.remove("JARVISH_TEST_SHADOW").as_deref(), Some("shell"));
+        assert_eq!(vars.lookup("JARVISH_TEST_SHADOW").as_deref(), Some("env"));
+        unsafe {
+            std::env::remove_var("JARVISH_TEST_SHADOW");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn assign_updates_existing_environment_variable() {
+        let vars = ShellVariables::default();
+        unsafe {
+            std::env::set_var("JARVISH_TEST_ASSIGN_ENV", "old");
+        }
+        vars.assign("JARVISH_TEST_ASSIGN_ENV", "new");
+        assert_eq!(
+            std::env::var("JARVISH_TEST_ASSIGN_ENV").as_deref(),
+            Ok("new")
+        );
+        assert!(vars.remove("JARVISH_TEST_ASSIGN_ENV").is_none());
+        unsafe {
+            std::env::remove_var("JARVISH_TEST_ASSIGN_ENV");
+        }
+
+        vars.assign("jarvish_test_assign_local", "v");
+        assert!(std::env::var_os("jarvish_test_assign_local").is_none());
+        assert_eq!(
+            vars.remove("jarvish_test_assign_local").as_deref(),
+            Some("v")
+        );
+    }
+
+    #[test]
+    fn clones_share_the_table() {
+        let vars = ShellVariables::default();
+        vars.clone().set("jarvish_test_shared", "1");
+        assert_eq!(vars.lookup("jarvish_test_shared").as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn separate_tables_are_independent() {
+        let a = ShellVariables::default();
+        let b = ShellVariables::default();
+        a.set("jarvish_test_separate", "a");
+        assert!(b.lookup("jarvish_test_separate").is_none());
+    }
+}
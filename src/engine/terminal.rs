@@ -2,7 +2,7 @@
 //!
 //! ターミナル属性の保存・復元と、RAII ガードによる確実な復元を提供する。
 
-use std::io;
+use std::io::{self, Write};
 use std::os::fd::AsFd;
 
 use nix::sys::termios::{self, SetArg, Termios};
@@ -18,6 +18,47 @@ fn restore_terminal_state(saved: &Termios) {
     let _ = termios::tcsetattr(io::stdin().as_fd(), SetArg::TCSANOW, saved);
 }
 
+/// 子プロセスが変更したまま終了したかもしれない端末モードを既定に戻すシーケンス。
+///
+/// 文字属性（色等）のリセット、カーソル表示、カーソルキー / キーパッドの通常モード化、
+/// マウストラッキングの無効化を行う。
+const TERMINAL_MODE_RESET: &str = concat!(
+    "\x1b[0m",     // SGR リセット（色・太字等）
+    "\x1b[?25h",   // カーソル表示
+    "\x1b[?1l",    // カーソルキーを通常モードに
+    "\x1b>",       // キーパッドを数値モードに
+    "\x1b[?1000l", // マウストラッキング無効化
+    "\x1b[?1002l",
+    "\x1b[?1003l",
+    "\x1b[?1006l",
+);
+
+/// Alternate Screen を終了するシーケンス
+const ALT_SCREEN_LEAVE: &str = "\x1b[?1049l";
+
+/// 端末モードのリセットシーケンスを組み立てる。
+///
+/// `?1049l` は alt screen 外で送るとカーソル位置の復元が起きるため、
+/// 子プロセスが alt screen を有効にしたまま終了した場合（`leave_alt_screen`）のみ含める。
+fn terminal_reset_sequence(leave_alt_screen: bool) -> String {
+    let mut seq = String::new();
+    if leave_alt_screen {
+        seq.push_str(ALT_SCREEN_LEAVE);
+    }
+    seq.push_str(TERMINAL_MODE_RESET);
+    seq
+}
+
+/// 対話コマンド終了後に、子プロセスが残したかもしれない端末モードを明示的にリセットする。
+///
+/// termios の復元（[`TerminalStateGuard`]）では戻らない、エスケープシーケンスで
+/// 切り替えられる状態（カーソル非表示、色、alt screen 等）を対象とする。
+pub(super) fn reset_terminal_modes(leave_alt_screen: bool) {
+    let mut stdout = io::stdout().lock();
+    let _ = stdout.write_all(terminal_reset_sequence(leave_alt_screen).as_bytes());
+    let _ = stdout.flush();
+}
+
 /// RAII ガード: スコープを抜けるときに自動的にターミナル状態を復元する。
 /// エラー発生時や早期リターン、パニック時も確実に復元される。
 pub(super) struct TerminalStateGuard {
@@ -53,3 +94,23 @@ impl Drop for TerminalStateGuard {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reset_sequence_shows_cursor_and_resets_colors() {
+        let seq = terminal_reset_sequence(false);
+        assert!(seq.contains("\x1b[?25h"));
+        assert!(seq.contains("\x1b[0m"));
+        assert!(!seq.contains(ALT_SCREEN_LEAVE));
+    }
+
+    #[test]
+    fn reset_sequence_leaves_alt_screen_first() {
+        let seq = terminal_reset_sequence(true);
+        assert!(seq.starts_with(ALT_SCREEN_LEAVE));
+        assert!(seq.contains("\x1b[?25h"));
+    }
+}
@@ -87,6 +87,10 @@ struct CommandCache {
 static PATH_COMMANDS_CACHE: Mutex<Option<CommandCache>> = Mutex::new(None);
 
 /// PATH 上の全実行可能ファイル名を収集する（キャッシュなし）。
+///
+/// 通常ファイルかつ実行ビット（0o111）のいずれかが立っているものだけを収集する。
+/// `DirEntry::metadata()` はシンボリックリンクを辿らないため、`fs::metadata()` で
+/// リンク先の種別・権限を確認する（リンク切れのエントリは除外される）。
 fn collect_path_commands_raw(path: &str) -> HashSet<String> {
     let mut commands = HashSet::new();
     for dir in path.split(':') {
@@ -94,10 +98,10 @@ fn collect_path_commands_raw(path: &str) -> HashSet<String> {
             continue;
         };
         for entry in entries.flatten() {
-            let Ok(metadata) = entry.metadata() else {
+            let Ok(metadata) = fs::metadata(entry.path()) else {
                 continue;
             };
-            if metadata.is_dir() {
+            if !metadata.is_file() {
                 continue;
             }
             if metadata.permissions().mode() & 0o111 == 0 {
@@ -173,6 +177,22 @@ pub fn find_correction(cmd: &str) -> Option<String> {
     best.map(|(_, name)| name)
 }
 
+/// `cmd` から編集距離 1 の PATH 上のコマンドが 1 つだけ存在する場合に返す。
+///
+/// `[shell] autocorrect` の自動修正に使う。候補が複数ある（曖昧な）場合は `None`。
+pub fn find_unique_correction(cmd: &str) -> Option<String> {
+    unique_correction_in(cmd, &get_path_commands())
+}
+
+/// [`find_unique_correction`] の本体（コマンド一覧を引数で受け取る）。
+fn unique_correction_in(cmd: &str, commands: &HashSet<String>) -> Option<String> {
+    let mut found = commands
+        .iter()
+        .filter(|candidate| damerau_levenshtein(cmd, candidate) == 1);
+    let first = found.next()?;
+    found.next().is_none().then(|| first.clone())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -235,4 +255,70 @@ mod tests {
         // 全コマンドと大きく離れた文字列
         assert_eq!(find_correction("zzzjarvishtest"), None);
     }
+
+    #[test]
+    fn unique_correction_requires_single_candidate() {
+        let commands: HashSet<String> = ["ls", "git", "gist"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            unique_correction_in("sl", &commands),
+            Some("ls".to_string())
+        );
+        // "gis" は git / gist の両方から距離 1 → 曖昧
+        assert_eq!(unique_correction_in("gis", &commands), None);
+        // 距離 2 以上は対象外
+        assert_eq!(unique_correction_in("lsss", &commands), None);
+        // 完全一致（距離 0）は補正ではない
+        assert_eq!(unique_correction_in("ls", &commands), None);
+    }
+
+    // ── collect_path_commands_raw: 実行権限チェック ──
+
+    fn write_file_with_mode(path: &std::path::Path, mode: u32) {
+        fs::write(path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn collect_excludes_non_executable_file() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file_with_mode(&dir.path().join("runme"), 0o755);
+        write_file_with_mode(&dir.path().join("readme"), 0o644);
+
+        let commands = collect_path_commands_raw(dir.path().to_str().unwrap());
+        assert!(commands.contains("runme"));
+        assert!(!commands.contains("readme"));
+    }
+
+    #[test]
+    fn collect_excludes_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        // ディレクトリは実行ビットが立っていてもコマンドではない
+        fs::create_dir(dir.path().join("subdir")).unwrap();
+
+        let commands = collect_path_commands_raw(dir.path().to_str().unwrap());
+        assert!(!commands.contains("subdir"));
+    }
+
+    #[test]
+    fn collect_follows_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let exec_target = dir.path().join("exec_target");
+        let plain_target = dir.path().join("plain_target");
+        write_file_with_mode(&exec_target, 0o755);
+        write_file_with_mode(&plain_target, 0o644);
+
+        let bin = dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+        std::os::unix::fs::symlink(&exec_target, bin.join("exec_link")).unwrap();
+        std::os::unix::fs::symlink(&plain_target, bin.join("plain_link")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("missing"), bin.join("broken_link")).unwrap();
+
+        let commands = collect_path_commands_raw(bin.to_str().unwrap());
+        assert!(commands.contains("exec_link"));
+        assert!(!commands.contains("plain_link"));
+        assert!(!commands.contains("broken_link"));
+    }
 }
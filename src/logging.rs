@@ -3,6 +3,7 @@
 //! `tracing` + `tracing-subscriber` を使用して、デバッグログを外部ファイルに出力する。
 //! ログファイルは XDG_DATA_HOME 準拠のシステムディレクトリ
 //! (`~/.local/share/jarvish/logs/`) に日次ローテーション（JST基準）で保存される。
+//! レベル・出力先（ファイル / 標準エラー）・保持世代数は `config.toml` の `[logging]` で制御する。
 
 use std::fs::{File, OpenOptions};
 use std::io::Write;
@@ -10,11 +11,20 @@ use std::path::{Path, PathBuf};
 
 use chrono::{FixedOffset, Utc};
 use tracing_subscriber::fmt::time::FormatTime;
+use tracing_subscriber::prelude::*;
 use tracing_subscriber::{fmt, EnvFilter};
 
+use crate::config::LoggingConfig;
+
 /// JST (UTC+09:00) のオフセット（秒）
 const JST_OFFSET_SECS: i32 = 9 * 3600;
 
+/// ログファイル名のプレフィックス（`jarvish_YYYY-MM-DD.log`）
+const LOG_FILE_PREFIX: &str = "jarvish";
+
+/// `[logging] level` が不正な場合に使うログレベル
+const DEFAULT_LOG_LEVEL: &str = "debug";
+
 /// JST タイムゾーンを返すヘルパー
 fn jst() -> FixedOffset {
     FixedOffset::east_opt(JST_OFFSET_SECS).expect("invalid JST offset")
@@ -48,47 +58,104 @@ impl FormatTime for JstTimer {
 // JST ベースのローリングファイルアペンダー
 // ---------------------------------------------------------------------------
 
+/// 古いログファイルの削除条件（`[logging] retention_days` / `max_files`）
+#[derive(Debug, Clone, Copy)]
+struct RetentionPolicy {
+    /// 保持する日数（0 で無制限）
+    retention_days: u64,
+    /// 保持する日数分のファイル数（0 で無制限）
+    max_files: usize,
+}
+
 /// JST 基準で日次ローテーションするファイルアペンダー。
 ///
-/// 書き込み時に現在の JST 日付を確認し、日付が変わっていれば新しいファイルを開く。
+/// 書き込み時に現在の JST 日付を確認し、日付が変わっていれば新しいファイルを開いて
+/// 古いログファイルを削除する。`max_bytes` が 1 以上なら、ファイルサイズが上限を超える前に
+/// `{prefix}_YYYY-MM-DD.1.log` に退避して新しいファイルに切り替える（退避は 1 世代のみ）。
 /// `tracing_appender::non_blocking` と組み合わせて使用する。
 struct JstRollingAppender {
     dir: PathBuf,
     prefix: String,
+    retention: RetentionPolicy,
+    max_bytes: u64,
     current_date: chrono::NaiveDate,
     file: File,
 }
 
 impl JstRollingAppender {
     /// 新しい JST ローリングアペンダーを作成する。
-    fn new(dir: PathBuf, prefix: &str) -> std::io::Result<Self> {
-        let today = Utc::now().with_timezone(&jst()).date_naive();
+    fn new(
+        dir: PathBuf,
+        prefix: &str,
+        retention: RetentionPolicy,
+        max_bytes: u64,
+    ) -> std::io::Result<Self> {
+        let today = jst_today();
         let file = Self::open_log_file(&dir, prefix, today)?;
         Ok(Self {
             dir,
             prefix: prefix.to_string(),
+            retention,
+            max_bytes,
             current_date: today,
             file,
         })
     }
 
+    /// 指定した日付のログファイルのパスを返す。
+    fn log_file_path(dir: &Path, prefix: &str, date: chrono::NaiveDate) -> PathBuf {
+        dir.join(format!("{}_{}.log", prefix, date.format("%Y-%m-%d")))
+    }
+
     /// 指定した日付のログファイルを開く（なければ作成）。
     fn open_log_file(dir: &Path, prefix: &str, date: chrono::NaiveDate) -> std::io::Result<File> {
-        let filename = format!("{}_{}.log", prefix, date.format("%Y-%m-%d"));
         OpenOptions::new()
             .create(true)
             .append(true)
-            .open(dir.join(filename))
+            .open(Self::log_file_path(dir, prefix, date))
+    }
+
+    /// 書き込むと `max_bytes` を超える場合、現在のファイルを退避して新しいファイルを開く。
+    ///
+    /// 同じファイルに書き込む別の jarvish プロセスが先に退避していた場合（パスが別の
+    /// ファイルを指している場合）は、退避せずに開き直すだけにする。
+    fn rotate_if_oversized(&mut self, incoming: usize) -> std::io::Result<()> {
+        use std::os::unix::fs::MetadataExt;
+
+        let current = self.file.metadata()?;
+        if self.max_bytes == 0
+            || current.len() == 0
+            || current.len() + incoming as u64 <= self.max_bytes
+        {
+            return Ok(());
+        }
+        let path = Self::log_file_path(&self.dir, &self.prefix, self.current_date);
+        let rotated_by_us = std::fs::metadata(&path)
+            .map(|m| m.ino() == current.ino() && m.dev() == current.dev())
+            .unwrap_or(false);
+        if rotated_by_us {
+            let backup = self.dir.join(format!(
+                "{}_{}.1.log",
+                self.prefix,
+                self.current_date.format("%Y-%m-%d")
+            ));
+            std::fs::rename(&path, backup)?;
+        }
+        self.file = Self::open_log_file(&self.dir, &self.prefix, self.current_date)?;
+        Ok(())
     }
 }
 
 impl Write for JstRollingAppender {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let today = Utc::now().with_timezone(&jst()).date_naive();
+        let today = jst_today();
         if today != self.current_date {
             self.file = Self::open_log_file(&self.dir, &self.prefix, today)?;
             self.current_date = today;
+            // ログ出力中のため、削除件数はログに出さない
+            remove_old_log_files(&self.dir, &self.prefix, self.retention, today);
         }
+        self.rotate_if_oversized(buf.len())?;
         self.file.write(buf)
     }
 
@@ -97,6 +164,81 @@ impl Write for JstRollingAppender {
     }
 }
 
+/// 現在の JST の日付を返す。
+fn jst_today() -> chrono::NaiveDate {
+    Utc::now().with_timezone(&jst()).date_naive()
+}
+
+/// ログファイル名（`{prefix}_YYYY-MM-DD.log` / サイズ退避後の `{prefix}_YYYY-MM-DD.1.log`）から
+/// 日付を取り出す。`{prefix}_*.log` でないファイルは `None`、日付を読めない場合は `Some(None)`。
+fn log_file_date(name: &str, prefix: &str) -> Option<Option<chrono::NaiveDate>> {
+    let stem = name
+        .strip_prefix(prefix)?
+        .strip_prefix('_')?
+        .strip_suffix(".log")?;
+    let date = stem
+        .get(..10)
+        .filter(|_| {
+            let rest = &stem[10..];
+            rest.is_empty()
+                || rest
+                    .strip_prefix('.')
+                    .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+    Some(date)
+}
+
+/// `dir` 内の古いログファイルを削除し、削除した件数を返す。
+///
+/// 日付はファイル名から判定し、読めない場合は最終更新日時（JST）を使う。
+/// - `retention_days` が 1 以上: `today` から数えてその日数より前の日付のファイルを削除
+/// - `max_files` が 1 以上: 新しい日付から `max_files` 日分を残して削除
+fn remove_old_log_files(
+    dir: &Path,
+    prefix: &str,
+    policy: RetentionPolicy,
+    today: chrono::NaiveDate,
+) -> usize {
+    if policy.retention_days == 0 && policy.max_files == 0 {
+        return 0;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let dated: Vec<(chrono::NaiveDate, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let date = log_file_date(entry.file_name().to_str()?, prefix)?.or_else(|| {
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                let modified: chrono::DateTime<Utc> = modified.into();
+                Some(modified.with_timezone(&jst()).date_naive())
+            })?;
+            Some((date, entry.path()))
+        })
+        .collect();
+
+    let mut dates: Vec<chrono::NaiveDate> = dated.iter().map(|(date, _)| *date).collect();
+    dates.sort_unstable_by(|a, b| b.cmp(a));
+    dates.dedup();
+    let oldest_kept_by_count = (policy.max_files > 0)
+        .then(|| dates.get(policy.max_files - 1).copied())
+        .flatten();
+    let oldest_kept_by_age = (policy.retention_days > 0)
+        .then(|| today.checked_sub_days(chrono::Days::new(policy.retention_days - 1)))
+        .flatten();
+
+    let mut removed = 0;
+    for (date, path) in dated {
+        let expired = oldest_kept_by_count.is_some_and(|oldest| date < oldest)
+            || oldest_kept_by_age.is_some_and(|oldest| date < oldest);
+        if expired && std::fs::remove_file(path).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
 // ---------------------------------------------------------------------------
 // ログ初期化
 // ---------------------------------------------------------------------------
@@ -108,10 +250,30 @@ fn log_dir() -> PathBuf {
     crate::storage::BlackBox::data_dir().join("logs")
 }
 
+/// ログレベルのフィルタを決定する。
+///
+/// 設定ファイルより先に効かせられるよう、`JARVISH_LOG` → `RUST_LOG` 環境変数の順に
+/// 優先し、どちらもなければ `[logging] level` を使う。不正な場合は `debug` にフォールバックする。
+fn build_env_filter(level: &str) -> EnvFilter {
+    if let Ok(filter) = EnvFilter::try_from_env("JARVISH_LOG") {
+        return filter;
+    }
+    if let Ok(filter) = EnvFilter::try_from_default_env() {
+        return filter;
+    }
+    EnvFilter::try_new(level).unwrap_or_else(|e| {
+        eprintln!("jarvish: warning: invalid [logging] level `{level}`: {e} (using `{DEFAULT_LOG_LEVEL}`)");
+        EnvFilter::new(DEFAULT_LOG_LEVEL)
+    })
+}
+
 /// ログシステムを初期化する。
 ///
-/// - ログレベルは `JARVISH_LOG` 環境変数で制御（デフォルト: `debug`）
-/// - ログファイルは日次ローテーション（JST基準）で出力
+/// - ログレベルは `JARVISH_LOG` → `RUST_LOG` 環境変数、なければ `[logging] level` で制御（デフォルト: `debug`）
+/// - `[logging] file = true`（デフォルト）ならログファイルに日次ローテーション（JST基準）で出力する。
+///   起動時と日付の切り替わり時に `retention_days` / `max_files` を超えた古いファイルを削除し、
+///   `max_file_size_mb` を超えそうなファイルは退避して切り替える
+/// - `[logging] stderr = true` なら標準エラー出力にも出力
 /// - `log_dir_override` が `Some` の場合はそのパスに、`None` の場合は
 ///   `XDG_DATA_HOME/jarvish/logs/` に出力
 /// - タイムスタンプは JST (UTC+09:00) で記録
@@ -120,14 +282,83 @@ fn log_dir() -> PathBuf {
 /// # Returns
 /// `(WorkerGuard, bool)` を返す。
 /// - `WorkerGuard` は `main()` で保持し続ける必要がある（ドロップするとログ出力が停止する）。
-/// - `bool` はログファイルへの書き込みが正常に開始できたかどうか。
-///   `false` の場合、ログは sink（破棄）にフォールバックしている。
+/// - `bool` はログの出力先が正常に開始できたかどうか。ログファイルの作成に失敗し、
+///   他の出力先もない場合は `false`（ログは sink（破棄）にフォールバックしている）。
 pub fn init_logging(
     log_dir_override: Option<PathBuf>,
     session_key: &str,
+    config: &LoggingConfig,
 ) -> (tracing_appender::non_blocking::WorkerGuard, bool) {
     let log_dir = log_dir_override.unwrap_or_else(log_dir);
+    let retention = RetentionPolicy {
+        retention_days: config.retention_days,
+        max_files: config.max_files,
+    };
+    let (file_writer, file_ok) = if config.file {
+        let max_bytes = config.max_file_size_mb.saturating_mul(1024 * 1024);
+        open_file_writer(log_dir.clone(), retention, max_bytes)
+    } else {
+        (Box::new(std::io::sink()) as Box<dyn Write + Send>, false)
+    };
+
+    // 非ブロッキング書き込み用のワーカーを作成
+    let (non_blocking, guard) = tracing_appender::non_blocking(file_writer);
+
+    let file_layer = file_ok.then(|| {
+        fmt::layer()
+            .with_writer(non_blocking)
+            .with_timer(JstTimer {
+                session_key: session_key.to_string(),
+            })
+            .with_ansi(false) // ファイル出力には ANSI カラーコードを含めない
+            .with_target(true) // ターゲット（モジュールパス）を表示
+            .with_thread_ids(false)
+            .with_line_number(true) // 行番号を表示（デバッグ用）
+            .with_file(true) // ファイル名を表示（デバッグ用）
+    });
+    let stderr_layer = config.stderr.then(|| {
+        fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_timer(JstTimer {
+                session_key: session_key.to_string(),
+            })
+            .with_target(true)
+            .with_line_number(true)
+            .with_file(true)
+    });
+
+    // サブスクライバーを構成して設定
+    tracing_subscriber::registry()
+        .with(build_env_filter(&config.level))
+        .with(file_layer)
+        .with(stderr_layer)
+        .init();
+
+    if file_ok {
+        let removed = remove_old_log_files(&log_dir, LOG_FILE_PREFIX, retention, jst_today());
+        if removed > 0 {
+            eprintln!("jarvish: removed {removed} old log file(s)");
+            tracing::info!(
+                removed,
+                retention_days = config.retention_days,
+                max_files = config.max_files,
+                "Removed old log files"
+            );
+        }
+    }
+
+    (guard, file_ok || config.stderr || !config.file)
+}
 
+/// ログファイルのライターを作成する。
+///
+/// ログファイル作成に失敗した場合は stderr に警告を出力し、sink にフォールバックする。
+/// 戻り値の `bool` はログファイルへの書き込みを開始できたかどうか。
+fn open_file_writer(
+    log_dir: PathBuf,
+    retention: RetentionPolicy,
+    max_bytes: u64,
+) -> (Box<dyn Write + Send>, bool) {
     // ログディレクトリが存在しない場合は作成
     if let Err(e) = std::fs::create_dir_all(&log_dir) {
         eprintln!(
@@ -136,42 +367,17 @@ pub fn init_logging(
         );
     }
 
-    // JST ベースの日次ローテーションアペンダーを作成。
-    // ログファイル作成に失敗した場合は stderr に警告を出力し、sink にフォールバック。
-    let (writer, operational): (Box<dyn Write + Send>, bool) =
-        match JstRollingAppender::new(log_dir.clone(), "jarvish") {
-            Ok(appender) => (Box::new(appender), true),
-            Err(e) => {
-                eprintln!(
-                    "jarvish: warning: failed to create log file in {}: {e}",
-                    log_dir.display()
-                );
-                (Box::new(std::io::sink()), false)
-            }
-        };
-
-    // 非ブロッキング書き込み用のワーカーを作成
-    let (non_blocking, guard) = tracing_appender::non_blocking(writer);
-
-    // JARVISH_LOG 環境変数でログレベルを制御（デフォルト: debug）
-    let env_filter =
-        EnvFilter::try_from_env("JARVISH_LOG").unwrap_or_else(|_| EnvFilter::new("debug"));
-
-    // サブスクライバーを構成して設定
-    fmt()
-        .with_env_filter(env_filter)
-        .with_writer(non_blocking)
-        .with_timer(JstTimer {
-            session_key: session_key.to_string(),
-        })
-        .with_ansi(false) // ファイル出力には ANSI カラーコードを含めない
-        .with_target(true) // ターゲット（モジュールパス）を表示
-        .with_thread_ids(false)
-        .with_line_number(true) // 行番号を表示（デバッグ用）
-        .with_file(true) // ファイル名を表示（デバッグ用）
-        .init();
-
-    (guard, operational)
+    // JST ベースの日次ローテーションアペンダーを作成
+    match JstRollingAppender::new(log_dir.clone(), LOG_FILE_PREFIX, retention, max_bytes) {
+        Ok(appender) => (Box::new(appender), true),
+        Err(e) => {
+            eprintln!(
+                "jarvish: warning: failed to create log file in {}: {e}",
+                log_dir.display()
+            );
+            (Box::new(std::io::sink()), false)
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -225,3 +431,163 @@ pub fn start_cpu_monitor() {
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> chrono::NaiveDate {
+        chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn touch_logs(dir: &Path, names: &[&str]) {
+        for name in names {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+    }
+
+    fn remaining(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn log_file_date_parses_daily_and_rotated_names() {
+        assert_eq!(
+            log_file_date("jarvish_2026-02-19.log", "jarvish"),
+            Some(Some(date("2026-02-19")))
+        );
+        assert_eq!(
+            log_file_date("jarvish_2026-02-19.1.log", "jarvish"),
+            Some(Some(date("2026-02-19")))
+        );
+        assert_eq!(log_file_date("jarvish_broken.log", "jarvish"), Some(None));
+        assert_eq!(log_file_date("other_2026-02-19.log", "jarvish"), None);
+        assert_eq!(log_file_date("jarvish_2026-02-19.txt", "jarvish"), None);
+    }
+
+    #[test]
+    fn retention_days_removes_expired_files() {
+        let dir = tempfile::tempdir().unwrap();
+        touch_logs(
+            dir.path(),
+            &[
+                "jarvish_2026-02-01.log",
+                "jarvish_2026-02-05.log",
+                "jarvish_2026-02-05.1.log",
+                "jarvish_2026-02-19.log",
+                "other.log",
+            ],
+        );
+        let policy = RetentionPolicy {
+            retention_days: 14,
+            max_files: 0,
+        };
+
+        let removed = remove_old_log_files(dir.path(), "jarvish", policy, date("2026-02-19"));
+        assert_eq!(removed, 3);
+        assert_eq!(
+            remaining(dir.path()),
+            vec!["jarvish_2026-02-19.log", "other.log"]
+        );
+    }
+
+    #[test]
+    fn max_files_keeps_newest_dates() {
+        let dir = tempfile::tempdir().unwrap();
+        touch_logs(
+            dir.path(),
+            &[
+                "jarvish_2026-01-01.log",
+                "jarvish_2026-01-03.log",
+                "jarvish_2026-01-02.log",
+                "jarvish_2026-01-02.1.log",
+            ],
+        );
+        let policy = RetentionPolicy {
+            retention_days: 0,
+            max_files: 2,
+        };
+
+        let removed = remove_old_log_files(dir.path(), "jarvish", policy, date("2026-01-03"));
+        assert_eq!(removed, 1);
+        assert_eq!(
+            remaining(dir.path()),
+            vec![
+                "jarvish_2026-01-02.1.log",
+                "jarvish_2026-01-02.log",
+                "jarvish_2026-01-03.log"
+            ]
+        );
+    }
+
+    #[test]
+    fn cleanup_disabled_when_both_limits_are_zero() {
+        let dir = tempfile::tempdir().unwrap();
+        touch_logs(dir.path(), &["jarvish_2000-01-01.log"]);
+        let policy = RetentionPolicy {
+            retention_days: 0,
+            max_files: 0,
+        };
+        assert_eq!(
+            remove_old_log_files(dir.path(), "jarvish", policy, date("2026-01-01")),
+            0
+        );
+    }
+
+    #[test]
+    fn oversized_file_is_rotated() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = RetentionPolicy {
+            retention_days: 0,
+            max_files: 0,
+        };
+        let mut appender =
+            JstRollingAppender::new(dir.path().to_path_buf(), "jarvish", policy, 16).unwrap();
+        appender.write_all(b"0123456789\n").unwrap();
+        appender.write_all(b"abcdefghij\n").unwrap();
+        appender.flush().unwrap();
+
+        let today = appender.current_date.format("%Y-%m-%d").to_string();
+        let current = std::fs::read_to_string(dir.path().join(format!("jarvish_{today}.log")));
+        let backup = std::fs::read_to_string(dir.path().join(format!("jarvish_{today}.1.log")));
+        assert_eq!(current.unwrap(), "abcdefghij\n");
+        assert_eq!(backup.unwrap(), "0123456789\n");
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn env_filter_prefers_jarvish_log_then_rust_log() {
+        let saved: Vec<_> = ["JARVISH_LOG", "RUST_LOG"]
+            .into_iter()
+            .map(|name| (name, std::env::var_os(name)))
+            .collect();
+        unsafe {
+            std::env::set_var("JARVISH_LOG", "trace");
+            std::env::set_var("RUST_LOG", "info");
+        }
+        assert_eq!(build_env_filter("warn").to_string(), "trace");
+
+        unsafe {
+            std::env::remove_var("JARVISH_LOG");
+        }
+        assert_eq!(build_env_filter("warn").to_string(), "info");
+
+        unsafe {
+            std::env::remove_var("RUST_LOG");
+        }
+        assert_eq!(build_env_filter("warn").to_string(), "warn");
+
+        for (name, value) in saved {
+            if let Some(value) = value {
+                unsafe {
+                    std::env::set_var(name, value);
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::io::IsTerminal;
 use std::path::PathBuf;
 
 use clap::{CommandFactory, FromArgMatches, Parser};
@@ -5,7 +6,7 @@ use rand::Rng;
 use tracing::{info, warn};
 
 use jarvish::shell::RcOptions;
-use jarvish::{engine, logging, shell};
+use jarvish::{config, engine, logging, shell};
 
 /// Next Generation AI Integrated Shell
 #[derive(Parser)]
@@ -19,6 +20,10 @@ struct Args {
     #[arg(short = 'c', allow_hyphen_values = true)]
     command: Option<String>,
 
+    /// スクリプトファイルを実行して終了する（各行をコマンドとして実行する）
+    #[arg(value_name = "SCRIPT", conflicts_with = "command")]
+    script: Option<PathBuf>,
+
     /// rc.jsh の代わりに指定したパスの起動スクリプトを読み込む（自動生成はしない）
     #[arg(long, value_name = "PATH", conflicts_with = "no_rc")]
     rcfile: Option<PathBuf>,
@@ -26,6 +31,19 @@ struct Args {
     /// 起動スクリプト（rc.jsh）の読み込みを完全に無効化する
     #[arg(long, conflicts_with = "rcfile")]
     no_rc: bool,
+
+    /// stdin パイプ入力（非対話モード）で自然言語と判定された行を AI にルーティングする
+    /// （未指定時は全ての行をコマンドとして実行する）
+    #[arg(long, conflicts_with = "no_ai")]
+    ai: bool,
+
+    /// AI を使わずに起動する（全ての入力をコマンドとして実行する）
+    #[arg(long)]
+    no_ai: bool,
+
+    /// コマンドを実行せず、展開・パース後の最終的なコマンドを表示するだけにする
+    #[arg(long)]
+    dry_run: bool,
 }
 
 #[tokio::main]
@@ -59,7 +77,10 @@ async fn main() {
     let session_key = format!("{:06x}", (session_id as u64) & 0xFFFFFF);
 
     // ログシステムの初期化（_guard は main 終了まで保持する必要がある）
-    let (_guard, logging_ok) = logging::init_logging(log_dir_override, &session_key);
+    // 設定ファイルの本読み込み（Shell::new）より前なので、[logging] だけを先に読む
+    let logging_config = config::JarvishConfig::load_logging();
+    let (_guard, logging_ok) =
+        logging::init_logging(log_dir_override, &session_key, &logging_config);
     logging::start_cpu_monitor();
 
     info!(
@@ -71,12 +92,22 @@ async fn main() {
         rcfile: args.rcfile,
         no_rc: args.no_rc,
     };
-    // `-c` 指定時（非対話単体実行）は Tab 補完が一切発生しないため、
-    // 起動時のウォーム zsh 補完デーモン事前ウォームアップをスキップする
-    // （S5 修正 — 孤児 `/bin/zsh -i` 対策の1つ目、`Shell::new` のドキュメント
-    // 参照）。
-    let interactive = resolve_interactive(args.command.is_some());
+    // `-c` 指定時（非対話単体実行）や stdin がパイプの場合は Tab 補完が
+    // 一切発生しないため、起動時のウォーム zsh 補完デーモン事前ウォームアップを
+    // スキップする（S5 修正 — 孤児 `/bin/zsh -i` 対策の1つ目、`Shell::new` の
+    // ドキュメント参照）。
+    let stdin_is_terminal = std::io::stdin().is_terminal();
+    let interactive = resolve_interactive(
+        args.command.is_some() || args.script.is_some(),
+        stdin_is_terminal,
+    );
     let mut shell = shell::Shell::new(logging_ok, session_id, rc_options, interactive);
+    if args.dry_run {
+        shell.force_dry_run();
+    }
+    if args.no_ai {
+        shell.disable_ai();
+    }
     let (exit_code, action) = if let Some(ref command) = args.command {
         let exit_code = shell.run_command(command).await;
         // Fix B2: `run()`（対話 REPL）は `restart_requested` を再チェックして
@@ -89,6 +120,15 @@ async fn main() {
         // 立っていれば正直に `LoopAction::Restart` を返す。
         let action = resolve_run_command_action(shell.restart_requested());
         (exit_code, action)
+    } else if let Some(ref script) = args.script {
+        let exit_code = shell.run_script(script).await;
+        let action = resolve_run_command_action(shell.restart_requested());
+        (exit_code, action)
+    } else if !stdin_is_terminal {
+        // `echo "..." | jarvish`: reedline を使わず stdin を 1 行ずつ処理する
+        let exit_code = shell.run_stdin(args.ai).await;
+        let action = resolve_run_command_action(shell.restart_requested());
+        (exit_code, action)
     } else {
         shell.run().await
     };
@@ -118,18 +158,22 @@ async fn main() {
     // を実際に真にする）。デーモンが元々稼働していなければ no-op。
     shell.shutdown_zsh_daemon();
 
+    // Ctrl+Z で停止したままのジョブを残さない
+    engine::exec::hangup_stopped_jobs();
+
     std::process::exit(exit_code);
 }
 
 /// CLI 引数から `Shell::new` へ渡す `interactive` フラグを決める純粋な
 /// 決定関数（S5 修正）。
 ///
-/// `has_command` は `args.command.is_some()`（`-c '<command>'` が指定
-/// されたか）。`-c` 指定時は Tab 補完が一切発生しない非対話単体実行のため
+/// `has_command` は `-c '<command>'` またはスクリプトファイルが指定
+/// されたか。`-c` / スクリプト指定時は Tab 補完が一切発生しない非対話単体実行のため
 /// `false`（= 起動時のウォーム zsh 補完デーモン事前ウォームアップを
-/// スキップする）を返す。
-fn resolve_interactive(has_command: bool) -> bool {
-    !has_command
+/// スキップする）を返す。`stdin_is_terminal` が `false`（`echo ... | jarvish`
+/// のような stdin パイプ入力）の場合も同様に非対話モードとして `false` を返す。
+fn resolve_interactive(has_command: bool, stdin_is_terminal: bool) -> bool {
+    !has_command && stdin_is_terminal
 }
 
 /// `-c`（`run_command`）実行後にどの `LoopAction` を選ぶべきかを決める
@@ -164,7 +208,7 @@ mod tests {
     /// 従来どおり起動時の zsh 補完デーモン事前ウォームアップが走ること。
     #[test]
     fn resolve_interactive_without_command_is_true() {
-        assert!(resolve_interactive(false));
+        assert!(resolve_interactive(false, true));
     }
 
     /// `-c '<command>'` 指定（非対話単体実行）では `interactive == false`
@@ -172,7 +216,71 @@ mod tests {
     /// （S5 の孤児 `/bin/zsh -i` 対策その1）。
     #[test]
     fn resolve_interactive_with_command_is_false() {
-        assert!(!resolve_interactive(true));
+        assert!(!resolve_interactive(true, true));
+    }
+
+    /// stdin がパイプ（`echo ... | jarvish`）の場合は `-c` なしでも
+    /// 非対話モードとして `interactive == false` を返すこと。
+    #[test]
+    fn resolve_interactive_with_piped_stdin_is_false() {
+        assert!(!resolve_interactive(false, false));
+        assert!(!resolve_interactive(true, false));
+    }
+
+    /// `--ai` はフラグとしてパースでき、未指定時は `false` になること。
+    #[test]
+    fn ai_flag_parses() {
+        let matches = Args::command()
+            .try_get_matches_from(["jarvish", "--ai"])
+            .expect("--ai must parse");
+        let args = Args::from_arg_matches(&matches).expect("must convert to Args");
+        assert!(args.ai);
+
+        let matches = Args::command()
+            .try_get_matches_from(["jarvish"])
+            .expect("no flags must parse");
+        let args = Args::from_arg_matches(&matches).expect("must convert to Args");
+        assert!(!args.ai);
+    }
+
+    /// スクリプトファイルは位置引数として受け付け、`-c` とは併用できないこと。
+    #[test]
+    fn script_is_positional_and_conflicts_with_dash_c() {
+        let matches = Args::command()
+            .try_get_matches_from(["jarvish", "build.jsh"])
+            .expect("script must parse");
+        let args = Args::from_arg_matches(&matches).expect("must convert to Args");
+        assert_eq!(args.script, Some(PathBuf::from("build.jsh")));
+        assert_eq!(args.command, None);
+
+        assert!(Args::command()
+            .try_get_matches_from(["jarvish", "-c", "ls", "build.jsh"])
+            .is_err());
+    }
+
+    /// `--no-ai` はフラグとしてパースでき、`--ai` とは併用できないこと。
+    #[test]
+    fn no_ai_flag_conflicts_with_ai() {
+        let matches = Args::command()
+            .try_get_matches_from(["jarvish", "--no-ai"])
+            .expect("--no-ai must parse");
+        let args = Args::from_arg_matches(&matches).expect("must convert to Args");
+        assert!(args.no_ai);
+
+        assert!(Args::command()
+            .try_get_matches_from(["jarvish", "--ai", "--no-ai"])
+            .is_err());
+    }
+
+    /// `--dry-run` はフラグとしてパースでき、`-c` と併用できること。
+    #[test]
+    fn dry_run_flag_combines_with_dash_c() {
+        let matches = Args::command()
+            .try_get_matches_from(["jarvish", "--dry-run", "-c", "rm -rf build"])
+            .expect("--dry-run + -c must parse");
+        let args = Args::from_arg_matches(&matches).expect("must convert to Args");
+        assert!(args.dry_run);
+        assert_eq!(args.command, Some("rm -rf build".to_string()));
     }
 
     // ── resolve_run_command_action（Fix B2 の決定ロジック）──
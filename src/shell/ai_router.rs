@@ -3,13 +3,143 @@
 //! 自然言語入力を AI に送信し、新規会話または継続会話を処理する。
 //! AI の応答（コマンド or 自然言語）に応じて適切なアクションを実行する。
 
-use tracing::{debug, warn};
+use reedline::{EditCommand, Signal};
+use tracing::{debug, info, warn};
 
-use crate::ai::{AiResponse, ConversationOrigin};
-use crate::cli::jarvis::jarvis_notice;
-use crate::engine::{execute, CommandResult};
+use crate::ai::{AiResponse, ConversationOrigin, ConversationState};
+use crate::cli::jarvis::{jarvis_ask_send_context, jarvis_notice, ContextPreviewAction};
+use crate::engine::CommandResult;
+use crate::storage::BlackBox;
 
-use super::Shell;
+use super::{attach, Shell};
+
+/// AI に渡す直近のコマンド履歴の件数
+const AI_CONTEXT_ENTRIES: usize = 5;
+
+/// 直前コマンドを参照する質問で、stdout/stderr それぞれに含める最大文字数
+const LAST_OUTPUT_MAX_CHARS: usize = 20_000;
+
+/// 単独で直前コマンドの出力を指す日本語の指示代名詞（「これは?」「これ直して」等）
+const LAST_OUTPUT_PRONOUNS_JA: &[&str] = &["これ", "それ"];
+
+/// 指示代名詞で始まるが、直前コマンドの出力を指さない語
+const NON_REFERRING_JA: &[&str] = &[
+    "これから",
+    "これまで",
+    "それぞれ",
+    "それから",
+    "それまで",
+    "それでは",
+    "それでも",
+    "それとも",
+    "それなら",
+];
+
+/// 直前コマンドを指す日本語の連体詞（[`LAST_OUTPUT_NOUNS_JA`] と続けて使われたときだけ反応する）
+const LAST_OUTPUT_REFS_JA: &[&str] = &[
+    "この",
+    "その",
+    "さっきの",
+    "今の",
+    "いまの",
+    "直前の",
+    "上の",
+];
+
+/// コマンドの出力を表す日本語の名詞
+const LAST_OUTPUT_NOUNS_JA: &[&str] = &["出力", "エラー", "結果", "ログ", "メッセージ", "コマンド"];
+
+/// 直前コマンドを指す英単語（[`LAST_OUTPUT_NOUNS_EN`] の直前に置かれたときに反応する）
+const LAST_OUTPUT_REFS_EN: &[&str] = &["this", "that", "previous", "last"];
+
+/// コマンドの出力を表す英単語
+const LAST_OUTPUT_NOUNS_EN: &[&str] = &["output", "error", "errors", "result", "log", "message"];
+
+/// 単独の「this」「that」の後に続いてよい英単語（「what does this mean?」等）
+const LAST_OUTPUT_TRAILING_EN: &[&str] = &["mean", "means", "say", "says"];
+
+/// 入力が直前のコマンド（の出力）を指しているか（「これは?」「さっきのエラー」「what is this?」
+/// 「the error above」等）。
+///
+/// 「この関数」「is this a good idea?」のように別の物を指す指示語には反応しないよう、
+/// 指示代名詞が単独で使われた場合と、出力を表す語と組み合わさった場合に限る。
+/// 該当する場合は直近 [`AI_CONTEXT_ENTRIES`] 件の代わりに、直前 1 件の出力を切り詰めずに渡す。
+fn refers_to_last_output(input: &str) -> bool {
+    let pronoun_ja = input.char_indices().any(|(i, _)| {
+        let rest = &input[i..];
+        LAST_OUTPUT_PRONOUNS_JA.iter().any(|p| rest.starts_with(p))
+            && !NON_REFERRING_JA.iter().any(|w| rest.starts_with(w))
+    });
+    let phrase_ja = LAST_OUTPUT_REFS_JA.iter().any(|r| {
+        LAST_OUTPUT_NOUNS_JA
+            .iter()
+            .any(|noun| input.contains(&format!("{r}{noun}")))
+    });
+    if pronoun_ja || phrase_ja {
+        return true;
+    }
+
+    let words: Vec<String> = input
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    words.iter().enumerate().any(|(i, word)| {
+        let next = words.get(i + 1).map(String::as_str);
+        let prev = i.checked_sub(1).map(|p| words[p].as_str());
+        match word.as_str() {
+            // 「that output」「previous error」
+            w if LAST_OUTPUT_REFS_EN.contains(&w)
+                && next.is_some_and(|n| LAST_OUTPUT_NOUNS_EN.contains(&n)) =>
+            {
+                true
+            }
+            // 「what is this?」「fix this」「what does that mean?」
+            "this" | "that" => match &words[i + 1..] {
+                [] => true,
+                [last] => LAST_OUTPUT_TRAILING_EN.contains(&last.as_str()),
+                _ => false,
+            },
+            // 「the output above」「the above」
+            "above" => prev.is_some_and(|p| p == "the" || LAST_OUTPUT_NOUNS_EN.contains(&p)),
+            _ => false,
+        }
+    })
+}
+
+/// 新規会話用の BlackBox コンテキストを取得する。
+///
+/// `last_output` が真なら直前 1 件をフルで、そうでなければ直近の履歴を切り詰めて返す。
+fn load_context(bb: &BlackBox, last_output: bool) -> Option<String> {
+    let context = if last_output {
+        bb.get_last_entry_context(LAST_OUTPUT_MAX_CHARS)
+    } else {
+        bb.get_recent_context(AI_CONTEXT_ENTRIES)
+    };
+    context.ok()
+}
+
+/// 入力分類と並行して取得中の BlackBox コンテキスト。
+///
+/// 最終的にコマンドとして実行することになった場合は [`wait`](Self::wait) せずにそのまま捨てる。
+/// `spawn_blocking` のタスクは中断できないため履歴 DB の読み取りは最後まで走るが、
+/// 読み取り専用のため履歴には影響しない。
+pub(super) struct ContextPrefetch {
+    handle: tokio::task::JoinHandle<Option<String>>,
+}
+
+impl ContextPrefetch {
+    /// 先読みの完了を待って結果を受け取る。失敗時は `None`。
+    async fn wait(self) -> Option<String> {
+        match self.handle.await {
+            Ok(context) => context,
+            Err(e) => {
+                warn!(error = %e, "AI context prefetch task failed");
+                None
+            }
+        }
+    }
+}
 
 /// AI ルーティングの結果
 pub(super) struct AiRoutingResult {
@@ -23,24 +153,181 @@ pub(super) struct AiRoutingResult {
     pub executed_command: Option<String>,
 }
 
-/// AI が提案したコマンドを実行し、stdout に実行記録を付与する。
-fn execute_ai_command(cmd: &str) -> CommandResult {
-    jarvis_notice(cmd);
-    let mut result = execute(cmd);
-    if result.stdout.is_empty() {
-        result.stdout = format!("[Jarvis executed: {cmd}]");
-    } else {
-        result.stdout = format!("[Jarvis executed: {cmd}]\n{}", result.stdout);
+/// AI に送るコンテキストの要約を作る（`[ai] preview_context` の確認表示用）。
+///
+/// 作業ディレクトリの行はそのまま、履歴はエントリの見出し行（`[#id] cmd (exit: ..)`）と
+/// 添付される stdout / stderr の行数に縮め、最後にメッセージ本文の文字数を示す。
+fn summarize_context(context: &str, message: &str) -> String {
+    let mut summary = Vec::new();
+    // (stdout 行数, stderr 行数) と、現在読んでいる出力セクション
+    let mut output = (0usize, 0usize);
+    let mut section: Option<bool> = None;
+    let mut entries = 0usize;
+
+    let flush = |summary: &mut Vec<String>, output: &mut (usize, usize)| {
+        if output.0 > 0 || output.1 > 0 {
+            if let Some(last) = summary.last_mut() {
+                last.push_str(&format!(
+                    "  [stdout: {} lines, stderr: {} lines]",
+                    output.0, output.1
+                ));
+            }
+        }
+        *output = (0, 0);
+    };
+
+    for line in context.lines() {
+        if let Some(cwd) = line.strip_prefix("Current working directory: ") {
+            summary.push(format!("cwd: {cwd}"));
+        } else if line.starts_with("[#") {
+            flush(&mut summary, &mut output);
+            summary.push(format!("  {line}"));
+            entries += 1;
+            section = None;
+        } else if line == "stdout:" {
+            section = Some(true);
+        } else if line == "stderr:" {
+            section = Some(false);
+        } else if !line.is_empty() {
+            match section {
+                Some(true) => output.0 += 1,
+                Some(false) => output.1 += 1,
+                None => {}
+            }
+        }
     }
-    result
+    flush(&mut summary, &mut output);
+
+    let history_line = format!("history: {entries} recent command(s)");
+    let position = usize::from(summary.first().is_some_and(|l| l.starts_with("cwd: ")));
+    summary.insert(position, history_line);
+    summary.push(format!("message: {} chars", message.chars().count()));
+    summary.join("\n")
 }
 
 impl Shell {
+    /// AI が提案したコマンドを実行し、stdout に実行記録を付与する。
+    ///
+    /// 実行結果（付与前の stdout/stderr と終了コード）は会話履歴に積み、
+    /// 次のターンで AI がコマンドの結果を参照できるようにする。
+    fn execute_ai_command(&self, cmd: &str, conversation: &mut ConversationState) -> CommandResult {
+        let mut result = self.execute_command_line(cmd);
+        conversation.record_command_result(cmd, &result, self.ai_max_tool_output_lines);
+        if result.stdout.is_empty() {
+            result.stdout = format!("[Jarvis executed: {cmd}]");
+        } else {
+            result.stdout = format!("[Jarvis executed: {cmd}]\n{}", result.stdout);
+        }
+        result
+    }
+
+    /// AI が提案したコマンドをアナウンスして実行し、ルーティング結果を返す。
+    ///
+    /// `[ai] edit_before_run = true`（対話モードのみ）の場合は、アナウンスの後に
+    /// コマンドを入力行にプリフィルし、ユーザーが編集・確定した内容を実行する。
+    /// 空で確定した場合や Ctrl-C / Ctrl-D の場合は実行せずにキャンセルする。
+    fn run_ai_command(
+        &mut self,
+        cmd: &str,
+        conversation: &mut ConversationState,
+    ) -> AiRoutingResult {
+        let reason = conversation.pending_command_reason();
+        jarvis_notice(cmd, reason.as_deref(), self.command_notice);
+
+        let edit = self.ai_edit_before_run && self.interactive;
+        let command = if edit {
+            let Some(edited) = self.edit_ai_command(cmd) else {
+                info!(command = %cmd, "AI command cancelled by user");
+                println!("  Cancelled.");
+                conversation.close_pending_shell_tool_call();
+                return AiRoutingResult {
+                    result: CommandResult::success(String::new()),
+                    from_tool_call: true,
+                    should_update_exit_code: false,
+                    executed_command: None,
+                };
+            };
+            debug!(original = %cmd, edited = %edited, "AI command confirmed on the input line");
+            edited
+        } else {
+            cmd.to_string()
+        };
+
+        let result = self.execute_ai_command(&command, conversation);
+        AiRoutingResult {
+            result,
+            from_tool_call: true,
+            should_update_exit_code: true,
+            // 入力行で確定したコマンドは reedline が履歴に保存済みのため、二重に追加しない
+            executed_command: (!edit).then_some(command),
+        }
+    }
+
+    /// AI のコマンドを入力行にプリフィルして編集させ、確定した内容を返す。
+    ///
+    /// 空（空白のみ）で確定した場合、Ctrl-C / Ctrl-D、読み込みエラーの場合は `None`。
+    fn edit_ai_command(&mut self, cmd: &str) -> Option<String> {
+        self.editor.run_edit_commands(&[
+            EditCommand::Clear,
+            EditCommand::InsertString(cmd.to_string()),
+        ]);
+        let signal = tokio::task::block_in_place(|| self.editor.read_line(&self.prompt));
+        match signal {
+            Ok(Signal::Success(line)) => {
+                let line = line.trim();
+                (!line.is_empty()).then(|| line.to_string())
+            }
+            Ok(_) => None,
+            Err(e) => {
+                warn!(error = %e, "Failed to read edited AI command");
+                None
+            }
+        }
+    }
+
+    /// 新規 AI 会話で使う BlackBox コンテキストの取得をバックグラウンドで開始する。
+    ///
+    /// 分類器が自然言語と判定した入力についてのみ呼び、タイポ確認・オフライン判定や
+    /// ファイル添付の処理と並行して履歴 DB を読むことで、AI へのリクエスト送信までの
+    /// 待ち時間を減らす。
+    /// 継続会話になる場合・AI や BlackBox が無効な場合は `None`。
+    pub(super) fn prefetch_ai_context(&self, line: &str) -> Option<ContextPrefetch> {
+        self.ai_client.as_ref()?;
+        if self
+            .conversation_state
+            .as_ref()
+            .is_some_and(|conv| conv.origin != ConversationOrigin::Investigation)
+        {
+            return None;
+        }
+        // クローンは同じ DB 接続を共有する（読み取り中はシェル側の書き込みを待たせる）
+        let bb = self.black_box.as_ref()?.clone();
+        let last_output = refers_to_last_output(line);
+        let handle = tokio::task::spawn_blocking(move || load_context(&bb, last_output));
+        Some(ContextPrefetch { handle })
+    }
+
+    /// AI が有効で、かつ API に到達できない（オフライン）か。
+    ///
+    /// 判定結果は一定時間使い回すため、オフラインの間は接続を待たずにすぐ返る。
+    pub(super) async fn ai_offline(&self) -> bool {
+        match self.ai_client.as_ref() {
+            Some(ai) => !ai.is_online().await,
+            None => false,
+        }
+    }
+
     /// 自然言語入力を AI にルーティングする。
     ///
+    /// 入力中の `@path` が指すファイルは内容をメッセージに添付して送信する。
     /// 既存の会話コンテキストがある場合は継続会話、なければ新規会話を開始する。
-    /// AI が無効な場合や処理失敗時はエラーメッセージを返す。
-    pub(super) async fn route_to_ai(&mut self, line: &str) -> AiRoutingResult {
+    /// AI が無効な場合・API に到達できない場合や処理失敗時はエラーメッセージを返す。
+    /// `prefetch` は [`Self::prefetch_ai_context`] で先読みした新規会話用のコンテキスト。
+    pub(super) async fn route_to_ai(
+        &mut self,
+        line: &str,
+        prefetch: Option<ContextPrefetch>,
+    ) -> AiRoutingResult {
         if self.ai_client.is_none() {
             debug!(ai_enabled = false, "AI disabled, returning error");
             let msg = "jarvish: AI is not available (API key not configured)\n".to_string();
@@ -53,8 +340,32 @@ impl Shell {
             };
         }
 
+        if let Some(ai) = self.ai_client.as_ref() {
+            if !ai.is_online().await {
+                debug!(host = %ai.reachability_host(), "AI offline, skipping AI request");
+                let msg = format!(
+                    "jarvish: AI offline (cannot reach {})\n",
+                    ai.reachability_host()
+                );
+                eprint!("{msg}");
+                return AiRoutingResult {
+                    result: CommandResult::error(msg, 1),
+                    from_tool_call: false,
+                    should_update_exit_code: false,
+                    executed_command: None,
+                };
+            }
+        }
+
         debug!(ai_enabled = true, "Routing natural language to AI");
 
+        // 添付ファイルの内容に反応しないよう、参照表現の判定は入力そのもので行う
+        let last_output = refers_to_last_output(line);
+
+        // `@path` で指定されたファイルの内容をメッセージに添付する
+        let message = attach::embed_file_attachments(line);
+        let line = message.as_str();
+
         // 既存の会話コンテキストがある場合は継続、なければ新規会話。
         // ただしエラー調査由来の会話は自然言語入力に流用しない。
         let existing_conv = self.conversation_state.take();
@@ -74,14 +385,9 @@ impl Shell {
                             command = %cmd,
                             "AI continued conversation with a command"
                         );
-                        let result = execute_ai_command(cmd);
+                        let routing = self.run_ai_command(cmd, &mut conv);
                         self.conversation_state = Some(conv);
-                        return AiRoutingResult {
-                            result,
-                            from_tool_call: true,
-                            should_update_exit_code: true,
-                            executed_command: Some(cmd.clone()),
-                        };
+                        return routing;
                     }
                     Ok(AiResponse::NaturalLanguage(ref text)) => {
                         debug!(
@@ -109,16 +415,49 @@ impl Shell {
         }
 
         // === 新規会話 ===
-        self.start_new_ai_conversation(line).await
+        self.start_new_ai_conversation(line, prefetch, last_output)
+            .await
+    }
+
+    /// `[ai] preview_context = true`（対話モードのみ）の場合、送信するコンテキストの要約を
+    /// 表示して送信の是非を確認する。送信してよければ `true`。
+    ///
+    /// 「今後確認しない」を選んだ場合は、このセッションの間は確認を省略する。
+    fn confirm_context(&mut self, context: &str, message: &str) -> bool {
+        if !self.ai_preview_context || self.context_preview_muted || !self.interactive {
+            return true;
+        }
+        match jarvis_ask_send_context(&summarize_context(context, message)) {
+            ContextPreviewAction::Send => true,
+            ContextPreviewAction::SendAlways => {
+                debug!("Context preview muted for this session");
+                self.context_preview_muted = true;
+                true
+            }
+            ContextPreviewAction::Cancel => false,
+        }
     }
 
     /// BlackBox コンテキストを取得して新規 AI 会話を開始する。
-    async fn start_new_ai_conversation(&mut self, line: &str) -> AiRoutingResult {
-        let ai = self.ai_client.as_ref().unwrap();
-        let bb_context = self
-            .black_box
-            .as_ref()
-            .and_then(|bb| bb.get_recent_context(5).ok())
+    ///
+    /// 先読みの結果があればそれを使い、なければその場で取得する。
+    /// `last_output` は入力が直前のコマンドを指しているか（[`refers_to_last_output`]）。
+    async fn start_new_ai_conversation(
+        &mut self,
+        line: &str,
+        prefetch: Option<ContextPrefetch>,
+        last_output: bool,
+    ) -> AiRoutingResult {
+        let prefetched = match prefetch {
+            Some(prefetch) => prefetch.wait().await,
+            None => None,
+        };
+        let bb_context = prefetched
+            .or_else(|| {
+                self.black_box
+                    .as_ref()
+                    .and_then(|bb| load_context(bb, last_output))
+            })
             .unwrap_or_default();
 
         let cwd = std::env::current_dir()
@@ -128,21 +467,31 @@ impl Shell {
 
         debug!(context_length = context.len(), cwd = %cwd, "Context retrieved for AI");
 
+        if !self.confirm_context(&context, line) {
+            info!("AI request cancelled at context preview");
+            println!("  Cancelled.");
+            return AiRoutingResult {
+                result: CommandResult::success(String::new()),
+                from_tool_call: false,
+                should_update_exit_code: false,
+                executed_command: None,
+            };
+        }
+
+        let ai = self.ai_client.as_ref().unwrap();
+
         match ai.process_input(line, &context).await {
-            Ok(conv_result) => match conv_result.response {
+            Ok(mut conv_result) => match conv_result.response {
                 AiResponse::Command(ref cmd) => {
                     debug!(
                         ai_response = "Command",
                         command = %cmd,
                         "AI interpreted natural language as a command"
                     );
-                    let result = execute_ai_command(cmd);
-                    AiRoutingResult {
-                        result,
-                        from_tool_call: true,
-                        should_update_exit_code: true,
-                        executed_command: Some(cmd.clone()),
-                    }
+                    let routing = self.run_ai_command(cmd, &mut conv_result.conversation);
+                    // コマンドの実行結果を含む会話を保持し、続く質問で参照できるようにする
+                    self.conversation_state = Some(conv_result.conversation);
+                    routing
                 }
                 AiResponse::NaturalLanguage(ref text) => {
                     debug!(
@@ -165,6 +514,8 @@ impl Shell {
                     input = %line,
                     "AI processing failed"
                 );
+                // 接続できずに失敗した場合は、以降のリクエストをバックオフの間スキップする
+                ai.recheck_online().await;
                 let msg = format!("jarvish: AI processing failed: {e}\n");
                 eprint!("{msg}");
                 AiRoutingResult {
@@ -176,4 +527,84 @@ impl Shell {
             }
         }
     }
+
+    /// Goodbye 入力を AI に送り、別れの挨拶を表示させる（`[ai] farewell = true` 時）。
+    ///
+    /// AI が自然言語で応答した場合は farewell 表示済みとし、終了時の goodbye バナーを省略する。
+    /// AI が無効・失敗した場合やコマンドを返した場合（実行はしない）はバナーで締める。
+    pub(super) async fn say_ai_farewell(&mut self, line: &str) {
+        let Some(ai) = self.ai_client.as_ref() else {
+            debug!("AI disabled, skipping AI farewell");
+            return;
+        };
+
+        let response = match self.conversation_state.as_mut() {
+            Some(conv) => ai.continue_conversation(conv, line).await,
+            None => ai.process_input(line, "").await.map(|r| r.response),
+        };
+        match response {
+            Ok(AiResponse::NaturalLanguage(_)) => self.farewell_shown = true,
+            Ok(AiResponse::Command(cmd)) => {
+                debug!(command = %cmd, "AI returned a command for goodbye, not executing");
+            }
+            Err(e) => warn!(error = %e, "AI farewell failed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refers_to_last_output_detects_references_to_previous_command() {
+        assert!(refers_to_last_output("jarvis, これは?"));
+        assert!(refers_to_last_output("さっきのエラーを直して"));
+        assert!(refers_to_last_output("What does this mean?"));
+        assert!(refers_to_last_output("explain the output ABOVE"));
+        assert!(!refers_to_last_output("jarvis, list large files"));
+        assert!(refers_to_last_output("これ直して"));
+        assert!(refers_to_last_output("jarvis, この出力は?"));
+        assert!(refers_to_last_output("What does that output mean?"));
+        assert!(refers_to_last_output("fix the error above"));
+        assert!(refers_to_last_output("what is that"));
+        // 別の物を指す指示語には反応しない
+        assert!(!refers_to_last_output("その後どうすればいい?"));
+        assert!(!refers_to_last_output("この関数の使い方を教えて"));
+        assert!(!refers_to_last_output("それぞれのファイルサイズを教えて"));
+        assert!(!refers_to_last_output("is this a good idea?"));
+        assert!(!refers_to_last_output("how do I do that in rust"));
+        // 単語の一部には反応しない
+        assert!(!refers_to_last_output(
+            "show thistle and abovementioned files"
+        ));
+    }
+
+    #[test]
+    fn summarize_context_lists_entries_and_output_sizes() {
+        let context = "Current working directory: /work\n\n\
+             === Recent Command History ===\n\
+             \n[#2] cargo build (exit: 101, cwd: /work)\n\
+             stderr:\nerror: a\nerror: b\n\
+             \n[#1] ls (exit: 0, cwd: /work)\n\
+             stdout:\nCargo.toml\n";
+        let summary = summarize_context(context, "why did it fail?");
+        assert_eq!(
+            summary,
+            "cwd: /work\n\
+             history: 2 recent command(s)\n\
+             \x20 [#2] cargo build (exit: 101, cwd: /work)  [stdout: 0 lines, stderr: 2 lines]\n\
+             \x20 [#1] ls (exit: 0, cwd: /work)  [stdout: 1 lines, stderr: 0 lines]\n\
+             message: 16 chars"
+        );
+    }
+
+    #[test]
+    fn summarize_context_without_history() {
+        let summary = summarize_context("Current working directory: /tmp\n\n", "こんにちは");
+        assert_eq!(
+            summary,
+            "cwd: /tmp\nhistory: 0 recent command(s)\nmessage: 5 chars"
+        );
+    }
 }
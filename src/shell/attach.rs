@@ -0,0 +1,158 @@
+//! AI へのファイル添付（`@path` 構文）
+//!
+//! `jarvis, @src/main.rs のバグを直して` のように、自然言語入力中の `@path` トークンが
+//! 指すファイルを読み込み、ユーザーメッセージの末尾に `[File: path]` ブロックとして埋め込む。
+//! AI が `read_file` ツールで探しに行くのと違い、ユーザーが対象を明示できる。
+
+use tracing::{debug, warn};
+
+/// 添付 1 ファイルあたりの最大行数。超過分は省略して注記する
+const ATTACH_MAX_LINES: usize = 1000;
+
+/// 添付パスの末尾から取り除く句読点（`@foo.rs,` や `@foo.rs。` への対応）
+const TRAILING_PUNCTUATION: &[char] = &[',', ';', ':', '!', '?', ')', '、', '。', '」', '）'];
+
+/// 入力中の `@path` トークンを検出し、ファイル内容を埋め込んだメッセージを返す。
+///
+/// 元の入力はそのまま残し、その後ろに `[File: path]\n<content>` を添付順に連結する。
+/// 同じパスは一度だけ添付する。存在しない・読めないパスは警告を表示してスキップする。
+/// 添付するファイルがなければ入力をそのまま返す。
+pub(super) fn embed_file_attachments(line: &str) -> String {
+    let mut attached: Vec<String> = Vec::new();
+    let mut message = line.to_string();
+
+    for path in attachment_paths(line) {
+        if attached.contains(&path) {
+            continue;
+        }
+        match read_attachment(&path) {
+            Ok(content) => {
+                println!("  📎 Attached: {path}");
+                message.push_str(&format!("\n\n[File: {path}]\n{content}"));
+                attached.push(path);
+            }
+            Err(e) => {
+                warn!(path = %path, error = %e, "Failed to attach file");
+                eprintln!("jarvish: warning: @{path}: {e} (skipped)");
+            }
+        }
+    }
+
+    debug!(
+        attached = attached.len(),
+        message_length = message.len(),
+        "File attachments embedded"
+    );
+    message
+}
+
+/// 入力から `@path` トークンのパス部分を出現順に取り出す。
+///
+/// トークンは空白区切りで `@` から始まるもの（`user@host` のような途中の `@` は対象外）。
+/// そのままのパスが存在しない場合は、末尾の句読点を除いたパスを使う。
+fn attachment_paths(line: &str) -> Vec<String> {
+    line.split_whitespace()
+        .filter_map(|token| token.strip_prefix('@'))
+        .filter(|raw| !raw.is_empty())
+        .filter_map(|raw| {
+            if std::path::Path::new(&expand_home(raw)).exists() {
+                return Some(raw.to_string());
+            }
+            let trimmed = raw.trim_end_matches(TRAILING_PUNCTUATION);
+            (!trimmed.is_empty()).then(|| trimmed.to_string())
+        })
+        .collect()
+}
+
+/// `~/` で始まるパスをホームディレクトリに展開する。
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
+
+/// 添付ファイルを読み込む。[`ATTACH_MAX_LINES`] を超える場合は先頭部分のみ返す。
+fn read_attachment(path: &str) -> std::io::Result<String> {
+    let resolved = expand_home(path);
+    if std::path::Path::new(&resolved).is_dir() {
+        return Err(std::io::Error::other("is a directory"));
+    }
+    let content = std::fs::read_to_string(&resolved)?;
+
+    let total = content.lines().count();
+    if total <= ATTACH_MAX_LINES {
+        return Ok(content);
+    }
+    let mut head: String = content
+        .lines()
+        .take(ATTACH_MAX_LINES)
+        .flat_map(|l| [l, "\n"])
+        .collect();
+    head.push_str(&format!(
+        "... (truncated: showing first {ATTACH_MAX_LINES} of {total} lines)\n"
+    ));
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_at_tokens_only_at_word_start() {
+        let paths = attachment_paths("jarvis, @src/main.rs と @Cargo.toml を見て user@example.com");
+        assert_eq!(paths, vec!["src/main.rs", "Cargo.toml"]);
+    }
+
+    #[test]
+    fn strips_trailing_punctuation_for_missing_paths() {
+        let paths = attachment_paths("check @/nonexistent_jarvish/a.rs, please");
+        assert_eq!(paths, vec!["/nonexistent_jarvish/a.rs"]);
+    }
+
+    #[test]
+    fn embeds_file_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("note.txt");
+        std::fs::write(&file, "hello\nworld\n").unwrap();
+        let path = file.to_str().unwrap();
+
+        let message = embed_file_attachments(&format!("summarize @{path} and @{path}"));
+        assert_eq!(
+            message,
+            format!("summarize @{path} and @{path}\n\n[File: {path}]\nhello\nworld\n")
+        );
+    }
+
+    #[test]
+    fn missing_file_is_skipped() {
+        let line = "fix @/nonexistent_jarvish/missing.rs";
+        assert_eq!(embed_file_attachments(line), line);
+    }
+
+    #[test]
+    fn directory_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let line = format!("look at @{}", dir.path().display());
+        assert_eq!(embed_file_attachments(&line), line);
+    }
+
+    #[test]
+    fn long_file_is_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("long.txt");
+        let content: String = (1..=ATTACH_MAX_LINES + 10)
+            .map(|i| format!("line {i}\n"))
+            .collect();
+        std::fs::write(&file, content).unwrap();
+
+        let attached = read_attachment(file.to_str().unwrap()).unwrap();
+        assert!(attached.contains(&format!("line {ATTACH_MAX_LINES}\n")));
+        assert!(!attached.contains(&format!("line {}\n", ATTACH_MAX_LINES + 1)));
+        assert!(attached.ends_with(&format!(
+            "(truncated: showing first {ATTACH_MAX_LINES} of {} lines)\n",
+            ATTACH_MAX_LINES + 10
+        )));
+    }
+}
@@ -1,7 +1,7 @@
 //! reedline エディタの構築
 //!
-//! ハイライター、補完、キーバインディング、履歴、オートサジェストを設定した
-//! reedline エディタを構築する。
+//! ハイライター、補完、キーバインディング、履歴、オートサジェスト、履歴検索メニューを
+//! 設定した reedline エディタを構築する。
 
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -9,7 +9,7 @@ use std::sync::{Arc, RwLock};
 
 use nu_ansi_term::{Color, Style};
 use reedline::{
-    default_emacs_keybindings, ColumnarMenu, DefaultHinter, Emacs, KeyCode, KeyModifiers,
+    default_emacs_keybindings, ColumnarMenu, Emacs, History, KeyCode, KeyModifiers, ListMenu,
     MenuBuilder, Reedline, ReedlineEvent, ReedlineMenu,
 };
 
@@ -17,6 +17,8 @@ use crate::cli::completer::{
     registry::CompletionRegistry, ExternalCompletionSettings, JarvishCompleter, SharedDaemonSlot,
 };
 use crate::cli::highlighter::JarvisHighlighter;
+use crate::cli::hinter::JarvisHinter;
+use crate::cli::validator::JarvishValidator;
 use crate::engine::classifier::InputClassifier;
 use crate::storage::BlackBoxHistory;
 
@@ -25,6 +27,19 @@ use crate::storage::BlackBoxHistory;
 ///
 /// `db_path` は BlackBox と共有する `history.db` へのパス。
 ///
+/// `hints` が `true`（`[editor] hints`）なら履歴からのオートサジェストを表示し、
+/// 右矢印 / Ctrl-F で候補を受け入れる。
+///
+/// 行末の `\` や未閉じのクオート（コマンド入力のみ）で Enter を押すと、確定せずに
+/// 継続行（`::`）の入力に入る。
+///
+/// キーバインドを変更したら `help keys` の一覧
+/// （`engine::builtins::help::KEY_BINDINGS`）も合わせて更新する。
+///
+/// Ctrl-R は履歴検索メニューを開く。入力した文字列を部分一致で含む履歴を
+/// 一覧表示し（一致部分をハイライト）、上下キーで選んで Enter で入力行に展開する
+/// （実行はせず、そのまま編集できる）。Ctrl-R を続けて押すと次のページに進む。
+///
 /// `zsh_daemon` は温存 zsh 補完デーモンのスロットを `Shell` と共有する
 /// `Arc`（Task A, #89）。`Shell` はこれを経由して reload/exit/restart など
 /// `provide()` が次に呼ばれるとは限らないライフサイクルイベント上でも
@@ -33,6 +48,7 @@ use crate::storage::BlackBoxHistory;
 /// # Returns
 /// `(Reedline, bool)` — `bool` はコマンド履歴の読み込みに成功したかどうか。
 /// `false` の場合、矢印キー履歴とオートサジェスト（ヒンター）は無効。
+/// `hints` の切り替えはエディタ構築時にのみ反映される（再起動で適用）。
 // Shell::new から配管される共有状態（Arc）を素直に受け取っているだけで、
 // 個々のパラメータに強い結びつきはない（パラメータオブジェクト化するほどの
 // 複雑さではない）ため、警告を抑制する。
@@ -46,6 +62,7 @@ pub fn build_editor(
     external_completion: Arc<RwLock<ExternalCompletionSettings>>,
     zsh_daemon: SharedDaemonSlot,
     complete_registry: Arc<RwLock<CompletionRegistry>>,
+    hints: bool,
 ) -> (Reedline, bool) {
     let completer = Box::new(JarvishCompleter::new(
         git_branch_commands,
@@ -55,6 +72,14 @@ pub fn build_editor(
         complete_registry,
     ));
     let completion_menu = Box::new(ColumnarMenu::default().with_name("completion_menu"));
+    let history_menu = Box::new(
+        ListMenu::default()
+            .with_name("history_menu")
+            .with_marker("history? ")
+            .with_only_buffer_difference(false)
+            .with_match_text_style(Style::new().fg(Color::Yellow).bold())
+            .with_selected_match_text_style(Style::new().fg(Color::Yellow).bold().reverse()),
+    );
 
     let mut keybindings = default_emacs_keybindings();
     keybindings.add_binding(
@@ -66,22 +91,52 @@ pub fn build_editor(
         ]),
     );
 
+    // Ctrl-R: 履歴検索メニュー（既定のインライン逆方向検索を置き換える）
+    keybindings.add_binding(
+        KeyModifiers::CONTROL,
+        KeyCode::Char('r'),
+        ReedlineEvent::UntilFound(vec![
+            ReedlineEvent::Menu("history_menu".to_string()),
+            ReedlineEvent::MenuPageNext,
+        ]),
+    );
+
+    // 右矢印 / Ctrl-F: ヒントがあれば受け入れ、なければ通常のカーソル移動
+    for (modifiers, code) in [
+        (KeyModifiers::NONE, KeyCode::Right),
+        (KeyModifiers::CONTROL, KeyCode::Char('f')),
+    ] {
+        keybindings.add_binding(
+            modifiers,
+            code,
+            ReedlineEvent::UntilFound(vec![
+                ReedlineEvent::HistoryHintComplete,
+                ReedlineEvent::MenuRight,
+                ReedlineEvent::Right,
+            ]),
+        );
+    }
+
     let mut editor = Reedline::create()
-        .with_highlighter(Box::new(JarvisHighlighter::new(classifier)))
+        .with_highlighter(Box::new(JarvisHighlighter::new(Arc::clone(&classifier))))
+        .with_validator(Box::new(JarvishValidator::new(classifier)))
         .with_completer(completer)
         .with_menu(ReedlineMenu::EngineCompleter(completion_menu))
+        .with_menu(ReedlineMenu::HistoryMenu(history_menu))
         .with_edit_mode(Box::new(Emacs::new(keybindings)));
 
     // コマンド履歴を BlackBox の SQLite テーブル (command_history) で管理。
+    // 上下矢印は現セッション（+ 終了済みセッション）の履歴、Ctrl-R は全セッションの履歴を辿る。
     // DB オープンに失敗した場合は警告を出力し、履歴・ヒンターなしで動作を継続する。
     let history_available = match BlackBoxHistory::open(db_path, session_id) {
         Ok(history) => {
-            let hinter = Box::new(
-                DefaultHinter::default()
-                    .with_style(Style::new().fg(Color::DarkGray))
-                    .with_min_chars(2),
-            );
-            editor = editor.with_history(Box::new(history)).with_hinter(hinter);
+            let session = history.session();
+            editor = editor
+                .with_history(Box::new(history))
+                .with_history_session_id(session);
+            if hints {
+                editor = editor.with_hinter(Box::new(JarvisHinter::default()));
+            }
             true
         }
         Err(e) => {
@@ -3,6 +3,7 @@
 //! ユーザー入力を受け取り、ビルトイン/コマンド/自然言語を分類し、
 //! 適切な実行パスに振り分ける。
 
+use std::io::{IsTerminal, Write};
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, RwLock};
 use std::time::Instant;
@@ -13,14 +14,29 @@ use reedline::HistoryItem;
 
 use crate::cli::completer::registry::CompletionRegistry;
 use crate::cli::prompt::starship::CMD_DURATION_NONE;
-
-use crate::cli::jarvis::{jarvis_ask_typo_correction, TypoAction};
-use crate::engine::builtins::{alias, cd, cdj, complete, dirstack, source, unalias, which_type};
-use crate::engine::classifier::{is_ai_goodbye_response, InputType};
+use crate::cli::prompt::title;
+use crate::cli::validator;
+
+use crate::cli::jarvis::{
+    jarvis_ask_autocorrect, jarvis_ask_exit, jarvis_ask_typo_correction, TypoAction,
+};
+use crate::config::UrlAction;
+use crate::engine::builtins::{
+    ai as ai_builtin, alias, bookmark, capture, cd, cdj, complete, dirstack, exit, history,
+    is_builtin, is_builtin_call, source, unalias, which_type,
+};
+use crate::engine::classifier::{
+    is_ai_goodbye_response, strip_goodbye_marker, ClassifyReason, InputClassifier, InputType,
+    OverrideKind, OverrideScope,
+};
 use crate::engine::dispatch::{AiPipeMode, AiPipeRequest};
+use crate::engine::dry_run;
+use crate::engine::exec;
 use crate::engine::expand;
 use crate::engine::typo;
-use crate::engine::{execute, try_builtin, try_execute_ai_pipe, CommandResult, LoopAction};
+use crate::engine::{
+    execute, try_builtin, try_execute_ai_pipe, CommandResult, ExecContext, LoopAction,
+};
 
 use super::Shell;
 
@@ -31,13 +47,48 @@ impl Shell {
     pub(super) async fn handle_input(&mut self, line: &str) -> bool {
         info!("\n\n==== USER INPUT RECEIVED, START PROCESS ====");
 
+        // 前の入力（`export PATH=...`・`a && PATH=...`・AI が実行したコマンド等）で `$PATH` が
+        // 変わっていれば、分類の前に PATH lookup キャッシュを差分更新する
+        self.classifier.reload_path_cache_incremental();
+
+        // 複数行入力（行継続 `\` + 改行）を 1 コマンドに結合する
+        let line = validator::join_continued_lines(line.trim());
         let line = line.trim().to_string();
 
         if line.is_empty() {
             return true;
         }
 
-        // 0. エイリアス展開（先頭トークンがエイリアスに一致すれば置換）
+        // 0. `:explain [input]`: 入力（省略時は直前の入力）の分類結果と判定理由を表示する
+        if let Some(target) = parse_explain(&line) {
+            self.explain_classification(target);
+            return true;
+        }
+
+        // 直前の入力が AI との会話だったか（この入力がビルトイン等で終わってもクリアされる）
+        let after_ai_turn = std::mem::take(&mut self.after_ai_turn);
+
+        // 0.02. `history run N`: 履歴のコマンドを入力し直したものとして以降の処理に通す
+        //       （エイリアス展開・AI 判定・cd フック・履歴記録も通常の入力と同じく行う）
+        let line = match history::parse_run_request(&line) {
+            Some(id) => match history::resolve_run_entry(id) {
+                Ok(command) => command,
+                Err(result) => return self.handle_builtin(&line, &line, result),
+            },
+            None => line,
+        };
+
+        // 0.05. 分類訂正プレフィックス（`:ai` / `:cmd`）: 分類を強制し、以後のために学習する
+        let (line, forced_type) = match parse_classifier_correction(&line) {
+            Some(correction) => match self.apply_classifier_correction(correction) {
+                Some((target, input_type)) => (target, Some(input_type)),
+                None => return true,
+            },
+            None => (line, None),
+        };
+        self.last_input = Some(line.clone());
+
+        // 0.1. エイリアス展開（先頭トークンがエイリアスに一致すれば置換）
         // 履歴にはユーザーが実際に入力した文字列を記録するため、展開前の入力を保持する
         let original_line = line.clone();
         // read ガードは短命スコープで取得し、await を跨いで保持しない
@@ -56,22 +107,96 @@ impl Shell {
 
         debug!(input = %line, "User input received");
 
+        // 学習済みオーバーライドで自然言語とされた入力はビルトインとしても扱わない
+        // AI ルーティング無効時（stdin パイプで `--ai` 未指定）は全てコマンドとして扱う
+        let forced_type = forced_type
+            .or_else(|| self.classifier.override_for(&line))
+            .or_else(|| (!self.ai_routing).then_some(InputType::Command));
+        // `which is better` / `help me ...` のようにビルトイン名で始まる自然言語もビルトインにしない
+        let skip_builtins = forced_type == Some(InputType::NaturalLanguage)
+            || (forced_type.is_none() && self.classifier.is_command_word_question(&line));
+
+        // 0.4. AUTO_CD（`[shell] auto_cd = true`）: 既存ディレクトリだけの入力は `cd` として扱う
+        //      ディレクトリの存在確認を自然言語判定より優先する（`:ai` で強制した場合は除く）
+        let auto_cd = if self.auto_cd && forced_type != Some(InputType::NaturalLanguage) {
+            self.classifier
+                .auto_cd_target(&line)
+                .map(|dir| format!("cd {dir}"))
+        } else {
+            None
+        };
+        let line = match auto_cd {
+            Some(cd_line) => {
+                debug!(input = %line, expanded = %cd_line, "AUTO_CD: treating directory as cd");
+                cd_line
+            }
+            None => line,
+        };
+
+        // 0.45. URL だけの入力（`[shell] url_action`）: コマンドとして実行して失敗させず、
+        //       AI に委ねる / open で開く / エラーにする（`:ai` / `:cmd` で強制した場合は除く）
+        let url = match forced_type {
+            None => InputClassifier::url_target(&line).map(str::to_string),
+            Some(_) => None,
+        };
+        let (line, forced_type) = match (url, self.url_action) {
+            (Some(_), UrlAction::Ai) => {
+                debug!(input = %line, "URL input: routing to AI");
+                (line, Some(InputType::NaturalLanguage))
+            }
+            (Some(url), UrlAction::Open) => {
+                let open_line = format!("{URL_OPENER} '{url}'");
+                debug!(input = %line, expanded = %open_line, "URL input: opening");
+                (open_line, Some(InputType::Command))
+            }
+            (Some(url), UrlAction::Error) => {
+                let msg = format!(
+                    "jarvish: {url}: is a URL, not a command (set [shell] url_action = \"open\" to open it)\n"
+                );
+                eprint!("{msg}");
+                return self.handle_builtin(&original_line, &line, CommandResult::error(msg, 1));
+            }
+            (None, _) => (line, forced_type),
+        };
+
         // 0.5. alias / unalias / source は Shell 状態を操作するためインターセプト
-        if let Some(result) = self.try_shell_builtins(&line) {
-            return self.handle_builtin(&original_line, &line, result);
+        if !skip_builtins {
+            if let Some(result) = self.try_shell_builtins(&line) {
+                return self.handle_builtin(&original_line, &line, result);
+            }
         }
 
         // 1. ビルトインコマンドをチェック（cd, cwd, exit, export 等は AI を介さず直接実行）
-        if let Some(result) = try_builtin(&line) {
-            return self.handle_builtin(&original_line, &line, result);
+        if !skip_builtins {
+            if let Some(result) = try_builtin(&line, &self.exec_context) {
+                return self.handle_builtin(&original_line, &line, result);
+            }
         }
 
         // 2. アルゴリズムで入力を分類（AI を呼ばず瞬時に判定）
-        let input_type = self.classifier.classify(&line);
-        debug!(input = %line, classification = ?input_type, "Input classified");
+        //    `:ai` / `:cmd` による強制指定があればそれを優先する
+        let input_type = forced_type
+            .clone()
+            .unwrap_or_else(|| self.classifier.classify_with_context(&line, after_ai_turn));
+        debug!(input = %line, classification = ?input_type, forced = forced_type.is_some(), "Input classified");
+
+        // 2.1. 自然言語と判定した入力は、AI に渡す履歴コンテキストをタイポ確認・オフライン判定と
+        //      並行して先読みする（コマンドの実行ごとに履歴 DB を読まないよう、分類の後に始める）。
+        //      最終的にコマンドとして実行することになった場合、先読みの結果は使わずに
+        //      ステップ3を抜けた時点で捨てる
+        let context_prefetch = if input_type == InputType::NaturalLanguage {
+            self.prefetch_ai_context(&line)
+        } else {
+            None
+        };
 
         // 2.5. タイポ補正チェック（NaturalLanguage 判定かつコマンド名らしい入力に限定）
-        let (line, input_type) = if input_type == InputType::NaturalLanguage {
+        //      ユーザーが明示的に分類を指定した場合は補正を提案しない
+        //      stdin が端末でない場合は確認プロンプトが後続の入力行を読んでしまうため行わない
+        let (line, input_type) = if input_type == InputType::NaturalLanguage
+            && forced_type.is_none()
+            && std::io::stdin().is_terminal()
+        {
             match check_typo_correction(&line) {
                 TypoCorrectionOutcome::UseCommand(corrected) => {
                     let new_type = self.classifier.classify(&corrected);
@@ -84,35 +209,90 @@ impl Shell {
             (line, input_type)
         };
 
+        // 2.52. AI がオフライン（API に到達できない）なら、接続を待たずにコマンドとして実行する
+        //       `:ai` で AI を明示した場合はフォールバックせず、route_to_ai がオフラインを伝える
+        let input_type = if input_type == InputType::NaturalLanguage
+            && forced_type.is_none()
+            && self.ai_offline().await
+        {
+            info!(input = %line, "AI offline, falling back to command execution");
+            eprintln!("jarvish: AI offline, running as a command");
+            InputType::Command
+        } else {
+            input_type
+        };
+
+        // 2.55. 構文チェック: コマンドとして実行する入力に構文エラーがあれば、
+        //       タイポ補正・AI パイプ検出・実行に進まずエラーを表示する（自然言語は対象外）
+        //       結果の処理（終了コード・履歴）はビルトインと同じ
+        if input_type == InputType::Command {
+            if let Some(e) = validator::syntax_error(&line) {
+                debug!(input = %line, error = %e, "Syntax error detected before execution");
+                let msg = format!("jarvish: {e}\n");
+                eprint!("{msg}");
+                return self.handle_builtin(&original_line, &line, CommandResult::error(msg, 1));
+            }
+        }
+
         // 3. 入力タイプに応じて実行（実行時間を計測）
         //    `is_ai_response`: この出力が AI（Jarvis）の発話かどうか。
         //    goodbye 検出（ステップ8）は AI 応答に対してのみ行うべきで、
         //    人間が打った通常コマンドの stdout を farewell 判定に回してはならない。
-        let start = Instant::now();
-        let (result, from_tool_call, should_update_exit_code, executed_command, is_ai_response) =
+        let mut start = Instant::now();
+        // オートコレクトで修正して実行したコマンド（ステップ 3 の Command 分岐で設定）
+        let mut autocorrected: Option<String> = None;
+        let (mut result, from_tool_call, should_update_exit_code, executed_command, is_ai_response) =
             match input_type {
                 InputType::Goodbye => {
                     // Goodbye → シェル終了（farewell メッセージは run() 側で表示）
-                    info!("Goodbye input detected, exiting shell");
+                    // `[ai] farewell = true` なら AI の別れの挨拶を待ってから終了する
+                    info!(
+                        ai_farewell = self.ai_farewell,
+                        "Goodbye input detected, exiting shell"
+                    );
+                    if self.ai_farewell {
+                        self.say_ai_farewell(&line).await;
+                    }
                     return false;
                 }
                 InputType::Command => {
                     // AI パイプ / リダイレクト検出:
                     // `cmd | ai "prompt"` または `cmd > ai "prompt"` をインターセプト
-                    if let Some(ai_pipe_req) = try_execute_ai_pipe(&line) {
+                    // dry-run 中は AI パイプも実行せず、execute() のプレビューに任せる
+                    let hooked = self.begin_exec(&line);
+                    let ai_pipe_req = if dry_run::is_enabled() {
+                        None
+                    } else {
+                        try_execute_ai_pipe(&line, &self.exec_context)
+                    };
+                    if let Some(ai_pipe_req) = ai_pipe_req {
                         debug!(input = %line, mode = ?ai_pipe_req.mode, "AI pipe/redirect detected");
+                        // フックの対象は手前のパイプラインの実行まで（AI の応答待ちは含めない）
+                        self.end_exec(hooked, &line, ai_pipe_req.exit_code);
                         let result = self.handle_ai_pipe(ai_pipe_req).await;
                         // AI パイプの出力は AI の発話なので goodbye 判定の対象
                         (result, false, true, None, true)
                     } else {
                         debug!(input = %line, "Executing as command (no AI)");
+                        let result = execute(&line, &self.exec_context);
+                        self.end_exec(hooked, &line, result.exit_code);
                         // 通常コマンドの stdout は人間の打鍵結果。goodbye 判定に回さない。
-                        (execute(&line), false, true, None, false)
+                        // オートコレクト（`[shell] autocorrect = true`）: 先頭コマンドが
+                        // 見つからず、編集距離 1 の候補が 1 つに絞れれば確認して修正後を実行する
+                        match self.autocorrect_command(&line, &result) {
+                            Some(corrected) => {
+                                start = Instant::now();
+                                let result = self.execute_command_line(&corrected);
+                                autocorrected = Some(corrected.clone());
+                                (result, false, true, Some(corrected), false)
+                            }
+                            None => (result, false, true, None, false),
+                        }
                     }
                 }
                 InputType::NaturalLanguage => {
                     // 自然言語 → AI にルーティング
-                    let ai_result = self.route_to_ai(&line).await;
+                    let ai_result = self.route_to_ai(&line, context_prefetch).await;
                     (
                         ai_result.result,
                         ai_result.from_tool_call,
@@ -124,6 +304,26 @@ impl Shell {
             };
         let elapsed_ms = start.elapsed().as_millis() as u64;
         self.cmd_duration_ms.store(elapsed_ms, Ordering::Relaxed);
+        self.after_ai_turn = is_ai_turn(&input_type);
+
+        // オートコレクトで修正した場合は、修正後のコマンドを入力として履歴に記録し、
+        // エラー調査の対象にする
+        let (line, original_line) = match autocorrected {
+            Some(corrected) => (corrected.clone(), corrected),
+            None => (line, original_line),
+        };
+
+        // 3.5. 自然言語応答の末尾の会話終了マーカーを取り除き、goodbye 判定（ステップ8）に使う
+        //      マーカーを指示していない AI パイプ/リダイレクトの応答は `None`（フレーズで判定）
+        let goodbye_marked = (input_type == InputType::NaturalLanguage).then(|| {
+            match strip_goodbye_marker(&result.stdout) {
+                Some(stripped) => {
+                    result.stdout = stripped;
+                    true
+                }
+                None => false,
+            }
+        });
 
         // 4. プロンプト表示用に終了コードを更新
         // AI の NaturalLanguage 応答時はコマンド未実行のためスキップ
@@ -132,6 +332,19 @@ impl Shell {
                 .store(result.exit_code, Ordering::Relaxed);
         }
 
+        // 4.1. `[shell] bell_on_error` / `bell_on_ai_response` が有効ならベルを鳴らす
+        let failed = should_update_exit_code
+            && result.exit_code != 0
+            && result.exit_code != exec::STOPPED_EXIT_CODE;
+        if should_ring_bell(
+            self.bell_on_error,
+            self.bell_on_ai_response,
+            failed,
+            is_ai_response,
+        ) {
+            ring_bell();
+        }
+
         // 4.5. Alternate Screen 復元後、カーソルを旧プロンプト領域の下に押し下げる。
         // ターミナルが復元した旧画面（旧プロンプト+コマンド）はそのまま残し、
         // 追加の改行で reedline の新プロンプトが旧内容を上書きしないようにする。
@@ -139,12 +352,20 @@ impl Shell {
             println!();
         }
 
-        println!(); // 実行結果の後に空行を追加
+        print!("{}", blank_line_after(&result, is_ai_response)); // 実行結果の後に空行を 1 行だけ入れる
 
         // 5. 履歴を記録（エイリアス展開前の入力を記録する）
-        self.record_history(&original_line, &result);
+        //    所要時間は人間が打ったコマンドのみ保存する（AI の応答時間は `history profile` の対象外）
+        //    コマンドを実行しなかった AI との会話は `[history] record_ai_conversation = false` なら残さない
+        let duration_ms = (!is_ai_response).then_some(elapsed_ms);
+        if is_ai_response && !should_update_exit_code && !self.record_ai_conversation {
+            self.discard_history(&original_line);
+        } else {
+            self.record_history(&original_line, &result, duration_ms);
+        }
 
-        // 6. AI が実行したコマンドを reedline 履歴に追加（矢印キーで辿れるようにする）
+        // 6. AI が実行したコマンド・オートコレクト後のコマンドを reedline 履歴に追加
+        //    （矢印キーで辿れるようにする）
         if let Some(ref cmd) = executed_command {
             if let Err(e) = self
                 .editor
@@ -155,14 +376,19 @@ impl Shell {
             }
         }
 
-        // 7. エラー調査フロー
-        if result.exit_code != 0 {
+        // 7. エラー調査フロー（Ctrl+Z で停止したコマンドは失敗ではないため対象外）
+        if result.exit_code != 0 && result.exit_code != exec::STOPPED_EXIT_CODE {
             self.investigate_error(&line, &result, from_tool_call).await;
         }
 
-        // 8. AI Goodbye 検出: AI の応答が farewell を含む場合はシェル終了
+        // 8. AI Goodbye 検出: AI が会話終了マーカーを付けた（AI パイプでは farewell を含む）場合はシェル終了
         //    AI が既に farewell を言っているためバナーは非表示にする
-        if should_exit_on_goodbye(is_ai_response, from_tool_call, &result.stdout) {
+        if should_exit_on_goodbye(
+            is_ai_response,
+            from_tool_call,
+            goodbye_marked,
+            &result.stdout,
+        ) {
             info!("AI goodbye response detected, exiting shell");
             self.farewell_shown = true;
             return false;
@@ -191,14 +417,26 @@ impl Shell {
             .store(result.exit_code, Ordering::Relaxed);
         self.cmd_duration_ms
             .store(CMD_DURATION_NONE, Ordering::Relaxed);
-        println!(); // 実行結果の後に空行を追加
+        if should_ring_bell(self.bell_on_error, false, result.exit_code != 0, false) {
+            ring_bell();
+        }
+        print!("{}", blank_line_after(&result, false)); // 実行結果の後に空行を 1 行だけ入れる
 
         match result.action {
             LoopAction::Continue => {
-                self.record_history(original_line, &result);
+                self.record_history(original_line, &result, None);
                 true
             }
             LoopAction::Exit => {
+                // `[shell] confirm_exit = true` なら引数なしの exit / logout は確認してから終了
+                if self.confirm_exit
+                    && self.interactive
+                    && is_bare_exit_command(line)
+                    && !jarvis_ask_exit()
+                {
+                    info!("Exit cancelled by user");
+                    return true;
+                }
                 info!("Exit command received");
                 false
             }
@@ -212,11 +450,13 @@ impl Shell {
 
     /// Shell 状態を操作するビルトインをインターセプトする。
     ///
-    /// 対象: alias / unalias / source / cd / pushd / popd / dirs / complete
+    /// 対象: alias / unalias / source / cd / jump / pushd / popd / dirs / complete / ai / capture
     ///
     /// 先頭ワードが対象コマンドであり、かつパイプ・リダイレクト等を
     /// 含まない単純なコマンドの場合に `Some(CommandResult)` を返す。
     /// それ以外は `None` を返し、通常の実行パスに委ねる。
+    ///
+    /// ビルトインは出力を返すだけのため、実行結果の stdout はここで表示する。
     pub(super) fn try_shell_builtins(&mut self, input: &str) -> Option<CommandResult> {
         let first_word = input.split_whitespace().next().unwrap_or("");
         if !matches!(
@@ -226,17 +466,35 @@ impl Shell {
                 | "source"
                 | "cd"
                 | "cdj"
+                | "jump"
                 | "pushd"
                 | "popd"
                 | "dirs"
                 | "which"
                 | "type"
                 | "complete"
+                | "ai"
+                | "capture"
         ) {
             return None;
         }
+        // `ai` は `ai use <name>` 等のプロファイル操作のみ。それ以外は AI への入力として通す
+        if first_word == "ai" {
+            let args: Vec<&str> = input.split_whitespace().skip(1).collect();
+            if !ai_builtin::is_profile_command(&args) {
+                return None;
+            }
+        }
+
+        // dry-run 中は cd 等で実際に Shell 状態を変更せず、表示のみ行う
+        if dry_run::is_enabled() && !dry_run::is_exempt(first_word) {
+            return Some(dry_run::preview(input, &self.exec_context.variables));
+        }
 
-        let tokens = match expand::split_quoted(input) {
+        // ビルトインの引数に現れた `<(...)` はビルトインの実行後に片付ける
+        let _subst_scope = expand::ProcessSubstScope::enter();
+
+        let tokens = match expand::split_quoted_with_vars(input, &self.exec_context.variables) {
             Ok(t) => t,
             Err(e) => {
                 let msg = format!("jarvish: parse error: {e}\n");
@@ -250,10 +508,7 @@ impl Shell {
         }
 
         // パイプ・リダイレクト・接続演算子を含む場合は通常パスに委ねる
-        if tokens
-            .iter()
-            .any(|t| matches!(t.value.as_str(), "|" | ">" | ">>" | "<" | "&&" | "||" | ";"))
-        {
+        if tokens.iter().any(|t| t.is_operator()) {
             return None;
         }
 
@@ -264,11 +519,21 @@ impl Shell {
                 continue;
             }
             let expanded_result = if tok.quoted && tok.has_subst {
-                expand::expand_token_subst_only(&tok.value, tok.subst_quoting)
+                expand::expand_token_subst_only(
+                    &tok.value,
+                    tok.subst_quoting,
+                    &tok.process_subst,
+                    &self.exec_context.variables,
+                )
             } else if tok.has_subst {
-                expand::expand_token_globs_with_quoting(&tok.value, tok.subst_quoting)
+                expand::expand_token_globs_with_quoting(
+                    &tok.value,
+                    tok.subst_quoting,
+                    &tok.process_subst,
+                    &self.exec_context.variables,
+                )
             } else {
-                expand::expand_token_globs(&tok.value)
+                expand::expand_token_globs(&tok.value, &self.exec_context.variables)
             };
             match expanded_result {
                 Ok(parts) => expanded.extend(parts),
@@ -277,7 +542,7 @@ impl Shell {
                     eprint!("{msg}");
                     return Some(CommandResult::error(msg, 1));
                 }
-                Err(expand::ExpandError::Substitution(m)) => {
+                Err(expand::ExpandError::Substitution(m) | expand::ExpandError::Parameter(m)) => {
                     let msg = format!("jarvish: {m}\n");
                     eprint!("{msg}");
                     return Some(CommandResult::error(msg, 1));
@@ -289,6 +554,7 @@ impl Shell {
         }
         let args: Vec<&str> = expanded[1..].iter().map(|s| s.as_str()).collect();
 
+        let mut cd_hook_pending = false;
         let result = match first_word {
             "alias" => {
                 let Ok(mut guard) = self.aliases.write() else {
@@ -306,38 +572,69 @@ impl Shell {
                 };
                 unalias::execute_with_aliases(&args, &mut guard)
             }
-            "source" => {
-                let path_str = match source::parse(&args) {
-                    Ok(p) => p,
-                    Err(cmd_result) => return Some(cmd_result),
+            "source" => match source::parse(&args) {
+                Ok(path_str) => self.dispatch_source(&path_str),
+                Err(cmd_result) => cmd_result,
+            },
+            "cd" | "jump" => {
+                // cd は成功時のみ dir_stack に移動前のディレクトリを積む
+                // （`--help` 等ではディレクトリは変わらない）
+                let depth = self.dir_stack.len();
+                let result = if first_word == "jump" {
+                    bookmark::execute_jump(
+                        &args,
+                        &mut self.dir_stack,
+                        self.exec_context.cd_to_file_parent,
+                    )
+                } else {
+                    cd::execute(
+                        &args,
+                        &mut self.dir_stack,
+                        self.exec_context.cd_to_file_parent,
+                    )
                 };
-                self.dispatch_source(&path_str)
+                cd_hook_pending = self.auto_ls_on_cd && self.dir_stack.len() > depth;
+                result
             }
-            "cd" => cd::execute(&args, &mut self.dir_stack),
             "cdj" => cdj::execute(&args, &mut self.dir_stack),
             "pushd" => dirstack::execute_pushd(&args, &mut self.dir_stack),
             "popd" => dirstack::execute_popd(&args, &mut self.dir_stack),
             "dirs" => dirstack::execute_dirs(&args, &mut self.dir_stack),
-            "which" => {
+            "which" => which_type::execute_which(&args, Some(self.classifier.as_ref())),
+            "type" => {
                 let Ok(guard) = self.aliases.read() else {
-                    let msg = "jarvish: which: internal error: lock poisoned\n".to_string();
+                    let msg = "jarvish: type: internal error: lock poisoned\n".to_string();
                     eprint!("{msg}");
                     return Some(CommandResult::error(msg, 1));
                 };
-                which_type::execute_which(&args, &guard)
+                which_type::execute_type(&args, &guard)
             }
-            "type" => {
+            "capture" => {
                 let Ok(guard) = self.aliases.read() else {
-                    let msg = "jarvish: type: internal error: lock poisoned\n".to_string();
+                    let msg = "jarvish: capture: internal error: lock poisoned\n".to_string();
                     eprint!("{msg}");
                     return Some(CommandResult::error(msg, 1));
                 };
-                which_type::execute_type(&args, &guard)
+                capture::execute_with_aliases(&args, &guard, &self.exec_context)
             }
             "complete" => run_complete_builtin(&self.complete_registry, &args),
+            "ai" => {
+                let before = self.ai_profile.clone();
+                let result =
+                    ai_builtin::execute_with_profiles(&args, &self.ai_config, &mut self.ai_profile);
+                if self.ai_profile != before {
+                    self.apply_ai_profile();
+                }
+                result
+            }
             _ => unreachable!(),
         };
 
+        print!("{}", result.stdout);
+        if cd_hook_pending {
+            self.run_cd_hook();
+        }
+
         debug!(
             command = %first_word,
             exit_code = result.exit_code,
@@ -347,11 +644,84 @@ impl Shell {
         Some(result)
     }
 
+    /// `[shell] auto_ls_on_cd` 有効時に `cd` 成功後のフックコマンドを実行する。
+    ///
+    /// フックの失敗は表示するのみで、`cd` 自体の結果（終了コード）には影響させない。
+    fn run_cd_hook(&self) {
+        let hook = self.cd_hook.trim();
+        if hook.is_empty() {
+            return;
+        }
+        let result = execute(hook, &self.exec_context);
+        if result.exit_code != 0 {
+            debug!(
+                hook = %hook,
+                exit_code = result.exit_code,
+                "cd hook failed (cd itself succeeded)"
+            );
+        }
+    }
+
+    /// `:ai` / `:cmd` による分類訂正を学習し、強制する分類と対象入力を返す。
+    ///
+    /// 対象入力が省略された場合は直前の入力を対象とする。直前の入力がない場合は
+    /// エラーを表示して `None` を返す（プロンプトに戻る）。
+    /// オーバーライドファイルへの保存に失敗しても、今回の入力は指定の分類で処理する。
+    fn apply_classifier_correction(
+        &self,
+        correction: ClassifierCorrection,
+    ) -> Option<(String, InputType)> {
+        let prefix = match correction.kind {
+            OverrideKind::Ai => ":ai",
+            OverrideKind::Cmd => ":cmd",
+        };
+        let Some(target) = correction.input.or_else(|| self.last_input.clone()) else {
+            eprintln!("jarvish: {prefix}: no previous input to correct");
+            return None;
+        };
+
+        if let Err(e) = self
+            .classifier
+            .learn(&target, correction.kind, correction.scope)
+        {
+            warn!(error = %e, "Failed to save classifier override");
+            eprintln!("jarvish: warning: failed to save classifier override: {e}");
+        }
+
+        Some((target, correction.kind.input_type()))
+    }
+
+    /// `:explain` の本体。入力の分類結果と判定理由を表示する（実行はしない）。
+    fn explain_classification(&self, input: Option<&str>) {
+        let Some(target) = input
+            .map(str::to_string)
+            .or_else(|| self.last_input.clone())
+        else {
+            eprintln!("jarvish: :explain: no previous input to explain");
+            println!();
+            return;
+        };
+        let (input_type, reason) = self
+            .classifier
+            .classify_with_reason(&target, self.after_ai_turn);
+        // ビルトインは分類より先に実行される（handle_input のステップ 0.5 / 1 と同じ条件）
+        let builtin = target.split_whitespace().next().filter(|word| {
+            is_builtin(word)
+                && reason != ClassifyReason::UserOverride
+                && !self.classifier.is_command_word_question(&target)
+        });
+        println!(
+            "{}",
+            format_explanation(&target, &input_type, reason, builtin)
+        );
+    }
+
     /// 履歴を BlackBox に記録する。
-    fn record_history(&self, line: &str, result: &CommandResult) {
+    /// `duration_ms` は計測した所要時間（計測対象外なら `None`）。
+    fn record_history(&self, line: &str, result: &CommandResult, duration_ms: Option<u64>) {
         if result.action == LoopAction::Continue {
             if let Some(ref bb) = self.black_box {
-                if let Err(e) = bb.record(line, result) {
+                if let Err(e) = bb.record(line, result, duration_ms) {
                     warn!("Failed to record history: {e}");
                     eprintln!("jarvish: warning: failed to record history: {e}");
                 }
@@ -359,6 +729,45 @@ impl Shell {
         }
     }
 
+    /// 入力を履歴に残さない（reedline が先に保存した行を削除する）。
+    fn discard_history(&self, line: &str) {
+        if let Some(ref bb) = self.black_box {
+            if let Err(e) = bb.discard(line) {
+                warn!("Failed to discard history: {e}");
+            }
+        }
+    }
+
+    /// `[shell] autocorrect` による先頭コマンドの自動修正を確認する。
+    ///
+    /// コマンドとして実行した `line` が見つからず（終了コード 127）、先頭トークンが
+    /// ビルトインでも PATH 上のコマンドでもなく、編集距離 1 の候補が 1 つだけある場合に
+    /// 「Did you mean `ls`? [Y/n]」と確認し、承諾されれば先頭トークンを置き換えた行を返す。
+    /// 候補が複数ある場合は何もしない。
+    fn autocorrect_command(&self, line: &str, result: &CommandResult) -> Option<String> {
+        if !self.autocorrect
+            || result.exit_code != 127
+            || dry_run::is_enabled()
+            || !std::io::stdin().is_terminal()
+        {
+            return None;
+        }
+        let first_token = line.split_whitespace().next()?;
+        if !typo::is_command_like(first_token)
+            || is_builtin(first_token)
+            || self.classifier.is_command_in_path(first_token)
+        {
+            return None;
+        }
+        let suggestion = typo::find_unique_correction(first_token)?;
+        if !jarvis_ask_autocorrect(&suggestion) {
+            return None;
+        }
+        info!(typo = %first_token, suggestion = %suggestion, "Command autocorrected");
+        let rest = &line[first_token.len()..];
+        Some(format!("{suggestion}{rest}"))
+    }
+
     /// AI パイプ / リダイレクトリクエストを処理する。
     ///
     /// 手前パイプラインの stdout キャプチャ結果を AI に渡し、結果を返す。
@@ -436,8 +845,109 @@ fn run_complete_builtin(
     complete::execute_with_registry(args, &mut guard)
 }
 
+// ── 分類訂正プレフィックス ──
+
+/// `:ai` / `:cmd` プレフィックスの解析結果
+#[derive(Debug, PartialEq)]
+struct ClassifierCorrection {
+    /// 強制する分類先
+    kind: OverrideKind,
+    /// 学習するオーバーライドの範囲（`-t` 指定時は先頭トークン）
+    scope: OverrideScope,
+    /// 訂正対象の入力。`None` の場合は直前の入力を対象とする。
+    input: Option<String>,
+}
+
+/// 入力が分類訂正プレフィックスで始まっていれば解析する。
+///
+/// - `:ai [input]` — 入力（省略時は直前の入力）を自然言語として扱い、完全一致で学習する
+/// - `:cmd [input]` — 入力（省略時は直前の入力）をコマンドとして扱い、完全一致で学習する
+/// - `-t` を付けると（例: `:ai -t say hello`）先頭トークンに対して学習する
+fn parse_classifier_correction(line: &str) -> Option<ClassifierCorrection> {
+    let (kind, rest) = if let Some(rest) = line.strip_prefix(":ai") {
+        (OverrideKind::Ai, rest)
+    } else if let Some(rest) = line.strip_prefix(":cmd") {
+        (OverrideKind::Cmd, rest)
+    } else {
+        return None;
+    };
+    // `:aix` のようにプレフィックスの直後が空白でない場合は別の入力として扱う
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let rest = rest.trim();
+    let (scope, rest) = match rest.strip_prefix("-t") {
+        Some(after) if after.is_empty() || after.starts_with(char::is_whitespace) => {
+            (OverrideScope::FirstToken, after.trim())
+        }
+        _ => (OverrideScope::Exact, rest),
+    };
+
+    Some(ClassifierCorrection {
+        kind,
+        scope,
+        input: (!rest.is_empty()).then(|| rest.to_string()),
+    })
+}
+
+/// 入力が `:explain [input]` なら、説明対象の入力（省略時は `None`）を返す。
+fn parse_explain(line: &str) -> Option<Option<&str>> {
+    let rest = line.strip_prefix(":explain")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let rest = rest.trim();
+    Some((!rest.is_empty()).then_some(rest))
+}
+
+/// `:explain` の表示内容（末尾の空行まで含む）を組み立てる。
+///
+/// `builtin` が指定されていれば、分類より先にそのビルトインとして実行されることを併記する。
+fn format_explanation(
+    input: &str,
+    input_type: &InputType,
+    reason: ClassifyReason,
+    builtin: Option<&str>,
+) -> String {
+    let mut output = format!(
+        "input:           {input}\n\
+         classification:  {input_type:?}\n\
+         reason:          {} ({})\n",
+        reason.as_str(),
+        reason.description()
+    );
+    if let Some(builtin) = builtin {
+        output.push_str(&format!(
+            "note:            `{builtin}` is a builtin and runs before classification\n"
+        ));
+    }
+    output
+}
+
+// ── 終了確認 ──
+
+/// 入力が終了確認の対象となる `exit` / `logout` か判定する。
+///
+/// 引数なし（`--force` も終了コードも指定されていない）の場合のみ `true`。
+fn is_bare_exit_command(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    if !matches!(words.next(), Some("exit" | "logout")) {
+        return false;
+    }
+    let args: Vec<&str> = words.collect();
+    exit::requires_confirmation(&args)
+}
+
 // ── Goodbye 判定 ──
 
+/// この入力が AI との会話だったか（次の入力の `bye` 等を AI への返事として扱うか）。
+///
+/// AI に送った自然言語だけが対象で、コマンド・ビルトインを挟むと会話は途切れたとみなす。
+fn is_ai_turn(input_type: &InputType) -> bool {
+    *input_type == InputType::NaturalLanguage
+}
+
 /// 実行結果を受けてシェルを goodbye 終了すべきかを判定する。
 ///
 /// goodbye 検出は **AI（Jarvis）の発話** に対してのみ行う。人間が打った
@@ -449,9 +959,210 @@ fn run_complete_builtin(
 /// - `is_ai_response`: 出力が AI の発話か（NaturalLanguage 経路または AI パイプ）
 /// - `from_tool_call`: AI がツール呼び出しでコマンドを実行したか
 ///   （その場合 stdout はコマンド出力であり farewell 文ではないため除外）
+/// - `marked`: 自然言語応答に会話終了マーカーが付いていたか。マーカーを指示していない
+///   応答（AI パイプ/リダイレクト）は `None` で、`stdout` 末尾のフレーズで判定する
 /// - `stdout`: 判定対象テキスト
-fn should_exit_on_goodbye(is_ai_response: bool, from_tool_call: bool, stdout: &str) -> bool {
-    is_ai_response && !from_tool_call && is_ai_goodbye_response(stdout)
+fn should_exit_on_goodbye(
+    is_ai_response: bool,
+    from_tool_call: bool,
+    marked: Option<bool>,
+    stdout: &str,
+) -> bool {
+    is_ai_response && !from_tool_call && marked.unwrap_or_else(|| is_ai_goodbye_response(stdout))
+}
+
+// ── 空行 ──
+
+/// 実行結果の後に空行を 1 行だけ入れるために出力すべき改行を返す。
+///
+/// 表示された出力の末尾の改行数で調整する（PTY 由来の `\r` は無視する）:
+/// - 改行で終わっていない → 行を終わらせてから空行（`"\n\n"`）
+/// - 改行 1 つで終わる・出力なし → 空行（`"\n"`）
+/// - 既に空行で終わっている → 何もしない
+///
+/// stdout が空なら stderr の末尾で判定する。AI 応答の表示は行末で終わることが
+/// 保証されている（末尾の空行も除いて表示する）ため、stdout が画面制御シーケンスである
+/// Alternate Screen 使用時と同じく常に空行 1 行にする。
+pub(super) fn blank_line_after(result: &CommandResult, is_ai_response: bool) -> &'static str {
+    if is_ai_response || result.used_alt_screen {
+        return "\n";
+    }
+    let output = if result.stdout.is_empty() {
+        &result.stderr
+    } else {
+        &result.stdout
+    };
+    let newlines = output
+        .chars()
+        .rev()
+        .take_while(|c| matches!(c, '\n' | '\r'))
+        .filter(|&c| c == '\n')
+        .count();
+    match newlines {
+        0 if !output.is_empty() => "\n\n",
+        0 | 1 => "\n",
+        _ => "",
+    }
+}
+
+// ── ベル ──
+
+/// 実行結果を受けてベルを鳴らすべきかを判定する。
+///
+/// - `bell_on_error` / `bell_on_ai_response`: `[shell]` の設定値
+/// - `failed`: 実行したコマンドが非ゼロで終了したか
+/// - `is_ai_response`: 出力が AI の発話か（NaturalLanguage 経路または AI パイプ）
+fn should_ring_bell(
+    bell_on_error: bool,
+    bell_on_ai_response: bool,
+    failed: bool,
+    is_ai_response: bool,
+) -> bool {
+    (bell_on_error && failed) || (bell_on_ai_response && is_ai_response)
+}
+
+/// ターミナルにベル（`\x07`）を送る。stdout が端末でない場合は何もしない。
+fn ring_bell() {
+    let mut stdout = std::io::stdout();
+    if !stdout.is_terminal() {
+        return;
+    }
+    let _ = stdout.write_all(b"\x07");
+    let _ = stdout.flush();
+}
+
+// ── 実行フック ──
+
+impl Shell {
+    /// コマンドラインを実行する（ユーザー入力以外の AI のツール呼び出し・調査の修正コマンド用）。
+    ///
+    /// ユーザー入力と同じく実行前後の `[hooks]` と端末タイトルの更新を行う（[`Self::begin_exec`]）。
+    pub(super) fn execute_command_line(&self, line: &str) -> CommandResult {
+        let hooked = self.begin_exec(line);
+        let result = execute(line, &self.exec_context);
+        self.end_exec(hooked, line, result.exit_code);
+        result
+    }
+
+    /// コマンドラインの実行開始時に `[hooks] pre_exec` を実行し、端末タイトルを設定する
+    /// （`[prompt] set_title`）。
+    ///
+    /// 対象は外部コマンドを含む行だけで、ビルトインだけの行（シェル内で完結し、
+    /// 子プロセスを起動しない）では何もしない（[`runs_external_command`]）。
+    /// 戻り値は対象だったか（[`Self::end_exec`] に渡す）。
+    fn begin_exec(&self, line: &str) -> bool {
+        if !runs_external_command(line) {
+            return false;
+        }
+        run_exec_hook(
+            "pre_exec",
+            &self.hooks.pre_exec,
+            line,
+            None,
+            &self.exec_context,
+        );
+        if self.set_title {
+            title::set_command(line);
+        }
+        true
+    }
+
+    /// [`Self::begin_exec`] の対象だった行の実行後に `[hooks] post_exec` を実行する。
+    fn end_exec(&self, hooked: bool, line: &str, exit_code: i32) {
+        if hooked {
+            run_exec_hook(
+                "post_exec",
+                &self.hooks.post_exec,
+                line,
+                Some(exit_code),
+                &self.exec_context,
+            );
+        }
+    }
+}
+
+/// コマンドラインがビルトイン以外のコマンドを含むか（`cd /tmp && make` は含む、
+/// `cd /tmp && pushd -` や `history > out.txt` は含まない）。
+///
+/// トークンに分割できない場合は外部コマンドを含むものとして扱う。
+fn runs_external_command(line: &str) -> bool {
+    let Ok(tokens) = expand::split_quoted(line) else {
+        return true;
+    };
+    let mut commands: Vec<Vec<&str>> = vec![Vec::new()];
+    let mut tokens = tokens.iter();
+    while let Some(tok) = tokens.next() {
+        if !tok.is_operator() {
+            if let Some(words) = commands.last_mut() {
+                words.push(&tok.value);
+            }
+        } else if matches!(tok.value.as_str(), "|" | "&&" | "||" | ";") {
+            commands.push(Vec::new());
+        } else if tok.value != ">&2" {
+            // リダイレクト先はコマンドではない
+            tokens.next();
+        }
+    }
+    commands
+        .iter()
+        .filter_map(|words| words.split_first())
+        .any(|(cmd, args)| !is_builtin_call(cmd, args))
+}
+
+/// フック実行中であることを示す環境変数。設定されている間はフックを発火させない
+/// （フックから起動された jarvish を含め、フックの再帰を防ぐ）。
+const IN_HOOK_ENV: &str = "JARVISH_IN_HOOK";
+
+/// `[shell] url_action = "open"` で URL を開くコマンド
+#[cfg(target_os = "macos")]
+const URL_OPENER: &str = "open";
+#[cfg(not(target_os = "macos"))]
+const URL_OPENER: &str = "xdg-open";
+
+/// `[hooks] pre_exec` / `post_exec` のコマンドを実行する。
+///
+/// 実行中だけ `JARVISH_LAST_CMD`（対象コマンド）と、`exit_code` があれば
+/// `JARVISH_LAST_EXIT` を設定し、終了後に元の値へ戻す。
+/// フックの失敗はログに残すのみで、コマンド本体の結果には影響させない。
+/// dry-run 中は実行しない。
+fn run_exec_hook(name: &str, hook: &str, command: &str, exit_code: Option<i32>, ctx: &ExecContext) {
+    let hook = hook.trim();
+    if hook.is_empty() || dry_run::is_enabled() || std::env::var_os(IN_HOOK_ENV).is_some() {
+        return;
+    }
+
+    let vars = [
+        (IN_HOOK_ENV, Some("1".to_string())),
+        ("JARVISH_LAST_CMD", Some(command.to_string())),
+        ("JARVISH_LAST_EXIT", exit_code.map(|code| code.to_string())),
+    ];
+    let saved: Vec<_> = vars
+        .iter()
+        .map(|(key, _)| (*key, std::env::var_os(key)))
+        .collect();
+    for (key, value) in &vars {
+        match value {
+            Some(value) => std::env::set_var(key, value),
+            None => std::env::remove_var(key),
+        }
+    }
+
+    let result = execute(hook, ctx);
+
+    for (key, value) in saved {
+        match value {
+            Some(value) => std::env::set_var(key, value),
+            None => std::env::remove_var(key),
+        }
+    }
+    if result.exit_code != 0 {
+        debug!(
+            hook = name,
+            command = %hook,
+            exit_code = result.exit_code,
+            "Exec hook failed (command result unaffected)"
+        );
+    }
 }
 
 // ── タイポ補正 ──
@@ -492,6 +1203,26 @@ fn check_typo_correction(line: &str) -> TypoCorrectionOutcome {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serial_test::serial;
+
+    /// AI との会話の直後の `bye` は AI への返事として扱い、間にコマンドを挟めば
+    /// 通常どおりシェルを終了する。
+    #[test]
+    fn goodbye_after_command_following_ai_turn_exits() {
+        let classifier = InputClassifier::new();
+
+        let after_ai_turn = is_ai_turn(&InputType::NaturalLanguage);
+        assert_eq!(
+            classifier.classify_with_context("bye", after_ai_turn),
+            InputType::NaturalLanguage
+        );
+
+        let after_ai_turn = is_ai_turn(&classifier.classify_with_context("ls", after_ai_turn));
+        assert_eq!(
+            classifier.classify_with_context("bye", after_ai_turn),
+            InputType::Goodbye
+        );
+    }
 
     /// 末尾に farewell パターンを含む goodbye らしい AI 応答テキスト。
     const GOODBYE_TEXT: &str = "承知しました。\nさようなら、サー。";
@@ -509,7 +1240,7 @@ mod tests {
             \tdocs/design-notes/2026-06-22/obituary-corporate-farewell-other-venue-WIP.md";
         // is_ai_response=false（通常コマンド）なので、内容に farewell があっても終了しない
         assert!(
-            !should_exit_on_goodbye(false, false, git_status),
+            !should_exit_on_goodbye(false, false, None, git_status),
             "通常コマンドの出力で farewell を含んでもシェルを終了してはならない"
         );
     }
@@ -518,7 +1249,7 @@ mod tests {
     #[test]
     fn ai_response_with_farewell_exits() {
         assert!(
-            should_exit_on_goodbye(true, false, GOODBYE_TEXT),
+            should_exit_on_goodbye(true, false, None, GOODBYE_TEXT),
             "AI の farewell 応答ではシェルを終了する"
         );
     }
@@ -530,11 +1261,11 @@ mod tests {
         // ツール実行結果にたまたま farewell パスが含まれていても終了しない
         let tool_output = "ファイル一覧:\n./docs/farewell-template.md";
         assert!(
-            !should_exit_on_goodbye(true, true, tool_output),
+            !should_exit_on_goodbye(true, true, None, tool_output),
             "from_tool_call の出力は farewell 判定対象外"
         );
         // goodbye 文そのものでも、from_tool_call なら終了しない
-        assert!(!should_exit_on_goodbye(true, true, GOODBYE_TEXT));
+        assert!(!should_exit_on_goodbye(true, true, None, GOODBYE_TEXT));
     }
 
     /// AI 応答で farewell を含まない通常応答では終了しない。
@@ -543,20 +1274,203 @@ mod tests {
         assert!(!should_exit_on_goodbye(
             true,
             false,
+            None,
             "エラーの原因はこちらです。"
         ));
     }
 
+    /// 自然言語応答はマーカーの有無だけで判定する（言い回しに依存しない）。
+    #[test]
+    fn marked_ai_response_exits_regardless_of_phrasing() {
+        assert!(should_exit_on_goodbye(
+            true,
+            false,
+            Some(true),
+            "では、また。"
+        ));
+        // マーカーがなければ farewell の言い回しを含んでも終了しない
+        assert!(!should_exit_on_goodbye(
+            true,
+            false,
+            Some(false),
+            GOODBYE_TEXT
+        ));
+        assert!(!should_exit_on_goodbye(
+            true,
+            true,
+            Some(true),
+            "では、また。"
+        ));
+    }
+
     /// 通常コマンドが goodbye 文そのものを出力しても終了しない
     /// （例: `echo さようなら` や farewell を含むファイルの `cat`）。
     #[test]
     fn command_echoing_goodbye_text_does_not_exit() {
         assert!(
-            !should_exit_on_goodbye(false, false, GOODBYE_TEXT),
+            !should_exit_on_goodbye(false, false, None, GOODBYE_TEXT),
             "コマンドが goodbye 文を出力してもシェルを終了してはならない"
         );
     }
 
+    // ── should_ring_bell ──
+
+    #[test]
+    fn bell_is_silent_by_default() {
+        assert!(!should_ring_bell(false, false, true, true));
+    }
+
+    #[test]
+    fn bell_on_error_rings_only_for_failures() {
+        assert!(should_ring_bell(true, false, true, false));
+        assert!(!should_ring_bell(true, false, false, false));
+        // AI 応答の完了だけでは鳴らさない
+        assert!(!should_ring_bell(true, false, false, true));
+    }
+
+    #[test]
+    fn bell_on_ai_response_rings_after_ai_output() {
+        assert!(should_ring_bell(false, true, false, true));
+        assert!(!should_ring_bell(false, true, true, false));
+    }
+
+    // ── blank_line_after ──
+
+    #[test]
+    fn blank_line_after_adjusts_to_trailing_newlines() {
+        let blank = |stdout: &str| blank_line_after(&CommandResult::success(stdout.into()), false);
+        assert_eq!(blank(""), "\n");
+        assert_eq!(blank("hello\n"), "\n");
+        assert_eq!(blank("hello\r\n"), "\n");
+        assert_eq!(blank("hello"), "\n\n");
+        assert_eq!(blank("hello\n\n"), "");
+        assert_eq!(blank("hello\r\n\r\n"), "");
+    }
+
+    #[test]
+    fn blank_line_after_uses_stderr_without_stdout_and_ignores_ai_text() {
+        let error = CommandResult::error("jarvish: cd: no such file".into(), 1);
+        assert_eq!(blank_line_after(&error, false), "\n\n");
+
+        let ai = CommandResult::success("Certainly, sir.\n\n".into());
+        assert_eq!(blank_line_after(&ai, true), "\n");
+    }
+
+    // ── :explain ──
+
+    #[test]
+    fn parse_explain_takes_optional_input() {
+        assert_eq!(parse_explain(":explain"), Some(None));
+        assert_eq!(
+            parse_explain(":explain  git stauts "),
+            Some(Some("git stauts"))
+        );
+        assert_eq!(parse_explain(":explainer"), None);
+        assert_eq!(parse_explain("explain this"), None);
+    }
+
+    #[test]
+    fn explanation_shows_reason_and_builtin_note() {
+        let output = format_explanation(
+            "ls -la",
+            &InputType::Command,
+            ClassifyReason::PathLookup,
+            None,
+        );
+        assert!(output.contains("classification:  Command\n"));
+        assert!(output
+            .contains("reason:          path_lookup (first word is a command found in $PATH)\n"));
+        assert!(!output.contains("note:"));
+
+        let output = format_explanation(
+            "cd /tmp",
+            &InputType::Command,
+            ClassifyReason::PathLookup,
+            Some("cd"),
+        );
+        assert!(output.contains("note:            `cd` is a builtin"));
+    }
+
+    // ── is_bare_exit_command ──
+
+    #[test]
+    fn bare_exit_and_logout_require_confirmation() {
+        assert!(is_bare_exit_command("exit"));
+        assert!(is_bare_exit_command("logout"));
+        assert!(is_bare_exit_command("  exit  "));
+    }
+
+    #[test]
+    fn exit_with_args_or_force_skips_confirmation() {
+        assert!(!is_bare_exit_command("exit 0"));
+        assert!(!is_bare_exit_command("logout 1"));
+        assert!(!is_bare_exit_command("exit --force"));
+        assert!(!is_bare_exit_command("exit -f"));
+        assert!(!is_bare_exit_command("restart"));
+    }
+
+    // ── parse_classifier_correction ──
+
+    #[test]
+    fn correction_prefix_with_input() {
+        assert_eq!(
+            parse_classifier_correction(":ai make coffee"),
+            Some(ClassifierCorrection {
+                kind: OverrideKind::Ai,
+                scope: OverrideScope::Exact,
+                input: Some("make coffee".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_classifier_correction(":cmd  fd pattern "),
+            Some(ClassifierCorrection {
+                kind: OverrideKind::Cmd,
+                scope: OverrideScope::Exact,
+                input: Some("fd pattern".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn correction_prefix_without_input_targets_previous() {
+        assert_eq!(
+            parse_classifier_correction(":ai"),
+            Some(ClassifierCorrection {
+                kind: OverrideKind::Ai,
+                scope: OverrideScope::Exact,
+                input: None,
+            })
+        );
+    }
+
+    #[test]
+    fn correction_prefix_first_token_scope() {
+        assert_eq!(
+            parse_classifier_correction(":ai -t say hello"),
+            Some(ClassifierCorrection {
+                kind: OverrideKind::Ai,
+                scope: OverrideScope::FirstToken,
+                input: Some("say hello".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_classifier_correction(":cmd -t"),
+            Some(ClassifierCorrection {
+                kind: OverrideKind::Cmd,
+                scope: OverrideScope::FirstToken,
+                input: None,
+            })
+        );
+    }
+
+    #[test]
+    fn correction_prefix_requires_word_boundary() {
+        assert_eq!(parse_classifier_correction(":aix"), None);
+        assert_eq!(parse_classifier_correction(":cmdline"), None);
+        assert_eq!(parse_classifier_correction("ai hello"), None);
+        assert_eq!(parse_classifier_correction("ls :ai"), None);
+    }
+
     // ── run_complete_builtin (try_shell_builtins の "complete" 分岐, #89 C1) ──
 
     /// register 呼び出しが `Shell::complete_registry` と同じ実共有 Arc を
@@ -631,4 +1545,55 @@ mod tests {
         assert_eq!(result.exit_code, 1);
         assert!(result.stderr.contains("lock poisoned"));
     }
+
+    /// 実行フックの対象は外部コマンドを含む行だけ。
+    #[test]
+    fn exec_hooks_target_lines_with_external_commands() {
+        assert!(runs_external_command("ls -la"));
+        assert!(runs_external_command("cd /tmp && make"));
+        assert!(runs_external_command("history | grep git"));
+        assert!(!runs_external_command("cd /tmp"));
+        assert!(!runs_external_command("cd /tmp && pushd -"));
+        assert!(!runs_external_command("ai use fast"));
+        assert!(!runs_external_command("history > out.txt"));
+    }
+
+    #[test]
+    #[serial]
+    fn exec_hook_receives_command_and_exit_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("hook.log");
+        let hook = format!(
+            "sh -c 'echo \"$JARVISH_LAST_CMD:$JARVISH_LAST_EXIT\" > {}'",
+            log.display()
+        );
+
+        run_exec_hook(
+            "post_exec",
+            &hook,
+            "make test",
+            Some(2),
+            &ExecContext::default(),
+        );
+        assert_eq!(std::fs::read_to_string(&log).unwrap().trim(), "make test:2");
+        // フック終了後は元の環境に戻る
+        assert!(std::env::var_os("JARVISH_LAST_CMD").is_none());
+        assert!(std::env::var_os(IN_HOOK_ENV).is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn exec_hook_does_not_fire_inside_another_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("hook.log");
+        let hook = format!("touch {}", log.display());
+
+        std::env::set_var(IN_HOOK_ENV, "1");
+        run_exec_hook("pre_exec", &hook, "ls", None, &ExecContext::default());
+        std::env::remove_var(IN_HOOK_ENV);
+        assert!(!log.exists());
+
+        // 失敗するフックはコマンド本体に影響しない（パニックしない）
+        run_exec_hook("post_exec", "false", "ls", Some(0), &ExecContext::default());
+    }
 }
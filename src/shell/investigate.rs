@@ -10,10 +10,17 @@ use tracing::{debug, info, warn};
 
 use crate::ai::AiResponse;
 use crate::cli::jarvis::{jarvis_ask_investigate, jarvis_notice};
-use crate::engine::{execute, CommandResult, LoopAction};
+use crate::engine::{CommandResult, LoopAction};
 
+use super::input::blank_line_after;
 use super::Shell;
 
+/// エラー調査で参照する直近の履歴件数
+const ERROR_CONTEXT_ENTRIES: usize = 5;
+
+/// エラー調査で渡す失敗コマンドの stderr の最大文字数（行数では切り詰めない）
+const ERROR_CONTEXT_MAX_CHARS: usize = 20_000;
+
 /// コマンドが ignore リストのいずれかのパターンに前方一致するかを判定する。
 ///
 /// パターンがコマンドと完全一致するか、コマンドが「パターン + スペース」で始まる場合に true。
@@ -55,6 +62,13 @@ impl Shell {
             return;
         }
 
+        // AI がオフラインなら調査できないため、確認を出さずにスキップする
+        // （オフラインのフォールバックでコマンドとして実行した入力の失敗もここで止まる）
+        if !ai.is_online().await {
+            info!("Skipping investigation (AI offline)");
+            return;
+        }
+
         // 調査開始の判定
         let should_investigate = if from_tool_call {
             info!("Tool Call command failed, auto-investigating");
@@ -93,7 +107,10 @@ impl Shell {
         let bb_context = self
             .black_box
             .as_ref()
-            .and_then(|bb| bb.get_recent_context(5).ok())
+            .and_then(|bb| {
+                bb.get_error_context(ERROR_CONTEXT_ENTRIES, ERROR_CONTEXT_MAX_CHARS)
+                    .ok()
+            })
             .unwrap_or_default();
 
         let cwd = std::env::current_dir()
@@ -117,19 +134,26 @@ impl Shell {
     fn handle_investigation_response(
         &mut self,
         response: AiResponse,
-        conversation: Option<crate::ai::ConversationState>,
+        mut conversation: Option<crate::ai::ConversationState>,
     ) {
         match response {
             AiResponse::Command(ref fix_cmd) => {
-                jarvis_notice(fix_cmd);
-                let fix_result = execute(fix_cmd);
+                let reason = conversation
+                    .as_ref()
+                    .and_then(|conv| conv.pending_command_reason());
+                jarvis_notice(fix_cmd, reason.as_deref(), self.command_notice);
+                let fix_result = self.execute_command_line(fix_cmd);
+                // 修正コマンドの結果を会話履歴に積み、次ターンで参照できるようにする
+                if let Some(ref mut conv) = conversation {
+                    conv.record_command_result(fix_cmd, &fix_result, self.ai_max_tool_output_lines);
+                }
                 self.last_exit_code
                     .store(fix_result.exit_code, Ordering::Relaxed);
-                println!();
+                print!("{}", blank_line_after(&fix_result, false));
 
                 if fix_result.action == LoopAction::Continue {
                     if let Some(ref bb) = self.black_box {
-                        if let Err(e) = bb.record(fix_cmd, &fix_result) {
+                        if let Err(e) = bb.record(fix_cmd, &fix_result, None) {
                             warn!("Failed to record fix command history: {e}");
                         }
                     }
@@ -4,6 +4,7 @@
 //! 入力ハンドリング、AI ルーティング、エラー調査の各責務をサブモジュールに分離する。
 
 mod ai_router;
+mod attach;
 mod editor;
 mod input;
 mod investigate;
@@ -12,12 +13,13 @@ mod rc;
 pub use rc::RcOptions;
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use reedline::{Reedline, Signal};
-use tracing::{info, warn};
+use reedline::{EditCommand, Reedline, Signal};
+use tracing::{debug, info, warn};
 
 use std::sync::atomic::AtomicBool as StaticAtomicBool;
 
@@ -32,12 +34,20 @@ use crate::cli::completer::{
     prewarm_zsh_daemon, registry::CompletionRegistry, shutdown_shared_daemon,
     shutdown_shared_daemon_blocking, DaemonGate, ExternalCompletionSettings, SharedDaemonSlot,
 };
+use crate::cli::jarvis::{jarvis_ask_exit, jarvis_talk};
 use crate::cli::prompt::starship::CMD_DURATION_NONE;
+use crate::cli::prompt::title;
 use crate::cli::prompt::{ShellPrompt, EXIT_CODE_NONE};
-use crate::config::JarvishConfig;
-use crate::engine::classifier::InputClassifier;
+use crate::config::{
+    AiConfig, BannerConfig, CommandNotice, HooksConfig, JarvishConfig, OutputWrap, TypeAhead,
+    UrlAction,
+};
+use crate::engine::builtins;
+use crate::engine::classifier::{ClassifierOverrides, InputClassifier};
+use crate::engine::dry_run;
 use crate::engine::expand;
-use crate::engine::LoopAction;
+use crate::engine::persist_env;
+use crate::engine::{ExecContext, LoopAction};
 use crate::storage::BlackBox;
 
 /// Jarvis Shell の状態を管理する構造体。
@@ -45,17 +55,44 @@ use crate::storage::BlackBox;
 pub struct Shell {
     editor: Reedline,
     prompt: ShellPrompt,
+    /// ターミナルタイトルに実行中のコマンド / カレントディレクトリを表示するか（`[prompt] set_title`）
+    set_title: bool,
     ai_client: Option<JarvisAI>,
+    /// 設定ファイルの `[ai]`（プロファイル適用前の値と `[ai.profiles]`）
+    ai_config: AiConfig,
+    /// 現在の AI プロファイル名（`ai use <name>`）。`None` は `[ai]` の値をそのまま使う
+    ai_profile: Option<String>,
     black_box: Option<BlackBox>,
     conversation_state: Option<ConversationState>,
+    /// 直前の入力が AI との会話だったか。`bye` 等を AI への返事として扱う判定に使い、
+    /// 次の入力を処理し始めた時点でクリアする（間にコマンドを挟めば通常の Goodbye に戻る）
+    after_ai_turn: bool,
     last_exit_code: Arc<AtomicI32>,
     /// 直前コマンドの実行時間（ミリ秒）。Starship プロンプトの `--cmd-duration` に使用。
     cmd_duration_ms: Arc<AtomicU64>,
     classifier: Arc<InputClassifier>,
+    /// 直前のユーザー入力。引数なしの `:ai` / `:cmd` で分類を訂正する対象。
+    last_input: Option<String>,
     /// 設定ファイルで定義されたコマンドエイリアス（JarvishCompleter と共有）
     aliases: Arc<RwLock<HashMap<String, String>>>,
     /// 異常終了時に自動調査をスキップするコマンドの前方一致パターン
     ignore_auto_investigation_cmds: Vec<String>,
+    /// AI がコマンドを実行する前のアナウンスの表示レベル（`[ai] command_notice`）
+    command_notice: CommandNotice,
+    /// Goodbye 入力時に AI の別れの挨拶を表示してから終了するか（`[ai] farewell`）
+    ai_farewell: bool,
+    /// AI が提案したコマンドを入力行で編集・確定してから実行するか（`[ai] edit_before_run`）
+    ai_edit_before_run: bool,
+    /// 新規会話の前に送信するコンテキストを確認するか（`[ai] preview_context`）
+    ai_preview_context: bool,
+    /// このセッションでコンテキストの確認を「今後確認しない」にしたか
+    context_preview_muted: bool,
+    /// AI 応答中に打鍵された入力の扱い（`[ai] type_ahead`）
+    ai_type_ahead: TypeAhead,
+    /// AI が実行したコマンドの出力を会話に戻す際の最大行数（`[ai] max_tool_output_lines`）
+    ai_max_tool_output_lines: usize,
+    /// コマンドを実行しなかった AI との会話を履歴に記録するか（`[history] record_ai_conversation`）
+    record_ai_conversation: bool,
     /// pushd / popd / cd で管理されるディレクトリスタック
     dir_stack: Vec<PathBuf>,
     /// Farewell メッセージが既に表示済みかどうか（AI goodbye 等で表示済みの場合 true）
@@ -116,6 +153,38 @@ pub struct Shell {
     restart_requested: Arc<AtomicBool>,
     /// 起動時に実行するコマンドのリスト（config.toml の `[startup]` セクション）
     startup_commands: Vec<String>,
+    /// 引数なしの `exit` / `logout` や Ctrl-D で終了前に確認するか（`[shell] confirm_exit`）
+    confirm_exit: bool,
+    /// 見つからないコマンドを確認のうえ自動修正するか（`[shell] autocorrect`）
+    autocorrect: bool,
+    /// ディレクトリパスだけの入力を `cd` として扱うか（`[shell] auto_cd`）
+    auto_cd: bool,
+    /// URL だけの入力の扱い（`[shell] url_action`）
+    url_action: UrlAction,
+    /// `cd` 成功後に `cd_hook` を自動実行するか（`[shell] auto_ls_on_cd`）
+    auto_ls_on_cd: bool,
+    /// `cd` 成功後に実行するコマンド（`[shell] cd_hook`）
+    cd_hook: String,
+    /// コマンド実行時にエンジンへ渡す設定（`[shell] wrap_output` 等）
+    exec_context: ExecContext,
+    /// 外部コマンドの実行前後に実行するフック（`[hooks]`）
+    hooks: HooksConfig,
+    /// コマンドの非ゼロ終了時にベルを鳴らすか（`[shell] bell_on_error`）
+    bell_on_error: bool,
+    /// AI の応答完了時にベルを鳴らすか（`[shell] bell_on_ai_response`）
+    bell_on_ai_response: bool,
+    /// 起動・終了時のバナー設定（`[banner]`）
+    banner: BannerConfig,
+    /// REPL（対話モード）として起動されたか。`-c` 単体実行では終了確認を行わない。
+    interactive: bool,
+    /// 自然言語と判定された入力を AI にルーティングするか。
+    /// stdin パイプ入力の非対話モードでは `--ai` 指定時のみ `true` になり、
+    /// `false` の場合は全ての入力をコマンドとして実行する。
+    ai_routing: bool,
+    /// `[shell] dry_run` の値
+    dry_run: bool,
+    /// `--dry-run` 起動フラグで dry-run が強制されているか（`source` で設定を再読み込みしても解除しない）
+    dry_run_forced: bool,
     /// `--rcfile` / `--no-rc` CLI オプション（Phase 4.2）。rc.jsh の
     /// 読み込みを `run()` / `run_command()` の両方から解決するために保持する。
     rc_options: RcOptions,
@@ -147,12 +216,26 @@ impl Shell {
         // 設定ファイルの読み込み
         let config = JarvishConfig::load();
 
+        // 子プロセスが正しい $PWD を見られるよう、起動時の CWD で初期化する
+        if let Err(e) = builtins::cwd::sync_pwd() {
+            warn!(error = %e, "Failed to initialize PWD");
+        }
+
         // [export] セクションの環境変数を設定
         Self::apply_exports(&config);
 
+        // [env] persist = true なら `export --persist` で保存した環境変数を設定
+        persist_env::set_enabled(config.env.persist);
+        if config.env.persist {
+            persist_env::apply_saved(&persist_env::default_path());
+        }
+
         // 入力分類器の初期化（キャッシュレス設計: which クレートでリアルタイム PATH 解決）
-        // ハイライターと REPL ループの両方で共有するため Arc で包む
-        let classifier = Arc::new(InputClassifier::new());
+        // ハイライターと REPL ループの両方で共有するため Arc で包む。
+        // `:ai` / `:cmd` で学習したユーザー訂正（classifier_overrides.toml）を最優先で適用する。
+        let classifier = Arc::new(InputClassifier::with_overrides_file(
+            ClassifierOverrides::default_path(),
+        ));
 
         // データディレクトリを一度だけ決定し、エディタ履歴と BlackBox の両方で共有する。
         let data_dir = BlackBox::data_dir();
@@ -218,6 +301,7 @@ impl Shell {
             Arc::clone(&external_completion),
             Arc::clone(&zsh_daemon),
             Arc::clone(&complete_registry),
+            config.editor.hints,
         );
 
         // 直前コマンドの終了コードを共有するアトミック変数
@@ -232,11 +316,15 @@ impl Shell {
         );
         prompt.refresh_git_status();
 
+        // [shell] の実行時設定をエンジンに渡すコンテキスト
+        let exec_context = Self::build_exec_context(&config);
+
         // Black Box（履歴永続化）の初期化
         // BlackBox::open() ではなく open_at() を使い、フォールバック時も同じパスを使用する
         let black_box = match BlackBox::open_at(data_dir, session_id) {
-            Ok(bb) => {
+            Ok(mut bb) => {
                 info!("BlackBox initialized successfully");
+                bb.set_track_env(config.history.track_env.clone());
                 Some(bb)
             }
             Err(e) => {
@@ -246,30 +334,45 @@ impl Shell {
             }
         };
 
-        // AI クライアントの初期化（設定ファイルの [ai] セクションを反映）
-        let ai_client = match JarvisAI::new(&config.ai) {
-            Ok(ai) => {
-                info!("AI client initialized successfully");
-                Some(ai)
-            }
-            Err(e) => {
-                warn!("AI disabled: {e}");
-                eprintln!("jarvish: warning: AI disabled: {e}");
-                None // API キー未設定時は AI 機能を無効化
-            }
-        };
+        // AI クライアントの初期化（設定ファイルの [ai] セクションと起動時のプロファイルを反映）
+        let ai_profile = Self::startup_ai_profile(&config.ai);
+        let ai_client =
+            match JarvisAI::new(&Self::profile_ai_config(&config.ai, ai_profile.as_deref())) {
+                Ok(ai) => {
+                    info!("AI client initialized successfully");
+                    Some(ai)
+                }
+                Err(e) => {
+                    warn!("AI disabled: {e}");
+                    eprintln!("jarvish: warning: AI disabled: {e}");
+                    None // API キー未設定時は AI 機能を無効化
+                }
+            };
 
         Self {
             editor: reedline,
             prompt,
+            set_title: config.prompt.set_title,
             ai_client,
+            ai_config: config.ai.clone(),
+            ai_profile,
             black_box,
             conversation_state: None,
+            after_ai_turn: false,
             last_exit_code,
             cmd_duration_ms,
             classifier,
+            last_input: None,
             aliases,
             ignore_auto_investigation_cmds: config.ai.ignore_auto_investigation_cmds,
+            command_notice: config.ai.command_notice,
+            ai_farewell: config.ai.farewell,
+            ai_edit_before_run: config.ai.edit_before_run,
+            ai_preview_context: config.ai.preview_context,
+            ai_type_ahead: config.ai.type_ahead,
+            ai_max_tool_output_lines: config.ai.max_tool_output_lines,
+            record_ai_conversation: config.history.record_ai_conversation,
+            context_preview_muted: false,
             dir_stack: Vec::new(),
             farewell_shown: false,
             history_available,
@@ -282,17 +385,72 @@ impl Shell {
             complete_registry,
             restart_requested: Arc::new(AtomicBool::new(false)),
             startup_commands: config.startup.commands,
+            confirm_exit: config.shell.confirm_exit,
+            autocorrect: config.shell.autocorrect,
+            auto_cd: config.shell.auto_cd,
+            url_action: config.shell.url_action,
+            auto_ls_on_cd: config.shell.auto_ls_on_cd,
+            cd_hook: config.shell.cd_hook,
+            exec_context,
+            hooks: config.hooks,
+            bell_on_error: config.shell.bell_on_error,
+            bell_on_ai_response: config.shell.bell_on_ai_response,
+            banner: config.banner,
+            interactive,
+            ai_routing: true,
+            dry_run: config.shell.dry_run,
+            dry_run_forced: false,
             rc_options,
             source_depth: 0,
         }
     }
 
+    /// `[ai] profile`（起動時のプロファイル）を返す。
+    ///
+    /// 空文字列なら `None`。`[ai.profiles]` に定義されていない場合は警告して `None`。
+    fn startup_ai_profile(ai_config: &AiConfig) -> Option<String> {
+        let name = ai_config.profile.trim();
+        if name.is_empty() {
+            return None;
+        }
+        if !ai_config.profiles.contains_key(name) {
+            warn!(profile = %name, "Unknown AI profile in [ai] profile");
+            eprintln!("jarvish: warning: unknown AI profile: {name} (using [ai] settings)");
+            return None;
+        }
+        Some(name.to_string())
+    }
+
+    /// プロファイルを適用した AI 設定を返す（`None` や未定義のプロファイルなら `[ai]` のまま）。
+    fn profile_ai_config(ai_config: &AiConfig, profile: Option<&str>) -> AiConfig {
+        profile
+            .and_then(|name| ai_config.with_profile(name))
+            .unwrap_or_else(|| ai_config.clone())
+    }
+
+    /// 現在の AI プロファイルを AI クライアントに反映する。
+    pub(super) fn apply_ai_profile(&mut self) {
+        let config = Self::profile_ai_config(&self.ai_config, self.ai_profile.as_deref());
+        info!(profile = ?self.ai_profile, model = %config.model, "AI profile applied");
+        if let Some(ref mut ai) = self.ai_client {
+            ai.update_config(&config);
+        }
+    }
+
     /// 設定ファイルの `[export]` セクションを環境変数に適用する。
     ///
-    /// 値に含まれる環境変数参照（`$PATH` 等）は展開してから設定する。
+    /// 値に含まれる環境変数参照（`$PATH`・`${VAR:-default}` 等）は展開してから設定する。
+    /// `${VAR:?message}` が失敗した変数は警告を表示して設定しない。
     fn apply_exports(config: &JarvishConfig) {
         for (key, value) in &config.export {
-            let expanded = expand::expand_token(value);
+            let expanded = match expand::try_expand_token(value) {
+                Ok(expanded) => expanded,
+                Err(e) => {
+                    warn!(key = %key, error = %e, "Failed to expand export from config");
+                    eprintln!("jarvish: warning: [export] {key}: {e}");
+                    continue;
+                }
+            };
             let display = format!("{key}={expanded}");
             let masked = if crate::storage::sanitizer::contains_secrets(&display) {
                 crate::storage::sanitizer::mask_secrets(&display)
@@ -360,11 +518,25 @@ impl Shell {
         }
     }
 
+    /// 設定からコマンド実行時にエンジンへ渡すコンテキストを構築する。
+    fn build_exec_context(config: &JarvishConfig) -> ExecContext {
+        ExecContext {
+            truncate_output: config.shell.wrap_output == OutputWrap::Truncate,
+            force_color: config.shell.force_color,
+            cd_to_file_parent: config.shell.cd_to_file_parent,
+            mkdir_on_redirect: config.shell.mkdir_on_redirect,
+            default_timeout: (config.shell.default_command_timeout > 0)
+                .then(|| Duration::from_secs(config.shell.default_command_timeout)),
+            ..Default::default()
+        }
+    }
+
     /// 指定されたパスから設定ファイルを再読み込みし、Shell の状態に反映する。
     ///
     /// `source` ビルトインコマンドから呼び出される。
-    /// `[ai]`、`[alias]`、`[export]`、`[prompt]`、`[completion]`、`[startup]`
-    /// の各セクションを反映する（`[startup]` は値の更新のみで再実行はしない）。
+    /// `[ai]`、`[alias]`、`[export]`、`[prompt]`、`[completion]`、`[startup]`、`[shell]`、
+    /// `[banner]` の各セクションを反映する（`[startup]` は値の更新のみで再実行はしない。
+    /// `[editor]` はエディタ構築時にのみ反映されるため、再起動まで適用されない）。
     pub(super) fn reload_config(&mut self, path: &std::path::Path) -> crate::engine::CommandResult {
         use crate::engine::CommandResult;
 
@@ -384,12 +556,26 @@ impl Shell {
 
         // [export] を反映
         Self::apply_exports(&config);
-
-        // [ai] を反映
-        if let Some(ref mut ai) = self.ai_client {
-            ai.update_config(&config.ai);
+        // PATH が変わりうるため、PATH lookup と補完候補のキャッシュを破棄する
+        self.classifier.reload_path_cache();
+
+        // [ai] を反映（現在のプロファイルが残っていれば維持し、なければ [ai] profile に戻す）
+        let keep_profile = self
+            .ai_profile
+            .as_ref()
+            .is_some_and(|name| config.ai.profiles.contains_key(name));
+        if !keep_profile {
+            self.ai_profile = Self::startup_ai_profile(&config.ai);
         }
+        self.ai_config = config.ai.clone();
+        self.apply_ai_profile();
         self.ignore_auto_investigation_cmds = config.ai.ignore_auto_investigation_cmds.clone();
+        self.command_notice = config.ai.command_notice;
+        self.ai_farewell = config.ai.farewell;
+        self.ai_edit_before_run = config.ai.edit_before_run;
+        self.ai_preview_context = config.ai.preview_context;
+        self.ai_type_ahead = config.ai.type_ahead;
+        self.ai_max_tool_output_lines = config.ai.max_tool_output_lines;
 
         // [prompt] を反映（starship フラグ変更時はプロンプト自体を入れ替え）
         self.prompt = Self::build_prompt(
@@ -398,6 +584,7 @@ impl Shell {
             Arc::clone(&self.cmd_duration_ms),
         );
         self.prompt.refresh_git_status();
+        self.set_title = config.prompt.set_title;
 
         // [completion] を反映
         if let Ok(mut cmds) = self.git_branch_commands.write() {
@@ -418,12 +605,74 @@ impl Shell {
         // [startup] を反映（再実行はしない、値の更新のみ）
         self.startup_commands = config.startup.commands.clone();
 
-        // サマリー出力（config.toml のセクション順: ai, alias, export, prompt, completion, startup）
+        // [shell] を反映
+        self.confirm_exit = config.shell.confirm_exit;
+        self.autocorrect = config.shell.autocorrect;
+        self.auto_cd = config.shell.auto_cd;
+        self.url_action = config.shell.url_action;
+        self.dry_run = config.shell.dry_run;
+        self.auto_ls_on_cd = config.shell.auto_ls_on_cd;
+        self.cd_hook = config.shell.cd_hook.clone();
+        // シェル変数の表は設定の再読み込みでは消さない
+        self.exec_context = ExecContext {
+            variables: self.exec_context.variables.clone(),
+            ..Self::build_exec_context(&config)
+        };
+        self.bell_on_error = config.shell.bell_on_error;
+        self.bell_on_ai_response = config.shell.bell_on_ai_response;
+        self.apply_dry_run();
+
+        // [banner] を反映（次回の終了時・再起動時の表示から適用）
+        self.banner = config.banner.clone();
+        self.hooks = config.hooks.clone();
+
+        // [env] を反映（保存済みの変数の再適用はしない、以後の export / unset から適用）
+        persist_env::set_enabled(config.env.persist);
+
+        // [history] を反映（以後に記録するコマンドから適用）
+        if let Some(ref mut bb) = self.black_box {
+            bb.set_track_env(config.history.track_env.clone());
+        }
+        self.record_ai_conversation = config.history.record_ai_conversation;
+
+        // サマリー出力（config.toml のセクション順: ai, alias, export, prompt, completion, startup, shell, editor, banner, env, history）
         let ignore_cmds_display = if config.ai.ignore_auto_investigation_cmds.is_empty() {
             "none".to_string()
         } else {
             format!("{:?}", config.ai.ignore_auto_investigation_cmds)
         };
+        let profiles_display = if config.ai.profiles.is_empty() {
+            "none".to_string()
+        } else {
+            let mut names: Vec<&String> = config.ai.profiles.keys().collect();
+            names.sort();
+            format!("{names:?}")
+        };
+        let enabled_tools_display = if config.ai.enabled_tools.is_empty() {
+            "none".to_string()
+        } else {
+            format!("{:?}", config.ai.enabled_tools)
+        };
+        let track_env_display = if config.history.track_env.is_empty() {
+            "none".to_string()
+        } else {
+            format!("{:?}", config.history.track_env)
+        };
+        let hook_display = |hook: &str| {
+            if hook.trim().is_empty() {
+                "none".to_string()
+            } else {
+                format!("{hook:?}")
+            }
+        };
+        // [banner] のファイル未指定は組み込みバナーを使うことを示す
+        let banner_file_display = |path: &str| {
+            if path.is_empty() {
+                "builtin".to_string()
+            } else {
+                path.to_string()
+            }
+        };
         let external_mode_display =
             format_external_summary(&config.completion.external.to_string(), &resolved_external);
         // 解決済みの優先順に沿って、各プロバイダのバイナリパス（未検出なら
@@ -436,27 +685,84 @@ impl Shell {
              \x20\x20 model: {}\n\
              \x20\x20 max_rounds: {}\n\
              \x20\x20 markdown_rendering: {}\n\
+             \x20\x20 code_highlight: {}\n\
              \x20\x20 ai_pipe_max_chars: {}\n\
              \x20\x20 ai_redirect_max_chars: {}\n\
              \x20\x20 temperature: {}\n\
+             \x20\x20 top_p: {}\n\
+             \x20\x20 max_tokens: {}\n\
+             \x20\x20 show_usage: {}\n\
+             \x20\x20 command_notice: {}\n\
+             \x20\x20 verbosity: {}\n\
+             \x20\x20 pager: {}\n\
+             \x20\x20 farewell: {}\n\
+             \x20\x20 stream_idle_timeout_secs: {}\n\
+             \x20\x20 edit_before_run: {}\n\
+             \x20\x20 preview_context: {}\n\
+             \x20\x20 type_ahead: {}\n\
              \x20\x20 ignore_auto_investigation_cmds: {}\n\
+             \x20\x20 enabled_tools: {}\n\
+             \x20\x20 show_git_diff_after_write: {}\n\
+             \x20\x20 cache: {} (ttl_secs: {}, max_entries: {})\n\
+             \x20\x20 max_tool_output_lines: {}\n\
+             \x20\x20 profile: {} (profiles: {})\n\
              \x20 [alias]   {} {}\n\
              \x20 [export]  {} {}\n\
-             \x20 [prompt]  nerd_font: {}, starship: {}\n\
+             \x20 [prompt]  nerd_font: {}, starship: {}, success_symbol: {}, error_symbol: {}, set_title: {}\n\
+             \x20\x20 right_segments: {:?}, max_width_behavior: {}\n\
              \x20 [completion]  git_branch_commands: {} {}\n\
              \x20\x20 external: {}\n\
              {}\
              \x20\x20 external_timeout_ms: {}\n\
              \x20\x20 external_zsh_daemon: {}\n\
-             \x20 [startup]  {} {}\n",
+             \x20 [startup]  {} {}\n\
+             \x20 [shell]  confirm_exit: {}, dry_run: {}, auto_ls_on_cd: {}, cd_hook: {}, auto_cd: {}, url_action: {}, cd_to_file_parent: {}, mkdir_on_redirect: {}, wrap_output: {}, autocorrect: {}, force_color: {}, bell_on_error: {}, bell_on_ai_response: {}, default_command_timeout: {}\n\
+             \x20 [editor]  hints: {}\n\
+             \x20 [banner]  enabled: {}, welcome_file: {}, goodbye_file: {}\n\
+             \x20 [env]  persist: {}\n\
+             \x20 [history]  track_env: {}, record_ai_conversation: {}\n\
+             \x20 [hooks]  pre_exec: {}, post_exec: {}\n\
+             \x20 [logging]  level: {}, file: {}, stderr: {}, max_files: {}, retention_days: {}, max_file_size_mb: {} (applied on restart)\n",
             path.display(),
             config.ai.model,
             config.ai.max_rounds,
             config.ai.markdown_rendering,
+            config.ai.code_highlight,
             config.ai.ai_pipe_max_chars,
             config.ai.ai_redirect_max_chars,
-            config.ai.temperature,
+            config.ai
+                .temperature
+                .map_or_else(|| "default".to_string(), |t| t.to_string()),
+            config.ai
+                .top_p
+                .map_or_else(|| "default".to_string(), |p| p.to_string()),
+            config.ai
+                .max_tokens
+                .map_or_else(|| "default".to_string(), |n| n.to_string()),
+            config.ai.show_usage,
+            config.ai.command_notice,
+            config.ai.verbosity,
+            if config.ai.pager.is_empty() {
+                "none"
+            } else {
+                config.ai.pager.as_str()
+            },
+            config.ai.farewell,
+            config.ai.stream_idle_timeout_secs,
+            config.ai.edit_before_run,
+            config.ai.preview_context,
+            config.ai.type_ahead,
             ignore_cmds_display,
+            enabled_tools_display,
+            config.ai.show_git_diff_after_write,
+            config.ai.cache,
+            config.ai.cache_ttl_secs,
+            config.ai.cache_max_entries,
+            config.ai.max_tool_output_lines,
+            self.ai_profile
+                .as_deref()
+                .unwrap_or(builtins::ai::DEFAULT_PROFILE),
+            profiles_display,
             config.alias.len(),
             if config.alias.len() == 1 {
                 "entry"
@@ -471,6 +777,11 @@ impl Shell {
             },
             config.prompt.nerd_font,
             config.prompt.starship,
+            config.prompt.success_symbol,
+            config.prompt.error_symbol,
+            config.prompt.set_title,
+            config.prompt.right_segments,
+            config.prompt.max_width_behavior,
             config.completion.git_branch_commands.len(),
             if config.completion.git_branch_commands.len() == 1 {
                 "command"
@@ -487,6 +798,35 @@ impl Shell {
             } else {
                 "commands"
             },
+            config.shell.confirm_exit,
+            config.shell.dry_run,
+            config.shell.auto_ls_on_cd,
+            config.shell.cd_hook,
+            config.shell.auto_cd,
+            config.shell.url_action,
+            config.shell.cd_to_file_parent,
+            config.shell.mkdir_on_redirect,
+            config.shell.wrap_output,
+            config.shell.autocorrect,
+            config.shell.force_color,
+            config.shell.bell_on_error,
+            config.shell.bell_on_ai_response,
+            config.shell.default_command_timeout,
+            config.editor.hints,
+            config.banner.enabled,
+            banner_file_display(&config.banner.welcome_file),
+            banner_file_display(&config.banner.goodbye_file),
+            config.env.persist,
+            track_env_display,
+            config.history.record_ai_conversation,
+            hook_display(&config.hooks.pre_exec),
+            hook_display(&config.hooks.post_exec),
+            config.logging.level,
+            config.logging.file,
+            config.logging.stderr,
+            config.logging.max_files,
+            config.logging.retention_days,
+            config.logging.max_file_size_mb,
         );
         print!("{summary}");
 
@@ -509,12 +849,9 @@ impl Shell {
         if self.rc_options.rcfile.is_some()
             && rc::RcOutcome::ExitRequested == self.run_configured_rc().await
         {
-            if let Some(ref bb) = self.black_box {
-                bb.release_session();
-            }
-            let code = self.last_exit_code.load(Ordering::Relaxed);
-            return if code == EXIT_CODE_NONE { 0 } else { code };
+            return self.finish_non_interactive();
         }
+        self.apply_dry_run();
 
         for line in command.lines() {
             if !self.handle_input(line).await {
@@ -522,9 +859,123 @@ impl Shell {
             }
         }
 
+        self.finish_non_interactive()
+    }
+
+    /// `jarvish <script>` 指定時の非対話モード。
+    ///
+    /// スクリプトファイルを rc スクリプトと同じ実行器（[`Self::run_rc_script`]、
+    /// 分類器を経由しない・失敗した行は行番号付きで報告して継続・`exit` で終了）で
+    /// 実行して終了する。rc スクリプトの扱いは `run_command()` と同じく
+    /// `--rcfile` 明示時のみ読み込む。
+    ///
+    /// 戻り値: 最後に実行したコマンドの終了コード（ファイルが読めなかった場合は 1）。
+    pub async fn run_script(&mut self, path: &Path) -> i32 {
+        if self.rc_options.rcfile.is_some()
+            && rc::RcOutcome::ExitRequested == self.run_configured_rc().await
+        {
+            return self.finish_non_interactive();
+        }
+        self.apply_dry_run();
+
+        let display_name = path.display().to_string();
+        let outcome = self.run_rc_script(path, &display_name, 0).await;
+        if outcome == (rc::RcOutcome::Continue { had_failure: true })
+            && self.last_exit_code.load(Ordering::Relaxed) == EXIT_CODE_NONE
+        {
+            self.last_exit_code.store(1, Ordering::Relaxed);
+        }
+
+        self.finish_non_interactive()
+    }
+
+    /// stdin がパイプ（端末でない）の場合の非対話モード。
+    ///
+    /// `echo "ls -la" | jarvish` のように stdin から 1 行ずつ読み込み、
+    /// `handle_input()` で処理して EOF で終了する。reedline・バナー・
+    /// プロンプトは使わない。rc スクリプトの扱いは `run_command()` と同じく
+    /// `--rcfile` 明示時のみ読み込む。
+    ///
+    /// `ai_routing` が `false`（`--ai` 未指定）の場合、自然言語と判定される
+    /// 入力も含めて全ての行をコマンドとして実行する（パイプ段としての
+    /// 挙動を決定的にするため）。
+    ///
+    /// 戻り値: 最後に実行したコマンドの終了コード。
+    pub async fn run_stdin(&mut self, ai_routing: bool) -> i32 {
+        self.ai_routing = ai_routing;
+
+        if self.rc_options.rcfile.is_some()
+            && rc::RcOutcome::ExitRequested == self.run_configured_rc().await
+        {
+            return self.finish_non_interactive();
+        }
+        self.apply_dry_run();
+
+        loop {
+            let mut line = String::new();
+            let read = tokio::task::block_in_place(|| std::io::stdin().read_line(&mut line));
+            match read {
+                Ok(0) => break, // EOF
+                Ok(_) => {
+                    if !self.handle_input(&line).await {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    warn!(error = %e, "Failed to read line from stdin");
+                    eprintln!("jarvish: failed to read stdin: {e}");
+                    self.last_exit_code.store(1, Ordering::Relaxed);
+                    break;
+                }
+            }
+        }
+
+        self.finish_non_interactive()
+    }
+
+    /// `--dry-run` 起動フラグを反映する（`[shell] dry_run` の値に関わらず dry-run にする）。
+    ///
+    /// 実際の有効化は rc.jsh・`[startup]` の実行後（[`Self::apply_dry_run`]）に行う。
+    pub fn force_dry_run(&mut self) {
+        self.dry_run_forced = true;
+    }
+
+    /// `--no-ai` 起動フラグを反映する（AI クライアントを無効化し、全ての入力をコマンドとして実行する）。
+    ///
+    /// AI パイプ・エラー調査も AI オフライン時と同じく行わない。
+    pub fn disable_ai(&mut self) {
+        self.ai_client = None;
+        self.ai_routing = false;
+    }
+
+    /// dry-run モードをエンジンに反映する。
+    ///
+    /// rc.jsh や `[startup].commands` の `alias` / `export` 等は実際に適用したいため、
+    /// それらを実行し終えてから呼び出す。
+    fn apply_dry_run(&self) {
+        dry_run::set_enabled(self.dry_run || self.dry_run_forced);
+    }
+
+    /// シェル終了前に履歴をディスクへ確実に反映する。
+    ///
+    /// reedline 側の履歴（`BlackBoxHistory::sync`）と BlackBox の WAL を
+    /// データベース本体に書き戻し、session_id を NULL に解放して次回起動時に
+    /// 上下矢印で辿れるようにする。
+    fn close_session(&mut self) {
+        if let Err(e) = self.editor.sync_history() {
+            warn!(error = %e, "Failed to sync line editor history");
+        }
         if let Some(ref bb) = self.black_box {
             bb.release_session();
+            bb.checkpoint();
         }
+    }
+
+    /// 非対話実行（`-c` / stdin パイプ）の終了処理。
+    ///
+    /// 履歴をフラッシュしてセッションを解放し、最後の終了コード（未実行なら 0）を返す。
+    fn finish_non_interactive(&mut self) -> i32 {
+        self.close_session();
 
         let code = self.last_exit_code.load(Ordering::Relaxed);
         if code == EXIT_CODE_NONE {
@@ -557,7 +1008,7 @@ impl Shell {
         if self.ai_client.is_none() {
             offline.push("AI module offline");
         }
-        crate::cli::banner::print_welcome(&offline);
+        crate::cli::banner::print_welcome(&self.banner, &offline);
 
         // バックグラウンドでバージョンチェックを実行（24時間キャッシュ付き）
         let update_check = tokio::spawn(crate::cli::update_check::check_for_update_notification());
@@ -582,9 +1033,7 @@ impl Shell {
         // 初回起動時にコメントのみのテンプレートを自動生成する。
         if rc::RcOutcome::ExitRequested == self.run_configured_rc().await {
             info!("rc.jsh triggered shell exit");
-            if let Some(ref bb) = self.black_box {
-                bb.release_session();
-            }
+            self.close_session();
             let exit_code = self.last_exit_code.load(Ordering::Relaxed);
             return (
                 if exit_code == EXIT_CODE_NONE {
@@ -613,9 +1062,7 @@ impl Shell {
                 if !self.handle_input(cmd).await {
                     // exit 等でシェル終了が要求された場合
                     info!("Startup command triggered shell exit");
-                    if let Some(ref bb) = self.black_box {
-                        bb.release_session();
-                    }
+                    self.close_session();
                     let exit_code = self.last_exit_code.load(Ordering::Relaxed);
                     return (
                         if exit_code == EXIT_CODE_NONE {
@@ -630,6 +1077,12 @@ impl Shell {
             }
         }
 
+        self.apply_dry_run();
+        if dry_run::is_enabled() {
+            jarvis_talk("Dry-run mode is active, sir. Commands will be shown, not executed.");
+            println!();
+        }
+
         loop {
             // 別プロセスの update コマンドによるフラグファイルを検出し、通知を表示
             if let Some(notification) = crate::engine::builtins::update::check_update_flag() {
@@ -645,6 +1098,24 @@ impl Shell {
                 break;
             }
 
+            // AI 応答中に打鍵された入力を次の入力行に引き継ぐ
+            if let Some(typed) = crate::ai::type_ahead::take() {
+                match self.ai_type_ahead {
+                    TypeAhead::Keep => self
+                        .editor
+                        .run_edit_commands(&[EditCommand::InsertString(typed)]),
+                    TypeAhead::Discard => {
+                        debug!(chars = typed.len(), "Discarding type-ahead input")
+                    }
+                }
+            }
+
+            if self.set_title {
+                if let Ok(cwd) = std::env::current_dir() {
+                    title::set_prompt(&cwd);
+                }
+            }
+
             let signal = tokio::task::block_in_place(|| self.editor.read_line(&self.prompt));
 
             // read_line の完了後にシグナルフラグをチェック
@@ -666,6 +1137,8 @@ impl Shell {
                         }
                         break;
                     }
+                    // 次の入力のハイライトに備えて、`$PATH` が変わっていればキャッシュを差分更新する
+                    self.classifier.reload_path_cache_incremental();
                     self.prompt.refresh_git_status();
                 }
                 Ok(Signal::CtrlC) => {
@@ -674,7 +1147,11 @@ impl Shell {
                     println!(); // 改行して次のプロンプトを見やすくする
                 }
                 Ok(Signal::CtrlD) => {
-                    // EOF → シェル終了
+                    // EOF → シェル終了（`[shell] confirm_exit = true` なら確認してから）
+                    if self.confirm_exit && !jarvis_ask_exit() {
+                        info!("Ctrl-D received but exit was cancelled by user");
+                        continue;
+                    }
                     info!("\n!!!! Ctrl-D received: exiting shell !!!!!\n");
                     break;
                 }
@@ -689,13 +1166,11 @@ impl Shell {
 
         // Farewell メッセージ表示（再起動時と AI goodbye 表示済みの場合はスキップ）
         if action != LoopAction::Restart && !self.farewell_shown {
-            crate::cli::banner::print_goodbye();
+            crate::cli::banner::print_goodbye(&self.banner);
         }
 
-        // セッション終了: session_id を NULL に解放し、次回起動時に履歴を辿れるようにする
-        if let Some(ref bb) = self.black_box {
-            bb.release_session();
-        }
+        // セッション終了: 履歴をディスクに反映し、次回起動時に辿れるようにする
+        self.close_session();
 
         // 終了コードを決定
         let exit_code = if repl_error {
@@ -867,6 +1342,9 @@ impl Shell {
         // Rust の `Drop` が一切実行されない（A1, #89 レビュー指摘）。
         self.shutdown_zsh_daemon();
 
+        // Ctrl+Z で停止したままのジョブは exec() 後に再開できないため終了させる
+        crate::engine::exec::hangup_stopped_jobs();
+
         // stdout/stderr をフラッシュ
         let _ = std::io::Write::flush(&mut std::io::stdout());
         let _ = std::io::Write::flush(&mut std::io::stderr());
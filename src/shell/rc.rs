@@ -3,7 +3,8 @@
 //! `~/.config/jarvish/rc.jsh` は、対話起動のたびに `[startup].commands`
 //! （config.toml）より前に読み込まれるプレーンテキストのコマンドスクリプト。
 //! `complete` ビルトイン等、セッション限りだった状態をファイルとして
-//! 永続化する受け皿になる。
+//! 永続化する受け皿になる。`~/.jarvishrc` がある場合は、rc.jsh（あれば）の
+//! 後にそちらも同じ実行器で読み込む。
 //!
 //! 実行される各行は **分類器（AI ルーティング）を一切経由しない**。
 //! 先頭トークンのエイリアス展開（`handle_input` ステップ0と同一）の後、
@@ -64,6 +65,9 @@ pub(super) enum ResolvedRc {
     Default(PathBuf),
     /// `--rcfile` で明示指定されたパス。自動生成は行わない。
     Explicit(PathBuf),
+    /// `~/.jarvishrc`（`home`）が存在する。デフォルトパスもあれば先に実行する。
+    /// 自動生成は行わない。
+    DefaultWithHome { default: PathBuf, home: PathBuf },
     /// `--no-rc` 指定、または明示パスが見つからず読み込むものがない。
     None,
 }
@@ -76,6 +80,8 @@ impl RcOptions {
     /// - `rcfile` が `Some` ならそれを [`ResolvedRc::Explicit`] として返す
     ///   （存在確認は呼び出し側が行う）。
     /// - どちらも未指定ならデフォルトパスを [`ResolvedRc::Default`] として返す。
+    ///   ただし `~/.jarvishrc` が存在する場合は [`ResolvedRc::DefaultWithHome`] を返す
+    ///   （両方ある場合は両方を実行する）。
     pub(super) fn resolve(&self) -> ResolvedRc {
         if self.no_rc {
             return ResolvedRc::None;
@@ -83,7 +89,12 @@ impl RcOptions {
         if let Some(ref path) = self.rcfile {
             return ResolvedRc::Explicit(path.clone());
         }
-        ResolvedRc::Default(rc_path())
+        let default = rc_path();
+        let home = home_rc_path();
+        if home.is_file() {
+            return ResolvedRc::DefaultWithHome { default, home };
+        }
+        ResolvedRc::Default(default)
     }
 }
 
@@ -110,6 +121,9 @@ pub(super) enum RcBootstrapPlan {
     /// このパスをそのまま実行する。自動生成は行わない
     /// （`--rcfile` で明示指定されたパスが存在する場合）。
     RunExplicit { path: PathBuf, display_name: String },
+    /// デフォルトパス（存在する場合のみ）、`~/.jarvishrc` の順に実行する。
+    /// 自動生成は行わない。
+    RunDefaultAndHome { default: PathBuf, home: PathBuf },
     /// 明示 `--rcfile` パスが存在しない —— 警告のみで実行はしない。
     ExplicitMissing { path: PathBuf },
 }
@@ -126,6 +140,9 @@ pub(super) fn plan_rc_bootstrap(resolved: ResolvedRc) -> RcBootstrapPlan {
             path,
             display_name: "rc.jsh",
         },
+        ResolvedRc::DefaultWithHome { default, home } => {
+            RcBootstrapPlan::RunDefaultAndHome { default, home }
+        }
         ResolvedRc::Explicit(path) => {
             if !path.exists() {
                 return RcBootstrapPlan::ExplicitMissing { path };
@@ -287,6 +304,16 @@ pub(super) fn rc_path() -> PathBuf {
         .join(".config/jarvish/rc.jsh")
 }
 
+/// bash の `.bashrc` に倣ったホーム直下の rc スクリプト（`~/.jarvishrc`）のパス。
+///
+/// rc.jsh の後に読み込む（[`RcOptions::resolve`]）。
+pub(super) fn home_rc_path() -> PathBuf {
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join(".jarvishrc")
+}
+
 /// rc.jsh が存在しなければコメントのみのテンプレートを生成する。
 ///
 /// 既存ファイルは絶対に上書きしない。生成に失敗した場合は警告を表示して
@@ -597,6 +624,8 @@ impl Shell {
     ///   デフォルトパスのテンプレート自動生成も行わない。
     /// - [`ResolvedRc::Default`][]: デフォルトパスが存在しなければテンプレートを
     ///   自動生成してから実行する（[`ensure_default_rc`]）。
+    /// - [`ResolvedRc::DefaultWithHome`][]: デフォルトパス（存在する場合のみ）、
+    ///   `~/.jarvishrc` の順に実行する。前者で `exit` が要求されたら後者は実行しない。
     /// - [`ResolvedRc::Explicit`][]: 指定パスをそのまま実行する。自動生成は
     ///   行わない。ファイルが存在しない場合は
     ///   `jarvish: rcfile not found: {path}` を stderr に出して
@@ -613,6 +642,23 @@ impl Shell {
                 info!(path = %path.display(), "Executing explicit --rcfile");
                 self.run_rc_script(&path, &display_name, 0).await
             }
+            RcBootstrapPlan::RunDefaultAndHome { default, home } => {
+                let mut default_failed = false;
+                if default.exists() {
+                    info!(path = %default.display(), "Executing rc.jsh");
+                    match self.run_rc_script(&default, "rc.jsh", 0).await {
+                        RcOutcome::ExitRequested => return RcOutcome::ExitRequested,
+                        RcOutcome::Continue { had_failure } => default_failed = had_failure,
+                    }
+                }
+                info!(path = %home.display(), "Executing ~/.jarvishrc");
+                match self.run_rc_script(&home, ".jarvishrc", 0).await {
+                    RcOutcome::ExitRequested => RcOutcome::ExitRequested,
+                    RcOutcome::Continue { had_failure } => RcOutcome::Continue {
+                        had_failure: default_failed || had_failure,
+                    },
+                }
+            }
             RcBootstrapPlan::ExplicitMissing { path } => {
                 eprintln!("jarvish: rcfile not found: {}", path.display());
                 RcOutcome::Continue { had_failure: false }
@@ -785,10 +831,10 @@ impl Shell {
         if let Some(result) = self.try_shell_builtins(line) {
             return RcLineOutcome::Ran(result);
         }
-        if let Some(result) = try_builtin(line) {
+        if let Some(result) = try_builtin(line, &self.exec_context) {
             return RcLineOutcome::Ran(result);
         }
-        RcLineOutcome::Ran(execute(line))
+        RcLineOutcome::Ran(execute(line, &self.exec_context))
     }
 }
 
@@ -802,6 +848,7 @@ enum RcLineOutcome {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::ExecContext;
     use serial_test::serial;
 
     // ── parse_rc_lines ──
@@ -978,6 +1025,50 @@ mod tests {
         }
     }
 
+    #[test]
+    #[serial]
+    fn resolve_runs_home_rc_alongside_rc_jsh() {
+        let home = tempfile::tempdir().unwrap();
+        let original = std::env::var("HOME").ok();
+        unsafe {
+            std::env::set_var("HOME", home.path());
+        }
+        let opts = RcOptions::default();
+
+        // どちらもなければデフォルトパス（テンプレート自動生成の対象）
+        assert!(matches!(opts.resolve(), ResolvedRc::Default(_)));
+
+        // ~/.jarvishrc があれば rc.jsh の有無に関わらず読む（テンプレートは生成しない）
+        let rc = home.path().join(".config/jarvish/rc.jsh");
+        let home_rc = home.path().join(".jarvishrc");
+        std::fs::write(&home_rc, "alias ll='ls -l'\n").unwrap();
+        assert_eq!(
+            plan_rc_bootstrap(opts.resolve()),
+            RcBootstrapPlan::RunDefaultAndHome {
+                default: rc.clone(),
+                home: home_rc.clone(),
+            }
+        );
+
+        // rc.jsh もあれば両方を実行する（rc.jsh が先）
+        std::fs::create_dir_all(rc.parent().unwrap()).unwrap();
+        std::fs::write(&rc, "").unwrap();
+        match opts.resolve() {
+            ResolvedRc::DefaultWithHome { default, home } => {
+                assert_eq!(default, rc);
+                assert_eq!(home, home_rc);
+            }
+            other => panic!("expected ResolvedRc::DefaultWithHome, got {other:?}"),
+        }
+
+        unsafe {
+            match original {
+                Some(home) => std::env::set_var("HOME", home),
+                None => std::env::remove_var("HOME"),
+            }
+        }
+    }
+
     // ── plan_rc_bootstrap（Fix C4: --no-rc の対話コードパス回帰防止）──
     //
     // `Shell::run_configured_rc`（対話 `run()` / `-c` の両方から呼ばれる、
@@ -1342,7 +1433,8 @@ mod tests {
         // run_rc_line が exit 行を RcLineOutcome::Ran として受け取り、
         // その action が LoopAction::Exit であることを直接確認する
         // (run_rc_script はこれを見て ExitRequested を返す)。
-        let result = try_builtin("exit").expect("exit must be a recognized builtin");
+        let result = try_builtin("exit", &ExecContext::default())
+            .expect("exit must be a recognized builtin");
         assert_eq!(result.action, LoopAction::Exit);
     }
 
@@ -1355,8 +1447,11 @@ mod tests {
         let original = std::env::current_dir().expect("failed to get current dir");
         let tmpdir = tempfile::tempdir().unwrap();
 
-        let result = try_builtin(&format!("cd {}", tmpdir.path().display()))
-            .expect("cd must be a recognized builtin");
+        let result = try_builtin(
+            &format!("cd {}", tmpdir.path().display()),
+            &ExecContext::default(),
+        )
+        .expect("cd must be a recognized builtin");
         assert_eq!(result.action, LoopAction::Continue);
 
         std::env::set_current_dir(&original).expect("failed to restore current dir");
@@ -1366,7 +1461,7 @@ mod tests {
     fn execute_unknown_command_line_is_nonzero_exit_but_continues() {
         // 分類器を経由しないため、自然言語らしい行はただの「不明なコマンド」
         // として失敗する（AI には絶対にルーティングされない）。
-        let result = execute("please explain this error to me");
+        let result = execute("please explain this error to me", &ExecContext::default());
         assert_ne!(result.exit_code, 0);
     }
 
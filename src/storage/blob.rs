@@ -1,11 +1,14 @@
 use anyhow::{Context, Result};
 use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::fs;
-use std::io::Read;
-use std::path::PathBuf;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 /// Git のようなコンテンツアドレッサブルストレージ。
 /// テキストを SHA-256 でハッシュ化し、zstd 圧縮して保存する。
+#[derive(Clone)]
 pub struct BlobStore {
     base_dir: PathBuf,
 }
@@ -29,9 +32,12 @@ impl BlobStore {
         let hash = Self::sha256_hex(content);
         let blob_path = self.blob_path(&hash);
 
-        // 同一ハッシュの Blob が既に存在する場合はスキップ（冪等）
-        if blob_path.exists() {
-            return Ok(Some(hash));
+        // 同一ハッシュの Blob が既に存在する場合は書き込まない（冪等）。
+        // 再び参照されたばかりの Blob を別セッションの GC（`min_age` の猶予）が消さないよう、
+        // 更新時刻だけ新しくする。その間に GC で消えていれば書き直す
+        match Self::touch(&blob_path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            _ => return Ok(Some(hash)),
         }
 
         // ディレクトリ作成（先頭2文字のサブディレクトリ）
@@ -68,6 +74,80 @@ impl BlobStore {
         Ok(decompressed)
     }
 
+    /// 保存されているすべての Blob のハッシュを列挙する。
+    ///
+    /// `blobs/{先頭2文字}/{残り}` の形式に合わないファイル・ディレクトリは無視する。
+    pub fn list_hashes(&self) -> Result<Vec<String>> {
+        let mut hashes = Vec::new();
+        let entries = fs::read_dir(&self.base_dir).with_context(|| {
+            format!("failed to read blob directory: {}", self.base_dir.display())
+        })?;
+        for prefix_entry in entries.flatten() {
+            let prefix = prefix_entry.file_name().to_string_lossy().into_owned();
+            if prefix.len() != 2 || !prefix_entry.path().is_dir() {
+                continue;
+            }
+            let Ok(files) = fs::read_dir(prefix_entry.path()) else {
+                continue;
+            };
+            for file in files.flatten() {
+                let hash = format!("{prefix}{}", file.file_name().to_string_lossy());
+                if hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                    hashes.push(hash);
+                }
+            }
+        }
+        hashes.sort();
+        Ok(hashes)
+    }
+
+    /// `referenced` に含まれない孤立 Blob を削除する（ガベージコレクション）。
+    ///
+    /// 並行する別セッションが Blob を保存してから履歴行を書き込むまでの間に消さないよう、
+    /// 更新から `min_age` 経っていない Blob は残す。空になったサブディレクトリも削除する。
+    pub fn collect_garbage(
+        &self,
+        referenced: &HashSet<String>,
+        min_age: Duration,
+    ) -> Result<GcStats> {
+        let now = SystemTime::now();
+        let mut stats = GcStats::default();
+        for hash in self.list_hashes()? {
+            if referenced.contains(&hash) {
+                continue;
+            }
+            let path = self.blob_path(&hash);
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            if age < min_age {
+                continue;
+            }
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove blob: {}", path.display()))?;
+            stats.removed += 1;
+            stats.freed_bytes += metadata.len();
+            if let Some(parent) = path.parent() {
+                // 他の Blob が残っていれば失敗するだけなので結果は無視する
+                let _ = fs::remove_dir(parent);
+            }
+        }
+        Ok(stats)
+    }
+
+    /// ファイルの更新時刻を現在時刻にする。
+    fn touch(path: &Path) -> io::Result<()> {
+        fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(SystemTime::now())
+    }
+
     /// SHA-256 ハッシュの16進文字列を計算する。
     fn sha256_hex(content: &str) -> String {
         let mut hasher = Sha256::new();
@@ -84,6 +164,15 @@ impl BlobStore {
     }
 }
 
+/// [`BlobStore::collect_garbage`] の結果
+#[derive(Debug, Default, PartialEq)]
+pub struct GcStats {
+    /// 削除した Blob の数
+    pub removed: usize,
+    /// 削除した Blob の合計サイズ（圧縮後のバイト数）
+    pub freed_bytes: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,4 +239,73 @@ mod tests {
         let result = store.load("0000000000000000000000000000000000000000000000000000000000000000");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn list_hashes_returns_stored_blobs() {
+        let tmp = TempDir::new().unwrap();
+        let store = BlobStore::new(tmp.path().join("blobs")).unwrap();
+
+        let mut expected = vec![
+            store.store("first").unwrap().unwrap(),
+            store.store("second").unwrap().unwrap(),
+        ];
+        expected.sort();
+        // 形式に合わないファイルは無視する
+        fs::write(tmp.path().join("blobs").join("README"), "x").unwrap();
+
+        assert_eq!(store.list_hashes().unwrap(), expected);
+    }
+
+    #[test]
+    fn collect_garbage_removes_only_unreferenced_blobs() {
+        let tmp = TempDir::new().unwrap();
+        let store = BlobStore::new(tmp.path().join("blobs")).unwrap();
+
+        let kept = store.store("kept output").unwrap().unwrap();
+        let orphan = store.store("orphan output").unwrap().unwrap();
+        let orphan_size = fs::metadata(store.blob_path(&orphan)).unwrap().len();
+        let referenced: HashSet<String> = [kept.clone()].into_iter().collect();
+
+        // 作成直後の Blob は猶予期間内なので残す
+        let stats = store
+            .collect_garbage(&referenced, Duration::from_secs(3600))
+            .unwrap();
+        assert_eq!(stats, GcStats::default());
+
+        let stats = store.collect_garbage(&referenced, Duration::ZERO).unwrap();
+        assert_eq!(
+            stats,
+            GcStats {
+                removed: 1,
+                freed_bytes: orphan_size,
+            }
+        );
+        assert_eq!(store.list_hashes().unwrap(), vec![kept.clone()]);
+        assert!(store.load(&kept).is_ok());
+        // 空になったサブディレクトリも消える
+        assert!(!store.blob_path(&orphan).parent().unwrap().exists());
+    }
+    #[test]
+    fn storing_existing_blob_refreshes_mtime() {
+        let tmp = TempDir::new().unwrap();
+        let store = BlobStore::new(tmp.path().join("blobs")).unwrap();
+
+        let hash = store.store("reused output").unwrap().unwrap();
+        let path = store.blob_path(&hash);
+        let old = SystemTime::now() - Duration::from_secs(7200);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        // 別セッションが同じ内容を保存し直した直後は GC の猶予期間内になる
+        assert_eq!(store.store("reused output").unwrap(), Some(hash.clone()));
+        let stats = store
+            .collect_garbage(&HashSet::new(), Duration::from_secs(3600))
+            .unwrap();
+        assert_eq!(stats, GcStats::default());
+        assert!(store.load(&hash).is_ok());
+    }
 }
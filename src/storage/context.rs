@@ -22,35 +22,119 @@ impl super::BlackBox {
 
         let mut context = String::from("=== Recent Command History ===\n");
         for entry in &entries {
-            let masked_command = if sanitizer::contains_secrets(&entry.command) {
-                sanitizer::mask_secrets(&entry.command)
-            } else {
-                entry.command.clone()
-            };
-            context.push_str(&format!(
-                "\n[#{}] {} (exit: {}, cwd: {})\n",
-                entry.id, masked_command, entry.exit_code, entry.cwd
-            ));
-            if let Some(ref stdout) = entry.stdout {
-                let truncated = Self::truncate_lines(stdout, 50);
-                if !truncated.is_empty() {
-                    context.push_str(&format!("stdout:\n{truncated}\n"));
-                }
+            Self::push_entry(&mut context, entry, |text| Self::truncate_lines(text, 50));
+        }
+        Ok(context)
+    }
+
+    /// 直前 1 件のコマンド履歴を、stdout/stderr を行数で切り詰めずに AI 用コンテキスト文字列にする。
+    /// トークン上限を考慮し、各出力は末尾 `max_chars` 文字までに制限する。
+    pub fn get_last_entry_context(&self, max_chars: usize) -> Result<String> {
+        let Some(entry) = self.get_recent_entries(1)?.into_iter().next() else {
+            return Ok(String::new());
+        };
+        debug!(id = entry.id, max_chars, "get_last_entry_context()");
+
+        let mut context = String::from("=== Last Command (full output) ===\n");
+        Self::push_entry(&mut context, &entry, |text| {
+            Self::truncate_chars(text, max_chars)
+        });
+        Ok(context)
+    }
+
+    /// エラー調査用に、直近 N 件のうち失敗したコマンドを優先したコンテキスト文字列を生成する。
+    ///
+    /// - 失敗したコマンド（exit != 0）を先に並べ、stderr は行数で切り詰めない
+    ///   （各 stderr は末尾 `max_chars` 文字まで。stdout は末尾 50 行）
+    /// - 成功したコマンドはコマンド行のみに簡略化する
+    pub fn get_error_context(&self, limit: usize, max_chars: usize) -> Result<String> {
+        let entries = self.get_recent_entries(limit)?;
+        let (failed, succeeded): (Vec<_>, Vec<_>) =
+            entries.iter().partition(|entry| entry.exit_code != 0);
+        debug!(
+            requested = limit,
+            failed = failed.len(),
+            succeeded = succeeded.len(),
+            "get_error_context()"
+        );
+        if entries.is_empty() {
+            return Ok(String::new());
+        }
+
+        let mut context = String::new();
+        if !failed.is_empty() {
+            context.push_str("=== Recent Failed Commands (full stderr) ===\n");
+            for entry in failed {
+                Self::push_entry_with(
+                    &mut context,
+                    entry,
+                    |text| Self::truncate_lines(text, 50),
+                    |text| Self::truncate_chars(text, max_chars),
+                );
             }
-            if let Some(ref stderr) = entry.stderr {
-                let truncated = Self::truncate_lines(stderr, 50);
-                if !truncated.is_empty() {
-                    context.push_str(&format!("stderr:\n{truncated}\n"));
-                }
+        }
+        if !succeeded.is_empty() {
+            if !context.is_empty() {
+                context.push('\n');
+            }
+            context.push_str("=== Other Recent Commands (output omitted) ===\n");
+            for entry in succeeded {
+                Self::push_header(&mut context, entry);
             }
         }
         Ok(context)
     }
 
+    /// 履歴エントリ 1 件をコンテキスト文字列に追記する（コマンド中の秘密情報はマスクする）。
+    fn push_entry(context: &mut String, entry: &HistoryEntry, truncate: impl Fn(&str) -> String) {
+        Self::push_entry_with(context, entry, &truncate, &truncate);
+    }
+
+    /// [`push_entry`](Self::push_entry) の stdout / stderr で切り詰め方を変える版。
+    fn push_entry_with(
+        context: &mut String,
+        entry: &HistoryEntry,
+        truncate_stdout: impl Fn(&str) -> String,
+        truncate_stderr: impl Fn(&str) -> String,
+    ) {
+        Self::push_header(context, entry);
+        if entry.binary_output {
+            // バイナリ出力は AI にとって無意味なため中身を渡さない
+            context.push_str("(binary output omitted)\n");
+            return;
+        }
+        if let Some(ref stdout) = entry.stdout {
+            let truncated = truncate_stdout(stdout);
+            if !truncated.is_empty() {
+                context.push_str(&format!("stdout:\n{truncated}\n"));
+            }
+        }
+        if let Some(ref stderr) = entry.stderr {
+            let truncated = truncate_stderr(stderr);
+            if !truncated.is_empty() {
+                context.push_str(&format!("stderr:\n{truncated}\n"));
+            }
+        }
+    }
+
+    /// エントリの見出し行（ID・コマンド・終了コード・cwd）を追記する。
+    fn push_header(context: &mut String, entry: &HistoryEntry) {
+        let masked_command = if sanitizer::contains_secrets(&entry.command) {
+            sanitizer::mask_secrets(&entry.command)
+        } else {
+            entry.command.clone()
+        };
+        context.push_str(&format!(
+            "\n[#{}] {} (exit: {}, cwd: {})\n",
+            entry.id, masked_command, entry.exit_code, entry.cwd
+        ));
+    }
+
     /// 直近 N 件のコマンド履歴エントリを取得する（新しい順）。
     fn get_recent_entries(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, command, cwd, exit_code, stdout_hash, stderr_hash, created_at
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, command, cwd, exit_code, stdout_hash, stderr_hash, created_at, binary_output
              FROM command_history
              ORDER BY id DESC
              LIMIT ?1",
@@ -65,19 +149,24 @@ impl super::BlackBox {
                 row.get::<_, Option<String>>(4)?,
                 row.get::<_, Option<String>>(5)?,
                 row.get::<_, String>(6)?,
+                row.get::<_, bool>(7)?,
             ))
         })?;
 
         let mut entries = Vec::new();
         for row in rows {
-            let (id, command, cwd, exit_code, stdout_hash, stderr_hash, created_at) = row?;
+            let (id, command, cwd, exit_code, stdout_hash, stderr_hash, created_at, binary_output) =
+                row?;
 
+            // バイナリ出力の Blob は AI コンテキストに使わないため読み込まない
             let stdout = stdout_hash
+                .filter(|_| !binary_output)
                 .as_deref()
                 .map(|h| self.blob_store.load(h))
                 .transpose()
                 .unwrap_or(None);
             let stderr = stderr_hash
+                .filter(|_| !binary_output)
                 .as_deref()
                 .map(|h| self.blob_store.load(h))
                 .transpose()
@@ -91,12 +180,24 @@ impl super::BlackBox {
                 stdout,
                 stderr,
                 created_at,
+                binary_output,
             });
         }
 
         Ok(entries)
     }
 
+    /// テキストを末尾 N 文字に切り詰める（文字境界を保つ）。
+    fn truncate_chars(text: &str, max_chars: usize) -> String {
+        let total = text.chars().count();
+        if total <= max_chars {
+            return text.to_string();
+        }
+        let skip = total - max_chars;
+        let tail: String = text.chars().skip(skip).collect();
+        format!("... ({skip} chars omitted) ...\n{tail}")
+    }
+
     /// テキストを末尾 N 行に切り詰める。
     fn truncate_lines(text: &str, max_lines: usize) -> String {
         let lines: Vec<&str> = text.lines().collect();
@@ -12,6 +12,7 @@ use reedline::{
     SearchDirection, SearchQuery,
 };
 use rusqlite::{types::Value, Connection};
+use tracing::debug;
 
 /// `HistorySessionId::new()` は `pub(crate)` のため外部から呼べない。
 /// `Deserialize` derive を利用して `serde_json` 経由で生成する。
@@ -55,7 +56,9 @@ impl BlackBoxHistory {
                 stdout_hash TEXT,
                 stderr_hash TEXT,
                 created_at  TEXT    NOT NULL,
-                session_id  INTEGER
+                session_id  INTEGER,
+                duration_ms INTEGER,
+                env_json    TEXT
             );",
         )
         .map_err(|e| format!("failed to create command_history table: {e}"))?;
@@ -69,6 +72,24 @@ impl BlackBoxHistory {
                 .map_err(|e| format!("failed to add session_id column: {e}"))?;
         }
 
+        // 既存 DB に duration_ms カラムがない場合に追加する
+        let has_duration_ms = conn
+            .prepare("SELECT duration_ms FROM command_history LIMIT 0")
+            .is_ok();
+        if !has_duration_ms {
+            conn.execute_batch("ALTER TABLE command_history ADD COLUMN duration_ms INTEGER;")
+                .map_err(|e| format!("failed to add duration_ms column: {e}"))?;
+        }
+
+        // 既存 DB に env_json カラムがない場合に追加する
+        let has_env_json = conn
+            .prepare("SELECT env_json FROM command_history LIMIT 0")
+            .is_ok();
+        if !has_env_json {
+            conn.execute_batch("ALTER TABLE command_history ADD COLUMN env_json TEXT;")
+                .map_err(|e| format!("failed to add env_json column: {e}"))?;
+        }
+
         // WAL モードを有効化（BlackBox との並行アクセスを安全にする）
         conn.execute_batch("PRAGMA journal_mode=WAL;")
             .map_err(|e| format!("failed to enable WAL mode: {e}"))?;
@@ -120,12 +141,12 @@ impl BlackBoxHistory {
         if let Some(ref cmd_search) = query.filter.command_line {
             match cmd_search {
                 CommandLineSearch::Prefix(p) => {
-                    conditions.push("command LIKE ?".to_string());
-                    params.push(Value::Text(format!("{p}%")));
+                    conditions.push("command LIKE ? ESCAPE '\\'".to_string());
+                    params.push(Value::Text(format!("{}%", escape_like(p))));
                 }
                 CommandLineSearch::Substring(s) => {
-                    conditions.push("command LIKE ?".to_string());
-                    params.push(Value::Text(format!("%{s}%")));
+                    conditions.push("command LIKE ? ESCAPE '\\'".to_string());
+                    params.push(Value::Text(format!("%{}%", escape_like(s))));
                 }
                 CommandLineSearch::Exact(e) => {
                     conditions.push("command = ?".to_string());
@@ -146,10 +167,11 @@ impl BlackBoxHistory {
             params.push(Value::Text(format!("{cwd_prefix}%")));
         }
 
-        // session_id フィルター
-        // reedline のヒンター (filter.session あり) → セッション横断で補完
-        // reedline の上下矢印 (filter.session なし) → 現セッション + 終了済みセッション (NULL)
-        if query.filter.session.is_none() {
+        // session_id フィルター（reedline 標準の SqliteBackedHistory と同じく、セッション指定ありで絞り込む）
+        // 上下矢印 (`with_history_session_id` で filter.session あり) → 現セッション + 終了済みセッション (NULL)。
+        //   並行して動いている他端末の履歴が混ざらないようにする。
+        // Ctrl-R 履歴メニュー・ヒンター (filter.session なし) → 全セッション横断で検索
+        if query.filter.session.is_some() {
             conditions.push("(session_id IS NULL OR session_id = ?)".to_string());
             params.push(Value::Integer(self.session_id));
         }
@@ -364,7 +386,11 @@ impl History for BlackBoxHistory {
     }
 
     fn sync(&mut self) -> std::io::Result<()> {
-        // SQLite は自動的にディスクに書き出すため no-op
+        // 各書き込みはコミット済みのため、WAL をデータベース本体に書き戻す
+        let complete = super::checkpoint_wal(&self.conn).map_err(std::io::Error::other)?;
+        if !complete {
+            debug!("History WAL checkpoint incomplete (database busy)");
+        }
         Ok(())
     }
 
@@ -372,3 +398,130 @@ impl History for BlackBoxHistory {
         Some(make_session_id(self.session_id))
     }
 }
+
+/// LIKE パターン中のワイルドカード（`%` / `_`）とエスケープ文字（`\`）をエスケープする。
+///
+/// 履歴検索（Ctrl-R メニュー・ヒンター）で入力した文字列をそのまま部分一致させるため。
+fn escape_like(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// テスト用: 一時ディレクトリに `commands` を古い順に保存した履歴を作る。
+/// 一時ディレクトリは履歴を使い終わるまで保持すること。
+#[cfg(test)]
+pub(crate) fn history_with(commands: &[&str]) -> (tempfile::TempDir, BlackBoxHistory) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut history = BlackBoxHistory::open(dir.path().join("history.db"), 1).unwrap();
+    for cmd in commands {
+        history.save(HistoryItem::from_command_line(*cmd)).unwrap();
+    }
+    (dir, history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(history: &BlackBoxHistory, query: SearchQuery) -> Vec<String> {
+        history
+            .search(query)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.command_line)
+            .collect()
+    }
+
+    fn substring_search(history: &BlackBoxHistory, text: &str) -> Vec<String> {
+        let mut query = SearchQuery::everything(SearchDirection::Backward, None);
+        query.filter.command_line = Some(CommandLineSearch::Substring(text.to_string()));
+        history
+            .search(query)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.command_line)
+            .collect()
+    }
+
+    #[test]
+    fn substring_search_matches_middle_of_command() {
+        let (_dir, history) = history_with(&["git status", "cargo test", "git log --oneline"]);
+        let found = substring_search(&history, "log");
+        assert_eq!(found, vec!["git log --oneline"]);
+    }
+
+    #[test]
+    fn substring_search_treats_wildcards_literally() {
+        let (_dir, history) = history_with(&["echo 100%", "echo 1000", "ls my_dir", "ls myXdir"]);
+        assert_eq!(substring_search(&history, "0%"), vec!["echo 100%"]);
+        assert_eq!(substring_search(&history, "my_d"), vec!["ls my_dir"]);
+    }
+
+    #[test]
+    fn session_filter_scopes_navigation_but_not_global_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("history.db");
+        let mut mine = BlackBoxHistory::open(db_path.clone(), 1).unwrap();
+        let mut other = BlackBoxHistory::open(db_path, 2).unwrap();
+        mine.conn
+            .execute(
+                "INSERT INTO command_history (command, cwd, exit_code, created_at) \
+                 VALUES ('echo closed', '/', 0, '2024-01-01T00:00:00+00:00')",
+                [],
+            )
+            .unwrap();
+        mine.save(HistoryItem::from_command_line("echo mine"))
+            .unwrap();
+        other
+            .save(HistoryItem::from_command_line("echo other"))
+            .unwrap();
+
+        // 上下矢印: 現セッション + 終了済みセッションのみ（並行セッションは除外）
+        let navigation = SearchQuery::everything(SearchDirection::Backward, mine.session());
+        assert_eq!(
+            commands(&mine, navigation),
+            vec!["echo mine", "echo closed"]
+        );
+
+        // Ctrl-R 履歴メニュー: 全セッション横断
+        let global = SearchQuery::all_that_contain_rev("echo".to_string());
+        assert_eq!(
+            commands(&mine, global),
+            vec!["echo other", "echo mine", "echo closed"]
+        );
+    }
+
+    #[test]
+    fn sync_checkpoints_wal_into_database() {
+        let (dir, mut history) = history_with(&["echo flushed"]);
+        let wal = dir.path().join("history.db-wal");
+        assert!(wal.metadata().unwrap().len() > 0);
+
+        history.sync().unwrap();
+        assert_eq!(wal.metadata().map(|m| m.len()).unwrap_or(0), 0);
+
+        // WAL を参照しない状態でもデータベース本体に反映されている
+        drop(history);
+        let conn = Connection::open(dir.path().join("history.db")).unwrap();
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM command_history WHERE command = 'echo flushed'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn escape_like_escapes_special_characters() {
+        assert_eq!(escape_like(r"50%_a\b"), r"50\%\_a\\b");
+        assert_eq!(escape_like("plain"), "plain");
+    }
+}
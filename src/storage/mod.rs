@@ -9,6 +9,8 @@ use anyhow::{Context, Result};
 use directories::ProjectDirs;
 use rusqlite::Connection;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::warn;
 
 use blob::BlobStore;
 
@@ -25,14 +27,21 @@ pub struct HistoryEntry {
     pub stdout: Option<String>,
     pub stderr: Option<String>,
     pub created_at: String,
+    /// 出力がバイナリだったか（AI コンテキストでは出力を省略する）
+    pub binary_output: bool,
 }
 
 /// コマンド実行履歴とその出力を永続化する Black Box。
 /// SQLite でメタデータを管理し、BlobStore で stdout/stderr を保存する。
+///
+/// クローンは同じ DB 接続を共有する（AI コンテキストの先読み等、別スレッドからの読み取り用）。
+#[derive(Clone)]
 pub struct BlackBox {
-    conn: Connection,
+    conn: Arc<Mutex<Connection>>,
     blob_store: BlobStore,
     session_id: i64,
+    /// 実行ごとに値を記録する環境変数名（`[history] track_env`）
+    track_env: Vec<String>,
 }
 
 impl BlackBox {
@@ -50,23 +59,41 @@ impl BlackBox {
         let blob_store = BlobStore::new(data_dir.join("blobs"))?;
 
         Ok(Self {
-            conn,
+            conn: Arc::new(Mutex::new(conn)),
             blob_store,
             session_id,
+            track_env: Vec::new(),
         })
     }
 
+    /// DB 接続を取得する（クローン間で共有しているため排他ロックする）。
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 実行ごとに値を記録する環境変数を設定する（`[history] track_env`）。
+    pub fn set_track_env(&mut self, names: Vec<String>) {
+        self.track_env = names;
+    }
+
     /// セッション終了時に session_id を NULL に解放する。
     ///
     /// 終了済みセッションの履歴は次回起動時に上下矢印で辿れるようになる。
     /// 同時実行中の他セッションの履歴は session_id が残っているため分離が維持される。
     pub fn release_session(&self) {
-        let _ = self.conn.execute(
+        let _ = self.conn().execute(
             "UPDATE command_history SET session_id = NULL WHERE session_id = ?1",
             rusqlite::params![self.session_id],
         );
     }
 
+    /// WAL の内容をデータベース本体に書き戻す（シェル終了時に呼ぶ）。
+    pub fn checkpoint(&self) {
+        if let Err(e) = checkpoint_wal(&self.conn()) {
+            warn!(error = %e, "Failed to checkpoint history database");
+        }
+    }
+
     /// データディレクトリのパスを返す。
     ///
     /// `directories` クレートを使用してプラットフォームに応じたパスを決定する。
@@ -85,6 +112,15 @@ impl BlackBox {
     }
 }
 
+/// `PRAGMA wal_checkpoint(TRUNCATE)` で WAL をデータベース本体に書き戻す。
+///
+/// 他セッションの読み取り中などで全ページを書き戻せなかった場合は `Ok(false)`
+/// （コミット済みの内容は WAL に残っているため失われない）。
+pub(crate) fn checkpoint_wal(conn: &Connection) -> rusqlite::Result<bool> {
+    let busy: i64 = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))?;
+    Ok(busy == 0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,6 +134,7 @@ mod tests {
             exit_code,
             action: LoopAction::Continue,
             used_alt_screen: false,
+            binary_output: false,
         }
     }
 
@@ -107,7 +144,7 @@ mod tests {
         let bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
 
         let count: i32 = bb
-            .conn
+            .conn()
             .query_row(
                 "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='command_history'",
                 [],
@@ -123,10 +160,10 @@ mod tests {
         let bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
 
         let result = make_result("hello world\n", "", 0);
-        bb.record("echo hello world", &result).unwrap();
+        bb.record("echo hello world", &result, None).unwrap();
 
         let (cmd, exit_code): (String, i32) = bb
-            .conn
+            .conn()
             .query_row(
                 "SELECT command, exit_code FROM command_history WHERE id = 1",
                 [],
@@ -145,10 +182,10 @@ mod tests {
         let stdout_content = "output line 1\noutput line 2\n";
         let stderr_content = "error: something went wrong\n";
         let result = make_result(stdout_content, stderr_content, 1);
-        bb.record("failing-command", &result).unwrap();
+        bb.record("failing-command", &result, None).unwrap();
 
         let (stdout_hash, stderr_hash): (Option<String>, Option<String>) = bb
-            .conn
+            .conn()
             .query_row(
                 "SELECT stdout_hash, stderr_hash FROM command_history WHERE id = 1",
                 [],
@@ -169,10 +206,10 @@ mod tests {
         let bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
 
         let result = make_result("", "", 0);
-        bb.record("cd /tmp", &result).unwrap();
+        bb.record("cd /tmp", &result, None).unwrap();
 
         let (stdout_hash, stderr_hash): (Option<String>, Option<String>) = bb
-            .conn
+            .conn()
             .query_row(
                 "SELECT stdout_hash, stderr_hash FROM command_history WHERE id = 1",
                 [],
@@ -189,9 +226,9 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         let bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
 
-        bb.record("echo hello", &make_result("hello\n", "", 0))
+        bb.record("echo hello", &make_result("hello\n", "", 0), None)
             .unwrap();
-        bb.record("bad-cmd", &make_result("", "error: not found\n", 1))
+        bb.record("bad-cmd", &make_result("", "error: not found\n", 1), None)
             .unwrap();
 
         let ctx = bb.get_recent_context(5).unwrap();
@@ -201,6 +238,130 @@ mod tests {
         assert!(ctx.contains("hello"));
     }
 
+    #[test]
+    fn binary_output_is_stored_but_omitted_from_context() {
+        let tmp = TempDir::new().unwrap();
+        let bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
+
+        let mut result = make_result("\u{fffd}PNG secret-bytes", "", 0);
+        result.binary_output = true;
+        bb.record("cat image.png", &result, None).unwrap();
+
+        // Blob には保存する
+        let stdout_hash: Option<String> = bb
+            .conn()
+            .query_row(
+                "SELECT stdout_hash FROM command_history WHERE command = 'cat image.png'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(stdout_hash.is_some());
+
+        for ctx in [
+            bb.get_recent_context(5).unwrap(),
+            bb.get_last_entry_context(1000).unwrap(),
+        ] {
+            assert!(ctx.contains("cat image.png"));
+            assert!(ctx.contains("binary output omitted"));
+            assert!(!ctx.contains("secret-bytes"));
+        }
+    }
+
+    #[test]
+    fn discard_removes_only_unrecorded_entry_of_this_session() {
+        let tmp = TempDir::new().unwrap();
+        let bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
+        let count = |bb: &BlackBox| -> i64 {
+            bb.conn()
+                .query_row("SELECT COUNT(*) FROM command_history", [], |row| row.get(0))
+                .unwrap()
+        };
+
+        // 以前に出力付きで記録した行は消さない
+        bb.record("what is rust", &make_result("Rust is ...\n", "", 0), None)
+            .unwrap();
+        bb.discard("what is rust").unwrap();
+        assert_eq!(count(&bb), 1);
+
+        // reedline が先に INSERT した（出力未記録の）行は消す
+        bb.conn()
+            .execute(
+                "INSERT INTO command_history (command, cwd, exit_code, created_at, session_id) \
+                 VALUES ('what is rust', '/', 0, '2026-01-01T00:00:00Z', 1)",
+                [],
+            )
+            .unwrap();
+        assert_eq!(count(&bb), 2);
+        bb.discard("what is rust").unwrap();
+        assert_eq!(count(&bb), 1);
+    }
+
+    #[test]
+    fn clone_shares_connection() {
+        let tmp = TempDir::new().unwrap();
+        let bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
+
+        // 別スレッドに渡したクローンからも同じ履歴を読める
+        let other = bb.clone();
+        bb.record("echo shared", &make_result("shared\n", "", 0), None)
+            .unwrap();
+        let ctx = std::thread::spawn(move || other.get_recent_context(5).unwrap())
+            .join()
+            .unwrap();
+        assert!(ctx.contains("echo shared"));
+    }
+
+    #[test]
+    fn get_last_entry_context_keeps_full_output_of_latest_command() {
+        let tmp = TempDir::new().unwrap();
+        let bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
+        assert!(bb.get_last_entry_context(1000).unwrap().is_empty());
+
+        bb.record("echo old", &make_result("old\n", "", 0), None)
+            .unwrap();
+        let long_stderr: String = (1..=80).map(|i| format!("error line {i}\n")).collect();
+        bb.record("cargo build", &make_result("", &long_stderr, 101), None)
+            .unwrap();
+
+        let ctx = bb.get_last_entry_context(10_000).unwrap();
+        assert!(ctx.contains("cargo build (exit: 101"));
+        assert!(!ctx.contains("echo old"));
+        // 行数（50 行）では切り詰めない
+        assert!(ctx.contains("error line 1\n"));
+        assert!(ctx.contains("error line 80"));
+
+        // 文字数の上限を超えた分は先頭から省略し、末尾を残す
+        let ctx = bb.get_last_entry_context(20).unwrap();
+        assert!(ctx.contains("chars omitted"));
+        assert!(ctx.contains("error line 80"));
+        assert!(!ctx.contains("error line 1\n"));
+    }
+
+    #[test]
+    fn get_error_context_prioritizes_failed_commands() {
+        let tmp = TempDir::new().unwrap();
+        let bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
+        assert!(bb.get_error_context(5, 1000).unwrap().is_empty());
+
+        let long_stderr: String = (1..=80).map(|i| format!("error line {i}\n")).collect();
+        bb.record("cargo build", &make_result("", &long_stderr, 101), None)
+            .unwrap();
+        bb.record("echo ok", &make_result("ok-output\n", "", 0), None)
+            .unwrap();
+
+        let ctx = bb.get_error_context(5, 10_000).unwrap();
+        let failed_at = ctx.find("cargo build (exit: 101").unwrap();
+        let succeeded_at = ctx.find("echo ok (exit: 0").unwrap();
+        // 新しい成功コマンドより失敗コマンドを先に並べる
+        assert!(failed_at < succeeded_at);
+        // 失敗コマンドの stderr は行数で切り詰めない
+        assert!(ctx.contains("error line 1\n"));
+        assert!(ctx.contains("error line 80"));
+        // 成功コマンドの出力は省略する
+        assert!(!ctx.contains("ok-output"));
+    }
+
     #[test]
     fn get_recent_context_empty_when_no_history() {
         let tmp = TempDir::new().unwrap();
@@ -215,12 +376,15 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         let bb = BlackBox::open_at(tmp.path().to_path_buf(), 1).unwrap();
 
-        bb.record("cmd1", &make_result("out1", "", 0)).unwrap();
-        bb.record("cmd2", &make_result("out2", "", 0)).unwrap();
-        bb.record("cmd3", &make_result("out3", "", 0)).unwrap();
+        bb.record("cmd1", &make_result("out1", "", 0), None)
+            .unwrap();
+        bb.record("cmd2", &make_result("out2", "", 0), None)
+            .unwrap();
+        bb.record("cmd3", &make_result("out3", "", 0), None)
+            .unwrap();
 
         let count: i32 = bb
-            .conn
+            .conn()
             .query_row("SELECT COUNT(*) FROM command_history", [], |row| row.get(0))
             .unwrap();
         assert_eq!(count, 3);
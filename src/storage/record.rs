@@ -1,5 +1,7 @@
 //! コマンド実行結果の記録 + DB マイグレーション
 
+use std::collections::BTreeMap;
+
 use anyhow::{Context, Result};
 use chrono::Utc;
 use rusqlite::Connection;
@@ -16,13 +18,28 @@ impl super::BlackBox {
     ///
     /// reedline の History::save() が先に INSERT しているため、
     /// 最新の該当行を UPDATE する。該当行が見つからない場合は INSERT にフォールバックする。
-    pub fn record(&self, command: &str, result: &CommandResult) -> Result<()> {
+    ///
+    /// `duration_ms` はコマンドの所要時間（ミリ秒）。計測していない場合（ビルトイン・
+    /// AI 応答など）は `None` を渡し、`history profile` の集計対象から外す。
+    ///
+    /// `[history] track_env` で指定した環境変数は、記録時点の値を JSON で `env_json` に保存する。
+    ///
+    /// バイナリ出力（`binary_output`）も Blob には保存するが、`binary_output` 列を立てて
+    /// AI コンテキストの対象から外す。
+    pub fn record(
+        &self,
+        command: &str,
+        result: &CommandResult,
+        duration_ms: Option<u64>,
+    ) -> Result<()> {
         debug!(
             command = %command,
             exit_code = result.exit_code,
+            duration_ms = ?duration_ms,
             stdout_len = result.stdout.len(),
             stderr_len = result.stderr.len(),
             used_alt_screen = result.used_alt_screen,
+            binary_output = result.binary_output,
             "Recording command result to BlackBox"
         );
 
@@ -43,14 +60,25 @@ impl super::BlackBox {
             self.blob_store.store(&masked_stdout)
         }?;
         let stderr_hash = self.blob_store.store(&masked_stderr)?;
+        let duration_ms = duration_ms.map(|ms| i64::try_from(ms).unwrap_or(i64::MAX));
+        let env_json = env_snapshot(&self.track_env)?;
 
         let rows_updated = self
-            .conn
+            .conn()
             .execute(
                 "UPDATE command_history \
-                 SET exit_code = ?1, stdout_hash = ?2, stderr_hash = ?3 \
-                 WHERE id = (SELECT MAX(id) FROM command_history WHERE command = ?4)",
-                rusqlite::params![result.exit_code, stdout_hash, stderr_hash, command,],
+                 SET exit_code = ?1, stdout_hash = ?2, stderr_hash = ?3, duration_ms = ?4, env_json = ?5, \
+                 binary_output = ?6 \
+                 WHERE id = (SELECT MAX(id) FROM command_history WHERE command = ?7)",
+                rusqlite::params![
+                    result.exit_code,
+                    stdout_hash,
+                    stderr_hash,
+                    duration_ms,
+                    env_json,
+                    result.binary_output,
+                    command,
+                ],
             )
             .context("failed to update command history")?;
 
@@ -60,10 +88,10 @@ impl super::BlackBox {
                 .unwrap_or_default();
             let created_at = Utc::now().to_rfc3339();
 
-            self.conn
+            self.conn()
                 .execute(
-                    "INSERT INTO command_history (command, cwd, exit_code, stdout_hash, stderr_hash, created_at, session_id)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    "INSERT INTO command_history (command, cwd, exit_code, stdout_hash, stderr_hash, created_at, session_id, duration_ms, env_json, binary_output)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
                     rusqlite::params![
                         command,
                         cwd,
@@ -72,6 +100,9 @@ impl super::BlackBox {
                         stderr_hash,
                         created_at,
                         self.session_id,
+                        duration_ms,
+                        env_json,
+                        result.binary_output,
                     ],
                 )
                 .context("failed to insert command history")?;
@@ -80,6 +111,24 @@ impl super::BlackBox {
         Ok(())
     }
 
+    /// コマンドを履歴に残さない（`[history] record_ai_conversation = false` の AI 会話等）。
+    ///
+    /// [`record`](Self::record) の代わりに呼び、reedline の History::save() が先に INSERT した
+    /// このセッションの最新の該当行を削除する。出力を記録済みの行（以前の実行結果）は消さない。
+    pub fn discard(&self, command: &str) -> Result<()> {
+        let rows_deleted = self
+            .conn()
+            .execute(
+                "DELETE FROM command_history \
+                 WHERE id = (SELECT MAX(id) FROM command_history WHERE command = ?1 AND session_id = ?2) \
+                 AND stdout_hash IS NULL AND stderr_hash IS NULL",
+                rusqlite::params![command, self.session_id],
+            )
+            .context("failed to discard command history")?;
+        debug!(command = %command, rows_deleted, "Discarded command from BlackBox");
+        Ok(())
+    }
+
     /// DB スキーマのマイグレーションを実行する。
     pub(super) fn migrate(conn: &Connection) -> Result<()> {
         conn.execute_batch(
@@ -91,7 +140,10 @@ impl super::BlackBox {
                 stdout_hash TEXT,
                 stderr_hash TEXT,
                 created_at  TEXT    NOT NULL,
-                session_id  INTEGER
+                session_id  INTEGER,
+                duration_ms INTEGER,
+                env_json    TEXT,
+                binary_output INTEGER NOT NULL DEFAULT 0
             );",
         )
         .context("failed to create command_history table")?;
@@ -105,6 +157,59 @@ impl super::BlackBox {
                 .context("failed to add session_id column")?;
         }
 
+        // 既存 DB に duration_ms カラムがない場合に追加する
+        let has_duration_ms = conn
+            .prepare("SELECT duration_ms FROM command_history LIMIT 0")
+            .is_ok();
+        if !has_duration_ms {
+            conn.execute_batch("ALTER TABLE command_history ADD COLUMN duration_ms INTEGER;")
+                .context("failed to add duration_ms column")?;
+        }
+
+        // 既存 DB に env_json カラムがない場合に追加する
+        let has_env_json = conn
+            .prepare("SELECT env_json FROM command_history LIMIT 0")
+            .is_ok();
+        if !has_env_json {
+            conn.execute_batch("ALTER TABLE command_history ADD COLUMN env_json TEXT;")
+                .context("failed to add env_json column")?;
+        }
+
+        // 既存 DB に binary_output カラムがない場合に追加する
+        let has_binary_output = conn
+            .prepare("SELECT binary_output FROM command_history LIMIT 0")
+            .is_ok();
+        if !has_binary_output {
+            conn.execute_batch(
+                "ALTER TABLE command_history ADD COLUMN binary_output INTEGER NOT NULL DEFAULT 0;",
+            )
+            .context("failed to add binary_output column")?;
+        }
+
         Ok(())
     }
 }
+
+/// 追跡対象の環境変数の現在値を JSON オブジェクト文字列にする。
+///
+/// 未設定の変数は含めない。1 つも設定されていなければ `None`（NULL を保存）。
+/// 値に API キー等が含まれる場合はマスクする。
+fn env_snapshot(names: &[String]) -> Result<Option<String>> {
+    let vars: BTreeMap<&str, String> = names
+        .iter()
+        .filter_map(|name| {
+            let value = std::env::var(name).ok()?;
+            let value = if sanitizer::contains_secrets(&value) {
+                sanitizer::mask_secrets(&value)
+            } else {
+                value
+            };
+            Some((name.as_str(), value))
+        })
+        .collect();
+    if vars.is_empty() {
+        return Ok(None);
+    }
+    let json = serde_json::to_string(&vars).context("failed to serialize environment snapshot")?;
+    Ok(Some(json))
+}